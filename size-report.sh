@@ -0,0 +1,20 @@
+#!/bin/sh
+# Print the size of the release wasm module with the default features and
+# with the minimal set, to see what the optional windows cost.
+set -e
+
+target=wasm32-unknown-unknown
+wasm=target/$target/release/conrod_floatwin_demo_glow.wasm
+
+report() {
+    name=$1
+    shift
+    cargo build --release --lib --target $target "$@"
+    echo "$name: $(wc -c < $wasm) bytes" >> target/size-report.txt
+}
+
+mkdir -p target
+: > target/size-report.txt
+report default
+report minimal --no-default-features
+cat target/size-report.txt
@@ -0,0 +1,98 @@
+// Windows asking for the attention of the user, when something happens in
+// them while they aren't focused, such as a chat message or a finished task.
+//
+// There is no taskbar, so the compact tabs and the workspace pager stand in
+// for it: the tab of such a window flashes for a while and shows how many
+// times it asked, and so does the page of its workspace. It all clears when
+// the window gets the focus.
+
+use crate::trace::now_us;
+use std::time::Duration;
+
+struct Request {
+    title: &'static str,
+    count: u32,
+    /// When the window last asked, in microseconds, from which it flashes.
+    since: f64,
+}
+
+pub struct Attention {
+    requests: Vec<Request>,
+}
+
+impl Default for Attention {
+    fn default() -> Self {
+        Attention::new()
+    }
+}
+
+impl Attention {
+    const FLASH_US: f64 = 3_000_000.0;
+    /// How long the flash is on, and then off.
+    const BLINK_US: f64 = 400_000.0;
+
+    pub fn new() -> Self {
+        Attention {
+            requests: Vec::new(),
+        }
+    }
+
+    /// Ask for attention to the window `title`, once more.
+    pub fn request(&mut self, title: &'static str) {
+        let now = now_us();
+        match self.requests.iter_mut().find(|r| r.title == title) {
+            Some(request) => {
+                request.count += 1;
+                request.since = now;
+            }
+            None => self.requests.push(Request {
+                title,
+                count: 1,
+                since: now,
+            }),
+        }
+    }
+
+    /// The window `title` got the focus.
+    pub fn clear(&mut self, title: &str) {
+        self.requests.retain(|r| r.title != title);
+    }
+
+    /// How many times the window `title` asked since it last had the focus.
+    pub fn count(&self, title: &str) -> u32 {
+        self.requests
+            .iter()
+            .find(|r| r.title == title)
+            .map_or(0, |r| r.count)
+    }
+
+    /// The titles of the windows which asked.
+    pub fn titles<'a>(&'a self) -> impl Iterator<Item = &'static str> + 'a {
+        self.requests.iter().map(|r| r.title)
+    }
+
+    /// Whether the flash of the window `title` is on at `now`. It stays on
+    /// once it is done blinking.
+    pub fn is_lit(&self, title: &str, now: f64) -> bool {
+        let request = match self.requests.iter().find(|r| r.title == title) {
+            Some(request) => request,
+            None => return false,
+        };
+        let elapsed = now - request.since;
+        elapsed >= Self::FLASH_US || (elapsed / Self::BLINK_US) as u64 % 2 == 0
+    }
+
+    /// How long until a flash turns on or off, while any is blinking, after
+    /// which the UI needs to be updated.
+    pub fn next_blink(&self, now: f64) -> Option<Duration> {
+        self.requests
+            .iter()
+            .map(|r| now - r.since)
+            .filter(|&elapsed| elapsed < Self::FLASH_US)
+            .map(|elapsed| Self::BLINK_US - elapsed % Self::BLINK_US)
+            .fold(None, |next: Option<f64>, us| {
+                Some(next.map_or(us, |next| next.min(us)))
+            })
+            .map(|us| Duration::from_micros(us.max(0.0) as u64))
+    }
+}
@@ -0,0 +1,191 @@
+// An overlay flagging text with too little contrast against what is behind
+// it and click targets which are too small, for checking the demo without a
+// screen reader.
+//
+// It works on what conrod last drew: the text primitives are compared with
+// the last opaque rectangle drawn under them, and the buttons, toggles and
+// dialers with the configured minimum size.
+
+use crate::conrod_glow::gamma_srgb_to_linear;
+use crate::selection::is_within;
+use conrod_core::render::PrimitiveKind;
+use conrod_core::{
+    color, widget, widget_ids, Color, Colorable, Positionable, Rect, Sizeable, Ui, Widget,
+    WidgetCommon,
+};
+
+/// The contrast ratio WCAG asks of normal text.
+pub const MIN_CONTRAST: f32 = 4.5;
+
+#[derive(Clone, Copy, Debug)]
+pub enum Issue {
+    /// Text with the contrast ratio against its background.
+    Contrast(f32),
+    /// A click target smaller than the minimum size.
+    SmallTarget,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Finding {
+    pub id: widget::Id,
+    pub rect: Rect,
+    pub issue: Issue,
+}
+
+/// The contrast ratio of two colors, from 1 to 21.
+pub fn contrast_ratio(a: Color, b: Color) -> f32 {
+    let luminance = |c: Color| {
+        let [r, g, b, _] = gamma_srgb_to_linear(c.to_fsa());
+        0.2126 * r + 0.7152 * g + 0.0722 * b
+    };
+    let (la, lb) = (luminance(a), luminance(b));
+    (la.max(lb) + 0.05) / (la.min(lb) + 0.05)
+}
+
+/// Check what `ui` last drew, except for the widgets within `skip`.
+pub fn audit(ui: &Ui, skip: widget::Id, min_target: f64) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut backgrounds: Vec<(Rect, Color)> = Vec::new();
+    let mut seen_targets = Vec::new();
+    let mut primitives = ui.draw();
+    while let Some(primitive) = primitives.next() {
+        if is_within(ui, primitive.id, skip) {
+            continue;
+        }
+        match primitive.kind {
+            PrimitiveKind::Rectangle { color } if color.alpha() >= 1.0 => {
+                backgrounds.push((primitive.rect, color));
+            }
+            PrimitiveKind::Text { color, .. } => {
+                let rect = primitive.rect;
+                let background = backgrounds
+                    .iter()
+                    .rev()
+                    .find(|(bg, _)| bg.is_over(rect.xy()))
+                    .map_or(ui.theme.background_color, |&(_, color)| color);
+                let ratio = contrast_ratio(color, background);
+                if ratio < MIN_CONTRAST {
+                    findings.push(Finding {
+                        id: primitive.id,
+                        rect,
+                        issue: Issue::Contrast(ratio),
+                    });
+                }
+            }
+            _ => {}
+        }
+        if !seen_targets.contains(&primitive.id) && is_click_target(ui, primitive.id) {
+            seen_targets.push(primitive.id);
+            let rect = primitive.rect;
+            if rect.w() < min_target || rect.h() < min_target {
+                findings.push(Finding {
+                    id: primitive.id,
+                    rect,
+                    issue: Issue::SmallTarget,
+                });
+            }
+        }
+    }
+    findings
+}
+
+/// Whether `id` is one of the conrod widgets which are clicked on.
+fn is_click_target(ui: &Ui, id: widget::Id) -> bool {
+    let container = match ui.widget_graph().widget(id) {
+        Some(container) => container,
+        None => return false,
+    };
+    container
+        .unique_widget_state::<widget::Button<widget::button::Flat>>()
+        .is_some()
+        || container
+            .unique_widget_state::<widget::Button<widget::button::Image>>()
+            .is_some()
+        || container.unique_widget_state::<widget::Toggle>().is_some()
+        || container
+            .unique_widget_state::<widget::NumberDialer<f32>>()
+            .is_some()
+        || container
+            .unique_widget_state::<widget::DropDownList<String>>()
+            .is_some()
+}
+
+/// Outlines the findings over the whole UI, with a summary in the corner.
+#[derive(WidgetCommon)]
+pub struct AuditOverlay {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    min_target: f64,
+}
+
+impl AuditOverlay {
+    pub fn new(min_target: f64) -> Self {
+        AuditOverlay {
+            common: widget::CommonBuilder::default(),
+            min_target,
+        }
+    }
+}
+
+widget_ids! {
+    pub struct AuditIds {
+        summary,
+        outlines[],
+    }
+}
+
+impl Widget for AuditOverlay {
+    type State = AuditIds;
+    type Style = ();
+    type Event = ();
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        AuditIds::new(id_gen)
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    // The outlines shouldn't block the widgets they point at.
+    fn is_over(&self) -> widget::IsOverFn {
+        |_, _, _| widget::IsOver::Bool(false)
+    }
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs { id, state, ui, .. } = args;
+
+        const PAD: conrod_core::Scalar = 8.0;
+
+        let findings = audit(ui, id, self.min_target);
+        if state.outlines.len() < findings.len() {
+            let id_gen = &mut ui.widget_id_generator();
+            state.update(|state| state.outlines.resize(findings.len(), id_gen));
+        }
+        let mut low_contrast = 0;
+        for (i, finding) in findings.iter().enumerate() {
+            let color = match finding.issue {
+                Issue::Contrast(_) => {
+                    low_contrast += 1;
+                    color::ORANGE
+                }
+                Issue::SmallTarget => color::RED,
+            };
+            let style = widget::line::Style::solid().thickness(2.0).color(color);
+            widget::Rectangle::outline_styled(finding.rect.dim(), style)
+                .xy(finding.rect.xy())
+                .parent(id)
+                .graphics_for(finding.id)
+                .set(state.outlines[i], ui);
+        }
+
+        let summary = format!(
+            "Audit: {} low contrast (orange), {} small targets (red)",
+            low_contrast,
+            findings.len() - low_contrast
+        );
+        widget::Text::new(&summary)
+            .font_size(12)
+            .color(color::LIGHT_GREY)
+            .bottom_left_with_margin_on(id, PAD)
+            .set(state.summary, ui);
+    }
+}
@@ -0,0 +1,68 @@
+// Bridge between background work (threads on native, JS callbacks on wasm)
+// and the winit event loop.
+
+/// Events delivered to the event loop as `winit::event::Event::UserEvent`.
+#[derive(Debug)]
+pub enum AppEvent {
+    Capture(crate::capture::CaptureEvent),
+    #[cfg(feature = "chat")]
+    Chat(crate::chat::ChatEvent),
+    #[cfg(all(feature = "devserver", not(target_arch = "wasm32")))]
+    DevServer(crate::devserver::DevRequest),
+    File(crate::file_dialog::FileEvent),
+    /// A mesh was filled on the `FillThread`.
+    #[cfg(not(target_arch = "wasm32"))]
+    MeshReady,
+    /// Text pasted into the page, see `clipboard::listen_for_paste`.
+    #[cfg(target_arch = "wasm32")]
+    Paste(String),
+    #[cfg(all(feature = "mirror", not(target_arch = "wasm32")))]
+    Mirror(crate::mirror::MirrorMessage),
+    Task(crate::tasks::TaskEvent),
+    #[cfg(all(feature = "terminal", not(target_arch = "wasm32")))]
+    Terminal(crate::terminal::TerminalEvent),
+}
+
+/// A cloneable handle for posting `AppEvent`s to the event loop.
+///
+/// Sending an event wakes up the event loop, so the UI gets updated even if
+/// it is currently waiting for input.
+#[derive(Clone)]
+pub struct Bridge {
+    target: Target,
+}
+
+#[derive(Clone)]
+enum Target {
+    EventLoop(winit::event_loop::EventLoopProxy<AppEvent>),
+    /// Without an event loop, for the headless `simulation`.
+    Channel(std::sync::mpsc::Sender<AppEvent>),
+}
+
+impl Bridge {
+    pub fn new(event_loop: &winit::event_loop::EventLoop<AppEvent>) -> Self {
+        Bridge {
+            target: Target::EventLoop(event_loop.create_proxy()),
+        }
+    }
+
+    /// A bridge which posts the events to a channel instead, for running the
+    /// UI without winit.
+    pub fn headless() -> (Self, std::sync::mpsc::Receiver<AppEvent>) {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let bridge = Bridge {
+            target: Target::Channel(sender),
+        };
+        (bridge, receiver)
+    }
+
+    /// Post an event to the event loop.
+    ///
+    /// Returns `false` if the event loop no longer exists.
+    pub fn send(&self, event: AppEvent) -> bool {
+        match &self.target {
+            Target::EventLoop(proxy) => proxy.send_event(event).is_ok(),
+            Target::Channel(sender) => sender.send(event).is_ok(),
+        }
+    }
+}
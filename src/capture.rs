@@ -0,0 +1,221 @@
+// Recording the UI as an animated GIF on native or a WebM video on wasm.
+//
+// On native, the event loop reads every drawn frame back from the default
+// framebuffer and the frames are encoded into a GIF on a background thread.
+// On wasm, the browser records the canvas with a `MediaRecorder`. Either way,
+// the finished recording is delivered as `AppEvent::Capture` through the
+// `Bridge`. The GIF encoding shows up as a task in the Tasks window.
+
+use crate::bridge::{AppEvent, Bridge};
+
+#[derive(Debug)]
+pub enum CaptureEvent {
+    Finished { name: &'static str, data: Vec<u8> },
+    Error(String),
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use native::Recorder;
+#[cfg(target_arch = "wasm32")]
+pub use web::Recorder;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use super::{AppEvent, Bridge, CaptureEvent};
+    use crate::conrod_glow;
+    use crate::tasks::TaskHandle;
+    use gif::SetParameter;
+    use std::convert::TryFrom;
+    use std::time::{Duration, Instant};
+
+    pub struct Recorder {
+        width: u32,
+        height: u32,
+        started: Instant,
+        duration: Duration,
+        /// Each frame with the time it was drawn, relative to `started`.
+        frames: Vec<(Duration, Vec<u8>)>,
+    }
+
+    impl Recorder {
+        /// Frames drawn faster than this replace the previous frame, which
+        /// keeps the memory use and the size of the GIF down.
+        const MIN_FRAME_INTERVAL: Duration = Duration::from_millis(50);
+
+        pub fn new(width: u32, height: u32, seconds: u32) -> Self {
+            Recorder {
+                width,
+                height,
+                started: Instant::now(),
+                duration: Duration::from_secs(seconds as u64),
+                frames: Vec::new(),
+            }
+        }
+
+        pub fn is_finished(&self) -> bool {
+            self.started.elapsed() >= self.duration
+        }
+
+        /// Read back the frame which has just been drawn to the default
+        /// framebuffer.
+        ///
+        /// All frames of a GIF have the same size, so the recording stops
+        /// early if the window has been resized.
+        pub fn capture_frame(&mut self, gl: &glow::Context, dimensions: (u32, u32)) {
+            let now = self.started.elapsed();
+            if dimensions != (self.width, self.height) {
+                self.duration = now;
+                return;
+            }
+            let pixels = conrod_glow::read_framebuffer_pixels(gl, None, self.width, self.height);
+            match self.frames.last_mut() {
+                Some((time, last)) if now - *time < Self::MIN_FRAME_INTERVAL => *last = pixels,
+                _ => self.frames.push((now, pixels)),
+            }
+        }
+
+        /// Stop recording and encode the GIF on a background thread, which
+        /// reports its progress to `task`.
+        pub fn finish(self, bridge: &Bridge, task: TaskHandle) {
+            let bridge = bridge.clone();
+            std::thread::spawn(move || match self.encode(&task) {
+                Ok(Some(data)) => {
+                    bridge.send(AppEvent::Capture(CaptureEvent::Finished {
+                        name: "recording.gif",
+                        data,
+                    }));
+                    task.finish(Ok(()));
+                }
+                // Cancelled.
+                Ok(None) => task.finish(Ok(())),
+                Err(err) => task.finish(Err(err)),
+            });
+        }
+
+        /// Returns `None` if the task is cancelled.
+        fn encode(self, task: &TaskHandle) -> Result<Option<Vec<u8>>, String> {
+            let too_large = || "the window is too large for a GIF".to_owned();
+            let width = u16::try_from(self.width).map_err(|_| too_large())?;
+            let height = u16::try_from(self.height).map_err(|_| too_large())?;
+            let end = self.duration;
+            let frame_count = self.frames.len();
+
+            let mut data = Vec::new();
+            {
+                let mut encoder =
+                    gif::Encoder::new(&mut data, width, height, &[]).map_err(|e| e.to_string())?;
+                encoder
+                    .set(gif::Repeat::Infinite)
+                    .map_err(|e| e.to_string())?;
+                let mut frames = self.frames.into_iter().peekable();
+                let mut encoded = 0;
+                while let Some((time, mut pixels)) = frames.next() {
+                    if task.is_cancelled() {
+                        return Ok(None);
+                    }
+                    let next = frames.peek().map_or(end, |(next, _)| *next);
+                    // Quantizing with the best quality is very slow for
+                    // full window frames.
+                    let mut frame = gif::Frame::from_rgba_speed(width, height, &mut pixels, 10);
+                    // The delay is in units of 10 ms.
+                    let delay = next.checked_sub(time).unwrap_or_default().as_millis() / 10;
+                    frame.delay = delay.max(1).min(u16::MAX as u128) as u16;
+                    encoder.write_frame(&frame).map_err(|e| e.to_string())?;
+                    encoded += 1;
+                    task.set_progress(encoded as f32 / frame_count as f32);
+                }
+                // The trailer is written when the encoder is dropped.
+            }
+            Ok(Some(data))
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod web {
+    use super::{AppEvent, Bridge, CaptureEvent};
+    use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+
+    fn js_err(err: JsValue) -> String {
+        err.as_string().unwrap_or_else(|| format!("{:?}", err))
+    }
+
+    pub struct Recorder {
+        recorder: web_sys::MediaRecorder,
+        /// When to stop, as milliseconds since the epoch.
+        stop_at: f64,
+        on_data: Closure<dyn FnMut(web_sys::BlobEvent)>,
+    }
+
+    impl Recorder {
+        pub fn new(
+            canvas: &web_sys::HtmlCanvasElement,
+            seconds: u32,
+            bridge: &Bridge,
+        ) -> Result<Self, String> {
+            let stream = canvas.capture_stream().map_err(js_err)?;
+            let mut options = web_sys::MediaRecorderOptions::new();
+            options.mime_type("video/webm");
+            let recorder =
+                web_sys::MediaRecorder::new_with_media_stream_and_media_recorder_options(
+                    &stream, &options,
+                )
+                .map_err(js_err)?;
+
+            // Without a timeslice, the whole recording arrives as one blob
+            // after `stop` is called.
+            let on_data = {
+                let bridge = bridge.clone();
+                Closure::wrap(Box::new(move |event: web_sys::BlobEvent| {
+                    if let Some(blob) = event.data() {
+                        if let Err(err) = read_blob(bridge.clone(), &blob) {
+                            bridge.send(AppEvent::Capture(CaptureEvent::Error(err)));
+                        }
+                    }
+                }) as Box<dyn FnMut(_)>)
+            };
+            recorder.set_ondataavailable(Some(on_data.as_ref().unchecked_ref()));
+            recorder.start().map_err(js_err)?;
+
+            Ok(Recorder {
+                recorder,
+                stop_at: js_sys::Date::now() + seconds as f64 * 1000.0,
+                on_data,
+            })
+        }
+
+        pub fn is_finished(&self) -> bool {
+            js_sys::Date::now() >= self.stop_at
+        }
+
+        /// Stop recording. The video is sent once the browser has finished
+        /// encoding it.
+        pub fn finish(self, bridge: &Bridge) {
+            if let Err(err) = self.recorder.stop() {
+                bridge.send(AppEvent::Capture(CaptureEvent::Error(js_err(err))));
+            }
+            // The callback is still needed after the recorder is gone.
+            self.on_data.forget();
+        }
+    }
+
+    fn read_blob(bridge: Bridge, blob: &web_sys::Blob) -> Result<(), String> {
+        let reader = web_sys::FileReader::new().map_err(js_err)?;
+        let on_load = {
+            let reader = reader.clone();
+            Closure::once(move |_: web_sys::Event| {
+                let event = match reader.result() {
+                    Ok(result) => CaptureEvent::Finished {
+                        name: "recording.webm",
+                        data: js_sys::Uint8Array::new(&result).to_vec(),
+                    },
+                    Err(err) => CaptureEvent::Error(js_err(err)),
+                };
+                bridge.send(AppEvent::Capture(event));
+            })
+        };
+        reader.set_onload(Some(on_load.as_ref().unchecked_ref()));
+        on_load.forget();
+        reader.read_as_array_buffer(blob).map_err(js_err)
+    }
+}
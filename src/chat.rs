@@ -0,0 +1,411 @@
+// A WebSocket chat window.
+//
+// The connection is driven by a background thread using `tungstenite` on
+// native and by the browser's `WebSocket` on wasm. Either way, incoming
+// events are posted to the event loop through the `Bridge`.
+
+use crate::bridge::{AppEvent, Bridge};
+use crate::title_bar::{TitleBarItem, TitleBarItems};
+use conrod_core::{
+    color, widget, widget_ids, Colorable, Labelable, Positionable, Sizeable, Widget, WidgetCommon,
+};
+
+/// Tells the connections of a `ChatState` apart, as the events of one which
+/// was replaced may still be on their way.
+pub type ConnectionId = u64;
+
+/// Events produced by a chat connection.
+#[derive(Debug)]
+pub enum ChatEvent {
+    Connected { id: ConnectionId },
+    Message { id: ConnectionId, text: String },
+    Disconnected { id: ConnectionId },
+    Error { id: ConnectionId, error: String },
+}
+
+impl ChatEvent {
+    fn id(&self) -> ConnectionId {
+        match *self {
+            ChatEvent::Connected { id }
+            | ChatEvent::Message { id, .. }
+            | ChatEvent::Disconnected { id }
+            | ChatEvent::Error { id, .. } => id,
+        }
+    }
+}
+
+pub struct ChatState {
+    pub url: String,
+    pub input: String,
+    pub messages: Vec<String>,
+    connection: Option<Connection>,
+    /// The id of the next connection.
+    next_id: ConnectionId,
+}
+
+// Green while connected or connecting.
+impl TitleBarItems for ChatState {
+    fn title_bar_items(&self) -> Vec<TitleBarItem> {
+        let color = if self.is_connected() {
+            color::GREEN
+        } else {
+            color::DARK_GREY
+        };
+        vec![TitleBarItem::Indicator(color)]
+    }
+}
+
+impl Default for ChatState {
+    fn default() -> Self {
+        ChatState::new()
+    }
+}
+
+impl ChatState {
+    pub fn new() -> Self {
+        ChatState {
+            url: "ws://127.0.0.1:9001".to_owned(),
+            input: String::new(),
+            messages: Vec::new(),
+            connection: None,
+            next_id: 0,
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connection.is_some()
+    }
+
+    pub fn connect(&mut self, bridge: &Bridge) {
+        self.disconnect();
+        self.messages
+            .push(format!("* Connecting to {}...", self.url));
+        let id = self.next_id;
+        self.next_id += 1;
+        match Connection::open(id, &self.url, bridge.clone()) {
+            Ok(connection) => self.connection = Some(connection),
+            Err(err) => self.messages.push(format!("* Error: {}", err)),
+        }
+    }
+
+    pub fn disconnect(&mut self) {
+        if let Some(connection) = self.connection.take() {
+            connection.close();
+        }
+    }
+
+    pub fn send_input(&mut self) {
+        if self.input.is_empty() {
+            return;
+        }
+        if let Some(connection) = &self.connection {
+            let text = std::mem::replace(&mut self.input, String::new());
+            connection.send(&text);
+            self.messages.push(format!("> {}", text));
+        }
+    }
+
+    /// Take in an event of the current connection. Those of the connections
+    /// closed before are left out, such as the `Disconnected` of the one a
+    /// reconnection replaced.
+    pub fn handle_event(&mut self, event: ChatEvent) {
+        if self.connection.as_ref().map(|c| c.id) != Some(event.id()) {
+            return;
+        }
+        match event {
+            ChatEvent::Connected { .. } => self.messages.push("* Connected".to_owned()),
+            ChatEvent::Message { text, .. } => self.messages.push(text),
+            ChatEvent::Disconnected { .. } => {
+                self.disconnect();
+                self.messages.push("* Disconnected".to_owned());
+            }
+            ChatEvent::Error { error, .. } => {
+                self.disconnect();
+                self.messages.push(format!("* Error: {}", error));
+            }
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+struct Connection {
+    id: ConnectionId,
+    sender: std::sync::mpsc::Sender<Option<String>>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Connection {
+    fn open(id: ConnectionId, url: &str, bridge: Bridge) -> Result<Self, String> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let url = url.to_owned();
+        std::thread::spawn(move || {
+            let event = match run_connection(id, &url, &bridge, receiver) {
+                Ok(()) => ChatEvent::Disconnected { id },
+                Err(error) => ChatEvent::Error { id, error },
+            };
+            bridge.send(AppEvent::Chat(event));
+        });
+        Ok(Connection { id, sender })
+    }
+
+    fn send(&self, text: &str) {
+        let _ = self.sender.send(Some(text.to_owned()));
+    }
+
+    fn close(self) {
+        let _ = self.sender.send(None);
+    }
+}
+
+// Runs the connection until it is closed by either side. `None` received from
+// `receiver` requests the connection to be closed.
+#[cfg(not(target_arch = "wasm32"))]
+fn run_connection(
+    id: ConnectionId,
+    url: &str,
+    bridge: &Bridge,
+    receiver: std::sync::mpsc::Receiver<Option<String>>,
+) -> Result<(), String> {
+    use std::sync::mpsc::TryRecvError;
+    use tungstenite::{Error, Message};
+
+    let parsed = url::Url::parse(url).map_err(|e| e.to_string())?;
+    let host = parsed.host_str().ok_or("URL has no host")?;
+    let port = parsed.port_or_known_default().ok_or("URL has no port")?;
+    let stream = std::net::TcpStream::connect((host, port)).map_err(|e| e.to_string())?;
+    let (mut socket, _) = tungstenite::client(url, stream).map_err(|e| e.to_string())?;
+    // Poll for outgoing messages between reads.
+    socket
+        .get_mut()
+        .set_read_timeout(Some(std::time::Duration::from_millis(50)))
+        .map_err(|e| e.to_string())?;
+    bridge.send(AppEvent::Chat(ChatEvent::Connected { id }));
+
+    loop {
+        match socket.read_message() {
+            Ok(Message::Text(text)) => {
+                if !bridge.send(AppEvent::Chat(ChatEvent::Message { id, text })) {
+                    return Ok(());
+                }
+            }
+            Ok(_) => {}
+            Err(Error::Io(ref e))
+                if e.kind() == std::io::ErrorKind::WouldBlock
+                    || e.kind() == std::io::ErrorKind::TimedOut => {}
+            Err(Error::ConnectionClosed) | Err(Error::AlreadyClosed) => return Ok(()),
+            Err(err) => return Err(err.to_string()),
+        }
+        loop {
+            match receiver.try_recv() {
+                Ok(Some(text)) => socket
+                    .write_message(Message::Text(text))
+                    .map_err(|e| e.to_string())?,
+                Ok(None) | Err(TryRecvError::Disconnected) => {
+                    let _ = socket.close(None);
+                    let _ = socket.write_pending();
+                    return Ok(());
+                }
+                Err(TryRecvError::Empty) => break,
+            }
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+struct Connection {
+    id: ConnectionId,
+    socket: web_sys::WebSocket,
+    // The callbacks must be kept alive for as long as the socket is open.
+    _callbacks: Vec<wasm_bindgen::closure::Closure<dyn FnMut(web_sys::Event)>>,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Connection {
+    fn open(id: ConnectionId, url: &str, bridge: Bridge) -> Result<Self, String> {
+        use wasm_bindgen::{closure::Closure, JsCast};
+
+        let socket = web_sys::WebSocket::new(url)
+            .map_err(|err| err.as_string().unwrap_or_else(|| "invalid URL".to_owned()))?;
+
+        let callback = |f: Box<dyn FnMut(web_sys::Event)>| Closure::wrap(f);
+        let on_open = {
+            let bridge = bridge.clone();
+            callback(Box::new(move |_: web_sys::Event| {
+                bridge.send(AppEvent::Chat(ChatEvent::Connected { id }));
+            }))
+        };
+        let on_message = {
+            let bridge = bridge.clone();
+            callback(Box::new(move |event: web_sys::Event| {
+                let event: web_sys::MessageEvent = event.unchecked_into();
+                if let Some(text) = event.data().as_string() {
+                    bridge.send(AppEvent::Chat(ChatEvent::Message { id, text }));
+                }
+            }))
+        };
+        let on_close = {
+            let bridge = bridge.clone();
+            callback(Box::new(move |_: web_sys::Event| {
+                bridge.send(AppEvent::Chat(ChatEvent::Disconnected { id }));
+            }))
+        };
+        let on_error = callback(Box::new(move |_: web_sys::Event| {
+            bridge.send(AppEvent::Chat(ChatEvent::Error {
+                id,
+                error: "WebSocket error".to_owned(),
+            }));
+        }));
+        socket.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+        socket.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+        socket.set_onclose(Some(on_close.as_ref().unchecked_ref()));
+        socket.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+
+        Ok(Connection {
+            id,
+            socket,
+            _callbacks: vec![on_open, on_message, on_close, on_error],
+        })
+    }
+
+    fn send(&self, text: &str) {
+        let _ = self.socket.send_with_str(text);
+    }
+
+    fn close(self) {
+        self.socket.set_onopen(None);
+        self.socket.set_onmessage(None);
+        self.socket.set_onclose(None);
+        self.socket.set_onerror(None);
+        let _ = self.socket.close();
+    }
+}
+
+#[derive(WidgetCommon)]
+pub struct ChatWidget<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    chat: &'a mut ChatState,
+    bridge: &'a Bridge,
+}
+
+impl<'a> ChatWidget<'a> {
+    pub fn new(chat: &'a mut ChatState, bridge: &'a Bridge) -> Self {
+        ChatWidget {
+            common: widget::CommonBuilder::default(),
+            chat,
+            bridge,
+        }
+    }
+}
+
+widget_ids! {
+    pub struct ChatIds {
+        url,
+        connect,
+        messages,
+        input,
+        send,
+    }
+}
+
+impl<'a> Widget for ChatWidget<'a> {
+    type State = ChatIds;
+    type Style = ();
+    type Event = ();
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        ChatIds::new(id_gen)
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs {
+            id,
+            state,
+            ui,
+            rect,
+            ..
+        } = args;
+        let ChatWidget { chat, bridge, .. } = self;
+
+        const PAD: conrod_core::Scalar = 8.0;
+        const ROW_H: conrod_core::Scalar = 28.0;
+        const BUTTON_W: conrod_core::Scalar = 100.0;
+        let field_w = (rect.w() - BUTTON_W - PAD * 3.0).max(0.0);
+
+        for event in widget::TextBox::new(&chat.url)
+            .font_size(14)
+            .w_h(field_w, ROW_H)
+            .top_left_with_margin_on(id, PAD)
+            .set(state.url, ui)
+        {
+            match event {
+                widget::text_box::Event::Update(url) => chat.url = url,
+                widget::text_box::Event::Enter => chat.connect(bridge),
+            }
+        }
+        let connect_label = if chat.is_connected() {
+            "Disconnect"
+        } else {
+            "Connect"
+        };
+        if widget::Button::new()
+            .label(connect_label)
+            .label_font_size(14)
+            .w_h(BUTTON_W, ROW_H)
+            .right_from(state.url, PAD)
+            .set(state.connect, ui)
+            .was_clicked()
+        {
+            if chat.is_connected() {
+                chat.disconnect();
+            } else {
+                chat.connect(bridge);
+            }
+        }
+
+        let list_h = (rect.h() - ROW_H * 2.0 - PAD * 4.0).max(0.0);
+        let (mut items, scrollbar) = widget::List::flow_down(chat.messages.len())
+            .item_size(20.0)
+            .scrollbar_on_top()
+            .w_h(rect.w() - PAD * 2.0, list_h)
+            .down_from(state.url, PAD)
+            .set(state.messages, ui);
+        while let Some(item) = items.next(ui) {
+            let text = widget::Text::new(&chat.messages[item.i])
+                .font_size(14)
+                .color(color::WHITE);
+            item.set(text, ui);
+        }
+        if let Some(scrollbar) = scrollbar {
+            scrollbar.set(ui);
+        }
+
+        let mut send = false;
+        for event in widget::TextBox::new(&chat.input)
+            .font_size(14)
+            .w_h(field_w, ROW_H)
+            .bottom_left_with_margin_on(id, PAD)
+            .set(state.input, ui)
+        {
+            match event {
+                widget::text_box::Event::Update(input) => chat.input = input,
+                widget::text_box::Event::Enter => send = true,
+            }
+        }
+        if widget::Button::new()
+            .label("Send")
+            .label_font_size(14)
+            .w_h(BUTTON_W, ROW_H)
+            .right_from(state.input, PAD)
+            .set(state.send, ui)
+            .was_clicked()
+        {
+            send = true;
+        }
+        if send {
+            chat.send_input();
+        }
+    }
+}
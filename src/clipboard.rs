@@ -0,0 +1,109 @@
+// Copying text to the system clipboard, and pasting from it.
+//
+// On the web there is no clipboard API without unstable web-sys features, so
+// the text is selected in a temporary text area and copied with the `copy`
+// command, which browsers allow while handling a click. Reading the clipboard
+// is only allowed in the `paste` event which the browser sends for Ctrl+V, so
+// pasted text comes to the event loop through the `Bridge` instead.
+
+#[cfg(target_arch = "wasm32")]
+use crate::bridge::{AppEvent, Bridge};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+
+pub struct Clipboard {
+    /// Opened on first use. It is kept open as on X11 the copied text
+    /// is only available while it is.
+    #[cfg(not(target_arch = "wasm32"))]
+    context: Option<copypasta::ClipboardContext>,
+}
+
+impl Default for Clipboard {
+    fn default() -> Self {
+        Clipboard::new()
+    }
+}
+
+impl Clipboard {
+    pub fn new() -> Self {
+        Clipboard {
+            #[cfg(not(target_arch = "wasm32"))]
+            context: None,
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_text(&mut self, text: &str) -> Result<(), String> {
+        use copypasta::ClipboardProvider;
+        self.context()?
+            .set_contents(text.to_owned())
+            .map_err(|e| e.to_string())
+    }
+
+    /// The text on the clipboard, with Windows line endings turned into
+    /// `\n` as conrod's text boxes expect.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn get_text(&mut self) -> Result<String, String> {
+        use copypasta::ClipboardProvider;
+        let text = self.context()?.get_contents().map_err(|e| e.to_string())?;
+        Ok(text.replace("\r\n", "\n"))
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn context(&mut self) -> Result<&mut copypasta::ClipboardContext, String> {
+        let context = match self.context.take() {
+            Some(context) => context,
+            None => copypasta::ClipboardContext::new().map_err(|e| e.to_string())?,
+        };
+        Ok(self.context.get_or_insert(context))
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn set_text(&mut self, text: &str) -> Result<(), String> {
+        let document = web_sys::window()
+            .and_then(|window| window.document())
+            .ok_or("no document")?;
+        let body = document.body().ok_or("no document body")?;
+        let text_area = document
+            .create_element("textarea")
+            .ok()
+            .and_then(|element| element.dyn_into::<web_sys::HtmlTextAreaElement>().ok())
+            .ok_or("cannot create a text area")?;
+        text_area.set_value(text);
+        body.append_child(&text_area)
+            .map_err(|_| "cannot add the text area")?;
+        text_area.select();
+        let copied = document
+            .dyn_ref::<web_sys::HtmlDocument>()
+            .and_then(|document| document.exec_command("copy").ok())
+            .unwrap_or(false);
+        let _ = body.remove_child(&text_area);
+        if copied {
+            Ok(())
+        } else {
+            Err("the browser refused to copy".to_owned())
+        }
+    }
+}
+
+/// Post the text of each `paste` event on the page to the event loop as an
+/// `AppEvent::Paste`, where it is given to conrod as text input.
+#[cfg(target_arch = "wasm32")]
+pub fn listen_for_paste(document: &web_sys::Document, bridge: &Bridge) -> Result<(), JsValue> {
+    let on_paste = {
+        let bridge = bridge.clone();
+        Closure::wrap(Box::new(move |event: web_sys::ClipboardEvent| {
+            let text = event
+                .clipboard_data()
+                .and_then(|data| data.get_data("text").ok())
+                .filter(|text| !text.is_empty());
+            if let Some(text) = text {
+                event.prevent_default();
+                bridge.send(AppEvent::Paste(text.replace("\r\n", "\n")));
+            }
+        }) as Box<dyn FnMut(_)>)
+    };
+    document.add_event_listener_with_callback("paste", on_paste.as_ref().unchecked_ref())?;
+    on_paste.forget();
+    Ok(())
+}
@@ -0,0 +1,169 @@
+// A round clock with hands, on the backdrop above the windows, as a window
+// shaped by its content. It shows UTC, as there is nothing to find out the
+// time zone on the desktop.
+
+use crate::shaped_window::{ShapedWindowState, Silhouette};
+use conrod_core::{
+    color, widget, widget_ids, Colorable, Dimensions, Point, Positionable, Widget, WidgetCommon,
+};
+use std::time::Duration;
+
+pub struct ClockState {
+    pub window: ShapedWindowState,
+}
+
+impl Default for ClockState {
+    fn default() -> Self {
+        ClockState::new()
+    }
+}
+
+impl ClockState {
+    pub const SIZE: conrod_core::Scalar = 120.0;
+
+    pub fn new() -> Self {
+        ClockState {
+            window: ShapedWindowState::new(),
+        }
+    }
+
+    /// How long until the second hand moves, after which the UI needs to be
+    /// updated.
+    pub fn until_tick() -> Duration {
+        let millis = (seconds_of_day() * 1000.0) as u64 % 1000;
+        Duration::from_millis(1000 - millis)
+    }
+}
+
+/// The face, which is a disc, smoothed over a point at the edge.
+pub struct ClockSilhouette;
+
+impl Silhouette for ClockSilhouette {
+    fn alpha(&self, xy: Point, dim: Dimensions) -> f32 {
+        let radius = dim[0].min(dim[1]) / 2.0;
+        let distance = (xy[0] * xy[0] + xy[1] * xy[1]).sqrt();
+        (radius - distance + 0.5).max(0.0).min(1.0) as f32
+    }
+}
+
+/// The seconds since midnight UTC.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn seconds_of_day() -> f64 {
+    let since_epoch = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    since_epoch.as_secs_f64() % 86400.0
+}
+
+/// The seconds since midnight UTC.
+#[cfg(target_arch = "wasm32")]
+pub fn seconds_of_day() -> f64 {
+    js_sys::Date::now() / 1000.0 % 86400.0
+}
+
+/// The face and hands, set over the `ShapedWindow` `window` as graphics for
+/// it.
+#[derive(WidgetCommon)]
+pub struct ClockFace {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    window: widget::Id,
+}
+
+impl ClockFace {
+    pub fn new(window: widget::Id) -> Self {
+        ClockFace {
+            common: widget::CommonBuilder::default(),
+            window,
+        }
+    }
+}
+
+widget_ids! {
+    pub struct ClockFaceIds {
+        face,
+        ticks[],
+        hour,
+        minute,
+        second,
+        label,
+    }
+}
+
+impl Widget for ClockFace {
+    type State = ClockFaceIds;
+    type Style = ();
+    type Event = ();
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        ClockFaceIds::new(id_gen)
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs {
+            id,
+            state,
+            rect,
+            ui,
+            ..
+        } = args;
+        let window = self.window;
+
+        if state.ticks.len() < 12 {
+            let id_gen = &mut ui.widget_id_generator();
+            state.update(|state| state.ticks.resize(12, id_gen));
+        }
+
+        let radius = rect.w().min(rect.h()) / 2.0;
+        let center = rect.xy();
+        // The point at `length` times the radius from the middle, at the
+        // angle of `turns` clockwise from 12 o'clock.
+        let at = |turns: f64, length: f64| {
+            let angle = turns * std::f64::consts::PI * 2.0;
+            [
+                center[0] + angle.sin() * radius * length,
+                center[1] + angle.cos() * radius * length,
+            ]
+        };
+
+        widget::Circle::fill(radius)
+            .color(color::CHARCOAL.alpha(0.85))
+            .xy(center)
+            .parent(id)
+            .graphics_for(window)
+            .set(state.face, ui);
+        for hour in 0..12 {
+            let turns = hour as f64 / 12.0;
+            widget::Line::abs(at(turns, 0.8), at(turns, 0.92))
+                .thickness(if hour % 3 == 0 { 3.0 } else { 1.5 })
+                .color(color::LIGHT_GREY)
+                .parent(id)
+                .graphics_for(window)
+                .set(state.ticks[hour], ui);
+        }
+        widget::Text::new("UTC")
+            .font_size(10)
+            .color(color::GREY)
+            .x_y(center[0], center[1] - radius * 0.4)
+            .parent(id)
+            .graphics_for(window)
+            .set(state.label, ui);
+
+        let seconds = seconds_of_day().floor();
+        let hands = [
+            (state.hour, seconds / 43200.0, 0.5, 4.0, color::WHITE),
+            (state.minute, seconds / 3600.0, 0.75, 2.5, color::WHITE),
+            (state.second, seconds / 60.0, 0.85, 1.0, color::LIGHT_RED),
+        ];
+        for &(hand, turns, length, thickness, hand_color) in hands.iter() {
+            widget::Line::abs(center, at(turns, length))
+                .thickness(thickness)
+                .color(hand_color)
+                .parent(id)
+                .graphics_for(window)
+                .set(hand, ui);
+        }
+    }
+}
@@ -0,0 +1,244 @@
+// A debug filter simulating color blindness over the whole UI, for checking
+// that the colors of a theme stay apart.
+//
+// The UI is drawn offscreen and then through a `ColorMatrixPass` to the
+// window. The matrices are those of Machado, Oliveira and Fernandes (2009)
+// for a complete lack of each kind of cone, which work on linear RGB.
+//
+// The UI can also be drawn with multisample antialiasing, into an
+// `MsaaTarget` which is resolved into the window or the offscreen target of
+// the filter. The window itself is made without multisampling, so that the
+// number of samples can change while the demo runs.
+//
+// Where multisampling can't be had, as on a canvas the browser made without
+// antialiasing, the UI can be smoothed by an `FxaaPass` instead, on its way
+// from the offscreen target to the window. It works on the drawn pixels
+// alone, so it goes along with MSAA too.
+
+use crate::conrod_glow::{
+    ColorMatrixPass, FxaaPass, MsaaTarget, RenderTargetPool, Renderer, Texture,
+};
+use conrod_core::image;
+use glow::HasContext;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorBlindness {
+    /// No red cones.
+    Protanopia,
+    /// No green cones.
+    Deuteranopia,
+    /// No blue cones.
+    Tritanopia,
+}
+
+impl ColorBlindness {
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorBlindness::Protanopia => "protanopia",
+            ColorBlindness::Deuteranopia => "deuteranopia",
+            ColorBlindness::Tritanopia => "tritanopia",
+        }
+    }
+
+    /// The next simulation to cycle to, ending with none.
+    pub fn cycle(current: Option<Self>) -> Option<Self> {
+        match current {
+            None => Some(ColorBlindness::Protanopia),
+            Some(ColorBlindness::Protanopia) => Some(ColorBlindness::Deuteranopia),
+            Some(ColorBlindness::Deuteranopia) => Some(ColorBlindness::Tritanopia),
+            Some(ColorBlindness::Tritanopia) => None,
+        }
+    }
+
+    /// The matrix mapping linear RGB to what is seen, row by row.
+    pub fn matrix(self) -> [f32; 9] {
+        match self {
+            ColorBlindness::Protanopia => [
+                0.152286, 1.052583, -0.204868, //
+                0.114503, 0.786281, 0.099216, //
+                -0.003882, -0.048116, 1.051998,
+            ],
+            ColorBlindness::Deuteranopia => [
+                0.367322, 0.860646, -0.227968, //
+                0.280085, 0.672501, 0.047413, //
+                -0.011820, 0.042940, 0.968881,
+            ],
+            ColorBlindness::Tritanopia => [
+                1.255528, -0.076749, -0.178779, //
+                -0.078411, 0.930809, 0.147602, //
+                0.004733, 0.691367, 0.303900,
+            ],
+        }
+    }
+}
+
+/// Draws the UI to the window, through the simulation if one is on, and
+/// multisampled or smoothed if asked.
+pub struct ColorFilter {
+    /// The pass of the last simulation, built when it is first used.
+    pass: Option<(ColorBlindness, ColorMatrixPass)>,
+    /// Built when FXAA is first asked for.
+    fxaa: Option<FxaaPass>,
+    /// The multisampled target, with the samples it was asked for.
+    msaa: Option<(u32, MsaaTarget)>,
+    /// The samples which a target could not be made for, which are not
+    /// tried again until others are asked for.
+    failed_samples: Option<u32>,
+}
+
+impl Default for ColorFilter {
+    fn default() -> Self {
+        ColorFilter::new()
+    }
+}
+
+impl ColorFilter {
+    pub fn new() -> Self {
+        ColorFilter {
+            pass: None,
+            fxaa: None,
+            msaa: None,
+            failed_samples: None,
+        }
+    }
+
+    /// The samples per pixel the UI was last drawn with, 0 without
+    /// multisampling.
+    pub fn samples(&self) -> u32 {
+        self.msaa.as_ref().map_or(0, |(_, msaa)| msaa.samples())
+    }
+
+    /// Draw what `renderer` was filled with to the default framebuffer of
+    /// `size` pixels, which the caller has cleared, through the simulation of
+    /// `kind`, with `samples` per pixel or without multisampling for 0, and
+    /// smoothed by FXAA if `fxaa` is on.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw(
+        &mut self,
+        gl: &glow::Context,
+        pool: &mut RenderTargetPool,
+        renderer: &Renderer,
+        image_map: &image::Map<Texture>,
+        (width, height): (u32, u32),
+        kind: Option<ColorBlindness>,
+        samples: u32,
+        fxaa: bool,
+    ) -> Result<(), String> {
+        // There is no target to make for a minimized window.
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+        if kind.is_none() && !fxaa {
+            return self.draw_ui(gl, renderer, image_map, (width, height), samples, None);
+        }
+        if let Some(kind) = kind {
+            let pass = match self.pass.take() {
+                Some((pass_kind, pass)) if pass_kind == kind => pass,
+                other => {
+                    if let Some((_, pass)) = other {
+                        pass.delete(gl);
+                    }
+                    ColorMatrixPass::new(gl, renderer.info(), kind.matrix())?
+                }
+            };
+            self.pass = Some((kind, pass));
+        }
+        if fxaa && self.fxaa.is_none() {
+            self.fxaa = Some(FxaaPass::new(gl, renderer.info())?);
+        }
+
+        let is_srgb = renderer.info().framebuffer_srgb;
+        let mut target = pool.take(gl, width, height, is_srgb)?;
+        target.bind(gl);
+        unsafe {
+            gl.clear(glow::COLOR_BUFFER_BIT);
+        }
+        let mut result = self.draw_ui(
+            gl,
+            renderer,
+            image_map,
+            (width, height),
+            samples,
+            Some(target.framebuffer()),
+        );
+        // Smoothed into another target first, for the simulation to see the
+        // edges as they are shown.
+        if let (true, Some(fxaa), Some(_)) = (result.is_ok(), &self.fxaa, kind) {
+            match pool.take(gl, width, height, is_srgb) {
+                Ok(smoothed) => {
+                    smoothed.bind(gl);
+                    fxaa.draw(gl, target.texture(), (width, height));
+                    pool.give_back(gl, std::mem::replace(&mut target, smoothed));
+                }
+                Err(err) => result = Err(err),
+            }
+        }
+        unsafe {
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+            gl.viewport(0, 0, width as i32, height as i32);
+        }
+        if result.is_ok() {
+            match (&self.pass, kind, &self.fxaa) {
+                (Some((_, pass)), Some(_), _) => pass.draw(gl, target.texture()),
+                (_, None, Some(fxaa)) => fxaa.draw(gl, target.texture(), (width, height)),
+                _ => {}
+            }
+        }
+        pool.give_back(gl, target);
+        result
+    }
+
+    /// Draw the UI into `framebuffer`, which is bound, through the
+    /// multisampled target unless `samples` is 0.
+    fn draw_ui(
+        &mut self,
+        gl: &glow::Context,
+        renderer: &Renderer,
+        image_map: &image::Map<Texture>,
+        (width, height): (u32, u32),
+        samples: u32,
+        framebuffer: Option<glow::Framebuffer>,
+    ) -> Result<(), String> {
+        let draw = || renderer.draw(gl, image_map).map_err(|err| err.to_string());
+        if self.failed_samples != Some(samples) {
+            self.failed_samples = None;
+        }
+        if samples == 0 || self.failed_samples.is_some() {
+            if let Some((_, msaa)) = self.msaa.take() {
+                msaa.delete(gl);
+            }
+            return draw();
+        }
+        let is_srgb = renderer.info().framebuffer_srgb;
+        let msaa = match self.msaa.take() {
+            Some((asked, msaa))
+                if asked == samples
+                    && msaa.dimensions() == (width, height)
+                    && msaa.is_srgb() == is_srgb =>
+            {
+                msaa
+            }
+            other => {
+                if let Some((_, msaa)) = other {
+                    msaa.delete(gl);
+                }
+                match MsaaTarget::new(gl, width, height, samples, is_srgb) {
+                    Ok(msaa) => msaa,
+                    Err(err) => {
+                        self.failed_samples = Some(samples);
+                        draw()?;
+                        return Err(format!("Cannot draw with {}x MSAA: {}", samples, err));
+                    }
+                }
+            }
+        };
+        msaa.bind(gl);
+        unsafe {
+            gl.clear(glow::COLOR_BUFFER_BIT);
+        }
+        let result = draw();
+        msaa.resolve(gl, framebuffer);
+        self.msaa = Some((samples, msaa));
+        result
+    }
+}
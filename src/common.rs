@@ -1,5 +1,62 @@
-use conrod_core::{widget, widget_ids, Colorable, Positionable, Sizeable, Widget, WidgetCommon};
+use crate::attention::Attention;
+use crate::audit::AuditOverlay;
+use crate::bridge::{AppEvent, Bridge};
+use crate::capture::CaptureEvent;
+use crate::clipboard::Clipboard;
+use crate::clock::{ClockFace, ClockSilhouette, ClockState};
+use crate::color_filter::ColorBlindness;
+use crate::compact::{CompactLayout, CompactTabs};
+use crate::config::Config;
+use crate::conrod_winit_compat::ClipboardShortcut;
+use crate::data_table::{DataTableState, DataTableWidget};
+use crate::designer::{DesignerState, DesignerWidget};
+use crate::desktop_widgets::{DesktopWidgetsLayer, DesktopWidgetsState};
+use crate::diagnostics::{Diagnostics, DiagnosticsWidget};
+use crate::error::{self, AppError};
+use crate::extra_windows::{ExtraContent, ExtraKind, ExtraWindowWidget, ExtraWindows};
+use crate::file_browser::{FileBrowserState, FileBrowserWidget};
+use crate::file_dialog::{self, FileEvent, FilePurpose};
+use crate::file_menu::{FileMenu, FileMenuAction};
+use crate::focus::FocusState;
+use crate::game_view::{GameViewState, GameViewWidget};
+use crate::gesture::Gesture;
+use crate::hotspots::Hotspots;
+use crate::image_viewer::{ImageViewerState, ImageViewerWidget};
+use crate::input::KeyboardState;
+use crate::layout::WindowLayout;
+use crate::metrics::{self, MetricsProvider};
+use crate::minimap::{MinimapState, MinimapWidget};
+use crate::mouse_bindings::MouseAction;
+use crate::node_graph::{NodeGraphState, NodeGraphWidget};
+use crate::notes::{NotesState, NotesWidget};
+use crate::notifications::{Notifications, NotificationsWidget};
+use crate::palette::PaletteOverrides;
+use crate::pen::PenInput;
+use crate::profiler::{Profiler, ProfilerWidget};
+use crate::raw_input::RawInput;
+use crate::session::{NotesSession, Session, SESSION_VERSION};
+use crate::settings::SettingsWidget;
+use crate::shaped_window::ShapedWindow;
+use crate::shortcuts::{Command, Shortcuts};
+use crate::sketchpad::{SketchpadState, SketchpadWidget};
+use crate::tasks::{self, TaskEvent, TaskManager, TaskStatus, TasksWidget};
+use crate::timers::{TimerId, TimerMsg, Timers};
+use crate::title_bar::TitleBars;
+use crate::tool_palette::{Tool, ToolPalette};
+use crate::trace::{now_us, Jank};
+use crate::whiteboard::{WhiteboardState, WhiteboardWidget};
+use crate::window_modes::{WindowControl, WindowModes};
+use crate::window_switcher::{SwitcherState, WindowSwitcher};
+use crate::workspaces::{WindowTracker, WorkspaceAction, WorkspaceBar};
+use conrod_core::event::{Input, Motion};
+use conrod_core::{
+    widget, widget_ids, Colorable, Positionable, Sizeable, Ui, Widget, WidgetCommon,
+};
 use conrod_floatwin::{WinId, WindowBuilder, WindowingArea, WindowingContext, WindowingState};
+use winit::event::VirtualKeyCode;
+
+/// How often the user is told about slow frames. They are all logged.
+const JANK_NOTICE_INTERVAL_US: f64 = 10_000_000.0;
 
 #[derive(WidgetCommon)]
 pub struct ExampleWidget<'a> {
@@ -40,18 +97,953 @@ widget_ids! {
         text,
         button,
         conrod_example,
+        notifications,
+        audit,
+        file_menu,
+        focus_outline,
+        workspace_bar,
+        compact_tabs,
+        desktop_widgets,
+        clock,
+        clock_face,
+        new_window,
+        window_switcher,
+        unfocused_dim,
     }
 }
 
 pub struct WinIds {
     pub conrod_example: WinId,
+    #[cfg(feature = "chat")]
+    pub chat: WinId,
+    pub notes: WinId,
+    #[cfg(feature = "markdown")]
+    pub markdown: WinId,
+    pub image_viewer: WinId,
+    pub profiler: WinId,
+    pub settings: WinId,
+    pub game_view: WinId,
+    pub minimap: WinId,
+    pub sketchpad: WinId,
+    pub whiteboard: WinId,
+    pub node_graph: WinId,
+    pub data_table: WinId,
+    pub file_browser: WinId,
+    pub tool_palette: WinId,
+    pub tasks: WinId,
+    pub diagnostics: WinId,
+    pub designer: WinId,
+    #[cfg(all(feature = "terminal", not(target_arch = "wasm32")))]
+    pub terminal: WinId,
+    /// The ids of the slots of `UiState::extra_windows`, allocated as the
+    /// slots are added.
+    pub extra: Vec<WinId>,
+}
+
+impl WinIds {
+    pub fn new(win_state: &mut WindowingState) -> Self {
+        WinIds {
+            conrod_example: win_state.next_id(),
+            #[cfg(feature = "chat")]
+            chat: win_state.next_id(),
+            notes: win_state.next_id(),
+            #[cfg(feature = "markdown")]
+            markdown: win_state.next_id(),
+            image_viewer: win_state.next_id(),
+            profiler: win_state.next_id(),
+            settings: win_state.next_id(),
+            game_view: win_state.next_id(),
+            minimap: win_state.next_id(),
+            sketchpad: win_state.next_id(),
+            whiteboard: win_state.next_id(),
+            node_graph: win_state.next_id(),
+            data_table: win_state.next_id(),
+            file_browser: win_state.next_id(),
+            tool_palette: win_state.next_id(),
+            tasks: win_state.next_id(),
+            diagnostics: win_state.next_id(),
+            designer: win_state.next_id(),
+            #[cfg(all(feature = "terminal", not(target_arch = "wasm32")))]
+            terminal: win_state.next_id(),
+            extra: Vec::new(),
+        }
+    }
+
+    /// Allocate the ids of the extra windows up to `slot_count` slots.
+    pub fn reserve_extra(&mut self, slot_count: usize, win_state: &mut WindowingState) {
+        while self.extra.len() < slot_count {
+            self.extra.push(win_state.next_id());
+        }
+    }
 }
 
 pub struct UiState {
     pub enable_debug: bool,
+    /// The color blindness simulated over the whole UI by the event loop.
+    pub color_filter: Option<ColorBlindness>,
+    /// Whether the accessibility audit overlay is shown.
+    pub show_audit: bool,
+    /// Whether the OS window has the keyboard focus. Everything is dimmed
+    /// while it doesn't.
+    pub window_focused: bool,
     pub win_state: WindowingState,
     pub win_ids: WinIds,
     pub conrod_example_app: conrod_example_shared::DemoApp,
+    pub bridge: Bridge,
+    pub config: Config,
+    /// Whether changes to `config` are saved. Off for the headless
+    /// simulation, which shouldn't touch the user's configuration.
+    pub persist_config: bool,
+    pub notifications: Notifications,
+    pub file_menu_open: bool,
+    #[cfg(feature = "chat")]
+    pub chat: crate::chat::ChatState,
+    pub notes: NotesState,
+    /// The Notes text parsed for the Markdown window.
+    #[cfg(feature = "markdown")]
+    pub markdown: crate::markdown::MarkdownState,
+    pub image_viewer: ImageViewerState,
+    pub profiler: Profiler,
+    pub shortcuts: Shortcuts,
+    pub game_view: GameViewState,
+    /// The tile map of the Minimap window. The event loop uploads its
+    /// changes.
+    pub minimap: MinimapState,
+    pub sketchpad: SketchpadState,
+    pub whiteboard: WhiteboardState,
+    pub node_graph: NodeGraphState,
+    pub data_table: DataTableState,
+    pub file_browser: FileBrowserState,
+    pub tool: Tool,
+    pub tasks: TaskManager,
+    #[cfg(all(feature = "terminal", not(target_arch = "wasm32")))]
+    pub terminal: crate::terminal::TerminalState,
+    /// Set by Ctrl+F until the next `set_widgets`, for the focused window to
+    /// open its search bar.
+    pub find_requested: bool,
+    /// Set by `Command::GatherWindows` until the next `set_widgets`, which
+    /// knows where the windows are.
+    pub gather_requested: bool,
+    /// The focused window, moved by `config.focus_policy`.
+    pub focus: FocusState,
+    /// The windows which asked for attention while they weren't focused.
+    pub attention: Attention,
+    pub window_tracker: WindowTracker,
+    pub title_bars: TitleBars,
+    pub compact: CompactLayout,
+    /// The minimized and maximized windows. The position and size to restore
+    /// a maximized window to stay in `win_state`, under its usual id.
+    pub window_modes: WindowModes,
+    /// Where the windows are, read back after each update for
+    /// `save_layout`.
+    pub layout: WindowLayout,
+    /// Whether `layout` changed since it was last saved. The wasm event loop
+    /// saves it once the mouse is released.
+    pub layout_dirty: bool,
+    /// The palettes picked for windows from their context menus.
+    pub palettes: PaletteOverrides,
+    /// The window whose workspace menu is open, and where it opens.
+    pub workspace_menu: Option<(&'static str, conrod_core::Point)>,
+    /// The Alt+Tab window switcher. The event loop takes the snapshot for
+    /// its thumbnails and deletes it when it is closed.
+    pub switcher: SwitcherState,
+    /// The window content which takes the key events from conrod.
+    pub raw_input: RawInput,
+    /// The keys which are held down, kept up to date by the event loop.
+    pub keyboard: KeyboardState,
+    /// Set by `set_widgets` when something is animating, so that the event
+    /// loop keeps updating the UI.
+    pub keep_updating: bool,
+    /// Images which have been opened and decoded but still need to be
+    /// uploaded to the GPU by the event loop, which owns the GL context and
+    /// the `image::Map`.
+    pub pending_images: Vec<(String, ::image::RgbaImage)>,
+    /// Set when the user asked to export the view, with the scale factor to
+    /// export at. The event loop does the rendering and then calls
+    /// `finish_export`.
+    pub pending_export: Option<u32>,
+    /// Set when the user asked for a screenshot. The event loop reads the
+    /// next frame back and calls `finish_screenshot`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub pending_screenshot: bool,
+    /// Set when the user asked to start or stop replaying the last frame.
+    /// The event loop keeps the frame and calls `start_replay`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub pending_replay: bool,
+    /// Set when the user asked to record the UI, with the number of seconds
+    /// to record. The event loop owns the recorder.
+    pub pending_recording: Option<u32>,
+    /// Text to paste, which the event loop gives to conrod as `Input::Text`
+    /// so that it goes to the focused text box.
+    pub pending_paste: Option<String>,
+    /// Whether the user wants a frame trace to be recorded. The event loop
+    /// owns the `Tracer`, starts it when this is set and calls `finish_trace`
+    /// when it is cleared.
+    pub tracing: bool,
+    /// The graphics set-up, filled in by the event loop for the Diagnostics
+    /// window.
+    pub diagnostics: Diagnostics,
+    pub designer: DesignerState,
+    /// The clock on the backdrop.
+    pub clock: ClockState,
+    pub desktop_widgets: DesktopWidgetsState,
+    /// The readings of the machine, for the desktop widgets and the
+    /// Diagnostics window.
+    pub metrics: Box<dyn MetricsProvider>,
+    /// Refreshes the `metrics` while anything shows them.
+    metrics_timer: Option<TimerId>,
+    /// The windows opened from the New window menu.
+    pub extra_windows: ExtraWindows,
+    #[cfg(all(feature = "devserver", not(target_arch = "wasm32")))]
+    pub devserver: Option<crate::devserver::DevServer>,
+    /// Inputs from the dev server, which the event loop gives to conrod
+    /// before the next update.
+    #[cfg(all(feature = "devserver", not(target_arch = "wasm32")))]
+    pub injected_inputs: Vec<Input>,
+    /// What a mirror viewer received, which the event loop draws.
+    #[cfg(all(feature = "mirror", not(target_arch = "wasm32")))]
+    pub mirror_inbox: Vec<crate::mirror::MirrorMessage>,
+    pub clipboard: Clipboard,
+    /// When the user was last told about a slow frame, in microseconds.
+    /// Starts at the creation, as the first frames are always slow.
+    last_jank_notice: f64,
+    /// Wake-ups for things which happen after some time, handled by
+    /// `handle_timer`.
+    pub timers: Timers,
+    /// The links and other regions which change the cursor, registered by
+    /// the widgets in each `set_widgets`.
+    pub hotspots: Hotspots,
+}
+
+impl UiState {
+    pub fn new(
+        conrod_example_app: conrod_example_shared::DemoApp,
+        bridge: Bridge,
+        config: Config,
+    ) -> Self {
+        let mut win_state = WindowingState::new();
+        let win_ids = WinIds::new(&mut win_state);
+        let tasks = TaskManager::new(bridge.clone());
+        UiState {
+            enable_debug: false,
+            color_filter: None,
+            show_audit: false,
+            window_focused: true,
+            win_state,
+            win_ids,
+            conrod_example_app,
+            bridge,
+            config,
+            persist_config: true,
+            notifications: Notifications::new(),
+            file_menu_open: false,
+            #[cfg(feature = "chat")]
+            chat: crate::chat::ChatState::new(),
+            notes: NotesState::new(),
+            #[cfg(feature = "markdown")]
+            markdown: crate::markdown::MarkdownState::new(),
+            image_viewer: ImageViewerState::new(),
+            profiler: Profiler::new(),
+            shortcuts: Shortcuts::new(),
+            game_view: GameViewState::new(),
+            minimap: MinimapState::new(),
+            sketchpad: SketchpadState::new(),
+            whiteboard: WhiteboardState::new(),
+            node_graph: NodeGraphState::new(),
+            data_table: DataTableState::new(),
+            file_browser: FileBrowserState::new(),
+            tool: Tool::Select,
+            tasks,
+            #[cfg(all(feature = "terminal", not(target_arch = "wasm32")))]
+            terminal: crate::terminal::TerminalState::new(),
+            find_requested: false,
+            gather_requested: false,
+            focus: FocusState::new(),
+            attention: Attention::new(),
+            window_tracker: WindowTracker::new(),
+            title_bars: TitleBars::new(),
+            compact: CompactLayout::new(),
+            window_modes: WindowModes::new(),
+            layout: WindowLayout::default(),
+            layout_dirty: false,
+            palettes: PaletteOverrides::new(),
+            workspace_menu: None,
+            switcher: SwitcherState::new(),
+            raw_input: RawInput::new(),
+            keyboard: KeyboardState::new(),
+            keep_updating: false,
+            pending_images: Vec::new(),
+            pending_export: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            pending_screenshot: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            pending_replay: false,
+            pending_recording: None,
+            pending_paste: None,
+            tracing: false,
+            diagnostics: Diagnostics::default(),
+            designer: DesignerState::new(),
+            clock: ClockState::new(),
+            desktop_widgets: DesktopWidgetsState::new(),
+            metrics: metrics::provider(),
+            metrics_timer: None,
+            extra_windows: ExtraWindows::new(),
+            #[cfg(all(feature = "devserver", not(target_arch = "wasm32")))]
+            devserver: None,
+            #[cfg(all(feature = "devserver", not(target_arch = "wasm32")))]
+            injected_inputs: Vec::new(),
+            #[cfg(all(feature = "mirror", not(target_arch = "wasm32")))]
+            mirror_inbox: Vec::new(),
+            clipboard: Clipboard::new(),
+            last_jank_notice: now_us(),
+            timers: Timers::new(),
+            hotspots: Hotspots::new(),
+        }
+    }
+
+    /// Handle an event posted through the `Bridge`.
+    pub fn handle_app_event(&mut self, event: AppEvent) {
+        match event {
+            AppEvent::Capture(event) => self.handle_capture_event(event),
+            #[cfg(feature = "chat")]
+            AppEvent::Chat(event) => {
+                self.chat.handle_event(event);
+                self.request_attention("Chat");
+            }
+            #[cfg(all(feature = "devserver", not(target_arch = "wasm32")))]
+            AppEvent::DevServer(request) => self.handle_dev_request(request),
+            AppEvent::File(event) => self.handle_file_event(event),
+            // Waking up the event loop is enough for it to take the mesh.
+            #[cfg(not(target_arch = "wasm32"))]
+            AppEvent::MeshReady => {}
+            #[cfg(target_arch = "wasm32")]
+            AppEvent::Paste(text) => self.pending_paste = Some(text),
+            #[cfg(all(feature = "mirror", not(target_arch = "wasm32")))]
+            AppEvent::Mirror(message) => self.mirror_inbox.push(message),
+            AppEvent::Task(event) => self.handle_task_event(event),
+            #[cfg(all(feature = "terminal", not(target_arch = "wasm32")))]
+            AppEvent::Terminal(event) => self.terminal.handle_event(event),
+        }
+    }
+
+    #[cfg(all(feature = "devserver", not(target_arch = "wasm32")))]
+    fn handle_dev_request(&mut self, request: crate::devserver::DevRequest) {
+        use conrod_core::input::{Button, MouseButton};
+        match request {
+            crate::devserver::DevRequest::Command(name) => {
+                let command = self
+                    .shortcuts
+                    .bindings()
+                    .iter()
+                    .map(|&(_, command)| command)
+                    .find(|command| command.name() == name);
+                match command {
+                    Some(command) => self.handle_command(command),
+                    None => self.notifications.error(format!(
+                        "The dev server asked for no such command: {}",
+                        name
+                    )),
+                }
+            }
+            crate::devserver::DevRequest::Click([x, y]) => {
+                self.injected_inputs.extend(vec![
+                    Input::Motion(Motion::MouseCursor { x, y }),
+                    Input::Press(Button::Mouse(MouseButton::Left)),
+                    Input::Release(Button::Mouse(MouseButton::Left)),
+                ]);
+            }
+            // Waking up the event loop is enough for it to update the UI.
+            crate::devserver::DevRequest::Snapshot => {}
+        }
+    }
+
+    /// Handle the message of a timer which is due.
+    pub fn handle_timer(&mut self, msg: TimerMsg) {
+        match msg {
+            TimerMsg::DismissNotification(id) => self.notifications.dismiss_id(id),
+            TimerMsg::RefreshMetrics => self.metrics.refresh(),
+            #[cfg(not(target_arch = "wasm32"))]
+            TimerMsg::ReloadDesigner => {
+                if self.designer.reload_if_changed() {
+                    self.notifications.info("Reloaded the Designer window");
+                }
+            }
+        }
+    }
+
+    /// Refresh the `metrics` every `metrics::INTERVAL` while `wanted`, and
+    /// stop when they aren't, so that they don't wake up an idle UI.
+    fn keep_metrics_refreshed(&mut self, wanted: bool) {
+        match (wanted, self.metrics_timer) {
+            (true, None) => {
+                self.metrics.refresh();
+                let timer = self
+                    .timers
+                    .every(metrics::INTERVAL, TimerMsg::RefreshMetrics);
+                self.metrics_timer = Some(timer);
+            }
+            (false, Some(timer)) => {
+                self.timers.cancel(timer);
+                self.metrics_timer = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// Handle Ctrl+C, Ctrl+X or Ctrl+V. The key press still goes on to
+    /// conrod, for the widgets which copy their own selection.
+    pub fn handle_clipboard_shortcut(&mut self, shortcut: ClipboardShortcut) {
+        match shortcut {
+            #[cfg(not(target_arch = "wasm32"))]
+            ClipboardShortcut::Paste => match self.clipboard.get_text() {
+                Ok(text) => self.pending_paste = Some(text),
+                Err(err) => self
+                    .notifications
+                    .error(format!("Cannot read the clipboard: {}", err)),
+            },
+            // The browser sends a `paste` event on its own.
+            #[cfg(target_arch = "wasm32")]
+            ClipboardShortcut::Paste => {}
+            // conrod's text boxes keep their selection to themselves, so
+            // there is nothing here to copy from them.
+            ClipboardShortcut::Copy | ClipboardShortcut::Cut => {}
+        }
+    }
+
+    /// Run the command of a keyboard shortcut.
+    pub fn handle_command(&mut self, command: Command) {
+        match command {
+            Command::OpenNotes => file_dialog::open_file(&self.bridge, FilePurpose::Notes),
+            Command::OpenImage => file_dialog::open_file(&self.bridge, FilePurpose::Image),
+            Command::ExportView => self.pending_export = Some(1),
+            #[cfg(not(target_arch = "wasm32"))]
+            Command::Screenshot => self.pending_screenshot = true,
+            #[cfg(not(target_arch = "wasm32"))]
+            Command::ReplayLastFrame => self.pending_replay = true,
+            Command::ToggleDebug => self.enable_debug = !self.enable_debug,
+            Command::CycleColorFilter => {
+                self.color_filter = ColorBlindness::cycle(self.color_filter);
+                match self.color_filter {
+                    Some(kind) => self
+                        .notifications
+                        .info(format!("Simulating {}", kind.name())),
+                    None => self.notifications.info("Color blindness simulation off"),
+                }
+            }
+            Command::ToggleAudit => self.show_audit = !self.show_audit,
+            Command::GatherWindows => self.gather_requested = true,
+            Command::Find => self.find_requested = true,
+            Command::SwitchWorkspace(index) => {
+                self.handle_workspace_action(WorkspaceAction::Switch(index))
+            }
+            Command::SaveLayout(slot) => self.save_layout_slot(slot),
+            Command::RecallLayout(slot) => self.recall_layout_slot(slot),
+            Command::NextWindow => self.switcher.cycle(false),
+            Command::PreviousWindow => self.switcher.cycle(true),
+        }
+    }
+
+    /// Handle a press of a mouse button bound in `config.mouse_bindings`.
+    ///
+    /// The notes window is the only one with a history for now.
+    pub fn handle_mouse_action(&mut self, action: MouseAction) {
+        match action {
+            MouseAction::Back => self.notes.go_back(),
+            MouseAction::Forward => self.notes.go_forward(),
+        }
+    }
+
+    /// Handle a zoom or rotate gesture.
+    ///
+    /// Only the image viewers can be zoomed, when the mouse is over them.
+    /// Nothing can be rotated yet.
+    pub fn handle_gesture(&mut self, gesture: Gesture) {
+        let amount = match gesture {
+            Gesture::Magnify(amount) => amount,
+            _ => return,
+        };
+        if self.image_viewer.hovered {
+            self.image_viewer.zoom_by(amount);
+        }
+        for (_, window) in self.extra_windows.iter_mut() {
+            if let ExtraContent::ImageViewer(viewer) = &mut window.content {
+                if viewer.hovered {
+                    viewer.zoom_by(amount);
+                }
+            }
+        }
+    }
+
+    /// Handle pen or touch input with its pressure, as converted by
+    /// `v023_convert_pen`. Only the sketchpad uses it.
+    pub fn handle_pen(&mut self, pen: PenInput) {
+        self.sketchpad.handle_pen(pen);
+    }
+
+    fn handle_task_event(&mut self, event: TaskEvent) {
+        let task = match self.tasks.handle_event(event) {
+            Some(task) => task,
+            None => return,
+        };
+        match &task.status {
+            TaskStatus::Done => self.notifications.info(format!("{} finished", task.name)),
+            TaskStatus::Failed(err) => self
+                .notifications
+                .error(format!("{} failed: {}", task.name, err)),
+            TaskStatus::Cancelled => self.notifications.info(format!("{} cancelled", task.name)),
+            TaskStatus::Running => return,
+        }
+        self.request_attention("Tasks");
+    }
+
+    /// Ask for attention to the window `title`, unless it has the focus.
+    fn request_attention(&mut self, title: &'static str) {
+        if self.focused_title() != Some(title) {
+            self.attention.request(title);
+        }
+    }
+
+    /// The title of the focused window, if it is shown.
+    fn focused_title(&self) -> Option<&'static str> {
+        let focused = self.focus.focused()?;
+        let (title, _) = self
+            .window_tracker
+            .windows()
+            .find(|&(_, window)| window == focused)?;
+        let shown = self.config.workspaces.shows(title)
+            && (!self.compact.is_on() || self.compact.active == Some(title));
+        if shown {
+            Some(title)
+        } else {
+            None
+        }
+    }
+
+    fn handle_capture_event(&mut self, event: CaptureEvent) {
+        match event {
+            CaptureEvent::Finished { name, data } => {
+                file_dialog::save_file(&self.bridge, FilePurpose::Image, name, &data)
+            }
+            CaptureEvent::Error(err) => self
+                .notifications
+                .error(format!("Recording failed: {}", err)),
+        }
+    }
+
+    fn handle_file_event(&mut self, event: FileEvent) {
+        match event {
+            FileEvent::Opened {
+                purpose: FilePurpose::Notes,
+                name,
+                data,
+            } => {
+                self.notifications.info(format!("Opened {}", name));
+                self.remember_recent_file(&name);
+                self.notes.load(name, data);
+            }
+            FileEvent::Opened {
+                purpose: FilePurpose::Image,
+                name,
+                data,
+            } => match ::image::load_from_memory(&data) {
+                Ok(image) => {
+                    self.remember_recent_file(&name);
+                    self.pending_images.push((name, image.to_rgba()));
+                }
+                Err(err) => self
+                    .notifications
+                    .error(format!("Cannot decode {}: {}", name, err)),
+            },
+            FileEvent::Opened {
+                purpose: FilePurpose::Session,
+                name,
+                data,
+            } => match Session::from_json(&data) {
+                Ok((session, newer)) => {
+                    self.import_session(session);
+                    if newer {
+                        self.notifications.info(format!(
+                            "{} is from a newer version, some of it may be missing",
+                            name
+                        ));
+                    } else {
+                        self.notifications.info(format!("Imported {}", name));
+                    }
+                }
+                Err(err) => self
+                    .notifications
+                    .error(format!("Cannot import {}: {}", name, err)),
+            },
+            FileEvent::Opened {
+                purpose: FilePurpose::Trace,
+                name,
+                ..
+            } => self
+                .notifications
+                .error(format!("Cannot open {}: traces are only saved", name)),
+            FileEvent::Saved { purpose, name } => {
+                if purpose == FilePurpose::Notes {
+                    self.notes.file_name = Some(name.clone());
+                }
+                self.notifications.info(format!("Saved {}", name));
+            }
+            FileEvent::Error(err) => self.notifications.error(err),
+        }
+    }
+
+    // Files opened in the browser cannot be reopened by name, so only native
+    // keeps track of them.
+    fn remember_recent_file(&mut self, name: &str) {
+        if cfg!(target_arch = "wasm32") {
+            return;
+        }
+        self.config.add_recent_file(name);
+        self.save_config();
+    }
+
+    fn save_config(&mut self) {
+        if !self.persist_config {
+            return;
+        }
+        if let Err(err) = self.config.save() {
+            self.notifications
+                .error(format!("Cannot save configuration: {}", err));
+        }
+    }
+
+    /// Store where the windows are, to be restored by `load_layout` at the
+    /// next startup.
+    pub fn save_layout(&mut self) -> Result<(), String> {
+        if !self.persist_config {
+            return Ok(());
+        }
+        self.layout.save()?;
+        self.layout_dirty = false;
+        Ok(())
+    }
+
+    /// Put the windows where the stored layout has them, before the first
+    /// update. The windowing state starts over for that, as floatwin only
+    /// takes the placement of a window when it is first made.
+    pub fn load_layout(&mut self) -> Result<(), String> {
+        if !self.persist_config {
+            return Ok(());
+        }
+        let layout = match WindowLayout::load()? {
+            Some(layout) => layout,
+            None => return Ok(()),
+        };
+        self.win_state = WindowingState::new();
+        self.win_ids = WinIds::new(&mut self.win_state);
+        self.window_modes.place(&layout);
+        self.layout = layout;
+        Ok(())
+    }
+
+    fn handle_workspace_action(&mut self, action: WorkspaceAction) {
+        let workspaces = &mut self.config.workspaces;
+        match action {
+            WorkspaceAction::Switch(index) if index == workspaces.current => return,
+            WorkspaceAction::Switch(index) => {
+                workspaces.current = index;
+                self.workspace_menu = None;
+            }
+            WorkspaceAction::Move(title, index) => workspaces.move_window(title, index),
+            // Only for the session, so there is nothing to save.
+            WorkspaceAction::Palette(title, palette) => {
+                self.palettes.set(title, palette);
+                return;
+            }
+        }
+        self.save_config();
+    }
+
+    fn save_layout_slot(&mut self, slot: usize) {
+        self.config.layout_slots[slot] = Some(self.config.workspaces.clone());
+        self.save_config();
+        self.notifications
+            .info(format!("Saved the layout to slot {}", slot + 1));
+    }
+
+    fn recall_layout_slot(&mut self, slot: usize) {
+        let layout = match &self.config.layout_slots[slot] {
+            Some(layout) => layout.clone(),
+            None => {
+                self.notifications
+                    .info(format!("Layout slot {} is empty", slot + 1));
+                return;
+            }
+        };
+        self.config.workspaces = layout;
+        self.workspace_menu = None;
+        self.save_config();
+        self.notifications
+            .info(format!("Recalled the layout of slot {}", slot + 1));
+    }
+
+    /// Open a window from the New window menu. A new image viewer shows the
+    /// image of the Image Viewer window.
+    fn open_extra_window(&mut self, kind: ExtraKind) {
+        let title = self
+            .extra_windows
+            .open(kind, self.image_viewer.image.clone());
+        if self.compact.is_on() {
+            self.compact.active = Some(title);
+        }
+    }
+
+    /// Close the window in `slot`, whose `WinId` goes to the next window
+    /// opened. It leaves its workspace, so the next window with its title
+    /// opens on the current one.
+    fn close_extra_window(&mut self, slot: usize) {
+        let window = match self.extra_windows.close(slot) {
+            Some(window) => window,
+            None => return,
+        };
+        self.window_tracker.forget(window.title);
+        self.window_modes.forget(window.title);
+        if self.compact.active == Some(window.title) {
+            self.compact.active = None;
+        }
+        if self.config.workspaces.workspace_of(window.title) != 0 {
+            self.config.workspaces.move_window(window.title, 0);
+            self.save_config();
+        }
+    }
+
+    fn handle_file_menu_action(&mut self, action: FileMenuAction) {
+        match action {
+            FileMenuAction::Open(purpose) => file_dialog::open_file(&self.bridge, purpose),
+            #[cfg(not(target_arch = "wasm32"))]
+            FileMenuAction::OpenRecent(name) => {
+                file_dialog::open_path(&self.bridge, std::path::Path::new(&name))
+            }
+            #[cfg(target_arch = "wasm32")]
+            FileMenuAction::OpenRecent(_) => {}
+            FileMenuAction::ExportView(scale) => self.pending_export = Some(scale),
+            FileMenuAction::Record(seconds) => self.pending_recording = Some(seconds),
+            FileMenuAction::ToggleTrace => self.tracing = !self.tracing,
+            FileMenuAction::ExportSession => self.export_session(),
+        }
+    }
+
+    fn export_session(&mut self) {
+        let session = Session {
+            version: SESSION_VERSION,
+            config: self.config.clone(),
+            notes: NotesSession {
+                text: self.notes.text.clone(),
+                file_name: self.notes.file_name.clone(),
+            },
+            whiteboard: std::mem::take(&mut self.whiteboard),
+            node_graph: std::mem::take(&mut self.node_graph),
+            tool: self.tool,
+        };
+        let result = session.to_json();
+        // Taken rather than cloned, as they may be large.
+        self.whiteboard = session.whiteboard;
+        self.node_graph = session.node_graph;
+        match result {
+            Ok(json) => {
+                file_dialog::save_file(&self.bridge, FilePurpose::Session, "session.json", &json)
+            }
+            Err(err) => self
+                .notifications
+                .error(format!("Cannot export session: {}", err)),
+        }
+    }
+
+    /// Replace the state with an imported session. The recent files are
+    /// kept, as they are about this machine.
+    fn import_session(&mut self, session: Session) {
+        let recent_files = std::mem::take(&mut self.config.recent_files);
+        self.config = session.config;
+        self.config.recent_files = recent_files;
+        self.config.normalize();
+        self.save_config();
+        self.notes.text = session.notes.text;
+        self.notes.file_name = session.notes.file_name;
+        self.whiteboard = session.whiteboard;
+        self.node_graph = session.node_graph;
+        self.node_graph.normalize();
+        self.tool = session.tool;
+    }
+
+    /// Save a frame trace recorded while `tracing` was set.
+    pub fn finish_trace(&mut self, result: Result<Vec<u8>, String>) {
+        match result {
+            Ok(json) => {
+                file_dialog::save_file(&self.bridge, FilePurpose::Trace, "trace.json", &json)
+            }
+            Err(err) => self
+                .notifications
+                .error(format!("Cannot save trace: {}", err)),
+        }
+    }
+
+    /// Save the result of an export requested through `pending_export`.
+    pub fn finish_export(&mut self, result: Result<Vec<u8>, String>) {
+        match result {
+            Ok(png) => file_dialog::save_file(&self.bridge, FilePurpose::Image, "view.png", &png),
+            Err(err) => self.notifications.error(format!("Export failed: {}", err)),
+        }
+    }
+
+    /// Save a screenshot next to the ones before, in the working directory,
+    /// so that a script can pick them up.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn finish_screenshot(&mut self, result: Result<::image::RgbaImage, String>) {
+        let saved = result.and_then(|image| {
+            let name = (1..)
+                .map(|n| format!("screenshot-{}.png", n))
+                .find(|name| !std::path::Path::new(name).exists())
+                .unwrap();
+            image
+                .save(&name)
+                .map(|()| name)
+                .map_err(|err| err.to_string())
+        });
+        match saved {
+            Ok(name) => self.notifications.info(format!("Saved {}", name)),
+            Err(err) => self
+                .notifications
+                .error(format!("Screenshot failed: {}", err)),
+        }
+    }
+
+    /// Save the bytes of the frame being replayed like a screenshot, to be
+    /// looked into or drawn again with `Renderer::import_frame`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn start_replay(&mut self, frame: &[u8]) {
+        let name = (1..)
+            .map(|n| format!("frame-{}.bin", n))
+            .find(|name| !std::path::Path::new(name).exists())
+            .unwrap();
+        match std::fs::write(&name, frame) {
+            Ok(()) => self
+                .notifications
+                .info(format!("Replaying the last frame, saved as {}", name)),
+            Err(err) => self.notifications.error(format!(
+                "Replaying the last frame, which cannot be saved: {}",
+                err
+            )),
+        }
+    }
+
+    /// Log an error from the event loop and show it as a notification,
+    /// unless it is the same as the newest one, as drawing may fail on every
+    /// frame.
+    /// Log a frame which went over the budget, with what was going on, and
+    /// tell the user now and then.
+    pub fn report_jank(&mut self, jank: Jank) {
+        let mut text = format!("Slow frame: {}", jank);
+        let focused = self.focus.focused().and_then(|focused| {
+            self.window_tracker
+                .windows()
+                .find(|&(_, window)| window == focused)
+        });
+        if let Some((title, _)) = focused {
+            text.push_str(&format!(", {} focused", title));
+        }
+        // Only known when the windows are being profiled.
+        let slowest = self
+            .profiler
+            .sorted_entries()
+            .into_iter()
+            .max_by(|a, b| a.last.partial_cmp(&b.last).unwrap());
+        if let Some(entry) = slowest {
+            text.push_str(&format!(
+                ", slowest window {} {:.1} ms",
+                entry.name,
+                entry.last / 1000.0
+            ));
+        }
+        error::warn(&text);
+
+        let now = now_us();
+        if now - self.last_jank_notice > JANK_NOTICE_INTERVAL_US {
+            self.notifications.info(text);
+            self.last_jank_notice = now;
+        }
+    }
+
+    /// Put the windows back in their initial places if the title bar of any
+    /// shown window is out of the windowing area, such as after the OS window
+    /// has shrunk. conrod_floatwin can't move a window from outside, so the
+    /// windowing state starts over instead, for all of them.
+    fn gather_windows(&mut self, ui: &Ui, area: widget::Id) {
+        const TITLE_BAR_H: conrod_core::Scalar = 24.0;
+
+        let area = match ui.rect_of(area) {
+            Some(area) => area,
+            None => return,
+        };
+        let workspaces = &self.config.workspaces;
+        let lost = self
+            .window_tracker
+            .windows()
+            .filter(|&(title, _)| workspaces.shows(title))
+            .filter_map(|(_, window)| ui.rect_of(window))
+            .filter(|rect| {
+                let bar_bottom_left = [rect.left(), rect.top() - TITLE_BAR_H];
+                !area.is_over(bar_bottom_left) || !area.is_over(rect.top_right())
+            })
+            .count();
+        if lost == 0 {
+            self.notifications.info("No windows are out of view");
+            return;
+        }
+        self.win_state = WindowingState::new();
+        self.win_ids = WinIds::new(&mut self.win_state);
+        self.window_modes.forget_ids();
+        self.notifications.info(format!(
+            "Gathered the windows, {} of which were out of view",
+            lost
+        ));
+    }
+
+    /// While a floating window is dragged or resized, keep the cursor given
+    /// to conrod `window_edge_margin` inside the windowing area, so that the
+    /// window can't be moved out of reach.
+    pub fn confine_drag(&self, input: Input, ui: &Ui, ids: &Ids) -> Input {
+        if !self.config.keep_windows_in_view {
+            return input;
+        }
+        let (x, y) = match input {
+            Input::Motion(Motion::MouseCursor { x, y }) => (x, y),
+            _ => return input,
+        };
+        let capturing = match ui.global_input().current.widget_capturing_mouse {
+            Some(capturing) => capturing,
+            None => return input,
+        };
+        // The windows are the children of the area, and capture the mouse
+        // themselves while they are dragged by the title bar or resized.
+        if ui.widget_graph().depth_parent(capturing) != Some(ids.windowing_area) {
+            return input;
+        }
+        let bounds = match ui.rect_of(ids.windowing_area) {
+            Some(rect) => rect.pad(self.config.window_edge_margin),
+            None => return input,
+        };
+        Input::Motion(Motion::MouseCursor {
+            x: x.max(bounds.left()).min(bounds.right()),
+            y: y.max(bounds.bottom()).min(bounds.top()),
+        })
+    }
+
+    pub fn report_error(&mut self, err: &AppError) {
+        let text = format!("{}: {}", err.title(), err);
+        let repeated = self
+            .notifications
+            .items()
+            .last()
+            .map_or(false, |newest| newest.text == text);
+        if !repeated {
+            error::log(err);
+            self.notifications.error(text);
+        }
+    }
 }
 
 pub fn set_widgets(
@@ -64,19 +1056,790 @@ pub fn set_widgets(
         .color(conrod_core::color::BLUE)
         .middle()
         .set(ids.backdrop, ui);
-    let mut win_ctx: WindowingContext = WindowingArea::new(&mut state.win_state, hidpi_factor)
-        .with_debug(state.enable_debug)
-        .middle_of(ids.backdrop)
+    if state.gather_requested {
+        state.gather_requested = false;
+        state.gather_windows(ui, ids.windowing_area);
+    }
+    // Ctrl+F goes to the window which had the focus when it was pressed.
+    let find_in = if state.find_requested {
+        state.focus.focused()
+    } else {
+        None
+    };
+    // The area stays clear of the edges of the OS window, and doesn't crop
+    // the windows, so that the resize handles outside of a window against its
+    // edge can still be grabbed.
+    let margin = state.config.window_edge_margin;
+    let [window_w, window_h] = ui.window_dim();
+    // Down to nothing, while minimized.
+    let area_w = (window_w - margin * 2.0).max(0.0);
+    let area_h = (window_h - margin * 2.0 - CompactLayout::TABS_H).max(0.0);
+    state
+        .compact
+        .update([area_w, area_h], &mut state.win_state, &mut state.win_ids);
+    state
+        .win_ids
+        .reserve_extra(state.extra_windows.slot_count(), &mut state.win_state);
+    let area_h = if state.compact.is_on() {
+        area_h
+    } else {
+        area_h + CompactLayout::TABS_H
+    };
+    state.window_modes.update(
+        [area_w, area_h],
+        state.compact.is_on(),
+        &mut state.win_state,
+    );
+    state.keep_updating = false;
+
+    // Under the windows, clear of the File button and the workspace pager.
+    state.desktop_widgets.count_update(now_us());
+    DesktopWidgetsLayer::new(
+        &state.config.desktop_widgets,
+        &state.desktop_widgets,
+        state.metrics.readings(),
+    )
+    .margins(CompactLayout::TABS_H + margin, 32.0 + margin)
+    .middle_of(ids.backdrop)
+    .wh_of(ids.backdrop)
+    .set(ids.desktop_widgets, ui);
+    if state.config.desktop_widgets.any_shown() {
+        state.timers.wake_after(ClockState::until_tick());
+    }
+    // Also set by the Diagnostics window when it is shown.
+    let mut wants_metrics = state.config.desktop_widgets.shows_metrics();
+
+    // The windowing context borrows `state.win_state`, so keep it in its own
+    // scope to be able to use `state` as a whole afterwards. While minimized
+    // there is no room for the windows, and none are made until there is, so
+    // that they keep their places.
+    if area_w > 0.0 && area_h > 0.0 {
+        let mut win_ctx: WindowingContext = WindowingArea::new(&mut state.win_state, hidpi_factor)
+            .with_debug(state.enable_debug)
+            .mid_bottom_with_margin_on(ids.backdrop, margin)
+            .w_h(area_w, area_h)
+            .set(ids.windowing_area, ui);
+
+        if state
+            .compact
+            .shows("Conrod Example", &state.config.workspaces)
+            && state.window_modes.shows("Conrod Example")
+        {
+            let builder = WindowBuilder::new()
+                .title("Conrod Example")
+                .initial_size([640.0, 480.0])
+                .min_size([320.0, 240.0]);
+            let (builder, win_id) = state.window_modes.fit(
+                "Conrod Example",
+                state.compact.fit(builder),
+                state.win_ids.conrod_example,
+            );
+            if let (_, Some(win)) = win_ctx.make_window(builder, win_id, ui) {
+                let example = ExampleWidget::new(&mut state.conrod_example_app);
+                let timer = state.profiler.start();
+                win.set(example, ui);
+                state.profiler.record("Conrod Example", timer);
+                state
+                    .window_tracker
+                    .track("Conrod Example", ids.windowing_area, ui);
+            }
+        }
+
+        #[cfg(feature = "chat")]
+        {
+            if state.compact.shows("Chat", &state.config.workspaces)
+                && state.window_modes.shows("Chat")
+            {
+                let builder = WindowBuilder::new()
+                    .title("Chat")
+                    .initial_size([400.0, 360.0])
+                    .min_size([280.0, 200.0]);
+                let (builder, win_id) =
+                    state
+                        .window_modes
+                        .fit("Chat", state.compact.fit(builder), state.win_ids.chat);
+                if let (_, Some(win)) = win_ctx.make_window(builder, win_id, ui) {
+                    let chat = crate::chat::ChatWidget::new(&mut state.chat, &state.bridge);
+                    let timer = state.profiler.start();
+                    win.set(chat, ui);
+                    state.profiler.record("Chat", timer);
+                    state.window_tracker.track("Chat", ids.windowing_area, ui);
+                    state
+                        .title_bars
+                        .set("Chat", &mut state.chat, &state.window_tracker, ui);
+                }
+            }
+        }
+
+        #[cfg(all(feature = "terminal", not(target_arch = "wasm32")))]
+        {
+            if state.compact.shows("Terminal", &state.config.workspaces)
+                && state.window_modes.shows("Terminal")
+            {
+                let builder = WindowBuilder::new()
+                    .title("Terminal")
+                    .initial_size([600.0, 400.0])
+                    .min_size([200.0, 120.0]);
+                let (builder, win_id) = state.window_modes.fit(
+                    "Terminal",
+                    state.compact.fit(builder),
+                    state.win_ids.terminal,
+                );
+                if let (_, Some(win)) = win_ctx.make_window(builder, win_id, ui) {
+                    let terminal = crate::terminal::TerminalWidget::new(
+                        &mut state.terminal,
+                        &mut state.raw_input,
+                        &state.bridge,
+                    );
+                    let timer = state.profiler.start();
+                    win.set(terminal, ui);
+                    state.profiler.record("Terminal", timer);
+                    state
+                        .window_tracker
+                        .track("Terminal", ids.windowing_area, ui);
+                }
+            }
+        }
+
+        if state.compact.shows("Notes", &state.config.workspaces)
+            && state.window_modes.shows("Notes")
+        {
+            let builder = WindowBuilder::new()
+                .title("Notes")
+                .initial_size([400.0, 300.0])
+                .min_size([240.0, 160.0]);
+            let (builder, win_id) =
+                state
+                    .window_modes
+                    .fit("Notes", state.compact.fit(builder), state.win_ids.notes);
+            if let (_, Some(win)) = win_ctx.make_window(builder, win_id, ui) {
+                let timer = state.profiler.start();
+                let palette = state.palettes.of("Notes");
+                win.set(
+                    NotesWidget::new(&mut state.notes, &state.bridge).palette(palette),
+                    ui,
+                );
+                state.profiler.record("Notes", timer);
+                state.window_tracker.track("Notes", ids.windowing_area, ui);
+            }
+        }
+
+        #[cfg(feature = "markdown")]
+        {
+            if state.compact.shows("Markdown", &state.config.workspaces)
+                && state.window_modes.shows("Markdown")
+            {
+                let builder = WindowBuilder::new()
+                    .title("Markdown")
+                    .initial_size([400.0, 400.0])
+                    .min_size([200.0, 160.0]);
+                let (builder, win_id) = state.window_modes.fit(
+                    "Markdown",
+                    state.compact.fit(builder),
+                    state.win_ids.markdown,
+                );
+                if let (_, Some(win)) = win_ctx.make_window(builder, win_id, ui) {
+                    let preview = crate::markdown::MarkdownWidget::new(
+                        &mut state.markdown,
+                        &state.notes.text,
+                        &mut state.hotspots,
+                    );
+                    let timer = state.profiler.start();
+                    let event = win.set(preview, ui);
+                    state.profiler.record("Markdown", timer);
+                    if let Some(text) = event.copy {
+                        match state.clipboard.set_text(&text) {
+                            Ok(()) => state.notifications.info("Copied the selection"),
+                            Err(err) => state
+                                .notifications
+                                .error(format!("Cannot copy the selection: {}", err)),
+                        }
+                    }
+                    if let Some(delay) = event.next_blink {
+                        state.timers.wake_after(delay);
+                    }
+                    state
+                        .window_tracker
+                        .track("Markdown", ids.windowing_area, ui);
+                }
+            }
+        }
+
+        if state
+            .compact
+            .shows("Image Viewer", &state.config.workspaces)
+            && state.window_modes.shows("Image Viewer")
+        {
+            let builder = WindowBuilder::new()
+                .title("Image Viewer")
+                .initial_size([400.0, 300.0])
+                .min_size([240.0, 160.0]);
+            let (builder, win_id) = state.window_modes.fit(
+                "Image Viewer",
+                state.compact.fit(builder),
+                state.win_ids.image_viewer,
+            );
+            if let (_, Some(win)) = win_ctx.make_window(builder, win_id, ui) {
+                let timer = state.profiler.start();
+                win.set(
+                    ImageViewerWidget::new(&mut state.image_viewer, &state.bridge),
+                    ui,
+                );
+                state.profiler.record("Image Viewer", timer);
+                state
+                    .window_tracker
+                    .track("Image Viewer", ids.windowing_area, ui);
+            }
+        }
+
+        if state.compact.shows("Performance", &state.config.workspaces)
+            && state.window_modes.shows("Performance")
+        {
+            let builder = WindowBuilder::new()
+                .title("Performance")
+                .initial_size([400.0, 240.0])
+                .min_size([320.0, 160.0]);
+            let (builder, win_id) = state.window_modes.fit(
+                "Performance",
+                state.compact.fit(builder),
+                state.win_ids.profiler,
+            );
+            if let (_, Some(win)) = win_ctx.make_window(builder, win_id, ui) {
+                win.set(ProfilerWidget::new(&mut state.profiler), ui);
+                state
+                    .window_tracker
+                    .track("Performance", ids.windowing_area, ui);
+            }
+        }
+
+        if state
+            .compact
+            .shows("Game Viewport", &state.config.workspaces)
+            && state.window_modes.shows("Game Viewport")
+        {
+            let builder = WindowBuilder::new()
+                .title("Game Viewport")
+                .initial_size([400.0, 300.0])
+                .min_size([240.0, 160.0]);
+            let (builder, win_id) = state.window_modes.fit(
+                "Game Viewport",
+                state.compact.fit(builder),
+                state.win_ids.game_view,
+            );
+            if let (_, Some(win)) = win_ctx.make_window(builder, win_id, ui) {
+                let game_view = GameViewWidget::new(
+                    &mut state.game_view,
+                    &mut state.raw_input,
+                    &state.keyboard,
+                );
+                let timer = state.profiler.start();
+                state.keep_updating |= win.set(game_view, ui);
+                state.profiler.record("Game Viewport", timer);
+                state
+                    .window_tracker
+                    .track("Game Viewport", ids.windowing_area, ui);
+                state.title_bars.set(
+                    "Game Viewport",
+                    &mut state.game_view,
+                    &state.window_tracker,
+                    ui,
+                );
+            }
+        }
+
+        if state.compact.shows("Minimap", &state.config.workspaces)
+            && state.window_modes.shows("Minimap")
+        {
+            let builder = WindowBuilder::new()
+                .title("Minimap")
+                .initial_size([420.0, 340.0])
+                .min_size([200.0, 160.0]);
+            let (builder, win_id) = state.window_modes.fit(
+                "Minimap",
+                state.compact.fit(builder),
+                state.win_ids.minimap,
+            );
+            if let (_, Some(win)) = win_ctx.make_window(builder, win_id, ui) {
+                state.keep_updating |= win.set(MinimapWidget::new(&mut state.minimap), ui);
+                state
+                    .window_tracker
+                    .track("Minimap", ids.windowing_area, ui);
+            }
+        }
+
+        if state.compact.shows("Sketchpad", &state.config.workspaces)
+            && state.window_modes.shows("Sketchpad")
+        {
+            let builder = WindowBuilder::new()
+                .title("Sketchpad")
+                .initial_size([360.0, 300.0])
+                .min_size([200.0, 160.0]);
+            let (builder, win_id) = state.window_modes.fit(
+                "Sketchpad",
+                state.compact.fit(builder),
+                state.win_ids.sketchpad,
+            );
+            if let (_, Some(win)) = win_ctx.make_window(builder, win_id, ui) {
+                let timer = state.profiler.start();
+                win.set(SketchpadWidget::new(&mut state.sketchpad), ui);
+                state.profiler.record("Sketchpad", timer);
+                state
+                    .window_tracker
+                    .track("Sketchpad", ids.windowing_area, ui);
+            }
+        }
+
+        if state.compact.shows("Whiteboard", &state.config.workspaces)
+            && state.window_modes.shows("Whiteboard")
+        {
+            let builder = WindowBuilder::new()
+                .title("Whiteboard")
+                .initial_size([420.0, 320.0])
+                .min_size([240.0, 160.0]);
+            let (builder, win_id) = state.window_modes.fit(
+                "Whiteboard",
+                state.compact.fit(builder),
+                state.win_ids.whiteboard,
+            );
+            if let (_, Some(win)) = win_ctx.make_window(builder, win_id, ui) {
+                let timer = state.profiler.start();
+                win.set(WhiteboardWidget::new(&mut state.whiteboard), ui);
+                state.profiler.record("Whiteboard", timer);
+                state
+                    .window_tracker
+                    .track("Whiteboard", ids.windowing_area, ui);
+            }
+        }
+
+        if state.compact.shows("Node Graph", &state.config.workspaces)
+            && state.window_modes.shows("Node Graph")
+        {
+            let builder = WindowBuilder::new()
+                .title("Node Graph")
+                .initial_size([480.0, 360.0])
+                .min_size([240.0, 160.0]);
+            let (builder, win_id) = state.window_modes.fit(
+                "Node Graph",
+                state.compact.fit(builder),
+                state.win_ids.node_graph,
+            );
+            if let (_, Some(win)) = win_ctx.make_window(builder, win_id, ui) {
+                let timer = state.profiler.start();
+                win.set(NodeGraphWidget::new(&mut state.node_graph), ui);
+                state.profiler.record("Node Graph", timer);
+                state
+                    .window_tracker
+                    .track("Node Graph", ids.windowing_area, ui);
+            }
+        }
+
+        if state.compact.shows("Data Table", &state.config.workspaces)
+            && state.window_modes.shows("Data Table")
+        {
+            let builder = WindowBuilder::new()
+                .title("Data Table")
+                .initial_size([520.0, 360.0])
+                .min_size([240.0, 160.0]);
+            let (builder, win_id) = state.window_modes.fit(
+                "Data Table",
+                state.compact.fit(builder),
+                state.win_ids.data_table,
+            );
+            if let (_, Some(win)) = win_ctx.make_window(builder, win_id, ui) {
+                let timer = state.profiler.start();
+                win.set(DataTableWidget::new(&mut state.data_table, find_in), ui);
+                state.profiler.record("Data Table", timer);
+                state
+                    .window_tracker
+                    .track("Data Table", ids.windowing_area, ui);
+            }
+        }
+
+        if state
+            .compact
+            .shows("File Browser", &state.config.workspaces)
+            && state.window_modes.shows("File Browser")
+        {
+            let builder = WindowBuilder::new()
+                .title("File Browser")
+                .initial_size([300.0, 360.0])
+                .min_size([200.0, 160.0]);
+            let (builder, win_id) = state.window_modes.fit(
+                "File Browser",
+                state.compact.fit(builder),
+                state.win_ids.file_browser,
+            );
+            if let (_, Some(win)) = win_ctx.make_window(builder, win_id, ui) {
+                let timer = state.profiler.start();
+                win.set(FileBrowserWidget::new(&mut state.file_browser, find_in), ui);
+                state.profiler.record("File Browser", timer);
+                state
+                    .window_tracker
+                    .track("File Browser", ids.windowing_area, ui);
+            }
+        }
+
+        if state.compact.shows("Tools", &state.config.workspaces)
+            && state.window_modes.shows("Tools")
+        {
+            let builder = WindowBuilder::new()
+                .title("Tools")
+                .initial_size([64.0, 380.0])
+                .min_size([56.0, 120.0]);
+            let (builder, win_id) = state.window_modes.fit(
+                "Tools",
+                state.compact.fit(builder),
+                state.win_ids.tool_palette,
+            );
+            if let (_, Some(win)) = win_ctx.make_window(builder, win_id, ui) {
+                if win.set(
+                    ToolPalette::new(&mut state.config.tool_order, &mut state.tool),
+                    ui,
+                ) {
+                    state.save_config();
+                }
+                state.window_tracker.track("Tools", ids.windowing_area, ui);
+            }
+        }
+
+        if state.compact.shows("Tasks", &state.config.workspaces)
+            && state.window_modes.shows("Tasks")
+        {
+            let builder = WindowBuilder::new()
+                .title("Tasks")
+                .initial_size([360.0, 240.0])
+                .min_size([240.0, 120.0]);
+            let (builder, win_id) =
+                state
+                    .window_modes
+                    .fit("Tasks", state.compact.fit(builder), state.win_ids.tasks);
+            if let (_, Some(win)) = win_ctx.make_window(builder, win_id, ui) {
+                if win.set(TasksWidget::new(&mut state.tasks), ui) {
+                    tasks::run_demo_task(state.tasks.start("Demo task"));
+                }
+                state.window_tracker.track("Tasks", ids.windowing_area, ui);
+            }
+        }
+
+        if state.compact.shows("Settings", &state.config.workspaces)
+            && state.window_modes.shows("Settings")
+        {
+            let builder = WindowBuilder::new()
+                .title("Settings")
+                .initial_size([320.0, 400.0])
+                .min_size([240.0, 120.0]);
+            let (builder, win_id) = state.window_modes.fit(
+                "Settings",
+                state.compact.fit(builder),
+                state.win_ids.settings,
+            );
+            if let (_, Some(win)) = win_ctx.make_window(builder, win_id, ui) {
+                if win.set(SettingsWidget::new(&mut state.config, &state.shortcuts), ui) {
+                    state.save_config();
+                }
+                state
+                    .window_tracker
+                    .track("Settings", ids.windowing_area, ui);
+            }
+        }
+
+        if state.compact.shows("Diagnostics", &state.config.workspaces)
+            && state.window_modes.shows("Diagnostics")
+        {
+            let builder = WindowBuilder::new()
+                .title("Diagnostics")
+                .initial_size([360.0, 200.0])
+                .min_size([240.0, 120.0]);
+            let (builder, win_id) = state.window_modes.fit(
+                "Diagnostics",
+                state.compact.fit(builder),
+                state.win_ids.diagnostics,
+            );
+            if let (_, Some(win)) = win_ctx.make_window(builder, win_id, ui) {
+                let windows: Vec<_> = state.window_tracker.windows().collect();
+                state.diagnostics.widget_ids.count(ui, &windows);
+                state.diagnostics.system = state.metrics.readings();
+                wants_metrics = true;
+                if win.set(DiagnosticsWidget::new(&state.diagnostics, hidpi_factor), ui) {
+                    let report = state.diagnostics.report(hidpi_factor);
+                    match state.clipboard.set_text(&report) {
+                        Ok(()) => state.notifications.info("Copied the report"),
+                        Err(err) => state
+                            .notifications
+                            .error(format!("Cannot copy the report: {}", err)),
+                    }
+                }
+                state
+                    .window_tracker
+                    .track("Diagnostics", ids.windowing_area, ui);
+            }
+        }
+
+        if state.compact.shows("Designer", &state.config.workspaces)
+            && state.window_modes.shows("Designer")
+        {
+            let builder = WindowBuilder::new()
+                .title("Designer")
+                .initial_size([440.0, 240.0])
+                .min_size([220.0, 120.0]);
+            let (builder, win_id) = state.window_modes.fit(
+                "Designer",
+                state.compact.fit(builder),
+                state.win_ids.designer,
+            );
+            if let (_, Some(win)) = win_ctx.make_window(builder, win_id, ui) {
+                let designer =
+                    DesignerWidget::new(&mut state.designer).palette(state.palettes.of("Designer"));
+                for label in win.set(designer, ui) {
+                    state.notifications.info(format!("Clicked {}", label));
+                }
+                state
+                    .window_tracker
+                    .track("Designer", ids.windowing_area, ui);
+            }
+        }
+
+        let mut closed = Vec::new();
+        for (slot, window) in state.extra_windows.iter_mut() {
+            let title = window.title;
+            if !state.compact.shows(title, &state.config.workspaces)
+                || !state.window_modes.shows(title)
+            {
+                continue;
+            }
+            let builder = WindowBuilder::new()
+                .title(title)
+                .initial_size([320.0, 240.0])
+                .min_size([200.0, 160.0]);
+            let (builder, win_id) = state.window_modes.fit(
+                title,
+                state.compact.fit(builder),
+                state.win_ids.extra[slot],
+            );
+            if let (_, Some(win)) = win_ctx.make_window(builder, win_id, ui) {
+                let timer = state.profiler.start();
+                win.set(
+                    ExtraWindowWidget::new(&mut window.content, &state.bridge),
+                    ui,
+                );
+                state.profiler.record(title, timer);
+                state.window_tracker.track(title, ids.windowing_area, ui);
+                state
+                    .title_bars
+                    .set(title, window, &state.window_tracker, ui);
+                if window.close_requested {
+                    closed.push(slot);
+                }
+            }
+        }
+        for slot in closed {
+            state.close_extra_window(slot);
+        }
+
+        // Above the title bars, and the strips above all of the windows.
+        if !state.compact.is_on() {
+            let workspaces = &state.config.workspaces;
+            let window_modes = &state.window_modes;
+            let shown: Vec<_> = state
+                .window_tracker
+                .windows()
+                .filter(|&(title, _)| workspaces.shows(title) && window_modes.shows(title))
+                .collect();
+            for (title, window) in shown {
+                if let Some(control) = state.window_modes.set_buttons(title, window, ui) {
+                    state.window_modes.apply(title, control);
+                    state.keep_updating = true;
+                }
+            }
+        }
+        if let Some(title) = state.window_modes.set_strips(
+            ids.windowing_area,
+            ids.backdrop,
+            &state.config.workspaces,
+            ui,
+        ) {
+            state.window_modes.apply(&title, WindowControl::Restore);
+            state.keep_updating = true;
+        }
+        if !state.compact.is_on() {
+            if let Some(area) = ui.rect_of(ids.windowing_area) {
+                state.layout_dirty |= state.layout.capture(
+                    &state.window_tracker,
+                    &state.config.workspaces,
+                    &state.window_modes,
+                    area,
+                    ui,
+                );
+            }
+        }
+    }
+    state.keep_metrics_refreshed(wants_metrics);
+
+    // Above the windows, and below the menus and the notifications.
+    ShapedWindow::new(&mut state.clock.window, &ClockSilhouette)
+        .w_h(ClockState::SIZE, ClockState::SIZE)
+        .parent(ids.backdrop)
+        .placed(|window| window.top_right_with_margins_on(ids.backdrop, 40.0, 16.0))
+        .set(ids.clock, ui);
+    ClockFace::new(ids.clock)
+        .middle_of(ids.clock)
+        .wh_of(ids.clock)
+        .graphics_for(ids.clock)
+        .set(ids.clock_face, ui);
+    state.timers.wake_after(ClockState::until_tick());
+
+    // The tabs leave room for the File button on their left, and the New
+    // window menu on their right.
+    const FILE_BUTTON_ROOM: conrod_core::Scalar = 72.0;
+    const NEW_WINDOW_W: conrod_core::Scalar = 120.0;
+    if state.compact.is_on() {
+        let tabs_w = (window_w - margin - FILE_BUTTON_ROOM - NEW_WINDOW_W - 8.0).max(0.0);
+        if CompactTabs::new(&mut state.compact, &state.attention)
+            .top_left_with_margins_on(ids.backdrop, margin, FILE_BUTTON_ROOM)
+            .w_h(tabs_w, CompactLayout::TABS_H)
+            .crop_kids()
+            .scroll_kids_horizontally()
+            .set(ids.compact_tabs, ui)
+        {
+            state.keep_updating = true;
+        }
+    }
+
+    state
+        .focus
+        .update(ui, ids.windowing_area, state.config.focus_policy);
+    if let Some(title) = state.focused_title() {
+        state.attention.clear(title);
+    }
+    if let Some(delay) = state.attention.next_blink(now_us()) {
+        state.timers.wake_after(delay);
+    }
+    // The outline is a child of the window so that it is only drawn over the
+    // windows below it.
+    if let Some(focused) = state.focus.focused() {
+        if let Some(rect) = ui.rect_of(focused) {
+            widget::Rectangle::outline_styled(
+                rect.dim(),
+                widget::line::Style::solid().thickness(2.0),
+            )
+            .color(conrod_core::color::LIGHT_BLUE)
+            .xy(rect.xy())
+            .parent(focused)
+            .graphics_for(focused)
+            .set(ids.focus_outline, ui);
+        }
+    }
+
+    // The menu is only opened after the bar has been set, as it closes on
+    // any click outside of it, including the one which opened it.
+    if let Some(action) = WorkspaceBar::new(
+        &state.config.workspaces,
+        &state.palettes,
+        &state.attention,
+        &mut state.workspace_menu,
+    )
+    .middle_of(ids.backdrop)
+    .wh_of(ids.backdrop)
+    .set(ids.workspace_bar, ui)
+    {
+        state.handle_workspace_action(action);
+    }
+    if let Some(title) = state.window_tracker.right_clicked(ui) {
+        state.workspace_menu = Some((title, ui.global_input().current.mouse.xy));
+    }
+
+    if let Some(action) = FileMenu::new(&mut state.file_menu_open, &state.config.recent_files)
+        .tracing(state.tracing)
+        .top_left_with_margin_on(ids.backdrop, 4.0)
         .wh_of(ids.backdrop)
-        .crop_kids()
-        .set(ids.windowing_area, ui);
+        .set(ids.file_menu, ui)
+    {
+        state.handle_file_menu_action(action);
+    }
+
+    let kind_names: Vec<&str> = ExtraKind::ALL.iter().map(|kind| kind.name()).collect();
+    if let Some(index) = widget::DropDownList::new(&kind_names, None)
+        .label("New window")
+        .label_font_size(14)
+        .w_h(NEW_WINDOW_W, 26.0)
+        .top_right_with_margin_on(ids.backdrop, 4.0)
+        .set(ids.new_window, ui)
+    {
+        state.open_extra_window(ExtraKind::ALL[index]);
+    }
+
+    // The overlay also takes the hover away from everything below, and the
+    // first click only focuses the window, like on macOS.
+    if !state.window_focused {
+        widget::Rectangle::fill(ui.window_dim())
+            .color(conrod_core::color::BLACK.alpha(0.3))
+            .middle()
+            .graphics_for(ids.backdrop)
+            .set(ids.unfocused_dim, ui);
+    }
+
+    if state.switcher.is_open() {
+        let workspaces = &state.config.workspaces;
+        let window_modes = &state.window_modes;
+        let mut windows: Vec<_> = state
+            .window_tracker
+            .windows()
+            .filter(|&(title, _)| workspaces.shows(title) && window_modes.shows(title))
+            .collect();
+        // The focused window comes first, so that Alt+Tab goes to the next.
+        if let Some(focused) = state.focus.focused() {
+            if let Some(i) = windows.iter().position(|&(_, window)| window == focused) {
+                let window = windows.remove(i);
+                windows.insert(0, window);
+            }
+        }
+        let alt_down = state.keyboard.is_down(VirtualKeyCode::LAlt)
+            || state.keyboard.is_down(VirtualKeyCode::RAlt);
+        let picked = WindowSwitcher::new(&mut state.switcher, &windows, hidpi_factor)
+            .middle_of(ids.backdrop)
+            .wh_of(ids.backdrop)
+            .set(ids.window_switcher, ui);
+        // Releasing Alt picks the selected window.
+        let picked = match picked {
+            Some(window) => Some(window),
+            None if !alt_down => state.switcher.selected(&windows),
+            None => None,
+        };
+        if let Some(window) = picked {
+            state.focus.set_focused(window);
+            if state.compact.is_on() {
+                state.compact.active = windows
+                    .iter()
+                    .find(|&&(_, w)| w == window)
+                    .map(|&(title, _)| title);
+                state.keep_updating = true;
+            }
+        }
+        if picked.is_some() || !alt_down {
+            state.switcher.close();
+        }
+    }
 
-    let builder = WindowBuilder::new()
-        .title("Conrod Example")
-        .initial_size([640.0, 480.0])
-        .min_size([320.0, 240.0]);
-    if let (_, Some(win)) = win_ctx.make_window(builder, state.win_ids.conrod_example, ui) {
-        let example = ExampleWidget::new(&mut state.conrod_example_app);
-        win.set(example, ui);
+    for action in state.hotspots.finish(ui) {
+        action(state);
     }
+
+    for id in state.notifications.take_timeouts() {
+        state
+            .timers
+            .after(Notifications::TIMEOUT, TimerMsg::DismissNotification(id));
+    }
+    NotificationsWidget::new(&mut state.notifications)
+        .middle_of(ids.backdrop)
+        .wh_of(ids.backdrop)
+        .set(ids.notifications, ui);
+    if state.show_audit {
+        AuditOverlay::new(state.config.min_hit_target)
+            .middle_of(ids.backdrop)
+            .wh_of(ids.backdrop)
+            .set(ids.audit, ui);
+    }
+
+    state.raw_input.finish();
+    state.find_requested = false;
 }
@@ -0,0 +1,227 @@
+// A layout for small viewports, such as phones on the web: while the
+// windowing area is narrower than `CompactLayout::MAX_WIDTH`, one window at a
+// time fills it, and a row of tabs switches between the windows of the
+// current workspace.
+//
+// conrod_floatwin can't move or resize a window from outside, so the compact
+// layout has a `WindowingState` of its own, whose windows are made with the
+// size of the area, and which starts over whenever the area changes size.
+// The floating windows keep their places in theirs for when the area grows
+// again.
+
+use crate::attention::Attention;
+use crate::common::WinIds;
+use crate::trace::now_us;
+use crate::workspaces::Workspaces;
+use conrod_core::{
+    color, widget, widget_ids, Colorable, Dimensions, Labelable, Positionable, Scalar, Sizeable,
+    Widget, WidgetCommon,
+};
+use conrod_floatwin::{WindowBuilder, WindowingState};
+
+pub struct CompactLayout {
+    /// The floating windows, while the compact layout is on.
+    floating: Option<(WindowingState, WinIds)>,
+    /// The size of the windowing area the windows are made with.
+    size: Dimensions,
+    /// The windows of the current workspace, in the order they are made.
+    titles: Vec<&'static str>,
+    /// The window which fills the area, the first one if none is picked.
+    pub active: Option<&'static str>,
+}
+
+impl Default for CompactLayout {
+    fn default() -> Self {
+        CompactLayout::new()
+    }
+}
+
+impl CompactLayout {
+    /// Windowing areas narrower than this use the compact layout.
+    pub const MAX_WIDTH: Scalar = 600.0;
+    /// The height of the row of tabs above the area.
+    pub const TABS_H: Scalar = 32.0;
+    /// Room left in the area for the title bar of the window.
+    const TITLE_BAR_H: Scalar = 24.0;
+
+    pub fn new() -> Self {
+        CompactLayout {
+            floating: None,
+            size: [0.0, 0.0],
+            titles: Vec::new(),
+            active: None,
+        }
+    }
+
+    pub fn is_on(&self) -> bool {
+        self.floating.is_some()
+    }
+
+    /// Turn the layout on or off for a windowing area of `size`, before the
+    /// windows are made. The floating windows are swapped out of `win_state`
+    /// and `win_ids` while it is on.
+    pub fn update(
+        &mut self,
+        size: Dimensions,
+        win_state: &mut WindowingState,
+        win_ids: &mut WinIds,
+    ) {
+        self.titles.clear();
+        // A minimized window has no area, and the layout stays as it was for
+        // when it is restored.
+        if size[0] <= 0.0 || size[1] <= 0.0 {
+            return;
+        }
+        let compact = size[0] < Self::MAX_WIDTH;
+        if compact && !self.is_on() {
+            let mut state = WindowingState::new();
+            let ids = WinIds::new(&mut state);
+            self.floating = Some((
+                std::mem::replace(win_state, state),
+                std::mem::replace(win_ids, ids),
+            ));
+        } else if !compact {
+            if let Some((state, ids)) = self.floating.take() {
+                *win_state = state;
+                *win_ids = ids;
+            }
+        } else if size != self.size {
+            *win_state = WindowingState::new();
+            *win_ids = WinIds::new(win_state);
+        }
+        self.size = size;
+    }
+
+    /// Whether to make the window `title`. While the layout is on, only the
+    /// active window of the current workspace is made, and the others get
+    /// tabs.
+    pub fn shows(&mut self, title: &'static str, workspaces: &Workspaces) -> bool {
+        if !workspaces.shows(title) {
+            return false;
+        }
+        if !self.is_on() {
+            return true;
+        }
+        self.titles.push(title);
+        match self.active {
+            Some(active) => active == title,
+            None => self.titles.len() == 1,
+        }
+    }
+
+    /// Make the window fill the area while the layout is on.
+    pub fn fit(&self, builder: WindowBuilder) -> WindowBuilder {
+        if !self.is_on() {
+            return builder;
+        }
+        let size = [self.size[0], (self.size[1] - Self::TITLE_BAR_H).max(0.0)];
+        builder.initial_size(size).min_size(size)
+    }
+}
+
+/// The row of tabs of the compact layout, set after the windows.
+#[derive(WidgetCommon)]
+pub struct CompactTabs<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    layout: &'a mut CompactLayout,
+    attention: &'a Attention,
+}
+
+impl<'a> CompactTabs<'a> {
+    pub fn new(layout: &'a mut CompactLayout, attention: &'a Attention) -> Self {
+        CompactTabs {
+            common: widget::CommonBuilder::default(),
+            layout,
+            attention,
+        }
+    }
+}
+
+widget_ids! {
+    pub struct CompactTabsIds {
+        background,
+        tabs[],
+    }
+}
+
+impl<'a> Widget for CompactTabs<'a> {
+    type State = CompactTabsIds;
+    type Style = ();
+    /// Whether another window was made active, which shows in the next
+    /// update.
+    type Event = bool;
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        CompactTabsIds::new(id_gen)
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs {
+            id,
+            state,
+            rect,
+            ui,
+            ..
+        } = args;
+        let CompactTabs {
+            layout, attention, ..
+        } = self;
+
+        const TAB_W: Scalar = 120.0;
+
+        let titles = layout.titles.clone();
+        // The active window may have gone to another workspace.
+        let mut changed = false;
+        if layout
+            .active
+            .map_or(true, |active| !titles.contains(&active))
+        {
+            let first = titles.first().cloned();
+            changed = layout.active.is_some() && first.is_some();
+            layout.active = first;
+        }
+
+        widget::Rectangle::fill(rect.dim())
+            .color(color::DARK_CHARCOAL)
+            .middle_of(id)
+            .graphics_for(id)
+            .set(state.background, ui);
+
+        if state.tabs.len() < titles.len() {
+            let id_gen = &mut ui.widget_id_generator();
+            state.update(|state| state.tabs.resize(titles.len(), id_gen));
+        }
+        let now = now_us();
+        for (i, &title) in titles.iter().enumerate() {
+            let label = match attention.count(title) {
+                0 => title.to_owned(),
+                count => format!("{} ({})", title, count),
+            };
+            let tab = widget::Button::new()
+                .label(&label)
+                .label_font_size(12)
+                .w_h(TAB_W, rect.h())
+                .parent(id);
+            let tab = if layout.active == Some(title) {
+                tab.color(color::LIGHT_BLUE).label_color(color::BLACK)
+            } else if attention.is_lit(title, now) {
+                tab.color(color::ORANGE).label_color(color::BLACK)
+            } else {
+                tab.color(color::DARK_CHARCOAL).label_color(color::WHITE)
+            };
+            let tab = if i == 0 {
+                tab.top_left_of(id)
+            } else {
+                tab.right_from(state.tabs[i - 1], 0.0)
+            };
+            if tab.set(state.tabs[i], ui).was_clicked() && layout.active != Some(title) {
+                layout.active = Some(title);
+                changed = true;
+            }
+        }
+        changed
+    }
+}
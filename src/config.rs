@@ -0,0 +1,168 @@
+// Persistent user configuration.
+//
+// The configuration is stored as JSON, in the user's config directory on
+// native and in `localStorage` on wasm. The window layout is stored next to
+// it, under its own name.
+
+use crate::desktop_widgets::DesktopWidgets;
+use crate::focus::FocusPolicy;
+use crate::input::InputSettings;
+use crate::mouse_bindings::MouseBindings;
+use crate::tool_palette::{self, Tool};
+use crate::workspaces::{Workspaces, LAYOUT_SLOT_COUNT};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Recently opened files, most recent first.
+    pub recent_files: Vec<String>,
+    pub mouse_bindings: MouseBindings,
+    pub input: InputSettings,
+    /// The order of the buttons in the tool palette.
+    pub tool_order: Vec<Tool>,
+    pub focus_policy: FocusPolicy,
+    pub workspaces: Workspaces,
+    /// The layouts saved by the Save layout commands, to compare
+    /// arrangements by recalling them in turn.
+    pub layout_slots: Vec<Option<Workspaces>>,
+    /// The smallest click target the accessibility audit accepts, in
+    /// logical pixels.
+    pub min_hit_target: f64,
+    /// How far the windowing area stays from the edges of the OS window, in
+    /// logical pixels, which leaves room for the resize handles.
+    pub window_edge_margin: f64,
+    /// Stop windows from being dragged or resized out of the windowing area.
+    pub keep_windows_in_view: bool,
+    /// Where the widgets under the windows are.
+    pub desktop_widgets: DesktopWidgets,
+    /// The samples per pixel of multisample antialiasing, one of
+    /// `MSAA_CHOICES`, 0 for none. Fewer are used if the GL has fewer.
+    pub msaa_samples: u32,
+    /// Smooth the edges of the UI in screen space, for where MSAA can't be
+    /// had, such as in a browser without antialiasing.
+    pub fxaa: bool,
+    /// Draw a glowing outline around the widget with the keyboard.
+    pub focus_ring: bool,
+}
+
+pub const MSAA_CHOICES: [u32; 4] = [0, 2, 4, 8];
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            recent_files: Vec::new(),
+            mouse_bindings: MouseBindings::default(),
+            input: InputSettings::default(),
+            tool_order: Tool::ALL.to_vec(),
+            focus_policy: FocusPolicy::default(),
+            workspaces: Workspaces::default(),
+            layout_slots: vec![None; LAYOUT_SLOT_COUNT],
+            min_hit_target: 24.0,
+            window_edge_margin: 6.0,
+            keep_windows_in_view: true,
+            desktop_widgets: DesktopWidgets::default(),
+            msaa_samples: 4,
+            fxaa: false,
+            focus_ring: true,
+        }
+    }
+}
+
+impl Config {
+    const MAX_RECENT_FILES: usize = 10;
+
+    /// Load the stored configuration, falling back to the defaults if there
+    /// is none or it cannot be read.
+    ///
+    /// Recent files which no longer exist are pruned.
+    pub fn load() -> Self {
+        let mut config: Config = storage::read("config")
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        config.prune_recent_files();
+        config.normalize();
+        config
+    }
+
+    /// Fix up values which may come from an older or edited file.
+    pub fn normalize(&mut self) {
+        tool_palette::normalize_order(&mut self.tool_order);
+        self.workspaces.normalize();
+        self.msaa_samples = MSAA_CHOICES
+            .iter()
+            .cloned()
+            .filter(|&samples| samples <= self.msaa_samples)
+            .max()
+            .unwrap_or(0);
+        self.layout_slots.resize(LAYOUT_SLOT_COUNT, None);
+        for layout in self.layout_slots.iter_mut().flatten() {
+            layout.normalize();
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        storage::write("config", &json)
+    }
+
+    /// Move `name` to the top of the recent files list.
+    pub fn add_recent_file(&mut self, name: &str) {
+        self.recent_files.retain(|f| f != name);
+        self.recent_files.insert(0, name.to_owned());
+        self.recent_files.truncate(Self::MAX_RECENT_FILES);
+    }
+
+    /// Remove recent files which no longer exist.
+    ///
+    /// On wasm, files cannot be accessed by name again so this does nothing.
+    pub fn prune_recent_files(&mut self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.recent_files
+            .retain(|f| std::path::Path::new(f).is_file());
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod storage {
+    use std::path::PathBuf;
+
+    fn path(name: &str) -> Option<PathBuf> {
+        let file = format!("{}.json", name);
+        dirs::config_dir().map(|dir| dir.join("conrod_floatwin_demo_glow").join(file))
+    }
+
+    pub(crate) fn read(name: &str) -> Option<String> {
+        std::fs::read_to_string(path(name)?).ok()
+    }
+
+    pub(crate) fn write(name: &str, json: &str) -> Result<(), String> {
+        let path = path(name).ok_or("no config directory")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        std::fs::write(&path, json).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) mod storage {
+    fn key(name: &str) -> String {
+        format!("conrod_floatwin_demo_glow.{}", name)
+    }
+
+    fn local_storage() -> Option<web_sys::Storage> {
+        web_sys::window()?.local_storage().ok()?
+    }
+
+    pub(crate) fn read(name: &str) -> Option<String> {
+        local_storage()?.get_item(&key(name)).ok()?
+    }
+
+    pub(crate) fn write(name: &str, json: &str) -> Result<(), String> {
+        local_storage()
+            .ok_or("localStorage is not available")?
+            .set_item(&key(name), json)
+            .map_err(|_| "cannot write to localStorage".to_owned())
+    }
+}
@@ -1,8 +1,15 @@
 // A glow backend for rendering conrod primitives.
 
-use conrod_core::{color, image, render, text, Rect, Scalar};
+use crate::debug_draw;
+use crate::glyph_grid::GlyphGrid;
+use conrod_core::{color, image, render, text, widget, Rect, Scalar};
 use glow::HasContext;
+use std::cell::RefCell;
+use std::rc::Rc;
 
+/// A rectangle of framebuffer pixels, with the origin at the bottom left, for `glScissor`.
+///
+/// It always fits in the framebuffer it was made for.
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct GlRect {
     left: u32,
@@ -11,6 +18,194 @@ pub struct GlRect {
     height: u32,
 }
 
+impl GlRect {
+    /// The pixels of a framebuffer of `screen` size covered by `rect`, which is in conrod
+    /// coordinates. Empty if the framebuffer is.
+    ///
+    /// Any coordinates and `dpi_factor` are taken, including huge, infinite and NaN ones from
+    /// a broken layout, as GL errors on a rectangle which doesn't fit.
+    pub fn from_rect(rect: Rect, dpi_factor: Scalar, (screen_w, screen_h): (u32, u32)) -> Self {
+        // Clamped as floats, so that nothing wraps on the way to pixels. NaN goes to 0.
+        let to_pixels = |value: Scalar, half: Scalar, max: u32| {
+            let pixels = (value * dpi_factor + half).round();
+            if pixels.is_nan() {
+                0
+            } else {
+                pixels.max(0.0).min(max as Scalar) as u32
+            }
+        };
+        let (half_w, half_h) = (screen_w as Scalar / 2.0, screen_h as Scalar / 2.0);
+        let left = to_pixels(rect.left(), half_w, screen_w);
+        let right = to_pixels(rect.right(), half_w, screen_w);
+        let bottom = to_pixels(rect.bottom(), half_h, screen_h);
+        let top = to_pixels(rect.top(), half_h, screen_h);
+        GlRect {
+            left,
+            bottom,
+            width: right.saturating_sub(left),
+            height: top.saturating_sub(bottom),
+        }
+    }
+
+    /// The whole of a framebuffer of `screen` size.
+    pub fn full((screen_w, screen_h): (u32, u32)) -> Self {
+        GlRect {
+            left: 0,
+            bottom: 0,
+            width: screen_w,
+            height: screen_h,
+        }
+    }
+
+    /// Scale to a framebuffer of `to` pixels from one of `from` pixels, clamping to `to`.
+    fn scaled(self, to: (u32, u32), from: (u32, u32)) -> Self {
+        let scale = |value: u32, to: u32, from: u32| {
+            let scaled = value as u64 * to as u64 / from.max(1) as u64;
+            scaled.min(to as u64) as u32
+        };
+        let left = scale(self.left, to.0, from.0);
+        let bottom = scale(self.bottom, to.1, from.1);
+        GlRect {
+            left,
+            bottom,
+            width: scale(self.width, to.0, from.0).min(to.0 - left),
+            height: scale(self.height, to.1, from.1).min(to.1 - bottom),
+        }
+    }
+
+    pub fn left(&self) -> u32 {
+        self.left
+    }
+
+    pub fn bottom(&self) -> u32 {
+        self.bottom
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The pixels covered by both rectangles, empty if they don't overlap.
+    pub fn intersection(self, other: GlRect) -> Self {
+        let left = self.left.max(other.left);
+        let bottom = self.bottom.max(other.bottom);
+        let right = (self.left + self.width).min(other.left + other.width);
+        let top = (self.bottom + self.height).min(other.bottom + other.height);
+        GlRect {
+            left,
+            bottom,
+            width: right.saturating_sub(left),
+            height: top.saturating_sub(bottom),
+        }
+    }
+}
+
+/// Nested clip rectangles for drawing of the application's own, such as the debug layer and
+/// the passes drawn over the UI, which conrod's scissor rectangle of each primitive doesn't
+/// cover. Each rectangle pushed only narrows the one it is pushed inside of.
+#[derive(Clone, Debug)]
+pub struct ClipStack {
+    base: GlRect,
+    stack: Vec<GlRect>,
+}
+
+impl ClipStack {
+    /// A stack over `base`, such as the whole framebuffer.
+    pub fn new(base: GlRect) -> Self {
+        ClipStack {
+            base,
+            stack: Vec::new(),
+        }
+    }
+
+    /// Clip to `rect` within the current clip rectangle. Returns the rectangle to scissor to.
+    pub fn push_clip(&mut self, rect: GlRect) -> GlRect {
+        let clip = self.current().intersection(rect);
+        self.stack.push(clip);
+        clip
+    }
+
+    /// Go back to the clip rectangle from before the last `push_clip`, which is returned.
+    /// Popping more than was pushed leaves the base.
+    pub fn pop_clip(&mut self) -> GlRect {
+        self.stack.pop();
+        self.current()
+    }
+
+    pub fn current(&self) -> GlRect {
+        self.stack.last().copied().unwrap_or(self.base)
+    }
+
+    /// How many clip rectangles are pushed.
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Scissor the bound framebuffer to the current clip rectangle, turning the scissor test on,
+    /// as `FrameGuard` turns it back off.
+    pub fn apply(&self, gl: &glow::Context) {
+        let clip = self.current();
+        // GL takes signed sizes, which no real framebuffer gets near.
+        let to_gl = |value: u32| value.min(i32::MAX as u32) as i32;
+        unsafe {
+            gl.enable(glow::SCISSOR_TEST);
+            gl.scissor(
+                to_gl(clip.left),
+                to_gl(clip.bottom),
+                to_gl(clip.width),
+                to_gl(clip.height),
+            );
+        }
+    }
+}
+
+/// Why the renderer couldn't be set up, fill or draw.
+#[derive(Clone, Debug)]
+pub enum RendererError {
+    /// A shader didn't compile, with the stage and the info log.
+    Compile { stage: &'static str, log: String },
+    /// The shaders didn't link, with the info log.
+    Link(String),
+    /// The linked program doesn't have one of the attributes of `Vertex`.
+    MissingAttribute(&'static str),
+    /// None of the shader variants for the platform worked, with why each failed.
+    NoShader(String),
+    /// A GL object couldn't be created.
+    Gl(String),
+    /// The GL error flag was set while drawing.
+    GlError(u32),
+    /// Some glyphs didn't fit even in the largest glyph cache, and were left out. The rest of the
+    /// frame was filled.
+    GlyphCache(text::rt::gpu_cache::CacheWriteErr),
+}
+
+impl std::fmt::Display for RendererError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RendererError::Compile { stage, log } => write!(f, "{} shader: {}", stage, log),
+            RendererError::Link(log) => write!(f, "link: {}", log),
+            RendererError::MissingAttribute(name) => write!(f, "missing attribute `{}`", name),
+            RendererError::NoShader(errors) => write!(f, "No shader compiled. {}", errors),
+            RendererError::Gl(err) => write!(f, "{}", err),
+            RendererError::GlError(err) => write!(f, "GL error {:#x}", err),
+            RendererError::GlyphCache(err) => write!(f, "Caching the glyphs failed: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for RendererError {}
+
+// glow reports the objects it couldn't create as strings.
+impl From<String> for RendererError {
+    fn from(err: String) -> Self {
+        RendererError::Gl(err)
+    }
+}
+
 /// A `Command` for drawing to the target.
 ///
 /// Each variant describes how to draw the contents of the vertex buffer.
@@ -29,27 +224,293 @@ enum PreparedCommand {
     Scizzor(GlRect),
 }
 
+/// What a command prepared by the last `fill` does, for profilers and inspectors to attribute
+/// the draw calls to widgets. See `Renderer::commands`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CommandKind {
+    Image(image::Id),
+    Plain,
+    Scissor(GlRect),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CommandInfo {
+    pub kind: CommandKind,
+    /// The indices drawn, three per triangle, and none for a scissor rectangle. A quad is six
+    /// indices into four vertices.
+    pub indices: usize,
+    /// The widget whose primitives the command is of, while the renderer tags the commands
+    /// with them. `None` otherwise, and for the debug layer.
+    pub widget: Option<widget::Id>,
+}
+
+/// The commands prepared by the last `fill`, from `Renderer::commands`.
+pub struct Commands<'a> {
+    commands: std::iter::Enumerate<std::slice::Iter<'a, PreparedCommand>>,
+    /// The index of the first command of each widget in turn.
+    widget_starts: &'a [(usize, Option<widget::Id>)],
+}
+
+impl<'a> Iterator for Commands<'a> {
+    type Item = CommandInfo;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (index, command) = self.commands.next()?;
+        while self.widget_starts.len() > 1 && self.widget_starts[1].0 <= index {
+            self.widget_starts = &self.widget_starts[1..];
+        }
+        let widget = match self.widget_starts.first() {
+            Some(&(start, widget)) if start <= index => widget,
+            _ => None,
+        };
+        let (kind, indices) = match command {
+            PreparedCommand::Image(id, range) => (CommandKind::Image(*id), range.len()),
+            PreparedCommand::Plain(range) => (CommandKind::Plain, range.len()),
+            PreparedCommand::Scizzor(rect) => (CommandKind::Scissor(*rect), 0),
+        };
+        Some(CommandInfo {
+            kind,
+            indices,
+            widget,
+        })
+    }
+}
+
 /// A rusttype `GlyphCache` along with a OpenGL texture handle for caching text on the `GPU`.
 pub struct GlyphCache {
     cache: text::GlyphCache<'static>,
     texture: glow::Texture,
+    /// A copy of the texture, kept after `keep_pixels`.
+    pixels: Option<Vec<u8>>,
+    /// Counts the uploads to the texture.
+    version: u64,
+    /// How the texture was created, for `grow`.
+    safe_mode: bool,
+    /// The largest size `grow` may reach.
+    max_size: (u32, u32),
+}
+
+/// The contents of a glyph cache texture, one byte per pixel, as returned by
+/// `Renderer::glyph_pixels`.
+#[derive(Clone, Debug)]
+pub struct GlyphPixels {
+    /// The number of uploads to the texture so far.
+    pub version: u64,
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+/// A command of a `Frame`. Images are given by an index which the sender and the receiver of
+/// the frame agree on, as `image::Id`s only make sense to the `image::Map` they came from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FrameCommand {
+    Image(u32, std::ops::Range<usize>),
+    Plain(std::ops::Range<usize>),
+    Scissor {
+        left: u32,
+        bottom: u32,
+        width: u32,
+        height: u32,
+    },
+}
+
+/// The commands and vertices prepared by `Renderer::fill`, taken out so that another `Renderer`,
+/// possibly in another process, can draw them after `Renderer::load_frame`. `to_bytes` gives the
+/// bytes to send or save them as.
+#[derive(Clone, Debug, Default)]
+pub struct Frame {
+    /// The size of the framebuffer it was filled for, in pixels.
+    pub framebuffer: (u32, u32),
+    pub commands: Vec<FrameCommand>,
+    /// The vertices of each triangle in turn, which the ranges of the commands are of.
+    pub vertices: Vec<Vertex>,
+}
+
+/// Starts the bytes of a `Frame`, and changes with their layout.
+const FRAME_MAGIC: &[u8; 8] = b"CFDGFRM1";
+
+impl Frame {
+    /// The frame as bytes, to be sent elsewhere or saved, and read back with `from_bytes`.
+    ///
+    /// After `FRAME_MAGIC` come the framebuffer size, the commands and the vertices, each list
+    /// after its length. The numbers are little-endian `u32`s and `f32`s, and each command starts
+    /// with a byte for its kind.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        const COMMAND_LEN: usize = 1 + 4 * 4;
+        const VERTEX_LEN: usize = 4 + 4 * 8;
+        let mut bytes = Vec::with_capacity(
+            FRAME_MAGIC.len()
+                + 16
+                + self.commands.len() * COMMAND_LEN
+                + self.vertices.len() * VERTEX_LEN,
+        );
+        let put_u32 =
+            |bytes: &mut Vec<u8>, value: u32| bytes.extend_from_slice(&value.to_le_bytes());
+        let put_f32s = |bytes: &mut Vec<u8>, values: &[f32]| {
+            for value in values {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        };
+        bytes.extend_from_slice(FRAME_MAGIC);
+        put_u32(&mut bytes, self.framebuffer.0);
+        put_u32(&mut bytes, self.framebuffer.1);
+        put_u32(&mut bytes, self.commands.len() as u32);
+        for command in &self.commands {
+            match command {
+                FrameCommand::Plain(range) => {
+                    bytes.push(0);
+                    put_u32(&mut bytes, range.start as u32);
+                    put_u32(&mut bytes, range.end as u32);
+                }
+                FrameCommand::Image(index, range) => {
+                    bytes.push(1);
+                    put_u32(&mut bytes, *index);
+                    put_u32(&mut bytes, range.start as u32);
+                    put_u32(&mut bytes, range.end as u32);
+                }
+                FrameCommand::Scissor {
+                    left,
+                    bottom,
+                    width,
+                    height,
+                } => {
+                    bytes.push(2);
+                    for &value in &[*left, *bottom, *width, *height] {
+                        put_u32(&mut bytes, value);
+                    }
+                }
+            }
+        }
+        put_u32(&mut bytes, self.vertices.len() as u32);
+        for vertex in &self.vertices {
+            put_u32(&mut bytes, vertex.mode);
+            put_f32s(&mut bytes, &vertex.position);
+            put_f32s(&mut bytes, &vertex.tex_coords);
+            put_f32s(&mut bytes, &vertex.color);
+        }
+        bytes
+    }
+
+    /// Read back the bytes of `to_bytes`. Fails on bytes of another layout, and on bytes which
+    /// end early or go on after the frame, but the ranges of the commands are not checked, as
+    /// `Renderer::load_frame` leaves out those out of range.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Frame, String> {
+        if !bytes.starts_with(FRAME_MAGIC) {
+            return Err("not a frame, or a frame of another version".to_owned());
+        }
+        let mut reader = FrameReader(&bytes[FRAME_MAGIC.len()..]);
+        let framebuffer = (reader.u32()?, reader.u32()?);
+        // The counts are not trusted for allocating more than the bytes could hold.
+        let command_count = reader.u32()? as usize;
+        let mut commands = Vec::with_capacity(command_count.min(reader.0.len()));
+        for _ in 0..command_count {
+            let command = match reader.u8()? {
+                0 => FrameCommand::Plain(reader.u32()? as usize..reader.u32()? as usize),
+                1 => {
+                    let index = reader.u32()?;
+                    FrameCommand::Image(index, reader.u32()? as usize..reader.u32()? as usize)
+                }
+                2 => FrameCommand::Scissor {
+                    left: reader.u32()?,
+                    bottom: reader.u32()?,
+                    width: reader.u32()?,
+                    height: reader.u32()?,
+                },
+                kind => return Err(format!("unknown command {}", kind)),
+            };
+            commands.push(command);
+        }
+        let vertex_count = reader.u32()? as usize;
+        let mut vertices = Vec::with_capacity(vertex_count.min(reader.0.len()));
+        for _ in 0..vertex_count {
+            let mode = reader.u32()?;
+            let mut position = [0.0; 2];
+            let mut tex_coords = [0.0; 2];
+            let mut color = [0.0; 4];
+            reader.f32s(&mut position)?;
+            reader.f32s(&mut tex_coords)?;
+            reader.f32s(&mut color)?;
+            vertices.push(Vertex {
+                mode,
+                position,
+                tex_coords,
+                color,
+            });
+        }
+        if !reader.0.is_empty() {
+            return Err(format!("{} bytes after the frame", reader.0.len()));
+        }
+        Ok(Frame {
+            framebuffer,
+            commands,
+            vertices,
+        })
+    }
+}
+
+/// The bytes of a `Frame`, read from the front.
+struct FrameReader<'a>(&'a [u8]);
+
+impl<'a> FrameReader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.0.len() < len {
+            return Err("the frame ends early".to_owned());
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(self.bytes(4)?);
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    fn f32s(&mut self, values: &mut [f32]) -> Result<(), String> {
+        for value in values.iter_mut() {
+            *value = f32::from_bits(self.u32()?);
+        }
+        Ok(())
+    }
 }
 
 /// A type used for translating `render::Primitives` into `Command`s that indicate how to draw the
 /// conrod GUI using `glow`.
 pub struct Renderer {
     program: Program,
+    info: RendererInfo,
     vbo: glow::Buffer,
+    /// The index buffer, bound to `vao`.
+    ebo: glow::Buffer,
     vao: glow::VertexArray,
-    glyph_cache: GlyphCache,
+    /// The bytes allocated for `vbo` and `ebo`, which only grow.
+    buffer_sizes: std::cell::Cell<(usize, usize)>,
+    /// Shared with the renderers made with `sharing_glyph_cache`.
+    glyph_cache: Rc<RefCell<GlyphCache>>,
+    /// Whether the glyph cache grew during the last `fill`.
+    glyph_cache_grew: bool,
     commands: Vec<PreparedCommand>,
-    vertices: Vec<Vertex>,
+    geometry: Geometry,
+    /// Whether `fill` tags the commands with the widget of their primitives.
+    tags_widgets: bool,
+    /// The index of the first command of each widget in turn, while `tags_widgets` is on.
+    widget_starts: Vec<(usize, Option<widget::Id>)>,
 }
 
 pub struct Texture {
     pub texture: glow::Texture,
     pub width: u32,
     pub height: u32,
+    /// The format of the pixels given to `update_region`.
+    pub format: PixelFormat,
+    /// Whether `update_region` generates the mipmaps again.
+    pub mipmaps: bool,
 }
 
 /// The `Vertex` type passed to the vertex shader.
@@ -133,6 +594,42 @@ pub const FRAGMENT_SHADER_120: &'static str = "
     }
 ";
 
+/// The fragment shader with sRGB gamma correction used for OpenGL, for when
+/// the framebuffer doesn't convert to sRGB itself.
+pub const FRAGMENT_SHADER_120_LINEAR_TO_SRGB: &'static str = "
+    #version 120
+    uniform sampler2D tex;
+
+    varying vec2 v_tex_coords;
+    varying vec4 v_color;
+    varying float v_mode;
+
+    vec3 toSrgb(vec3 linearRgb) {
+        // `mix` can't take a `bvec3` in GLSL 1.20.
+        vec3 cutoff = vec3(1.0) - step(vec3(0.0031308), linearRgb);
+        vec3 higher = vec3(1.055) * pow(linearRgb, vec3(1.0 / 2.4)) - vec3(0.055);
+        vec3 lower = linearRgb * vec3(12.92);
+        return mix(higher, lower, cutoff);
+    }
+
+    void main() {
+        // Text
+        if (v_mode == 0.0) {
+            gl_FragColor.rgb = toSrgb(v_color.rgb);
+            gl_FragColor.a = v_color.a * texture2D(tex, v_tex_coords).r;
+
+        // Image
+        } else if (v_mode == 1.0) {
+            vec4 color = texture2D(tex, v_tex_coords);
+            gl_FragColor = vec4(toSrgb(color.rgb), color.a);
+
+        // 2D Geometry
+        } else if (v_mode == 2.0) {
+            gl_FragColor = vec4(toSrgb(v_color.rgb), v_color.a);
+        }
+    }
+";
+
 /// The vertex shader used for OpenGL.
 pub const VERTEX_SHADER_140: &'static str = "
     #version 140
@@ -275,6 +772,256 @@ pub const FRAGMENT_SHADER_300_ES_LINEAR_TO_SRGB: &'static str = "\
     }
 ";
 
+/// The version of the GL and its shading language, which the shaders are
+/// picked by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GlVersion {
+    /// OpenGL ES or WebGL, rather than desktop OpenGL.
+    pub is_es: bool,
+    pub major: u32,
+    pub minor: u32,
+    /// The version of GLSL, or GLSL ES, as major and minor, like `(1, 40)`.
+    pub glsl: (u32, u32),
+}
+
+impl GlVersion {
+    /// The version of `gl`, `None` if the driver reports it in a way
+    /// `parse` doesn't understand.
+    pub fn query(gl: &glow::Context) -> Option<Self> {
+        let (version, glsl_version) = unsafe {
+            (
+                gl.get_parameter_string(glow::VERSION),
+                gl.get_parameter_string(glow::SHADING_LANGUAGE_VERSION),
+            )
+        };
+        Self::parse(&version, &glsl_version)
+    }
+
+    /// The version from the strings of `GL_VERSION` and
+    /// `GL_SHADING_LANGUAGE_VERSION`, like "4.6.0 NVIDIA 460.39" and "4.60
+    /// NVIDIA", or "OpenGL ES 3.2 Mesa 20.0.8" and "OpenGL ES GLSL ES 3.20".
+    /// WebGL reports "WebGL 2.0 (OpenGL ES 3.0 Chromium)", which is taken as
+    /// the WebGL version.
+    pub fn parse(version: &str, glsl_version: &str) -> Option<Self> {
+        let is_es = version.starts_with("OpenGL ES") || version.starts_with("WebGL");
+        let (major, minor) = first_version(version)?;
+        let (glsl_major, glsl_minor) = first_version(glsl_version)?;
+        // GLSL versions go "1.40", but a driver may say "1.4".
+        let glsl_minor = match glsl_minor.len() {
+            1 => glsl_minor.parse::<u32>().ok()? * 10,
+            _ => glsl_minor.parse().ok()?,
+        };
+        Some(GlVersion {
+            is_es,
+            major,
+            minor: minor.parse().ok()?,
+            glsl: (glsl_major, glsl_minor),
+        })
+    }
+
+    /// Whether it has GLSL, or GLSL ES, of at least `version`.
+    fn has_glsl(&self, version: (u32, u32)) -> bool {
+        self.glsl >= version
+    }
+}
+
+/// The first "major.minor" in `s`, with the digits of the minor version.
+fn first_version(s: &str) -> Option<(u32, &str)> {
+    let start = s.find(|c: char| c.is_ascii_digit())?;
+    let mut parts = s[start..].splitn(2, '.');
+    let major = parts.next()?.parse().ok()?;
+    let rest = parts.next()?;
+    let end = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    Some((major, &rest[..end])).filter(|(_, minor)| !minor.is_empty())
+}
+
+/// A set of shaders to draw with. `Renderer::new` tries the variants for
+/// the platform in order, as some drivers fail to compile the newer ones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShaderVariant {
+    /// GLSL 1.40, the default on desktop.
+    Glsl140,
+    /// GLSL 1.20, which takes the mode as a float attribute.
+    Glsl120,
+    /// GLSL 1.20, converting the colors to sRGB for a framebuffer which
+    /// doesn't, as in safe mode.
+    Glsl120LinearToSrgb,
+    /// GLSL ES 3.00, converting the colors to sRGB as the framebuffer of a
+    /// WebGL canvas isn't sRGB.
+    Es300LinearToSrgb,
+    /// GLSL ES 3.00 without the conversion, so colors come out a little too
+    /// dark.
+    Es300,
+}
+
+impl ShaderVariant {
+    /// The variants to try in order on a GL of `version`. In safe mode only the simplest
+    /// shaders are tried. Those converting the colors to sRGB are picked when the framebuffer
+    /// doesn't. Without a version, those for the platform are tried.
+    pub fn candidates(
+        version: Option<GlVersion>,
+        is_framebuffer_srgb: bool,
+        safe_mode: bool,
+    ) -> Vec<ShaderVariant> {
+        let version = match version {
+            Some(version) => version,
+            None => return Self::platform_candidates(is_framebuffer_srgb, safe_mode).to_vec(),
+        };
+        let mut candidates = Vec::new();
+        if version.is_es {
+            // OpenGL ES 2.0 and WebGL 1 only have GLSL ES 1.00, which there are no shaders
+            // for.
+            if version.has_glsl((3, 0)) {
+                if !is_framebuffer_srgb {
+                    candidates.push(ShaderVariant::Es300LinearToSrgb);
+                }
+                candidates.push(ShaderVariant::Es300);
+            }
+        } else if !is_framebuffer_srgb {
+            if version.has_glsl((1, 20)) {
+                candidates.push(ShaderVariant::Glsl120LinearToSrgb);
+            }
+        } else {
+            if version.has_glsl((1, 40)) && !safe_mode {
+                candidates.push(ShaderVariant::Glsl140);
+            }
+            if version.has_glsl((1, 20)) {
+                candidates.push(ShaderVariant::Glsl120);
+            }
+        }
+        candidates
+    }
+
+    /// The variants to try when the version is unknown. The web has no other choice than
+    /// GLSL ES 3.00.
+    fn platform_candidates(is_framebuffer_srgb: bool, safe_mode: bool) -> &'static [ShaderVariant] {
+        if cfg!(target_arch = "wasm32") {
+            if is_framebuffer_srgb {
+                &[ShaderVariant::Es300]
+            } else {
+                &[ShaderVariant::Es300LinearToSrgb, ShaderVariant::Es300]
+            }
+        } else if !is_framebuffer_srgb {
+            &[ShaderVariant::Glsl120LinearToSrgb]
+        } else if safe_mode {
+            &[ShaderVariant::Glsl120]
+        } else {
+            &[ShaderVariant::Glsl140, ShaderVariant::Glsl120]
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ShaderVariant::Glsl140 => "GLSL 1.40",
+            ShaderVariant::Glsl120 => "GLSL 1.20",
+            ShaderVariant::Glsl120LinearToSrgb => "GLSL 1.20 with sRGB conversion",
+            ShaderVariant::Es300LinearToSrgb => "GLSL ES 3.00 with sRGB conversion",
+            ShaderVariant::Es300 => "GLSL ES 3.00",
+        }
+    }
+
+    fn sources(self) -> (&'static str, &'static str) {
+        match self {
+            ShaderVariant::Glsl140 => (VERTEX_SHADER_140, FRAGMENT_SHADER_140),
+            ShaderVariant::Glsl120 => (VERTEX_SHADER_120, FRAGMENT_SHADER_120),
+            ShaderVariant::Glsl120LinearToSrgb => {
+                (VERTEX_SHADER_120, FRAGMENT_SHADER_120_LINEAR_TO_SRGB)
+            }
+            ShaderVariant::Es300LinearToSrgb => {
+                (VERTEX_SHADER_300_ES, FRAGMENT_SHADER_300_ES_LINEAR_TO_SRGB)
+            }
+            ShaderVariant::Es300 => (VERTEX_SHADER_300_ES, FRAGMENT_SHADER_300_ES),
+        }
+    }
+
+    /// Whether the shaders convert the colors to sRGB themselves.
+    pub fn converts_to_srgb(self) -> bool {
+        match self {
+            ShaderVariant::Glsl120LinearToSrgb | ShaderVariant::Es300LinearToSrgb => true,
+            _ => false,
+        }
+    }
+
+    /// The start of the shaders for `ColorMatrixPass` and `FocusGlowPass`
+    /// in the same GLSL version, with the macros for the keywords which
+    /// differ between them.
+    fn color_matrix_header(self) -> &'static str {
+        match self {
+            ShaderVariant::Glsl120 | ShaderVariant::Glsl120LinearToSrgb => {
+                "#version 120
+                #define IN_ATTRIBUTE attribute
+                #define IN_VARYING varying
+                #define OUT_VARYING varying
+                #define TEXTURE texture2D
+                #define FRAG_COLOR gl_FragColor
+                "
+            }
+            ShaderVariant::Glsl140 => {
+                "#version 140
+                #define IN_ATTRIBUTE in
+                #define IN_VARYING in
+                #define OUT_VARYING out
+                #define TEXTURE texture
+                #define FRAG_COLOR f_color
+                out vec4 f_color;
+                "
+            }
+            ShaderVariant::Es300LinearToSrgb | ShaderVariant::Es300 => {
+                "#version 300 es
+                precision mediump float;
+                #define IN_ATTRIBUTE in
+                #define IN_VARYING in
+                #define OUT_VARYING out
+                #define TEXTURE texture
+                #define FRAG_COLOR f_color
+                out vec4 f_color;
+                "
+            }
+        }
+    }
+
+    /// Whether the shaders take the mode as an integer attribute.
+    fn has_integer_mode(self) -> bool {
+        match self {
+            ShaderVariant::Glsl120 | ShaderVariant::Glsl120LinearToSrgb => false,
+            _ => true,
+        }
+    }
+}
+
+/// How the `Renderer` was set up, for the diagnostics window.
+#[derive(Clone, Debug)]
+pub struct RendererInfo {
+    pub shader: ShaderVariant,
+    /// The version the shaders were picked for, `None` if it wasn't understood.
+    pub gl_version: Option<GlVersion>,
+    /// Whether the renderer was set up in safe mode.
+    pub safe_mode: bool,
+    /// Whether the framebuffer converts the output to sRGB.
+    pub framebuffer_srgb: bool,
+    /// The variants which were tried first and failed, with their errors.
+    pub failed: Vec<(ShaderVariant, String)>,
+    /// The size of the glyph cache texture.
+    pub glyph_cache_size: (u32, u32),
+    /// Whether the program was loaded from the `ProgramCache` rather than compiled.
+    pub from_program_cache: bool,
+}
+
+/// Keeps the linked programs of the `Renderer` between runs, to skip compiling the shaders,
+/// see `RendererBuilder::program_cache`.
+pub trait ProgramCache {
+    /// The program linked from `vs` and `fs` before, if it is kept and the GL takes it back.
+    fn load(&self, gl: &glow::Context, vs: &str, fs: &str) -> Option<glow::Program>;
+
+    /// Prepare `program` to be stored, before it is linked.
+    fn before_link(&self, gl: &glow::Context, program: glow::Program);
+
+    /// Keep `program`, linked from `vs` and `fs`.
+    fn store(&self, gl: &glow::Context, vs: &str, fs: &str, program: glow::Program);
+}
+
 pub struct Program {
     program: glow::Program,
     attrib_position: u32,
@@ -283,60 +1030,178 @@ pub struct Program {
     attrib_mode: u32,
 }
 
-/// Construct the OpenGL shader program that can be used to render `Vertex`es.
-pub fn program(gl: &glow::Context, is_framebuffer_srgb: bool) -> Result<Program, String> {
-    let (vs, fs) = if cfg!(target_arch = "wasm32") {
-        if is_framebuffer_srgb {
-            (VERTEX_SHADER_300_ES, FRAGMENT_SHADER_300_ES)
-        } else {
-            (VERTEX_SHADER_300_ES, FRAGMENT_SHADER_300_ES_LINEAR_TO_SRGB)
-        }
-    } else {
-        assert_eq!(is_framebuffer_srgb, true);
-        (VERTEX_SHADER_140, FRAGMENT_SHADER_140)
-    };
-    unsafe {
-        let program = gl.create_program().expect("program creation failure");
+/// Compile a shader of type `kind`, named `stage` in the error with the info log on failure.
+unsafe fn compile_shader(
+    gl: &glow::Context,
+    kind: u32,
+    stage: &'static str,
+    source: &str,
+) -> Result<glow::Shader, RendererError> {
+    let shader = gl.create_shader(kind)?;
+    gl.shader_source(shader, source);
+    gl.compile_shader(shader);
+    if !gl.get_shader_compile_status(shader) {
+        let log = gl.get_shader_info_log(shader);
+        gl.delete_shader(shader);
+        return Err(RendererError::Compile { stage, log });
+    }
+    Ok(shader)
+}
 
-        let vertex_shader = gl.create_shader(glow::VERTEX_SHADER).unwrap();
-        gl.shader_source(vertex_shader, vs);
-        gl.compile_shader(vertex_shader);
-        if !gl.get_shader_compile_status(vertex_shader) {
-            panic!("{}", gl.get_shader_info_log(vertex_shader));
-        }
-        gl.attach_shader(program, vertex_shader);
+/// Compile and link a program from the vertex shader `vs` and the fragment
+/// shader `fs`.
+unsafe fn link_program(
+    gl: &glow::Context,
+    vs: &str,
+    fs: &str,
+) -> Result<glow::Program, RendererError> {
+    link_program_with(gl, vs, fs, |_| {})
+}
+
+/// Like `link_program`, loading the program from `cache` if it is there, and storing it there
+/// otherwise. Also returns whether it was loaded.
+unsafe fn link_cached_program(
+    gl: &glow::Context,
+    vs: &str,
+    fs: &str,
+    cache: Option<&dyn ProgramCache>,
+) -> Result<(glow::Program, bool), RendererError> {
+    let cache = match cache {
+        Some(cache) => cache,
+        None => return Ok((link_program(gl, vs, fs)?, false)),
+    };
+    if let Some(program) = cache.load(gl, vs, fs) {
+        return Ok((program, true));
+    }
+    let program = link_program_with(gl, vs, fs, |program| cache.before_link(gl, program))?;
+    cache.store(gl, vs, fs, program);
+    Ok((program, false))
+}
 
-        let fragment_shader = gl.create_shader(glow::FRAGMENT_SHADER).unwrap();
-        gl.shader_source(fragment_shader, fs);
-        gl.compile_shader(fragment_shader);
-        if !gl.get_shader_compile_status(fragment_shader) {
-            panic!("{}", gl.get_shader_info_log(fragment_shader));
+/// Like `link_program`, calling `before_link` with the program right before linking it.
+unsafe fn link_program_with(
+    gl: &glow::Context,
+    vs: &str,
+    fs: &str,
+    before_link: impl FnOnce(glow::Program),
+) -> Result<glow::Program, RendererError> {
+    let vertex_shader = compile_shader(gl, glow::VERTEX_SHADER, "vertex", vs)?;
+    let fragment_shader = match compile_shader(gl, glow::FRAGMENT_SHADER, "fragment", fs) {
+        Ok(shader) => shader,
+        Err(err) => {
+            gl.delete_shader(vertex_shader);
+            return Err(err);
         }
-        gl.attach_shader(program, fragment_shader);
+    };
 
-        gl.link_program(program);
-        if !gl.get_program_link_status(program) {
-            panic!(gl.get_program_info_log(program));
+    let program = match gl.create_program() {
+        Ok(program) => program,
+        Err(err) => {
+            gl.delete_shader(vertex_shader);
+            gl.delete_shader(fragment_shader);
+            return Err(RendererError::Gl(err));
         }
+    };
+    gl.attach_shader(program, vertex_shader);
+    gl.attach_shader(program, fragment_shader);
+    before_link(program);
+    gl.link_program(program);
+    let linked = gl.get_program_link_status(program);
+
+    gl.detach_shader(program, vertex_shader);
+    gl.delete_shader(vertex_shader);
+    gl.detach_shader(program, fragment_shader);
+    gl.delete_shader(fragment_shader);
+
+    if !linked {
+        let log = gl.get_program_info_log(program);
+        gl.delete_program(program);
+        return Err(RendererError::Link(log));
+    }
+    Ok(program)
+}
 
-        gl.detach_shader(program, vertex_shader);
-        gl.delete_shader(vertex_shader);
-        gl.detach_shader(program, fragment_shader);
-        gl.delete_shader(fragment_shader);
+/// Construct the OpenGL shader program that can be used to render `Vertex`es.
+pub fn program(gl: &glow::Context, variant: ShaderVariant) -> Result<Program, RendererError> {
+    program_with_cache(gl, variant, None).map(|(program, _)| program)
+}
 
-        let attrib_position = gl.get_attrib_location(program, "position").unwrap();
-        let attrib_tex_coords = gl.get_attrib_location(program, "tex_coords").unwrap();
-        let attrib_color = gl.get_attrib_location(program, "color").unwrap();
-        let attrib_mode = gl.get_attrib_location(program, "mode").unwrap();
+/// Like `program`, through `cache`. Also returns whether it was loaded from the cache.
+fn program_with_cache(
+    gl: &glow::Context,
+    variant: ShaderVariant,
+    cache: Option<&dyn ProgramCache>,
+) -> Result<(Program, bool), RendererError> {
+    let (vs, fs) = variant.sources();
+    unsafe {
+        let (program, from_cache) = link_cached_program(gl, vs, fs, cache)?;
+        let attribs = ["position", "tex_coords", "color", "mode"]
+            .iter()
+            .map(|&name| {
+                gl.get_attrib_location(program, name)
+                    .ok_or(RendererError::MissingAttribute(name))
+            })
+            .collect::<Result<Vec<u32>, RendererError>>();
+        let attribs = match attribs {
+            Ok(attribs) => attribs,
+            Err(err) => {
+                gl.delete_program(program);
+                return Err(err);
+            }
+        };
 
-        Ok(Program {
+        let program = Program {
             program,
-            attrib_position,
-            attrib_tex_coords,
-            attrib_color,
-            attrib_mode,
-        })
+            attrib_position: attribs[0],
+            attrib_tex_coords: attribs[1],
+            attrib_color: attribs[2],
+            attrib_mode: attribs[3],
+        };
+        Ok((program, from_cache))
+    }
+}
+
+/// Try the shader variants for the version of `gl` in order, and return the
+/// program of the first one which works, through `cache` if any.
+fn first_working_program(
+    gl: &glow::Context,
+    is_framebuffer_srgb: bool,
+    safe_mode: bool,
+    cache: Option<&dyn ProgramCache>,
+) -> Result<(Program, RendererInfo), RendererError> {
+    let version = GlVersion::query(gl);
+    let candidates = ShaderVariant::candidates(version, is_framebuffer_srgb, safe_mode);
+    if candidates.is_empty() {
+        let version = version.map_or((0, 0), |version| version.glsl);
+        return Err(RendererError::NoShader(format!(
+            "There are no shaders for GLSL {}.{:02} with this framebuffer.",
+            version.0, version.1
+        )));
+    }
+    let mut failed = Vec::new();
+    for variant in candidates {
+        match program_with_cache(gl, variant, cache) {
+            Ok((program, from_program_cache)) => {
+                let info = RendererInfo {
+                    shader: variant,
+                    gl_version: version,
+                    safe_mode,
+                    framebuffer_srgb: is_framebuffer_srgb,
+                    failed,
+                    // Filled in with the glyph cache.
+                    glyph_cache_size: (0, 0),
+                    from_program_cache,
+                };
+                return Ok((program, info));
+            }
+            Err(err) => failed.push((variant, err.to_string())),
+        }
     }
+    let errors: Vec<String> = failed
+        .iter()
+        .map(|(variant, err)| format!("{}: {}", variant.name(), err))
+        .collect();
+    Err(RendererError::NoShader(errors.join("; ")))
 }
 
 /// Converts gamma (brightness) from sRGB to linear color space.
@@ -367,13 +1232,17 @@ fn rusttype_glyph_cache(w: u32, h: u32) -> text::GlyphCache<'static> {
 }
 
 // Create the texture used within a `GlyphCache` of the given size.
+//
+// In safe mode the storage is allocated with `tex_image_2d` rather than
+// `tex_storage_2d`, which needs OpenGL 4.2.
 fn glyph_cache_texture(
     gl: &glow::Context,
     width: u32,
     height: u32,
+    safe_mode: bool,
 ) -> Result<<glow::Context as HasContext>::Texture, String> {
     unsafe {
-        let texture = gl.create_texture().unwrap();
+        let texture = gl.create_texture()?;
 
         let num_components = 1;
         let data_size = num_components as usize * width as usize * height as usize;
@@ -394,130 +1263,2558 @@ fn glyph_cache_texture(
         );
         gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_BASE_LEVEL, 0);
         gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MAX_LEVEL, 0);
-        gl.tex_storage_2d(glow::TEXTURE_2D, 1, glow::R8, width as i32, height as i32);
         gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
-        gl.tex_sub_image_2d(
-            glow::TEXTURE_2D,
-            0,
-            0,
+        if safe_mode {
+            gl.tex_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                glow::R8 as i32,
+                width as i32,
+                height as i32,
+                0,
+                glow::RED,
+                glow::UNSIGNED_BYTE,
+                Some(&data),
+            );
+        } else {
+            gl.tex_storage_2d(glow::TEXTURE_2D, 1, glow::R8, width as i32, height as i32);
+            gl.tex_sub_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                0,
+                0,
+                width as i32,
+                height as i32,
+                glow::RED,
+                glow::UNSIGNED_BYTE,
+                glow::PixelUnpackData::Slice(&data),
+            );
+        }
+        Ok(texture)
+    }
+}
+
+/// The largest texture the GL supports, in each dimension.
+fn max_texture_size(gl: &glow::Context) -> u32 {
+    unsafe { gl.get_parameter_i32(glow::MAX_TEXTURE_SIZE) }.max(0) as u32
+}
+
+/// The size of a glyph cache of `size` grown twice as big in each dimension, but no bigger than
+/// `max`. The same size if it can't grow.
+fn grown_size((width, height): (u32, u32), (max_w, max_h): (u32, u32)) -> (u32, u32) {
+    let grown = |size: u32, max: u32| size.saturating_mul(2).min(max).max(size);
+    (grown(width, max_w), grown(height, max_h))
+}
+
+/// Upload the pixels of a glyph to `rect` of a glyph cache texture `cache_w` pixels wide, and
+/// to the copy of the texture, if any.
+fn upload_glyph(
+    gl: &glow::Context,
+    texture: glow::Texture,
+    pixels: &mut Option<Vec<u8>>,
+    cache_w: u32,
+    rect: text::rt::Rect<u32>,
+    data: &[u8],
+) {
+    let w = rect.width();
+    let h = rect.height();
+
+    if let Some(pixels) = pixels {
+        for row in 0..h as usize {
+            let start = (rect.min.y as usize + row) * cache_w as usize + rect.min.x as usize;
+            pixels[start..start + w as usize]
+                .copy_from_slice(&data[row * w as usize..(row + 1) * w as usize]);
+        }
+    }
+
+    unsafe {
+        gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+        assert_eq!(w * h, data.len() as _);
+        gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
+        gl.tex_sub_image_2d(
+            glow::TEXTURE_2D,
             0,
-            width as i32,
-            height as i32,
+            rect.min.x as i32,
+            rect.min.y as i32,
+            w as i32,
+            h as i32,
             glow::RED,
             glow::UNSIGNED_BYTE,
-            glow::PixelUnpackData::Slice(&data),
+            glow::PixelUnpackData::Slice(data),
         );
-        Ok(texture)
+        gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 4);
     }
 }
 
 impl GlyphCache {
-    /// Construct a **GlyphCache** with the given texture dimensions.
+    /// The size of the cache relative to the framebuffer used by `Renderer::new`.
     ///
-    /// When calling `GlyphCache::new`, the `get_framebuffer_dimensions` method is used to produce
-    /// the width and height. However, often creating a texture the size of the screen might not be
-    /// large enough to cache the necessary text for an application. The following constant
-    /// multiplier is used to ensure plenty of room in the cache.
+    /// The framebuffer is in physical pixels, so the cache grows with the scale factor like the
+    /// glyphs do.
+    pub const DEFAULT_SCALE: f32 = 1.0;
+    /// The smallest cache made for a framebuffer, as the window may start out tiny on the web.
+    const MIN_SIZE: u32 = 512;
+
+    /// The size of the cache for a framebuffer of `framebuffer` pixels, `scale` times as big but
+    /// no bigger than the largest texture the GL supports.
+    pub fn size_for_framebuffer(
+        gl: &glow::Context,
+        framebuffer: (u32, u32),
+        scale: f32,
+    ) -> (u32, u32) {
+        let max = max_texture_size(gl);
+        let size = |pixels: u32| {
+            ((pixels as f32 * scale) as u32)
+                .max(Self::MIN_SIZE)
+                .min(max.max(Self::MIN_SIZE))
+        };
+        (size(framebuffer.0), size(framebuffer.1))
+    }
+
+    /// Construct a **GlyphCache** with the given texture dimensions.
     pub fn with_dimensions(gl: &glow::Context, width: u32, height: u32) -> Result<Self, String> {
+        Self::create(gl, width, height, false)
+    }
+
+    fn create(
+        gl: &glow::Context,
+        width: u32,
+        height: u32,
+        safe_mode: bool,
+    ) -> Result<Self, String> {
         // First, the rusttype `Cache` which performs the logic for rendering and laying out glyphs
         // in the cache.
         let cache = rusttype_glyph_cache(width, height);
 
         // Now the texture to which glyphs will be rendered.
-        let texture = glyph_cache_texture(gl, width, height)?;
+        let texture = glyph_cache_texture(gl, width, height, safe_mode)?;
 
         Ok(GlyphCache {
             cache: cache,
             texture: texture,
+            pixels: None,
+            version: 0,
+            safe_mode,
+            max_size: (u32::MAX, u32::MAX),
         })
     }
 
-    /// Construct a `GlyphCache` with a size equal to the given `Display`'s current framebuffer
-    /// dimensions.
-    pub fn new(gl: &glow::Context) -> Result<Self, String> {
-        Self::with_dimensions(gl, 1200, 900)
+    /// Construct a `GlyphCache` sized for a framebuffer of `framebuffer` pixels, see
+    /// `size_for_framebuffer`.
+    pub fn new(gl: &glow::Context, framebuffer: (u32, u32), scale: f32) -> Result<Self, String> {
+        let (width, height) = Self::size_for_framebuffer(gl, framebuffer, scale);
+        Self::create(gl, width, height, false)
+    }
+
+    /// Like `new`, but only using OpenGL 3.0 to create the texture.
+    pub fn new_safe_mode(
+        gl: &glow::Context,
+        framebuffer: (u32, u32),
+        scale: f32,
+    ) -> Result<Self, String> {
+        let (width, height) = Self::size_for_framebuffer(gl, framebuffer, scale);
+        Self::create(gl, width, height, true)
+    }
+
+    /// The size of the texture.
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.cache.dimensions()
     }
 
     /// The texture used to cache the glyphs on the GPU.
     pub fn texture(&self) -> &glow::Texture {
         &self.texture
     }
+
+    /// The largest size the cache grows to when the glyphs of a frame don't fit. It never grows
+    /// past the largest texture the GL supports either, which is the only limit by default.
+    pub fn max_size(&self) -> (u32, u32) {
+        self.max_size
+    }
+
+    pub fn set_max_size(&mut self, width: u32, height: u32) {
+        self.max_size = (width, height);
+    }
+
+    /// Make the texture twice as big in each dimension, up to `max_size`, and forget the cached
+    /// glyphs. The queued ones stay queued. Returns whether it grew.
+    fn grow(&mut self, gl: &glow::Context) -> bool {
+        let size = self.dimensions();
+        let max_texture = max_texture_size(gl);
+        let max = (
+            self.max_size.0.min(max_texture),
+            self.max_size.1.min(max_texture),
+        );
+        let (width, height) = grown_size(size, max);
+        (width, height) != size && self.resize(gl, width, height).is_ok()
+    }
+
+    /// Replace the texture with an empty one of `width` by `height` pixels, and forget the
+    /// cached glyphs. The queued ones stay queued.
+    fn resize(&mut self, gl: &glow::Context, width: u32, height: u32) -> Result<(), String> {
+        let texture = glyph_cache_texture(gl, width, height, self.safe_mode)?;
+        let old = std::mem::replace(&mut self.texture, texture);
+        unsafe { gl.delete_texture(old) };
+        self.cache
+            .to_builder()
+            .dimensions(width, height)
+            .rebuild(&mut self.cache);
+        if self.pixels.is_some() {
+            self.pixels = Some(vec![128u8; width as usize * height as usize]);
+        }
+        self.version += 1;
+        Ok(())
+    }
+
+    /// Upload a glyph rasterized elsewhere, as by a `Tessellator`.
+    fn upload(&mut self, gl: &glow::Context, rect: text::rt::Rect<u32>, data: &[u8]) {
+        let (cache_w, _) = self.dimensions();
+        self.version += 1;
+        upload_glyph(gl, self.texture, &mut self.pixels, cache_w, rect, data);
+    }
+
+    /// Like `cache_queued`, but growing the cache as long as the queued glyphs don't fit.
+    /// Returns whether it grew, which forgets the glyphs cached before.
+    fn cache_queued_growing(
+        &mut self,
+        gl: &glow::Context,
+    ) -> Result<bool, text::rt::gpu_cache::CacheWriteErr> {
+        let mut grew = false;
+        loop {
+            match self.cache_queued(gl) {
+                Ok(()) => return Ok(grew),
+                Err(err) => {
+                    if !self.grow(gl) {
+                        return Err(err);
+                    }
+                    grew = true;
+                }
+            }
+        }
+    }
+
+    /// Keep a copy of the texture in memory from now on. The texture starts out filled, so the
+    /// copy is only complete if this is called before any glyphs are cached.
+    pub fn keep_pixels(&mut self) {
+        if self.pixels.is_none() {
+            let (width, height) = self.dimensions();
+            self.pixels = Some(vec![128u8; width as usize * height as usize]);
+        }
+    }
+
+    /// Rasterize the queued glyphs which aren't cached yet and upload them to the texture.
+    fn cache_queued(
+        &mut self,
+        gl: &glow::Context,
+    ) -> Result<(), text::rt::gpu_cache::CacheWriteErr> {
+        let (cache_w, _) = self.dimensions();
+        let GlyphCache {
+            ref mut cache,
+            ref texture,
+            ref mut pixels,
+            ref mut version,
+            ..
+        } = *self;
+        cache.cache_queued(|rect, data| {
+            *version += 1;
+            upload_glyph(gl, *texture, pixels, cache_w, rect, data);
+        })?;
+        Ok(())
+    }
 }
 
-pub trait Display {
-    fn framebuffer_dimensions(&self) -> (u32, u32);
-    fn hidpi_factor(&self) -> f64;
+/// Caches the glyphs of the text for `tessellate`.
+trait GlyphStore {
+    fn cache(&mut self) -> &mut text::GlyphCache<'static>;
+
+    /// Cache the queued glyphs, growing the cache if they don't fit. Returns whether it grew.
+    fn cache_queued(&mut self) -> Result<bool, text::rt::gpu_cache::CacheWriteErr>;
 }
 
-#[cfg(not(target_arch = "wasm32"))]
-impl<T> Display for glutin::WindowedContext<T>
+/// The glyph cache of a `Renderer`, uploading to its texture right away.
+struct GpuGlyphs<'a> {
+    glyph_cache: &'a mut GlyphCache,
+    gl: &'a glow::Context,
+    /// Why glyphs were last left out, if they were.
+    error: Option<text::rt::gpu_cache::CacheWriteErr>,
+}
+
+impl<'a> GpuGlyphs<'a> {
+    fn new(glyph_cache: &'a mut GlyphCache, gl: &'a glow::Context) -> Self {
+        GpuGlyphs {
+            glyph_cache,
+            gl,
+            error: None,
+        }
+    }
+
+    /// Whether all the glyphs were cached.
+    fn result(&self) -> Result<(), RendererError> {
+        match self.error {
+            Some(err) => Err(RendererError::GlyphCache(err)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'a> GlyphStore for GpuGlyphs<'a> {
+    fn cache(&mut self) -> &mut text::GlyphCache<'static> {
+        &mut self.glyph_cache.cache
+    }
+
+    fn cache_queued(&mut self) -> Result<bool, text::rt::gpu_cache::CacheWriteErr> {
+        let result = self.glyph_cache.cache_queued_growing(self.gl);
+        if let Err(err) = result {
+            self.error = Some(err);
+        }
+        result
+    }
+}
+
+/// A glyph rasterized by a `Tessellator`, to upload to the glyph cache texture.
+#[derive(Clone, Debug)]
+struct GlyphUpload {
+    rect: text::rt::Rect<u32>,
+    data: Vec<u8>,
+}
+
+/// What a `Tessellator` made of the primitives of a frame, for `Renderer::load_mesh`.
+pub struct Mesh {
+    glyph_cache_size: (u32, u32),
+    glyph_uploads: Vec<GlyphUpload>,
+    glyph_cache_grew: bool,
+    commands: Vec<PreparedCommand>,
+    geometry: Geometry,
+}
+
+impl Mesh {
+    /// Take out the commands and vertices for a framebuffer of `framebuffer` pixels, like
+    /// `Renderer::frame`, for renderers which draw meshes without GL.
+    pub fn frame(&self, framebuffer: (u32, u32), images: &[image::Id]) -> Frame {
+        frame_of(&self.commands, &self.geometry, framebuffer, images)
+    }
+
+    /// Whether the glyph cache had to grow while making the mesh, like
+    /// `Renderer::glyph_cache_grew`.
+    pub fn glyph_cache_grew(&self) -> bool {
+        self.glyph_cache_grew
+    }
+
+    /// Upload the glyphs of the mesh to `pixels`, a copy of the glyph cache texture kept by a
+    /// renderer without GL, like `Renderer::load_mesh` does to the texture. It is made anew
+    /// when the cache has changed size.
+    pub fn upload_glyphs(&self, pixels: &mut GlyphPixels) {
+        let (width, height) = self.glyph_cache_size;
+        if (pixels.width, pixels.height) != (width, height) {
+            pixels.width = width;
+            pixels.height = height;
+            pixels.data = vec![0; width as usize * height as usize];
+        }
+        for upload in &self.glyph_uploads {
+            let rect = upload.rect;
+            let upload_w = rect.width() as usize;
+            for (row, line) in upload.data.chunks(upload_w.max(1)).enumerate() {
+                let start = (rect.min.y as usize + row) * width as usize + rect.min.x as usize;
+                if let Some(target) = pixels.data.get_mut(start..start + line.len()) {
+                    target.copy_from_slice(line);
+                }
+            }
+            pixels.version += 1;
+        }
+    }
+
+    /// Take over the glyph uploads of `older`, a mesh of the same `Tessellator` which won't be
+    /// loaded, as the glyphs of this one may have been cached while making it.
+    pub fn carry_glyphs_from(&mut self, older: Mesh) {
+        // Otherwise the cache grew since, and the texture is made anew.
+        if older.glyph_cache_size == self.glyph_cache_size {
+            let mut uploads = older.glyph_uploads;
+            uploads.append(&mut self.glyph_uploads);
+            self.glyph_uploads = uploads;
+        }
+    }
+}
+
+/// The part of a `Renderer` which translates primitives, from `Renderer::tessellator`. It
+/// doesn't touch GL, so it can run on another thread.
+///
+/// It caches the glyphs in a cache of its own, and each `Mesh` carries the glyphs to upload to
+/// the texture of the renderer. So the renderer should only draw meshes from then on, loaded in
+/// the order they were made.
+pub struct Tessellator {
+    cache: text::GlyphCache<'static>,
+    /// The largest size the cache grows to, within what the GL supports.
+    max_size: (u32, u32),
+    uploads: Vec<GlyphUpload>,
+}
+
+impl Tessellator {
+    /// A tessellator with a glyph cache of `size`, which grows up to `max_size`, for drawing
+    /// without a `Renderer`. `Renderer::tessellator` makes one for the renderer.
+    pub fn new(size: (u32, u32), max_size: (u32, u32)) -> Self {
+        Tessellator {
+            cache: rusttype_glyph_cache(size.0, size.1),
+            max_size,
+            uploads: Vec::new(),
+        }
+    }
+
+    /// Translate `primitives` for a framebuffer of `display`, like `Renderer::fill`. The images
+    /// not in `image_sizes` are left out.
+    pub fn tessellate<D, P>(
+        &mut self,
+        display: &D,
+        primitives: P,
+        image_sizes: &std::collections::HashMap<image::Id, (u32, u32)>,
+    ) -> Mesh
+    where
+        P: render::PrimitiveWalker,
+        D: Display,
+    {
+        let mut commands = Vec::new();
+        let mut geometry = Geometry::default();
+        let image_size = |id| image_sizes.get(&id).copied();
+        let glyph_cache_grew = tessellate(
+            display,
+            primitives,
+            self,
+            &image_size,
+            &mut commands,
+            &mut geometry,
+            None,
+        );
+        Mesh {
+            glyph_cache_size: self.cache.dimensions(),
+            glyph_uploads: std::mem::take(&mut self.uploads),
+            glyph_cache_grew,
+            commands,
+            geometry,
+        }
+    }
+}
+
+impl Tessellator {
+    /// Add the shapes of the debug layer above the primitives of `mesh`, which must be the
+    /// latest mesh made, like `Renderer::fill_debug`.
+    pub fn tessellate_debug<D: Display>(
+        &mut self,
+        display: &D,
+        mesh: &mut Mesh,
+        shapes: &[debug_draw::Shape],
+        font: Option<(text::font::Id, &text::Font)>,
+    ) {
+        let grew = tessellate_debug(
+            display,
+            shapes,
+            font,
+            self,
+            &mut mesh.commands,
+            &mut mesh.geometry,
+        );
+        if grew {
+            // The texture is made anew, with only the glyphs cached since.
+            mesh.glyph_uploads.clear();
+            mesh.glyph_cache_size = self.cache.dimensions();
+            mesh.glyph_cache_grew = true;
+        }
+        mesh.glyph_uploads.append(&mut self.uploads);
+    }
+}
+
+impl GlyphStore for Tessellator {
+    fn cache(&mut self) -> &mut text::GlyphCache<'static> {
+        &mut self.cache
+    }
+
+    fn cache_queued(&mut self) -> Result<bool, text::rt::gpu_cache::CacheWriteErr> {
+        let mut grew = false;
+        loop {
+            let uploads = &mut self.uploads;
+            let cached = self.cache.cache_queued(|rect, data| {
+                uploads.push(GlyphUpload {
+                    rect,
+                    data: data.to_vec(),
+                })
+            });
+            let err = match cached {
+                Ok(_) => return Ok(grew),
+                Err(err) => err,
+            };
+            let size = self.cache.dimensions();
+            let (width, height) = grown_size(size, self.max_size);
+            if (width, height) == size {
+                return Err(err);
+            }
+            self.cache
+                .to_builder()
+                .dimensions(width, height)
+                .rebuild(&mut self.cache);
+            // The texture is made anew for the new size, so the glyphs uploaded so far are gone.
+            self.uploads.clear();
+            grew = true;
+        }
+    }
+}
+
+/// Translate `primitives` into `commands` and `geometry` for a framebuffer of `display`,
+/// caching the glyphs in `glyph_store`. The images are drawn if `image_size` knows their size.
+/// With `widget_starts`, the commands are split between widgets, and the index of the first
+/// command of each one is pushed to it. Returns whether the glyph cache grew.
+fn tessellate<D, P>(
+    display: &D,
+    mut primitives: P,
+    glyph_store: &mut dyn GlyphStore,
+    image_size: &dyn Fn(image::Id) -> Option<(u32, u32)>,
+    commands: &mut Vec<PreparedCommand>,
+    geometry: &mut Geometry,
+    mut widget_starts: Option<&mut Vec<(usize, Option<widget::Id>)>>,
+) -> bool
 where
-    T: glutin::ContextCurrentState,
+    P: render::PrimitiveWalker,
+    D: Display,
 {
-    fn framebuffer_dimensions(&self) -> (u32, u32) {
-        (
-            self.window().inner_size().width,
-            self.window().inner_size().height,
-        )
+    let mut glyph_cache_grew = false;
+
+    enum State {
+        Image { image_id: image::Id, start: usize },
+        Plain { start: usize },
     }
 
-    fn hidpi_factor(&self) -> f64 {
-        self.window().scale_factor()
+    let mut current_state = State::Plain { start: 0 };
+
+    // Switches to the `Plain` state and completes the previous `Command` if not already in the
+    // `Plain` state.
+    macro_rules! switch_to_plain_state {
+        () => {
+            match current_state {
+                State::Plain { .. } => (),
+                State::Image { image_id, start } => {
+                    commands.push(PreparedCommand::Image(image_id, start..geometry.len()));
+                    current_state = State::Plain {
+                        start: geometry.len(),
+                    };
+                }
+            }
+        };
     }
-}
 
-#[cfg(target_arch = "wasm32")]
-impl Display for (u32, u32, f64) {
-    fn framebuffer_dimensions(&self) -> (u32, u32) {
-        (self.0, self.1)
+    // Completes the current `Command`, and sets the state back to plain drawing.
+    macro_rules! finish_command {
+        () => {
+            match current_state {
+                State::Plain { start } => {
+                    commands.push(PreparedCommand::Plain(start..geometry.len()))
+                }
+                State::Image { image_id, start } => {
+                    commands.push(PreparedCommand::Image(image_id, start..geometry.len()))
+                }
+            }
+            current_state = State::Plain {
+                start: geometry.len(),
+            };
+        };
     }
 
-    fn hidpi_factor(&self) -> f64 {
-        self.2
+    // Framebuffer dimensions and the "dots per inch" factor.
+    let (screen_w, screen_h) = display.framebuffer_dimensions();
+    // A minimized window or a collapsed canvas has nothing to draw to, and the conversions
+    // below would divide by zero.
+    if screen_w == 0 || screen_h == 0 {
+        return false;
+    }
+    let (win_w, win_h) = (screen_w as Scalar, screen_h as Scalar);
+    let half_win_w = win_w / 2.0;
+    let half_win_h = win_h / 2.0;
+    let dpi_factor = display.hidpi_factor() as Scalar;
+
+    // Functions for converting for conrod scalar coords to GL vertex coords (-1.0 to 1.0).
+    let vx = |x: Scalar| (x * dpi_factor / half_win_w) as f32;
+    let vy = |y: Scalar| (y * dpi_factor / half_win_h) as f32;
+
+    let mut current_scizzor = GlRect {
+        left: 0,
+        width: screen_w,
+        bottom: 0,
+        height: screen_h,
+    };
+
+    // Draw each primitive in order of depth.
+    while let Some(primitive) = primitives.next_primitive() {
+        let render::Primitive {
+            id,
+            kind,
+            scizzor,
+            rect,
+            ..
+        } = primitive;
+
+        // Start the commands of another widget, if they are tagged.
+        if let Some(starts) = widget_starts.as_mut() {
+            if starts.last().map(|&(_, widget)| widget) != Some(Some(id)) {
+                finish_command!();
+                starts.push((commands.len(), Some(id)));
+            }
+        }
+
+        // Check for a `Scizzor` command.
+        let new_scizzor = GlRect::from_rect(scizzor, dpi_factor, (screen_w, screen_h));
+        if new_scizzor != current_scizzor {
+            finish_command!();
+
+            // Update the scizzor and produce a command.
+            current_scizzor = new_scizzor;
+            commands.push(PreparedCommand::Scizzor(new_scizzor));
+        }
+
+        match kind {
+            render::PrimitiveKind::Rectangle { color } => {
+                switch_to_plain_state!();
+
+                let color = gamma_srgb_to_linear(color.to_fsa());
+                let (l, r, b, t) = rect.l_r_b_t();
+
+                let v = |x, y| {
+                    // Convert from conrod Scalar range to GL range -1.0 to 1.0.
+                    Vertex {
+                        position: [vx(x), vy(y)],
+                        tex_coords: [0.0, 0.0],
+                        color: color,
+                        mode: MODE_GEOMETRY,
+                    }
+                };
+
+                geometry.push_quad([v(l, t), v(l, b), v(r, b), v(r, t)]);
+            }
+
+            render::PrimitiveKind::TrianglesSingleColor { color, triangles } => {
+                if triangles.is_empty() {
+                    continue;
+                }
+
+                switch_to_plain_state!();
+
+                let color = gamma_srgb_to_linear(color.into());
+
+                let v = |p: [Scalar; 2]| Vertex {
+                    position: [vx(p[0]), vy(p[1])],
+                    tex_coords: [0.0, 0.0],
+                    color: color,
+                    mode: MODE_GEOMETRY,
+                };
+
+                for triangle in triangles {
+                    geometry.push_triangle([v(triangle[0]), v(triangle[1]), v(triangle[2])]);
+                }
+            }
+
+            render::PrimitiveKind::TrianglesMultiColor { triangles } => {
+                if triangles.is_empty() {
+                    continue;
+                }
+
+                switch_to_plain_state!();
+
+                let v = |(p, c): ([Scalar; 2], color::Rgba)| Vertex {
+                    position: [vx(p[0]), vy(p[1])],
+                    tex_coords: [0.0, 0.0],
+                    color: gamma_srgb_to_linear(c.into()),
+                    mode: MODE_GEOMETRY,
+                };
+
+                for triangle in triangles {
+                    geometry.push_triangle([v(triangle[0]), v(triangle[1]), v(triangle[2])]);
+                }
+            }
+
+            render::PrimitiveKind::Text {
+                color,
+                text,
+                font_id,
+            } => {
+                switch_to_plain_state!();
+
+                let positioned_glyphs = text.positioned_glyphs(dpi_factor as f32);
+
+                let cache_id = font_id.index();
+
+                // Cache the glyphs on the GPU. Those which don't fit even in the largest
+                // cache are left out.
+                let queue = |cache: &mut text::GlyphCache<'static>| {
+                    for glyph in positioned_glyphs.iter() {
+                        cache.queue_glyph(cache_id, glyph.clone());
+                    }
+                };
+                match cache_glyphs(glyph_store, geometry, &queue) {
+                    Ok(grew) => glyph_cache_grew |= grew,
+                    Err(_) => glyph_store.cache().clear_queue(),
+                }
+
+                let color = gamma_srgb_to_linear(color.to_fsa());
+
+                for g in positioned_glyphs {
+                    if let Ok(Some((uv_rect, screen_rect))) =
+                        glyph_store.cache().rect_for(cache_id, g)
+                    {
+                        let glyph = (cache_id, g);
+                        push_glyph(
+                            geometry,
+                            glyph,
+                            uv_rect,
+                            screen_rect,
+                            color,
+                            (screen_w, screen_h),
+                        );
+                    }
+                }
+            }
+
+            render::PrimitiveKind::Image {
+                image_id,
+                color,
+                source_rect,
+            } => {
+                // Switch to the `Image` state for this image if we're not in it already.
+                let new_image_id = image_id;
+                match current_state {
+                    // If we're already in the drawing mode for this image, we're done.
+                    State::Image { image_id, .. } if image_id == new_image_id => (),
+
+                    // If we were in the `Plain` drawing state, switch to Image drawing state.
+                    State::Plain { start } => {
+                        commands.push(PreparedCommand::Plain(start..geometry.len()));
+                        current_state = State::Image {
+                            image_id: new_image_id,
+                            start: geometry.len(),
+                        };
+                    }
+
+                    // If we were drawing a different image, switch state to draw *this* image.
+                    State::Image { image_id, start } => {
+                        commands.push(PreparedCommand::Image(image_id, start..geometry.len()));
+                        current_state = State::Image {
+                            image_id: new_image_id,
+                            start: geometry.len(),
+                        };
+                    }
+                }
+
+                let color = color.unwrap_or(color::WHITE).to_fsa();
+
+                if let Some((image_w, image_h)) = image_size(image_id) {
+                    let (image_w, image_h) = (image_w as Scalar, image_h as Scalar);
+
+                    // Get the sides of the source rectangle as uv coordinates.
+                    //
+                    // Texture coordinates range:
+                    // - left to right: 0.0 to 1.0
+                    // - bottom to top: 0.0 to 1.0
+                    let (uv_l, uv_r, uv_b, uv_t) = match source_rect {
+                        Some(src_rect) => {
+                            let (l, r, b, t) = src_rect.l_r_b_t();
+                            (
+                                (l / image_w) as f32,
+                                (r / image_w) as f32,
+                                (b / image_h) as f32,
+                                (t / image_h) as f32,
+                            )
+                        }
+                        None => (0.0, 1.0, 0.0, 1.0),
+                    };
+
+                    let v = |x, y, t| {
+                        // Convert from conrod Scalar range to GL range -1.0 to 1.0.
+                        let x = (x * dpi_factor as Scalar / half_win_w) as f32;
+                        let y = (y * dpi_factor as Scalar / half_win_h) as f32;
+                        Vertex {
+                            position: [x, y],
+                            tex_coords: t,
+                            color: color,
+                            mode: MODE_IMAGE,
+                        }
+                    };
+
+                    let (l, r, b, t) = rect.l_r_b_t();
+                    geometry.push_quad([
+                        v(l, t, [uv_l, uv_t]),
+                        v(l, b, [uv_l, uv_b]),
+                        v(r, b, [uv_r, uv_b]),
+                        v(r, t, [uv_r, uv_t]),
+                    ]);
+                }
+            }
+
+            // The only special case widget is the glyph grid, which is drawn cell by cell.
+            render::PrimitiveKind::Other(container) => {
+                let grid = match container.unique_widget_state::<GlyphGrid>() {
+                    Some(grid) => &grid.state,
+                    None => continue,
+                };
+                let (font_id, font) = match &grid.font {
+                    Some((font_id, font)) if grid.cols > 0 => (font_id, font),
+                    _ => continue,
+                };
+                switch_to_plain_state!();
+
+                // Place the cells on whole pixels, so that every cell showing the same
+                // character uses the same glyph cache entry.
+                let cell_w = (grid.cell_w * dpi_factor).round() as i32;
+                let cell_h = (grid.cell_h * dpi_factor).round() as i32;
+                let left = (rect.left() * dpi_factor + half_win_w).round() as i32;
+                let top = (half_win_h - rect.top() * dpi_factor).round() as i32;
+                let scale = text::f32_pt_to_scale(grid.font_size as f32 * dpi_factor as f32);
+                let baseline = font.v_metrics(scale).ascent.round();
+                let cache_id = font_id.index();
+
+                // Lay out each distinct character once, at the origin of a cell.
+                let mut glyphs = std::collections::HashMap::new();
+                for cell in &grid.cells {
+                    if cell.ch != ' ' && !glyphs.contains_key(&cell.ch) {
+                        let glyph = font.glyph(cell.ch).scaled(scale);
+                        let advance = glyph.h_metrics().advance_width;
+                        // Centre narrow glyphs in the cell.
+                        let x = ((cell_w as f32 - advance) / 2.0).max(0.0).round();
+                        let glyph = glyph.positioned(text::rt::point(x, baseline));
+                        glyphs.insert(cell.ch, glyph);
+                    }
+                }
+                let queue = |cache: &mut text::GlyphCache<'static>| {
+                    for glyph in glyphs.values() {
+                        cache.queue_glyph(cache_id, glyph.clone());
+                    }
+                };
+                match cache_glyphs(glyph_store, geometry, &queue) {
+                    Ok(grew) => glyph_cache_grew |= grew,
+                    Err(_) => {
+                        glyph_store.cache().clear_queue();
+                        continue;
+                    }
+                }
+                let rects: std::collections::HashMap<_, _> = glyphs
+                    .iter()
+                    .filter_map(|(&ch, glyph)| {
+                        match glyph_store.cache().rect_for(cache_id, glyph) {
+                            Ok(Some(rects)) => Some((ch, rects)),
+                            _ => None,
+                        }
+                    })
+                    .collect();
+
+                for (i, cell) in grid.cells.iter().enumerate() {
+                    let (glyph, uv_rect, offset) = match (glyphs.get(&cell.ch), rects.get(&cell.ch))
+                    {
+                        (Some(glyph), Some(&(uv_rect, offset))) => (glyph, uv_rect, offset),
+                        _ => continue,
+                    };
+                    let (col, row) = ((i % grid.cols) as i32, (i / grid.cols) as i32);
+                    let origin = text::rt::vector(left + col * cell_w, top + row * cell_h);
+                    let screen_rect = text::rt::Rect {
+                        min: offset.min + origin,
+                        max: offset.max + origin,
+                    };
+                    let color = gamma_srgb_to_linear(cell.color.to_fsa());
+                    let glyph = (cache_id, glyph);
+                    push_glyph(
+                        geometry,
+                        glyph,
+                        uv_rect,
+                        screen_rect,
+                        color,
+                        (screen_w, screen_h),
+                    );
+                }
+            }
+        }
+    }
+
+    // Enter the final command.
+    match current_state {
+        State::Plain { start } => commands.push(PreparedCommand::Plain(start..geometry.len())),
+        State::Image { image_id, start } => {
+            commands.push(PreparedCommand::Image(image_id, start..geometry.len()))
+        }
+    }
+    glyph_cache_grew
+}
+
+/// Translate the shapes of the debug layer into commands above those already in `commands`,
+/// with the text in `font`. Returns whether the glyph cache grew.
+fn tessellate_debug<D: Display>(
+    display: &D,
+    shapes: &[debug_draw::Shape],
+    font: Option<(text::font::Id, &text::Font)>,
+    glyph_store: &mut dyn GlyphStore,
+    commands: &mut Vec<PreparedCommand>,
+    geometry: &mut Geometry,
+) -> bool {
+    let (screen_w, screen_h) = display.framebuffer_dimensions();
+    if shapes.is_empty() || screen_w == 0 || screen_h == 0 {
+        return false;
+    }
+    let mut glyph_cache_grew = false;
+    let half_win_w = screen_w as Scalar / 2.0;
+    let half_win_h = screen_h as Scalar / 2.0;
+    let dpi_factor = display.hidpi_factor() as Scalar;
+    let vx = |x: Scalar| (x * dpi_factor / half_win_w) as f32;
+    let vy = |y: Scalar| (y * dpi_factor / half_win_h) as f32;
+
+    // The whole framebuffer, whatever the last primitive was clipped to.
+    let mut clips = ClipStack::new(GlRect::full((screen_w, screen_h)));
+    commands.push(PreparedCommand::Scizzor(clips.current()));
+    let mut start = geometry.len();
+
+    // Finishes the shapes drawn so far, to draw the next ones clipped to `clip`.
+    macro_rules! switch_clip {
+        ($clip:expr) => {{
+            let clip = $clip;
+            commands.push(PreparedCommand::Plain(start..geometry.len()));
+            commands.push(PreparedCommand::Scizzor(clip));
+            start = geometry.len();
+        }};
+    }
+
+    for shape in shapes {
+        match shape {
+            debug_draw::Shape::PushClip(rect) => {
+                let rect = GlRect::from_rect(*rect, dpi_factor, (screen_w, screen_h));
+                switch_clip!(clips.push_clip(rect));
+            }
+            debug_draw::Shape::PopClip => switch_clip!(clips.pop_clip()),
+            debug_draw::Shape::Rect { rect, color } => {
+                let color = gamma_srgb_to_linear(color.to_fsa());
+                let v = |x, y| Vertex {
+                    position: [vx(x), vy(y)],
+                    tex_coords: [0.0, 0.0],
+                    color,
+                    mode: MODE_GEOMETRY,
+                };
+                let (l, r, b, t) = rect.l_r_b_t();
+                geometry.push_quad([v(l, t), v(l, b), v(r, b), v(r, t)]);
+            }
+            debug_draw::Shape::Line { start, end, color } => {
+                let (dx, dy) = (end[0] - start[0], end[1] - start[1]);
+                let len = (dx * dx + dy * dy).sqrt();
+                if !(len > 0.0) {
+                    continue;
+                }
+                let color = gamma_srgb_to_linear(color.to_fsa());
+                // Half a point to each side.
+                let (nx, ny) = (-dy / len * 0.5, dx / len * 0.5);
+                let v = |p: &[Scalar; 2], side: Scalar| Vertex {
+                    position: [vx(p[0] + nx * side), vy(p[1] + ny * side)],
+                    tex_coords: [0.0, 0.0],
+                    color,
+                    mode: MODE_GEOMETRY,
+                };
+                geometry.push_quad([v(start, 1.0), v(start, -1.0), v(end, -1.0), v(end, 1.0)]);
+            }
+            debug_draw::Shape::Text {
+                position,
+                text,
+                color,
+            } => {
+                let (font_id, font) = match font {
+                    Some(font) => font,
+                    None => continue,
+                };
+                let scale = text::f32_pt_to_scale(12.0 * dpi_factor as f32);
+                let v_metrics = font.v_metrics(scale);
+                let line_height = v_metrics.ascent - v_metrics.descent + v_metrics.line_gap;
+                // In pixels from the top left of the framebuffer, as rusttype lays them out.
+                let left = (position[0] * dpi_factor + half_win_w) as f32;
+                let top = (half_win_h - position[1] * dpi_factor) as f32;
+                let cache_id = font_id.index();
+                let mut glyphs = Vec::new();
+                for (i, line) in text.lines().enumerate() {
+                    let baseline = top + v_metrics.ascent + i as f32 * line_height;
+                    glyphs.extend(font.layout(line, scale, text::rt::point(left, baseline)));
+                }
+                let queue = |cache: &mut text::GlyphCache<'static>| {
+                    for glyph in &glyphs {
+                        cache.queue_glyph(cache_id, glyph.clone());
+                    }
+                };
+                match cache_glyphs(glyph_store, geometry, &queue) {
+                    Ok(grew) => glyph_cache_grew |= grew,
+                    Err(_) => {
+                        glyph_store.cache().clear_queue();
+                        continue;
+                    }
+                }
+                let color = gamma_srgb_to_linear(color.to_fsa());
+                for glyph in &glyphs {
+                    if let Ok(Some((uv_rect, screen_rect))) =
+                        glyph_store.cache().rect_for(cache_id, glyph)
+                    {
+                        let glyph = (cache_id, glyph);
+                        push_glyph(
+                            geometry,
+                            glyph,
+                            uv_rect,
+                            screen_rect,
+                            color,
+                            (screen_w, screen_h),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    commands.push(PreparedCommand::Plain(start..geometry.len()));
+    glyph_cache_grew
+}
+
+/// The vertices to draw, and the triangles as indices into them. The ranges of the commands are
+/// of indices.
+#[derive(Clone, Debug, Default)]
+struct Geometry {
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+    /// The glyph of each glyph quad, to point the quads at the glyph cache again when it grows.
+    glyphs: Vec<PlacedGlyph>,
+}
+
+/// A glyph drawn by the quad of four vertices from `first_vertex`.
+#[derive(Clone)]
+struct PlacedGlyph {
+    /// The font of the glyph in the glyph cache.
+    cache_id: usize,
+    glyph: text::PositionedGlyph,
+    first_vertex: usize,
+}
+
+impl std::fmt::Debug for PlacedGlyph {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("PlacedGlyph")
+            .field("cache_id", &self.cache_id)
+            .field("glyph", &self.glyph.id())
+            .field("first_vertex", &self.first_vertex)
+            .finish()
+    }
+}
+
+impl Geometry {
+    /// Each triangle of `vertices` in turn, as in a `Frame`.
+    fn from_triangles(vertices: Vec<Vertex>) -> Self {
+        let indices = (0..vertices.len() as u32).collect();
+        Geometry {
+            vertices,
+            indices,
+            glyphs: Vec::new(),
+        }
+    }
+
+    /// The number of indices, where the range of the next command starts.
+    fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    fn clear(&mut self) {
+        self.vertices.clear();
+        self.indices.clear();
+        self.glyphs.clear();
+    }
+
+    /// Point the glyph quads at where `cache` has their glyphs now, and hide those it doesn't
+    /// have.
+    fn repoint_glyphs(&mut self, cache: &text::GlyphCache<'static>) {
+        let Geometry {
+            vertices, glyphs, ..
+        } = self;
+        for placed in glyphs.iter() {
+            let quad = &mut vertices[placed.first_vertex..placed.first_vertex + 4];
+            match cache.rect_for(placed.cache_id, &placed.glyph) {
+                Ok(Some((uv_rect, _))) => {
+                    for (vertex, &tex_coords) in quad.iter_mut().zip(&glyph_tex_coords(uv_rect)) {
+                        vertex.tex_coords = tex_coords;
+                    }
+                }
+                _ => {
+                    for vertex in quad {
+                        vertex.color[3] = 0.0;
+                    }
+                }
+            }
+        }
+    }
+
+    fn push_triangle(&mut self, triangle: [Vertex; 3]) {
+        let first = self.vertices.len() as u32;
+        self.vertices.extend_from_slice(&triangle);
+        self.indices
+            .extend_from_slice(&[first, first + 1, first + 2]);
+    }
+
+    /// Push a quad with its corners in order around it, as two triangles sharing the diagonal
+    /// from the first corner to the third.
+    fn push_quad(&mut self, corners: [Vertex; 4]) {
+        let first = self.vertices.len() as u32;
+        self.vertices.extend_from_slice(&corners);
+        self.indices
+            .extend_from_slice(&[first, first + 1, first + 2, first, first + 2, first + 3]);
+    }
+
+    /// The vertices of each triangle in turn, without indices.
+    fn triangles(&self) -> Vec<Vertex> {
+        self.indices
+            .iter()
+            .map(|&index| self.vertices[index as usize])
+            .collect()
+    }
+}
+
+/// The texture coordinates of the corners of a glyph quad, in the order `push_glyph` pushes
+/// them, for the glyph at `uv_rect` in the cache texture.
+fn glyph_tex_coords(uv_rect: text::rt::Rect<f32>) -> [[f32; 2]; 4] {
+    [
+        [uv_rect.min.x, uv_rect.max.y],
+        [uv_rect.min.x, uv_rect.min.y],
+        [uv_rect.max.x, uv_rect.min.y],
+        [uv_rect.max.x, uv_rect.max.y],
+    ]
+}
+
+/// Cache the glyphs which `queue` queues, for a primitive about to be pushed to `geometry`.
+///
+/// Growing the cache forgets the glyphs cached before, so the glyphs of the quads already in
+/// `geometry` are then queued again, along with those of the primitive if they fit, and the quads
+/// are pointed at where they are now, instead of drawing whatever took their place. The cache
+/// may grow even when the glyphs of the primitive don't fit in the largest one. Returns whether
+/// the cache grew.
+fn cache_glyphs(
+    glyph_store: &mut dyn GlyphStore,
+    geometry: &mut Geometry,
+    queue: &dyn Fn(&mut text::GlyphCache<'static>),
+) -> Result<bool, text::rt::gpu_cache::CacheWriteErr> {
+    let size = glyph_store.cache().dimensions();
+    queue(glyph_store.cache());
+    let result = glyph_store.cache_queued();
+    if glyph_store.cache().dimensions() == size {
+        return result;
+    }
+    if result.is_err() {
+        glyph_store.cache().clear_queue();
+    }
+    for placed in &geometry.glyphs {
+        glyph_store
+            .cache()
+            .queue_glyph(placed.cache_id, placed.glyph.clone());
+    }
+    if result.is_ok() {
+        queue(glyph_store.cache());
+    }
+    // Growing again caches everything queued in the bigger cache.
+    let recached = glyph_store.cache_queued();
+    geometry.repoint_glyphs(glyph_store.cache());
+    result.and(recached).map(|_| true)
+}
+
+/// Push the two triangles of a cached glyph, given the font of `glyph` in the cache, and its
+/// rectangle in the cache texture and in screen pixels with the origin at the top left.
+fn push_glyph(
+    geometry: &mut Geometry,
+    (cache_id, glyph): (usize, &text::PositionedGlyph),
+    uv_rect: text::rt::Rect<f32>,
+    screen_rect: text::rt::Rect<i32>,
+    color: [f32; 4],
+    (screen_w, screen_h): (u32, u32),
+) {
+    let gl_x = |x: i32| (x as f32 / screen_w as f32 - 0.5) * 2.0;
+    let gl_y = |y: i32| (1.0 - y as f32 / screen_h as f32 - 0.5) * 2.0;
+    let (l, r) = (gl_x(screen_rect.min.x), gl_x(screen_rect.max.x));
+    let (t, b) = (gl_y(screen_rect.min.y), gl_y(screen_rect.max.y));
+    let v = |p, t| Vertex {
+        position: p,
+        tex_coords: t,
+        color: color,
+        mode: MODE_TEXT,
+    };
+    let [lb, lt, rt, rb] = glyph_tex_coords(uv_rect);
+    geometry.glyphs.push(PlacedGlyph {
+        cache_id,
+        glyph: glyph.clone(),
+        first_vertex: geometry.vertices.len(),
+    });
+    geometry.push_quad([v([l, b], lb), v([l, t], lt), v([r, t], rt), v([r, b], rb)]);
+}
+
+/// The `Frame` of `commands` and `geometry`, for `Renderer::frame` and `Mesh::frame`.
+fn frame_of(
+    commands: &[PreparedCommand],
+    geometry: &Geometry,
+    framebuffer: (u32, u32),
+    images: &[image::Id],
+) -> Frame {
+    let commands = commands
+        .iter()
+        .filter_map(|command| match command {
+            PreparedCommand::Image(id, range) => images
+                .iter()
+                .position(|image| image == id)
+                .map(|index| FrameCommand::Image(index as u32, range.clone())),
+            PreparedCommand::Plain(range) => Some(FrameCommand::Plain(range.clone())),
+            PreparedCommand::Scizzor(rect) => Some(FrameCommand::Scissor {
+                left: rect.left,
+                bottom: rect.bottom,
+                width: rect.width,
+                height: rect.height,
+            }),
+        })
+        .collect();
+    Frame {
+        framebuffer,
+        commands,
+        vertices: geometry.triangles(),
+    }
+}
+
+pub trait Display {
+    fn framebuffer_dimensions(&self) -> (u32, u32);
+    fn hidpi_factor(&self) -> f64;
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<T> Display for glutin::WindowedContext<T>
+where
+    T: glutin::ContextCurrentState,
+{
+    fn framebuffer_dimensions(&self) -> (u32, u32) {
+        (
+            self.window().inner_size().width,
+            self.window().inner_size().height,
+        )
+    }
+
+    fn hidpi_factor(&self) -> f64 {
+        self.window().scale_factor()
+    }
+}
+
+impl Display for (u32, u32, f64) {
+    fn framebuffer_dimensions(&self) -> (u32, u32) {
+        (self.0, self.1)
+    }
+
+    fn hidpi_factor(&self) -> f64 {
+        self.2
+    }
+}
+
+/// How a texture is sampled when drawn at another size.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextureFilter {
+    Nearest,
+    Linear,
+}
+
+/// How `Texture::from_rgba8` and the like create a texture.
+#[derive(Clone, Copy, Debug)]
+pub struct TextureOptions {
+    /// Take the color channels as sRGB.
+    pub is_srgb: bool,
+    pub filter: TextureFilter,
+    /// Generate mipmaps, for images mostly drawn smaller than they are.
+    pub mipmaps: bool,
+}
+
+impl Default for TextureOptions {
+    fn default() -> Self {
+        TextureOptions {
+            is_srgb: false,
+            filter: TextureFilter::Nearest,
+            mipmaps: false,
+        }
+    }
+}
+
+/// The layout of the pixels given to `Texture::from_pixels`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// One byte per pixel, drawn as white with the byte as the alpha, for
+    /// masks.
+    R8,
+    /// Gray and alpha. The gray is taken as linear, as there is no sRGB
+    /// format with two channels.
+    Rg8,
+    Rgba8,
+    /// The byte order of most platform frame sources.
+    Bgra8,
+}
+
+impl PixelFormat {
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            PixelFormat::R8 => 1,
+            PixelFormat::Rg8 => 2,
+            PixelFormat::Rgba8 | PixelFormat::Bgra8 => 4,
+        }
+    }
+
+    fn data_format(self) -> u32 {
+        match self {
+            PixelFormat::R8 => glow::RED,
+            PixelFormat::Rg8 => glow::RG,
+            PixelFormat::Rgba8 => glow::RGBA,
+            PixelFormat::Bgra8 => glow::BGRA,
+        }
+    }
+
+    /// Convert `data` to RGBA, drawing the same as it would with swizzling.
+    fn to_rgba(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            PixelFormat::R8 => data.iter().flat_map(|&a| vec![255, 255, 255, a]).collect(),
+            PixelFormat::Rg8 => data
+                .chunks(2)
+                .flat_map(|p| vec![p[0], p[0], p[0], p[1]])
+                .collect(),
+            PixelFormat::Rgba8 => data.to_vec(),
+            PixelFormat::Bgra8 => data
+                .chunks(4)
+                .flat_map(|p| vec![p[2], p[1], p[0], p[3]])
+                .collect(),
+        }
+    }
+}
+
+/// Check that the `width` by `height` region at `x`, `y` is inside of a
+/// texture of `size`.
+fn check_region(x: u32, y: u32, width: u32, height: u32, size: (u32, u32)) -> Result<(), String> {
+    let (texture_w, texture_h) = size;
+    // In u64, so that the sums can't wrap around to inside of the texture.
+    if u64::from(x) + u64::from(width) > u64::from(texture_w)
+        || u64::from(y) + u64::from(height) > u64::from(texture_h)
+    {
+        return Err(format!(
+            "region {}x{} at ({}, {}) is outside of the {}x{} texture",
+            width, height, x, y, texture_w, texture_h
+        ));
+    }
+    Ok(())
+}
+
+/// Check the size of `data`, then flip its rows as OpenGL takes the bottom
+/// row first, and convert it if the platform can't take `format` as is.
+fn prepare_pixels(
+    format: PixelFormat,
+    width: u32,
+    height: u32,
+    data: &[u8],
+) -> Result<(PixelFormat, Vec<u8>), String> {
+    let row_len = (width as usize).checked_mul(format.bytes_per_pixel());
+    let len = row_len.and_then(|row_len| row_len.checked_mul(height as usize));
+    let row_len = match (row_len, len) {
+        (Some(row_len), Some(len)) if data.len() == len => row_len,
+        _ => {
+            return Err(format!(
+                "expected {}x{} {:?} pixels, got {} bytes",
+                width,
+                height,
+                format,
+                data.len()
+            ))
+        }
+    };
+    let flipped: Vec<u8> = data
+        .chunks(row_len.max(1))
+        .rev()
+        .flat_map(|row| row.iter())
+        .copied()
+        .collect();
+    if cfg!(target_arch = "wasm32") && format != PixelFormat::Rgba8 {
+        Ok((PixelFormat::Rgba8, format.to_rgba(&flipped)))
+    } else {
+        Ok((format, flipped))
+    }
+}
+
+impl Texture {
+    /// Upload `data` in `format`, with the rows from top to bottom, to a new
+    /// texture for the `image::Map`. The color channels are taken as sRGB if
+    /// `is_srgb`.
+    ///
+    /// On desktop GL the data is uploaded as is and swizzled to RGBA by the
+    /// GPU. WebGL 2 has neither swizzles nor BGRA, so the data is converted
+    /// to RGBA first there.
+    pub fn from_pixels(
+        gl: &glow::Context,
+        width: u32,
+        height: u32,
+        format: PixelFormat,
+        data: &[u8],
+        is_srgb: bool,
+    ) -> Result<Self, String> {
+        let options = TextureOptions {
+            is_srgb,
+            ..TextureOptions::default()
+        };
+        Self::from_pixels_with(gl, width, height, format, data, options)
+    }
+
+    /// Upload RGBA `data`, with the rows from top to bottom, to a new texture.
+    pub fn from_rgba8(
+        gl: &glow::Context,
+        width: u32,
+        height: u32,
+        data: &[u8],
+        options: TextureOptions,
+    ) -> Result<Self, String> {
+        Self::from_pixels_with(gl, width, height, PixelFormat::Rgba8, data, options)
+    }
+
+    /// Upload an image decoded by the `image` crate to a new texture, as RGBA.
+    pub fn from_image(
+        gl: &glow::Context,
+        image: &::image::DynamicImage,
+        options: TextureOptions,
+    ) -> Result<Self, String> {
+        let rgba = image.to_rgba();
+        let (width, height) = rgba.dimensions();
+        Self::from_rgba8(gl, width, height, &rgba.into_raw(), options)
+    }
+
+    /// Like `from_pixels`, with the filter and the mipmaps of `options`.
+    pub fn from_pixels_with(
+        gl: &glow::Context,
+        width: u32,
+        height: u32,
+        format: PixelFormat,
+        data: &[u8],
+        options: TextureOptions,
+    ) -> Result<Self, String> {
+        let (upload_format, pixels) = prepare_pixels(format, width, height, data)?;
+        let rgba_format = if options.is_srgb {
+            glow::SRGB8_ALPHA8
+        } else {
+            glow::RGBA8
+        };
+        let (internal_format, swizzle) = match upload_format {
+            PixelFormat::R8 => (glow::R8, Some([glow::ONE, glow::ONE, glow::ONE, glow::RED])),
+            PixelFormat::Rg8 => (
+                glow::RG8,
+                Some([glow::RED, glow::RED, glow::RED, glow::GREEN]),
+            ),
+            PixelFormat::Rgba8 | PixelFormat::Bgra8 => (rgba_format, None),
+        };
+
+        unsafe {
+            let texture = gl.create_texture()?;
+            gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+            gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
+            gl.tex_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                internal_format as i32,
+                width as i32,
+                height as i32,
+                0,
+                upload_format.data_format(),
+                glow::UNSIGNED_BYTE,
+                Some(&pixels),
+            );
+            gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 4);
+            if let Some(swizzle) = swizzle {
+                let params = [
+                    glow::TEXTURE_SWIZZLE_R,
+                    glow::TEXTURE_SWIZZLE_G,
+                    glow::TEXTURE_SWIZZLE_B,
+                    glow::TEXTURE_SWIZZLE_A,
+                ];
+                for (&param, &source) in params.iter().zip(swizzle.iter()) {
+                    gl.tex_parameter_i32(glow::TEXTURE_2D, param, source as i32);
+                }
+            }
+            let (mag_filter, min_filter) = match (options.filter, options.mipmaps) {
+                (TextureFilter::Nearest, false) => (glow::NEAREST, glow::NEAREST),
+                (TextureFilter::Nearest, true) => (glow::NEAREST, glow::NEAREST_MIPMAP_NEAREST),
+                (TextureFilter::Linear, false) => (glow::LINEAR, glow::LINEAR),
+                (TextureFilter::Linear, true) => (glow::LINEAR, glow::LINEAR_MIPMAP_LINEAR),
+            };
+            gl.tex_parameter_i32(
+                glow::TEXTURE_2D,
+                glow::TEXTURE_MAG_FILTER,
+                mag_filter as i32,
+            );
+            gl.tex_parameter_i32(
+                glow::TEXTURE_2D,
+                glow::TEXTURE_MIN_FILTER,
+                min_filter as i32,
+            );
+            if options.mipmaps {
+                gl.generate_mipmap(glow::TEXTURE_2D);
+            }
+            gl.bind_texture(glow::TEXTURE_2D, None);
+
+            Ok(Texture {
+                texture,
+                width,
+                height,
+                format,
+                mipmaps: options.mipmaps,
+            })
+        }
+    }
+
+    /// Replace the `width` by `height` pixels at `x`, `y` from the top left
+    /// with `data`, in the format the texture was created with, without
+    /// uploading the rest of the texture again.
+    pub fn update_region(
+        &self,
+        gl: &glow::Context,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        data: &[u8],
+    ) -> Result<(), String> {
+        check_region(x, y, width, height, (self.width, self.height))?;
+        let (upload_format, pixels) = prepare_pixels(self.format, width, height, data)?;
+        unsafe {
+            gl.bind_texture(glow::TEXTURE_2D, Some(self.texture));
+            gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
+            gl.tex_sub_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                x as i32,
+                (self.height - y - height) as i32,
+                width as i32,
+                height as i32,
+                upload_format.data_format(),
+                glow::UNSIGNED_BYTE,
+                glow::PixelUnpackData::Slice(&pixels),
+            );
+            gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 4);
+            if self.mipmaps {
+                gl.generate_mipmap(glow::TEXTURE_2D);
+            }
+            gl.bind_texture(glow::TEXTURE_2D, None);
+        }
+        Ok(())
+    }
+
+    /// Delete the texture, after removing it from the `image::Map`.
+    pub fn delete(self, gl: &glow::Context) {
+        unsafe { gl.delete_texture(self.texture) };
+    }
+}
+
+/// A framebuffer object with a texture as its color attachment, used to
+/// render offscreen.
+pub struct OffscreenTarget {
+    framebuffer: glow::Framebuffer,
+    texture: glow::Texture,
+    width: u32,
+    height: u32,
+    is_srgb: bool,
+}
+
+impl OffscreenTarget {
+    /// Create a new offscreen target of the given size.
+    ///
+    /// If `is_srgb` is `true` the color attachment uses an sRGB internal
+    /// format, matching a default framebuffer with sRGB conversion enabled.
+    pub fn new(gl: &glow::Context, width: u32, height: u32, is_srgb: bool) -> Result<Self, String> {
+        let internal_format = if is_srgb {
+            glow::SRGB8_ALPHA8
+        } else {
+            glow::RGBA8
+        };
+        unsafe {
+            let texture = gl.create_texture()?;
+            gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+            gl.tex_parameter_i32(
+                glow::TEXTURE_2D,
+                glow::TEXTURE_MAG_FILTER,
+                glow::LINEAR as i32,
+            );
+            gl.tex_parameter_i32(
+                glow::TEXTURE_2D,
+                glow::TEXTURE_MIN_FILTER,
+                glow::LINEAR as i32,
+            );
+            gl.tex_storage_2d(
+                glow::TEXTURE_2D,
+                1,
+                internal_format,
+                width as i32,
+                height as i32,
+            );
+            gl.bind_texture(glow::TEXTURE_2D, None);
+
+            let framebuffer = match gl.create_framebuffer() {
+                Ok(framebuffer) => framebuffer,
+                Err(err) => {
+                    gl.delete_texture(texture);
+                    return Err(err);
+                }
+            };
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(framebuffer));
+            gl.framebuffer_texture_2d(
+                glow::FRAMEBUFFER,
+                glow::COLOR_ATTACHMENT0,
+                glow::TEXTURE_2D,
+                Some(texture),
+                0,
+            );
+            let status = gl.check_framebuffer_status(glow::FRAMEBUFFER);
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+            if status != glow::FRAMEBUFFER_COMPLETE {
+                gl.delete_framebuffer(framebuffer);
+                gl.delete_texture(texture);
+                return Err(format!("framebuffer incomplete: {:#x}", status));
+            }
+
+            Ok(OffscreenTarget {
+                framebuffer,
+                texture,
+                width,
+                height,
+                is_srgb,
+            })
+        }
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// The texture holding the rendered contents.
+    pub fn texture(&self) -> glow::Texture {
+        self.texture
+    }
+
+    /// The framebuffer object, for `Renderer::draw_to_framebuffer`.
+    pub fn framebuffer(&self) -> glow::Framebuffer {
+        self.framebuffer
+    }
+
+    /// Bind the target for drawing and set the viewport to cover it.
+    ///
+    /// Bind `None` to `glow::FRAMEBUFFER` to go back to the default
+    /// framebuffer.
+    pub fn bind(&self, gl: &glow::Context) {
+        unsafe {
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(self.framebuffer));
+            gl.viewport(0, 0, self.width as i32, self.height as i32);
+        }
+    }
+
+    /// Read back the contents as tightly packed RGBA rows, top row first.
+    pub fn read_pixels(&self, gl: &glow::Context) -> Vec<u8> {
+        read_framebuffer_pixels(gl, Some(self.framebuffer), self.width, self.height)
+    }
+
+    /// Delete the GL objects.
+    pub fn delete(self, gl: &glow::Context) {
+        unsafe {
+            gl.delete_framebuffer(self.framebuffer);
+            gl.delete_texture(self.texture);
+        }
+    }
+}
+
+/// A framebuffer object with a multisampled renderbuffer as its color
+/// attachment, to draw with multisample antialiasing whatever the window was
+/// made with. What is drawn to it is resolved into another framebuffer with
+/// `resolve`.
+pub struct MsaaTarget {
+    framebuffer: glow::Framebuffer,
+    renderbuffer: glow::Renderbuffer,
+    width: u32,
+    height: u32,
+    samples: u32,
+    is_srgb: bool,
+}
+
+impl MsaaTarget {
+    /// The most samples the GL supports, which `new` limits `samples` to.
+    pub fn max_samples(gl: &glow::Context) -> u32 {
+        unsafe { gl.get_parameter_i32(glow::MAX_SAMPLES) }.max(0) as u32
+    }
+
+    /// Create a target of the given size with up to `samples` samples.
+    ///
+    /// `is_srgb` has the same meaning as for `OffscreenTarget::new`.
+    pub fn new(
+        gl: &glow::Context,
+        width: u32,
+        height: u32,
+        samples: u32,
+        is_srgb: bool,
+    ) -> Result<Self, String> {
+        let samples = samples.min(Self::max_samples(gl));
+        let internal_format = if is_srgb {
+            glow::SRGB8_ALPHA8
+        } else {
+            glow::RGBA8
+        };
+        unsafe {
+            let renderbuffer = gl.create_renderbuffer()?;
+            gl.bind_renderbuffer(glow::RENDERBUFFER, Some(renderbuffer));
+            gl.renderbuffer_storage_multisample(
+                glow::RENDERBUFFER,
+                samples as i32,
+                internal_format,
+                width as i32,
+                height as i32,
+            );
+            gl.bind_renderbuffer(glow::RENDERBUFFER, None);
+
+            let framebuffer = match gl.create_framebuffer() {
+                Ok(framebuffer) => framebuffer,
+                Err(err) => {
+                    gl.delete_renderbuffer(renderbuffer);
+                    return Err(err);
+                }
+            };
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(framebuffer));
+            gl.framebuffer_renderbuffer(
+                glow::FRAMEBUFFER,
+                glow::COLOR_ATTACHMENT0,
+                glow::RENDERBUFFER,
+                Some(renderbuffer),
+            );
+            let status = gl.check_framebuffer_status(glow::FRAMEBUFFER);
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+            if status != glow::FRAMEBUFFER_COMPLETE {
+                gl.delete_framebuffer(framebuffer);
+                gl.delete_renderbuffer(renderbuffer);
+                return Err(format!(
+                    "multisampled framebuffer incomplete: {:#x}",
+                    status
+                ));
+            }
+
+            Ok(MsaaTarget {
+                framebuffer,
+                renderbuffer,
+                width,
+                height,
+                samples,
+                is_srgb,
+            })
+        }
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// The samples per pixel, which may be fewer than were asked for.
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+
+    pub fn is_srgb(&self) -> bool {
+        self.is_srgb
+    }
+
+    /// Bind the target for drawing and set the viewport to cover it.
+    pub fn bind(&self, gl: &glow::Context) {
+        unsafe {
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(self.framebuffer));
+            gl.viewport(0, 0, self.width as i32, self.height as i32);
+        }
+    }
+
+    /// Average the samples into `framebuffer` of the same size, or the default
+    /// framebuffer for `None`, and leave that bound with the viewport covering
+    /// it.
+    pub fn resolve(&self, gl: &glow::Context, framebuffer: Option<glow::Framebuffer>) {
+        let (w, h) = (self.width as i32, self.height as i32);
+        unsafe {
+            gl.bind_framebuffer(glow::READ_FRAMEBUFFER, Some(self.framebuffer));
+            gl.bind_framebuffer(glow::DRAW_FRAMEBUFFER, framebuffer);
+            gl.blit_framebuffer(
+                0,
+                0,
+                w,
+                h,
+                0,
+                0,
+                w,
+                h,
+                glow::COLOR_BUFFER_BIT,
+                glow::NEAREST,
+            );
+            gl.bind_framebuffer(glow::FRAMEBUFFER, framebuffer);
+            gl.viewport(0, 0, w, h);
+        }
+    }
+
+    /// Delete the GL objects.
+    pub fn delete(self, gl: &glow::Context) {
+        unsafe {
+            gl.delete_framebuffer(self.framebuffer);
+            gl.delete_renderbuffer(self.renderbuffer);
+        }
+    }
+}
+
+/// Offscreen targets which are done with, kept to be used again for the next
+/// target of the same size and format instead of creating a new one each
+/// frame.
+pub struct RenderTargetPool {
+    free: Vec<OffscreenTarget>,
+    /// The most targets kept. The oldest go first.
+    capacity: usize,
+}
+
+impl RenderTargetPool {
+    pub fn new(capacity: usize) -> Self {
+        RenderTargetPool {
+            free: Vec::new(),
+            capacity,
+        }
+    }
+
+    /// A target of the given size and format, reused from the pool if there
+    /// is one. A reused target still has what was drawn to it before, so
+    /// clear it first.
+    pub fn take(
+        &mut self,
+        gl: &glow::Context,
+        width: u32,
+        height: u32,
+        is_srgb: bool,
+    ) -> Result<OffscreenTarget, String> {
+        let found = self.free.iter().rposition(|target| {
+            target.dimensions() == (width, height) && target.is_srgb == is_srgb
+        });
+        match found {
+            Some(i) => Ok(self.free.remove(i)),
+            None => OffscreenTarget::new(gl, width, height, is_srgb),
+        }
+    }
+
+    /// Keep `target` for `take`, deleting the oldest kept target if the pool
+    /// is full.
+    pub fn give_back(&mut self, gl: &glow::Context, target: OffscreenTarget) {
+        self.free.push(target);
+        if self.free.len() > self.capacity {
+            self.free.remove(0).delete(gl);
+        }
+    }
+
+    /// Delete all the kept targets, such as when memory is low or the sizes
+    /// which are needed change.
+    pub fn trim(&mut self, gl: &glow::Context) {
+        for target in self.free.drain(..) {
+            target.delete(gl);
+        }
+    }
+
+    /// Roughly how many bytes of GPU memory the kept targets use.
+    pub fn bytes(&self) -> u64 {
+        self.free
+            .iter()
+            .map(|target| target.width as u64 * target.height as u64 * 4)
+            .sum()
+    }
+}
+
+/// The vertex shader of `ColorMatrixPass`, drawing a quad over the whole
+/// target.
+const COLOR_MATRIX_VERTEX_SHADER: &str = "
+    IN_ATTRIBUTE vec2 position;
+    OUT_VARYING vec2 v_tex_coords;
+
+    void main() {
+        gl_Position = vec4(position, 0.0, 1.0);
+        v_tex_coords = position * 0.5 + 0.5;
+    }
+";
+
+/// The fragment shader of `ColorMatrixPass`, after the definitions of
+/// `MATRIX` and `ENCODED`.
+const COLOR_MATRIX_FRAGMENT_SHADER: &str = "
+    uniform sampler2D tex;
+    IN_VARYING vec2 v_tex_coords;
+
+    vec3 toLinear(vec3 srgb) {
+        vec3 cutoff = vec3(1.0) - step(vec3(0.04045), srgb);
+        vec3 higher = pow((srgb + vec3(0.055)) / vec3(1.055), vec3(2.4));
+        vec3 lower = srgb / vec3(12.92);
+        return mix(higher, lower, cutoff);
+    }
+
+    vec3 toSrgb(vec3 linearRgb) {
+        vec3 cutoff = vec3(1.0) - step(vec3(0.0031308), linearRgb);
+        vec3 higher = vec3(1.055) * pow(linearRgb, vec3(1.0 / 2.4)) - vec3(0.055);
+        vec3 lower = linearRgb * vec3(12.92);
+        return mix(higher, lower, cutoff);
+    }
+
+    void main() {
+        vec4 color = TEXTURE(tex, v_tex_coords);
+        vec3 rgb = ENCODED ? toLinear(color.rgb) : color.rgb;
+        rgb = clamp(MATRIX * rgb, 0.0, 1.0);
+        FRAG_COLOR = vec4(ENCODED ? toSrgb(rgb) : rgb, color.a);
+    }
+";
+
+/// A post-process which draws a texture, such as an `OffscreenTarget` the
+/// UI was drawn to, over the whole bound framebuffer through a 3x3 matrix
+/// applied to the linear RGB colors.
+pub struct ColorMatrixPass {
+    program: glow::Program,
+    vbo: glow::Buffer,
+    vao: glow::VertexArray,
+}
+
+impl ColorMatrixPass {
+    /// Build the pass for `matrix`, given row by row, in the GLSL version of
+    /// `info.shader`, which also tells whether the colors drawn by the
+    /// renderer are still encoded as sRGB in the texture.
+    pub fn new(gl: &glow::Context, info: &RendererInfo, matrix: [f32; 9]) -> Result<Self, String> {
+        let header = info.shader.color_matrix_header();
+        // GLSL takes the matrix column by column.
+        let m = matrix;
+        let definitions = format!(
+            "const mat3 MATRIX = mat3({:?}, {:?}, {:?}, {:?}, {:?}, {:?}, {:?}, {:?}, {:?});
+            const bool ENCODED = {};
+            ",
+            m[0],
+            m[3],
+            m[6],
+            m[1],
+            m[4],
+            m[7],
+            m[2],
+            m[5],
+            m[8],
+            info.shader.converts_to_srgb(),
+        );
+        let vs = format!("{}{}", header, COLOR_MATRIX_VERTEX_SHADER);
+        let fs = format!("{}{}{}", header, definitions, COLOR_MATRIX_FRAGMENT_SHADER);
+        let (program, vbo, vao) = full_quad_program(gl, &vs, &fs)?;
+        Ok(ColorMatrixPass { program, vbo, vao })
+    }
+
+    /// Draw `texture` over the bound framebuffer, replacing what is there.
+    pub fn draw(&self, gl: &glow::Context, texture: glow::Texture) {
+        unsafe {
+            gl.disable(glow::BLEND);
+            gl.use_program(Some(self.program));
+            gl.bind_vertex_array(Some(self.vao));
+            gl.active_texture(glow::TEXTURE0);
+            gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+            gl.draw_arrays(glow::TRIANGLES, 0, 6);
+            gl.bind_vertex_array(None);
+            gl.enable(glow::BLEND);
+        }
+    }
+
+    /// Delete the GL objects.
+    pub fn delete(self, gl: &glow::Context) {
+        unsafe {
+            gl.delete_vertex_array(self.vao);
+            gl.delete_buffer(self.vbo);
+            gl.delete_program(self.program);
+        }
+    }
+}
+
+/// Link a program whose vertex shader takes the `position` of two triangles
+/// covering the whole target, and fill the buffer and vertex array of those.
+fn full_quad_program(
+    gl: &glow::Context,
+    vs: &str,
+    fs: &str,
+) -> Result<(glow::Program, glow::Buffer, glow::VertexArray), String> {
+    // Two triangles covering the target.
+    let quad: [f32; 12] = [
+        -1.0, -1.0, 1.0, -1.0, 1.0, 1.0, -1.0, -1.0, 1.0, 1.0, -1.0, 1.0,
+    ];
+    unsafe {
+        let program = link_program(gl, vs, fs).map_err(|err| err.to_string())?;
+        let position = match gl.get_attrib_location(program, "position") {
+            Some(position) => position,
+            None => {
+                gl.delete_program(program);
+                return Err("missing attribute `position`".to_owned());
+            }
+        };
+        let vbo = gl.create_buffer()?;
+        gl.bind_buffer(glow::ARRAY_BUFFER, Some(vbo));
+        let bytes = std::slice::from_raw_parts(
+            quad.as_ptr() as *const u8,
+            quad.len() * std::mem::size_of::<f32>(),
+        );
+        gl.buffer_data_u8_slice(glow::ARRAY_BUFFER, bytes, glow::STATIC_DRAW);
+        let vao = gl.create_vertex_array()?;
+        gl.bind_vertex_array(Some(vao));
+        gl.enable_vertex_attrib_array(position);
+        gl.vertex_attrib_pointer_f32(position, 2, glow::FLOAT, false, 2 * 4, 0);
+        gl.bind_vertex_array(None);
+        Ok((program, vbo, vao))
+    }
+}
+
+/// The fragment shader of `FocusGlowPass`, after the definition of
+/// `ENCODED`. The vertex shader is that of `ColorMatrixPass`.
+const FOCUS_GLOW_FRAGMENT_SHADER: &str = "
+    // The middle and the half size of the rect, in framebuffer pixels.
+    uniform vec4 rect;
+    // Linear RGB and alpha.
+    uniform vec4 color;
+    // How far the glow reaches out of the rect, in pixels.
+    uniform float spread;
+
+    vec3 toSrgb(vec3 linearRgb) {
+        vec3 cutoff = vec3(1.0) - step(vec3(0.0031308), linearRgb);
+        vec3 higher = vec3(1.055) * pow(linearRgb, vec3(1.0 / 2.4)) - vec3(0.055);
+        vec3 lower = linearRgb * vec3(12.92);
+        return mix(higher, lower, cutoff);
+    }
+
+    void main() {
+        // The distance from the edge of the rect, with the corners rounded
+        // outside of it and negative inside of it.
+        vec2 d = abs(gl_FragCoord.xy - rect.xy) - rect.zw;
+        float outside = length(max(d, vec2(0.0)));
+        float inside = -min(max(d.x, d.y), 0.0);
+        // Fading out over the spread outside, and within two pixels inside,
+        // squared for a softer falloff.
+        float fade = outside > 0.0
+            ? 1.0 - smoothstep(0.0, spread, outside)
+            : 1.0 - smoothstep(0.0, 2.0, inside);
+        vec3 rgb = ENCODED ? toSrgb(color.rgb) : color.rgb;
+        FRAG_COLOR = vec4(rgb, color.a * fade * fade);
+    }
+";
+
+/// Draws a feathered outline around a rect over the whole bound framebuffer,
+/// blended over what is there, for the focus ring.
+pub struct FocusGlowPass {
+    program: glow::Program,
+    vbo: glow::Buffer,
+    vao: glow::VertexArray,
+}
+
+impl FocusGlowPass {
+    /// Build the pass in the GLSL version of `info.shader`, which also tells
+    /// whether it has to encode the colors as sRGB itself.
+    pub fn new(gl: &glow::Context, info: &RendererInfo) -> Result<Self, String> {
+        let header = info.shader.color_matrix_header();
+        let definitions = format!(
+            "const bool ENCODED = {};
+            ",
+            info.shader.converts_to_srgb()
+        );
+        let vs = format!("{}{}", header, COLOR_MATRIX_VERTEX_SHADER);
+        let fs = format!("{}{}{}", header, definitions, FOCUS_GLOW_FRAGMENT_SHADER);
+        let (program, vbo, vao) = full_quad_program(gl, &vs, &fs)?;
+        Ok(FocusGlowPass { program, vbo, vao })
+    }
+
+    /// Draw the glow around `rect`, in conrod coordinates, over the bound
+    /// framebuffer of `size` pixels, with the blending of the `Renderer`.
+    /// `spread` is in points.
+    pub fn draw(
+        &self,
+        gl: &glow::Context,
+        rect: Rect,
+        dpi_factor: Scalar,
+        (width, height): (u32, u32),
+        color: color::Color,
+        spread: f32,
+    ) {
+        let [r, g, b, a] = gamma_srgb_to_linear(color.to_fsa());
+        let [x, y] = rect.xy();
+        let [w, h] = rect.dim();
+        let to_pixels = |value: Scalar| (value * dpi_factor) as f32;
+        unsafe {
+            gl.use_program(Some(self.program));
+            let location = |name| gl.get_uniform_location(self.program, name);
+            gl.uniform_4_f32(
+                location("rect").as_ref(),
+                to_pixels(x) + width as f32 / 2.0,
+                to_pixels(y) + height as f32 / 2.0,
+                to_pixels(w / 2.0),
+                to_pixels(h / 2.0),
+            );
+            gl.uniform_4_f32(location("color").as_ref(), r, g, b, a);
+            gl.uniform_1_f32(location("spread").as_ref(), spread * dpi_factor as f32);
+            gl.bind_vertex_array(Some(self.vao));
+            gl.draw_arrays(glow::TRIANGLES, 0, 6);
+            gl.bind_vertex_array(None);
+        }
+    }
+
+    /// Delete the GL objects.
+    pub fn delete(self, gl: &glow::Context) {
+        unsafe {
+            gl.delete_vertex_array(self.vao);
+            gl.delete_buffer(self.vbo);
+            gl.delete_program(self.program);
+        }
+    }
+}
+
+/// The fragment shader of `FxaaPass`, after the definition of `ENCODED`. The
+/// vertex shader is that of `ColorMatrixPass`. It is the simple FXAA of
+/// Timothy Lottes: where the luma of the four diagonal neighbours differs,
+/// it blurs along the edge between them, unless that brings in a luma from
+/// outside of the neighbourhood.
+const FXAA_FRAGMENT_SHADER: &str = "
+    uniform sampler2D tex;
+    // The size of a pixel of the texture, in texture coordinates.
+    uniform vec2 texel;
+    IN_VARYING vec2 v_tex_coords;
+
+    const float REDUCE_MIN = 1.0 / 128.0;
+    const float REDUCE_MUL = 1.0 / 8.0;
+    // In pixels.
+    const float SPAN_MAX = 8.0;
+
+    // On colors encoded as sRGB, roughly, which is how the edges are seen.
+    float luma(vec3 rgb) {
+        return dot(ENCODED ? rgb : sqrt(rgb), vec3(0.299, 0.587, 0.114));
+    }
+
+    vec4 at(vec2 offset) {
+        return TEXTURE(tex, v_tex_coords + offset);
+    }
+
+    void main() {
+        vec4 middle = at(vec2(0.0));
+        float lumaNW = luma(at(vec2(-1.0, 1.0) * texel).rgb);
+        float lumaNE = luma(at(vec2(1.0, 1.0) * texel).rgb);
+        float lumaSW = luma(at(vec2(-1.0, -1.0) * texel).rgb);
+        float lumaSE = luma(at(vec2(1.0, -1.0) * texel).rgb);
+        float lumaM = luma(middle.rgb);
+        float lumaMin = min(lumaM, min(min(lumaNW, lumaNE), min(lumaSW, lumaSE)));
+        float lumaMax = max(lumaM, max(max(lumaNW, lumaNE), max(lumaSW, lumaSE)));
+
+        // Along the edge, across the gradient.
+        vec2 dir = vec2(
+            (lumaSW + lumaSE) - (lumaNW + lumaNE),
+            (lumaNW + lumaSW) - (lumaNE + lumaSE)
+        );
+        float reduce = max((lumaNW + lumaNE + lumaSW + lumaSE) * 0.25 * REDUCE_MUL, REDUCE_MIN);
+        float scale = 1.0 / (min(abs(dir.x), abs(dir.y)) + reduce);
+        dir = clamp(dir * scale, vec2(-SPAN_MAX), vec2(SPAN_MAX)) * texel;
+
+        vec4 near = 0.5 * (at(dir * (1.0 / 3.0 - 0.5)) + at(dir * (2.0 / 3.0 - 0.5)));
+        vec4 far = near * 0.5 + 0.25 * (at(dir * -0.5) + at(dir * 0.5));
+        float lumaFar = luma(far.rgb);
+        FRAG_COLOR = lumaFar < lumaMin || lumaFar > lumaMax ? near : far;
+    }
+";
+
+/// A post-process which draws a texture, such as an `OffscreenTarget` the
+/// UI was drawn to, over the whole bound framebuffer with the edges smoothed
+/// out in screen space. It antialiases where multisampling can't be had, as
+/// it only needs the drawn pixels.
+pub struct FxaaPass {
+    program: glow::Program,
+    vbo: glow::Buffer,
+    vao: glow::VertexArray,
+}
+
+impl FxaaPass {
+    /// Build the pass in the GLSL version of `info.shader`, which also tells
+    /// whether the colors drawn by the renderer are still encoded as sRGB in
+    /// the texture.
+    pub fn new(gl: &glow::Context, info: &RendererInfo) -> Result<Self, String> {
+        let header = info.shader.color_matrix_header();
+        let definitions = format!(
+            "const bool ENCODED = {};
+            ",
+            info.shader.converts_to_srgb()
+        );
+        let vs = format!("{}{}", header, COLOR_MATRIX_VERTEX_SHADER);
+        let fs = format!("{}{}{}", header, definitions, FXAA_FRAGMENT_SHADER);
+        let (program, vbo, vao) = full_quad_program(gl, &vs, &fs)?;
+        Ok(FxaaPass { program, vbo, vao })
+    }
+
+    /// Draw `texture` of `size` pixels, filtered linearly, over the bound
+    /// framebuffer, replacing what is there.
+    pub fn draw(&self, gl: &glow::Context, texture: glow::Texture, (width, height): (u32, u32)) {
+        unsafe {
+            gl.disable(glow::BLEND);
+            gl.use_program(Some(self.program));
+            let texel = gl.get_uniform_location(self.program, "texel");
+            gl.uniform_2_f32(
+                texel.as_ref(),
+                1.0 / width.max(1) as f32,
+                1.0 / height.max(1) as f32,
+            );
+            gl.bind_vertex_array(Some(self.vao));
+            gl.active_texture(glow::TEXTURE0);
+            gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+            gl.draw_arrays(glow::TRIANGLES, 0, 6);
+            gl.bind_vertex_array(None);
+            gl.enable(glow::BLEND);
+        }
+    }
+
+    /// Delete the GL objects.
+    pub fn delete(self, gl: &glow::Context) {
+        unsafe {
+            gl.delete_vertex_array(self.vao);
+            gl.delete_buffer(self.vbo);
+            gl.delete_program(self.program);
+        }
+    }
+}
+
+/// Read back `width` x `height` pixels of `framebuffer` (or the default
+/// framebuffer for `None`) as tightly packed RGBA rows, top row first.
+pub fn read_framebuffer_pixels(
+    gl: &glow::Context,
+    framebuffer: Option<glow::Framebuffer>,
+    width: u32,
+    height: u32,
+) -> Vec<u8> {
+    unsafe {
+        gl.bind_framebuffer(glow::FRAMEBUFFER, framebuffer);
+    }
+    let pixels = read_pixels(gl, GlRect::full((width, height)));
+    unsafe {
+        gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+    }
+    pixels
+}
+
+/// Read back the pixels of `rect` in the bound framebuffer, such as the back buffer right after
+/// drawing a frame, as an image. For screenshots and visual regression tests.
+pub fn capture_frame(
+    gl: &glow::Context,
+    rect: GlRect,
+) -> Result<::image::RgbaImage, RendererError> {
+    let pixels = read_pixels(gl, rect);
+    let err = unsafe { gl.get_error() };
+    if err != 0 {
+        return Err(RendererError::GlError(err));
+    }
+    ::image::RgbaImage::from_raw(rect.width, rect.height, pixels)
+        .ok_or_else(|| RendererError::Gl("the pixels don't fill the image".to_owned()))
+}
+
+/// The pixels of `rect` in the bound framebuffer as tightly packed RGBA rows, top row first.
+fn read_pixels(gl: &glow::Context, rect: GlRect) -> Vec<u8> {
+    let row_len = rect.width as usize * 4;
+    let mut pixels = vec![0u8; row_len * rect.height as usize];
+    unsafe {
+        gl.pixel_store_i32(glow::PACK_ALIGNMENT, 1);
+        gl.read_pixels(
+            rect.left as i32,
+            rect.bottom as i32,
+            rect.width as i32,
+            rect.height as i32,
+            glow::RGBA,
+            glow::UNSIGNED_BYTE,
+            &mut pixels,
+        );
+        gl.pixel_store_i32(glow::PACK_ALIGNMENT, 4);
+    }
+    // OpenGL returns the bottom row first.
+    pixels
+        .chunks(row_len.max(1))
+        .rev()
+        .flat_map(|row| row.iter())
+        .copied()
+        .collect()
+}
+
+/// The GL state `Renderer::draw` needs, from `Renderer::begin_frame`. It is put back as it was
+/// when the guard is dropped.
+///
+/// While the guard lives:
+/// - blending is on, with `SRC_ALPHA, ONE_MINUS_SRC_ALPHA` for the colors and
+///   `ONE, ONE_MINUS_SRC_ALPHA` for the alpha;
+/// - `FRAMEBUFFER_SRGB` is on if and only if `RendererInfo::framebuffer_srgb` is, except on the
+///   web, which has no such switch;
+/// - the scissor test is off, as `draw` turns it on for its scissor rectangles and off after.
+///
+/// The viewport is set to the framebuffer and left so, as every pass sets its own, and so is the
+/// clear color after `clear`.
+pub struct FrameGuard<'a> {
+    gl: &'a glow::Context,
+    blend: bool,
+    /// The source and destination factors for the colors, then for the alpha.
+    blend_func: [u32; 4],
+    #[cfg(not(target_arch = "wasm32"))]
+    framebuffer_srgb: bool,
+    scissor_test: bool,
+    flush: bool,
+}
+
+impl<'a> FrameGuard<'a> {
+    /// Clear the framebuffer to `color`, with straight alpha.
+    pub fn clear(&self, [r, g, b, a]: [f32; 4]) {
+        unsafe {
+            self.gl.clear_color(r, g, b, a);
+            self.gl
+                .clear(glow::COLOR_BUFFER_BIT | glow::DEPTH_BUFFER_BIT);
+        }
+    }
+
+    /// Flush the GL commands when dropped, as when another context draws next. Off by default.
+    pub fn flush_on_drop(mut self, flush: bool) -> Self {
+        self.flush = flush;
+        self
+    }
+}
+
+impl<'a> Drop for FrameGuard<'a> {
+    fn drop(&mut self) {
+        let gl = self.gl;
+        let set = |capability: u32, enabled: bool| unsafe {
+            if enabled {
+                gl.enable(capability);
+            } else {
+                gl.disable(capability);
+            }
+        };
+        set(glow::BLEND, self.blend);
+        #[cfg(not(target_arch = "wasm32"))]
+        set(glow::FRAMEBUFFER_SRGB, self.framebuffer_srgb);
+        set(glow::SCISSOR_TEST, self.scissor_test);
+        let [src_rgb, dst_rgb, src_alpha, dst_alpha] = self.blend_func;
+        unsafe {
+            gl.blend_func_separate(src_rgb, dst_rgb, src_alpha, dst_alpha);
+            if self.flush {
+                gl.flush();
+            }
+        }
+    }
+}
+
+/// Where the glyph cache of a `Renderer` comes from.
+enum GlyphCacheSource {
+    Framebuffer((u32, u32), f32),
+    Dimensions(u32, u32),
+    Shared(Rc<RefCell<GlyphCache>>),
+}
+
+/// The options for setting up a `Renderer`, from `Renderer::builder`.
+pub struct RendererBuilder {
+    framebuffer_srgb: bool,
+    safe_mode: bool,
+    glyph_cache: GlyphCacheSource,
+    max_glyph_cache_size: Option<(u32, u32)>,
+    program_cache: Option<Box<dyn ProgramCache>>,
+}
+
+impl RendererBuilder {
+    fn new() -> Self {
+        RendererBuilder {
+            framebuffer_srgb: false,
+            safe_mode: false,
+            glyph_cache: GlyphCacheSource::Dimensions(1024, 1024),
+            max_glyph_cache_size: None,
+            program_cache: None,
+        }
+    }
+
+    /// Whether the framebuffer converts the output to sRGB. Off by default.
+    pub fn framebuffer_srgb(mut self, framebuffer_srgb: bool) -> Self {
+        self.framebuffer_srgb = framebuffer_srgb;
+        self
+    }
+
+    /// Only use the simplest shaders and texture uploads, for drivers which fail otherwise. Off
+    /// by default.
+    pub fn safe_mode(mut self, safe_mode: bool) -> Self {
+        self.safe_mode = safe_mode;
+        self
+    }
+
+    /// Size the glyph cache for a framebuffer of `framebuffer` pixels, see
+    /// `GlyphCache::size_for_framebuffer`.
+    pub fn glyph_cache_for_framebuffer(mut self, framebuffer: (u32, u32), scale: f32) -> Self {
+        self.glyph_cache = GlyphCacheSource::Framebuffer(framebuffer, scale);
+        self
+    }
+
+    /// Make the glyph cache `width` by `height` pixels. 1024x1024 by default.
+    pub fn glyph_cache_dimensions(mut self, width: u32, height: u32) -> Self {
+        self.glyph_cache = GlyphCacheSource::Dimensions(width, height);
+        self
+    }
+
+    /// Let the glyph cache grow to at most `width` by `height` pixels when the glyphs of a frame
+    /// don't fit, see `GlyphCache::set_max_size`. Only limited by the GL by default.
+    pub fn max_glyph_cache_size(mut self, width: u32, height: u32) -> Self {
+        self.max_glyph_cache_size = Some((width, height));
+        self
+    }
+
+    /// Use the glyph cache of `other`, a renderer for another window, so that the glyphs are only
+    /// cached once for all windows.
+    ///
+    /// The context given to `build` must share its objects with the context of `other`, such as
+    /// a glutin context built `with_shared_lists`. Draw each window right after filling its
+    /// renderer, as filling another one may evict the glyphs from the cache.
+    pub fn share_glyph_cache(mut self, other: &Renderer) -> Self {
+        self.glyph_cache = GlyphCacheSource::Shared(other.glyph_cache.clone());
+        self
+    }
+
+    /// Load the program from `cache` instead of compiling the shaders when it has it, and store
+    /// it there when it doesn't. It isn't used in safe mode, as a broken driver may load a
+    /// program wrong. None by default.
+    pub fn program_cache(mut self, cache: Box<dyn ProgramCache>) -> Self {
+        self.program_cache = Some(cache);
+        self
+    }
+
+    /// Construct the `Renderer`, finding the first shaders which work with `gl`.
+    pub fn build(self, gl: &glow::Context) -> Result<Renderer, RendererError> {
+        let program_cache = self.program_cache.as_deref().filter(|_| !self.safe_mode);
+        let mut glyph_cache = match self.glyph_cache {
+            GlyphCacheSource::Framebuffer(framebuffer, scale) => {
+                let (width, height) = GlyphCache::size_for_framebuffer(gl, framebuffer, scale);
+                GlyphCache::create(gl, width, height, self.safe_mode)?
+            }
+            GlyphCacheSource::Dimensions(width, height) => {
+                GlyphCache::create(gl, width, height, self.safe_mode)?
+            }
+            GlyphCacheSource::Shared(glyph_cache) => {
+                if let Some((width, height)) = self.max_glyph_cache_size {
+                    glyph_cache.borrow_mut().set_max_size(width, height);
+                }
+                return Renderer::with_glyph_cache(
+                    gl,
+                    glyph_cache,
+                    self.framebuffer_srgb,
+                    self.safe_mode,
+                    program_cache,
+                );
+            }
+        };
+        if let Some((width, height)) = self.max_glyph_cache_size {
+            glyph_cache.set_max_size(width, height);
+        }
+        Renderer::with_glyph_cache(
+            gl,
+            Rc::new(RefCell::new(glyph_cache)),
+            self.framebuffer_srgb,
+            self.safe_mode,
+            program_cache,
+        )
     }
 }
 
 impl Renderer {
-    // // This is almost 1MiB of buffer (29127 * 36 = 1048572)
-    // const VBO_BUFFER_VERTEX_COUNT: usize = 29_127;
-    const VBO_BUFFER_VERTEX_COUNT: usize = 10_000;
+    /// The vertices the buffers are made for at first, which is 360KiB of vertices. They grow
+    /// as needed.
+    const INITIAL_VERTEX_COUNT: usize = 10_000;
+
+    /// Start setting up a `Renderer`.
+    pub fn builder() -> RendererBuilder {
+        RendererBuilder::new()
+    }
 
     /// Construct a new empty `Renderer`.
     ///
-    /// The dimensions of the inner glyph cache will be equal to the dimensions of the given
-    /// facade's framebuffer.
-    pub fn new(gl: &glow::Context, is_framebuffer_srgb: bool) -> Result<Self, String> {
-        let glyph_cache = GlyphCache::new(gl)?;
-        Self::with_glyph_cache(gl, glyph_cache, is_framebuffer_srgb)
+    /// The inner glyph cache is sized for a framebuffer of `framebuffer` pixels, with
+    /// `GlyphCache::DEFAULT_SCALE`.
+    #[deprecated(note = "use `Renderer::builder` instead")]
+    pub fn new(
+        gl: &glow::Context,
+        framebuffer: (u32, u32),
+        is_framebuffer_srgb: bool,
+    ) -> Result<Self, RendererError> {
+        Self::builder()
+            .framebuffer_srgb(is_framebuffer_srgb)
+            .glyph_cache_for_framebuffer(framebuffer, GlyphCache::DEFAULT_SCALE)
+            .build(gl)
+    }
+
+    /// Like `new`, with the glyph cache `scale` times the size of the framebuffer.
+    #[deprecated(note = "use `Renderer::builder` instead")]
+    pub fn with_glyph_cache_scale(
+        gl: &glow::Context,
+        framebuffer: (u32, u32),
+        scale: f32,
+        is_framebuffer_srgb: bool,
+    ) -> Result<Self, RendererError> {
+        Self::builder()
+            .framebuffer_srgb(is_framebuffer_srgb)
+            .glyph_cache_for_framebuffer(framebuffer, scale)
+            .build(gl)
+    }
+
+    /// Construct a new empty `Renderer` which only uses the simplest shaders
+    /// and texture uploads, for drivers which fail otherwise.
+    #[deprecated(note = "use `Renderer::builder` instead")]
+    pub fn new_safe_mode(
+        gl: &glow::Context,
+        framebuffer: (u32, u32),
+        is_framebuffer_srgb: bool,
+    ) -> Result<Self, RendererError> {
+        Self::builder()
+            .framebuffer_srgb(is_framebuffer_srgb)
+            .safe_mode(true)
+            .glyph_cache_for_framebuffer(framebuffer, GlyphCache::DEFAULT_SCALE)
+            .build(gl)
     }
 
     /// Construct a new empty `Renderer` with the given glyph cache dimensions.
+    #[deprecated(note = "use `Renderer::builder` instead")]
     pub fn with_glyph_cache_dimensions(
         gl: &glow::Context,
         width: u32,
         height: u32,
         is_framebuffer_srgb: bool,
-    ) -> Result<Self, String> {
-        let glyph_cache = GlyphCache::with_dimensions(gl, width, height)?;
-        Self::with_glyph_cache(gl, glyph_cache, is_framebuffer_srgb)
+    ) -> Result<Self, RendererError> {
+        Self::builder()
+            .framebuffer_srgb(is_framebuffer_srgb)
+            .glyph_cache_dimensions(width, height)
+            .build(gl)
+    }
+
+    /// Construct a new empty `Renderer` for another window which uses the glyph cache of `other`.
+    #[deprecated(note = "use `Renderer::builder` instead")]
+    pub fn sharing_glyph_cache(
+        gl: &glow::Context,
+        other: &Renderer,
+        is_framebuffer_srgb: bool,
+    ) -> Result<Self, RendererError> {
+        Self::builder()
+            .framebuffer_srgb(is_framebuffer_srgb)
+            .safe_mode(other.info.safe_mode)
+            .share_glyph_cache(other)
+            .build(gl)
     }
 
     // Construct a new **Renderer** that uses the given glyph cache for caching text.
     fn with_glyph_cache(
         gl: &glow::Context,
-        gc: GlyphCache,
+        gc: Rc<RefCell<GlyphCache>>,
         is_framebuffer_srgb: bool,
-    ) -> Result<Self, String> {
-        let program = program(gl, is_framebuffer_srgb)?;
+        safe_mode: bool,
+        program_cache: Option<&dyn ProgramCache>,
+    ) -> Result<Self, RendererError> {
+        let (program, mut info) =
+            first_working_program(gl, is_framebuffer_srgb, safe_mode, program_cache)?;
+        info.glyph_cache_size = gc.borrow().dimensions();
         let vbo;
+        let ebo;
         let vao;
+        let vbo_size = Self::INITIAL_VERTEX_COUNT * std::mem::size_of::<Vertex>();
+        // Most vertices are the corners of quads, drawn with 6 indices.
+        let ebo_size = Self::INITIAL_VERTEX_COUNT * 6 / 4 * std::mem::size_of::<u32>();
         unsafe {
-            vbo = gl.create_buffer().unwrap();
+            vbo = gl.create_buffer()?;
             gl.bind_buffer(glow::ARRAY_BUFFER, Some(vbo));
 
-            vao = gl.create_vertex_array().unwrap();
+            vao = gl.create_vertex_array()?;
             gl.bind_vertex_array(Some(vao));
 
+            // The binding is part of the state of the vertex array.
+            ebo = gl.create_buffer()?;
+            gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(ebo));
+            gl.buffer_data_size(
+                glow::ELEMENT_ARRAY_BUFFER,
+                ebo_size as i32,
+                glow::STREAM_DRAW,
+            );
+
             gl.enable_vertex_attrib_array(program.attrib_mode);
             gl.enable_vertex_attrib_array(program.attrib_position);
             gl.enable_vertex_attrib_array(program.attrib_tex_coords);
@@ -525,7 +3822,18 @@ impl Renderer {
             gl.bind_buffer(glow::ARRAY_BUFFER, Some(vbo));
             let stride = 9 * 4;
             assert_eq!(std::mem::size_of::<Vertex>(), stride as _);
-            gl.vertex_attrib_pointer_i32(program.attrib_mode, 1, glow::UNSIGNED_INT, stride, 0);
+            if info.shader.has_integer_mode() {
+                gl.vertex_attrib_pointer_i32(program.attrib_mode, 1, glow::UNSIGNED_INT, stride, 0);
+            } else {
+                gl.vertex_attrib_pointer_f32(
+                    program.attrib_mode,
+                    1,
+                    glow::UNSIGNED_INT,
+                    false,
+                    stride,
+                    0,
+                );
+            }
             gl.vertex_attrib_pointer_f32(
                 program.attrib_position,
                 2,
@@ -550,408 +3858,378 @@ impl Renderer {
                 stride,
                 5 * 4,
             );
-            gl.buffer_data_size(
-                glow::ARRAY_BUFFER,
-                (Self::VBO_BUFFER_VERTEX_COUNT * std::mem::size_of::<Vertex>()) as i32,
-                glow::STREAM_DRAW,
-            );
+            gl.buffer_data_size(glow::ARRAY_BUFFER, vbo_size as i32, glow::STREAM_DRAW);
+            gl.bind_vertex_array(None);
         }
         Ok(Renderer {
             program,
+            info,
             vbo,
+            ebo,
             vao,
+            buffer_sizes: std::cell::Cell::new((vbo_size, ebo_size)),
             glyph_cache: gc,
+            glyph_cache_grew: false,
             commands: Vec::new(),
-            vertices: Vec::new(),
+            geometry: Geometry::default(),
+            tags_widgets: false,
+            widget_starts: Vec::new(),
         })
     }
 
-    /// Fill the inner vertex and command buffers by translating the given `primitives`.
-    pub fn fill<D, P>(
-        &mut self,
-        display: &D,
-        gl: &glow::Context,
-        mut primitives: P,
-        image_map: &image::Map<Texture>,
-    ) where
-        P: render::PrimitiveWalker,
-        D: Display,
-    {
-        let Renderer {
-            ref mut commands,
-            ref mut vertices,
-            ref mut glyph_cache,
-            ..
-        } = *self;
-
-        commands.clear();
-        vertices.clear();
-
-        enum State {
-            Image { image_id: image::Id, start: usize },
-            Plain { start: usize },
-        }
+    /// Which shaders are used and why.
+    pub fn info(&self) -> &RendererInfo {
+        &self.info
+    }
 
-        let mut current_state = State::Plain { start: 0 };
+    /// Tag each command prepared by `fill` with the widget its primitives are of, for
+    /// `commands`. The commands are split between widgets for it, so there are more draw calls
+    /// while it is on. Off by default.
+    pub fn set_tags_widgets(&mut self, tags_widgets: bool) {
+        self.tags_widgets = tags_widgets;
+    }
 
-        // Switches to the `Plain` state and completes the previous `Command` if not already in the
-        // `Plain` state.
-        macro_rules! switch_to_plain_state {
-            () => {
-                match current_state {
-                    State::Plain { .. } => (),
-                    State::Image { image_id, start } => {
-                        commands.push(PreparedCommand::Image(image_id, start..vertices.len()));
-                        current_state = State::Plain {
-                            start: vertices.len(),
-                        };
-                    }
-                }
-            };
+    /// The commands prepared by the last `fill` and `fill_debug`, in the order they are drawn.
+    pub fn commands(&self) -> Commands<'_> {
+        Commands {
+            commands: self.commands.iter().enumerate(),
+            widget_starts: &self.widget_starts,
         }
+    }
 
-        // Framebuffer dimensions and the "dots per inch" factor.
-        let (screen_w, screen_h) = display.framebuffer_dimensions();
-        let (win_w, win_h) = (screen_w as Scalar, screen_h as Scalar);
-        let half_win_w = win_w / 2.0;
-        let half_win_h = win_h / 2.0;
-        let dpi_factor = display.hidpi_factor() as Scalar;
-
-        // Functions for converting for conrod scalar coords to GL vertex coords (-1.0 to 1.0).
-        let vx = |x: Scalar| (x * dpi_factor / half_win_w) as f32;
-        let vy = |y: Scalar| (y * dpi_factor / half_win_h) as f32;
-
-        let mut current_scizzor = GlRect {
-            left: 0,
-            width: screen_w,
-            bottom: 0,
-            height: screen_h,
-        };
-
-        let rect_to_gl_rect = |rect: Rect| {
-            let (w, h) = rect.w_h();
-            let left = (rect.left() * dpi_factor + half_win_w).round() as u32;
-            let bottom = (rect.bottom() * dpi_factor + half_win_h).round() as u32;
-            let width = (w * dpi_factor).round() as u32;
-            let height = (h * dpi_factor).round() as u32;
-            GlRect {
-                left: std::cmp::max(left, 0),
-                bottom: std::cmp::max(bottom, 0),
-                width: std::cmp::min(width, screen_w),
-                height: std::cmp::min(height, screen_h),
-            }
-        };
-
-        // Draw each primitive in order of depth.
-        while let Some(primitive) = primitives.next_primitive() {
-            let render::Primitive {
-                kind,
-                scizzor,
-                rect,
-                ..
-            } = primitive;
-
-            // Check for a `Scizzor` command.
-            let new_scizzor = rect_to_gl_rect(scizzor);
-            if new_scizzor != current_scizzor {
-                // Finish the current command.
-                match current_state {
-                    State::Plain { start } => {
-                        commands.push(PreparedCommand::Plain(start..vertices.len()))
-                    }
-                    State::Image { image_id, start } => {
-                        commands.push(PreparedCommand::Image(image_id, start..vertices.len()))
-                    }
-                }
-
-                // Update the scizzor and produce a command.
-                current_scizzor = new_scizzor;
-                commands.push(PreparedCommand::Scizzor(new_scizzor));
-
-                // Set the state back to plain drawing.
-                current_state = State::Plain {
-                    start: vertices.len(),
-                };
-            }
-
-            match kind {
-                render::PrimitiveKind::Rectangle { color } => {
-                    switch_to_plain_state!();
-
-                    let color = gamma_srgb_to_linear(color.to_fsa());
-                    let (l, r, b, t) = rect.l_r_b_t();
-
-                    let v = |x, y| {
-                        // Convert from conrod Scalar range to GL range -1.0 to 1.0.
-                        Vertex {
-                            position: [vx(x), vy(y)],
-                            tex_coords: [0.0, 0.0],
-                            color: color,
-                            mode: MODE_GEOMETRY,
-                        }
-                    };
-
-                    let mut push_v = |x, y| vertices.push(v(x, y));
-
-                    // Bottom left triangle.
-                    push_v(l, t);
-                    push_v(r, b);
-                    push_v(l, b);
+    /// The images drawn by the last `fill`, possibly more than once each.
+    pub fn images(&self) -> impl Iterator<Item = image::Id> + '_ {
+        self.commands.iter().filter_map(|command| match *command {
+            PreparedCommand::Image(id, _) => Some(id),
+            _ => None,
+        })
+    }
 
-                    // Top right triangle.
-                    push_v(l, t);
-                    push_v(r, b);
-                    push_v(r, t);
-                }
+    /// The largest size the glyph cache grows to, see `GlyphCache::max_size`.
+    pub fn max_glyph_cache_size(&self) -> (u32, u32) {
+        self.glyph_cache.borrow().max_size()
+    }
 
-                render::PrimitiveKind::TrianglesSingleColor { color, triangles } => {
-                    if triangles.is_empty() {
-                        continue;
-                    }
+    /// Change the largest size the glyph cache grows to. It doesn't shrink if it is bigger
+    /// already. Renderers sharing the cache share the limit too.
+    pub fn set_max_glyph_cache_size(&self, width: u32, height: u32) {
+        self.glyph_cache.borrow_mut().set_max_size(width, height);
+    }
 
-                    switch_to_plain_state!();
+    /// Whether the glyph cache had to grow during the last `fill`, which changes the size in
+    /// `info`. The text filled before it grew is pointed at the new texture.
+    pub fn glyph_cache_grew(&self) -> bool {
+        self.glyph_cache_grew
+    }
 
-                    let color = gamma_srgb_to_linear(color.into());
+    /// A `Tessellator` with an empty glyph cache of the same size and limit, for filling on
+    /// another thread.
+    pub fn tessellator(&self, gl: &glow::Context) -> Tessellator {
+        let glyph_cache = self.glyph_cache.borrow();
+        let (width, height) = glyph_cache.dimensions();
+        let (max_w, max_h) = glyph_cache.max_size();
+        let max_texture = max_texture_size(gl);
+        Tessellator::new(
+            (width, height),
+            (max_w.min(max_texture), max_h.min(max_texture)),
+        )
+    }
 
-                    let v = |p: [Scalar; 2]| Vertex {
-                        position: [vx(p[0]), vy(p[1])],
-                        tex_coords: [0.0, 0.0],
-                        color: color,
-                        mode: MODE_GEOMETRY,
-                    };
+    /// Draw `mesh` from now on instead of what `fill` prepared, after uploading its glyphs to
+    /// the glyph cache texture, which is made anew if the cache of the `Tessellator` grew.
+    pub fn load_mesh(&mut self, gl: &glow::Context, mesh: Mesh) -> Result<(), String> {
+        let mut glyph_cache = self.glyph_cache.borrow_mut();
+        let (width, height) = mesh.glyph_cache_size;
+        if glyph_cache.dimensions() != (width, height) {
+            glyph_cache.resize(gl, width, height)?;
+            self.info.glyph_cache_size = (width, height);
+        }
+        for upload in &mesh.glyph_uploads {
+            glyph_cache.upload(gl, upload.rect, &upload.data);
+        }
+        self.commands = mesh.commands;
+        self.geometry = mesh.geometry;
+        self.widget_starts.clear();
+        self.glyph_cache_grew = mesh.glyph_cache_grew;
+        Ok(())
+    }
 
-                    for triangle in triangles {
-                        vertices.push(v(triangle[0]));
-                        vertices.push(v(triangle[1]));
-                        vertices.push(v(triangle[2]));
-                    }
-                }
+    /// Keep a copy of the glyph cache texture in memory, for `glyph_pixels`. Call it before the
+    /// first `fill`.
+    pub fn keep_glyph_pixels(&self) {
+        self.glyph_cache.borrow_mut().keep_pixels();
+    }
 
-                render::PrimitiveKind::TrianglesMultiColor { triangles } => {
-                    if triangles.is_empty() {
-                        continue;
-                    }
+    /// The contents of the glyph cache texture, if glyphs were uploaded to it after `version`
+    /// and it is kept since `keep_glyph_pixels`. Pass 0 for any contents.
+    pub fn glyph_pixels(&self, version: u64) -> Option<GlyphPixels> {
+        let glyph_cache = self.glyph_cache.borrow();
+        let data = glyph_cache.pixels.as_ref()?;
+        if glyph_cache.version <= version {
+            return None;
+        }
+        let (width, height) = glyph_cache.dimensions();
+        Some(GlyphPixels {
+            version: glyph_cache.version,
+            width,
+            height,
+            data: data.clone(),
+        })
+    }
 
-                    switch_to_plain_state!();
+    /// Replace the contents of the glyph cache texture with `pixels`, making a new texture if
+    /// the size differs.
+    ///
+    /// This is for renderers which only draw frames from `load_frame`: the glyphs `fill` has
+    /// cached are overwritten without it knowing.
+    pub fn load_glyph_pixels(
+        &mut self,
+        gl: &glow::Context,
+        pixels: &GlyphPixels,
+    ) -> Result<(), String> {
+        let (width, height) = (pixels.width, pixels.height);
+        if pixels.data.len() != width as usize * height as usize {
+            return Err(format!(
+                "{} bytes of glyph pixels for a {}x{} cache",
+                pixels.data.len(),
+                width,
+                height
+            ));
+        }
+        let mut glyph_cache = self.glyph_cache.borrow_mut();
+        if glyph_cache.dimensions() != (width, height) {
+            glyph_cache.resize(gl, width, height)?;
+            self.info.glyph_cache_size = (width, height);
+        }
+        unsafe {
+            gl.bind_texture(glow::TEXTURE_2D, Some(glyph_cache.texture));
+            gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
+            gl.tex_sub_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                0,
+                0,
+                width as i32,
+                height as i32,
+                glow::RED,
+                glow::UNSIGNED_BYTE,
+                glow::PixelUnpackData::Slice(&pixels.data),
+            );
+            gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 4);
+        }
+        Ok(())
+    }
 
-                    let v = |(p, c): ([Scalar; 2], color::Rgba)| Vertex {
-                        position: [vx(p[0]), vy(p[1])],
-                        tex_coords: [0.0, 0.0],
-                        color: gamma_srgb_to_linear(c.into()),
-                        mode: MODE_GEOMETRY,
-                    };
+    /// Take out what the last `fill` prepared for a framebuffer of `framebuffer` pixels. The
+    /// images are given by their index in `images`, and the others are left out.
+    pub fn frame(&self, framebuffer: (u32, u32), images: &[image::Id]) -> Frame {
+        frame_of(&self.commands, &self.geometry, framebuffer, images)
+    }
 
-                    for triangle in triangles {
-                        vertices.push(v(triangle[0]));
-                        vertices.push(v(triangle[1]));
-                        vertices.push(v(triangle[2]));
-                    }
+    /// Replace the commands and vertices with those of `frame`, to be drawn with `draw` to a
+    /// framebuffer of `framebuffer` pixels, and the images by their index in `images`.
+    ///
+    /// The vertices are already relative to the framebuffer, but the scissor rectangles are
+    /// scaled and clamped to it. Commands with images not in `images` or vertices out of range
+    /// are left out.
+    pub fn load_frame(&mut self, frame: &Frame, framebuffer: (u32, u32), images: &[image::Id]) {
+        let vertex_count = frame.vertices.len();
+        let in_range =
+            |range: &std::ops::Range<usize>| range.start <= range.end && range.end <= vertex_count;
+        self.commands = frame
+            .commands
+            .iter()
+            .filter_map(|command| match command {
+                FrameCommand::Image(index, range) if in_range(range) => images
+                    .get(*index as usize)
+                    .map(|&id| PreparedCommand::Image(id, range.clone())),
+                FrameCommand::Plain(range) if in_range(range) => {
+                    Some(PreparedCommand::Plain(range.clone()))
                 }
-
-                render::PrimitiveKind::Text {
-                    color,
-                    text,
-                    font_id,
+                FrameCommand::Scissor {
+                    left,
+                    bottom,
+                    width,
+                    height,
                 } => {
-                    switch_to_plain_state!();
-
-                    let positioned_glyphs = text.positioned_glyphs(dpi_factor as f32);
-
-                    let GlyphCache {
-                        ref mut cache,
-                        ref mut texture,
-                    } = *glyph_cache;
-
-                    // Queue the glyphs to be cached.
-                    for glyph in positioned_glyphs.iter() {
-                        cache.queue_glyph(font_id.index(), glyph.clone());
-                    }
-
-                    // Cache the glyphs on the GPU.
-                    cache
-                        .cache_queued(|rect, data| {
-                            let w = rect.width();
-                            let h = rect.height();
-
-                            unsafe {
-                                gl.bind_texture(glow::TEXTURE_2D, Some(*texture));
-                                assert_eq!(w * h, data.len() as _);
-                                gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
-                                gl.tex_sub_image_2d(
-                                    glow::TEXTURE_2D,
-                                    0,
-                                    rect.min.x as i32,
-                                    rect.min.y as i32,
-                                    w as i32,
-                                    h as i32,
-                                    glow::RED,
-                                    glow::UNSIGNED_BYTE,
-                                    glow::PixelUnpackData::Slice(data),
-                                );
-                                gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 4);
-                            }
-                        })
-                        .unwrap();
-
-                    let color = gamma_srgb_to_linear(color.to_fsa());
-
-                    let cache_id = font_id.index();
-
-                    let origin = text::rt::point(0.0, 0.0);
-                    let to_gl_rect = |screen_rect: text::rt::Rect<i32>| text::rt::Rect {
-                        min: origin
-                            + (text::rt::vector(
-                                screen_rect.min.x as f32 / screen_w as f32 - 0.5,
-                                1.0 - screen_rect.min.y as f32 / screen_h as f32 - 0.5,
-                            )) * 2.0,
-                        max: origin
-                            + (text::rt::vector(
-                                screen_rect.max.x as f32 / screen_w as f32 - 0.5,
-                                1.0 - screen_rect.max.y as f32 / screen_h as f32 - 0.5,
-                            )) * 2.0,
+                    let rect = GlRect {
+                        left: *left,
+                        bottom: *bottom,
+                        width: *width,
+                        height: *height,
                     };
-
-                    for g in positioned_glyphs {
-                        if let Ok(Some((uv_rect, screen_rect))) = cache.rect_for(cache_id, g) {
-                            let gl_rect = to_gl_rect(screen_rect);
-                            let v = |p, t| Vertex {
-                                position: p,
-                                tex_coords: t,
-                                color: color,
-                                mode: MODE_TEXT,
-                            };
-                            let mut push_v = |p, t| vertices.push(v(p, t));
-                            push_v(
-                                [gl_rect.min.x, gl_rect.max.y],
-                                [uv_rect.min.x, uv_rect.max.y],
-                            );
-                            push_v(
-                                [gl_rect.min.x, gl_rect.min.y],
-                                [uv_rect.min.x, uv_rect.min.y],
-                            );
-                            push_v(
-                                [gl_rect.max.x, gl_rect.min.y],
-                                [uv_rect.max.x, uv_rect.min.y],
-                            );
-                            push_v(
-                                [gl_rect.max.x, gl_rect.min.y],
-                                [uv_rect.max.x, uv_rect.min.y],
-                            );
-                            push_v(
-                                [gl_rect.max.x, gl_rect.max.y],
-                                [uv_rect.max.x, uv_rect.max.y],
-                            );
-                            push_v(
-                                [gl_rect.min.x, gl_rect.max.y],
-                                [uv_rect.min.x, uv_rect.max.y],
-                            );
-                        }
-                    }
+                    Some(PreparedCommand::Scizzor(
+                        rect.scaled(framebuffer, frame.framebuffer),
+                    ))
                 }
+                _ => None,
+            })
+            .collect();
+        self.geometry = Geometry::from_triangles(frame.vertices.clone());
+        self.widget_starts.clear();
+    }
 
-                render::PrimitiveKind::Image {
-                    image_id,
-                    color,
-                    source_rect,
-                } => {
-                    // Switch to the `Image` state for this image if we're not in it already.
-                    let new_image_id = image_id;
-                    match current_state {
-                        // If we're already in the drawing mode for this image, we're done.
-                        State::Image { image_id, .. } if image_id == new_image_id => (),
-
-                        // If we were in the `Plain` drawing state, switch to Image drawing state.
-                        State::Plain { start } => {
-                            commands.push(PreparedCommand::Plain(start..vertices.len()));
-                            current_state = State::Image {
-                                image_id: new_image_id,
-                                start: vertices.len(),
-                            };
-                        }
-
-                        // If we were drawing a different image, switch state to draw *this* image.
-                        State::Image { image_id, start } => {
-                            commands.push(PreparedCommand::Image(image_id, start..vertices.len()));
-                            current_state = State::Image {
-                                image_id: new_image_id,
-                                start: vertices.len(),
-                            };
-                        }
-                    }
+    /// `frame` as bytes, for saving what the last `fill` prepared and drawing it again later
+    /// with `import_frame`. The images are given by their index in `images`, as for `frame`.
+    pub fn export_frame(&self, framebuffer: (u32, u32), images: &[image::Id]) -> Vec<u8> {
+        self.frame(framebuffer, images).to_bytes()
+    }
 
-                    let color = color.unwrap_or(color::WHITE).to_fsa();
-
-                    if let Some(image) = image_map.get(&image_id) {
-                        let (image_w, image_h) = (image.width, image.height);
-                        let (image_w, image_h) = (image_w as Scalar, image_h as Scalar);
-
-                        // Get the sides of the source rectangle as uv coordinates.
-                        //
-                        // Texture coordinates range:
-                        // - left to right: 0.0 to 1.0
-                        // - bottom to top: 0.0 to 1.0
-                        let (uv_l, uv_r, uv_b, uv_t) = match source_rect {
-                            Some(src_rect) => {
-                                let (l, r, b, t) = src_rect.l_r_b_t();
-                                (
-                                    (l / image_w) as f32,
-                                    (r / image_w) as f32,
-                                    (b / image_h) as f32,
-                                    (t / image_h) as f32,
-                                )
-                            }
-                            None => (0.0, 1.0, 0.0, 1.0),
-                        };
+    /// `load_frame` with the bytes of `export_frame`. Nothing is replaced if they aren't a
+    /// frame.
+    pub fn import_frame(
+        &mut self,
+        bytes: &[u8],
+        framebuffer: (u32, u32),
+        images: &[image::Id],
+    ) -> Result<(), String> {
+        let frame = Frame::from_bytes(bytes)?;
+        self.load_frame(&frame, framebuffer, images);
+        Ok(())
+    }
 
-                        let v = |x, y, t| {
-                            // Convert from conrod Scalar range to GL range -1.0 to 1.0.
-                            let x = (x * dpi_factor as Scalar / half_win_w) as f32;
-                            let y = (y * dpi_factor as Scalar / half_win_h) as f32;
-                            Vertex {
-                                position: [x, y],
-                                tex_coords: t,
-                                color: color,
-                                mode: MODE_IMAGE,
-                            }
-                        };
+    /// Fill the inner vertex and command buffers by translating the given `primitives`.
+    ///
+    /// The glyphs which don't fit in the glyph cache even at its largest are left out, and
+    /// reported after filling the rest.
+    pub fn fill<D, P>(
+        &mut self,
+        display: &D,
+        gl: &glow::Context,
+        primitives: P,
+        image_map: &image::Map<Texture>,
+    ) -> Result<(), RendererError>
+    where
+        P: render::PrimitiveWalker,
+        D: Display,
+    {
+        let Renderer {
+            ref mut info,
+            ref mut commands,
+            ref mut geometry,
+            ref glyph_cache,
+            ref mut glyph_cache_grew,
+            tags_widgets,
+            ref mut widget_starts,
+            ..
+        } = *self;
+        let mut glyph_cache = glyph_cache.borrow_mut();
 
-                        let mut push_v = |x, y, t| vertices.push(v(x, y, t));
+        commands.clear();
+        geometry.clear();
+        widget_starts.clear();
+        *glyph_cache_grew = false;
+
+        let mut glyphs = GpuGlyphs::new(&mut glyph_cache, gl);
+        let image_size = |id| image_map.get(&id).map(|image| (image.width, image.height));
+        *glyph_cache_grew = tessellate(
+            display,
+            primitives,
+            &mut glyphs,
+            &image_size,
+            commands,
+            geometry,
+            Some(widget_starts).filter(|_| tags_widgets),
+        );
+        let result = glyphs.result();
+        info.glyph_cache_size = glyph_cache.dimensions();
+        result
+    }
 
-                        let (l, r, b, t) = rect.l_r_b_t();
+    /// Add the shapes of the debug layer above everything filled by `fill`, with the text in the
+    /// first font of `fonts`. The glyphs are left out and reported like those of `fill`.
+    pub fn fill_debug<D: Display>(
+        &mut self,
+        display: &D,
+        gl: &glow::Context,
+        shapes: &[debug_draw::Shape],
+        fonts: &text::font::Map,
+    ) -> Result<(), RendererError> {
+        let Renderer {
+            ref mut info,
+            ref mut commands,
+            ref mut geometry,
+            ref glyph_cache,
+            ref mut glyph_cache_grew,
+            tags_widgets,
+            ref mut widget_starts,
+            ..
+        } = *self;
+        let mut glyph_cache = glyph_cache.borrow_mut();
+        let font = fonts.ids().next().and_then(|id| Some((id, fonts.get(id)?)));
+        // Of no widget.
+        if tags_widgets {
+            widget_starts.push((commands.len(), None));
+        }
+        let mut glyphs = GpuGlyphs::new(&mut glyph_cache, gl);
+        *glyph_cache_grew |=
+            tessellate_debug(display, shapes, font, &mut glyphs, commands, geometry);
+        let result = glyphs.result();
+        info.glyph_cache_size = glyph_cache.dimensions();
+        result
+    }
 
-                        // Bottom left triangle.
-                        push_v(l, t, [uv_l, uv_t]);
-                        push_v(r, b, [uv_r, uv_b]);
-                        push_v(l, b, [uv_l, uv_b]);
+    /// Set up the GL state for `draw` on a framebuffer of `framebuffer` pixels, until the guard
+    /// is dropped. Bind the framebuffer first.
+    pub fn begin_frame<'a>(
+        &self,
+        gl: &'a glow::Context,
+        (width, height): (u32, u32),
+    ) -> FrameGuard<'a> {
+        unsafe {
+            let factor = |parameter: u32| gl.get_parameter_i32(parameter) as u32;
+            let guard = FrameGuard {
+                gl,
+                blend: gl.is_enabled(glow::BLEND),
+                blend_func: [
+                    factor(glow::BLEND_SRC_RGB),
+                    factor(glow::BLEND_DST_RGB),
+                    factor(glow::BLEND_SRC_ALPHA),
+                    factor(glow::BLEND_DST_ALPHA),
+                ],
+                #[cfg(not(target_arch = "wasm32"))]
+                framebuffer_srgb: gl.is_enabled(glow::FRAMEBUFFER_SRGB),
+                scissor_test: gl.is_enabled(glow::SCISSOR_TEST),
+                flush: false,
+            };
 
-                        // Top right triangle.
-                        push_v(l, t, [uv_l, uv_t]);
-                        push_v(r, b, [uv_r, uv_b]);
-                        push_v(r, t, [uv_r, uv_t]);
-                    }
+            gl.enable(glow::BLEND);
+            gl.blend_func_separate(
+                glow::SRC_ALPHA,
+                glow::ONE_MINUS_SRC_ALPHA,
+                glow::ONE,
+                glow::ONE_MINUS_SRC_ALPHA,
+            );
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                if self.info.framebuffer_srgb {
+                    gl.enable(glow::FRAMEBUFFER_SRGB);
+                } else {
+                    gl.disable(glow::FRAMEBUFFER_SRGB);
                 }
-
-                // We have no special case widgets to handle.
-                render::PrimitiveKind::Other(_) => (),
-            }
-        }
-
-        // Enter the final command.
-        match current_state {
-            State::Plain { start } => commands.push(PreparedCommand::Plain(start..vertices.len())),
-            State::Image { image_id, start } => {
-                commands.push(PreparedCommand::Image(image_id, start..vertices.len()))
             }
+            gl.disable(glow::SCISSOR_TEST);
+            gl.viewport(0, 0, width as i32, height as i32);
+            guard
         }
     }
 
-    /// Draws using the inner list of `Command`s to the given `display`.
+    /// Draws using the inner list of `Command`s to the given `display`, which expects the GL
+    /// state set up by `begin_frame`.
+    ///
+    /// Nothing is drawn after a `fill` for a framebuffer with a zero dimension.
     ///
     /// Note: If you require more granular control over rendering, you may want to use the `fill`
     /// and `commands` methods separately. This method is simply a convenience wrapper around those
     /// methods for the case that the user does not require accessing or modifying conrod's draw
     /// parameters, uniforms or generated draw commands.
-    pub fn draw(&self, gl: &glow::Context, image_map: &image::Map<Texture>) -> Result<(), String> {
+    ///
+    /// A GL error stops the drawing, and leaves the GL state as it was at the error.
+    pub fn draw(
+        &self,
+        gl: &glow::Context,
+        image_map: &image::Map<Texture>,
+    ) -> Result<(), RendererError> {
         macro_rules! verify {
             () => {{
                 verify!(gl)
@@ -959,7 +4237,7 @@ impl Renderer {
             ($gl:expr) => {{
                 let err = $gl.get_error();
                 if err != 0 {
-                    panic!("gl error {}", err);
+                    return Err(RendererError::GlError(err));
                 }
             }};
         }
@@ -970,142 +4248,99 @@ impl Renderer {
             )
         }
 
-        /// Upload as much vertices as possible in a batch instead of uploading
-        /// individual batches for every command. Since the VBO was allocated
-        /// in advance, there is a chance there isn't enough space for all
-        /// vertices. This function returns a range of all the vertices which
-        /// had been uploaded.
-        unsafe fn upload_vbo(
+        /// Upload `data` to the buffer bound to `target`, growing it to the next power of two if
+        /// it is `size` bytes and too small. The storage is given up first, so that the driver
+        /// can hand out new storage rather than wait for the last frame to be drawn from it.
+        unsafe fn upload_buffer<T>(
             gl: &glow::Context,
-            vertices: &[Vertex],
-            batch_start_offset: usize,
-        ) -> std::ops::Range<usize> {
-            let next_unbatched_vertex_idx = vertices
-                .len()
-                .min(batch_start_offset + Renderer::VBO_BUFFER_VERTEX_COUNT);
-            let current_range = batch_start_offset..next_unbatched_vertex_idx;
-            let x = as_raw_bytes(&vertices[current_range.clone()]);
-            gl.buffer_sub_data_u8_slice(glow::ARRAY_BUFFER, 0, x);
+            target: u32,
+            size: &mut usize,
+            data: &[T],
+        ) -> Result<(), RendererError> {
+            let bytes = as_raw_bytes(data);
+            if bytes.len() > *size {
+                *size = bytes.len().next_power_of_two();
+            }
+            gl.buffer_data_size(target, *size as i32, glow::STREAM_DRAW);
+            verify!(gl);
+            gl.buffer_sub_data_u8_slice(target, 0, bytes);
             verify!(gl);
-            current_range
+            Ok(())
         }
 
-        let glyph_texture = *self.glyph_cache.texture();
+        if self.geometry.indices.is_empty() {
+            return Ok(());
+        }
 
-        const NUM_VERTICES_IN_TRIANGLE: usize = 3;
+        let glyph_texture = *self.glyph_cache.borrow().texture();
 
         let mut has_scissor = false;
-        let mut current_uploaded;
 
+        // All the vertices and indices are uploaded once, and the commands draw ranges of them.
         unsafe {
             gl.use_program(Some(self.program.program));
             verify!();
-            gl.bind_buffer(glow::ARRAY_BUFFER, Some(self.vbo));
-            verify!();
             gl.bind_vertex_array(Some(self.vao));
             verify!();
-            // Upload as much vertices as possible. If there isn't enough space
-            // for all vertices, it will be handled later in the code.
-            current_uploaded = upload_vbo(gl, &self.vertices, 0);
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(self.vbo));
+            verify!();
+            let (mut vbo_size, mut ebo_size) = self.buffer_sizes.get();
+            upload_buffer(
+                gl,
+                glow::ARRAY_BUFFER,
+                &mut vbo_size,
+                &self.geometry.vertices,
+            )?;
+            upload_buffer(
+                gl,
+                glow::ELEMENT_ARRAY_BUFFER,
+                &mut ebo_size,
+                &self.geometry.indices,
+            )?;
+            self.buffer_sizes.set((vbo_size, ebo_size));
         }
 
         for command in &self.commands {
-            match command {
+            let (texture, range) = match command {
                 // Update the `scizzor` before continuing to draw.
-                PreparedCommand::Scizzor(scizzor) => unsafe {
-                    if !has_scissor {
-                        has_scissor = true;
-                        gl.enable(glow::SCISSOR_TEST);
-                        verify!();
-                    }
-                    gl.scissor(
-                        scizzor.left as i32,
-                        scizzor.bottom as i32,
-                        scizzor.width as i32,
-                        scizzor.height as i32,
-                    );
-                },
-
-                // Draw to the target with the given `draw` command.
-                PreparedCommand::Plain(range) | PreparedCommand::Image(_, range) => unsafe {
-                    /// Draw a batch of triangles from those currently uploaded
-                    /// to the VBO. Returns a range of the vertices actually
-                    /// drawn.
-                    ///
-                    /// This expects that `draw_range.start >= current_uploaded.start`
-                    /// and if this is violated you will trigger an underflow
-                    /// and an overflow and the OpenGL call will be accessing
-                    /// invalid memory.
-                    unsafe fn draw_available_batch(
-                        gl: &glow::Context,
-                        current_uploaded: &std::ops::Range<usize>,
-                        draw_range: std::ops::Range<usize>,
-                    ) -> std::ops::Range<usize> {
-                        let batched = {
-                            let batched =
-                                draw_range.start..draw_range.end.min(current_uploaded.end);
-                            // We always draw triangles. If the vertices for the
-                            // current command aren't all in the VBO, the rest
-                            // has to be split into another draw. We need to make
-                            // sure that all the vertices of the last triangle
-                            // are not split across multiple draw calls as this
-                            // will result in broken geometry.
-                            let rem = batched.len() % NUM_VERTICES_IN_TRIANGLE;
-                            batched.start..batched.end - rem
-                        };
-                        if batched.len() >= NUM_VERTICES_IN_TRIANGLE {
-                            gl.draw_arrays(
-                                glow::TRIANGLES,
-                                (batched.start - current_uploaded.start) as i32,
-                                batched.len() as i32,
-                            );
-                            verify!(gl);
-                        }
-                        batched
-                    }
-
-                    if range.len() < NUM_VERTICES_IN_TRIANGLE {
-                        continue;
-                    }
-                    match command {
-                        PreparedCommand::Plain(_) => {
-                            // Draw text and plain 2D geometry.
-                            gl.bind_texture(glow::TEXTURE_2D, Some(glyph_texture));
+                PreparedCommand::Scizzor(scizzor) => {
+                    unsafe {
+                        if !has_scissor {
+                            has_scissor = true;
+                            gl.enable(glow::SCISSOR_TEST);
                             verify!();
                         }
-                        PreparedCommand::Image(image_id, _) => {
-                            // Draw an image whose texture data lies within the `image_map` at the
-                            // given `id`.
-                            if let Some(image) = image_map.get(&image_id) {
-                                gl.bind_texture(glow::TEXTURE_2D, Some(image.texture));
-                                verify!();
-                            } else {
-                                gl.bind_texture(glow::TEXTURE_2D, None);
-                                verify!();
-                            }
-                        }
-                        _ => unreachable!(),
+                        // GL takes signed sizes, which no real framebuffer gets near.
+                        let to_gl = |value: u32| value.min(i32::MAX as u32) as i32;
+                        gl.scissor(
+                            to_gl(scizzor.left),
+                            to_gl(scizzor.bottom),
+                            to_gl(scizzor.width),
+                            to_gl(scizzor.height),
+                        );
                     }
-
-                    let drawn_range = draw_available_batch(gl, &current_uploaded, range.clone());
-                    let mut drawn_end = drawn_range.end;
-                    // We check to see if there are any vertices in the current
-                    // command which had not been drawn due to not being in the
-                    // current uploaded VBO. If there are some, we upload a new
-                    // batch of vertices and draw the remaining vertices in the
-                    // current command. This is repeated until all vertices in
-                    // the current command are drawn.
-                    while {
-                        let unbatched = drawn_end..range.end;
-                        unbatched.len() >= NUM_VERTICES_IN_TRIANGLE
-                    } {
-                        current_uploaded = upload_vbo(gl, &self.vertices, drawn_end);
-
-                        let drawn_range =
-                            draw_available_batch(gl, &current_uploaded, drawn_end..range.end);
-                        drawn_end = drawn_range.end;
-                    }
-                },
+                    continue;
+                }
+                // Draw text and plain 2D geometry.
+                PreparedCommand::Plain(range) => (Some(glyph_texture), range),
+                // Draw an image whose texture data lies within the `image_map` at the given `id`.
+                PreparedCommand::Image(image_id, range) => {
+                    (image_map.get(image_id).map(|image| image.texture), range)
+                }
+            };
+            if range.is_empty() {
+                continue;
+            }
+            unsafe {
+                gl.bind_texture(glow::TEXTURE_2D, texture);
+                verify!();
+                gl.draw_elements(
+                    glow::TRIANGLES,
+                    range.len() as i32,
+                    glow::UNSIGNED_INT,
+                    (range.start * std::mem::size_of::<u32>()) as i32,
+                );
+                verify!();
             }
         }
 
@@ -1126,4 +4361,150 @@ impl Renderer {
 
         Ok(())
     }
+
+    /// Draw over the contents of `framebuffer`, such as one with a texture of the application's
+    /// own as its color attachment, rather than over the bound framebuffer. It wraps `draw` in
+    /// `begin_frame`, and binds the default framebuffer afterwards, as glow can't tell which one
+    /// was bound before.
+    ///
+    /// `dimensions` are those of the framebuffer, which the renderer should have been filled for.
+    /// Nothing is cleared. On a framebuffer cleared to transparent, the alpha comes out as the
+    /// coverage of the UI and the colors premultiplied by it, ready to be blended with
+    /// `ONE, ONE_MINUS_SRC_ALPHA`. The color attachment should be sRGB if
+    /// `RendererInfo::framebuffer_srgb` is set, as the shaders then leave the conversion to it.
+    pub fn draw_to_framebuffer(
+        &self,
+        gl: &glow::Context,
+        image_map: &image::Map<Texture>,
+        framebuffer: glow::Framebuffer,
+        dimensions: (u32, u32),
+    ) -> Result<(), RendererError> {
+        unsafe {
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(framebuffer));
+        }
+        let frame = self.begin_frame(gl, dimensions);
+        let result = self.draw(gl, image_map);
+        drop(frame);
+        unsafe {
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn turns_down_regions_outside_of_the_texture() {
+        assert!(check_region(0, 0, 16, 8, (16, 8)).is_ok());
+        assert!(check_region(8, 4, 8, 4, (16, 8)).is_ok());
+        assert!(check_region(9, 0, 8, 1, (16, 8)).is_err());
+        assert!(check_region(0, 5, 1, 4, (16, 8)).is_err());
+        // Would wrap around to inside of the texture.
+        assert!(check_region(u32::MAX, 0, 2, 1, (16, 8)).is_err());
+        assert!(check_region(0, 1, 1, u32::MAX, (16, 8)).is_err());
+    }
+
+    #[test]
+    fn turns_down_pixels_of_the_wrong_length() {
+        assert!(prepare_pixels(PixelFormat::Rgba8, 2, 2, &[0; 16]).is_ok());
+        assert!(prepare_pixels(PixelFormat::Rgba8, 2, 2, &[0; 15]).is_err());
+        assert!(prepare_pixels(PixelFormat::Rgba8, 2, 2, &[0; 17]).is_err());
+        assert!(prepare_pixels(PixelFormat::R8, u32::MAX, u32::MAX, &[0; 4]).is_err());
+    }
+
+    #[test]
+    fn flips_the_rows_of_pixels() {
+        let (_, pixels) = prepare_pixels(PixelFormat::Rg8, 1, 3, &[1, 1, 2, 2, 3, 3]).unwrap();
+        assert_eq!(pixels, [3, 3, 2, 2, 1, 1]);
+    }
+
+    fn noto_sans() -> text::Font {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/assets/fonts/NotoSans/NotoSans-Regular.ttf"
+        );
+        text::Font::from_bytes(std::fs::read(path).unwrap()).unwrap()
+    }
+
+    /// Cache and push the glyphs of `s` at `size` on the line at `y`, as `tessellate` does for
+    /// text.
+    fn push_text(
+        tessellator: &mut Tessellator,
+        geometry: &mut Geometry,
+        s: &str,
+        size: f32,
+        y: f32,
+    ) -> Result<bool, text::rt::gpu_cache::CacheWriteErr> {
+        let font = noto_sans();
+        let scale = text::rt::Scale::uniform(size);
+        let glyphs: Vec<_> = font.layout(s, scale, text::rt::point(0.0, y)).collect();
+        let queue = |cache: &mut text::GlyphCache<'static>| {
+            for glyph in &glyphs {
+                cache.queue_glyph(0, glyph.clone());
+            }
+        };
+        let result = cache_glyphs(tessellator, geometry, &queue);
+        for glyph in &glyphs {
+            if let Ok(Some((uv_rect, screen_rect))) = tessellator.cache.rect_for(0, glyph) {
+                push_glyph(
+                    geometry,
+                    (0, glyph),
+                    uv_rect,
+                    screen_rect,
+                    [1.0; 4],
+                    (256, 256),
+                );
+            }
+        }
+        result
+    }
+
+    /// Check that every glyph quad shows its glyph where the cache has it now.
+    fn assert_quads_point_at_the_cache(tessellator: &Tessellator, geometry: &Geometry) {
+        assert!(!geometry.glyphs.is_empty());
+        for placed in &geometry.glyphs {
+            let (uv_rect, _) = tessellator
+                .cache
+                .rect_for(placed.cache_id, &placed.glyph)
+                .unwrap()
+                .unwrap();
+            let quad = &geometry.vertices[placed.first_vertex..placed.first_vertex + 4];
+            let tex_coords: Vec<_> = quad.iter().map(|vertex| vertex.tex_coords).collect();
+            assert_eq!(tex_coords, glyph_tex_coords(uv_rect));
+            assert!(quad.iter().all(|vertex| vertex.color[3] == 1.0));
+        }
+    }
+
+    #[test]
+    fn points_earlier_text_at_the_grown_glyph_cache() {
+        let mut tessellator = Tessellator::new((32, 32), (1024, 1024));
+        let mut geometry = Geometry::default();
+        assert_eq!(
+            push_text(&mut tessellator, &mut geometry, "ab", 10.0, 10.0),
+            Ok(false)
+        );
+        let fox = "The quick brown fox jumps over the lazy dog";
+        assert_eq!(
+            push_text(&mut tessellator, &mut geometry, fox, 20.0, 40.0),
+            Ok(true)
+        );
+        assert_quads_point_at_the_cache(&tessellator, &geometry);
+    }
+
+    #[test]
+    fn points_earlier_text_at_the_grown_glyph_cache_when_the_rest_doesnt_fit() {
+        let mut tessellator = Tessellator::new((32, 32), (64, 64));
+        let mut geometry = Geometry::default();
+        assert_eq!(
+            push_text(&mut tessellator, &mut geometry, "ab", 10.0, 10.0),
+            Ok(false)
+        );
+        let alphabet = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+        assert!(push_text(&mut tessellator, &mut geometry, alphabet, 40.0, 60.0).is_err());
+        assert_eq!(tessellator.cache.dimensions(), (64, 64));
+        assert_quads_point_at_the_cache(&tessellator, &geometry);
+    }
 }
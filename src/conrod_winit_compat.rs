@@ -0,0 +1,237 @@
+// Functions for converting between the types of the `winit` version this
+// crate uses and `conrod_core`.
+//
+// The `v023_*` macros work with whatever `winit` is in the caller's crate
+// root, which is why they exist, but they have to be instantiated in each
+// module which uses them. For the `winit` this crate depends on, these
+// functions do the same with real types, and `v023_conversion_fns!` only
+// wraps them.
+
+// The macros come from conrod_winit, where they are expanded in other crates
+// and not linted.
+#![allow(
+    clippy::clone_on_copy,
+    clippy::redundant_field_names,
+    clippy::useless_conversion
+)]
+
+use conrod_core::event::Input;
+
+/// What the conversions need to know about the window the events are for.
+///
+/// # Example
+///
+/// ```
+/// use conrod_floatwin_demo_glow::conrod_winit_compat::{convert_window_event, WindowInfo};
+/// use winit::dpi::PhysicalSize;
+///
+/// let window = WindowInfo {
+///     scale_factor: 2.0,
+///     inner_size: PhysicalSize::new(1600, 1200),
+/// };
+/// let resized = winit::event::WindowEvent::Resized(PhysicalSize::new(1600, 1200));
+/// assert_eq!(
+///     convert_window_event(&resized, &window),
+///     Some(conrod_core::event::Input::Resize(800.0, 600.0)),
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WindowInfo {
+    pub scale_factor: f64,
+    /// The size of the window's client area in physical pixels.
+    pub inner_size: winit::dpi::PhysicalSize<u32>,
+}
+
+impl WindowInfo {
+    pub fn of(window: &winit::window::Window) -> Self {
+        WindowInfo {
+            scale_factor: window.scale_factor(),
+            inner_size: window.inner_size(),
+        }
+    }
+
+    // The methods of `winit::window::Window` which the macros call.
+
+    pub fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    pub fn inner_size(&self) -> winit::dpi::PhysicalSize<u32> {
+        self.inner_size
+    }
+}
+
+/// Map winit's key to a conrod `Key`.
+pub fn convert_key(keycode: winit::event::VirtualKeyCode) -> conrod_core::input::keyboard::Key {
+    crate::v023_convert_key!(keycode)
+}
+
+/// Convert a `winit::event::MouseButton` to a `conrod_core::input::MouseButton`.
+pub fn convert_mouse_button(
+    mouse_button: winit::event::MouseButton,
+) -> conrod_core::input::MouseButton {
+    crate::v023_convert_mouse_button!(mouse_button)
+}
+
+/// Convert a conrod mouse cursor to the corresponding winit cursor icon.
+pub fn convert_mouse_cursor(cursor: conrod_core::cursor::MouseCursor) -> winit::window::CursorIcon {
+    crate::v023_convert_mouse_cursor!(cursor)
+}
+
+/// Convert a `winit::event::WindowEvent` to conrod input, if conrod has an
+/// equivalent.
+pub fn convert_window_event(
+    event: &winit::event::WindowEvent,
+    window: &WindowInfo,
+) -> Option<Input> {
+    crate::v023_convert_window_event!(event, window)
+}
+
+/// Convert a `winit::event::Event` to conrod input, if it is a window event
+/// which conrod has an equivalent for.
+pub fn convert_event<T>(event: &winit::event::Event<T>, window: &WindowInfo) -> Option<Input> {
+    match event {
+        winit::event::Event::WindowEvent { event, .. } => convert_window_event(event, window),
+        _ => None,
+    }
+}
+
+/// Converts winit events to conrod input like `convert_event`, and also
+/// keeps conrod's modifier keys in line with winit's `ModifiersChanged`.
+///
+/// conrod only learns about the modifiers from the presses and releases of
+/// their keys, which don't arrive while the window doesn't have the focus.
+/// A Shift held down before clicking into the window would otherwise be
+/// missed, as would a Ctrl released elsewhere. The converter remembers which
+/// modifiers conrod has been given, and presses or releases the left key of
+/// each modifier which winit says changed.
+pub struct EventConverter {
+    /// The modifiers which conrod has been told are down.
+    modifiers: winit::event::ModifiersState,
+}
+
+impl Default for EventConverter {
+    fn default() -> Self {
+        EventConverter::new()
+    }
+}
+
+impl EventConverter {
+    /// The left key of each modifier, as given to conrod.
+    const MODIFIER_KEYS: [(winit::event::ModifiersState, conrod_core::input::Key); 4] = [
+        (
+            winit::event::ModifiersState::SHIFT,
+            conrod_core::input::Key::LShift,
+        ),
+        (
+            winit::event::ModifiersState::CTRL,
+            conrod_core::input::Key::LCtrl,
+        ),
+        (
+            winit::event::ModifiersState::ALT,
+            conrod_core::input::Key::LAlt,
+        ),
+        (
+            winit::event::ModifiersState::LOGO,
+            conrod_core::input::Key::LGui,
+        ),
+    ];
+
+    pub fn new() -> Self {
+        EventConverter {
+            modifiers: winit::event::ModifiersState::empty(),
+        }
+    }
+
+    /// Convert a `winit::event::WindowEvent` to conrod input, which is
+    /// empty if conrod has no equivalent.
+    pub fn convert_window_event(
+        &mut self,
+        event: &winit::event::WindowEvent,
+        window: &WindowInfo,
+    ) -> Vec<Input> {
+        use conrod_core::input::Button;
+        match event {
+            winit::event::WindowEvent::ModifiersChanged(modifiers) => {
+                let mut inputs = Vec::new();
+                for &(modifier, key) in &Self::MODIFIER_KEYS {
+                    let is_down = modifiers.contains(modifier);
+                    if is_down != self.modifiers.contains(modifier) {
+                        inputs.push(if is_down {
+                            Input::Press(Button::Keyboard(key))
+                        } else {
+                            Input::Release(Button::Keyboard(key))
+                        });
+                    }
+                }
+                self.modifiers = *modifiers;
+                inputs
+            }
+            _ => {
+                let input = convert_window_event(event, window);
+                if let Some(Input::Press(Button::Keyboard(key))) = &input {
+                    self.modifiers.insert(Self::modifier_of(*key));
+                }
+                if let Some(Input::Release(Button::Keyboard(key))) = &input {
+                    self.modifiers.remove(Self::modifier_of(*key));
+                }
+                input.into_iter().collect()
+            }
+        }
+    }
+
+    /// Convert a `winit::event::Event` to conrod input, which is empty if it
+    /// isn't a window event which conrod has an equivalent for.
+    pub fn convert_event<T>(
+        &mut self,
+        event: &winit::event::Event<T>,
+        window: &WindowInfo,
+    ) -> Vec<Input> {
+        match event {
+            winit::event::Event::WindowEvent { event, .. } => {
+                self.convert_window_event(event, window)
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// The modifier which conrod counts `key` as, if any.
+    fn modifier_of(key: conrod_core::input::Key) -> winit::event::ModifiersState {
+        use conrod_core::input::Key;
+        match key {
+            Key::LShift | Key::RShift => winit::event::ModifiersState::SHIFT,
+            Key::LCtrl | Key::RCtrl => winit::event::ModifiersState::CTRL,
+            Key::LAlt | Key::RAlt => winit::event::ModifiersState::ALT,
+            Key::LGui | Key::RGui => winit::event::ModifiersState::LOGO,
+            _ => winit::event::ModifiersState::empty(),
+        }
+    }
+}
+
+/// Where the input method should show its preedit text and candidates for
+/// the widget with the keyboard at `rect`, in points from the top left.
+pub fn convert_ime_position(
+    rect: conrod_core::Rect,
+    window: &WindowInfo,
+) -> winit::dpi::LogicalPosition<f64> {
+    crate::v023_convert_ime_position!(rect, window)
+}
+
+/// A clipboard shortcut, which conrod has no input for. Pasting is done by
+/// the app with `Input::Text`, while copying is left to the widgets which
+/// keep their own selection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClipboardShortcut {
+    Copy,
+    Cut,
+    Paste,
+}
+
+/// Recognise the press of Ctrl+C, Ctrl+X or Ctrl+V, or Cmd on macOS, given
+/// the `modifiers` held down.
+pub fn convert_clipboard_shortcut(
+    event: &winit::event::WindowEvent,
+    modifiers: winit::event::ModifiersState,
+) -> Option<ClipboardShortcut> {
+    crate::v023_convert_clipboard_shortcut!(event, modifiers)
+}
@@ -44,12 +44,18 @@ macro_rules! v023_convert_window_event {
             },
 
             winit::event::WindowEvent::ReceivedCharacter(ch) => {
+                // winit before 0.27 has no composition events, so the text committed by an input method
+                // arrives here too, one character at a time, and so do the characters made with
+                // dead keys. The preedit text is shown by the input method itself, next to the
+                // position given with `v023_convert_ime_position`.
                 let string = match ch {
                     // Ignore control characters and return ascii for Text event (like sdl2).
+                    // This also drops those typed with Ctrl, like `\u{16}` for Ctrl+V.
                     '\u{7f}' | // Delete
                     '\u{1b}' | // Escape
                     '\u{8}'  | // Backspace
                     '\r' | '\n' | '\t' => "".to_string(),
+                    _ if ch.is_control() => "".to_string(),
                     _ => ch.to_string()
                 };
                 Some(conrod_core::event::Input::Text(string).into())
@@ -91,6 +97,13 @@ macro_rules! v023_convert_window_event {
                 Some(conrod_core::event::Input::Motion(motion).into())
             },
 
+            // Move the cursor just outside of the window so that nothing stays hovered. There is
+            // nothing to do when it enters again, as a `CursorMoved` follows.
+            winit::event::WindowEvent::CursorLeft { .. } => {
+                let motion = conrod_core::input::Motion::MouseCursor { x: tx(-1.0), y: ty(-1.0) };
+                Some(conrod_core::event::Input::Motion(motion).into())
+            },
+
             winit::event::WindowEvent::MouseWheel { delta, .. } => match delta {
                 winit::event::MouseScrollDelta::PixelDelta(delta) => {
                     let winit::dpi::LogicalPosition { x, y } = delta.to_logical::<f64>(scale_factor);
@@ -138,6 +151,57 @@ macro_rules! v023_convert_event {
     }};
 }
 
+/// A macro for recognising the presses of the clipboard shortcuts, which conrod has no input for.
+///
+/// Expects a `winit::WindowEvent` and the `winit::event::ModifiersState` currently held down.
+/// Returns an `Option<ClipboardShortcut>` for Ctrl+C, Ctrl+X and Ctrl+V, or Cmd on macOS.
+///
+/// Requires that the `winit` crate is in the crate root.
+#[macro_export]
+macro_rules! v023_convert_clipboard_shortcut {
+    ($event:expr, $modifiers:expr) => {{
+        let command = if cfg!(target_os = "macos") {
+            winit::event::ModifiersState::LOGO
+        } else {
+            winit::event::ModifiersState::CTRL
+        };
+        match $event {
+            winit::event::WindowEvent::KeyboardInput {
+                input: winit::event::KeyboardInput {
+                    state: winit::event::ElementState::Pressed,
+                    virtual_keycode: Some(key),
+                    ..
+                },
+                ..
+            } if $modifiers == command => match key {
+                winit::event::VirtualKeyCode::C => Some($crate::conrod_winit_compat::ClipboardShortcut::Copy),
+                winit::event::VirtualKeyCode::X => Some($crate::conrod_winit_compat::ClipboardShortcut::Cut),
+                winit::event::VirtualKeyCode::V => Some($crate::conrod_winit_compat::ClipboardShortcut::Paste),
+                _ => None,
+            },
+            _ => None,
+        }
+    }};
+}
+
+/// A macro for converting the rect of the widget with the keyboard to where the input method
+/// should show its preedit and candidates, for `winit::window::Window::set_ime_position`.
+///
+/// Expects a `conrod_core::Rect` and a reference to a window implementing `WinitWindow`.
+/// Returns a `winit::dpi::LogicalPosition<f64>` at the bottom left of the rect. conrod keeps the
+/// caret of a text box to itself, so this is as close as it gets.
+///
+/// Requires that both the `conrod_core` and `winit` crates are in the crate root.
+#[macro_export]
+macro_rules! v023_convert_ime_position {
+    ($rect:expr, $window:expr) => {{
+        let scale_factor: f64 = $window.scale_factor();
+        let (win_w, win_h): (f64, f64) = $window.inner_size().to_logical::<f64>(scale_factor).into();
+        let rect: conrod_core::Rect = $rect;
+        winit::dpi::LogicalPosition::new(rect.left() + win_w / 2.0, win_h / 2.0 - rect.bottom())
+    }};
+}
+
 /// Convert a given conrod mouse cursor to the corresponding winit cursor type.
 ///
 /// Expects a `conrod_core::cursor::MouseCursor`, returns a `winit::MouseCursor`.
@@ -150,30 +214,28 @@ macro_rules! v023_convert_mouse_cursor {
     }};
 }
 
+/// Generate a set of conversion functions for converting between types of the crate's versions of
+/// `winit` and `conrod_core`, in the module where it is used.
+///
+/// These only wrap the functions in `conrod_winit_compat`, which can be used directly instead.
 #[macro_export]
 macro_rules! v023_conversion_fns {
     () => {
-        /// Generate a set of conversion functions for converting between types of the crate's versions of
-        /// `winit` and `conrod_core`.
         /// Maps winit's key to a conrod `Key`.
-        ///
-        /// Expects a `winit::VirtualKeyCode` as input and returns a `conrod_core::input::keyboard::Key`.
-        ///
-        /// Requires that both the `winit` and `conrod_core` crates exist within the crate root.
         pub fn convert_key(keycode: winit::event::VirtualKeyCode) -> conrod_core::input::keyboard::Key {
-            $crate::v023_convert_key!(keycode)
+            $crate::conrod_winit_compat::convert_key(keycode)
         }
 
         /// Convert a `winit::MouseButton` to a `conrod_core::input::MouseButton`.
         pub fn convert_mouse_button(
             mouse_button: winit::event::MouseButton,
         ) -> conrod_core::input::MouseButton {
-            $crate::v023_convert_mouse_button!(mouse_button)
+            $crate::conrod_winit_compat::convert_mouse_button(mouse_button)
         }
 
         /// Convert a given conrod mouse cursor to the corresponding winit cursor type.
         pub fn convert_mouse_cursor(cursor: conrod_core::cursor::MouseCursor) -> winit::window::CursorIcon {
-            $crate::v023_convert_mouse_cursor!(cursor)
+            $crate::conrod_winit_compat::convert_mouse_cursor(cursor)
         }
 
         /// A function for converting a `winit::WindowEvent` to a `conrod_core::event::Input`.
@@ -181,7 +243,8 @@ macro_rules! v023_conversion_fns {
             event: &winit::event::WindowEvent,
             window: &winit::window::Window,
         ) -> Option<conrod_core::event::Input> {
-            $crate::v023_convert_window_event!(event, window)
+            let window = $crate::conrod_winit_compat::WindowInfo::of(window);
+            $crate::conrod_winit_compat::convert_window_event(event, &window)
         }
 
         /// A function for converting a `winit::Event` to a `conrod_core::event::Input`.
@@ -189,7 +252,25 @@ macro_rules! v023_conversion_fns {
             event: &winit::event::Event<T>,
             window: &winit::window::Window,
         ) -> Option<conrod_core::event::Input> {
-            $crate::v023_convert_event!(event, window)
+            let window = $crate::conrod_winit_compat::WindowInfo::of(window);
+            $crate::conrod_winit_compat::convert_event(event, &window)
+        }
+
+        /// Where the input method should show up for the widget with the keyboard at `rect`.
+        pub fn convert_ime_position(
+            rect: conrod_core::Rect,
+            window: &winit::window::Window,
+        ) -> winit::dpi::LogicalPosition<f64> {
+            let window = $crate::conrod_winit_compat::WindowInfo::of(window);
+            $crate::conrod_winit_compat::convert_ime_position(rect, &window)
+        }
+
+        /// Recognise the presses of the clipboard shortcuts, given the modifiers held down.
+        pub fn convert_clipboard_shortcut(
+            event: &winit::event::WindowEvent,
+            modifiers: winit::event::ModifiersState,
+        ) -> Option<$crate::conrod_winit_compat::ClipboardShortcut> {
+            $crate::conrod_winit_compat::convert_clipboard_shortcut(event, modifiers)
         }
     };
 }
@@ -0,0 +1,431 @@
+//! Conversion macros for winit 0.27 and 0.28.
+//!
+//! These mirror the `v023_*` macros. Newer winit renamed a number of the numpad keys, added
+//! `WindowEvent::Ime` and `WindowEvent::ModifiersChanged`, and 0.28 added the touchpad gesture
+//! events, so the older macros no longer compile against it.
+//!
+//! Like the `v023_*` macros, nothing here depends on the crate's own `winit` version; the macros
+//! are expanded against whichever `winit` the calling crate has in its root.
+
+/// Maps winit's key to a conrod `Key`.
+///
+/// Expects a `winit::event::VirtualKeyCode` as input and returns a
+/// `conrod_core::input::keyboard::Key`.
+///
+/// Requires that both the `winit` and `conrod_core` crates exist within the crate root.
+#[macro_export]
+macro_rules! v027_convert_key {
+    ($keycode:expr) => {{
+        use conrod_core::input::keyboard::Key;
+        use winit::event::VirtualKeyCode as K;
+        match $keycode {
+            K::Key0 => Key::D0,
+            K::Key1 => Key::D1,
+            K::Key2 => Key::D2,
+            K::Key3 => Key::D3,
+            K::Key4 => Key::D4,
+            K::Key5 => Key::D5,
+            K::Key6 => Key::D6,
+            K::Key7 => Key::D7,
+            K::Key8 => Key::D8,
+            K::Key9 => Key::D9,
+            K::A => Key::A,
+            K::B => Key::B,
+            K::C => Key::C,
+            K::D => Key::D,
+            K::E => Key::E,
+            K::F => Key::F,
+            K::G => Key::G,
+            K::H => Key::H,
+            K::I => Key::I,
+            K::J => Key::J,
+            K::K => Key::K,
+            K::L => Key::L,
+            K::M => Key::M,
+            K::N => Key::N,
+            K::O => Key::O,
+            K::P => Key::P,
+            K::Q => Key::Q,
+            K::R => Key::R,
+            K::S => Key::S,
+            K::T => Key::T,
+            K::U => Key::U,
+            K::V => Key::V,
+            K::W => Key::W,
+            K::X => Key::X,
+            K::Y => Key::Y,
+            K::Z => Key::Z,
+            K::Escape => Key::Escape,
+            K::F1 => Key::F1,
+            K::F2 => Key::F2,
+            K::F3 => Key::F3,
+            K::F4 => Key::F4,
+            K::F5 => Key::F5,
+            K::F6 => Key::F6,
+            K::F7 => Key::F7,
+            K::F8 => Key::F8,
+            K::F9 => Key::F9,
+            K::F10 => Key::F10,
+            K::F11 => Key::F11,
+            K::F12 => Key::F12,
+            K::F13 => Key::F13,
+            K::F14 => Key::F14,
+            K::F15 => Key::F15,
+            K::F16 => Key::F16,
+            K::F17 => Key::F17,
+            K::F18 => Key::F18,
+            K::F19 => Key::F19,
+            K::F20 => Key::F20,
+            K::F21 => Key::F21,
+            K::F22 => Key::F22,
+            K::F23 => Key::F23,
+            K::F24 => Key::F24,
+            K::Snapshot => Key::PrintScreen,
+            K::Scroll => Key::ScrollLock,
+            K::Pause => Key::Pause,
+            K::Insert => Key::Insert,
+            K::Home => Key::Home,
+            K::Delete => Key::Delete,
+            K::End => Key::End,
+            K::PageDown => Key::PageDown,
+            K::PageUp => Key::PageUp,
+            K::Left => Key::Left,
+            K::Up => Key::Up,
+            K::Right => Key::Right,
+            K::Down => Key::Down,
+            K::Back => Key::Backspace,
+            K::Return => Key::Return,
+            K::Space => Key::Space,
+            K::Caret => Key::Caret,
+            K::Numlock => Key::NumLockClear,
+            K::Numpad0 => Key::NumPad0,
+            K::Numpad1 => Key::NumPad1,
+            K::Numpad2 => Key::NumPad2,
+            K::Numpad3 => Key::NumPad3,
+            K::Numpad4 => Key::NumPad4,
+            K::Numpad5 => Key::NumPad5,
+            K::Numpad6 => Key::NumPad6,
+            K::Numpad7 => Key::NumPad7,
+            K::Numpad8 => Key::NumPad8,
+            K::Numpad9 => Key::NumPad9,
+            K::NumpadAdd => Key::NumPadPlus,
+            K::NumpadDivide => Key::NumPadDivide,
+            K::NumpadDecimal => Key::NumPadPeriod,
+            K::NumpadComma => Key::NumPadComma,
+            K::NumpadEnter => Key::NumPadEnter,
+            K::NumpadEquals => Key::NumPadEquals,
+            K::NumpadMultiply => Key::NumPadMultiply,
+            K::NumpadSubtract => Key::NumPadMinus,
+            K::Apostrophe => Key::Quote,
+            K::Apps => Key::Application,
+            K::Asterisk => Key::Asterisk,
+            K::At => Key::At,
+            K::Backslash => Key::Backslash,
+            K::Calculator => Key::Calculator,
+            K::Capital => Key::CapsLock,
+            K::Colon => Key::Colon,
+            K::Comma => Key::Comma,
+            K::Equals => Key::Equals,
+            K::Grave => Key::Backquote,
+            K::LAlt => Key::LAlt,
+            K::LBracket => Key::LeftBracket,
+            K::LControl => Key::LCtrl,
+            K::LShift => Key::LShift,
+            K::LWin => Key::LGui,
+            K::Mail => Key::Mail,
+            K::MediaSelect => Key::MediaSelect,
+            K::MediaStop => Key::AudioStop,
+            K::Minus => Key::Minus,
+            K::Mute => Key::Mute,
+            K::MyComputer => Key::Computer,
+            K::NavigateForward => Key::AcForward,
+            K::NavigateBackward => Key::AcBack,
+            K::NextTrack => Key::AudioNext,
+            K::Period => Key::Period,
+            K::PlayPause => Key::AudioPlay,
+            K::Plus => Key::Plus,
+            K::Power => Key::Power,
+            K::PrevTrack => Key::AudioPrev,
+            K::RAlt => Key::RAlt,
+            K::RBracket => Key::RightBracket,
+            K::RControl => Key::RCtrl,
+            K::RShift => Key::RShift,
+            K::RWin => Key::RGui,
+            K::Semicolon => Key::Semicolon,
+            K::Slash => Key::Slash,
+            K::Sleep => Key::Sleep,
+            K::Stop => Key::Stop,
+            K::Sysrq => Key::Sysreq,
+            K::Tab => Key::Tab,
+            K::Underline => Key::Underscore,
+            K::VolumeDown => Key::VolumeDown,
+            K::VolumeUp => Key::VolumeUp,
+            K::WebBack => Key::AcBack,
+            K::WebFavorites => Key::AcBookmarks,
+            K::WebForward => Key::AcForward,
+            K::WebHome => Key::AcHome,
+            K::WebRefresh => Key::AcRefresh,
+            K::WebSearch => Key::AcSearch,
+            K::WebStop => Key::AcStop,
+            K::Copy => Key::Copy,
+            K::Paste => Key::Paste,
+            K::Cut => Key::Cut,
+            _ => Key::Unknown,
+        }
+    }};
+}
+
+/// Maps winit's mouse button to conrod's mouse button.
+///
+/// Expects a `winit::event::MouseButton` as input and returns a
+/// `conrod_core::input::MouseButton` as output.
+///
+/// Requires that both the `conrod_core` and `winit` crates are in the crate root.
+#[macro_export]
+macro_rules! v027_convert_mouse_button {
+    ($mouse_button:expr) => {{
+        use conrod_core::input::MouseButton;
+        match $mouse_button {
+            winit::event::MouseButton::Left => MouseButton::Left,
+            winit::event::MouseButton::Right => MouseButton::Right,
+            winit::event::MouseButton::Middle => MouseButton::Middle,
+            winit::event::MouseButton::Other(0) => MouseButton::X1,
+            winit::event::MouseButton::Other(1) => MouseButton::X2,
+            winit::event::MouseButton::Other(2) => MouseButton::Button6,
+            winit::event::MouseButton::Other(3) => MouseButton::Button7,
+            winit::event::MouseButton::Other(4) => MouseButton::Button8,
+            _ => MouseButton::Unknown,
+        }
+    }};
+}
+
+/// A macro for converting a `winit::event::WindowEvent` to a `Option<conrod_core::event::Input>`.
+///
+/// Expects a `winit::event::WindowEvent` and a reference to a window implementing `WinitWindow`.
+/// Returns an `Option<conrod_core::event::Input>`.
+#[macro_export]
+macro_rules! v027_convert_window_event {
+    ($event:expr, $window:expr) => {{
+        // The window size in points.
+        let scale_factor: f64 = $window.scale_factor();
+        let (win_w, win_h): (f64, f64) = $window.inner_size().to_logical::<f64>(scale_factor).into();
+
+        // Translate the coordinates from top-left-origin-with-y-down to centre-origin-with-y-up.
+        let tx = |x: conrod_core::Scalar| x - win_w / 2.0;
+        let ty = |y: conrod_core::Scalar| -(y - win_h / 2.0);
+
+        // Functions for converting keys and mouse buttons.
+        let map_key = |key: winit::event::VirtualKeyCode| $crate::v027_convert_key!(key);
+        let map_mouse = |button: winit::event::MouseButton| $crate::v027_convert_mouse_button!(button);
+
+        match $event {
+            winit::event::WindowEvent::Resized(physical_size) => {
+                let winit::dpi::LogicalSize { width, height } = physical_size.to_logical(scale_factor);
+                Some(conrod_core::event::Input::Resize(width, height).into())
+            },
+
+            winit::event::WindowEvent::ReceivedCharacter(ch) => {
+                let string = match ch {
+                    // Ignore control characters and return ascii for Text event (like sdl2).
+                    '\u{7f}' | // Delete
+                    '\u{1b}' | // Escape
+                    '\u{8}'  | // Backspace
+                    '\r' | '\n' | '\t' => "".to_string(),
+                    _ => ch.to_string()
+                };
+                Some(conrod_core::event::Input::Text(string).into())
+            },
+
+            // Text composed with an input method. The preedit text is not shown, as conrod has
+            // nowhere to put it.
+            winit::event::WindowEvent::Ime(winit::event::Ime::Commit(text)) =>
+                Some(conrod_core::event::Input::Text(text.clone()).into()),
+
+            // conrod keeps track of the modifiers from the key presses itself.
+            winit::event::WindowEvent::ModifiersChanged(_) => None,
+
+            winit::event::WindowEvent::Focused(focused) =>
+                Some(conrod_core::event::Input::Focus(focused.clone()).into()),
+
+            winit::event::WindowEvent::KeyboardInput { input, .. } => {
+                input.virtual_keycode.map(|key| {
+                    match input.state {
+                        winit::event::ElementState::Pressed =>
+                            conrod_core::event::Input::Press(conrod_core::input::Button::Keyboard(map_key(key))).into(),
+                        winit::event::ElementState::Released =>
+                            conrod_core::event::Input::Release(conrod_core::input::Button::Keyboard(map_key(key))).into(),
+                    }
+                })
+            },
+
+            winit::event::WindowEvent::Touch(winit::event::Touch { phase, location, id, .. }) => {
+                let winit::dpi::LogicalPosition { x, y } = location.to_logical::<f64>(scale_factor);
+                let phase = match phase {
+                    winit::event::TouchPhase::Started => conrod_core::input::touch::Phase::Start,
+                    winit::event::TouchPhase::Moved => conrod_core::input::touch::Phase::Move,
+                    winit::event::TouchPhase::Cancelled => conrod_core::input::touch::Phase::Cancel,
+                    winit::event::TouchPhase::Ended => conrod_core::input::touch::Phase::End,
+                };
+                let xy = [tx(x), ty(y)];
+                let id = conrod_core::input::touch::Id::new(id.clone());
+                let touch = conrod_core::input::Touch { phase: phase, id: id, xy: xy };
+                Some(conrod_core::event::Input::Touch(touch).into())
+            }
+
+            winit::event::WindowEvent::CursorMoved { position, .. } => {
+                let winit::dpi::LogicalPosition { x, y } = position.to_logical::<f64>(scale_factor);
+                let x = tx(x as conrod_core::Scalar);
+                let y = ty(y as conrod_core::Scalar);
+                let motion = conrod_core::input::Motion::MouseCursor { x: x, y: y };
+                Some(conrod_core::event::Input::Motion(motion).into())
+            },
+
+            // Move the cursor just outside of the window so that nothing stays hovered. There is
+            // nothing to do when it enters again, as a `CursorMoved` follows.
+            winit::event::WindowEvent::CursorLeft { .. } => {
+                let motion = conrod_core::input::Motion::MouseCursor { x: tx(-1.0), y: ty(-1.0) };
+                Some(conrod_core::event::Input::Motion(motion).into())
+            },
+
+            winit::event::WindowEvent::MouseWheel { delta, .. } => match delta {
+                winit::event::MouseScrollDelta::PixelDelta(delta) => {
+                    let winit::dpi::LogicalPosition { x, y } = delta.to_logical::<f64>(scale_factor);
+                    let x = x as conrod_core::Scalar;
+                    let y = -y as conrod_core::Scalar;
+                    let motion = conrod_core::input::Motion::Scroll { x: x, y: y };
+                    Some(conrod_core::event::Input::Motion(motion).into())
+                },
+
+                winit::event::MouseScrollDelta::LineDelta(x, y) => {
+                    // This should be configurable (we should provide a LineDelta event to allow for this).
+                    const ARBITRARY_POINTS_PER_LINE_FACTOR: conrod_core::Scalar = 10.0;
+                    let x = ARBITRARY_POINTS_PER_LINE_FACTOR * x.clone() as conrod_core::Scalar;
+                    let y = ARBITRARY_POINTS_PER_LINE_FACTOR * -y.clone() as conrod_core::Scalar;
+                    Some(conrod_core::event::Input::Motion(conrod_core::input::Motion::Scroll { x: x, y: y }).into())
+                },
+            },
+
+            winit::event::WindowEvent::MouseInput { state, button, .. } => match state {
+                winit::event::ElementState::Pressed =>
+                    Some(conrod_core::event::Input::Press(conrod_core::input::Button::Mouse(map_mouse(button.clone()))).into()),
+                winit::event::ElementState::Released =>
+                    Some(conrod_core::event::Input::Release(conrod_core::input::Button::Mouse(map_mouse(button.clone()))).into()),
+            },
+
+            _ => None,
+        }
+    }};
+}
+
+/// Like `v027_convert_window_event`, but for winit 0.28, which added touchpad gesture events.
+///
+/// conrod has no zoom or rotate input, so `TouchpadMagnify`, `SmartMagnify` and
+/// `TouchpadRotate` are dropped. Use `v028_convert_gesture` to handle the pinch and rotate
+/// gestures separately.
+#[macro_export]
+macro_rules! v028_convert_window_event {
+    ($event:expr, $window:expr) => {{
+        match $event {
+            winit::event::WindowEvent::TouchpadMagnify { .. } => None,
+            winit::event::WindowEvent::SmartMagnify { .. } => None,
+            winit::event::WindowEvent::TouchpadRotate { .. } => None,
+            event => $crate::v027_convert_window_event!(event, $window),
+        }
+    }};
+}
+
+/// A macro for converting a `winit::event::Event` to a `conrod_core::event::Input`.
+///
+/// Expects a `winit::event::Event` and a reference to a window implementing `WinitWindow`.
+/// Returns an `Option<conrod_core::event::Input>`.
+///
+/// Invocations of this macro require that a version of the `winit` and `conrod_core` crates are
+/// available in the crate root.
+#[macro_export]
+macro_rules! v027_convert_event {
+    ($event:expr, $window:expr) => {{
+        match $event {
+            winit::event::Event::WindowEvent { event, .. } => $crate::v027_convert_window_event!(event, $window),
+            _ => None,
+        }
+    }};
+}
+
+/// Like `v027_convert_event`, for winit 0.28.
+#[macro_export]
+macro_rules! v028_convert_event {
+    ($event:expr, $window:expr) => {{
+        match $event {
+            winit::event::Event::WindowEvent { event, .. } => $crate::v028_convert_window_event!(event, $window),
+            _ => None,
+        }
+    }};
+}
+
+/// Convert a given conrod mouse cursor to the corresponding winit cursor type.
+///
+/// Expects a `conrod_core::cursor::MouseCursor`, returns a `winit::window::CursorIcon`. The
+/// cursor icons didn't change since winit 0.21.
+///
+/// Requires that both the `conrod_core` and `winit` crates are in the crate root.
+#[macro_export]
+macro_rules! v027_convert_mouse_cursor {
+    ($cursor:expr) => {{
+        ::conrod_winit::v021_convert_mouse_cursor!($cursor)
+    }};
+}
+
+#[macro_export]
+macro_rules! v027_conversion_fns {
+    () => {
+        $crate::conversion_fns_for_winit!(v027_convert_window_event, v027_convert_event);
+    };
+}
+
+#[macro_export]
+macro_rules! v028_conversion_fns {
+    () => {
+        $crate::conversion_fns_for_winit!(v028_convert_window_event, v028_convert_event);
+    };
+}
+
+/// Generate a set of conversion functions for winit 0.27 or newer, using the given window event
+/// and event conversion macros.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! conversion_fns_for_winit {
+    ($convert_window_event:ident, $convert_event:ident) => {
+        /// Maps winit's key to a conrod `Key`.
+        pub fn convert_key(keycode: winit::event::VirtualKeyCode) -> conrod_core::input::keyboard::Key {
+            $crate::v027_convert_key!(keycode)
+        }
+
+        /// Convert a `winit::event::MouseButton` to a `conrod_core::input::MouseButton`.
+        pub fn convert_mouse_button(
+            mouse_button: winit::event::MouseButton,
+        ) -> conrod_core::input::MouseButton {
+            $crate::v027_convert_mouse_button!(mouse_button)
+        }
+
+        /// Convert a given conrod mouse cursor to the corresponding winit cursor type.
+        pub fn convert_mouse_cursor(cursor: conrod_core::cursor::MouseCursor) -> winit::window::CursorIcon {
+            $crate::v027_convert_mouse_cursor!(cursor)
+        }
+
+        /// A function for converting a `winit::event::WindowEvent` to a `conrod_core::event::Input`.
+        pub fn convert_window_event(
+            event: &winit::event::WindowEvent<'_>,
+            window: &winit::window::Window,
+        ) -> Option<conrod_core::event::Input> {
+            $crate::$convert_window_event!(event, window)
+        }
+
+        /// A function for converting a `winit::event::Event` to a `conrod_core::event::Input`.
+        pub fn convert_event<T>(
+            event: &winit::event::Event<'_, T>,
+            window: &winit::window::Window,
+        ) -> Option<conrod_core::event::Input> {
+            $crate::$convert_event!(event, window)
+        }
+    };
+}
@@ -0,0 +1,238 @@
+// The "Data Table" window, showing generated rows in a `Table`.
+
+use crate::search_bar::{SearchBar, SearchState};
+use crate::table::{Table, TableData, TableState};
+use conrod_core::{
+    color, widget, widget_ids, Colorable, Positionable, Sizeable, Widget, WidgetCommon,
+};
+use std::cmp::Ordering;
+
+struct Row {
+    name: String,
+    category: &'static str,
+    value: f64,
+}
+
+/// Rows of made-up data, many more than fit in the window.
+pub struct SampleData {
+    rows: Vec<Row>,
+}
+
+impl Default for SampleData {
+    fn default() -> Self {
+        SampleData::new()
+    }
+}
+
+impl SampleData {
+    const ROW_COUNT: usize = 10_000;
+    const COLUMNS: [&'static str; 4] = ["#", "Name", "Category", "Value"];
+
+    pub fn new() -> Self {
+        const CATEGORIES: [&str; 4] = ["Alpha", "Beta", "Gamma", "Delta"];
+        const SYLLABLES: [&str; 8] = ["ka", "ri", "to", "me", "su", "no", "ha", "lu"];
+        // A small LCG, so that the data is the same on every run.
+        let mut seed: u32 = 12345;
+        let mut next = move || {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (seed >> 16) as usize
+        };
+        let rows = (0..Self::ROW_COUNT)
+            .map(|_| {
+                let name: String = (0..3)
+                    .map(|_| SYLLABLES[next() % SYLLABLES.len()])
+                    .collect();
+                Row {
+                    name,
+                    category: CATEGORIES[next() % CATEGORIES.len()],
+                    value: (next() % 100_000) as f64 / 100.0,
+                }
+            })
+            .collect();
+        SampleData { rows }
+    }
+}
+
+impl TableData for SampleData {
+    fn column_names(&self) -> &[&str] {
+        &Self::COLUMNS
+    }
+
+    fn row_count(&self) -> usize {
+        self.rows.len()
+    }
+
+    fn cell(&self, row: usize, column: usize) -> String {
+        let r = &self.rows[row];
+        match column {
+            0 => (row + 1).to_string(),
+            1 => r.name.clone(),
+            2 => r.category.to_owned(),
+            _ => format!("{:.2}", r.value),
+        }
+    }
+
+    fn compare(&self, a: usize, b: usize, column: usize) -> Ordering {
+        let (ra, rb) = (&self.rows[a], &self.rows[b]);
+        match column {
+            0 => a.cmp(&b),
+            1 => ra.name.cmp(&rb.name),
+            2 => ra.category.cmp(rb.category),
+            _ => ra.value.partial_cmp(&rb.value).unwrap(),
+        }
+    }
+}
+
+/// What the search matches were found for: the query, the sort order and
+/// the number of rows in display order.
+type MatchesKey = (String, Option<(usize, bool)>, usize);
+
+pub struct DataTableState {
+    data: SampleData,
+    table: TableState,
+    search: SearchState,
+    /// The rows matching the search, in display order.
+    matches: Vec<usize>,
+    matches_key: Option<MatchesKey>,
+}
+
+impl Default for DataTableState {
+    fn default() -> Self {
+        DataTableState::new()
+    }
+}
+
+impl DataTableState {
+    pub fn new() -> Self {
+        DataTableState {
+            data: SampleData::new(),
+            table: TableState::new(),
+            search: SearchState::new(),
+            matches: Vec::new(),
+            matches_key: None,
+        }
+    }
+
+    /// Find the matching rows again if the query or the order has changed.
+    fn update_matches(&mut self) {
+        let key = (
+            self.search.query.clone(),
+            self.table.sort(),
+            self.table.display_order().len(),
+        );
+        if self.matches_key.as_ref() == Some(&key) {
+            return;
+        }
+        let (data, search) = (&self.data, &self.search);
+        let columns = data.column_names().len();
+        self.matches = self
+            .table
+            .display_order()
+            .iter()
+            .cloned()
+            .filter(|&row| (0..columns).any(|col| search.matches(&data.cell(row, col))))
+            .collect();
+        self.matches_key = Some(key);
+    }
+}
+
+#[derive(WidgetCommon)]
+pub struct DataTableWidget<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    data_table: &'a mut DataTableState,
+    find_in: Option<widget::Id>,
+}
+
+impl<'a> DataTableWidget<'a> {
+    /// `find_in` is the window Ctrl+F was pressed in, if any, which opens
+    /// the search bar if it is this one.
+    pub fn new(data_table: &'a mut DataTableState, find_in: Option<widget::Id>) -> Self {
+        DataTableWidget {
+            common: widget::CommonBuilder::default(),
+            data_table,
+            find_in,
+        }
+    }
+}
+
+widget_ids! {
+    pub struct DataTableIds {
+        search_bar,
+        table,
+        status,
+    }
+}
+
+impl<'a> Widget for DataTableWidget<'a> {
+    type State = DataTableIds;
+    type Style = ();
+    type Event = ();
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        DataTableIds::new(id_gen)
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs {
+            id,
+            state,
+            ui,
+            rect,
+            ..
+        } = args;
+        let data_table = self.data_table;
+
+        const PAD: conrod_core::Scalar = 8.0;
+        const ROW_H: conrod_core::Scalar = 28.0;
+        const SEARCH_H: conrod_core::Scalar = 24.0;
+
+        data_table.search.open_if_requested(ui, id, self.find_in);
+        data_table.update_matches();
+        let mut table_top = PAD;
+        if data_table.search.open {
+            let match_count = data_table.matches.len();
+            if SearchBar::new(&mut data_table.search, match_count)
+                .w_h((rect.w() - PAD * 2.0).max(0.0), SEARCH_H)
+                .top_left_with_margin_on(id, PAD)
+                .set(state.search_bar, ui)
+            {
+                data_table.update_matches();
+                let current = data_table.search.current;
+                if let Some(&row) = data_table.matches.get(current) {
+                    data_table.table.scroll_to(row);
+                }
+            }
+            table_top += SEARCH_H + PAD;
+        }
+
+        let DataTableState {
+            data,
+            table,
+            search,
+            matches,
+            ..
+        } = data_table;
+        let highlighted: &[usize] = if search.open { matches } else { &[] };
+        Table::new(table, &*data)
+            .highlighted(highlighted)
+            .w_h(
+                (rect.w() - PAD * 2.0).max(0.0),
+                (rect.h() - table_top - ROW_H - PAD * 2.0).max(0.0),
+            )
+            .top_left_with_margins_on(id, table_top, PAD)
+            .set(state.table, ui);
+
+        let status = match table.selected {
+            Some(row) => format!("{} rows, selected #{}", data.row_count(), row + 1),
+            None => format!("{} rows", data.row_count()),
+        };
+        widget::Text::new(&status)
+            .font_size(12)
+            .color(color::LIGHT_GREY)
+            .down_from(state.table, PAD)
+            .set(state.status, ui);
+    }
+}
@@ -0,0 +1,100 @@
+// An immediate debug layer: shapes drawn from anywhere in the app, above all
+// of the UI, to see hit regions, snap guides and the state of the gesture
+// recognizers while developing them.
+//
+// The shapes are collected on the UI thread and dropped before each update of
+// the widgets, so they are drawn while setting the widgets, or from state kept
+// for it. The UI is redrawn whenever they differ from those drawn last, and
+// the renderer appends them after the primitives of conrod.
+//
+// The shapes are clipped to the framebuffer only, unless pushed between
+// `push_clip` and `pop_clip`. Clips nest, each one within the last.
+
+use conrod_core::{Color, Point, Rect};
+use std::cell::RefCell;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Shape {
+    Rect {
+        rect: Rect,
+        color: Color,
+    },
+    /// A line one point thick.
+    Line {
+        start: Point,
+        end: Point,
+        color: Color,
+    },
+    /// Text in the first font of the `Ui`, with its first line's top left
+    /// corner at `position`.
+    Text {
+        position: Point,
+        text: String,
+        color: Color,
+    },
+    /// Clip the shapes which follow to the rect, within the current clip.
+    PushClip(Rect),
+    /// Go back to the clip before the last `PushClip`.
+    PopClip,
+}
+
+#[derive(Default)]
+struct Layer {
+    shapes: Vec<Shape>,
+    drawn: Vec<Shape>,
+}
+
+thread_local! {
+    static LAYER: RefCell<Layer> = RefCell::new(Layer::default());
+}
+
+fn push(shape: Shape) {
+    LAYER.with(|layer| layer.borrow_mut().shapes.push(shape));
+}
+
+pub fn rect(rect: Rect, color: Color) {
+    push(Shape::Rect { rect, color });
+}
+
+pub fn line(start: Point, end: Point, color: Color) {
+    push(Shape::Line { start, end, color });
+}
+
+pub fn text(position: Point, text: &str, color: Color) {
+    push(Shape::Text {
+        position,
+        text: text.to_owned(),
+        color,
+    });
+}
+
+pub fn push_clip(rect: Rect) {
+    push(Shape::PushClip(rect));
+}
+
+pub fn pop_clip() {
+    push(Shape::PopClip);
+}
+
+/// Drop the shapes of the last update, before updating the widgets.
+pub fn begin_frame() {
+    LAYER.with(|layer| layer.borrow_mut().shapes.clear());
+}
+
+/// Whether the shapes differ from those drawn last, so the UI needs to be
+/// redrawn.
+pub fn changed() -> bool {
+    LAYER.with(|layer| {
+        let layer = layer.borrow();
+        layer.shapes != layer.drawn
+    })
+}
+
+/// The shapes to draw, which are then the ones drawn last.
+pub fn take_frame() -> Vec<Shape> {
+    LAYER.with(|layer| {
+        let mut layer = layer.borrow_mut();
+        layer.drawn = layer.shapes.clone();
+        layer.drawn.clone()
+    })
+}
@@ -0,0 +1,320 @@
+// An experimental declarative layer: the content of the Designer window is a
+// tree of widgets described in `assets/ui/designer.json`, so that it can be
+// changed without rebuilding.
+//
+// On the desktop the file is checked every second and reloaded when it
+// changes, which resets the values of its toggles and sliders. The web build
+// embeds the file as it was when built.
+//
+// Only the containers take up no widget; each leaf is one conrod widget, with
+// the ids handed out in the order of the tree.
+
+use crate::palette::Palette;
+use conrod_core::{
+    color, widget, widget_ids, Colorable, Dimensions, Labelable, Positionable, Scalar, Sizeable,
+    UiCell, Widget, WidgetCommon,
+};
+use serde::Deserialize;
+
+const LEAF_W: Scalar = 200.0;
+const ROW_H: Scalar = 28.0;
+const PAD: Scalar = 8.0;
+
+#[derive(Clone, Debug, Deserialize)]
+pub enum Node {
+    Text {
+        text: String,
+        #[serde(default = "default_font_size")]
+        font_size: u32,
+    },
+    /// A button which shows a notification with its label when clicked.
+    Button { label: String },
+    Toggle {
+        label: String,
+        #[serde(default)]
+        value: bool,
+    },
+    Slider {
+        label: String,
+        min: f32,
+        max: f32,
+        value: f32,
+    },
+    /// The nodes side by side.
+    Row(Vec<Node>),
+    /// The nodes one below the other.
+    Column(Vec<Node>),
+}
+
+fn default_font_size() -> u32 {
+    14
+}
+
+impl Node {
+    fn leaf_count(&self) -> usize {
+        match self {
+            Node::Row(nodes) | Node::Column(nodes) => nodes.iter().map(Node::leaf_count).sum(),
+            _ => 1,
+        }
+    }
+}
+
+pub struct DesignerState {
+    pub root: Option<Node>,
+    /// Why the file couldn't be loaded, shown instead of the content.
+    pub error: Option<String>,
+    #[cfg(not(target_arch = "wasm32"))]
+    path: Option<std::path::PathBuf>,
+    #[cfg(not(target_arch = "wasm32"))]
+    modified: Option<std::time::SystemTime>,
+}
+
+impl Default for DesignerState {
+    fn default() -> Self {
+        DesignerState::new()
+    }
+}
+
+impl DesignerState {
+    pub fn new() -> Self {
+        DesignerState {
+            root: None,
+            error: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            path: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            modified: None,
+        }
+    }
+
+    pub fn load_str(&mut self, json: &str) {
+        match serde_json::from_str(json) {
+            Ok(root) => {
+                self.root = Some(root);
+                self.error = None;
+            }
+            Err(err) => self.error = Some(err.to_string()),
+        }
+    }
+
+    /// Load the file at `path`, and again whenever `reload_if_changed` finds
+    /// that it has changed.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn watch(&mut self, path: std::path::PathBuf) {
+        self.path = Some(path);
+        self.modified = None;
+        self.reload_if_changed();
+    }
+
+    /// Returns whether the file was reloaded.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn reload_if_changed(&mut self) -> bool {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return false,
+        };
+        let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        if modified.is_some() && modified == self.modified {
+            return false;
+        }
+        self.modified = modified;
+        match std::fs::read_to_string(path) {
+            Ok(json) => self.load_str(&json),
+            Err(err) => self.error = Some(format!("{}: {}", path.display(), err)),
+        }
+        true
+    }
+}
+
+/// The content of the Designer window.
+#[derive(WidgetCommon)]
+pub struct DesignerWidget<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    state: &'a mut DesignerState,
+    palette: Palette,
+}
+
+impl<'a> DesignerWidget<'a> {
+    pub fn new(state: &'a mut DesignerState) -> Self {
+        DesignerWidget {
+            common: widget::CommonBuilder::default(),
+            state,
+            palette: Palette::DARK,
+        }
+    }
+
+    pub fn palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+}
+
+widget_ids! {
+    pub struct DesignerIds {
+        background,
+        error,
+        leaves[],
+    }
+}
+
+impl<'a> Widget for DesignerWidget<'a> {
+    type State = DesignerIds;
+    type Style = ();
+    /// The labels of the buttons clicked.
+    type Event = Vec<String>;
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        DesignerIds::new(id_gen)
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs {
+            id,
+            state,
+            rect,
+            ui,
+            ..
+        } = args;
+        let designer = self.state;
+        let palette = self.palette;
+
+        widget::Rectangle::fill(rect.dim())
+            .color(palette.background)
+            .middle_of(id)
+            .graphics_for(id)
+            .set(state.background, ui);
+
+        let mut clicked = Vec::new();
+        if let Some(error) = &designer.error {
+            widget::Text::new(error)
+                .font_size(12)
+                .color(color::LIGHT_RED)
+                .w_of(id)
+                .wrap_by_word()
+                .top_left_with_margin_on(id, PAD)
+                .set(state.error, ui);
+            return clicked;
+        }
+        let root = match &mut designer.root {
+            Some(root) => root,
+            None => return clicked,
+        };
+        let count = root.leaf_count();
+        if state.leaves.len() < count {
+            let id_gen = &mut ui.widget_id_generator();
+            state.update(|state| state.leaves.resize(count, id_gen));
+        }
+        let mut place = Placement {
+            parent: id,
+            ids: &state.leaves,
+            next: 0,
+            palette,
+            clicked: &mut clicked,
+        };
+        place.node(root, [PAD, PAD], ui);
+        clicked
+    }
+}
+
+struct Placement<'a> {
+    parent: widget::Id,
+    ids: &'a [widget::Id],
+    next: usize,
+    palette: Palette,
+    clicked: &'a mut Vec<String>,
+}
+
+impl<'a> Placement<'a> {
+    /// Set `node` with its top left corner `[left, top]` from that of the
+    /// parent, and return its size.
+    fn node(&mut self, node: &mut Node, [left, top]: [Scalar; 2], ui: &mut UiCell) -> Dimensions {
+        let (nodes, is_row) = match node {
+            Node::Row(nodes) => (nodes, true),
+            Node::Column(nodes) => (nodes, false),
+            leaf => return self.leaf(leaf, [left, top], ui),
+        };
+        let (mut w, mut h) = (0.0, 0.0);
+        for (i, node) in nodes.iter_mut().enumerate() {
+            let gap = if i == 0 { 0.0 } else { PAD };
+            if is_row {
+                let [node_w, node_h] = self.node(node, [left + w + gap, top], ui);
+                w += gap + node_w;
+                h = node_h.max(h);
+            } else {
+                let [node_w, node_h] = self.node(node, [left, top + h + gap], ui);
+                w = node_w.max(w);
+                h += gap + node_h;
+            }
+        }
+        [w, h]
+    }
+
+    fn leaf(&mut self, node: &mut Node, [left, top]: [Scalar; 2], ui: &mut UiCell) -> Dimensions {
+        let id = self.ids[self.next];
+        self.next += 1;
+        let parent = self.parent;
+        let palette = self.palette;
+        match node {
+            Node::Text { text, font_size } => {
+                widget::Text::new(text)
+                    .font_size(*font_size)
+                    .color(palette.text)
+                    .w(LEAF_W)
+                    .wrap_by_word()
+                    .top_left_with_margins_on(parent, top, left)
+                    .set(id, ui);
+            }
+            Node::Button { label } => {
+                if widget::Button::new()
+                    .label(label)
+                    .label_font_size(14)
+                    .color(palette.control)
+                    .label_color(palette.control_text)
+                    .w_h(LEAF_W, ROW_H)
+                    .top_left_with_margins_on(parent, top, left)
+                    .set(id, ui)
+                    .was_clicked()
+                {
+                    self.clicked.push(label.clone());
+                }
+            }
+            Node::Toggle { label, value } => {
+                for new_value in widget::Toggle::new(*value)
+                    .label(label)
+                    .label_font_size(14)
+                    .color(palette.control)
+                    .label_color(palette.control_text)
+                    .w_h(LEAF_W, ROW_H)
+                    .top_left_with_margins_on(parent, top, left)
+                    .set(id, ui)
+                {
+                    *value = new_value;
+                }
+            }
+            Node::Slider {
+                label,
+                min,
+                max,
+                value,
+            } => {
+                if let Some(new_value) = widget::Slider::new(*value, *min, *max)
+                    .label(label)
+                    .label_font_size(14)
+                    .color(palette.control)
+                    .label_color(palette.control_text)
+                    .w_h(LEAF_W, ROW_H)
+                    .top_left_with_margins_on(parent, top, left)
+                    .set(id, ui)
+                {
+                    *value = new_value;
+                }
+            }
+            // Placed by `node`.
+            Node::Row(_) | Node::Column(_) => {}
+        }
+        ui.wh_of(id).unwrap_or([LEAF_W, ROW_H])
+    }
+}
@@ -0,0 +1,335 @@
+// Small widgets pinned to the corners of the backdrop, under the floating
+// windows: a clock, the UI updates per second, and the readings of the
+// `metrics` provider where it has them.
+//
+// They are a second layer next to the `WindowingArea`, set on the backdrop
+// before it so that the windows cover them. They only show things, as
+// graphics for the backdrop, so that they never take a click from the area.
+// Which corner each one goes to is picked in the Settings window and saved in
+// the `Config`.
+
+use crate::clock;
+use crate::metrics::Readings;
+use conrod_core::{
+    color, widget, widget_ids, Colorable, Positionable, Scalar, Widget, WidgetCommon,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DesktopWidgetKind {
+    Clock,
+    /// How many times the UI was updated in the last second. It is only
+    /// updated when something changes, so this is not the refresh rate.
+    UpdateRate,
+    Cpu,
+    Memory,
+    Battery,
+}
+
+impl DesktopWidgetKind {
+    pub const ALL: [DesktopWidgetKind; 5] = [
+        DesktopWidgetKind::Clock,
+        DesktopWidgetKind::UpdateRate,
+        DesktopWidgetKind::Cpu,
+        DesktopWidgetKind::Memory,
+        DesktopWidgetKind::Battery,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DesktopWidgetKind::Clock => "Clock",
+            DesktopWidgetKind::UpdateRate => "Updates per second",
+            DesktopWidgetKind::Cpu => "CPU meter",
+            DesktopWidgetKind::Memory => "Memory",
+            DesktopWidgetKind::Battery => "Battery",
+        }
+    }
+
+    /// Whether it can be shown in this build.
+    pub fn is_available(self) -> bool {
+        let sysinfo = cfg!(all(feature = "cpu", not(target_arch = "wasm32")));
+        let web = cfg!(target_arch = "wasm32");
+        match self {
+            DesktopWidgetKind::Clock | DesktopWidgetKind::UpdateRate => true,
+            DesktopWidgetKind::Cpu => sysinfo,
+            DesktopWidgetKind::Memory => sysinfo || web,
+            DesktopWidgetKind::Battery => web,
+        }
+    }
+
+    /// Whether it shows a reading of the `metrics` provider.
+    fn is_metric(self) -> bool {
+        match self {
+            DesktopWidgetKind::Clock | DesktopWidgetKind::UpdateRate => false,
+            DesktopWidgetKind::Cpu | DesktopWidgetKind::Memory | DesktopWidgetKind::Battery => true,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Corner {
+    pub const ALL: [Corner; 4] = [
+        Corner::TopLeft,
+        Corner::TopRight,
+        Corner::BottomLeft,
+        Corner::BottomRight,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Corner::TopLeft => "Top left",
+            Corner::TopRight => "Top right",
+            Corner::BottomLeft => "Bottom left",
+            Corner::BottomRight => "Bottom right",
+        }
+    }
+
+    fn is_top(self) -> bool {
+        self == Corner::TopLeft || self == Corner::TopRight
+    }
+
+    fn is_left(self) -> bool {
+        self == Corner::TopLeft || self == Corner::BottomLeft
+    }
+}
+
+/// The corner of each desktop widget, `None` for those which are hidden.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DesktopWidgets {
+    pub clock: Option<Corner>,
+    pub update_rate: Option<Corner>,
+    pub cpu: Option<Corner>,
+    pub memory: Option<Corner>,
+    pub battery: Option<Corner>,
+}
+
+impl Default for DesktopWidgets {
+    fn default() -> Self {
+        DesktopWidgets {
+            clock: Some(Corner::BottomLeft),
+            update_rate: None,
+            cpu: None,
+            memory: None,
+            battery: None,
+        }
+    }
+}
+
+impl DesktopWidgets {
+    pub fn corner(&self, kind: DesktopWidgetKind) -> Option<Corner> {
+        match kind {
+            DesktopWidgetKind::Clock => self.clock,
+            DesktopWidgetKind::UpdateRate => self.update_rate,
+            DesktopWidgetKind::Cpu => self.cpu,
+            DesktopWidgetKind::Memory => self.memory,
+            DesktopWidgetKind::Battery => self.battery,
+        }
+    }
+
+    fn is_shown(&self, kind: DesktopWidgetKind) -> bool {
+        kind.is_available() && self.corner(kind).is_some()
+    }
+
+    /// Whether any widget is shown, which change every second.
+    pub fn any_shown(&self) -> bool {
+        DesktopWidgetKind::ALL
+            .iter()
+            .any(|&kind| self.is_shown(kind))
+    }
+
+    /// Whether any widget shows a reading, which needs the `metrics`
+    /// provider to be refreshed.
+    pub fn shows_metrics(&self) -> bool {
+        DesktopWidgetKind::ALL
+            .iter()
+            .any(|&kind| kind.is_metric() && self.is_shown(kind))
+    }
+
+    pub fn set_corner(&mut self, kind: DesktopWidgetKind, corner: Option<Corner>) {
+        match kind {
+            DesktopWidgetKind::Clock => self.clock = corner,
+            DesktopWidgetKind::UpdateRate => self.update_rate = corner,
+            DesktopWidgetKind::Cpu => self.cpu = corner,
+            DesktopWidgetKind::Memory => self.memory = corner,
+            DesktopWidgetKind::Battery => self.battery = corner,
+        }
+    }
+}
+
+/// What the widgets measure between updates.
+pub struct DesktopWidgetsState {
+    /// When the UI was updated in the last second, in microseconds.
+    updates: VecDeque<f64>,
+}
+
+impl Default for DesktopWidgetsState {
+    fn default() -> Self {
+        DesktopWidgetsState::new()
+    }
+}
+
+impl DesktopWidgetsState {
+    pub fn new() -> Self {
+        DesktopWidgetsState {
+            updates: VecDeque::new(),
+        }
+    }
+
+    /// Count an update of the UI at `now`.
+    pub fn count_update(&mut self, now: f64) {
+        self.updates.push_back(now);
+        while self.updates.front().map_or(false, |&t| now - t > 1e6) {
+            self.updates.pop_front();
+        }
+    }
+
+    fn text(&self, kind: DesktopWidgetKind, readings: &Readings) -> String {
+        match kind {
+            DesktopWidgetKind::Clock => {
+                let seconds = clock::seconds_of_day() as u64;
+                format!(
+                    "{:02}:{:02}:{:02} UTC",
+                    seconds / 3600,
+                    seconds / 60 % 60,
+                    seconds % 60
+                )
+            }
+            DesktopWidgetKind::UpdateRate => format!("{} updates/s", self.updates.len()),
+            DesktopWidgetKind::Cpu => readings.cpu_text(),
+            DesktopWidgetKind::Memory => readings.memory_text(),
+            DesktopWidgetKind::Battery => readings.battery_text(),
+        }
+    }
+}
+
+/// The layer of desktop widgets, over the whole backdrop.
+#[derive(WidgetCommon)]
+pub struct DesktopWidgetsLayer<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    config: &'a DesktopWidgets,
+    desktop: &'a DesktopWidgetsState,
+    readings: Readings,
+    /// Kept clear at the top and the bottom, for the File button and the
+    /// workspace pager above the layer.
+    margins: (Scalar, Scalar),
+}
+
+impl<'a> DesktopWidgetsLayer<'a> {
+    pub fn new(
+        config: &'a DesktopWidgets,
+        desktop: &'a DesktopWidgetsState,
+        readings: Readings,
+    ) -> Self {
+        DesktopWidgetsLayer {
+            common: widget::CommonBuilder::default(),
+            config,
+            desktop,
+            readings,
+            margins: (0.0, 0.0),
+        }
+    }
+
+    pub fn margins(mut self, top: Scalar, bottom: Scalar) -> Self {
+        self.margins = (top, bottom);
+        self
+    }
+}
+
+widget_ids! {
+    pub struct DesktopWidgetsIds {
+        backgrounds[],
+        texts[],
+    }
+}
+
+impl<'a> Widget for DesktopWidgetsLayer<'a> {
+    type State = DesktopWidgetsIds;
+    type Style = ();
+    type Event = ();
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        DesktopWidgetsIds::new(id_gen)
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    fn is_over(&self) -> widget::IsOverFn {
+        |_, _, _| widget::IsOver::Bool(false)
+    }
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs {
+            id,
+            state,
+            rect,
+            ui,
+            ..
+        } = args;
+        let DesktopWidgetsLayer {
+            config,
+            desktop,
+            readings,
+            margins: (top_margin, bottom_margin),
+            ..
+        } = self;
+
+        const PAD: Scalar = 8.0;
+        const GAP: Scalar = 4.0;
+        const ITEM_W: Scalar = 140.0;
+        const ITEM_H: Scalar = 24.0;
+
+        let count = DesktopWidgetKind::ALL.len();
+        if state.texts.len() < count {
+            let id_gen = &mut ui.widget_id_generator();
+            state.update(|state| {
+                state.backgrounds.resize(count, id_gen);
+                state.texts.resize(count, id_gen);
+            });
+        }
+
+        // How many widgets are stacked in each corner so far.
+        let mut stacked = [0; 4];
+        for (i, &kind) in DesktopWidgetKind::ALL.iter().enumerate() {
+            let corner = match config.corner(kind) {
+                Some(corner) if kind.is_available() => corner,
+                _ => continue,
+            };
+            let corner_index = Corner::ALL.iter().position(|&c| c == corner).unwrap();
+            let offset = PAD + (ITEM_H + GAP) * stacked[corner_index] as f64 + ITEM_H / 2.0;
+            stacked[corner_index] += 1;
+            let x = if corner.is_left() {
+                rect.left() + PAD + ITEM_W / 2.0
+            } else {
+                rect.right() - PAD - ITEM_W / 2.0
+            };
+            let y = if corner.is_top() {
+                rect.top() - top_margin - offset
+            } else {
+                rect.bottom() + bottom_margin + offset
+            };
+
+            widget::Rectangle::fill([ITEM_W, ITEM_H])
+                .color(color::BLACK.alpha(0.35))
+                .x_y(x, y)
+                .parent(id)
+                .graphics_for(id)
+                .set(state.backgrounds[i], ui);
+            widget::Text::new(&desktop.text(kind, &readings))
+                .font_size(12)
+                .color(color::WHITE)
+                .middle_of(state.backgrounds[i])
+                .graphics_for(id)
+                .set(state.texts[i], ui);
+        }
+    }
+}
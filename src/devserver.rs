@@ -0,0 +1,266 @@
+// A debug HTTP server for inspecting the running demo from other tools.
+//
+// It runs on a background thread and only listens on the loopback address.
+// The event loop takes JSON snapshots of the UI only when they are asked
+// for: a `GET` wakes it up and waits for the snapshots of its next UI update.
+// The requests which change something are posted to the event loop through
+// the `Bridge` too:
+//
+// - `GET /state`: the config, the debug flags, the windows and notifications.
+// - `GET /render`: the diagnostics report.
+// - `GET /primitives`: what conrod drew in the last update.
+// - `POST /command/<name>`: run the command of a shortcut, by its name in
+//   Settings, with `%20` for spaces.
+// - `POST /click/<x>/<y>`: click the left mouse button at a point of the UI.
+//
+// Listening on loopback isn't enough on its own, as any web page open in the
+// browser can post to it, and one whose host name is rebound to 127.0.0.1 can
+// read it too. Requests with an `Origin` header, which browsers send with
+// those, or with a `Host` other than this server are turned down.
+
+use crate::bridge::{AppEvent, Bridge};
+use crate::common::UiState;
+use conrod_core::render::PrimitiveKind;
+use conrod_core::{Point, Ui};
+use serde_json::json;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::Duration;
+
+/// How long a client has to send its request and take the answer, as
+/// requests are served one at a time.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(2);
+/// How long a `GET` waits for new snapshots before taking the last ones.
+const SNAPSHOT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// What the event loop is asked to do.
+#[derive(Debug)]
+pub enum DevRequest {
+    Command(String),
+    Click(Point),
+    /// Update the UI, which publishes new snapshots.
+    Snapshot,
+}
+
+#[derive(Default)]
+struct Snapshots {
+    /// Set by a request until `publish` takes new snapshots.
+    wanted: bool,
+    state: String,
+    render: String,
+    primitives: String,
+}
+
+/// The snapshots, and the condition a request waits on for new ones.
+type SharedSnapshots = (Mutex<Snapshots>, Condvar);
+
+pub struct DevServer {
+    snapshots: Arc<SharedSnapshots>,
+    pub port: u16,
+}
+
+impl DevServer {
+    pub const DEFAULT_PORT: u16 = 7878;
+
+    /// The port from `--devserver` or `--devserver=PORT` on the command line,
+    /// if the server is wanted.
+    pub fn port_from_args(args: impl Iterator<Item = String>) -> Option<u16> {
+        let mut port = None;
+        for arg in args {
+            if arg == "--devserver" {
+                port = Some(Self::DEFAULT_PORT);
+            } else if let Some(value) = arg.strip_prefix("--devserver=") {
+                port = value.parse().ok();
+            }
+        }
+        port
+    }
+
+    pub fn start(port: u16, bridge: Bridge) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", port))?;
+        let port = listener.local_addr()?.port();
+        let snapshots = Arc::new(SharedSnapshots::default());
+        let server_snapshots = snapshots.clone();
+        std::thread::spawn(move || {
+            // Requests are served one at a time, so a client which doesn't
+            // send its request in time is dropped.
+            for stream in listener.incoming().flatten() {
+                let _ = stream.set_read_timeout(Some(CLIENT_TIMEOUT));
+                let _ = stream.set_write_timeout(Some(CLIENT_TIMEOUT));
+                let _ = serve(stream, port, &server_snapshots, &bridge);
+            }
+        });
+        Ok(DevServer { snapshots, port })
+    }
+
+    /// Take new snapshots after `set_widgets`, if a request is waiting for
+    /// them.
+    pub fn publish(&self, state: &UiState, ui: &Ui, hidpi_factor: f64) {
+        let (snapshots, taken) = &*self.snapshots;
+        match snapshots.lock() {
+            Ok(snapshots) if snapshots.wanted => {}
+            _ => return,
+        }
+        let windows: Vec<_> = state
+            .window_tracker
+            .windows()
+            .filter(|&(title, _)| state.config.workspaces.shows(title))
+            .map(|(title, window)| {
+                let rect = ui.rect_of(window);
+                json!({
+                    "title": title,
+                    "rect": rect.map(|r| [r.x(), r.y(), r.w(), r.h()]),
+                })
+            })
+            .collect();
+        let notifications: Vec<_> = state
+            .notifications
+            .items()
+            .iter()
+            .map(|n| n.text.as_str())
+            .collect();
+        let ui_state = json!({
+            "config": state.config,
+            "enable_debug": state.enable_debug,
+            "show_audit": state.show_audit,
+            "color_filter": state.color_filter.map(|kind| kind.name()),
+            "windows": windows,
+            "notifications": notifications,
+        });
+        let render = json!({ "diagnostics": state.diagnostics.report(hidpi_factor) });
+
+        let mut primitives = Vec::new();
+        let mut walk = ui.draw();
+        while let Some(primitive) = walk.next() {
+            let kind = match primitive.kind {
+                PrimitiveKind::Rectangle { .. } => "rectangle",
+                PrimitiveKind::TrianglesSingleColor { .. } => "triangles",
+                PrimitiveKind::TrianglesMultiColor { .. } => "triangles",
+                PrimitiveKind::Image { .. } => "image",
+                PrimitiveKind::Text { .. } => "text",
+                PrimitiveKind::Other(_) => "other",
+            };
+            let r = primitive.rect;
+            primitives.push(json!({
+                "id": primitive.id.index(),
+                "kind": kind,
+                "rect": [r.x(), r.y(), r.w(), r.h()],
+            }));
+        }
+
+        if let Ok(mut snapshots) = snapshots.lock() {
+            snapshots.state = ui_state.to_string();
+            snapshots.render = render.to_string();
+            snapshots.primitives = serde_json::Value::from(primitives).to_string();
+            snapshots.wanted = false;
+        }
+        taken.notify_all();
+    }
+}
+
+/// Ask the event loop for new snapshots and wait for them. The last ones are
+/// given if it doesn't get to it in time.
+fn new_snapshots<'a>(
+    shared: &'a SharedSnapshots,
+    bridge: &Bridge,
+) -> Option<MutexGuard<'a, Snapshots>> {
+    let (snapshots, taken) = shared;
+    let mut snapshots = snapshots.lock().ok()?;
+    snapshots.wanted = true;
+    if bridge.send(AppEvent::DevServer(DevRequest::Snapshot)) {
+        let (waited, _) = taken
+            .wait_timeout_while(snapshots, SNAPSHOT_TIMEOUT, |snapshots| snapshots.wanted)
+            .ok()?;
+        snapshots = waited;
+    }
+    Some(snapshots)
+}
+
+/// Whether a request with the headers `host` and `origin` comes from a tool
+/// on this machine rather than from a web page.
+fn is_local_request(host: Option<&str>, has_origin: bool, port: u16) -> bool {
+    let allowed = [format!("127.0.0.1:{}", port), format!("localhost:{}", port)];
+    !has_origin && host.map_or(false, |host| allowed.iter().any(|a| a == host))
+}
+
+fn serve(
+    stream: TcpStream,
+    port: u16,
+    snapshots: &SharedSnapshots,
+    bridge: &Bridge,
+) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Only `Host` and `Origin` are needed of the headers, and no body.
+    let mut host = None;
+    let mut has_origin = false;
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("host") {
+                host = Some(value.trim().to_owned());
+            } else if name.eq_ignore_ascii_case("origin") {
+                has_origin = true;
+            }
+        }
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("");
+    let path = parts.next().unwrap_or("");
+    let segments: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+    let (status, body) = match (method, segments.as_slice()) {
+        _ if !is_local_request(host.as_deref(), has_origin, port) => (
+            "403 Forbidden",
+            json!({ "error": "only local tools may connect" }).to_string(),
+        ),
+        ("GET", ["state"]) | ("GET", ["render"]) | ("GET", ["primitives"]) => {
+            let snapshots = match new_snapshots(snapshots, bridge) {
+                Some(snapshots) => snapshots,
+                None => return Ok(()),
+            };
+            let body = match segments[0] {
+                "state" => &snapshots.state,
+                "render" => &snapshots.render,
+                _ => &snapshots.primitives,
+            };
+            ("200 OK", body.clone())
+        }
+        ("POST", ["command", name]) => {
+            let name = name.replace("%20", " ");
+            post(bridge, DevRequest::Command(name))
+        }
+        ("POST", ["click", x, y]) => match (x.parse(), y.parse()) {
+            (Ok(x), Ok(y)) => post(bridge, DevRequest::Click([x, y])),
+            _ => (
+                "400 Bad Request",
+                json!({ "error": "bad point" }).to_string(),
+            ),
+        },
+        _ => (
+            "404 Not Found",
+            json!({ "error": "unknown request" }).to_string(),
+        ),
+    };
+
+    let mut stream = reader.into_inner();
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+fn post(bridge: &Bridge, request: DevRequest) -> (&'static str, String) {
+    if bridge.send(AppEvent::DevServer(request)) {
+        ("202 Accepted", "{}".to_owned())
+    } else {
+        ("503 Service Unavailable", "{}".to_owned())
+    }
+}
@@ -0,0 +1,292 @@
+// The Diagnostics window, describing how the demo renders on this machine,
+// for users to include in bug reports.
+
+use crate::conrod_glow::RendererInfo;
+use crate::id_stats::IdStats;
+use crate::metrics::Readings;
+use conrod_core::{
+    color, widget, widget_ids, Borderable, Colorable, Labelable, Positionable, Sizeable, Widget,
+    WidgetCommon,
+};
+use glow::HasContext;
+
+/// The extensions which the renderer may need, depending on the version.
+#[cfg(not(target_arch = "wasm32"))]
+const RELEVANT_EXTENSIONS: &[&str] = &[
+    "GL_ARB_framebuffer_sRGB",
+    "GL_EXT_framebuffer_sRGB",
+    "GL_ARB_framebuffer_object",
+    "GL_ARB_vertex_array_object",
+    "GL_ARB_texture_rg",
+];
+/// WebGL 2 has all the renderer needs, so these only tell more about the
+/// browser.
+#[cfg(target_arch = "wasm32")]
+const RELEVANT_EXTENSIONS: &[&str] = &[
+    "WEBGL_debug_renderer_info",
+    "EXT_color_buffer_float",
+    "OES_texture_float_linear",
+];
+
+/// What is known about the graphics set-up, filled in by the event loop.
+#[derive(Clone, Debug, Default)]
+pub struct Diagnostics {
+    pub gl_vendor: String,
+    pub gl_renderer: String,
+    pub gl_version: String,
+    pub glsl_version: String,
+    pub extensions: Vec<String>,
+    /// `None` when nothing is drawn, as in the headless simulation.
+    pub renderer: Option<RendererInfo>,
+    /// The pixel format of the window, as far as the platform tells.
+    pub surface: String,
+    /// Which GPU was asked for and how.
+    pub gpu_preference: String,
+    /// The samples per pixel the UI was last drawn with offscreen, 0 when
+    /// it wasn't multisampled.
+    pub msaa_samples: u32,
+    /// How many textures in the image map have not been drawn lately.
+    pub idle_textures: usize,
+    /// Counted while the window is open.
+    pub widget_ids: IdStats,
+    /// The readings of the machine, while the window is open.
+    pub system: Readings,
+}
+
+impl Diagnostics {
+    /// Query the current GL context. glow can't list the extensions on the
+    /// web, so the event loop sets `extensions` there.
+    pub fn query(gl: &glow::Context, renderer: RendererInfo, surface: String) -> Self {
+        unsafe {
+            Diagnostics {
+                gl_vendor: gl.get_parameter_string(glow::VENDOR),
+                gl_renderer: gl.get_parameter_string(glow::RENDERER),
+                gl_version: gl.get_parameter_string(glow::VERSION),
+                glsl_version: gl.get_parameter_string(glow::SHADING_LANGUAGE_VERSION),
+                extensions: query_extensions(gl),
+                renderer: Some(renderer),
+                surface,
+                gpu_preference: String::new(),
+                msaa_samples: 0,
+                idle_textures: 0,
+                widget_ids: IdStats::default(),
+                system: Readings::default(),
+            }
+        }
+    }
+
+    /// The report as titled sections of lines.
+    pub fn sections(&self, hidpi_factor: f64) -> Vec<(&'static str, Vec<String>)> {
+        let mut renderer = vec![
+            format!("Vendor: {}", self.gl_vendor),
+            format!("Renderer: {}", self.gl_renderer),
+            format!("Version: {}", self.gl_version),
+            format!("Shading language: {}", self.glsl_version),
+        ];
+        // Only native has a choice.
+        if !self.gpu_preference.is_empty() {
+            renderer.push(format!("GPU preference: {}", self.gpu_preference));
+        }
+        match &self.renderer {
+            Some(info) => {
+                renderer.push(format!("Shaders: {}", info.shader.name()));
+                renderer.push(format!(
+                    "Shaders from cache: {}",
+                    if info.from_program_cache { "yes" } else { "no" }
+                ));
+                renderer.push(format!(
+                    "Safe mode: {}",
+                    if info.safe_mode { "on" } else { "off" }
+                ));
+                renderer.push(format!(
+                    "sRGB framebuffer: {}",
+                    if info.framebuffer_srgb { "yes" } else { "no" }
+                ));
+                let (w, h) = info.glyph_cache_size;
+                renderer.push(format!("Glyph cache: {}x{}", w, h));
+                for (variant, err) in &info.failed {
+                    renderer.push(format!("{} failed: {}", variant.name(), err.trim()));
+                }
+            }
+            None => renderer.push("Shaders: none".to_owned()),
+        }
+        renderer.push(format!("Textures not drawn lately: {}", self.idle_textures));
+
+        let mut extensions: Vec<String> = RELEVANT_EXTENSIONS
+            .iter()
+            .map(|name| {
+                let present = self.extensions.iter().any(|e| e == name);
+                format!("{}: {}", name, if present { "yes" } else { "no" })
+            })
+            .collect();
+        extensions.push(format!("{} extensions in total", self.extensions.len()));
+
+        let display = vec![
+            format!("Scale factor: {}", hidpi_factor),
+            format!("Surface: {}", self.surface),
+            match self.msaa_samples {
+                0 => "Offscreen MSAA: off".to_owned(),
+                samples => format!("Offscreen MSAA: {}x", samples),
+            },
+            format!("Platform: {}", platform()),
+            format!("Demo version: {}", env!("CARGO_PKG_VERSION")),
+        ];
+
+        vec![
+            ("Renderer", renderer),
+            ("Extensions", extensions),
+            ("Display", display),
+            ("System", self.system.lines()),
+            ("Widget ids", self.widget_ids.lines()),
+        ]
+    }
+
+    /// The report as plain text, for pasting into a bug report.
+    pub fn report(&self, hidpi_factor: f64) -> String {
+        let mut report = String::new();
+        for (title, lines) in self.sections(hidpi_factor) {
+            report.push_str(&format!("{}:\n", title));
+            for line in lines {
+                report.push_str(&format!("  {}\n", line));
+            }
+        }
+        report
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn query_extensions(gl: &glow::Context) -> Vec<String> {
+    unsafe {
+        let count = gl.get_parameter_i32(glow::NUM_EXTENSIONS);
+        (0..count.max(0) as u32)
+            .map(|i| gl.get_parameter_indexed_string(glow::EXTENSIONS, i))
+            .collect()
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn query_extensions(_gl: &glow::Context) -> Vec<String> {
+    Vec::new()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn platform() -> String {
+    format!("{} {}", std::env::consts::OS, std::env::consts::ARCH)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn platform() -> String {
+    web_sys::window()
+        .and_then(|window| window.navigator().user_agent().ok())
+        .unwrap_or_else(|| "unknown browser".to_owned())
+}
+
+#[derive(WidgetCommon)]
+pub struct DiagnosticsWidget<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    diagnostics: &'a Diagnostics,
+    hidpi_factor: f64,
+}
+
+impl<'a> DiagnosticsWidget<'a> {
+    pub fn new(diagnostics: &'a Diagnostics, hidpi_factor: f64) -> Self {
+        DiagnosticsWidget {
+            common: widget::CommonBuilder::default(),
+            diagnostics,
+            hidpi_factor,
+        }
+    }
+}
+
+widget_ids! {
+    pub struct DiagnosticsIds {
+        copy,
+        canvas,
+        titles[],
+        lines[],
+    }
+}
+
+impl<'a> Widget for DiagnosticsWidget<'a> {
+    type State = DiagnosticsIds;
+    type Style = ();
+    /// Whether "Copy report" was clicked.
+    type Event = bool;
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        DiagnosticsIds::new(id_gen)
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs {
+            id,
+            state,
+            ui,
+            rect,
+            ..
+        } = args;
+
+        const PAD: conrod_core::Scalar = 8.0;
+        const ROW_H: conrod_core::Scalar = 28.0;
+
+        let copy = widget::Button::new()
+            .label("Copy report")
+            .label_font_size(14)
+            .w_h(120.0, ROW_H)
+            .top_left_with_margin_on(id, PAD)
+            .set(state.copy, ui)
+            .was_clicked();
+
+        widget::Canvas::new()
+            .color(color::TRANSPARENT)
+            .border(0.0)
+            .w_h(
+                rect.w() - PAD * 2.0,
+                (rect.h() - ROW_H - PAD * 3.0).max(0.0),
+            )
+            .down_from(state.copy, PAD)
+            .align_left_of(state.copy)
+            .scroll_kids_vertically()
+            .set(state.canvas, ui);
+
+        let sections = self.diagnostics.sections(self.hidpi_factor);
+        let line_count: usize = sections.iter().map(|(_, lines)| lines.len()).sum();
+        if state.titles.len() < sections.len() || state.lines.len() < line_count {
+            let id_gen = &mut ui.widget_id_generator();
+            state.update(|state| {
+                state.titles.resize(sections.len(), id_gen);
+                state.lines.resize(line_count, id_gen);
+            });
+        }
+        let mut prev = None;
+        let mut line_index = 0;
+        for (i, (title, lines)) in sections.iter().enumerate() {
+            let title = widget::Text::new(title).font_size(14).color(color::WHITE);
+            let title = match prev {
+                Some(prev) => title.down_from(prev, PAD * 1.5),
+                None => title.top_left_of(state.canvas),
+            };
+            title.set(state.titles[i], ui);
+            let mut prev_line = state.titles[i];
+            for line in lines {
+                let line_id = state.lines[line_index];
+                widget::Text::new(line)
+                    .font_size(12)
+                    .color(color::LIGHT_GREY)
+                    .padded_w_of(state.canvas, PAD)
+                    .left_justify()
+                    .down_from(prev_line, PAD / 2.0)
+                    .align_left_of(state.titles[i])
+                    .set(line_id, ui);
+                prev_line = line_id;
+                line_index += 1;
+            }
+            prev = Some(prev_line);
+        }
+
+        copy
+    }
+}
@@ -0,0 +1,116 @@
+// Errors which stop the demo from starting or from drawing a frame.
+//
+// A failure while starting is shown in place of the UI, in a message box on
+// the desktop and over the canvas on the web, with a hint on what to try.
+// Once the UI is up, errors are shown as notifications instead, see
+// `UiState::report_error`.
+
+use std::fmt;
+
+#[derive(Debug)]
+pub enum AppError {
+    /// The OpenGL context or the window couldn't be created.
+    Context(String),
+    /// The browser doesn't support WebGL 2 or has it turned off.
+    WebGl2Unavailable,
+    /// The page has no `<canvas id="canvas">` to draw in.
+    MissingCanvas,
+    /// The assets folder wasn't found near the executable.
+    AssetsNotFound,
+    Font(String),
+    Image(String),
+    /// The shaders, glyph cache or textures couldn't be set up.
+    Renderer(String),
+    /// A frame couldn't be drawn or presented.
+    Draw(String),
+    /// The driver was reset or the GPU went away while drawing, and a new
+    /// context couldn't be made, for the reason given.
+    ContextLost(String),
+    /// The instance being mirrored couldn't be reached or went away.
+    Mirror(String),
+}
+
+impl AppError {
+    pub fn title(&self) -> &'static str {
+        match self {
+            AppError::Context(_) | AppError::WebGl2Unavailable | AppError::MissingCanvas => {
+                "Cannot open a window for drawing"
+            }
+            AppError::AssetsNotFound | AppError::Font(_) | AppError::Image(_) => {
+                "Cannot load the demo's assets"
+            }
+            AppError::Renderer(_) => "Cannot set up the renderer",
+            AppError::Draw(_) | AppError::ContextLost(_) => "Cannot draw the UI",
+            AppError::Mirror(_) => "Cannot mirror the UI",
+        }
+    }
+
+    /// What the user could try to fix the error.
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            AppError::Context(_) | AppError::Renderer(_) => {
+                Some("Try updating the graphics driver.")
+            }
+            AppError::WebGl2Unavailable => Some(
+                "Try another browser, or check that hardware acceleration is turned on in its settings.",
+            ),
+            AppError::AssetsNotFound => {
+                Some("Run the demo from the repository, which has the assets folder.")
+            }
+            AppError::ContextLost(_) => Some(
+                "The demo opens its window again by itself when the graphics driver is reset or \
+                 updated, which failed this time.",
+            ),
+            _ => None,
+        }
+    }
+
+    /// The title, the error and the hint, for showing as plain text.
+    pub fn describe(&self) -> String {
+        let mut text = format!("{}\n\n{}", self.title(), self);
+        if let Some(hint) = self.hint() {
+            text.push_str("\n\n");
+            text.push_str(hint);
+        }
+        text
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AppError::Context(err) => write!(f, "Creating the OpenGL context failed: {}", err),
+            AppError::WebGl2Unavailable => write!(f, "WebGL 2 is unavailable"),
+            AppError::MissingCanvas => write!(f, "The page has no canvas with the id \"canvas\""),
+            AppError::AssetsNotFound => write!(f, "The assets folder was not found"),
+            AppError::Font(err) => write!(f, "Loading the font failed: {}", err),
+            AppError::Image(err) => write!(f, "Loading the image failed: {}", err),
+            AppError::Renderer(err) => write!(f, "{}", err),
+            AppError::Draw(err) => write!(f, "Drawing failed: {}", err),
+            AppError::ContextLost(err) => write!(
+                f,
+                "The OpenGL context was lost, and making a new one failed: {}",
+                err
+            ),
+            AppError::Mirror(err) => write!(f, "Mirroring failed: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+/// Write a warning to stderr, or the browser console on the web.
+pub fn warn(text: &str) {
+    #[cfg(not(target_arch = "wasm32"))]
+    eprintln!("{}", text);
+    #[cfg(target_arch = "wasm32")]
+    web_sys::console::warn_1(&text.into());
+}
+
+/// Write the error to stderr, or the browser console on the web.
+pub fn log(err: &AppError) {
+    #[cfg(not(target_arch = "wasm32"))]
+    eprintln!("{}: {}", err.title(), err);
+    #[cfg(target_arch = "wasm32")]
+    web_sys::console::error_1(&format!("{}: {}", err.title(), err).into());
+}
@@ -0,0 +1,50 @@
+// Exporting the current view as a PNG image.
+
+use crate::conrod_glow::{Display, RenderTargetPool, Renderer, Texture};
+use conrod_core::image;
+use glow::HasContext;
+
+/// Render the whole `Ui` offscreen at `scale` times the framebuffer size and
+/// encode the result as a PNG.
+///
+/// Text and geometry are rendered at the higher resolution rather than
+/// upscaled, which makes this useful for documentation screenshots.
+///
+/// This fills `renderer` with the exported frame, so the caller needs to
+/// fill it again before drawing the next regular frame.
+pub fn export_view<D: Display>(
+    gl: &glow::Context,
+    pool: &mut RenderTargetPool,
+    renderer: &mut Renderer,
+    ui: &conrod_core::Ui,
+    image_map: &image::Map<Texture>,
+    display: &D,
+    scale: u32,
+    is_srgb: bool,
+) -> Result<Vec<u8>, String> {
+    let (width, height) = display.framebuffer_dimensions();
+    let (width, height) = (width * scale, height * scale);
+    let display = (width, height, display.hidpi_factor() * scale as f64);
+    renderer
+        .fill(&display, gl, ui.draw(), image_map)
+        .map_err(|err| err.to_string())?;
+
+    let target = pool.take(gl, width, height, is_srgb)?;
+    target.bind(gl);
+    let frame = renderer.begin_frame(gl, (width, height));
+    frame.clear([0.0, 0.0, 0.0, 1.0]);
+    let result = renderer.draw(gl, image_map).map_err(|err| err.to_string());
+    drop(frame);
+    let pixels = target.read_pixels(gl);
+    unsafe {
+        gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+    }
+    pool.give_back(gl, target);
+    result?;
+
+    let mut png = Vec::new();
+    ::image::png::PNGEncoder::new(&mut png)
+        .encode(&pixels, width, height, ::image::ColorType::RGBA(8))
+        .map_err(|e| e.to_string())?;
+    Ok(png)
+}
@@ -0,0 +1,270 @@
+// Windows opened from the New window menu on the backdrop, any number of
+// each kind, until their Close button is clicked.
+//
+// Each window has a slot, and the slot has a `WinId` in `WinIds::extra`. A
+// closed window leaves its slot empty for the next one, which gets the same
+// `WinId`, as floatwin has no way to forget one. floatwin keeps the place of
+// the window for the id, so the next one opens where the last one was closed.
+//
+// Windows are known by their titles everywhere else, so the titles are
+// `&'static str`. They are numbered per kind, and leaked once for each number,
+// which keeps them to as many as were ever open at the same time.
+
+use crate::bridge::Bridge;
+use crate::image_viewer::{ImageViewerState, ImageViewerWidget};
+use crate::title_bar::{TitleBarItem, TitleBarItems};
+use conrod_core::{
+    color, widget, widget_ids, Color, Colorable, Labelable, Positionable, Sizeable, Widget,
+    WidgetCommon,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExtraKind {
+    TextEditor,
+    ColorPicker,
+    ImageViewer,
+}
+
+impl ExtraKind {
+    pub const ALL: [ExtraKind; 3] = [
+        ExtraKind::TextEditor,
+        ExtraKind::ColorPicker,
+        ExtraKind::ImageViewer,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ExtraKind::TextEditor => "Text Editor",
+            ExtraKind::ColorPicker => "Color Picker",
+            ExtraKind::ImageViewer => "Image Viewer",
+        }
+    }
+}
+
+pub enum ExtraContent {
+    TextEditor(String),
+    /// The red, green and blue of the color.
+    ColorPicker([f32; 3]),
+    ImageViewer(ImageViewerState),
+}
+
+pub struct ExtraWindow {
+    pub title: &'static str,
+    pub content: ExtraContent,
+    /// Set by the Close button in the title bar.
+    pub close_requested: bool,
+}
+
+impl TitleBarItems for ExtraWindow {
+    fn title_bar_items(&self) -> Vec<TitleBarItem> {
+        vec![TitleBarItem::Button("Close".to_owned())]
+    }
+
+    fn title_bar_clicked(&mut self, _index: usize) {
+        self.close_requested = true;
+    }
+}
+
+pub struct ExtraWindows {
+    slots: Vec<Option<ExtraWindow>>,
+    /// The titles leaked so far, by kind and number.
+    titles: Vec<(ExtraKind, usize, &'static str)>,
+}
+
+impl Default for ExtraWindows {
+    fn default() -> Self {
+        ExtraWindows::new()
+    }
+}
+
+impl ExtraWindows {
+    pub fn new() -> Self {
+        ExtraWindows {
+            slots: Vec::new(),
+            titles: Vec::new(),
+        }
+    }
+
+    /// How many slots there are, open or not, which is how many `WinId`s
+    /// the windows need.
+    pub fn slot_count(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Open a window of `kind` in the first empty slot, and return its
+    /// title. An image viewer starts with `image`.
+    pub fn open(
+        &mut self,
+        kind: ExtraKind,
+        image: Option<crate::image_viewer::LoadedImage>,
+    ) -> &'static str {
+        let title = self.free_title(kind);
+        let content = match kind {
+            ExtraKind::TextEditor => ExtraContent::TextEditor(String::new()),
+            ExtraKind::ColorPicker => ExtraContent::ColorPicker([0.5, 0.5, 0.5]),
+            ExtraKind::ImageViewer => {
+                let mut viewer = ImageViewerState::new();
+                viewer.image = image;
+                ExtraContent::ImageViewer(viewer)
+            }
+        };
+        let window = ExtraWindow {
+            title,
+            content,
+            close_requested: false,
+        };
+        match self.slots.iter().position(Option::is_none) {
+            Some(slot) => self.slots[slot] = Some(window),
+            None => self.slots.push(Some(window)),
+        }
+        title
+    }
+
+    /// Close the window in `slot`, leaving the slot for the next one.
+    pub fn close(&mut self, slot: usize) -> Option<ExtraWindow> {
+        self.slots.get_mut(slot)?.take()
+    }
+
+    /// The open windows with their slots.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (usize, &mut ExtraWindow)> {
+        self.slots
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(slot, window)| Some((slot, window.as_mut()?)))
+    }
+
+    /// The lowest numbered title of `kind` which no open window has.
+    fn free_title(&mut self, kind: ExtraKind) -> &'static str {
+        let open: Vec<&'static str> = self.slots.iter().flatten().map(|w| w.title).collect();
+        let mut number = 1;
+        loop {
+            let title = match self
+                .titles
+                .iter()
+                .find(|&&(k, n, _)| k == kind && n == number)
+            {
+                Some(&(_, _, title)) => title,
+                None => {
+                    let title: &'static str =
+                        Box::leak(format!("{} {}", kind.name(), number).into_boxed_str());
+                    self.titles.push((kind, number, title));
+                    title
+                }
+            };
+            if !open.contains(&title) {
+                return title;
+            }
+            number += 1;
+        }
+    }
+}
+
+/// The content of an extra window.
+#[derive(WidgetCommon)]
+pub struct ExtraWindowWidget<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    content: &'a mut ExtraContent,
+    bridge: &'a Bridge,
+}
+
+impl<'a> ExtraWindowWidget<'a> {
+    pub fn new(content: &'a mut ExtraContent, bridge: &'a Bridge) -> Self {
+        ExtraWindowWidget {
+            common: widget::CommonBuilder::default(),
+            content,
+            bridge,
+        }
+    }
+}
+
+widget_ids! {
+    pub struct ExtraWindowIds {
+        canvas,
+        text,
+        red,
+        green,
+        blue,
+        swatch,
+        viewer,
+    }
+}
+
+impl<'a> Widget for ExtraWindowWidget<'a> {
+    type State = ExtraWindowIds;
+    type Style = ();
+    type Event = ();
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        ExtraWindowIds::new(id_gen)
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs { id, state, ui, .. } = args;
+        let ExtraWindowWidget {
+            content, bridge, ..
+        } = self;
+
+        const PAD: conrod_core::Scalar = 8.0;
+        const ROW_H: conrod_core::Scalar = 28.0;
+
+        match content {
+            ExtraContent::TextEditor(text) => {
+                widget::Canvas::new()
+                    .color(color::CHARCOAL)
+                    .border(0.0)
+                    .wh_of(id)
+                    .middle_of(id)
+                    .scroll_kids_vertically()
+                    .set(state.canvas, ui);
+                if let Some(new_text) = widget::TextEdit::new(text)
+                    .font_size(14)
+                    .color(color::WHITE)
+                    .padded_w_of(state.canvas, PAD)
+                    .mid_top_with_margin_on(state.canvas, PAD)
+                    .left_justify()
+                    .restrict_to_height(false)
+                    .set(state.text, ui)
+                {
+                    *text = new_text;
+                }
+            }
+            ExtraContent::ColorPicker(rgb) => {
+                let channels = [
+                    ("Red", state.red, color::RED),
+                    ("Green", state.green, color::GREEN),
+                    ("Blue", state.blue, color::BLUE),
+                ];
+                for (i, &(label, slider, slider_color)) in channels.iter().enumerate() {
+                    if let Some(value) = widget::Slider::new(rgb[i], 0.0, 1.0)
+                        .label(label)
+                        .label_font_size(14)
+                        .color(slider_color)
+                        .label_color(color::WHITE)
+                        .padded_w_of(id, PAD)
+                        .h(ROW_H)
+                        .mid_top_with_margin_on(id, PAD + (ROW_H + PAD) * i as f64)
+                        .set(slider, ui)
+                    {
+                        rgb[i] = value;
+                    }
+                }
+                let swatch_top = PAD + (ROW_H + PAD) * channels.len() as f64;
+                let swatch_h = (ui.h_of(id).unwrap_or(0.0) - swatch_top - PAD).max(0.0);
+                widget::Rectangle::fill([0.0, swatch_h])
+                    .color(Color::Rgba(rgb[0], rgb[1], rgb[2], 1.0))
+                    .padded_w_of(id, PAD)
+                    .mid_top_with_margin_on(id, swatch_top)
+                    .set(state.swatch, ui);
+            }
+            ExtraContent::ImageViewer(viewer) => {
+                ImageViewerWidget::new(viewer, bridge)
+                    .wh_of(id)
+                    .middle_of(id)
+                    .set(state.viewer, ui);
+            }
+        }
+    }
+}
@@ -0,0 +1,227 @@
+// The "File Browser" window, listing the assets in a `TreeView`.
+//
+// On native the assets directory is read as the nodes are expanded. There is
+// no file system on wasm, so a fixed tree of the assets built into the app is
+// shown instead.
+
+use crate::search_bar::{SearchBar, SearchState};
+use crate::tree_view::{TreeEntry, TreeSource, TreeState, TreeView};
+use conrod_core::{
+    color, widget, widget_ids, Colorable, Positionable, Sizeable, Widget, WidgetCommon,
+};
+
+/// Lists a directory on the file system.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct DirSource {
+    root: std::path::PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl DirSource {
+    pub fn new(root: std::path::PathBuf) -> Self {
+        DirSource { root }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl TreeSource for DirSource {
+    fn children(&self, path: &[&str]) -> Vec<TreeEntry> {
+        let dir = path
+            .iter()
+            .fold(self.root.clone(), |dir, name| dir.join(name));
+        let read_dir = match std::fs::read_dir(&dir) {
+            Ok(read_dir) => read_dir,
+            // Shown in place of the children, where the user expanded the node.
+            Err(err) => {
+                return vec![TreeEntry {
+                    label: format!("Cannot list this folder: {}", err),
+                    has_children: false,
+                }]
+            }
+        };
+        let mut entries: Vec<TreeEntry> = read_dir
+            .filter_map(Result::ok)
+            .map(|entry| TreeEntry {
+                label: entry.file_name().to_string_lossy().into_owned(),
+                has_children: entry.file_type().map_or(false, |t| t.is_dir()),
+            })
+            .collect();
+        // Directories first, then by name.
+        entries.sort_by(|a, b| {
+            b.has_children
+                .cmp(&a.has_children)
+                .then_with(|| a.label.cmp(&b.label))
+        });
+        entries
+    }
+}
+
+/// A fixed tree, given as the paths of the leaves.
+pub struct VirtualSource {
+    paths: Vec<&'static str>,
+}
+
+impl VirtualSource {
+    /// The assets built into the app.
+    pub fn assets() -> Self {
+        VirtualSource {
+            paths: vec![
+                "fonts/NotoSans/LICENSE-2.0.txt",
+                "fonts/NotoSans/NotoSans-Regular.ttf",
+                "images/rust.png",
+            ],
+        }
+    }
+}
+
+impl TreeSource for VirtualSource {
+    fn children(&self, path: &[&str]) -> Vec<TreeEntry> {
+        let mut entries: Vec<TreeEntry> = Vec::new();
+        for leaf in &self.paths {
+            let mut parts = leaf.split('/');
+            if !path.iter().all(|&name| parts.next() == Some(name)) {
+                continue;
+            }
+            if let Some(label) = parts.next() {
+                let has_children = parts.next().is_some();
+                if !entries.iter().any(|e| e.label == label) {
+                    entries.push(TreeEntry {
+                        label: label.to_owned(),
+                        has_children,
+                    });
+                }
+            }
+        }
+        entries
+    }
+}
+
+pub struct FileBrowserState {
+    source: Box<dyn TreeSource>,
+    tree: TreeState,
+    search: SearchState,
+}
+
+impl Default for FileBrowserState {
+    fn default() -> Self {
+        FileBrowserState::new()
+    }
+}
+
+impl FileBrowserState {
+    pub fn new() -> Self {
+        FileBrowserState {
+            source: Box::new(VirtualSource::assets()),
+            tree: TreeState::new(),
+            search: SearchState::new(),
+        }
+    }
+
+    /// Show the nodes from `source` instead.
+    pub fn set_source(&mut self, source: Box<dyn TreeSource>) {
+        self.source = source;
+        self.tree.reset();
+    }
+}
+
+#[derive(WidgetCommon)]
+pub struct FileBrowserWidget<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    browser: &'a mut FileBrowserState,
+    find_in: Option<widget::Id>,
+}
+
+impl<'a> FileBrowserWidget<'a> {
+    /// `find_in` is the window Ctrl+F was pressed in, if any, which opens
+    /// the search bar if it is this one.
+    pub fn new(browser: &'a mut FileBrowserState, find_in: Option<widget::Id>) -> Self {
+        FileBrowserWidget {
+            common: widget::CommonBuilder::default(),
+            browser,
+            find_in,
+        }
+    }
+}
+
+widget_ids! {
+    pub struct FileBrowserIds {
+        search_bar,
+        tree,
+        path,
+    }
+}
+
+impl<'a> Widget for FileBrowserWidget<'a> {
+    type State = FileBrowserIds;
+    type Style = ();
+    type Event = ();
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        FileBrowserIds::new(id_gen)
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs {
+            id,
+            state,
+            ui,
+            rect,
+            ..
+        } = args;
+        let FileBrowserState {
+            source,
+            tree,
+            search,
+        } = self.browser;
+
+        const PAD: conrod_core::Scalar = 8.0;
+        const ROW_H: conrod_core::Scalar = 28.0;
+        const SEARCH_H: conrod_core::Scalar = 24.0;
+
+        // Only the nodes which have been loaded and are visible are searched.
+        search.open_if_requested(ui, id, self.find_in);
+        let matches: Vec<usize> = if search.open {
+            tree.visible_nodes()
+                .into_iter()
+                .filter(|&node| search.matches(tree.label(node)))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let mut tree_top = PAD;
+        if search.open {
+            if SearchBar::new(search, matches.len())
+                .w_h((rect.w() - PAD * 2.0).max(0.0), SEARCH_H)
+                .top_left_with_margin_on(id, PAD)
+                .set(state.search_bar, ui)
+            {
+                if let Some(&node) = matches.get(search.current) {
+                    tree.select(node);
+                }
+            }
+            tree_top += SEARCH_H + PAD;
+        }
+
+        TreeView::new(tree, &**source)
+            .highlighted(&matches)
+            .w_h(
+                (rect.w() - PAD * 2.0).max(0.0),
+                (rect.h() - tree_top - ROW_H - PAD * 2.0).max(0.0),
+            )
+            .top_left_with_margins_on(id, tree_top, PAD)
+            .set(state.tree, ui);
+
+        let path = match tree.selected_path() {
+            Some(path) => path.join("/"),
+            None => "Use the arrow keys or click to browse".to_owned(),
+        };
+        widget::Text::new(&path)
+            .font_size(12)
+            .color(color::LIGHT_GREY)
+            .down_from(state.tree, PAD)
+            .set(state.path, ui);
+    }
+}
@@ -0,0 +1,431 @@
+// Platform abstraction for opening and saving files.
+//
+// On native the dialogs are provided by `rfd`, and they and the reading and
+// writing run on a thread of their own, as they are called from the update
+// of the widgets and the dialogs wait for the user. On wasm, the pickers of
+// the File System Access API are used where the browser has them. Elsewhere
+// opening uses a hidden `<input type="file">` element and saving triggers a
+// download through an `<a download>` anchor. Results are always delivered
+// asynchronously as `AppEvent::File` through the `Bridge`, so callers don't
+// need to care which platform they are on.
+
+use crate::bridge::{AppEvent, Bridge};
+
+/// What an opened file is going to be used for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FilePurpose {
+    Notes,
+    Image,
+    /// A frame trace, which is only ever saved.
+    Trace,
+    /// A session exported from the demo.
+    Session,
+}
+
+impl FilePurpose {
+    /// Guess what a file is for from its extension.
+    pub fn from_path(path: &std::path::Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        [FilePurpose::Notes, FilePurpose::Image, FilePurpose::Session]
+            .iter()
+            .copied()
+            .find(|purpose| purpose.extensions().contains(&ext.as_str()))
+    }
+
+    fn extensions(self) -> &'static [&'static str] {
+        match self {
+            FilePurpose::Notes => &["txt", "md"],
+            FilePurpose::Image => &["png", "jpg", "jpeg", "gif", "bmp"],
+            FilePurpose::Trace => &["json"],
+            FilePurpose::Session => &["json"],
+        }
+    }
+
+    fn filter_name(self) -> &'static str {
+        match self {
+            FilePurpose::Notes => "Text files",
+            FilePurpose::Image => "Images",
+            FilePurpose::Trace => "Chrome traces",
+            FilePurpose::Session => "Demo sessions",
+        }
+    }
+
+    /// The MIME type of the files, which the web file pickers ask for along
+    /// with the extensions.
+    #[cfg(target_arch = "wasm32")]
+    fn mime_type(self) -> &'static str {
+        match self {
+            FilePurpose::Notes => "text/plain",
+            FilePurpose::Image => "image/*",
+            FilePurpose::Trace | FilePurpose::Session => "application/json",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum FileEvent {
+    Opened {
+        purpose: FilePurpose,
+        name: String,
+        data: Vec<u8>,
+    },
+    Saved {
+        purpose: FilePurpose,
+        name: String,
+    },
+    Error(String),
+}
+
+/// Run `future` to the end on this thread, for the async dialogs of `rfd`,
+/// which can be shown from any thread unlike the blocking ones.
+#[cfg(not(target_arch = "wasm32"))]
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake};
+
+    struct Unpark(std::thread::Thread);
+
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Arc::new(Unpark(std::thread::current())).into();
+    let mut cx = Context::from_waker(&waker);
+    let mut future = Box::pin(future);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => std::thread::park(),
+        }
+    }
+}
+
+/// Ask the user for a file to open.
+///
+/// Nothing is sent if the user cancels the dialog.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn open_file(bridge: &Bridge, purpose: FilePurpose) {
+    let bridge = bridge.clone();
+    std::thread::spawn(move || {
+        let file = block_on(
+            rfd::AsyncFileDialog::new()
+                .add_filter(purpose.filter_name(), purpose.extensions())
+                .pick_file(),
+        );
+        if let Some(file) = file {
+            read_file(&bridge, purpose, file.path());
+        }
+    });
+}
+
+/// Open a file by its path, e.g. from the recent files list or when it is
+/// dropped onto the window.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn open_path(bridge: &Bridge, path: &std::path::Path) {
+    match FilePurpose::from_path(path) {
+        Some(purpose) => read_file(bridge, purpose, path),
+        None => {
+            let err = format!("Don't know how to open {}", path.display());
+            bridge.send(AppEvent::File(FileEvent::Error(err)));
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_file(bridge: &Bridge, purpose: FilePurpose, path: &std::path::Path) {
+    let event = match std::fs::read(path) {
+        Ok(data) => FileEvent::Opened {
+            purpose,
+            name: path.display().to_string(),
+            data,
+        },
+        Err(err) => FileEvent::Error(format!("Cannot read {}: {}", path.display(), err)),
+    };
+    bridge.send(AppEvent::File(event));
+}
+
+/// Ask the user where to save `data`.
+///
+/// Nothing is sent if the user cancels the dialog.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_file(bridge: &Bridge, purpose: FilePurpose, suggested_name: &str, data: &[u8]) {
+    let bridge = bridge.clone();
+    let suggested_name = suggested_name.to_owned();
+    let data = data.to_vec();
+    std::thread::spawn(move || {
+        let file = block_on(
+            rfd::AsyncFileDialog::new()
+                .add_filter(purpose.filter_name(), purpose.extensions())
+                .set_file_name(&suggested_name)
+                .save_file(),
+        );
+        let path = match file {
+            Some(file) => file.path().to_owned(),
+            None => return,
+        };
+        let event = match std::fs::write(&path, data) {
+            Ok(()) => FileEvent::Saved {
+                purpose,
+                name: path.display().to_string(),
+            },
+            Err(err) => FileEvent::Error(format!("Cannot write {}: {}", path.display(), err)),
+        };
+        bridge.send(AppEvent::File(event));
+    });
+}
+
+/// Ask the user for a file to open.
+///
+/// Nothing is sent if the user cancels the dialog.
+#[cfg(target_arch = "wasm32")]
+pub fn open_file(bridge: &Bridge, purpose: FilePurpose) {
+    if web::has_file_pickers() {
+        web::pick_file_to_open(bridge.clone(), purpose);
+    } else if let Err(err) = web::open_file(bridge.clone(), purpose) {
+        bridge.send(AppEvent::File(FileEvent::Error(err)));
+    }
+}
+
+/// Ask the user where to save `data`, or offer it as a download if the
+/// browser has no file pickers.
+///
+/// For a download the browser decides where the file goes, so `Saved` is
+/// sent as soon as it has been started. Nothing is sent if the user cancels
+/// the picker.
+#[cfg(target_arch = "wasm32")]
+pub fn save_file(bridge: &Bridge, purpose: FilePurpose, suggested_name: &str, data: &[u8]) {
+    if web::has_file_pickers() {
+        web::pick_file_to_save(bridge.clone(), purpose, suggested_name, data.to_vec());
+        return;
+    }
+    let event = match web::download(suggested_name, data) {
+        Ok(()) => FileEvent::Saved {
+            purpose,
+            name: suggested_name.to_owned(),
+        },
+        Err(err) => FileEvent::Error(err),
+    };
+    bridge.send(AppEvent::File(event));
+}
+
+#[cfg(target_arch = "wasm32")]
+mod web {
+    use super::{FileEvent, FilePurpose};
+    use crate::bridge::{AppEvent, Bridge};
+    use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+    use wasm_bindgen_futures::JsFuture;
+
+    /// How long the object URL of a download is kept, as the download may
+    /// only start reading it after `click` returns.
+    const DOWNLOAD_URL_MS: i32 = 40_000;
+
+    fn js_err(err: JsValue) -> String {
+        err.as_string().unwrap_or_else(|| format!("{:?}", err))
+    }
+
+    /// Whether the browser has `showOpenFilePicker` and `showSaveFilePicker`
+    /// of the File System Access API. They aren't in web-sys without its
+    /// unstable APIs, so they are looked up and called through `Reflect`.
+    pub(super) fn has_file_pickers() -> bool {
+        let window = match web_sys::window() {
+            Some(window) => window,
+            None => return false,
+        };
+        ["showOpenFilePicker", "showSaveFilePicker"]
+            .iter()
+            .all(|name| js_sys::Reflect::has(&window, &(*name).into()).unwrap_or(false))
+    }
+
+    /// Call the method `name` of `target` and wait for the promise it returns.
+    async fn call(target: &JsValue, name: &str, args: &[&JsValue]) -> Result<JsValue, JsValue> {
+        let method: js_sys::Function = js_sys::Reflect::get(target, &name.into())?.dyn_into()?;
+        let args: js_sys::Array = args.iter().collect();
+        let promise: js_sys::Promise = method.apply(target, &args)?.dyn_into()?;
+        JsFuture::from(promise).await
+    }
+
+    /// The options of the pickers for the files of `purpose`.
+    fn picker_options(purpose: FilePurpose) -> Result<js_sys::Object, JsValue> {
+        let extensions: js_sys::Array = purpose
+            .extensions()
+            .iter()
+            .map(|ext| JsValue::from(format!(".{}", ext)))
+            .collect();
+        let accept = js_sys::Object::new();
+        js_sys::Reflect::set(&accept, &purpose.mime_type().into(), &extensions)?;
+        let file_type = js_sys::Object::new();
+        js_sys::Reflect::set(
+            &file_type,
+            &"description".into(),
+            &purpose.filter_name().into(),
+        )?;
+        js_sys::Reflect::set(&file_type, &"accept".into(), &accept)?;
+        let options = js_sys::Object::new();
+        js_sys::Reflect::set(&options, &"types".into(), &js_sys::Array::of1(&file_type))?;
+        Ok(options)
+    }
+
+    /// Send what `result` came to, where `Ok(None)` and an `AbortError`
+    /// are the user cancelling the picker.
+    fn send_result(bridge: &Bridge, result: Result<Option<FileEvent>, JsValue>) {
+        let event = match result {
+            Ok(Some(event)) => event,
+            Ok(None) => return,
+            Err(err) => {
+                let name = js_sys::Reflect::get(&err, &"name".into()).ok();
+                if name.and_then(|name| name.as_string()).as_deref() == Some("AbortError") {
+                    return;
+                }
+                FileEvent::Error(js_err(err))
+            }
+        };
+        bridge.send(AppEvent::File(event));
+    }
+
+    /// Open a file through a hidden file input, for browsers without the
+    /// pickers.
+    pub(super) fn open_file(bridge: Bridge, purpose: FilePurpose) -> Result<(), String> {
+        let document = web_sys::window()
+            .and_then(|w| w.document())
+            .ok_or("no document")?;
+        let input: web_sys::HtmlInputElement = document
+            .create_element("input")
+            .map_err(js_err)?
+            .unchecked_into();
+        input.set_type("file");
+        let accept: Vec<String> = purpose
+            .extensions()
+            .iter()
+            .map(|ext| format!(".{}", ext))
+            .collect();
+        input.set_accept(&accept.join(","));
+
+        let on_change = {
+            let input = input.clone();
+            Closure::once(move |_: web_sys::Event| {
+                let file = match input.files().and_then(|files| files.get(0)) {
+                    Some(file) => file,
+                    None => return,
+                };
+                let reader = match web_sys::FileReader::new() {
+                    Ok(reader) => reader,
+                    Err(err) => {
+                        bridge.send(AppEvent::File(FileEvent::Error(js_err(err))));
+                        return;
+                    }
+                };
+                let name = file.name();
+                let on_load = {
+                    let reader = reader.clone();
+                    Closure::once(move |_: web_sys::Event| {
+                        let event = match reader.result() {
+                            Ok(result) => FileEvent::Opened {
+                                purpose,
+                                name,
+                                data: js_sys::Uint8Array::new(&result).to_vec(),
+                            },
+                            Err(err) => FileEvent::Error(js_err(err)),
+                        };
+                        bridge.send(AppEvent::File(event));
+                    })
+                };
+                reader.set_onload(Some(on_load.as_ref().unchecked_ref()));
+                // The callback owns everything it needs and is only called once.
+                on_load.forget();
+                if let Err(err) = reader.read_as_array_buffer(&file) {
+                    web_sys::console::error_1(&err);
+                }
+            })
+        };
+        input.set_onchange(Some(on_change.as_ref().unchecked_ref()));
+        on_change.forget();
+        input.click();
+        Ok(())
+    }
+
+    pub(super) fn pick_file_to_open(bridge: Bridge, purpose: FilePurpose) {
+        wasm_bindgen_futures::spawn_local(async move {
+            send_result(&bridge, open_picked(purpose).await);
+        });
+    }
+
+    async fn open_picked(purpose: FilePurpose) -> Result<Option<FileEvent>, JsValue> {
+        let window = web_sys::window().ok_or("no window")?;
+        let options = picker_options(purpose)?;
+        let handles = call(&window, "showOpenFilePicker", &[&options]).await?;
+        let handle = js_sys::Array::from(&handles).get(0);
+        if handle.is_undefined() {
+            return Ok(None);
+        }
+        let file: web_sys::File = call(&handle, "getFile", &[]).await?.dyn_into()?;
+        let buffer = JsFuture::from(file.array_buffer()).await?;
+        Ok(Some(FileEvent::Opened {
+            purpose,
+            name: file.name(),
+            data: js_sys::Uint8Array::new(&buffer).to_vec(),
+        }))
+    }
+
+    pub(super) fn pick_file_to_save(
+        bridge: Bridge,
+        purpose: FilePurpose,
+        suggested_name: &str,
+        data: Vec<u8>,
+    ) {
+        let suggested_name = suggested_name.to_owned();
+        wasm_bindgen_futures::spawn_local(async move {
+            send_result(&bridge, save_picked(purpose, suggested_name, data).await);
+        });
+    }
+
+    async fn save_picked(
+        purpose: FilePurpose,
+        suggested_name: String,
+        data: Vec<u8>,
+    ) -> Result<Option<FileEvent>, JsValue> {
+        let window = web_sys::window().ok_or("no window")?;
+        let options = picker_options(purpose)?;
+        js_sys::Reflect::set(&options, &"suggestedName".into(), &suggested_name.into())?;
+        let handle = call(&window, "showSaveFilePicker", &[&options]).await?;
+        let writable = call(&handle, "createWritable", &[]).await?;
+        let bytes = js_sys::Uint8Array::from(&data[..]);
+        call(&writable, "write", &[&bytes]).await?;
+        call(&writable, "close", &[]).await?;
+        let name = js_sys::Reflect::get(&handle, &"name".into())?;
+        Ok(Some(FileEvent::Saved {
+            purpose,
+            name: name.as_string().unwrap_or_default(),
+        }))
+    }
+
+    pub(super) fn download(name: &str, data: &[u8]) -> Result<(), String> {
+        let document = web_sys::window()
+            .and_then(|w| w.document())
+            .ok_or("no document")?;
+        let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(data));
+        let blob = web_sys::Blob::new_with_u8_array_sequence(&parts).map_err(js_err)?;
+        let url = web_sys::Url::create_object_url_with_blob(&blob).map_err(js_err)?;
+        let anchor: web_sys::HtmlAnchorElement = document
+            .create_element("a")
+            .map_err(js_err)?
+            .unchecked_into();
+        anchor.set_href(&url);
+        anchor.set_download(name);
+        anchor.click();
+        let revoke = Closure::once(move || {
+            let _ = web_sys::Url::revoke_object_url(&url);
+        });
+        web_sys::window()
+            .ok_or("no window")?
+            .set_timeout_with_callback_and_timeout_and_arguments_0(
+                revoke.as_ref().unchecked_ref(),
+                DOWNLOAD_URL_MS,
+            )
+            .map_err(js_err)?;
+        // The callback owns the URL and is only called once.
+        revoke.forget();
+        Ok(())
+    }
+}
@@ -0,0 +1,233 @@
+// The "File" drop-down menu on the backdrop.
+
+use crate::file_dialog::FilePurpose;
+use conrod_core::{
+    color, widget, widget_ids, Colorable, Labelable, Positionable, Sizeable, Widget, WidgetCommon,
+};
+
+/// An action picked from the menu.
+#[derive(Clone, Debug)]
+pub enum FileMenuAction {
+    Open(FilePurpose),
+    OpenRecent(String),
+    /// Export the current view as an image, scaled by the given factor.
+    ExportView(u32),
+    /// Record the UI for the given number of seconds.
+    Record(u32),
+    /// Start recording a frame trace, or stop and save it.
+    ToggleTrace,
+    ExportSession,
+}
+
+#[derive(WidgetCommon)]
+pub struct FileMenu<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    is_open: &'a mut bool,
+    recent_files: &'a [String],
+    tracing: bool,
+}
+
+impl<'a> FileMenu<'a> {
+    pub fn new(is_open: &'a mut bool, recent_files: &'a [String]) -> Self {
+        FileMenu {
+            common: widget::CommonBuilder::default(),
+            is_open,
+            recent_files,
+            tracing: false,
+        }
+    }
+
+    /// Whether a frame trace is being recorded.
+    pub fn tracing(mut self, tracing: bool) -> Self {
+        self.tracing = tracing;
+        self
+    }
+}
+
+widget_ids! {
+    pub struct FileMenuIds {
+        toggle,
+        panel,
+        open_notes,
+        open_image,
+        import_session,
+        export_session,
+        export[],
+        record,
+        trace,
+        recent_label,
+        recent[],
+    }
+}
+
+impl<'a> Widget for FileMenu<'a> {
+    type State = FileMenuIds;
+    type Style = ();
+    type Event = Option<FileMenuAction>;
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        FileMenuIds::new(id_gen)
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    // Only the menu items should block the widgets underneath.
+    fn is_over(&self) -> widget::IsOverFn {
+        |_, _, _| widget::IsOver::Bool(false)
+    }
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs { id, state, ui, .. } = args;
+        let FileMenu {
+            is_open,
+            recent_files,
+            tracing,
+            ..
+        } = self;
+
+        const ITEM_W: conrod_core::Scalar = 240.0;
+        const ITEM_H: conrod_core::Scalar = 26.0;
+
+        if widget::Button::new()
+            .label("File")
+            .label_font_size(14)
+            .w_h(60.0, ITEM_H)
+            .top_left_of(id)
+            .set(state.toggle, ui)
+            .was_clicked()
+        {
+            *is_open = !*is_open;
+        }
+        if !*is_open {
+            return None;
+        }
+
+        // Show the file names only; the full paths would be too long.
+        let display_name = |name: &str| -> String {
+            std::path::Path::new(name)
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or(name)
+                .to_owned()
+        };
+
+        const EXPORT_SCALES: [u32; 3] = [1, 2, 4];
+
+        const RECORD_SECONDS: u32 = 5;
+
+        let item_count = 7 + EXPORT_SCALES.len() + recent_files.len();
+        widget::Canvas::new()
+            .color(color::DARK_CHARCOAL)
+            .w_h(ITEM_W, ITEM_H * item_count as conrod_core::Scalar)
+            .down_from(state.toggle, 0.0)
+            .set(state.panel, ui);
+
+        let mut action = None;
+        fn item(label: &str) -> widget::Button<widget::button::Flat> {
+            widget::Button::new()
+                .label(label)
+                .label_font_size(14)
+                .w_h(ITEM_W, ITEM_H)
+        }
+        if item("Open notes...")
+            .top_left_of(state.panel)
+            .set(state.open_notes, ui)
+            .was_clicked()
+        {
+            action = Some(FileMenuAction::Open(FilePurpose::Notes));
+        }
+        if item("Open image...")
+            .down_from(state.open_notes, 0.0)
+            .set(state.open_image, ui)
+            .was_clicked()
+        {
+            action = Some(FileMenuAction::Open(FilePurpose::Image));
+        }
+
+        if state.export.len() < EXPORT_SCALES.len() {
+            let id_gen = &mut ui.widget_id_generator();
+            state.update(|state| state.export.resize(EXPORT_SCALES.len(), id_gen));
+        }
+        if item("Import session...")
+            .down_from(state.open_image, 0.0)
+            .set(state.import_session, ui)
+            .was_clicked()
+        {
+            action = Some(FileMenuAction::Open(FilePurpose::Session));
+        }
+        if item("Export session...")
+            .down_from(state.import_session, 0.0)
+            .set(state.export_session, ui)
+            .was_clicked()
+        {
+            action = Some(FileMenuAction::ExportSession);
+        }
+
+        let mut prev = state.export_session;
+        for (i, &scale) in EXPORT_SCALES.iter().enumerate() {
+            if item(&format!("Export view ({}x)...", scale))
+                .down_from(prev, 0.0)
+                .set(state.export[i], ui)
+                .was_clicked()
+            {
+                action = Some(FileMenuAction::ExportView(scale));
+            }
+            prev = state.export[i];
+        }
+        if item(&format!("Record {} seconds", RECORD_SECONDS))
+            .down_from(prev, 0.0)
+            .set(state.record, ui)
+            .was_clicked()
+        {
+            action = Some(FileMenuAction::Record(RECORD_SECONDS));
+        }
+        let trace_label = if tracing {
+            "Stop frame trace..."
+        } else {
+            "Start frame trace"
+        };
+        if item(trace_label)
+            .down_from(state.record, 0.0)
+            .set(state.trace, ui)
+            .was_clicked()
+        {
+            action = Some(FileMenuAction::ToggleTrace);
+        }
+        prev = state.trace;
+
+        widget::Text::new(if recent_files.is_empty() {
+            "No recent files"
+        } else {
+            "Recent"
+        })
+        .font_size(12)
+        .color(color::LIGHT_GREY)
+        .h(ITEM_H)
+        .down_from(prev, 0.0)
+        .set(state.recent_label, ui);
+
+        if state.recent.len() < recent_files.len() {
+            let id_gen = &mut ui.widget_id_generator();
+            state.update(|state| state.recent.resize(recent_files.len(), id_gen));
+        }
+        prev = state.recent_label;
+        for (i, name) in recent_files.iter().enumerate() {
+            let label = display_name(name);
+            if item(&label)
+                .down_from(prev, 0.0)
+                .align_left_of(state.panel)
+                .set(state.recent[i], ui)
+                .was_clicked()
+            {
+                action = Some(FileMenuAction::OpenRecent(name.clone()));
+            }
+            prev = state.recent[i];
+        }
+
+        if action.is_some() {
+            *is_open = false;
+        }
+        action
+    }
+}
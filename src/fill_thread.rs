@@ -0,0 +1,104 @@
+// Filling the renderer on a worker thread, with `--fill-thread`, so that the
+// primitives of the next frame are translated while the main thread draws
+// the previous one.
+//
+// The worker owns a `conrod_glow::Tessellator` and gets owned primitives from
+// the event loop. It keeps only the latest mesh for the event loop, and posts
+// `AppEvent::MeshReady` to wake it up. The glyph cache of the worker assumes
+// that the texture got every glyph it rasterized, so a mesh replaced before
+// the event loop took it hands its glyph uploads to the next one.
+//
+// conrod can't own the primitives of custom widgets, so the glyph grid of the
+// Terminal window isn't drawn this way. The shapes of the debug layer are
+// sent along, as the text of the layer goes in the same glyph cache.
+
+use crate::bridge::{AppEvent, Bridge};
+use crate::conrod_glow::{Mesh, Tessellator};
+use crate::debug_draw;
+use conrod_core::{image, render::OwnedPrimitives, text};
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+
+struct FillRequest {
+    primitives: OwnedPrimitives,
+    /// The framebuffer size and the scale factor, as a `conrod_glow::Display`.
+    display: (u32, u32, f64),
+    image_sizes: HashMap<image::Id, (u32, u32)>,
+    debug_shapes: Vec<debug_draw::Shape>,
+    debug_font: Option<(text::font::Id, text::Font)>,
+}
+
+pub struct FillThread {
+    requests: Sender<FillRequest>,
+    ready: Arc<Mutex<Option<Mesh>>>,
+}
+
+impl FillThread {
+    pub fn start(mut tessellator: Tessellator, bridge: Bridge) -> Self {
+        let (requests, receiver) = mpsc::channel::<FillRequest>();
+        let ready = Arc::new(Mutex::new(None));
+        let worker_ready = ready.clone();
+        std::thread::spawn(move || {
+            while let Ok(mut request) = receiver.recv() {
+                // Only the latest frame is worth filling.
+                while let Ok(newer) = receiver.try_recv() {
+                    request = newer;
+                }
+                let mut mesh = tessellator.tessellate(
+                    &request.display,
+                    request.primitives.walk(),
+                    &request.image_sizes,
+                );
+                let debug_font = request.debug_font.as_ref().map(|(id, font)| (*id, font));
+                tessellator.tessellate_debug(
+                    &request.display,
+                    &mut mesh,
+                    &request.debug_shapes,
+                    debug_font,
+                );
+                let mut ready = match worker_ready.lock() {
+                    Ok(ready) => ready,
+                    Err(_) => return,
+                };
+                if let Some(older) = ready.take() {
+                    mesh.carry_glyphs_from(older);
+                }
+                *ready = Some(mesh);
+                drop(ready);
+                if !bridge.send(AppEvent::MeshReady) {
+                    return;
+                }
+            }
+        });
+        FillThread { requests, ready }
+    }
+
+    /// Fill `primitives` for a framebuffer of `framebuffer` pixels, with the images of
+    /// `image_sizes`, and the shapes of the debug layer above them in the first font of `fonts`.
+    pub fn submit(
+        &self,
+        primitives: OwnedPrimitives,
+        framebuffer: (u32, u32),
+        hidpi_factor: f64,
+        image_sizes: HashMap<image::Id, (u32, u32)>,
+        fonts: &text::font::Map,
+    ) {
+        let debug_font = fonts
+            .ids()
+            .next()
+            .and_then(|id| Some((id, fonts.get(id)?.clone())));
+        let _ = self.requests.send(FillRequest {
+            primitives,
+            display: (framebuffer.0, framebuffer.1, hidpi_factor),
+            image_sizes,
+            debug_shapes: debug_draw::take_frame(),
+            debug_font,
+        });
+    }
+
+    /// The latest mesh, if one was filled since the last call.
+    pub fn take(&self) -> Option<Mesh> {
+        self.ready.lock().ok()?.take()
+    }
+}
@@ -0,0 +1,118 @@
+// Which floating window has the focus, following the policy picked in the
+// Settings window.
+//
+// floatwin raises a window when it is clicked but has no notion of focus, so
+// the focus is tracked here from the widget under the mouse. It decides which
+// window gets window commands such as Ctrl+F. Text boxes still take the keys
+// from the last click, as conrod keeps the keyboard capture itself.
+
+use conrod_core::event;
+use conrod_core::{widget, UiCell};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FocusPolicy {
+    /// A window is focused when clicked, which also raises it.
+    ClickToFocus,
+    /// The window under the mouse is focused, without raising it. Nothing is
+    /// focused while the mouse is over the backdrop.
+    FocusFollowsMouse,
+    /// Like `FocusFollowsMouse`, but the focus stays on the last window when
+    /// the mouse leaves it for the backdrop.
+    SloppyFocus,
+}
+
+impl FocusPolicy {
+    pub const ALL: [FocusPolicy; 3] = [
+        FocusPolicy::ClickToFocus,
+        FocusPolicy::FocusFollowsMouse,
+        FocusPolicy::SloppyFocus,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FocusPolicy::ClickToFocus => "Click to focus",
+            FocusPolicy::FocusFollowsMouse => "Focus follows mouse",
+            FocusPolicy::SloppyFocus => "Sloppy focus",
+        }
+    }
+}
+
+impl Default for FocusPolicy {
+    fn default() -> Self {
+        FocusPolicy::ClickToFocus
+    }
+}
+
+pub struct FocusState {
+    /// The widget of the focused window, which is a child of the windowing
+    /// area.
+    focused: Option<widget::Id>,
+}
+
+impl Default for FocusState {
+    fn default() -> Self {
+        FocusState::new()
+    }
+}
+
+impl FocusState {
+    pub fn new() -> Self {
+        FocusState { focused: None }
+    }
+
+    pub fn focused(&self) -> Option<widget::Id> {
+        self.focused
+    }
+
+    /// Focus the window `window`, as picked in the window switcher.
+    pub fn set_focused(&mut self, window: widget::Id) {
+        self.focused = Some(window);
+    }
+
+    /// Move the focus according to `policy`, after the windows in the
+    /// windowing area `area` have been set.
+    pub fn update(&mut self, ui: &UiCell, area: widget::Id, policy: FocusPolicy) {
+        let hovered = window_under_mouse(ui, area);
+        match policy {
+            FocusPolicy::ClickToFocus => {
+                let pressed = ui.global_input().events().ui().any(|event| match event {
+                    event::Ui::Press(_, press) => match press.button {
+                        event::Button::Mouse(..) => true,
+                        _ => false,
+                    },
+                    _ => false,
+                });
+                if pressed {
+                    self.focused = hovered;
+                }
+            }
+            FocusPolicy::FocusFollowsMouse => self.focused = hovered,
+            FocusPolicy::SloppyFocus => {
+                if hovered.is_some() {
+                    self.focused = hovered;
+                }
+            }
+        }
+        // The window may have been closed.
+        if let Some(focused) = self.focused {
+            if ui.widget_graph().depth_parent(focused) != Some(area) {
+                self.focused = None;
+            }
+        }
+    }
+}
+
+/// The window of the widget under the mouse, found by going up from it to
+/// the child of the windowing area.
+fn window_under_mouse(ui: &UiCell, area: widget::Id) -> Option<widget::Id> {
+    let graph = ui.widget_graph();
+    let mut id = ui.global_input().current.widget_under_mouse?;
+    loop {
+        let parent = graph.depth_parent(id)?;
+        if parent == area {
+            return Some(id);
+        }
+        id = parent;
+    }
+}
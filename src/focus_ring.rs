@@ -0,0 +1,103 @@
+// A glowing outline around the widget with the keyboard, so that it is easy
+// to see where the keys go.
+//
+// conrod draws nothing to show which widget captures the keyboard, and a
+// text box looks the same with and without it. The ring is drawn by a
+// `FocusGlowPass` after the UI, over everything, so that the windows and
+// the cropping of their content never cut it off. When the keyboard moves to
+// another widget the ring fades in while closing in on it, which draws the
+// eye to it.
+
+use crate::conrod_glow::{FocusGlowPass, RendererInfo};
+use crate::trace::now_us;
+use conrod_core::{color, widget, Rect, Scalar};
+
+pub struct FocusRing {
+    /// The widget with the keyboard and its rect.
+    target: Option<(widget::Id, Rect)>,
+    /// When the keyboard moved to the widget, in microseconds.
+    since: f64,
+    /// Built when the ring is first drawn.
+    pass: Option<FocusGlowPass>,
+    /// The pass could not be built, and isn't tried again.
+    failed: bool,
+}
+
+impl Default for FocusRing {
+    fn default() -> Self {
+        FocusRing::new()
+    }
+}
+
+impl FocusRing {
+    const FADE_US: f64 = 250_000.0;
+    /// How far the glow reaches out of the widget once it has faded in, in
+    /// points.
+    const SPREAD: f32 = 6.0;
+    /// How much further it starts from.
+    const START_SPREAD: f32 = 18.0;
+
+    pub fn new() -> Self {
+        FocusRing {
+            target: None,
+            since: 0.0,
+            pass: None,
+            failed: false,
+        }
+    }
+
+    /// Follow the widget with the keyboard, after the widgets have been set,
+    /// `None` when no widget has it or the ring is turned off. Returns
+    /// whether the ring has to be drawn again.
+    pub fn update(&mut self, target: Option<(widget::Id, Rect)>) -> bool {
+        let now = now_us();
+        let moved = target.map(|(id, _)| id) != self.target.map(|(id, _)| id);
+        if moved {
+            self.since = now;
+        }
+        let changed = target != self.target;
+        self.target = target;
+        changed || self.is_fading(now)
+    }
+
+    fn is_fading(&self, now: f64) -> bool {
+        self.target.is_some() && now - self.since < Self::FADE_US
+    }
+
+    /// How far the ring has faded in at `now`, from 0.0 to 1.0, eased out.
+    fn progress(&self, now: f64) -> f32 {
+        let t = ((now - self.since) / Self::FADE_US).max(0.0).min(1.0) as f32;
+        1.0 - (1.0 - t).powi(3)
+    }
+
+    /// Draw the ring over the bound framebuffer of `size` pixels, which the
+    /// UI was drawn to with `info`.
+    pub fn draw(
+        &mut self,
+        gl: &glow::Context,
+        info: &RendererInfo,
+        size: (u32, u32),
+        dpi_factor: Scalar,
+    ) -> Result<(), String> {
+        let rect = match self.target {
+            Some((_, rect)) if !self.failed => rect,
+            _ => return Ok(()),
+        };
+        if self.pass.is_none() {
+            match FocusGlowPass::new(gl, info) {
+                Ok(pass) => self.pass = Some(pass),
+                Err(err) => {
+                    self.failed = true;
+                    return Err(format!("Cannot draw the focus ring: {}", err));
+                }
+            }
+        }
+        let progress = self.progress(now_us());
+        let spread = Self::START_SPREAD + (Self::SPREAD - Self::START_SPREAD) * progress;
+        let color = color::LIGHT_BLUE.alpha(0.9 * progress);
+        if let Some(pass) = &self.pass {
+            pass.draw(gl, rect, dpi_factor, size, color, spread);
+        }
+        Ok(())
+    }
+}
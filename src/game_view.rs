@@ -0,0 +1,169 @@
+// A game-like viewport moving a square around with WASD, using the raw
+// keyboard state instead of conrod's key events.
+//
+// The viewport claims the raw input while it has the keyboard, so that the
+// keys don't also go to conrod as text.
+
+use crate::input::KeyboardState;
+use crate::raw_input::RawInput;
+use crate::title_bar::{TitleBarItem, TitleBarItems};
+use crate::trace::now_us;
+use conrod_core::{
+    color, widget, widget_ids, Colorable, Positionable, Sizeable, Widget, WidgetCommon,
+};
+use winit::event::VirtualKeyCode;
+
+pub struct GameViewState {
+    /// The position of the square relative to the middle of the viewport.
+    pub pos: [f64; 2],
+    /// When the square was last moved, in microseconds.
+    last_update: Option<f64>,
+}
+
+impl Default for GameViewState {
+    fn default() -> Self {
+        GameViewState::new()
+    }
+}
+
+impl GameViewState {
+    pub fn new() -> Self {
+        GameViewState {
+            pos: [0.0, 0.0],
+            last_update: None,
+        }
+    }
+}
+
+impl TitleBarItems for GameViewState {
+    fn title_bar_items(&self) -> Vec<TitleBarItem> {
+        vec![TitleBarItem::Button("Center".to_owned())]
+    }
+
+    fn title_bar_clicked(&mut self, _index: usize) {
+        self.pos = [0.0, 0.0];
+    }
+}
+
+#[derive(WidgetCommon)]
+pub struct GameViewWidget<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    game: &'a mut GameViewState,
+    raw_input: &'a mut RawInput,
+    keyboard: &'a KeyboardState,
+}
+
+impl<'a> GameViewWidget<'a> {
+    pub fn new(
+        game: &'a mut GameViewState,
+        raw_input: &'a mut RawInput,
+        keyboard: &'a KeyboardState,
+    ) -> Self {
+        GameViewWidget {
+            common: widget::CommonBuilder::default(),
+            game,
+            raw_input,
+            keyboard,
+        }
+    }
+}
+
+widget_ids! {
+    pub struct GameViewIds {
+        help,
+        viewport,
+        player,
+    }
+}
+
+impl<'a> Widget for GameViewWidget<'a> {
+    type State = GameViewIds;
+    type Style = ();
+    /// Whether a movement key is held down, in which case the UI needs to be
+    /// updated on every frame.
+    type Event = bool;
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        GameViewIds::new(id_gen)
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs {
+            id,
+            state,
+            ui,
+            rect,
+            ..
+        } = args;
+        let GameViewWidget {
+            game,
+            raw_input,
+            keyboard,
+            ..
+        } = self;
+
+        const PAD: conrod_core::Scalar = 8.0;
+        const ROW_H: conrod_core::Scalar = 28.0;
+        const SIZE: conrod_core::Scalar = 24.0;
+        // In points per second.
+        const SPEED: conrod_core::Scalar = 200.0;
+
+        let focused = ui.global_input().current.widget_capturing_keyboard == Some(state.viewport);
+        widget::Text::new(if focused {
+            "WASD to move. Click outside to stop."
+        } else {
+            "Click the viewport, then WASD to move."
+        })
+        .font_size(12)
+        .color(color::LIGHT_GREY)
+        .h(ROW_H)
+        .top_left_with_margin_on(id, PAD)
+        .set(state.help, ui);
+
+        let view_w = (rect.w() - PAD * 2.0).max(0.0);
+        let view_h = (rect.h() - ROW_H - PAD * 3.0).max(0.0);
+        widget::Rectangle::fill([view_w, view_h])
+            .color(color::BLACK)
+            .down_from(state.help, PAD)
+            .set(state.viewport, ui);
+        if focused {
+            // The held keys are read from `keyboard`, so the events aren't
+            // needed.
+            raw_input.claim(state.viewport, ui.rect_of(state.viewport).unwrap_or(rect));
+        }
+
+        // Move by the time since the last update, so that the speed doesn't
+        // depend on how often the UI is updated.
+        let now = now_us();
+        let dt = game
+            .last_update
+            .map_or(0.0, |last| (now - last) / 1_000_000.0);
+        let axis = |positive, negative| {
+            let down = |key| focused && keyboard.is_down(key);
+            down(positive) as i32 as f64 - down(negative) as i32 as f64
+        };
+        let dx = axis(VirtualKeyCode::D, VirtualKeyCode::A);
+        let dy = axis(VirtualKeyCode::W, VirtualKeyCode::S);
+        let moving = dx != 0.0 || dy != 0.0;
+        if moving {
+            let max_x = ((view_w - SIZE) / 2.0).max(0.0);
+            let max_y = ((view_h - SIZE) / 2.0).max(0.0);
+            game.pos[0] = (game.pos[0] + dx * SPEED * dt).max(-max_x).min(max_x);
+            game.pos[1] = (game.pos[1] + dy * SPEED * dt).max(-max_y).min(max_y);
+            game.last_update = Some(now);
+        } else {
+            game.last_update = None;
+        }
+
+        widget::Rectangle::fill([SIZE, SIZE])
+            .color(color::LIGHT_GREEN)
+            .x_y_relative_to(state.viewport, game.pos[0], game.pos[1])
+            .graphics_for(state.viewport)
+            .set(state.player, ui);
+
+        moving
+    }
+}
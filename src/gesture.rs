@@ -0,0 +1,77 @@
+// Zoom and rotate gestures.
+//
+// winit 0.28 reports touchpad pinch and rotate gestures on macOS, which
+// `v028_convert_gesture` converts. The winit used by the demo doesn't, so
+// `GestureConverter` also treats Ctrl+wheel as a pinch, which works on every
+// platform.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Gesture {
+    /// Zoom by a relative amount. Positive values zoom in.
+    Magnify(f64),
+    /// Rotate by the given number of degrees, counterclockwise.
+    Rotate(f64),
+}
+
+/// Turns Ctrl+wheel into `Gesture::Magnify`.
+pub struct GestureConverter {
+    ctrl: bool,
+}
+
+impl Default for GestureConverter {
+    fn default() -> Self {
+        GestureConverter::new()
+    }
+}
+
+impl GestureConverter {
+    const MAGNIFY_PER_LINE: f64 = 0.1;
+    const MAGNIFY_PER_PIXEL: f64 = 0.005;
+
+    pub fn new() -> Self {
+        GestureConverter { ctrl: false }
+    }
+
+    /// Returns the gesture if `event` is one, in which case the event should
+    /// not also be passed on to conrod.
+    pub fn convert_window_event(&mut self, event: &winit::event::WindowEvent) -> Option<Gesture> {
+        match event {
+            winit::event::WindowEvent::ModifiersChanged(modifiers) => {
+                self.ctrl = modifiers.ctrl();
+                None
+            }
+            winit::event::WindowEvent::MouseWheel { delta, .. } if self.ctrl => {
+                let amount = match delta {
+                    winit::event::MouseScrollDelta::LineDelta(_, y) => {
+                        *y as f64 * Self::MAGNIFY_PER_LINE
+                    }
+                    winit::event::MouseScrollDelta::PixelDelta(delta) => {
+                        delta.y * Self::MAGNIFY_PER_PIXEL
+                    }
+                };
+                Some(Gesture::Magnify(amount))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Converts the touchpad gestures of winit 0.28 to a `Gesture`.
+///
+/// Expects a `winit::event::WindowEvent` and returns an `Option<Gesture>`.
+///
+/// Requires that the `winit` crate is in the crate root.
+#[macro_export]
+macro_rules! v028_convert_gesture {
+    ($event:expr) => {{
+        match $event {
+            winit::event::WindowEvent::TouchpadMagnify { delta, .. } => {
+                Some($crate::gesture::Gesture::Magnify(*delta))
+            }
+            winit::event::WindowEvent::TouchpadRotate { delta, .. } => {
+                Some($crate::gesture::Gesture::Rotate(*delta as f64))
+            }
+            _ => None,
+        }
+    }};
+}
@@ -0,0 +1,157 @@
+// A grid of characters in cells of equal size, as in a terminal.
+//
+// conrod lays out `Text` by the advance of each glyph and the demo's font
+// isn't monospace, so the grid can't be drawn as lines of `Text`. Instead
+// `GlyphGrid` keeps a copy of its cells in its widget state, which
+// `conrod_glow::Renderer` draws directly: one glyph per cell, placed on whole
+// pixels so that every cell showing the same character reuses the same glyph
+// cache entry.
+//
+// Only the rows marked as dirty are copied into the widget state, and when
+// none are, the grid doesn't cause the UI to be redrawn.
+
+use conrod_core::{color, text, widget, FontSize, Scalar, Ui, Widget, WidgetCommon};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GridCell {
+    pub ch: char,
+    pub color: color::Color,
+}
+
+impl GridCell {
+    pub fn blank(color: color::Color) -> Self {
+        GridCell { ch: ' ', color }
+    }
+}
+
+/// The size of a cell for `font_size` in the theme's font, as the advance of
+/// "M" and the line height.
+pub fn cell_size(ui: &Ui, font_size: FontSize) -> [Scalar; 2] {
+    let font = ui
+        .theme
+        .font_id
+        .or_else(|| ui.fonts.ids().next())
+        .and_then(|id| ui.fonts.get(id));
+    match font {
+        Some(font) => {
+            let scale = text::pt_to_scale(font_size);
+            let advance = font.glyph('M').scaled(scale).h_metrics().advance_width;
+            let v_metrics = font.v_metrics(scale);
+            [
+                advance.ceil() as Scalar,
+                (v_metrics.ascent - v_metrics.descent + v_metrics.line_gap).ceil() as Scalar,
+            ]
+        }
+        None => [font_size as Scalar, font_size as Scalar * 1.5],
+    }
+}
+
+#[derive(WidgetCommon)]
+pub struct GlyphGrid<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    cols: usize,
+    rows: usize,
+    cells: &'a [GridCell],
+    dirty: &'a mut [bool],
+    font_size: FontSize,
+}
+
+impl<'a> GlyphGrid<'a> {
+    /// A grid of `cols` by `rows` cells, given row by row from the top.
+    /// The rows whose flag in `dirty` is set are copied, and their flags
+    /// cleared.
+    pub fn new(cols: usize, rows: usize, cells: &'a [GridCell], dirty: &'a mut [bool]) -> Self {
+        GlyphGrid {
+            common: widget::CommonBuilder::default(),
+            cols,
+            rows,
+            cells,
+            dirty,
+            font_size: 14,
+        }
+    }
+
+    pub fn font_size(mut self, font_size: FontSize) -> Self {
+        self.font_size = font_size;
+        self
+    }
+}
+
+/// The copy of the cells, read by the renderer.
+pub struct GridState {
+    pub cols: usize,
+    pub rows: usize,
+    pub cells: Vec<GridCell>,
+    pub font: Option<(text::font::Id, text::Font)>,
+    pub font_size: FontSize,
+    pub cell_w: Scalar,
+    pub cell_h: Scalar,
+}
+
+impl<'a> Widget for GlyphGrid<'a> {
+    type State = GridState;
+    type Style = ();
+    type Event = ();
+
+    fn init_state(&self, _: widget::id::Generator) -> Self::State {
+        GridState {
+            cols: 0,
+            rows: 0,
+            cells: Vec::new(),
+            font: None,
+            font_size: 0,
+            cell_w: 0.0,
+            cell_h: 0.0,
+        }
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs { state, ui, .. } = args;
+        let GlyphGrid {
+            cols,
+            rows,
+            cells,
+            dirty,
+            font_size,
+            ..
+        } = self;
+        if cells.len() != cols * rows || dirty.len() != rows {
+            return;
+        }
+
+        let font_id = ui.theme.font_id.or_else(|| ui.fonts.ids().next());
+        let font_changed = state.font.as_ref().map(|&(id, _)| id) != font_id;
+        if font_changed || state.font_size != font_size || state.cols != cols || state.rows != rows
+        {
+            let font = font_id.and_then(|id| ui.fonts.get(id).map(|font| (id, font.clone())));
+            let [cell_w, cell_h] = cell_size(ui, font_size);
+            state.update(|state| {
+                *state = GridState {
+                    cols,
+                    rows,
+                    cells: cells.to_vec(),
+                    font,
+                    font_size,
+                    cell_w,
+                    cell_h,
+                };
+            });
+            for flag in dirty.iter_mut() {
+                *flag = false;
+            }
+        } else if dirty.iter().any(|&flag| flag) {
+            state.update(|state| {
+                for (row, flag) in dirty.iter_mut().enumerate() {
+                    if *flag {
+                        let range = row * cols..(row + 1) * cols;
+                        state.cells[range.clone()].copy_from_slice(&cells[range]);
+                        *flag = false;
+                    }
+                }
+            });
+        }
+    }
+}
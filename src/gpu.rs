@@ -0,0 +1,71 @@
+// Which GPU the GL context is created on, for machines with both an
+// integrated and a discrete one.
+//
+// A UI demo doesn't need the discrete GPU, so the integrated one is asked
+// for unless `--gpu=high-performance` is given. None of the platforms let
+// glutin pick an adapter, so this only sets the hints the drivers read when
+// the context is created:
+//
+// - On Linux, Mesa's PRIME offloading picks the GPU by `DRI_PRIME`, which is
+//   left alone if the user has set it.
+// - On Windows, the hybrid graphics drivers read exports of the executable,
+//   which can't change at run time, so the driver profile decides.
+// - On macOS, glutin 0.24 doesn't expose the offline renderer attribute, so
+//   the system decides.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GpuPreference {
+    /// Let the platform decide.
+    Default,
+    /// The integrated GPU.
+    LowPower,
+    /// The discrete GPU.
+    HighPerformance,
+}
+
+impl GpuPreference {
+    /// From `--gpu=default`, `--gpu=low-power` or `--gpu=high-performance`
+    /// on the command line, low power if there is none.
+    pub fn from_args(args: impl Iterator<Item = String>) -> Self {
+        let mut preference = GpuPreference::LowPower;
+        for arg in args {
+            match arg.as_str() {
+                "--gpu=default" => preference = GpuPreference::Default,
+                "--gpu=low-power" => preference = GpuPreference::LowPower,
+                "--gpu=high-performance" => preference = GpuPreference::HighPerformance,
+                _ => {}
+            }
+        }
+        preference
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GpuPreference::Default => "default",
+            GpuPreference::LowPower => "low power",
+            GpuPreference::HighPerformance => "high performance",
+        }
+    }
+
+    /// Set the hints for the drivers. Call before creating the context.
+    /// Returns how the preference was applied, for the diagnostics.
+    pub fn apply(self) -> String {
+        if self == GpuPreference::Default {
+            return "default".to_owned();
+        }
+        if cfg!(target_os = "linux") {
+            if let Ok(value) = std::env::var("DRI_PRIME") {
+                return format!("DRI_PRIME={} from the environment", value);
+            }
+            let value = if self == GpuPreference::HighPerformance {
+                "1"
+            } else {
+                "0"
+            };
+            std::env::set_var("DRI_PRIME", value);
+            format!("{} (DRI_PRIME={})", self.name(), value)
+        } else {
+            format!("{} (not supported on this platform)", self.name())
+        }
+    }
+}
@@ -0,0 +1,95 @@
+// Regions of the UI which change the mouse cursor and do something when
+// clicked, like the links in the Markdown window.
+//
+// Widgets register their hotspots while they are set, and `finish` at the
+// end of `set_widgets` picks the one under the mouse. conrod only has one
+// cursor per update, which the event loop gives to the OS window, so the
+// hotspot drawn last wins instead of every widget setting its own.
+
+use crate::common::UiState;
+use crate::selection::is_within;
+use conrod_core::cursor::MouseCursor;
+use conrod_core::{widget, UiCell};
+
+/// What a hotspot does when it is clicked. It runs after all the widgets
+/// have been set, so it can change anything in the state.
+pub type Action = Box<dyn FnOnce(&mut UiState)>;
+
+struct Hotspot {
+    id: widget::Id,
+    cursor: MouseCursor,
+    on_activate: Option<Action>,
+}
+
+/// The hotspots registered in this update.
+pub struct Hotspots {
+    hotspots: Vec<Hotspot>,
+}
+
+impl Default for Hotspots {
+    fn default() -> Self {
+        Hotspots::new()
+    }
+}
+
+impl Hotspots {
+    pub fn new() -> Self {
+        Hotspots {
+            hotspots: Vec::new(),
+        }
+    }
+
+    /// Show `cursor` while the mouse is over `id` or one of its
+    /// descendants, and call `on_activate` when `id` is clicked.
+    pub fn register(
+        &mut self,
+        id: widget::Id,
+        cursor: MouseCursor,
+        on_activate: impl FnOnce(&mut UiState) + 'static,
+    ) {
+        self.hotspots.push(Hotspot {
+            id,
+            cursor,
+            on_activate: Some(Box::new(on_activate)),
+        });
+    }
+
+    /// Only show `cursor` while the mouse is over `id`.
+    pub fn cursor(&mut self, id: widget::Id, cursor: MouseCursor) {
+        self.hotspots.push(Hotspot {
+            id,
+            cursor,
+            on_activate: None,
+        });
+    }
+
+    /// Set the cursor of the hotspot under the mouse and return the actions
+    /// of those which were clicked, clearing the hotspots for the next
+    /// update.
+    ///
+    /// Nothing changes while another widget has the mouse, such as a window
+    /// being resized, so that its cursor stays.
+    pub fn finish(&mut self, ui: &mut UiCell) -> Vec<Action> {
+        let input = &ui.global_input().current;
+        let captured = input.widget_capturing_mouse;
+        let under_mouse = input.widget_under_mouse;
+        let mut cursor = None;
+        let mut actions = Vec::new();
+        for hotspot in self.hotspots.drain(..) {
+            let within =
+                |id: Option<widget::Id>| id.map_or(false, |id| is_within(ui, id, hotspot.id));
+            if within(under_mouse) && (captured.is_none() || within(captured)) {
+                cursor = Some(hotspot.cursor);
+            }
+            if let Some(on_activate) = hotspot.on_activate {
+                if ui.widget_input(hotspot.id).clicks().left().next().is_some() {
+                    actions.push(on_activate);
+                }
+            }
+        }
+        if let Some(cursor) = cursor {
+            ui.set_mouse_cursor(cursor);
+        }
+        actions
+    }
+}
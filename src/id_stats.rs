@@ -0,0 +1,133 @@
+// Counting conrod's widget ids, to notice widgets which allocate new ids as
+// they go instead of keeping them in their state.
+//
+// conrod never frees an id: each one is a node of the widget graph for as
+// long as the `Ui` lives. So the ids of a window are all those under it in
+// the graph, including those of widgets it no longer sets, and the drawn ones
+// are what it really uses.
+
+use crate::trace::now_us;
+use conrod_core::{widget, Ui};
+
+/// The ids under one window.
+#[derive(Clone, Debug)]
+pub struct WindowIds {
+    pub title: &'static str,
+    pub widgets: usize,
+    pub drawn: usize,
+    /// For how many counts in a row `widgets` has grown.
+    pub growing_for: u32,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct IdStats {
+    /// All the ids, including those never used for a widget.
+    pub allocated: usize,
+    /// The ids of widgets outside of the windows.
+    pub outside: usize,
+    pub windows: Vec<WindowIds>,
+    /// For how many counts in a row `allocated` has grown.
+    pub growing_for: u32,
+    counted_at: f64,
+}
+
+impl IdStats {
+    /// How often the ids are counted while the Diagnostics window is open.
+    const INTERVAL_US: f64 = 1e6;
+    /// Growing for this many counts in a row is reported as a leak. Windows
+    /// allocate for new rows and such when they are used, but not for long.
+    const LEAK_COUNTS: u32 = 5;
+
+    /// Count the ids of `ui` under each of `windows`, unless they were
+    /// counted less than `INTERVAL_US` ago.
+    pub fn count(&mut self, ui: &Ui, windows: &[(&'static str, widget::Id)]) {
+        let now = now_us();
+        if now - self.counted_at < Self::INTERVAL_US && self.allocated > 0 {
+            return;
+        }
+        self.counted_at = now;
+
+        let graph = ui.widget_graph();
+        // The window of a widget, by going up from it.
+        let window_of = |mut id: widget::Id| loop {
+            if let Some(i) = windows.iter().position(|&(_, window)| window == id) {
+                return Some(i);
+            }
+            id = graph.depth_parent(id)?;
+        };
+
+        let allocated = graph.node_count();
+        let mut widgets = vec![0; windows.len()];
+        let mut outside = 0;
+        for index in 0..allocated {
+            let id = widget::Id::new(index);
+            if graph.widget(id).is_none() {
+                continue;
+            }
+            match window_of(id) {
+                Some(i) => widgets[i] += 1,
+                None => outside += 1,
+            }
+        }
+        let mut drawn = vec![0; windows.len()];
+        let mut seen = std::collections::HashSet::new();
+        let mut primitives = ui.draw();
+        while let Some(primitive) = primitives.next() {
+            if seen.insert(primitive.id) {
+                if let Some(i) = window_of(primitive.id) {
+                    drawn[i] += 1;
+                }
+            }
+        }
+
+        self.growing_for = if allocated > self.allocated && self.allocated > 0 {
+            self.growing_for + 1
+        } else {
+            0
+        };
+        self.allocated = allocated;
+        self.outside = outside;
+        self.windows = windows
+            .iter()
+            .enumerate()
+            .map(|(i, &(title, _))| {
+                let growing_for = match self.windows.iter().find(|w| w.title == title) {
+                    Some(old) if widgets[i] > old.widgets => old.growing_for + 1,
+                    _ => 0,
+                };
+                WindowIds {
+                    title,
+                    widgets: widgets[i],
+                    drawn: drawn[i],
+                    growing_for,
+                }
+            })
+            .collect();
+    }
+
+    /// The lines of the Diagnostics window.
+    pub fn lines(&self) -> Vec<String> {
+        if self.allocated == 0 {
+            return vec!["Not counted yet".to_owned()];
+        }
+        let mut lines = vec![format!(
+            "Allocated: {}, outside of the windows: {}",
+            self.allocated, self.outside
+        )];
+        if self.growing_for >= Self::LEAK_COUNTS {
+            lines.push(format!("Still growing after {} counts", self.growing_for));
+        }
+        for window in &self.windows {
+            let leak = if window.growing_for >= Self::LEAK_COUNTS {
+                " (leaking?)"
+            } else {
+                ""
+            };
+            lines.push(format!(
+                "{}: {} ids, {} drawn{}",
+                window.title, window.widgets, window.drawn, leak
+            ));
+        }
+        lines
+    }
+}
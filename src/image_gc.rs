@@ -0,0 +1,81 @@
+// Finding the textures in the `image::Map` which are no longer drawn.
+//
+// Windows which show an image insert its texture into the map, and they are
+// meant to remove it again when they close, but nothing checks that they do.
+// `ImageGc` notes which images each fill of the renderer draws, so that the
+// ones which have not been drawn for a while can be reported, or deleted
+// along with their textures.
+
+use crate::conrod_glow::{Renderer, Texture};
+use conrod_core::image;
+use std::collections::HashMap;
+
+pub struct ImageGc {
+    /// How many fills an image may go without being drawn before it counts
+    /// as unused.
+    max_idle_fills: u64,
+    fills: u64,
+    /// The fill each image in the map was last drawn in, or inserted before.
+    last_drawn: HashMap<image::Id, u64>,
+}
+
+impl ImageGc {
+    /// About ten seconds of continuous animation at 60 frames per second.
+    pub const DEFAULT_IDLE_FILLS: u64 = 600;
+
+    pub fn new(max_idle_fills: u64) -> Self {
+        ImageGc {
+            max_idle_fills,
+            fills: 0,
+            last_drawn: HashMap::new(),
+        }
+    }
+
+    /// Note the images drawn by the last `Renderer::fill`. Call after each
+    /// fill, with the map it was given.
+    pub fn track(&mut self, renderer: &Renderer, image_map: &image::Map<Texture>) {
+        self.fills += 1;
+        let fills = self.fills;
+        // Images inserted since the last fill count from now.
+        self.last_drawn.retain(|id, _| image_map.contains_key(id));
+        for &id in image_map.keys() {
+            self.last_drawn.entry(id).or_insert(fills);
+        }
+        for id in renderer.images() {
+            self.last_drawn.insert(id, fills);
+        }
+    }
+
+    /// The images which have not been drawn in the last `max_idle_fills`
+    /// fills.
+    pub fn unused(&self) -> Vec<image::Id> {
+        self.last_drawn
+            .iter()
+            .filter(|&(_, &last)| self.fills - last > self.max_idle_fills)
+            .map(|(&id, _)| id)
+            .collect()
+    }
+
+    /// Remove the unused images from `image_map` and delete their textures,
+    /// except those for which `keep` is true, such as the images of windows
+    /// which are only hidden. Returns how many were deleted.
+    pub fn collect(
+        &mut self,
+        gl: &glow::Context,
+        image_map: &mut image::Map<Texture>,
+        keep: impl Fn(image::Id) -> bool,
+    ) -> usize {
+        let mut deleted = 0;
+        for id in self.unused() {
+            if keep(id) {
+                continue;
+            }
+            self.last_drawn.remove(&id);
+            if let Some(texture) = image_map.remove(id) {
+                texture.delete(gl);
+                deleted += 1;
+            }
+        }
+        deleted
+    }
+}
@@ -0,0 +1,162 @@
+// A window displaying an image opened from disk.
+
+use crate::bridge::Bridge;
+use crate::file_dialog::{self, FilePurpose};
+use conrod_core::{
+    color, image, widget, widget_ids, Colorable, Labelable, Positionable, Sizeable, Widget,
+    WidgetCommon,
+};
+
+/// An image which has been uploaded to the GPU and inserted into the
+/// `image::Map`.
+#[derive(Clone, Debug)]
+pub struct LoadedImage {
+    pub name: String,
+    pub id: image::Id,
+    pub width: u32,
+    pub height: u32,
+}
+
+pub struct ImageViewerState {
+    pub image: Option<LoadedImage>,
+    /// The zoom relative to fitting the image into the window.
+    pub zoom: f64,
+    /// How far the image is scrolled from the middle of the window, in
+    /// points, when it is zoomed in larger than the window.
+    pan: [f64; 2],
+    /// Whether the mouse was over the viewer in the last update, which is
+    /// when zoom gestures apply to it.
+    pub hovered: bool,
+}
+
+impl Default for ImageViewerState {
+    fn default() -> Self {
+        ImageViewerState::new()
+    }
+}
+
+impl ImageViewerState {
+    const MIN_ZOOM: f64 = 0.1;
+    const MAX_ZOOM: f64 = 10.0;
+
+    pub fn new() -> Self {
+        ImageViewerState {
+            image: None,
+            zoom: 1.0,
+            pan: [0.0, 0.0],
+            hovered: false,
+        }
+    }
+
+    /// Zoom by a relative amount, as given by `Gesture::Magnify`.
+    pub fn zoom_by(&mut self, amount: f64) {
+        self.zoom = (self.zoom * (1.0 + amount))
+            .max(Self::MIN_ZOOM)
+            .min(Self::MAX_ZOOM);
+    }
+}
+
+#[derive(WidgetCommon)]
+pub struct ImageViewerWidget<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    viewer: &'a mut ImageViewerState,
+    bridge: &'a Bridge,
+}
+
+impl<'a> ImageViewerWidget<'a> {
+    pub fn new(viewer: &'a mut ImageViewerState, bridge: &'a Bridge) -> Self {
+        ImageViewerWidget {
+            common: widget::CommonBuilder::default(),
+            viewer,
+            bridge,
+        }
+    }
+}
+
+widget_ids! {
+    pub struct ImageViewerIds {
+        open,
+        name,
+        image,
+    }
+}
+
+impl<'a> Widget for ImageViewerWidget<'a> {
+    type State = ImageViewerIds;
+    type Style = ();
+    type Event = ();
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        ImageViewerIds::new(id_gen)
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs {
+            id,
+            state,
+            ui,
+            rect,
+            ..
+        } = args;
+        let ImageViewerWidget { viewer, bridge, .. } = self;
+
+        const PAD: conrod_core::Scalar = 8.0;
+        const ROW_H: conrod_core::Scalar = 28.0;
+
+        // Other floating windows on top of the viewer are not taken into
+        // account, but this is good enough to pick where gestures go.
+        viewer.hovered = rect.is_over(ui.global_input().current.mouse.xy);
+
+        if widget::Button::new()
+            .label("Open image...")
+            .label_font_size(14)
+            .w_h(120.0, ROW_H)
+            .top_left_with_margin_on(id, PAD)
+            .set(state.open, ui)
+            .was_clicked()
+        {
+            file_dialog::open_file(bridge, FilePurpose::Image);
+        }
+
+        let image = match &viewer.image {
+            Some(image) => image,
+            None => return,
+        };
+        widget::Text::new(&image.name)
+            .font_size(12)
+            .color(color::LIGHT_GREY)
+            .right_from(state.open, PAD)
+            .set(state.name, ui);
+
+        // Scale the image down to fit the window, keeping its aspect ratio,
+        // then apply the zoom.
+        let avail_w = (rect.w() - PAD * 2.0).max(0.0);
+        let avail_h = (rect.h() - ROW_H - PAD * 3.0).max(0.0);
+        let (img_w, img_h) = (image.width as f64, image.height as f64);
+        let scale = (avail_w / img_w).min(avail_h / img_h).min(1.0) * viewer.zoom;
+        let (w, h) = (img_w * scale, img_h * scale);
+
+        // Scroll around the image with the wheel, in both directions.
+        for scroll in ui
+            .widget_input(id)
+            .scrolls()
+            .chain(ui.widget_input(state.image).scrolls())
+        {
+            viewer.pan[0] -= scroll.x;
+            viewer.pan[1] += scroll.y;
+        }
+        let max_x = ((w - avail_w) / 2.0).max(0.0);
+        let max_y = ((h - avail_h) / 2.0).max(0.0);
+        viewer.pan[0] = viewer.pan[0].max(-max_x).min(max_x);
+        viewer.pan[1] = viewer.pan[1].max(-max_y).min(max_y);
+
+        widget::Image::new(image.id)
+            .w_h(w, h)
+            .mid_bottom_with_margin_on(id, PAD + (avail_h - h) / 2.0 + viewer.pan[1])
+            .x_relative_to(id, viewer.pan[0])
+            .set(state.image, ui);
+    }
+}
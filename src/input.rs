@@ -0,0 +1,401 @@
+// Input state tracked on top of the stateless conversion macros.
+
+use conrod_core::event::{Input, Motion};
+use conrod_core::input::{Button, MouseButton};
+use conrod_core::{Point, Rect, Scalar, Theme};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::Duration;
+use winit::event::{ElementState, ScanCode, VirtualKeyCode, WindowEvent};
+
+/// Inputs to give conrod when the OS window loses focus.
+///
+/// The mouse buttons may be released while the cursor is over another
+/// window, in which case the release never arrives, so release them now to
+/// avoid leaving a drag stuck.
+pub fn focus_lost_inputs() -> impl Iterator<Item = Input> {
+    [MouseButton::Left, MouseButton::Right, MouseButton::Middle]
+        .iter()
+        .map(|&button| Input::Release(Button::Mouse(button)))
+}
+
+/// How the mouse is interpreted, for every widget alike.
+///
+/// conrod's double clicks and drags use the thresholds of its theme, which
+/// `apply` sets, and widgets which count clicks themselves read them back
+/// from there.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputSettings {
+    /// The longest time between two clicks of a double click.
+    pub double_click_ms: u32,
+    /// How far the mouse moves with a button down before it starts a drag.
+    pub drag_distance: Scalar,
+    /// How much the wheel scrolls, relative to what the OS reports.
+    pub scroll_speed: Scalar,
+    /// Move the content with the wheel rather than the view, as on touch
+    /// screens.
+    pub natural_scroll: bool,
+    /// Move the cursor ahead of where it is while dragging, so that dragged
+    /// windows lag less behind it at low frame rates.
+    pub predict_drags: bool,
+}
+
+impl Default for InputSettings {
+    fn default() -> Self {
+        InputSettings {
+            double_click_ms: 500,
+            drag_distance: 4.0,
+            scroll_speed: 1.0,
+            natural_scroll: false,
+            predict_drags: false,
+        }
+    }
+}
+
+impl InputSettings {
+    /// Set the thresholds of conrod's double clicks and drags.
+    pub fn apply(&self, theme: &mut Theme) {
+        theme.double_click_threshold = Duration::from_millis(self.double_click_ms.into());
+        theme.mouse_drag_threshold = self.drag_distance;
+    }
+
+    /// Scale the scrolling in `input`, reversing it for natural scrolling.
+    ///
+    /// A vertical wheel scrolls sideways while Shift is held, as in most
+    /// apps, for mice without a tilt wheel. Trackpads already scroll in both
+    /// directions, so their horizontal scrolling is kept as it is.
+    pub fn adjust(&self, input: Input, keyboard: &KeyboardState) -> Input {
+        match input {
+            Input::Motion(Motion::Scroll { x, y }) => {
+                let shift = keyboard.is_down(VirtualKeyCode::LShift)
+                    || keyboard.is_down(VirtualKeyCode::RShift);
+                let (x, y) = if shift && x == 0.0 { (y, 0.0) } else { (x, y) };
+                let speed = if self.natural_scroll {
+                    -self.scroll_speed
+                } else {
+                    self.scroll_speed
+                };
+                Input::Motion(Motion::Scroll {
+                    x: x * speed,
+                    y: y * speed,
+                })
+            }
+            input => input,
+        }
+    }
+}
+
+/// Detects auto-repeated key presses.
+///
+/// winit keeps sending `KeyboardInput` presses while a key is held down
+/// without saying which ones are repeats, so this keeps track of the keys
+/// which are down.
+pub struct KeyRepeat {
+    pressed: HashSet<ScanCode>,
+}
+
+impl Default for KeyRepeat {
+    fn default() -> Self {
+        KeyRepeat::new()
+    }
+}
+
+impl KeyRepeat {
+    pub fn new() -> Self {
+        KeyRepeat {
+            pressed: HashSet::new(),
+        }
+    }
+
+    /// Track `event` and return whether it is an auto-repeated key press.
+    pub fn is_repeat(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::KeyboardInput { input, .. } => match input.state {
+                ElementState::Pressed => !self.pressed.insert(input.scancode),
+                ElementState::Released => {
+                    self.pressed.remove(&input.scancode);
+                    false
+                }
+            },
+            // The key releases go elsewhere while the window is not focused.
+            WindowEvent::Focused(false) => {
+                self.pressed.clear();
+                false
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Decides when to grab the pointer, so that a drag keeps getting events even
+/// if the cursor goes outside of the OS window.
+///
+/// The pointer is grabbed for as long as a widget captures the mouse, which
+/// includes dragging and resizing the floating windows.
+pub struct PointerCapture {
+    grabbed: bool,
+}
+
+impl Default for PointerCapture {
+    fn default() -> Self {
+        PointerCapture::new()
+    }
+}
+
+impl PointerCapture {
+    pub fn new() -> Self {
+        PointerCapture { grabbed: false }
+    }
+
+    /// Call after `set_widgets` with whether a widget is capturing the mouse.
+    ///
+    /// Returns whether the pointer should now be grabbed, if that changed.
+    pub fn update(&mut self, widget_capturing_mouse: bool) -> Option<bool> {
+        if widget_capturing_mouse == self.grabbed {
+            return None;
+        }
+        self.grabbed = widget_capturing_mouse;
+        Some(self.grabbed)
+    }
+}
+
+/// Decides when to move the input method, which follows the widget with the
+/// keyboard, such as a text box.
+///
+/// winit has the input method on whenever the OS window has the focus, so
+/// the only thing to do is to tell it where to show up.
+pub struct ImeSpot {
+    rect: Option<Rect>,
+}
+
+impl Default for ImeSpot {
+    fn default() -> Self {
+        ImeSpot::new()
+    }
+}
+
+impl ImeSpot {
+    pub fn new() -> Self {
+        ImeSpot { rect: None }
+    }
+
+    /// Call after `set_widgets` with the rect of the widget capturing the
+    /// keyboard, if any.
+    ///
+    /// Returns the rect to move the input method to, if it changed.
+    pub fn update(&mut self, keyboard_rect: Option<Rect>) -> Option<Rect> {
+        if keyboard_rect == self.rect {
+            return None;
+        }
+        self.rect = keyboard_rect;
+        keyboard_rect
+    }
+}
+
+/// Merges the mouse motion which arrives between two UI updates, as a fast
+/// mouse sends several `CursorMoved` per frame and conrod would handle each.
+///
+/// Consecutive cursor moves are merged into the latest position, and
+/// consecutive scrolls into their sum. Any other input lets the held motion
+/// out first, so that presses still happen where the cursor was.
+pub struct MotionCoalescer {
+    held: Option<Input>,
+    /// How many inputs have been merged into others, in total.
+    pub coalesced: u64,
+}
+
+impl Default for MotionCoalescer {
+    fn default() -> Self {
+        MotionCoalescer::new()
+    }
+}
+
+impl MotionCoalescer {
+    pub fn new() -> Self {
+        MotionCoalescer {
+            held: None,
+            coalesced: 0,
+        }
+    }
+
+    /// Take `input` and return the inputs to handle now, in order.
+    pub fn push(&mut self, input: Input) -> Vec<Input> {
+        let merged = match (&mut self.held, &input) {
+            (
+                Some(Input::Motion(Motion::MouseCursor { x, y })),
+                Input::Motion(Motion::MouseCursor { x: new_x, y: new_y }),
+            ) => {
+                *x = *new_x;
+                *y = *new_y;
+                true
+            }
+            (
+                Some(Input::Motion(Motion::Scroll { x, y })),
+                Input::Motion(Motion::Scroll { x: dx, y: dy }),
+            ) => {
+                *x += dx;
+                *y += dy;
+                true
+            }
+            _ => false,
+        };
+        if merged {
+            self.coalesced += 1;
+            return Vec::new();
+        }
+        let mut ready: Vec<Input> = self.held.take().into_iter().collect();
+        match input {
+            Input::Motion(Motion::MouseCursor { .. }) | Input::Motion(Motion::Scroll { .. }) => {
+                self.held = Some(input)
+            }
+            input => ready.push(input),
+        }
+        ready
+    }
+
+    /// The held motion, to handle before the UI is updated.
+    pub fn flush(&mut self) -> Option<Input> {
+        self.held.take()
+    }
+}
+
+/// Extrapolates the cursor during drags.
+///
+/// When the UI updates less often than the display refreshes, whatever is
+/// dragged is drawn where the cursor was a frame ago. While a widget
+/// captures the mouse, conrod is given the cursor moved ahead by its velocity
+/// over the last frame, by at most `MAX_LEAD`. The actual position is given
+/// again once the cursor stops, the drag ends or a button changes, so that
+/// drops happen where the cursor really is.
+pub struct DragPredictor {
+    /// The last actual cursor position given to `predict`, and when.
+    last: Option<(Point, f64)>,
+    /// The actual cursor position, while conrod has a predicted one.
+    actual: Option<Point>,
+}
+
+impl Default for DragPredictor {
+    fn default() -> Self {
+        DragPredictor::new()
+    }
+}
+
+impl DragPredictor {
+    /// The furthest the cursor is moved ahead, in points.
+    pub const MAX_LEAD: Scalar = 32.0;
+    /// Longer frames than this don't predict, as the cursor has likely
+    /// stopped in between.
+    const MAX_FRAME_US: f64 = 100_000.0;
+
+    pub fn new() -> Self {
+        DragPredictor {
+            last: None,
+            actual: None,
+        }
+    }
+
+    /// Take an input which is handled right away and return the inputs to
+    /// handle instead, in order. A mouse button goes after the actual cursor
+    /// position if conrod has a predicted one.
+    pub fn settle(&mut self, input: Input) -> Vec<Input> {
+        match input {
+            Input::Motion(Motion::MouseCursor { .. }) => {
+                self.actual = None;
+                vec![input]
+            }
+            Input::Press(Button::Mouse(_)) | Input::Release(Button::Mouse(_)) => {
+                match self.actual.take() {
+                    Some([x, y]) => vec![Input::Motion(Motion::MouseCursor { x, y }), input],
+                    None => vec![input],
+                }
+            }
+            input => vec![input],
+        }
+    }
+
+    /// Take the motion held until the UI update, if any, and return what to
+    /// handle instead. `dragging` is whether a widget captures the mouse.
+    pub fn predict(&mut self, input: Option<Input>, dragging: bool) -> Option<Input> {
+        let now = crate::trace::now_us();
+        let [x, y] = match input {
+            Some(Input::Motion(Motion::MouseCursor { x, y })) => [x, y],
+            // The cursor has stopped, so its position is no longer a guess.
+            None => {
+                self.last = self.last.map(|(xy, _)| (xy, now));
+                return self
+                    .actual
+                    .take()
+                    .map(|[x, y]| Input::Motion(Motion::MouseCursor { x, y }));
+            }
+            input => return input,
+        };
+        let last = self.last.replace(([x, y], now));
+        self.actual = None;
+        let (last_xy, last_us) = match last {
+            Some(last) if dragging => last,
+            _ => return input,
+        };
+        let frame_us = now - last_us;
+        if frame_us <= 0.0 || frame_us > Self::MAX_FRAME_US {
+            return input;
+        }
+        // Moving at the same speed, the cursor will have gone as far again by
+        // the time the next frame is shown.
+        let (mut dx, mut dy) = (x - last_xy[0], y - last_xy[1]);
+        let lead = (dx * dx + dy * dy).sqrt();
+        if lead > Self::MAX_LEAD {
+            dx *= Self::MAX_LEAD / lead;
+            dy *= Self::MAX_LEAD / lead;
+        }
+        if dx == 0.0 && dy == 0.0 {
+            return input;
+        }
+        self.actual = Some([x, y]);
+        Some(Input::Motion(Motion::MouseCursor {
+            x: x + dx,
+            y: y + dy,
+        }))
+    }
+}
+
+/// Which keys are currently held down, by `VirtualKeyCode`.
+///
+/// Used by windows which need the raw key state, like a game viewport,
+/// rather than conrod's key press events.
+#[derive(Clone, Default)]
+pub struct KeyboardState {
+    bits: [u64; 4],
+}
+
+impl KeyboardState {
+    pub fn new() -> Self {
+        KeyboardState::default()
+    }
+
+    pub fn is_down(&self, key: VirtualKeyCode) -> bool {
+        let (word, bit) = Self::position(key);
+        self.bits[word] & bit != 0
+    }
+
+    pub fn handle_window_event(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::KeyboardInput { input, .. } => {
+                if let Some(key) = input.virtual_keycode {
+                    let (word, bit) = Self::position(key);
+                    match input.state {
+                        ElementState::Pressed => self.bits[word] |= bit,
+                        ElementState::Released => self.bits[word] &= !bit,
+                    }
+                }
+            }
+            WindowEvent::Focused(false) => self.bits = [0; 4],
+            _ => {}
+        }
+    }
+
+    fn position(key: VirtualKeyCode) -> (usize, u64) {
+        let index = key as usize;
+        (index / 64, 1 << (index % 64))
+    }
+}
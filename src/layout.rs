@@ -0,0 +1,99 @@
+// Where the floating windows were, saved when the demo exits and restored at
+// startup.
+//
+// conrod_floatwin keeps the positions, the sizes and the z-order in its
+// `WindowingState` without letting anything read or set them, so the layout
+// is read back from the rects of the window widgets after each update
+// instead, and restored through the initial position and size of fresh
+// `WinId`s. The z-order can't be restored that way, nor can floatwin's own
+// collapsing of windows, so the minimized and maximized windows of
+// `WindowModes` stand for the collapsed state.
+//
+// It is stored as JSON next to the configuration: on native exit, and
+// whenever a window has been moved on wasm, where a page has no exit to save
+// it at.
+
+use crate::config::storage;
+use crate::window_modes::WindowModes;
+use crate::workspaces::{WindowTracker, Workspaces};
+use conrod_core::{Dimensions, Point, Rect, Scalar, UiCell};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+const TITLE_BAR_H: Scalar = 24.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WindowPlacement {
+    /// From the top left of the windowing area to the top left of the
+    /// window, with y going down.
+    pub position: Point,
+    /// The size of the content, without the title bar.
+    pub size: Dimensions,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowLayout {
+    /// By window title.
+    pub windows: BTreeMap<String, WindowPlacement>,
+    pub minimized: BTreeSet<String>,
+    pub maximized: BTreeSet<String>,
+}
+
+impl WindowLayout {
+    const STORAGE_NAME: &'static str = "layout";
+
+    /// The stored layout, `None` if there is none.
+    pub fn load() -> Result<Option<Self>, String> {
+        match storage::read(Self::STORAGE_NAME) {
+            Some(json) => serde_json::from_str(&json).map_err(|e| e.to_string()),
+            None => Ok(None),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        storage::write(Self::STORAGE_NAME, &json)
+    }
+
+    /// Read back the placements of the windows shown in the windowing area
+    /// `area`, after the windows have been set. Maximized windows keep the
+    /// placement they are restored to, and windows which aren't shown keep
+    /// their last one. Returns whether anything changed.
+    pub fn capture(
+        &mut self,
+        tracker: &WindowTracker,
+        workspaces: &Workspaces,
+        modes: &WindowModes,
+        area: Rect,
+        ui: &UiCell,
+    ) -> bool {
+        let mut changed = false;
+        let shown = tracker.windows().filter(|&(title, _)| {
+            workspaces.shows(title) && modes.shows(title) && !modes.is_maximized(title)
+        });
+        for (title, window) in shown {
+            let rect = match ui.rect_of(window) {
+                Some(rect) => rect,
+                None => continue,
+            };
+            let placement = WindowPlacement {
+                position: [rect.left() - area.left(), area.top() - rect.top()],
+                size: [rect.w(), (rect.h() - TITLE_BAR_H).max(0.0)],
+            };
+            if self.windows.get(title) != Some(&placement) {
+                self.windows.insert(title.to_owned(), placement);
+                changed = true;
+            }
+        }
+
+        let minimized: BTreeSet<String> = modes.minimized().map(String::from).collect();
+        let maximized: BTreeSet<String> = modes.maximized().map(String::from).collect();
+        if minimized != self.minimized || maximized != self.maximized {
+            self.minimized = minimized;
+            self.maximized = maximized;
+            changed = true;
+        }
+        changed
+    }
+}
@@ -1,5 +1,89 @@
+pub mod attention;
+pub mod audit;
+pub mod bridge;
+pub mod capture;
+#[cfg(feature = "chat")]
+pub mod chat;
+pub mod clipboard;
+pub mod clock;
+pub mod color_filter;
+pub mod compact;
+pub mod config;
 pub mod conrod_glow;
+pub mod conrod_winit_compat;
 pub mod conrod_winit_v023;
+pub mod conrod_winit_v027;
+pub mod data_table;
+pub mod debug_draw;
+pub mod designer;
+pub mod desktop_widgets;
+#[cfg(all(feature = "devserver", not(target_arch = "wasm32")))]
+pub mod devserver;
+pub mod diagnostics;
+pub mod error;
+pub mod export;
+pub mod extra_windows;
+pub mod file_browser;
+pub mod file_dialog;
+pub mod file_menu;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod fill_thread;
+pub mod focus;
+pub mod focus_ring;
+pub mod game_view;
+pub mod gesture;
+pub mod glyph_grid;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod gpu;
+pub mod hotspots;
+pub mod id_stats;
+pub mod image_gc;
+pub mod image_viewer;
+pub mod input;
+pub mod layout;
+#[cfg(feature = "markdown")]
+pub mod markdown;
+pub mod metrics;
+pub mod minimap;
+#[cfg(all(feature = "mirror", not(target_arch = "wasm32")))]
+pub mod mirror;
+pub mod mouse_bindings;
+pub mod node_graph;
+pub mod notes;
+pub mod notifications;
+pub mod palette;
+pub mod pen;
+pub mod profiler;
+pub mod raw_input;
+#[cfg(feature = "markdown")]
+pub mod richtext;
+pub mod search_bar;
+pub mod selection;
+pub mod session;
+pub mod settings;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod shader_cache;
+pub mod shaped_window;
+pub mod shortcuts;
+pub mod simulation;
+pub mod sketchpad;
+pub mod soft_raster;
+#[cfg(all(feature = "soft", not(target_arch = "wasm32")))]
+pub mod soft_window;
+pub mod splash;
+pub mod table;
+pub mod tasks;
+#[cfg(all(feature = "terminal", not(target_arch = "wasm32")))]
+pub mod terminal;
+pub mod timers;
+pub mod title_bar;
+pub mod tool_palette;
+pub mod trace;
+pub mod tree_view;
+pub mod whiteboard;
+pub mod window_modes;
+pub mod window_switcher;
+pub mod workspaces;
 
 #[cfg(target_arch = "wasm32")]
 mod wasm;
@@ -0,0 +1,1054 @@
+// The desktop entry point of the demo.
+//
+// It opens the window and its OpenGL context with glutin, builds the
+// `Renderer`, and runs the winit event loop around the `UiState` of the
+// library, which the web build in `wasm.rs` shares. Errors while starting,
+// such as a context which can't be made, are shown in a message box rather
+// than panicking, as the demo is often started without a terminal. On wasm
+// the library starts itself through `#[wasm_bindgen(start)]`, so `main`
+// does nothing there.
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use conrod_floatwin_demo_glow::conrod_winit_compat::{EventConverter, WindowInfo};
+    use conrod_floatwin_demo_glow::{
+        bridge::AppEvent, bridge::Bridge, capture, color_filter::ColorFilter, config::Config,
+        conrod_glow, conrod_glow::GlyphCache, conrod_glow::Renderer, debug_draw,
+        diagnostics::Diagnostics, error, error::AppError, export, file_browser, file_dialog,
+        fill_thread::FillThread, focus_ring::FocusRing, gesture::GestureConverter,
+        gpu::GpuPreference, image_gc::ImageGc, image_viewer::LoadedImage, input, set_widgets,
+        shader_cache::ProgramBinaryCache, splash, timers::TimerMsg, trace::Tracer, window_switcher,
+        Ids, UiState,
+    };
+    #[cfg(feature = "soft")]
+    use conrod_floatwin_demo_glow::{
+        conrod_glow::Tessellator,
+        soft_raster::{SoftImage, SoftRasterizer},
+        soft_window::SoftWindow,
+    };
+    use glow::HasContext;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    #[allow(dead_code)]
+    mod conversion_fns {
+        // Conversion functions for converting between types from `winit` and `conrod_core`.
+        conrod_floatwin_demo_glow::v023_conversion_fns!();
+    }
+    use conversion_fns::*;
+
+    const WIN_W: u32 = 800;
+    const WIN_H: u32 = 600;
+
+    pub fn main() {
+        if let Err(err) = run() {
+            show_error_dialog(&err);
+            std::process::exit(1);
+        }
+    }
+
+    /// For errors while starting or after the context is lost, when there is
+    /// no UI to show the error in.
+    fn show_error_dialog(err: &AppError) {
+        error::log(err);
+        rfd::MessageDialog::new()
+            .set_level(rfd::MessageLevel::Error)
+            .set_title(err.title())
+            .set_description(&err.describe())
+            .set_buttons(rfd::MessageButtons::Ok)
+            .show();
+    }
+
+    /// Find the assets folder, and add its `Font` to the `Ui`'s `font::Map`.
+    fn load_font(ui: &mut conrod_core::Ui) -> Result<std::path::PathBuf, AppError> {
+        let assets = find_folder::Search::KidsThenParents(3, 5)
+            .for_folder("assets")
+            .map_err(|_| AppError::AssetsNotFound)?;
+        let font_path = assets.join("fonts/NotoSans/NotoSans-Regular.ttf");
+        ui.fonts
+            .insert_from_file(font_path)
+            .map_err(|err| AppError::Font(err.to_string()))?;
+        Ok(assets)
+    }
+
+    type WindowedContext = glutin::WindowedContext<glutin::PossiblyCurrent>;
+
+    /// Open a window with an OpenGL context, made current, and load the GL
+    /// from it.
+    fn create_context(
+        window: glutin::window::WindowBuilder,
+        target: &glutin::event_loop::EventLoopWindowTarget<AppEvent>,
+        is_srgb: bool,
+    ) -> Result<(WindowedContext, glow::Context), String> {
+        let windowed_context = glutin::ContextBuilder::new()
+            .with_vsync(true)
+            .with_srgb(is_srgb)
+            .build_windowed(window, target)
+            .map_err(|err| err.to_string())?;
+        let windowed_context =
+            unsafe { windowed_context.make_current() }.map_err(|(_, err)| err.to_string())?;
+        let gl = glow::Context::from_loader_function(|s| {
+            windowed_context.get_proc_address(s) as *const _
+        });
+        Ok((windowed_context, gl))
+    }
+
+    /// The renderer for the context of `windowed_context`, with the linked
+    /// shader programs cached on disk.
+    fn build_renderer(
+        gl: &glow::Context,
+        windowed_context: &WindowedContext,
+        is_srgb: bool,
+        safe_mode: bool,
+    ) -> Result<Renderer, AppError> {
+        let framebuffer = windowed_context.window().inner_size().into();
+        let mut builder = Renderer::builder()
+            .framebuffer_srgb(is_srgb)
+            .safe_mode(safe_mode)
+            .glyph_cache_for_framebuffer(framebuffer, GlyphCache::DEFAULT_SCALE);
+        // The shaders are compiled on the first run only, where the driver
+        // can hand back the linked programs.
+        let program_cache =
+            ProgramBinaryCache::new(gl, |s| windowed_context.get_proc_address(s) as *const _);
+        if let Some(program_cache) = program_cache {
+            builder = builder.program_cache(Box::new(program_cache));
+        }
+        builder
+            .build(gl)
+            .map_err(|err| AppError::Renderer(err.to_string()))
+    }
+
+    fn run() -> Result<(), AppError> {
+        // Safe mode turns off multisampling and the sRGB framebuffer, and
+        // uses the simplest shaders, for drivers which fail otherwise. The
+        // window has no multisampling of its own, as the UI is drawn
+        // multisampled offscreen with the samples of the MSAA setting.
+        let safe_mode = std::env::args().any(|arg| arg == "--safe-mode");
+        let is_srgb = !safe_mode;
+        let gpu_preference = GpuPreference::from_args(std::env::args()).apply();
+
+        let event_loop = glutin::event_loop::EventLoop::with_user_event();
+        let bridge = Bridge::new(&event_loop);
+        let window = glutin::window::WindowBuilder::new()
+            .with_title("Conrod with glow!")
+            .with_inner_size(glutin::dpi::LogicalSize::new(WIN_W, WIN_H));
+        let (mut windowed_context, mut gl) =
+            match create_context(window.clone(), &event_loop, is_srgb) {
+                Ok(context) => context,
+                #[cfg(feature = "soft")]
+                Err(err) => return run_soft(event_loop, bridge, window, err),
+                #[cfg(not(feature = "soft"))]
+                Err(err) => return Err(AppError::Context(err)),
+            };
+
+        // The loading screen shows until the first frame of the UI, wherever
+        // the window system shows the window before the event loop runs.
+        let show_progress = |progress| {
+            let size = windowed_context.window().inner_size().into();
+            splash::draw(&gl, size, progress);
+            let _ = windowed_context.swap_buffers();
+        };
+        show_progress(0.0);
+
+        let mut current_hidpi_factor = windowed_context.window().scale_factor();
+
+        // Construct our `Ui`.
+        let mut ui = conrod_core::UiBuilder::new([WIN_W as f64, WIN_H as f64])
+            .theme(conrod_example_shared::theme())
+            .build();
+
+        let assets = load_font(&mut ui)?;
+
+        // Load the Rust logo from our assets folder to use as an example image.
+        let rust_logo = image::open(assets.join("images/rust.png"))
+            .map_err(|err| AppError::Image(err.to_string()))?;
+        let options = conrod_glow::TextureOptions {
+            is_srgb: true,
+            ..Default::default()
+        };
+        let logo_pixels = rust_logo.to_rgba();
+        let (width, height) = logo_pixels.dimensions();
+        let texture = conrod_glow::Texture::from_rgba8(&gl, width, height, &logo_pixels, options)
+            .map_err(AppError::Renderer)?;
+        let mut image_map = conrod_core::image::Map::new();
+        let rust_logo = image_map.insert(texture);
+        // The pixels of the images uploaded from files, to upload them again
+        // if the context is lost.
+        let mut image_sources = HashMap::new();
+        image_sources.insert(rust_logo, (logo_pixels, options));
+        show_progress(0.5);
+
+        let mut renderer = build_renderer(&gl, &windowed_context, is_srgb, safe_mode)?;
+        // Filling on a worker thread, while the previous frame is drawn.
+        let mut fill_thread = if std::env::args().any(|arg| arg == "--fill-thread") {
+            Some(FillThread::start(renderer.tessellator(&gl), bridge.clone()))
+        } else {
+            None
+        };
+        let mut image_gc = ImageGc::new(ImageGc::DEFAULT_IDLE_FILLS);
+        // The switcher snapshot and the exports, one of each at a time.
+        let mut target_pool = conrod_glow::RenderTargetPool::new(2);
+        let mut color_filter = ColorFilter::new();
+
+        let mut ids = Ids::new(ui.widget_id_generator());
+
+        let mut ui_state = UiState::new(
+            conrod_example_shared::DemoApp::new(rust_logo),
+            bridge,
+            Config::load(),
+        );
+        if let Err(err) = ui_state.load_layout() {
+            ui_state
+                .notifications
+                .error(format!("Cannot load the window layout: {}", err));
+        }
+        let pixel_format = windowed_context.get_pixel_format();
+        let surface = format!(
+            "{}-bit color, {}-bit alpha, {}x MSAA, sRGB: {}, hardware accelerated: {}",
+            pixel_format.color_bits,
+            pixel_format.alpha_bits,
+            pixel_format.multisampling.unwrap_or(0),
+            pixel_format.srgb,
+            pixel_format.hardware_accelerated,
+        );
+        ui_state.diagnostics = Diagnostics::query(&gl, renderer.info().clone(), surface);
+        ui_state.diagnostics.gpu_preference = gpu_preference;
+        ui_state
+            .file_browser
+            .set_source(Box::new(file_browser::DirSource::new(assets.clone())));
+        ui_state.designer.watch(assets.join("ui/designer.json"));
+        #[cfg(feature = "devserver")]
+        {
+            use conrod_floatwin_demo_glow::devserver::DevServer;
+            if let Some(port) = DevServer::port_from_args(std::env::args()) {
+                match DevServer::start(port, ui_state.bridge.clone()) {
+                    Ok(server) => {
+                        error::warn(&format!("Dev server on http://127.0.0.1:{}", server.port));
+                        ui_state.devserver = Some(server);
+                    }
+                    Err(err) => ui_state
+                        .notifications
+                        .error(format!("Cannot start the dev server: {}", err)),
+                }
+            }
+        }
+        ui_state
+            .timers
+            .every(Duration::from_secs(1), TimerMsg::ReloadDesigner);
+        #[cfg(feature = "mirror")]
+        let mirror_server = {
+            use conrod_floatwin_demo_glow::mirror::MirrorServer;
+            match MirrorServer::address_from_args(std::env::args()) {
+                Some(address) => match MirrorServer::start(&address, &renderer) {
+                    Ok(server) => {
+                        error::warn(&format!("Mirroring on {}", server.address));
+                        Some(server)
+                    }
+                    Err(err) => {
+                        ui_state
+                            .notifications
+                            .error(format!("Cannot start mirroring: {}", err));
+                        None
+                    }
+                },
+                None => None,
+            }
+        };
+        // A viewer draws the frames of another instance instead of its UI.
+        #[cfg(feature = "mirror")]
+        let mut mirror_viewer = {
+            use conrod_floatwin_demo_glow::mirror::MirrorViewer;
+            match MirrorViewer::address_from_args(std::env::args()) {
+                Some(address) => Some(
+                    MirrorViewer::connect(&address, ui_state.bridge.clone())
+                        .map_err(|err| AppError::Mirror(format!("{}: {}", address, err)))?,
+                ),
+                None => None,
+            }
+        };
+
+        let mut should_update_ui = true;
+        let mut needs_next_update = true;
+        let mut recorder: Option<capture::Recorder> = None;
+        let mut tracer = Tracer::new();
+        let mut gestures = GestureConverter::new();
+        let mut key_repeat = input::KeyRepeat::new();
+        let mut coalescer = input::MotionCoalescer::new();
+        let mut event_converter = EventConverter::new();
+        let mut drag_predictor = input::DragPredictor::new();
+        let mut pointer_capture = input::PointerCapture::new();
+        let mut ime_spot = input::ImeSpot::new();
+        let mut focus_ring = FocusRing::new();
+        // The bytes of the frame being replayed instead of the UI, with the
+        // images they were exported with.
+        let mut replay: Option<(Vec<u8>, Vec<conrod_core::image::Id>)> = None;
+        // Set when presenting a frame finds the context lost, to make a new
+        // one before handling the next event.
+        let mut context_lost = false;
+        event_loop.run(move |event, target, control_flow| {
+            if context_lost {
+                context_lost = false;
+                // Everything on the GPU is gone with the context, and glutin
+                // can't make a new one for the same window, so the window is
+                // opened again at the same size.
+                let size = windowed_context.window().inner_size();
+                let window = window.clone().with_inner_size(size);
+                let rebuilt =
+                    create_context(window, target, is_srgb).and_then(|(context, new_gl)| {
+                        build_renderer(&new_gl, &context, is_srgb, safe_mode)
+                            .map(|new_renderer| (context, new_gl, new_renderer))
+                            .map_err(|err| err.to_string())
+                    });
+                let (context, new_gl, new_renderer) = match rebuilt {
+                    Ok(rebuilt) => rebuilt,
+                    Err(err) => {
+                        show_error_dialog(&AppError::ContextLost(err));
+                        *control_flow = glutin::event_loop::ControlFlow::Exit;
+                        return;
+                    }
+                };
+                windowed_context = context;
+                gl = new_gl;
+                renderer = new_renderer;
+                #[cfg(feature = "mirror")]
+                if mirror_server.is_some() {
+                    renderer.keep_glyph_pixels();
+                }
+                if fill_thread.is_some() {
+                    fill_thread = Some(FillThread::start(
+                        renderer.tessellator(&gl),
+                        ui_state.bridge.clone(),
+                    ));
+                }
+                // The images from files are uploaded again, and the textures
+                // drawn to, like the snapshot of the switcher, are left out.
+                image_sources.retain(|id, _| image_map.contains_key(id));
+                let ids: Vec<_> = image_map.keys().copied().collect();
+                for id in ids {
+                    let texture = image_sources.get(&id).map(|(image, options)| {
+                        let (width, height) = image.dimensions();
+                        conrod_glow::Texture::from_rgba8(&gl, width, height, image, *options)
+                    });
+                    match texture {
+                        Some(Ok(texture)) => {
+                            if let Some(slot) = image_map.get_mut(id) {
+                                *slot = texture;
+                            }
+                        }
+                        Some(Err(err)) => {
+                            ui_state.report_error(&AppError::Renderer(err));
+                            image_map.remove(id);
+                        }
+                        None => {
+                            image_map.remove(id);
+                        }
+                    }
+                }
+                ui_state.minimap.texture_lost();
+                // The texture and the target of its snapshot went with the
+                // context.
+                ui_state.switcher.close();
+                ui_state.switcher.take_stale_snapshot();
+                target_pool = conrod_glow::RenderTargetPool::new(2);
+                color_filter = ColorFilter::new();
+                focus_ring = FocusRing::new();
+                // The grab of the pointer and the spot of the input method
+                // were set on the old window.
+                pointer_capture = input::PointerCapture::new();
+                ime_spot = input::ImeSpot::new();
+                ui_state.diagnostics.renderer = Some(renderer.info().clone());
+                ui_state
+                    .notifications
+                    .info("The OpenGL context was lost, so the window was opened again");
+                ui.needs_redraw();
+                should_update_ui = true;
+            }
+
+            // Events posted from background work through the `Bridge`.
+            if let glutin::event::Event::UserEvent(app_event) = event {
+                ui_state.handle_app_event(app_event);
+                for (name, image) in std::mem::take(&mut ui_state.pending_images) {
+                    let (width, height) = image.dimensions();
+                    let options = conrod_glow::TextureOptions {
+                        is_srgb: true,
+                        ..Default::default()
+                    };
+                    let texture =
+                        conrod_glow::Texture::from_rgba8(&gl, width, height, &image, options);
+                    let texture = match texture {
+                        Ok(texture) => texture,
+                        Err(err) => {
+                            ui_state.report_error(&AppError::Renderer(err));
+                            continue;
+                        }
+                    };
+                    let id = image_map.insert(texture);
+                    image_sources.insert(id, (image, options));
+                    let loaded = LoadedImage {
+                        name,
+                        id,
+                        width,
+                        height,
+                    };
+                    if let Some(old) = ui_state.image_viewer.image.replace(loaded) {
+                        image_sources.remove(&old.id);
+                        if let Some(texture) = image_map.remove(old.id) {
+                            texture.delete(&gl);
+                        }
+                    }
+                }
+                should_update_ui = true;
+                return;
+            }
+
+            match &event {
+                glutin::event::Event::WindowEvent { event, .. } => match event {
+                    // Break from the loop upon `Escape` or closed window.
+                    glutin::event::WindowEvent::CloseRequested
+                    | glutin::event::WindowEvent::KeyboardInput {
+                        input:
+                            glutin::event::KeyboardInput {
+                                virtual_keycode: Some(glutin::event::VirtualKeyCode::Escape),
+                                ..
+                            },
+                        ..
+                    } => {
+                        if let Err(err) = ui_state.save_layout() {
+                            error::warn(&format!("Cannot save the window layout: {}", err));
+                        }
+                        *control_flow = glutin::event_loop::ControlFlow::Exit;
+                        return;
+                    }
+                    glutin::event::WindowEvent::Resized(physical_size) => {
+                        windowed_context.resize(*physical_size);
+                        // The kept targets have the old size.
+                        target_pool.trim(&gl);
+                    }
+                    glutin::event::WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                        current_hidpi_factor = *scale_factor;
+                    }
+                    glutin::event::WindowEvent::Focused(focused) => {
+                        ui_state.window_focused = *focused;
+                        if !focused {
+                            for input in input::focus_lost_inputs() {
+                                ui.handle_event(input);
+                            }
+                        }
+                        should_update_ui = true;
+                    }
+                    glutin::event::WindowEvent::Touch(_) => {
+                        let window = windowed_context.window();
+                        if let Some(pen) =
+                            conrod_floatwin_demo_glow::v023_convert_pen!(event, window)
+                        {
+                            ui_state.handle_pen(pen);
+                        }
+                    }
+                    glutin::event::WindowEvent::DroppedFile(path) => {
+                        file_dialog::open_path(&ui_state.bridge, path);
+                    }
+                    glutin::event::WindowEvent::MouseInput {
+                        state: glutin::event::ElementState::Pressed,
+                        button,
+                        ..
+                    } => {
+                        if let Some(action) = ui_state.config.mouse_bindings.action_for(*button) {
+                            ui_state.handle_mouse_action(action);
+                            should_update_ui = true;
+                        }
+                    }
+                    _ => {}
+                },
+                glutin::event::Event::RedrawRequested(_) => {
+                    ui.needs_redraw();
+                    should_update_ui = true;
+                }
+                _ => {}
+            }
+
+            // Shortcuts and gestures are handled by the app rather than conrod.
+            if let glutin::event::Event::WindowEvent { event, .. } = &event {
+                ui_state.keyboard.handle_window_event(event);
+                let is_repeat = key_repeat.is_repeat(event);
+                if let Some(command) = ui_state.shortcuts.handle_window_event(event, is_repeat) {
+                    ui_state.handle_command(command);
+                    should_update_ui = true;
+                    return;
+                }
+                if let Some(gesture) = gestures.convert_window_event(event) {
+                    ui_state.handle_gesture(gesture);
+                    should_update_ui = true;
+                    return;
+                }
+                let modifiers = ui_state.shortcuts.modifiers();
+                if let Some(shortcut) = convert_clipboard_shortcut(event, modifiers) {
+                    ui_state.handle_clipboard_shortcut(shortcut);
+                }
+            }
+
+            // Use the `winit` backend feature to convert the winit event to a conrod one.
+            let window_info = WindowInfo::of(windowed_context.window());
+            let inputs = tracer.span("convert_event", || {
+                event_converter.convert_event(&event, &window_info)
+            });
+            for event in inputs {
+                let event = ui_state.config.input.adjust(event, &ui_state.keyboard);
+                for event in coalescer.push(event) {
+                    for event in drag_predictor.settle(event) {
+                        let event = ui_state.confine_drag(event, &ui, &ids);
+                        let mouse = ui.global_input().current.mouse.xy;
+                        if let Some(event) = ui_state.raw_input.route(event, mouse) {
+                            ui.handle_event(event);
+                        }
+                    }
+                }
+                should_update_ui = true;
+            }
+
+            // The pasted text goes after the press of Ctrl+V.
+            if let Some(text) = ui_state.pending_paste.take() {
+                let text = conrod_core::event::Input::Text(text);
+                let mouse = ui.global_input().current.mouse.xy;
+                if let Some(event) = ui_state.raw_input.route(text, mouse) {
+                    ui.handle_event(event);
+                }
+                should_update_ui = true;
+            }
+
+            match &event {
+                #[cfg(feature = "mirror")]
+                glutin::event::Event::MainEventsCleared if mirror_viewer.is_some() => {
+                    let viewer = mirror_viewer.as_mut().unwrap();
+                    let messages = std::mem::take(&mut ui_state.mirror_inbox);
+                    if let Err(err) = viewer.receive(&gl, &mut renderer, messages) {
+                        show_error_dialog(&AppError::Mirror(err));
+                        *control_flow = glutin::event_loop::ControlFlow::Exit;
+                        return;
+                    }
+                    let size = windowed_context.window().inner_size();
+                    if should_update_ui
+                        && viewer.load(&mut renderer, (size.width, size.height), &[rust_logo])
+                    {
+                        let frame = renderer.begin_frame(&gl, (size.width, size.height));
+                        frame.clear([0.0, 0.0, 0.0, 1.0]);
+                        if let Err(err) = renderer.draw(&gl, &image_map) {
+                            error::warn(&err.to_string());
+                        }
+                        drop(frame);
+                        if let Err(err) = windowed_context.swap_buffers() {
+                            error::warn(&err.to_string());
+                        }
+                    }
+                    should_update_ui = false;
+                }
+                glutin::event::Event::MainEventsCleared => {
+                    let dragging = ui_state.config.input.predict_drags
+                        && ui.global_input().current.widget_capturing_mouse.is_some();
+                    if let Some(event) = drag_predictor.predict(coalescer.flush(), dragging) {
+                        let event = ui_state.confine_drag(event, &ui, &ids);
+                        let mouse = ui.global_input().current.mouse.xy;
+                        if let Some(event) = ui_state.raw_input.route(event, mouse) {
+                            ui.handle_event(event);
+                        }
+                    }
+                    #[cfg(feature = "devserver")]
+                    for event in std::mem::take(&mut ui_state.injected_inputs) {
+                        ui.handle_event(event);
+                        should_update_ui = true;
+                    }
+                    ui_state.profiler.events_coalesced = coalescer.coalesced;
+                    if let Some(msgs) = ui_state.timers.take_due() {
+                        for msg in msgs {
+                            ui_state.handle_timer(msg);
+                        }
+                        should_update_ui = true;
+                    }
+                    if should_update_ui || needs_next_update {
+                        needs_next_update = true;
+                        should_update_ui = false;
+
+                        ui_state.config.input.apply(&mut ui.theme);
+                        debug_draw::begin_frame();
+                        tracer.span("set_widgets", || {
+                            set_widgets(
+                                ui.set_widgets(),
+                                &mut ids,
+                                current_hidpi_factor,
+                                &mut ui_state,
+                            )
+                        });
+                        if debug_draw::changed() {
+                            ui.needs_redraw();
+                        }
+                        #[cfg(feature = "devserver")]
+                        if let Some(server) = &ui_state.devserver {
+                            server.publish(&ui_state, &ui, current_hidpi_factor);
+                        }
+
+                        let capturing = ui.global_input().current.widget_capturing_mouse.is_some();
+                        if let Some(grab) = pointer_capture.update(capturing) {
+                            // Not all platforms support grabbing, in which case
+                            // drags may still get stuck outside of the window.
+                            let _ = windowed_context.window().set_cursor_grab(grab);
+                        }
+                        let keyboard = ui
+                            .global_input()
+                            .current
+                            .widget_capturing_keyboard
+                            .and_then(|id| Some((id, ui.rect_of(id)?)));
+                        if let Some(rect) = ime_spot.update(keyboard.map(|(_, rect)| rect)) {
+                            let window = windowed_context.window();
+                            window.set_ime_position(convert_ime_position(rect, window));
+                        }
+                        let ring = keyboard.filter(|_| ui_state.config.focus_ring);
+                        if focus_ring.update(ring) {
+                            ui.needs_redraw();
+                            needs_next_update = true;
+                        }
+
+                        // Update the mouse cursor as set by conrod and the hotspots.
+                        windowed_context
+                            .window()
+                            .set_cursor_icon(convert_mouse_cursor(ui.mouse_cursor()));
+
+                        match ui_state.minimap.upload(&gl, &mut image_map, is_srgb) {
+                            Ok(true) => {
+                                ui.needs_redraw();
+                                needs_next_update = true;
+                            }
+                            Ok(false) => {}
+                            Err(err) => ui_state.report_error(&AppError::Renderer(err)),
+                        }
+
+                        // Draw the `Ui` if it has changed, or once the fill thread
+                        // has filled it.
+                        let mut filled = false;
+                        if let Some(primitives) = ui.draw_if_changed() {
+                            match &fill_thread {
+                                Some(fill_thread) => {
+                                    let size = windowed_context.window().inner_size();
+                                    let image_sizes = image_map
+                                        .iter()
+                                        .map(|(&id, image)| (id, (image.width, image.height)))
+                                        .collect();
+                                    fill_thread.submit(
+                                        primitives.owned(),
+                                        (size.width, size.height),
+                                        windowed_context.window().scale_factor(),
+                                        image_sizes,
+                                        &ui.fonts,
+                                    );
+                                }
+                                None => {
+                                    let result = tracer.span("fill", || {
+                                        renderer
+                                            .fill(&windowed_context, &gl, primitives, &image_map)
+                                            .and(renderer.fill_debug(
+                                                &windowed_context,
+                                                &gl,
+                                                &debug_draw::take_frame(),
+                                                &ui.fonts,
+                                            ))
+                                    });
+                                    if let Err(err) = result {
+                                        ui_state.report_error(&AppError::Draw(err.to_string()));
+                                    }
+                                    filled = true;
+                                }
+                            }
+                        }
+                        if let Some(mesh) = fill_thread.as_ref().and_then(FillThread::take) {
+                            match renderer.load_mesh(&gl, mesh) {
+                                Ok(()) => filled = true,
+                                Err(err) => ui_state.report_error(&AppError::Renderer(err)),
+                            }
+                        }
+                        if ui_state.pending_replay {
+                            ui_state.pending_replay = false;
+                            replay = match replay.take() {
+                                Some(_) => {
+                                    ui_state.notifications.info("Stopped replaying");
+                                    ui.needs_redraw();
+                                    None
+                                }
+                                None => {
+                                    let size = windowed_context.window().inner_size();
+                                    let images: Vec<_> =
+                                        image_map.iter().map(|(&id, _)| id).collect();
+                                    let frame =
+                                        renderer.export_frame((size.width, size.height), &images);
+                                    ui_state.start_replay(&frame);
+                                    Some((frame, images))
+                                }
+                            };
+                            filled = true;
+                        }
+                        // The frame takes the place of whatever was filled.
+                        if let Some((frame, images)) = replay.as_ref().filter(|_| filled) {
+                            let size = windowed_context.window().inner_size();
+                            if let Err(err) =
+                                renderer.import_frame(frame, (size.width, size.height), images)
+                            {
+                                ui_state.report_error(&AppError::Draw(err));
+                            }
+                        }
+                        if filled {
+                            if renderer.glyph_cache_grew() {
+                                ui_state.diagnostics.renderer = Some(renderer.info().clone());
+                            }
+                            image_gc.track(&renderer, &image_map);
+                            ui_state.diagnostics.idle_textures = image_gc.unused().len();
+                            let size = windowed_context.window().inner_size();
+                            #[cfg(feature = "mirror")]
+                            if let Some(server) = &mirror_server {
+                                server.publish(&renderer, (size.width, size.height), &[rust_logo]);
+                            }
+                            let frame = renderer.begin_frame(&gl, (size.width, size.height));
+                            frame.clear([0.0, 0.0, 0.0, 1.0]);
+                            let samples = if safe_mode {
+                                0
+                            } else {
+                                ui_state.config.msaa_samples
+                            };
+                            let drawn = tracer.span("draw", || {
+                                color_filter.draw(
+                                    &gl,
+                                    &mut target_pool,
+                                    &renderer,
+                                    &image_map,
+                                    (size.width, size.height),
+                                    ui_state.color_filter,
+                                    samples,
+                                    !safe_mode && ui_state.config.fxaa,
+                                )
+                            });
+                            let ring_drawn = focus_ring.draw(
+                                &gl,
+                                renderer.info(),
+                                (size.width, size.height),
+                                windowed_context.window().scale_factor(),
+                            );
+                            drop(frame);
+                            ui_state.diagnostics.msaa_samples = color_filter.samples();
+                            if let Err(err) = drawn.and(ring_drawn) {
+                                ui_state.report_error(&AppError::Draw(err));
+                            }
+                            if let Some(recorder) = &mut recorder {
+                                recorder.capture_frame(&gl, (size.width, size.height));
+                            }
+                            if ui_state.pending_screenshot {
+                                ui_state.pending_screenshot = false;
+                                let rect = conrod_glow::GlRect::full((size.width, size.height));
+                                let result = conrod_glow::capture_frame(&gl, rect)
+                                    .map_err(|err| err.to_string());
+                                ui_state.finish_screenshot(result);
+                            }
+                            match tracer.span("swap_buffers", || windowed_context.swap_buffers()) {
+                                Ok(()) => {}
+                                // A new context is made before the next
+                                // event, instead of failing on every frame.
+                                Err(glutin::ContextError::ContextLost) => {
+                                    context_lost = true;
+                                    windowed_context.window().request_redraw();
+                                }
+                                // Other errors may pass, as when the window is
+                                // being moved between screens.
+                                Err(err) => ui_state.report_error(&AppError::Draw(err.to_string())),
+                            }
+                        } else {
+                            needs_next_update = false;
+                        }
+                        if ui_state.keep_updating {
+                            needs_next_update = true;
+                        }
+
+                        // Before the switcher is shown, so that it isn't in
+                        // its own thumbnails.
+                        if ui_state.switcher.wants_snapshot() {
+                            let size = windowed_context.window().inner_size();
+                            let snapshot = window_switcher::Snapshot::take(
+                                &gl,
+                                &mut target_pool,
+                                &renderer,
+                                &mut image_map,
+                                (size.width, size.height),
+                                is_srgb,
+                            );
+                            if let Err(err) = ui_state.switcher.set_snapshot(snapshot) {
+                                ui_state.notifications.error(format!(
+                                    "Cannot take a snapshot for the window switcher: {}",
+                                    err
+                                ));
+                            }
+                            needs_next_update = true;
+                        }
+                        if let Some(snapshot) = ui_state.switcher.take_stale_snapshot() {
+                            snapshot.delete(&gl, &mut target_pool, &mut image_map);
+                        }
+
+                        // The screenshot is read back once the next frame
+                        // is drawn.
+                        if ui_state.pending_screenshot {
+                            ui.needs_redraw();
+                            needs_next_update = true;
+                        }
+
+                        if let Some(scale) = ui_state.pending_export.take() {
+                            let result = export::export_view(
+                                &gl,
+                                &mut target_pool,
+                                &mut renderer,
+                                &ui,
+                                &image_map,
+                                &windowed_context,
+                                scale,
+                                is_srgb,
+                            );
+                            ui_state.finish_export(result);
+                            // The renderer now holds the exported frame.
+                            ui.needs_redraw();
+                            needs_next_update = true;
+                        }
+
+                        if ui_state.tracing != tracer.is_recording() {
+                            if ui_state.tracing {
+                                tracer.start();
+                            } else {
+                                ui_state.finish_trace(tracer.stop());
+                            }
+                        }
+
+                        if let Some(seconds) = ui_state.pending_recording.take() {
+                            let size = windowed_context.window().inner_size();
+                            recorder =
+                                Some(capture::Recorder::new(size.width, size.height, seconds));
+                            ui.needs_redraw();
+                        }
+                        if recorder.as_ref().map_or(false, |r| r.is_finished()) {
+                            let task = ui_state.tasks.start("Encode GIF");
+                            recorder.take().unwrap().finish(&ui_state.bridge, task);
+                            ui_state.notifications.info("Encoding recording...");
+                        }
+                        // Keep updating so that the recording has the right timing.
+                        if recorder.is_some() {
+                            needs_next_update = true;
+                        }
+
+                        if let Some(jank) = tracer.end_frame() {
+                            ui_state.report_jank(jank);
+                        }
+                    }
+                }
+                _ => {}
+            }
+            if needs_next_update {
+                // With vsync enabled, `swap_buffers` limits the polling rate.
+                *control_flow = glutin::event_loop::ControlFlow::Poll;
+            } else if let Some(due) = ui_state.timers.next_due() {
+                *control_flow =
+                    glutin::event_loop::ControlFlow::WaitUntil(std::time::Instant::now() + due);
+            } else {
+                *control_flow = glutin::event_loop::ControlFlow::Wait;
+            }
+        })
+    }
+
+    /// Run the demo drawing on the CPU, for when no OpenGL context can be
+    /// made for the window, because of `reason`. What needs GL is left out:
+    /// the color filters, MSAA, the focus ring, the minimap, the window
+    /// switcher thumbnails, screenshots, exports and recordings.
+    #[cfg(feature = "soft")]
+    fn run_soft(
+        event_loop: glutin::event_loop::EventLoop<AppEvent>,
+        bridge: Bridge,
+        window: glutin::window::WindowBuilder,
+        reason: String,
+    ) -> Result<(), AppError> {
+        let window = window
+            .build(&event_loop)
+            .map_err(|err| AppError::Context(err.to_string()))?;
+        let mut soft_window = SoftWindow::new(&window).map_err(|err| {
+            AppError::Context(format!("{}, nor drawing without it: {}", reason, err))
+        })?;
+        let mut current_hidpi_factor = window.scale_factor();
+
+        let mut ui = conrod_core::UiBuilder::new([WIN_W as f64, WIN_H as f64])
+            .theme(conrod_example_shared::theme())
+            .build();
+        let assets = load_font(&mut ui)?;
+        let rust_logo = image::open(assets.join("images/rust.png"))
+            .map_err(|err| AppError::Image(err.to_string()))?;
+        let mut image_map = conrod_core::image::Map::new();
+        let rust_logo = image_map.insert(SoftImage::from_rgba(&rust_logo.to_rgba()));
+
+        let mut ids = Ids::new(ui.widget_id_generator());
+        let mut ui_state = UiState::new(
+            conrod_example_shared::DemoApp::new(rust_logo),
+            bridge,
+            Config::load(),
+        );
+        if let Err(err) = ui_state.load_layout() {
+            ui_state
+                .notifications
+                .error(format!("Cannot load the window layout: {}", err));
+        }
+        error::warn(&format!("Drawing on the CPU: {}", reason));
+        ui_state
+            .notifications
+            .info("OpenGL is not available, drawing on the CPU");
+        ui_state
+            .file_browser
+            .set_source(Box::new(file_browser::DirSource::new(assets)));
+
+        // The glyph cache has no texture size to stay within.
+        let mut tessellator = Tessellator::new((1024, 1024), (4096, 4096));
+        let mut rasterizer = SoftRasterizer::new();
+        let mut should_update_ui = true;
+        let mut key_repeat = input::KeyRepeat::new();
+        let mut event_converter = EventConverter::new();
+        event_loop.run(move |event, _, control_flow| {
+            if let glutin::event::Event::UserEvent(app_event) = event {
+                ui_state.handle_app_event(app_event);
+                for (name, image) in std::mem::take(&mut ui_state.pending_images) {
+                    let (width, height) = image.dimensions();
+                    let id = image_map.insert(SoftImage::from_rgba(&image));
+                    let loaded = LoadedImage {
+                        name,
+                        id,
+                        width,
+                        height,
+                    };
+                    if let Some(old) = ui_state.image_viewer.image.replace(loaded) {
+                        image_map.remove(old.id);
+                    }
+                }
+                should_update_ui = true;
+                return;
+            }
+
+            match &event {
+                glutin::event::Event::WindowEvent { event, .. } => match event {
+                    glutin::event::WindowEvent::CloseRequested
+                    | glutin::event::WindowEvent::KeyboardInput {
+                        input:
+                            glutin::event::KeyboardInput {
+                                virtual_keycode: Some(glutin::event::VirtualKeyCode::Escape),
+                                ..
+                            },
+                        ..
+                    } => {
+                        if let Err(err) = ui_state.save_layout() {
+                            error::warn(&format!("Cannot save the window layout: {}", err));
+                        }
+                        *control_flow = glutin::event_loop::ControlFlow::Exit;
+                        return;
+                    }
+                    glutin::event::WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                        current_hidpi_factor = *scale_factor;
+                    }
+                    glutin::event::WindowEvent::Focused(focused) => {
+                        ui_state.window_focused = *focused;
+                        should_update_ui = true;
+                    }
+                    _ => {}
+                },
+                glutin::event::Event::RedrawRequested(_) => {
+                    ui.needs_redraw();
+                    should_update_ui = true;
+                }
+                _ => {}
+            }
+
+            if let glutin::event::Event::WindowEvent { event, .. } = &event {
+                ui_state.keyboard.handle_window_event(event);
+                let is_repeat = key_repeat.is_repeat(event);
+                if let Some(command) = ui_state.shortcuts.handle_window_event(event, is_repeat) {
+                    ui_state.handle_command(command);
+                    should_update_ui = true;
+                    return;
+                }
+            }
+
+            let window_info = WindowInfo::of(&window);
+            for event in event_converter.convert_event(&event, &window_info) {
+                let event = ui_state.config.input.adjust(event, &ui_state.keyboard);
+                let mouse = ui.global_input().current.mouse.xy;
+                if let Some(event) = ui_state.raw_input.route(event, mouse) {
+                    ui.handle_event(event);
+                }
+                should_update_ui = true;
+            }
+
+            if let glutin::event::Event::MainEventsCleared = event {
+                if let Some(msgs) = ui_state.timers.take_due() {
+                    for msg in msgs {
+                        ui_state.handle_timer(msg);
+                    }
+                    should_update_ui = true;
+                }
+                if should_update_ui {
+                    should_update_ui = false;
+
+                    ui_state.config.input.apply(&mut ui.theme);
+                    debug_draw::begin_frame();
+                    set_widgets(
+                        ui.set_widgets(),
+                        &mut ids,
+                        current_hidpi_factor,
+                        &mut ui_state,
+                    );
+                    if debug_draw::changed() {
+                        ui.needs_redraw();
+                    }
+                    window.set_cursor_icon(convert_mouse_cursor(ui.mouse_cursor()));
+
+                    if let Some(primitives) = ui.draw_if_changed() {
+                        let size = window.inner_size();
+                        let display = (size.width, size.height, window.scale_factor());
+                        let image_sizes = image_map
+                            .iter()
+                            .map(|(&id, image)| (id, (image.width, image.height)))
+                            .collect();
+                        let mut mesh = tessellator.tessellate(&display, primitives, &image_sizes);
+                        let font = ui
+                            .fonts
+                            .ids()
+                            .next()
+                            .and_then(|id| Some((id, ui.fonts.get(id)?)));
+                        tessellator.tessellate_debug(
+                            &display,
+                            &mut mesh,
+                            &debug_draw::take_frame(),
+                            font,
+                        );
+                        rasterizer.load_glyphs(&mesh);
+                        let (image_ids, images): (Vec<_>, Vec<_>) =
+                            image_map.iter().map(|(&id, image)| (id, image)).unzip();
+                        let frame = mesh.frame((size.width, size.height), &image_ids);
+                        rasterizer.draw(&frame, &images, [0.0, 0.0, 0.0, 1.0]);
+                        soft_window.present(&rasterizer);
+                    }
+                    if ui_state.keep_updating {
+                        should_update_ui = true;
+                    }
+                }
+            }
+            if should_update_ui {
+                *control_flow = glutin::event_loop::ControlFlow::Poll;
+            } else if let Some(due) = ui_state.timers.next_due() {
+                *control_flow =
+                    glutin::event_loop::ControlFlow::WaitUntil(std::time::Instant::now() + due);
+            } else {
+                *control_flow = glutin::event_loop::ControlFlow::Wait;
+            }
+        })
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {
+    native::main()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn main() {}
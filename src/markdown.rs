@@ -0,0 +1,610 @@
+// A preview of the Notes text as markdown.
+//
+// The text is parsed with pulldown-cmark into blocks of `richtext` spans.
+// The demo only has one font, so emphasis and strong text are told apart by
+// color, and only the size changes for headings.
+//
+// The blocks can be selected across as one text, with a line break between
+// blocks, and copied with Ctrl+C.
+
+use crate::common::UiState;
+use crate::hotspots::{Action, Hotspots};
+use crate::richtext::{self, Align, RichText};
+use crate::selection::{self, Selection, TextLayout};
+use crate::trace::now_us;
+use conrod_core::text::font;
+use conrod_core::{
+    color, widget, widget_ids, Borderable, Colorable, Point, Positionable, Scalar, Sizeable,
+    Widget, WidgetCommon,
+};
+use pulldown_cmark::{Event, Parser, Tag};
+use std::time::Duration;
+
+/// Shown while the notes are empty.
+const SAMPLE: &str = "# Markdown preview
+
+This window shows the text of the **Notes** window as *markdown*.
+
+## What is supported
+
+- Headings, *emphasis* and **strong** text
+- Lists, nested:
+    1. like
+    2. this
+- Inline `code` and code blocks
+- Links, like [the conrod repository](https://github.com/PistonDevelopers/conrod)
+
+```
+fn main() {
+    println!(\"Hello\");
+}
+```
+";
+
+const BODY_SIZE: conrod_core::FontSize = 14;
+const CODE_SIZE: conrod_core::FontSize = 13;
+const INDENT: Scalar = 20.0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Style {
+    emphasis: bool,
+    strong: bool,
+    code: bool,
+    /// The index of the link in `MarkdownState::links`.
+    link: Option<usize>,
+}
+
+#[derive(Debug, PartialEq)]
+enum BlockKind {
+    Paragraph,
+    Heading(u32),
+    /// A list item, with its bullet or number.
+    Item(String),
+    Code,
+    Rule,
+}
+
+#[derive(Debug)]
+struct Block {
+    kind: BlockKind,
+    /// How deep the block is in lists and quotes.
+    depth: usize,
+    spans: Vec<richtext::Span>,
+}
+
+pub struct MarkdownState {
+    /// The text which `blocks` were parsed from.
+    source: Option<String>,
+    blocks: Vec<Block>,
+    links: Vec<String>,
+    /// The selection in the text of the blocks joined by line breaks.
+    selection: Selection,
+}
+
+impl Default for MarkdownState {
+    fn default() -> Self {
+        MarkdownState::new()
+    }
+}
+
+impl MarkdownState {
+    pub fn new() -> Self {
+        MarkdownState {
+            source: None,
+            blocks: Vec::new(),
+            links: Vec::new(),
+            selection: Selection::new(),
+        }
+    }
+
+    /// Parse `source` unless it is what was parsed last time.
+    fn update(&mut self, source: &str) {
+        if self.source.as_deref() == Some(source) {
+            return;
+        }
+        let mut builder = BlockBuilder::default();
+        for event in Parser::new(source) {
+            builder.event(event);
+        }
+        builder.finish_block();
+        self.blocks = builder.blocks;
+        self.links = builder.links;
+        self.source = Some(source.to_owned());
+        self.selection.reset();
+    }
+}
+
+#[derive(Default)]
+struct BlockBuilder {
+    blocks: Vec<Block>,
+    links: Vec<String>,
+    current: Option<Block>,
+    style: Style,
+    /// The next number of each list that is open, `None` for bulleted ones.
+    lists: Vec<Option<u64>>,
+    quotes: usize,
+}
+
+impl BlockBuilder {
+    fn depth(&self) -> usize {
+        self.lists.len() + self.quotes
+    }
+
+    fn start_block(&mut self, kind: BlockKind) {
+        self.finish_block();
+        self.current = Some(Block {
+            kind,
+            depth: self.depth(),
+            spans: Vec::new(),
+        });
+    }
+
+    fn finish_block(&mut self) {
+        if let Some(mut block) = self.current.take() {
+            // Code blocks end with a line break, which would add an empty line.
+            if block.kind == BlockKind::Code {
+                if let Some(span) = block.spans.last_mut() {
+                    let len = span.text.trim_end_matches('\n').len();
+                    span.text.truncate(len);
+                }
+            }
+            if !block.spans.is_empty() || block.kind == BlockKind::Rule {
+                self.blocks.push(block);
+            }
+        }
+    }
+
+    fn push_text(&mut self, text: &str, style: Style) {
+        if self.current.is_none() {
+            self.start_block(BlockKind::Paragraph);
+        }
+        if let Some(block) = &mut self.current {
+            let font_size = match block.kind {
+                BlockKind::Heading(level) => heading_size(level),
+                BlockKind::Code => CODE_SIZE,
+                _ if style.code => CODE_SIZE,
+                _ => BODY_SIZE,
+            };
+            let heading = match block.kind {
+                BlockKind::Heading(_) => true,
+                _ => false,
+            };
+            let mut span = richtext::Span::new(text, font_size, span_color(style, heading));
+            if style.code && block.kind != BlockKind::Code {
+                span = span.background(color::DARK_CHARCOAL);
+            }
+            if let Some(link) = style.link {
+                span = span.underline().tag(link);
+            }
+            block.spans.push(span);
+        }
+    }
+
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(Tag::Paragraph) => {
+                // The first paragraph of a list item goes after its bullet.
+                let in_item = match &self.current {
+                    Some(block) => block.spans.is_empty(),
+                    None => false,
+                };
+                if !in_item {
+                    self.start_block(BlockKind::Paragraph);
+                }
+            }
+            Event::Start(Tag::Heading(level)) => self.start_block(BlockKind::Heading(level)),
+            Event::Start(Tag::CodeBlock(_)) => self.start_block(BlockKind::Code),
+            Event::Start(Tag::List(first)) => {
+                self.finish_block();
+                self.lists.push(first);
+            }
+            Event::Start(Tag::Item) => {
+                let marker = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}.", *number - 1)
+                    }
+                    _ => "\u{2022}".to_owned(),
+                };
+                self.finish_block();
+                // The bullet is indented as deep as the list's parent.
+                self.current = Some(Block {
+                    kind: BlockKind::Item(marker),
+                    depth: self.depth().saturating_sub(1),
+                    spans: Vec::new(),
+                });
+            }
+            Event::Start(Tag::BlockQuote) => {
+                self.finish_block();
+                self.quotes += 1;
+            }
+            Event::Start(Tag::Emphasis) => self.style.emphasis = true,
+            Event::Start(Tag::Strong) => self.style.strong = true,
+            Event::Start(Tag::Link(_, url, _)) => {
+                self.style.link = Some(self.links.len());
+                self.links.push(url.to_string());
+            }
+            Event::End(Tag::Paragraph)
+            | Event::End(Tag::Heading(_))
+            | Event::End(Tag::CodeBlock(_))
+            | Event::End(Tag::Item) => self.finish_block(),
+            Event::End(Tag::List(_)) => {
+                self.finish_block();
+                self.lists.pop();
+            }
+            Event::End(Tag::BlockQuote) => {
+                self.finish_block();
+                self.quotes -= 1;
+            }
+            Event::End(Tag::Emphasis) => self.style.emphasis = false,
+            Event::End(Tag::Strong) => self.style.strong = false,
+            Event::End(Tag::Link(..)) => self.style.link = None,
+            Event::Text(text) => self.push_text(&text, self.style),
+            Event::Code(text) => self.push_text(
+                &text,
+                Style {
+                    code: true,
+                    ..self.style
+                },
+            ),
+            Event::SoftBreak => self.push_text(" ", self.style),
+            Event::HardBreak => self.push_text("\n", self.style),
+            Event::Rule => {
+                self.start_block(BlockKind::Rule);
+                self.finish_block();
+            }
+            _ => {}
+        }
+    }
+}
+
+fn heading_size(level: u32) -> conrod_core::FontSize {
+    match level {
+        1 => 24,
+        2 => 20,
+        3 => 17,
+        _ => 15,
+    }
+}
+
+fn span_color(style: Style, heading: bool) -> color::Color {
+    if style.link.is_some() {
+        color::LIGHT_BLUE
+    } else if style.code {
+        color::LIGHT_GREEN
+    } else if style.strong || heading {
+        color::WHITE
+    } else if style.emphasis {
+        color::LIGHT_YELLOW
+    } else {
+        color::LIGHT_GREY
+    }
+}
+
+/// Open `url` in the browser, or in a new tab on the web.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn open_url(url: &str) -> Result<(), String> {
+    webbrowser::open(url).map(|_| ()).map_err(|e| e.to_string())
+}
+
+/// Open `url` in the browser, or in a new tab on the web.
+#[cfg(target_arch = "wasm32")]
+pub fn open_url(url: &str) -> Result<(), String> {
+    let window = web_sys::window().ok_or("no window")?;
+    window
+        .open_with_url_and_target(url, "_blank")
+        .map(|_| ())
+        .map_err(|err| {
+            err.as_string()
+                .unwrap_or_else(|| "the browser refused".to_owned())
+        })
+}
+
+/// The laid out blocks as one text for selecting in, with a line break
+/// between the blocks which have text.
+struct Document<'a> {
+    text: String,
+    /// The top left of each block, where its text starts, and its layout.
+    blocks: Vec<(Point, usize, &'a richtext::Layout)>,
+}
+
+impl<'a> Document<'a> {
+    fn new(blocks: Vec<(Point, &'a richtext::Layout)>) -> Self {
+        let mut text = String::new();
+        let blocks = blocks
+            .into_iter()
+            .map(|(top_left, layout)| {
+                if !text.is_empty() && !layout.text.is_empty() {
+                    text.push('\n');
+                }
+                let start = text.len();
+                text.push_str(&layout.text);
+                (top_left, start, layout)
+            })
+            .collect();
+        Document { text, blocks }
+    }
+
+    /// The block with the position `index`.
+    fn block_of(&self, index: usize) -> Option<&(Point, usize, &'a richtext::Layout)> {
+        self.blocks
+            .iter()
+            .find(|(_, start, layout)| index <= start + layout.text.len())
+    }
+}
+
+impl<'a> TextLayout for Document<'a> {
+    fn text(&self) -> &str {
+        &self.text
+    }
+
+    fn index_at(&self, fonts: &font::Map, [x, y]: [Scalar; 2]) -> usize {
+        let block = self
+            .blocks
+            .iter()
+            .find(|([_, top], _, layout)| y < top + layout.height)
+            .or_else(|| self.blocks.last());
+        match block {
+            Some(&([left, top], start, layout)) => {
+                start + layout.index_at(fonts, [x - left, y - top])
+            }
+            None => 0,
+        }
+    }
+
+    fn caret(&self, fonts: &font::Map, index: usize) -> [Scalar; 3] {
+        match self.block_of(index) {
+            Some(&([left, top], start, layout)) => {
+                let [x, y, h] = layout.caret(fonts, index - start);
+                [left + x, top + y, h]
+            }
+            None => [0.0, 0.0, 0.0],
+        }
+    }
+}
+
+/// A block laid out in the canvas.
+struct Placed {
+    /// Where the block starts, before the padding of code blocks.
+    y: Scalar,
+    indent: Scalar,
+    /// The top left of the text.
+    top_left: Point,
+    layout: richtext::Layout,
+}
+
+/// What happened in the Markdown window.
+#[derive(Debug, Default)]
+pub struct MarkdownEvent {
+    /// The selected text, which the user asked to copy.
+    pub copy: Option<String>,
+    /// How long until the caret blinks, while the window has the keyboard,
+    /// after which the UI needs to be updated.
+    pub next_blink: Option<Duration>,
+}
+
+#[derive(WidgetCommon)]
+pub struct MarkdownWidget<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    markdown: &'a mut MarkdownState,
+    source: &'a str,
+    hotspots: &'a mut Hotspots,
+}
+
+impl<'a> MarkdownWidget<'a> {
+    /// Preview `source`, registering its links in `hotspots`.
+    pub fn new(
+        markdown: &'a mut MarkdownState,
+        source: &'a str,
+        hotspots: &'a mut Hotspots,
+    ) -> Self {
+        MarkdownWidget {
+            common: widget::CommonBuilder::default(),
+            markdown,
+            source,
+            hotspots,
+        }
+    }
+}
+
+widget_ids! {
+    pub struct MarkdownIds {
+        canvas,
+        blocks[],
+        markers[],
+        backgrounds[],
+        caret,
+    }
+}
+
+impl<'a> Widget for MarkdownWidget<'a> {
+    type State = MarkdownIds;
+    type Style = ();
+    type Event = MarkdownEvent;
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        MarkdownIds::new(id_gen)
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs {
+            id,
+            state,
+            ui,
+            rect,
+            ..
+        } = args;
+        let MarkdownWidget {
+            markdown,
+            source,
+            hotspots,
+            ..
+        } = self;
+
+        const PAD: Scalar = 8.0;
+        const GAP: Scalar = 8.0;
+
+        let mut event = MarkdownEvent::default();
+        let source = if source.trim().is_empty() {
+            SAMPLE
+        } else {
+            source
+        };
+        markdown.update(source);
+
+        widget::Canvas::new()
+            .color(color::TRANSPARENT)
+            .border(0.0)
+            .w_h(rect.w() - PAD * 2.0, rect.h() - PAD * 2.0)
+            .top_left_with_margin_on(id, PAD)
+            .scroll_kids_vertically()
+            .set(state.canvas, ui);
+
+        let font_id = match richtext::default_font(ui) {
+            Some(font_id) => font_id,
+            None => return event,
+        };
+        // Leave room for the scrollbar.
+        let width = rect.w() - PAD * 4.0;
+
+        // Lay out the blocks from the top left of the canvas.
+        let mut placed = Vec::with_capacity(markdown.blocks.len());
+        let mut y = 0.0;
+        for block in &markdown.blocks {
+            let indent = block.depth as Scalar * INDENT;
+            let left = match block.kind {
+                BlockKind::Item(_) => indent + INDENT,
+                BlockKind::Code => indent + GAP / 2.0,
+                _ => indent,
+            };
+            let layout = richtext::layout(
+                &block.spans,
+                &ui.fonts,
+                font_id,
+                (width - left).max(0.0),
+                Align::Left,
+            );
+            let (top, h) = match block.kind {
+                BlockKind::Code => (y + GAP / 2.0, layout.height + GAP),
+                BlockKind::Rule => (y, GAP * 2.0),
+                _ => (y, layout.height),
+            };
+            placed.push(Placed {
+                y,
+                indent,
+                top_left: [left, top],
+                layout,
+            });
+            y += h + GAP;
+        }
+
+        let count = markdown.blocks.len();
+        if state.blocks.len() < count {
+            let id_gen = &mut ui.widget_id_generator();
+            state.update(|state| {
+                state.blocks.resize(count, id_gen);
+                state.markers.resize(count, id_gen);
+                state.backgrounds.resize(count, id_gen);
+            });
+        }
+
+        let document = Document::new(
+            placed
+                .iter()
+                .map(|placed| (placed.top_left, &placed.layout))
+                .collect(),
+        );
+        // The blocks are where they were drawn in the last update, which is
+        // what the user clicked on.
+        let origin = placed
+            .iter()
+            .enumerate()
+            .find_map(|(i, placed)| Some((ui.rect_of(state.blocks[i])?, placed.top_left)))
+            .map(|(drawn, [left, top])| [drawn.left() - left, drawn.top() + top]);
+        let now = now_us();
+        if let Some(origin) = origin {
+            event.copy = markdown
+                .selection
+                .update(ui, state.canvas, &document, origin, now);
+        }
+
+        let selection = markdown.selection.range();
+        let links = &markdown.links;
+        let open_link = |link: usize| -> Action {
+            let url = links[link].clone();
+            Box::new(move |state: &mut UiState| {
+                if let Err(err) = open_url(&url) {
+                    state
+                        .notifications
+                        .error(format!("Cannot open {}: {}", url, err));
+                }
+            })
+        };
+        for (i, (block, placed)) in markdown.blocks.iter().zip(&placed).enumerate() {
+            let Placed {
+                y,
+                indent,
+                top_left: [left, top],
+                ref layout,
+            } = *placed;
+            match &block.kind {
+                BlockKind::Item(marker) => {
+                    widget::Text::new(marker)
+                        .font_size(BODY_SIZE)
+                        .color(color::LIGHT_GREY)
+                        .top_left_with_margins_on(state.canvas, top, indent)
+                        .set(state.markers[i], ui);
+                }
+                BlockKind::Code => {
+                    widget::Rectangle::fill([width - indent, layout.height + GAP])
+                        .color(color::DARK_CHARCOAL)
+                        .top_left_with_margins_on(state.canvas, y, indent)
+                        .set(state.backgrounds[i], ui);
+                }
+                BlockKind::Rule => {
+                    widget::Rectangle::fill([width - indent, 1.0])
+                        .color(color::GREY)
+                        .top_left_with_margins_on(state.canvas, y + GAP, indent)
+                        .set(state.backgrounds[i], ui);
+                }
+                _ => {}
+            }
+            if layout.fragments.is_empty() {
+                continue;
+            }
+            let mut rich_text = RichText::new(&block.spans, layout);
+            let start = document.blocks[i].1;
+            let end = start + layout.text.len();
+            if selection.start < end && selection.end > start {
+                let local = selection.start.max(start) - start..selection.end.min(end) - start;
+                rich_text = rich_text.selection(local);
+            }
+            rich_text
+                .links(hotspots, &open_link)
+                .w_h(layout.width, layout.height)
+                .top_left_with_margins_on(state.canvas, top, left)
+                .set(state.blocks[i], ui);
+        }
+
+        // The caret shows while the window has the keyboard.
+        let focused = match ui.global_input().current.widget_capturing_keyboard {
+            Some(captured) => selection::is_within(ui, captured, state.canvas),
+            None => false,
+        };
+        if focused && markdown.selection.caret_visible(now) {
+            let [x, y, h] = document.caret(&ui.fonts, markdown.selection.head());
+            widget::Rectangle::fill([1.5, h])
+                .color(color::WHITE)
+                .top_left_with_margins_on(state.canvas, y, x)
+                .graphics_for(state.canvas)
+                .set(state.caret, ui);
+        }
+        if focused {
+            event.next_blink = Some(markdown.selection.until_blink(now));
+        }
+        event
+    }
+}
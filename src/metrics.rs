@@ -0,0 +1,295 @@
+// Readings of the machine the demo runs on, for the desktop widgets and the
+// Diagnostics window.
+//
+// Each platform has its `MetricsProvider`: sysinfo on the desktop with the
+// `cpu` feature, and the browser's APIs on the web, which only tell about
+// the memory of the page (in Chrome) and the battery. Whatever a provider
+// can't read is `None`, and shown as "n/a".
+//
+// Providers are only refreshed by a timer, every `INTERVAL`, while something
+// shows the readings. sysinfo needs that much time between two refreshes to
+// measure the CPU usage.
+
+use std::time::Duration;
+
+pub const INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Memory {
+    /// In bytes.
+    pub used: u64,
+    /// In bytes.
+    pub total: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Battery {
+    /// From 0.0 to 1.0.
+    pub level: f32,
+    pub charging: bool,
+}
+
+pub trait MetricsProvider {
+    /// Where the readings come from, for the Diagnostics window.
+    fn source(&self) -> &'static str;
+
+    /// Read everything again.
+    fn refresh(&mut self);
+
+    /// The usage of all the CPUs together, in percent.
+    fn cpu_usage(&self) -> Option<f32>;
+
+    fn memory(&self) -> Option<Memory>;
+
+    fn battery(&self) -> Option<Battery>;
+
+    /// All of the last readings.
+    fn readings(&self) -> Readings {
+        Readings {
+            source: self.source(),
+            cpu_usage: self.cpu_usage(),
+            memory: self.memory(),
+            battery: self.battery(),
+        }
+    }
+}
+
+/// The readings of a provider at some point.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Readings {
+    pub source: &'static str,
+    pub cpu_usage: Option<f32>,
+    pub memory: Option<Memory>,
+    pub battery: Option<Battery>,
+}
+
+impl Default for Readings {
+    fn default() -> Self {
+        Readings {
+            source: "none",
+            cpu_usage: None,
+            memory: None,
+            battery: None,
+        }
+    }
+}
+
+impl Readings {
+    pub fn cpu_text(&self) -> String {
+        match self.cpu_usage {
+            Some(usage) => format!("CPU {:.0}%", usage),
+            None => "CPU n/a".to_owned(),
+        }
+    }
+
+    pub fn memory_text(&self) -> String {
+        match self.memory {
+            Some(memory) => format!(
+                "Memory {} / {} MiB",
+                memory.used / (1 << 20),
+                memory.total / (1 << 20)
+            ),
+            None => "Memory n/a".to_owned(),
+        }
+    }
+
+    pub fn battery_text(&self) -> String {
+        match self.battery {
+            Some(battery) => format!(
+                "Battery {:.0}%{}",
+                battery.level * 100.0,
+                if battery.charging { " charging" } else { "" }
+            ),
+            None => "Battery n/a".to_owned(),
+        }
+    }
+
+    /// The lines of the System section of the Diagnostics window.
+    pub fn lines(&self) -> Vec<String> {
+        vec![
+            format!("Readings from: {}", self.source),
+            self.cpu_text(),
+            self.memory_text(),
+            self.battery_text(),
+        ]
+    }
+}
+
+/// The provider for this platform.
+pub fn provider() -> Box<dyn MetricsProvider> {
+    #[cfg(all(feature = "cpu", not(target_arch = "wasm32")))]
+    let provider = SysinfoMetrics::new();
+    #[cfg(target_arch = "wasm32")]
+    let provider = WebMetrics::new();
+    #[cfg(not(any(feature = "cpu", target_arch = "wasm32")))]
+    let provider = NoMetrics;
+    Box::new(provider)
+}
+
+/// For the desktop without the `cpu` feature.
+#[cfg(not(any(feature = "cpu", target_arch = "wasm32")))]
+struct NoMetrics;
+
+#[cfg(not(any(feature = "cpu", target_arch = "wasm32")))]
+impl MetricsProvider for NoMetrics {
+    fn source(&self) -> &'static str {
+        "none, built without the cpu feature"
+    }
+
+    fn refresh(&mut self) {}
+
+    fn cpu_usage(&self) -> Option<f32> {
+        None
+    }
+
+    fn memory(&self) -> Option<Memory> {
+        None
+    }
+
+    fn battery(&self) -> Option<Battery> {
+        None
+    }
+}
+
+/// sysinfo has no battery readings.
+#[cfg(all(feature = "cpu", not(target_arch = "wasm32")))]
+struct SysinfoMetrics {
+    system: sysinfo::System,
+    /// `None` until the second refresh, as the first one only starts the
+    /// measurement.
+    cpu_usage: Option<f32>,
+    refreshed: bool,
+}
+
+#[cfg(all(feature = "cpu", not(target_arch = "wasm32")))]
+impl SysinfoMetrics {
+    fn new() -> Self {
+        use sysinfo::SystemExt;
+        SysinfoMetrics {
+            system: sysinfo::System::new(),
+            cpu_usage: None,
+            refreshed: false,
+        }
+    }
+}
+
+#[cfg(all(feature = "cpu", not(target_arch = "wasm32")))]
+impl MetricsProvider for SysinfoMetrics {
+    fn source(&self) -> &'static str {
+        "sysinfo"
+    }
+
+    fn refresh(&mut self) {
+        use sysinfo::{ProcessorExt, SystemExt};
+        self.system.refresh_cpu();
+        self.system.refresh_memory();
+        if self.refreshed {
+            self.cpu_usage = Some(self.system.get_global_processor_info().get_cpu_usage());
+        }
+        self.refreshed = true;
+    }
+
+    fn cpu_usage(&self) -> Option<f32> {
+        self.cpu_usage
+    }
+
+    fn memory(&self) -> Option<Memory> {
+        use sysinfo::SystemExt;
+        if !self.refreshed {
+            return None;
+        }
+        // sysinfo counts in KiB.
+        Some(Memory {
+            used: self.system.get_used_memory() * 1024,
+            total: self.system.get_total_memory() * 1024,
+        })
+    }
+
+    fn battery(&self) -> Option<Battery> {
+        None
+    }
+}
+
+/// The memory is that of the JavaScript heap, from the non-standard
+/// `performance.memory` of Chrome. The battery comes from the Battery Status
+/// API, which hands over the `BatteryManager` some time after it is asked.
+#[cfg(target_arch = "wasm32")]
+struct WebMetrics {
+    battery_manager: std::rc::Rc<std::cell::RefCell<Option<wasm_bindgen::JsValue>>>,
+    memory: Option<Memory>,
+    battery: Option<Battery>,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl WebMetrics {
+    fn new() -> Self {
+        use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+        let battery_manager = std::rc::Rc::new(std::cell::RefCell::new(None));
+        let navigator = web_sys::window().map(|window| window.navigator());
+        let get_battery = navigator.as_ref().and_then(|navigator| {
+            let get_battery = get(navigator, "getBattery")?.dyn_into::<js_sys::Function>();
+            let promise = get_battery.ok()?.call0(navigator).ok()?;
+            promise.dyn_into::<js_sys::Promise>().ok()
+        });
+        if let Some(promise) = get_battery {
+            let on_battery = {
+                let battery_manager = battery_manager.clone();
+                Closure::once(move |manager: JsValue| {
+                    *battery_manager.borrow_mut() = Some(manager);
+                })
+            };
+            let _ = promise.then(&on_battery);
+            on_battery.forget();
+        }
+        WebMetrics {
+            battery_manager,
+            memory: None,
+            battery: None,
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl MetricsProvider for WebMetrics {
+    fn source(&self) -> &'static str {
+        "browser"
+    }
+
+    fn refresh(&mut self) {
+        let memory = web_sys::window()
+            .and_then(|window| window.performance())
+            .and_then(|performance| get(&performance, "memory"));
+        self.memory = memory.and_then(|memory| {
+            Some(Memory {
+                used: get(&memory, "usedJSHeapSize")?.as_f64()? as u64,
+                total: get(&memory, "jsHeapSizeLimit")?.as_f64()? as u64,
+            })
+        });
+        self.battery = self.battery_manager.borrow().as_ref().and_then(|manager| {
+            Some(Battery {
+                level: get(manager, "level")?.as_f64()? as f32,
+                charging: get(manager, "charging")?.as_bool()?,
+            })
+        });
+    }
+
+    fn cpu_usage(&self) -> Option<f32> {
+        None
+    }
+
+    fn memory(&self) -> Option<Memory> {
+        self.memory
+    }
+
+    fn battery(&self) -> Option<Battery> {
+        self.battery
+    }
+}
+
+/// The property `name` of `target`, unless it is undefined.
+#[cfg(target_arch = "wasm32")]
+fn get(target: &wasm_bindgen::JsValue, name: &str) -> Option<wasm_bindgen::JsValue> {
+    js_sys::Reflect::get(target, &name.into())
+        .ok()
+        .filter(|value| !value.is_undefined())
+}
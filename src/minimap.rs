@@ -0,0 +1,290 @@
+// A minimap of a tile world which changes a few tiles at a time, to show
+// `Texture::update_region` uploading only the tiles which changed.
+//
+// The event loop owns the GL context, so the changed regions are queued here
+// and the event loop calls `MinimapState::upload`, like it does for the
+// window switcher's snapshot.
+
+use crate::conrod_glow::{PixelFormat, Texture};
+use crate::trace::now_us;
+use conrod_core::{
+    color, image, widget, widget_ids, Colorable, Labelable, Positionable, Sizeable, Widget,
+    WidgetCommon,
+};
+
+pub const MAP_W: u32 = 96;
+pub const MAP_H: u32 = 64;
+
+/// Water, grass, sand, forest, and the road painted with the mouse.
+const TILE_COLORS: [[u8; 4]; 5] = [
+    [40, 90, 170, 255],
+    [90, 160, 70, 255],
+    [220, 200, 130, 255],
+    [30, 100, 50, 255],
+    [200, 200, 200, 255],
+];
+const ROAD: u8 = 4;
+
+const STEPS_PER_SECOND: f64 = 30.0;
+
+/// Pixels which changed and still need to be uploaded.
+struct Region {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+pub struct MinimapState {
+    /// The color index of each tile, row by row from the top.
+    tiles: Vec<u8>,
+    walker: (u32, u32),
+    /// An xorshift state, so that the walk is the same on every run.
+    rng: u32,
+    pub walking: bool,
+    last_step: Option<f64>,
+    /// The texture in the `image::Map`, once the event loop has uploaded it.
+    image: Option<image::Id>,
+    pending: Vec<Region>,
+    pub region_count: u64,
+    pub uploaded_bytes: u64,
+}
+
+impl Default for MinimapState {
+    fn default() -> Self {
+        MinimapState::new()
+    }
+}
+
+impl MinimapState {
+    pub fn new() -> Self {
+        let tiles = (0..MAP_H)
+            .flat_map(|y| (0..MAP_W).map(move |x| ((x / 12 + y / 8 + x * y / 97) % 4) as u8))
+            .collect();
+        MinimapState {
+            tiles,
+            walker: (MAP_W / 2, MAP_H / 2),
+            rng: 0x2545_f491,
+            walking: false,
+            last_step: None,
+            image: None,
+            pending: Vec::new(),
+            region_count: 0,
+            uploaded_bytes: 0,
+        }
+    }
+
+    /// The whole map as RGBA pixels.
+    fn pixels(&self) -> Vec<u8> {
+        self.tiles
+            .iter()
+            .flat_map(|&tile| TILE_COLORS[tile as usize].iter().copied())
+            .collect()
+    }
+
+    /// Set the tile at `x`, `y` and queue its pixel for uploading.
+    fn paint(&mut self, x: u32, y: u32, tile: u8) {
+        let index = (y * MAP_W + x) as usize;
+        if self.tiles[index] == tile {
+            return;
+        }
+        self.tiles[index] = tile;
+        // The first upload takes the whole map anyway.
+        if self.image.is_some() {
+            self.pending.push(Region {
+                x,
+                y,
+                width: 1,
+                height: 1,
+                pixels: TILE_COLORS[tile as usize].to_vec(),
+            });
+        }
+    }
+
+    fn random(&mut self) -> u32 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+        self.rng
+    }
+
+    /// Move the walker to a neighbouring tile, turning it to forest or
+    /// grass.
+    fn step(&mut self) {
+        let (x, y) = self.walker;
+        let (x, y) = match self.random() % 4 {
+            0 => ((x + 1) % MAP_W, y),
+            1 => ((x + MAP_W - 1) % MAP_W, y),
+            2 => (x, (y + 1) % MAP_H),
+            _ => (x, (y + MAP_H - 1) % MAP_H),
+        };
+        self.walker = (x, y);
+        let tile = if self.random() % 3 == 0 { 3 } else { 1 };
+        self.paint(x, y, tile);
+    }
+
+    /// Upload the whole map again on the next `upload`, after its texture
+    /// went away with the OpenGL context.
+    pub fn texture_lost(&mut self) {
+        self.image = None;
+        self.pending.clear();
+    }
+
+    /// Upload the map to a new texture the first time, and after that only
+    /// the regions which changed. Returns whether anything was uploaded, in
+    /// which case the UI needs to be updated and redrawn, as conrod can't
+    /// tell that the texture changed.
+    pub fn upload(
+        &mut self,
+        gl: &glow::Context,
+        image_map: &mut image::Map<Texture>,
+        is_srgb: bool,
+    ) -> Result<bool, String> {
+        let image = match self.image {
+            Some(image) => image,
+            None => {
+                let pixels = self.pixels();
+                let texture =
+                    Texture::from_pixels(gl, MAP_W, MAP_H, PixelFormat::Rgba8, &pixels, is_srgb)?;
+                self.image = Some(image_map.insert(texture));
+                self.region_count += 1;
+                self.uploaded_bytes += pixels.len() as u64;
+                return Ok(true);
+            }
+        };
+        let texture = match image_map.get(&image) {
+            Some(texture) => texture,
+            None => return Err("the minimap texture is gone".to_owned()),
+        };
+        let uploaded = !self.pending.is_empty();
+        for region in self.pending.drain(..) {
+            texture.update_region(
+                gl,
+                region.x,
+                region.y,
+                region.width,
+                region.height,
+                &region.pixels,
+            )?;
+            self.region_count += 1;
+            self.uploaded_bytes += region.pixels.len() as u64;
+        }
+        Ok(uploaded)
+    }
+}
+
+#[derive(WidgetCommon)]
+pub struct MinimapWidget<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    minimap: &'a mut MinimapState,
+}
+
+impl<'a> MinimapWidget<'a> {
+    pub fn new(minimap: &'a mut MinimapState) -> Self {
+        MinimapWidget {
+            common: widget::CommonBuilder::default(),
+            minimap,
+        }
+    }
+}
+
+widget_ids! {
+    pub struct MinimapIds {
+        walk_toggle,
+        stats,
+        map,
+    }
+}
+
+impl<'a> Widget for MinimapWidget<'a> {
+    type State = MinimapIds;
+    type Style = ();
+    /// Whether the walker is moving, in which case the UI needs to be
+    /// updated on every frame.
+    type Event = bool;
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        MinimapIds::new(id_gen)
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs {
+            id,
+            state,
+            ui,
+            rect,
+            ..
+        } = args;
+        let minimap = self.minimap;
+
+        const PAD: conrod_core::Scalar = 8.0;
+        const ROW_H: conrod_core::Scalar = 28.0;
+
+        for walking in widget::Toggle::new(minimap.walking)
+            .label("Walk")
+            .label_font_size(14)
+            .w_h(80.0, ROW_H)
+            .top_left_with_margin_on(id, PAD)
+            .set(state.walk_toggle, ui)
+        {
+            minimap.walking = walking;
+        }
+        let full_size = MAP_W as u64 * MAP_H as u64 * 4;
+        widget::Text::new(&format!(
+            "{} uploads, {} bytes in total. The whole map is {} bytes.",
+            minimap.region_count, minimap.uploaded_bytes, full_size
+        ))
+        .font_size(12)
+        .color(color::LIGHT_GREY)
+        .right_from(state.walk_toggle, PAD)
+        .set(state.stats, ui);
+
+        // Step by the time since the last update, so that the speed doesn't
+        // depend on how often the UI is updated.
+        if minimap.walking {
+            let now = now_us();
+            let last = *minimap.last_step.get_or_insert(now);
+            let steps = ((now - last) / 1_000_000.0 * STEPS_PER_SECOND) as u32;
+            for _ in 0..steps {
+                minimap.step();
+            }
+            minimap.last_step = Some(last + steps as f64 * 1_000_000.0 / STEPS_PER_SECOND);
+        } else {
+            minimap.last_step = None;
+        }
+
+        let image = match minimap.image {
+            Some(image) => image,
+            None => return minimap.walking,
+        };
+        let view_w = (rect.w() - PAD * 2.0).max(0.0);
+        let view_h = (rect.h() - ROW_H - PAD * 3.0).max(0.0);
+        let scale = (view_w / MAP_W as f64)
+            .min(view_h / MAP_H as f64)
+            .floor()
+            .max(1.0);
+        let (map_w, map_h) = (MAP_W as f64 * scale, MAP_H as f64 * scale);
+        widget::Image::new(image)
+            .w_h(map_w, map_h)
+            .down_from(state.walk_toggle, PAD)
+            .set(state.map, ui);
+
+        // Paint roads by dragging on the map.
+        if let Some(mouse) = ui.widget_input(state.map).mouse() {
+            if mouse.buttons.left().is_down() {
+                let [x, y] = mouse.rel_xy();
+                let tx = ((x + map_w / 2.0) / scale).floor();
+                let ty = ((map_h / 2.0 - y) / scale).floor();
+                if tx >= 0.0 && ty >= 0.0 && tx < MAP_W as f64 && ty < MAP_H as f64 {
+                    minimap.paint(tx as u32, ty as u32, ROAD);
+                }
+            }
+        }
+
+        minimap.walking
+    }
+}
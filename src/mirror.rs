@@ -0,0 +1,431 @@
+// Mirroring the UI to other instances over TCP, to show that what `fill`
+// prepares is enough to draw the UI elsewhere, as a thin client would.
+//
+// The instance started with `--mirror-serve` sends each frame it fills to the
+// viewers connected to it: the commands and vertices, and the whole glyph
+// cache texture whenever glyphs were added to it. Anyone who can connect can
+// read all that is drawn, so it listens on the loopback address unless
+// another one is given with `--mirror-serve=HOST:PORT`. An instance started
+// with `--mirror-connect=HOST:PORT` only draws the frames it receives with
+// its own `Renderer`, and its UI is left alone.
+//
+// The messages are a tag byte, the length of the rest as a `u32` and the
+// rest, little-endian. The rest of a frame is its `Frame::to_bytes`. Images
+// are only drawn if both sides have them in the list they are given, which
+// is only the Rust logo in the demo.
+
+use crate::bridge::{AppEvent, Bridge};
+use crate::conrod_glow::{Frame, GlyphPixels, Renderer};
+use conrod_core::image;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+const TAG_GLYPHS: u8 = 0;
+const TAG_FRAME: u8 = 1;
+/// Messages are refused beyond this many bytes, instead of allocating them.
+const MAX_MESSAGE_LEN: u32 = 64 * 1024 * 1024;
+/// A viewer which takes longer than this to take a frame is dropped.
+const WRITE_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// What a viewer receives, posted to the event loop through the `Bridge`.
+#[derive(Debug)]
+pub enum MirrorMessage {
+    Glyphs(GlyphPixels),
+    Frame(Frame),
+    Disconnected(String),
+}
+
+/// The messages waiting to be written to a viewer. Only the latest of each
+/// kind is kept, as a glyphs message has the whole glyph cache and a frame
+/// replaces the one before.
+#[derive(Default)]
+struct Outbox {
+    glyphs: Option<Arc<Vec<u8>>>,
+    frame: Option<Arc<Vec<u8>>>,
+    /// Set when the viewer is dropped, or by the writer when it fails.
+    closed: bool,
+}
+
+type SharedOutbox = Arc<(Mutex<Outbox>, Condvar)>;
+
+/// A connected viewer, written to by a thread of its own, so that a slow
+/// viewer doesn't hold up the event loop.
+struct Viewer {
+    outbox: SharedOutbox,
+    /// The version of the glyph cache last handed to the writer.
+    glyph_version: u64,
+}
+
+impl Viewer {
+    fn start(stream: TcpStream) -> Self {
+        let outbox = SharedOutbox::default();
+        let writer_outbox = outbox.clone();
+        std::thread::spawn(move || {
+            let mut stream = BufWriter::new(stream);
+            while let Some((glyphs, frame)) = next_messages(&writer_outbox) {
+                let written = glyphs
+                    .iter()
+                    .chain(&frame)
+                    .try_for_each(|message| stream.write_all(message))
+                    .and_then(|()| stream.flush());
+                if written.is_err() {
+                    if let Ok(mut outbox) = writer_outbox.0.lock() {
+                        outbox.closed = true;
+                    }
+                    return;
+                }
+            }
+        });
+        Viewer {
+            outbox,
+            glyph_version: 0,
+        }
+    }
+
+    /// Hand the messages to the writer, replacing any it hasn't taken yet.
+    /// Returns `false` if the viewer is gone.
+    fn post(&self, glyphs: Option<&Arc<Vec<u8>>>, frame: &Arc<Vec<u8>>) -> bool {
+        let (outbox, ready) = &*self.outbox;
+        let mut outbox = match outbox.lock() {
+            Ok(outbox) => outbox,
+            Err(_) => return false,
+        };
+        if outbox.closed {
+            return false;
+        }
+        if let Some(glyphs) = glyphs {
+            outbox.glyphs = Some(glyphs.clone());
+        }
+        outbox.frame = Some(frame.clone());
+        ready.notify_one();
+        true
+    }
+}
+
+impl Drop for Viewer {
+    fn drop(&mut self) {
+        let (outbox, ready) = &*self.outbox;
+        if let Ok(mut outbox) = outbox.lock() {
+            outbox.closed = true;
+        }
+        ready.notify_one();
+    }
+}
+
+/// Wait for messages to write to a viewer, the glyphs first. Returns `None`
+/// once the viewer is closed.
+fn next_messages(outbox: &SharedOutbox) -> Option<(Option<Arc<Vec<u8>>>, Option<Arc<Vec<u8>>>)> {
+    let (outbox, ready) = &**outbox;
+    let mut outbox = outbox.lock().ok()?;
+    while !outbox.closed && outbox.glyphs.is_none() && outbox.frame.is_none() {
+        outbox = ready.wait(outbox).ok()?;
+    }
+    if outbox.closed {
+        return None;
+    }
+    Some((outbox.glyphs.take(), outbox.frame.take()))
+}
+
+/// Sends the frames to the connected viewers.
+pub struct MirrorServer {
+    viewers: Arc<Mutex<Vec<Viewer>>>,
+    pub address: SocketAddr,
+}
+
+impl MirrorServer {
+    pub const DEFAULT_PORT: u16 = 7879;
+
+    /// The address to listen on from `--mirror-serve`, `--mirror-serve=PORT`
+    /// or `--mirror-serve=HOST:PORT` on the command line, if frames are to be
+    /// sent. Without a host it is the loopback address.
+    pub fn address_from_args(args: impl Iterator<Item = String>) -> Option<String> {
+        let mut address = None;
+        for arg in args {
+            if arg == "--mirror-serve" {
+                address = Some(format!("127.0.0.1:{}", Self::DEFAULT_PORT));
+            } else if let Some(value) = arg.strip_prefix("--mirror-serve=") {
+                address = Some(match value.parse::<u16>() {
+                    Ok(port) => format!("127.0.0.1:{}", port),
+                    Err(_) => value.to_owned(),
+                });
+            }
+        }
+        address
+    }
+
+    /// Listen for viewers on `address`. `renderer` starts keeping a copy of
+    /// its glyph cache for them, so start before the first `fill`.
+    pub fn start(address: &str, renderer: &Renderer) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        let address = listener.local_addr()?;
+        renderer.keep_glyph_pixels();
+        let viewers = Arc::new(Mutex::new(Vec::new()));
+        let new_viewers = viewers.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let _ = stream.set_nodelay(true);
+                let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+                if let Ok(mut viewers) = new_viewers.lock() {
+                    viewers.push(Viewer::start(stream));
+                }
+            }
+        });
+        Ok(MirrorServer { viewers, address })
+    }
+
+    /// Send what `renderer` was just filled with for a framebuffer of
+    /// `framebuffer` pixels. Only the encoding is done here; the writing is
+    /// left to the thread of each viewer, which skips the frames it is too
+    /// slow for. Viewers which failed to take a frame are dropped.
+    pub fn publish(&self, renderer: &Renderer, framebuffer: (u32, u32), images: &[image::Id]) {
+        let mut viewers = match self.viewers.lock() {
+            Ok(viewers) => viewers,
+            Err(_) => return,
+        };
+        let oldest_glyphs = match viewers.iter().map(|viewer| viewer.glyph_version).min() {
+            Some(version) => version,
+            None => return,
+        };
+        // The glyphs are encoded once for all the viewers which are behind.
+        let glyphs = renderer
+            .glyph_pixels(oldest_glyphs)
+            .map(|pixels| (pixels.version, Arc::new(encode_glyphs(&pixels))));
+        let frame = Arc::new(encode_frame(&renderer.export_frame(framebuffer, images)));
+        viewers.retain(|viewer| {
+            let glyphs = glyphs
+                .as_ref()
+                .filter(|&&(version, _)| viewer.glyph_version < version);
+            viewer.post(glyphs.map(|(_, glyphs)| glyphs), &frame)
+        });
+        if let Some((version, _)) = glyphs {
+            for viewer in viewers.iter_mut() {
+                viewer.glyph_version = version;
+            }
+        }
+    }
+}
+
+/// Receives the frames of a `MirrorServer`.
+pub struct MirrorViewer {
+    /// The last frame received, loaded again when the window changes size.
+    frame: Option<Frame>,
+    pub address: String,
+}
+
+impl MirrorViewer {
+    /// The address from `--mirror-connect=HOST:PORT` on the command line, if
+    /// this instance is to be a viewer.
+    pub fn address_from_args(args: impl Iterator<Item = String>) -> Option<String> {
+        args.filter_map(|arg| arg.strip_prefix("--mirror-connect=").map(str::to_owned))
+            .last()
+    }
+
+    /// Connect to `address`, posting the messages received to `bridge` as
+    /// `AppEvent::Mirror`.
+    pub fn connect(address: &str, bridge: Bridge) -> io::Result<Self> {
+        let stream = TcpStream::connect(address)?;
+        let _ = stream.set_nodelay(true);
+        std::thread::spawn(move || {
+            let mut reader = BufReader::new(stream);
+            loop {
+                let message = match read_message(&mut reader) {
+                    Ok(message) => message,
+                    Err(err) => {
+                        bridge.send(AppEvent::Mirror(MirrorMessage::Disconnected(
+                            err.to_string(),
+                        )));
+                        return;
+                    }
+                };
+                if !bridge.send(AppEvent::Mirror(message)) {
+                    return;
+                }
+            }
+        });
+        Ok(MirrorViewer {
+            frame: None,
+            address: address.to_owned(),
+        })
+    }
+
+    /// Take in the messages received since the last call, loading the glyphs
+    /// into `renderer` right away. Returns whether there is a new frame.
+    pub fn receive(
+        &mut self,
+        gl: &glow::Context,
+        renderer: &mut Renderer,
+        messages: Vec<MirrorMessage>,
+    ) -> Result<bool, String> {
+        let mut new_frame = false;
+        for message in messages {
+            match message {
+                MirrorMessage::Glyphs(pixels) => renderer.load_glyph_pixels(gl, &pixels)?,
+                MirrorMessage::Frame(frame) => {
+                    // Only the last one is drawn.
+                    self.frame = Some(frame);
+                    new_frame = true;
+                }
+                MirrorMessage::Disconnected(err) => {
+                    return Err(format!("Lost the mirror at {}: {}", self.address, err))
+                }
+            }
+        }
+        Ok(new_frame)
+    }
+
+    /// Load the last frame into `renderer` for a framebuffer of `framebuffer`
+    /// pixels. Returns `false` if there is no frame yet.
+    pub fn load(
+        &self,
+        renderer: &mut Renderer,
+        framebuffer: (u32, u32),
+        images: &[image::Id],
+    ) -> bool {
+        match &self.frame {
+            Some(frame) => {
+                renderer.load_frame(frame, framebuffer, images);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+fn put_u32(buf: &mut Vec<u8>, value: u32) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+/// Start a message, with room for the length which `finish` fills in.
+fn begin(tag: u8, capacity: usize) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(5 + capacity);
+    buf.push(tag);
+    put_u32(&mut buf, 0);
+    buf
+}
+
+fn finish(mut buf: Vec<u8>) -> Vec<u8> {
+    let len = (buf.len() - 5) as u32;
+    buf[1..5].copy_from_slice(&len.to_le_bytes());
+    buf
+}
+
+fn encode_glyphs(pixels: &GlyphPixels) -> Vec<u8> {
+    let mut buf = begin(TAG_GLYPHS, 8 + pixels.data.len());
+    put_u32(&mut buf, pixels.width);
+    put_u32(&mut buf, pixels.height);
+    buf.extend_from_slice(&pixels.data);
+    finish(buf)
+}
+
+fn encode_frame(frame: &[u8]) -> Vec<u8> {
+    let mut buf = begin(TAG_FRAME, frame.len());
+    buf.extend_from_slice(frame);
+    finish(buf)
+}
+
+/// The body of a message, read from the front.
+struct Body<'a>(&'a [u8]);
+
+impl<'a> Body<'a> {
+    fn bytes(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        if self.0.len() < len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "message ends early",
+            ));
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(self.bytes(4)?);
+        Ok(u32::from_le_bytes(bytes))
+    }
+}
+
+fn read_message(reader: &mut impl Read) -> io::Result<MirrorMessage> {
+    let mut header = [0; 5];
+    reader.read_exact(&mut header)?;
+    let mut len = [0; 4];
+    len.copy_from_slice(&header[1..]);
+    let len = u32::from_le_bytes(len);
+    if len > MAX_MESSAGE_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("message of {} bytes", len),
+        ));
+    }
+    let mut data = vec![0; len as usize];
+    reader.read_exact(&mut data)?;
+    let mut body = Body(&data);
+    match header[0] {
+        TAG_GLYPHS => {
+            let width = body.u32()?;
+            let height = body.u32()?;
+            // One byte a pixel.
+            if (width as usize).checked_mul(height as usize) != Some(body.0.len()) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{} bytes of glyphs for {}x{}", body.0.len(), width, height),
+                ));
+            }
+            Ok(MirrorMessage::Glyphs(GlyphPixels {
+                version: 0,
+                width,
+                height,
+                data: body.0.to_vec(),
+            }))
+        }
+        TAG_FRAME => {
+            let frame = Frame::from_bytes(body.0)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            Ok(MirrorMessage::Frame(frame))
+        }
+        tag => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unknown message {}", tag),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glyphs(width: u32, height: u32, len: usize) -> Vec<u8> {
+        encode_glyphs(&GlyphPixels {
+            version: 1,
+            width,
+            height,
+            data: vec![7; len],
+        })
+    }
+
+    #[test]
+    fn reads_back_glyphs() {
+        match read_message(&mut &glyphs(4, 2, 8)[..]).unwrap() {
+            MirrorMessage::Glyphs(pixels) => {
+                assert_eq!((pixels.width, pixels.height), (4, 2));
+                assert_eq!(pixels.data, [7; 8]);
+            }
+            other => panic!("read {:?}", other),
+        }
+    }
+
+    #[test]
+    fn turns_down_glyphs_of_the_wrong_size() {
+        for message in &[
+            glyphs(4, 2, 7),
+            glyphs(4, 2, 9),
+            glyphs(u32::MAX, u32::MAX, 1),
+        ] {
+            let err = read_message(&mut &message[..]).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+}
@@ -0,0 +1,48 @@
+// App actions bound to the extra mouse buttons.
+//
+// conrod only knows about the buttons up to `X2` and the conversion macros
+// drop winit's `MouseButton::Other`, whose numbering differs between
+// platforms anyway. Instead, the back and forward buttons are looked up here
+// by their winit number, which the user can change in the Settings window.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MouseAction {
+    Back,
+    Forward,
+}
+
+/// The numbers of `winit::event::MouseButton::Other` bound to each action.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MouseBindings {
+    pub back: u8,
+    pub forward: u8,
+}
+
+impl Default for MouseBindings {
+    fn default() -> Self {
+        // What winit reports for the usual side buttons.
+        let (back, forward) = if cfg!(target_arch = "wasm32") {
+            (0, 1)
+        } else if cfg!(target_os = "windows") {
+            (1, 2)
+        } else if cfg!(target_os = "macos") {
+            (3, 4)
+        } else {
+            (8, 9)
+        };
+        MouseBindings { back, forward }
+    }
+}
+
+impl MouseBindings {
+    pub fn action_for(&self, button: winit::event::MouseButton) -> Option<MouseAction> {
+        match button {
+            winit::event::MouseButton::Other(n) if n == self.back => Some(MouseAction::Back),
+            winit::event::MouseButton::Other(n) if n == self.forward => Some(MouseAction::Forward),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,312 @@
+// A node graph on a canvas which can be panned by dragging the background
+// and zoomed with the scroll wheel.
+//
+// The renderer has no transform of its own, so the pan and zoom are applied
+// to the positions here. The nodes and wires are kids of a cropping canvas,
+// which clips them with the scissor rect. The wires are cubic bezier curves
+// tessellated into a single `Triangles` widget.
+
+use conrod_core::widget::triangles::Triangle;
+use conrod_core::{
+    color, widget, widget_ids, Borderable, Colorable, Labelable, Point, Positionable, Sizeable,
+    Widget, WidgetCommon,
+};
+use serde::{Deserialize, Serialize};
+
+const NODE_W: f64 = 120.0;
+const NODE_H: f64 = 60.0;
+const WIRE_WIDTH: f64 = 2.0;
+const WIRE_SEGMENTS: usize = 32;
+const MIN_ZOOM: f64 = 0.25;
+const MAX_ZOOM: f64 = 4.0;
+
+#[derive(Serialize, Deserialize)]
+struct Node {
+    title: String,
+    /// The middle of the node in graph coordinates.
+    pos: Point,
+}
+
+/// The graph and the view are saved in sessions.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct NodeGraphState {
+    nodes: Vec<Node>,
+    /// Wires from the output of a node to the input of another, by index.
+    wires: Vec<(usize, usize)>,
+    /// The offset of the graph origin from the middle of the canvas.
+    pan: Point,
+    zoom: f64,
+}
+
+impl Default for NodeGraphState {
+    fn default() -> Self {
+        NodeGraphState::new()
+    }
+}
+
+impl NodeGraphState {
+    pub fn new() -> Self {
+        let mut graph = NodeGraphState {
+            nodes: Vec::new(),
+            wires: Vec::new(),
+            pan: [0.0, 0.0],
+            zoom: 1.0,
+        };
+        // A few columns of nodes, each wired to two in the next column.
+        const COLUMNS: usize = 4;
+        const ROWS: usize = 4;
+        for col in 0..COLUMNS {
+            for row in 0..ROWS {
+                graph.add_node([
+                    (col as f64 - 1.5) * NODE_W * 1.8,
+                    (1.5 - row as f64) * NODE_H * 1.6,
+                ]);
+                if col > 0 {
+                    let node = col * ROWS + row;
+                    let prev = (col - 1) * ROWS;
+                    graph.wires.push((prev + row, node));
+                    graph.wires.push((prev + (row + 1) % ROWS, node));
+                }
+            }
+        }
+        graph
+    }
+
+    /// Fix up values which may come from an edited session.
+    pub fn normalize(&mut self) {
+        let count = self.nodes.len();
+        self.wires.retain(|&(from, to)| from < count && to < count);
+        self.zoom = self.zoom.max(MIN_ZOOM).min(MAX_ZOOM);
+    }
+
+    fn add_node(&mut self, pos: Point) {
+        let title = format!("Node {}", self.nodes.len() + 1);
+        self.nodes.push(Node { title, pos });
+    }
+
+    /// From graph coordinates to coordinates relative to the middle of the
+    /// canvas.
+    fn to_view(&self, pos: Point) -> Point {
+        [
+            self.pan[0] + pos[0] * self.zoom,
+            self.pan[1] + pos[1] * self.zoom,
+        ]
+    }
+
+    /// Zoom by `factor` while keeping the point at `anchor`, relative to the
+    /// middle of the canvas, in place.
+    fn zoom_at(&mut self, factor: f64, anchor: Point) {
+        let zoom = (self.zoom * factor).max(MIN_ZOOM).min(MAX_ZOOM);
+        let graph_pos = [
+            (anchor[0] - self.pan[0]) / self.zoom,
+            (anchor[1] - self.pan[1]) / self.zoom,
+        ];
+        self.pan = [
+            anchor[0] - graph_pos[0] * zoom,
+            anchor[1] - graph_pos[1] * zoom,
+        ];
+        self.zoom = zoom;
+    }
+}
+
+fn bezier(p: [Point; 4], t: f64) -> Point {
+    let u = 1.0 - t;
+    let (a, b, c, d) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+    [
+        a * p[0][0] + b * p[1][0] + c * p[2][0] + d * p[3][0],
+        a * p[0][1] + b * p[1][1] + c * p[2][1] + d * p[3][1],
+    ]
+}
+
+/// Tessellate a wire between two absolute points into quads along the curve.
+fn wire_triangles(from: Point, to: Point, zoom: f64, triangles: &mut Vec<Triangle<Point>>) {
+    let bend = (to[0] - from[0]).abs().max(40.0 * zoom) / 2.0;
+    let curve = [from, [from[0] + bend, from[1]], [to[0] - bend, to[1]], to];
+    let r = WIRE_WIDTH / 2.0;
+    let mut prev = from;
+    for i in 1..=WIRE_SEGMENTS {
+        let next = bezier(curve, i as f64 / WIRE_SEGMENTS as f64);
+        let (dx, dy) = (next[0] - prev[0], next[1] - prev[1]);
+        let len = (dx * dx + dy * dy).sqrt();
+        if len > 0.0 {
+            let n = [-dy / len * r, dx / len * r];
+            let a1 = [prev[0] + n[0], prev[1] + n[1]];
+            let a2 = [prev[0] - n[0], prev[1] - n[1]];
+            let b1 = [next[0] + n[0], next[1] + n[1]];
+            let b2 = [next[0] - n[0], next[1] - n[1]];
+            triangles.push(Triangle([a1, a2, b1]));
+            triangles.push(Triangle([a2, b2, b1]));
+        }
+        prev = next;
+    }
+}
+
+#[derive(WidgetCommon)]
+pub struct NodeGraphWidget<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    graph: &'a mut NodeGraphState,
+}
+
+impl<'a> NodeGraphWidget<'a> {
+    pub fn new(graph: &'a mut NodeGraphState) -> Self {
+        NodeGraphWidget {
+            common: widget::CommonBuilder::default(),
+            graph,
+        }
+    }
+}
+
+widget_ids! {
+    pub struct NodeGraphIds {
+        add_button,
+        reset_button,
+        zoom_text,
+        canvas,
+        wires,
+        nodes[],
+        titles[],
+    }
+}
+
+impl<'a> Widget for NodeGraphWidget<'a> {
+    type State = NodeGraphIds;
+    type Style = ();
+    type Event = ();
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        NodeGraphIds::new(id_gen)
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs {
+            id,
+            state,
+            ui,
+            rect,
+            ..
+        } = args;
+        let graph = self.graph;
+
+        const PAD: conrod_core::Scalar = 8.0;
+        const ROW_H: conrod_core::Scalar = 28.0;
+        // How much one point of scrolling zooms.
+        const ZOOM_PER_SCROLL: f64 = 0.005;
+
+        for _click in widget::Button::new()
+            .label("Add node")
+            .label_font_size(14)
+            .w_h(90.0, ROW_H)
+            .top_left_with_margin_on(id, PAD)
+            .set(state.add_button, ui)
+        {
+            // In the middle of the view.
+            let pos = [-graph.pan[0] / graph.zoom, -graph.pan[1] / graph.zoom];
+            graph.add_node(pos);
+        }
+        for _click in widget::Button::new()
+            .label("Reset view")
+            .label_font_size(14)
+            .w_h(100.0, ROW_H)
+            .right_from(state.add_button, PAD)
+            .set(state.reset_button, ui)
+        {
+            graph.pan = [0.0, 0.0];
+            graph.zoom = 1.0;
+        }
+
+        let canvas_w = (rect.w() - PAD * 2.0).max(0.0);
+        let canvas_h = (rect.h() - ROW_H - PAD * 3.0).max(0.0);
+        widget::Canvas::new()
+            .color(color::CHARCOAL)
+            .border(0.0)
+            .crop_kids()
+            .w_h(canvas_w, canvas_h)
+            .down_from(state.add_button, PAD)
+            .set(state.canvas, ui);
+        let canvas = match ui.rect_of(state.canvas) {
+            Some(canvas) => canvas,
+            None => return,
+        };
+
+        if state.nodes.len() < graph.nodes.len() {
+            let id_gen = &mut ui.widget_id_generator();
+            let len = graph.nodes.len();
+            state.update(|state| {
+                state.nodes.resize(len, id_gen);
+                state.titles.resize(len, id_gen);
+            });
+        }
+
+        // Pan by dragging the background and zoom with the scroll wheel over
+        // the canvas or any node.
+        for drag in ui.widget_input(state.canvas).drags().left() {
+            graph.pan[0] += drag.delta_xy[0];
+            graph.pan[1] += drag.delta_xy[1];
+        }
+        let mut scroll = 0.0;
+        for &widget_id in std::iter::once(&state.canvas).chain(state.nodes.iter()) {
+            for event in ui.widget_input(widget_id).scrolls() {
+                scroll += event.y;
+            }
+        }
+        if scroll != 0.0 {
+            let mouse = ui.global_input().current.mouse.xy;
+            let anchor = [mouse[0] - canvas.x(), mouse[1] - canvas.y()];
+            graph.zoom_at((-scroll * ZOOM_PER_SCROLL).exp(), anchor);
+        }
+        for (i, &node_id) in state.nodes.iter().enumerate().take(graph.nodes.len()) {
+            for drag in ui.widget_input(node_id).drags().left() {
+                let node = &mut graph.nodes[i];
+                node.pos[0] += drag.delta_xy[0] / graph.zoom;
+                node.pos[1] += drag.delta_xy[1] / graph.zoom;
+            }
+        }
+
+        // From the right side of a node to the left side of another.
+        let mut triangles = Vec::new();
+        let origin = canvas.xy();
+        let abs = |xy: Point| [origin[0] + xy[0], origin[1] + xy[1]];
+        for &(from, to) in &graph.wires {
+            let from = graph.to_view(graph.nodes[from].pos);
+            let to = graph.to_view(graph.nodes[to].pos);
+            let half_w = NODE_W / 2.0 * graph.zoom;
+            wire_triangles(
+                abs([from[0] + half_w, from[1]]),
+                abs([to[0] - half_w, to[1]]),
+                graph.zoom,
+                &mut triangles,
+            );
+        }
+        widget::Triangles::single_color(color::LIGHT_BLUE, triangles)
+            .with_bounding_rect(canvas)
+            .parent(state.canvas)
+            .graphics_for(state.canvas)
+            .set(state.wires, ui);
+
+        let font_size = ((14.0 * graph.zoom).round() as u32).max(1);
+        for (i, node) in graph.nodes.iter().enumerate() {
+            let [x, y] = graph.to_view(node.pos);
+            widget::Rectangle::fill([NODE_W * graph.zoom, NODE_H * graph.zoom])
+                .color(color::DARK_GREY)
+                .x_y_relative_to(state.canvas, x, y)
+                .parent(state.canvas)
+                .set(state.nodes[i], ui);
+            widget::Text::new(&node.title)
+                .font_size(font_size)
+                .color(color::WHITE)
+                .middle_of(state.nodes[i])
+                .graphics_for(state.nodes[i])
+                .set(state.titles[i], ui);
+        }
+
+        widget::Text::new(&format!("Zoom: {:.0}%", graph.zoom * 100.0))
+            .font_size(12)
+            .color(color::LIGHT_GREY)
+            .right_from(state.reset_button, PAD)
+            .set(state.zoom_text, ui);
+    }
+}
@@ -0,0 +1,252 @@
+// A plain text notes window which can open and save text files.
+
+use crate::bridge::Bridge;
+use crate::file_dialog::{self, FilePurpose};
+use crate::palette::Palette;
+use conrod_core::{
+    widget, widget_ids, Colorable, Labelable, Positionable, Sizeable, Widget, WidgetCommon,
+};
+
+/// A document which has been replaced by opening another file, kept for
+/// going back to it.
+struct Document {
+    text: String,
+    file_name: Option<String>,
+}
+
+pub struct NotesState {
+    pub text: String,
+    /// The name of the file the text was last opened from or saved to.
+    pub file_name: Option<String>,
+    back: Vec<Document>,
+    forward: Vec<Document>,
+}
+
+impl Default for NotesState {
+    fn default() -> Self {
+        NotesState::new()
+    }
+}
+
+impl NotesState {
+    pub fn new() -> Self {
+        NotesState {
+            text: String::new(),
+            file_name: None,
+            back: Vec::new(),
+            forward: Vec::new(),
+        }
+    }
+
+    /// Replace the text with the contents of an opened file.
+    ///
+    /// The current text is kept in the history, including any unsaved
+    /// changes.
+    pub fn load(&mut self, name: String, data: Vec<u8>) {
+        let previous = self.replace(Document {
+            text: String::from_utf8_lossy(&data).into_owned(),
+            file_name: Some(name),
+        });
+        self.back.push(previous);
+        self.forward.clear();
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        !self.back.is_empty()
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        !self.forward.is_empty()
+    }
+
+    /// Go back to the document which was open before the last `load`.
+    pub fn go_back(&mut self) {
+        if let Some(document) = self.back.pop() {
+            let current = self.replace(document);
+            self.forward.push(current);
+        }
+    }
+
+    pub fn go_forward(&mut self) {
+        if let Some(document) = self.forward.pop() {
+            let current = self.replace(document);
+            self.back.push(current);
+        }
+    }
+
+    fn replace(&mut self, document: Document) -> Document {
+        Document {
+            text: std::mem::replace(&mut self.text, document.text),
+            file_name: std::mem::replace(&mut self.file_name, document.file_name),
+        }
+    }
+
+    fn suggested_file_name(&self) -> &str {
+        self.file_name
+            .as_ref()
+            .and_then(|name| std::path::Path::new(name).file_name())
+            .and_then(|name| name.to_str())
+            .unwrap_or("notes.txt")
+    }
+}
+
+#[derive(WidgetCommon)]
+pub struct NotesWidget<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    notes: &'a mut NotesState,
+    bridge: &'a Bridge,
+    palette: Palette,
+}
+
+impl<'a> NotesWidget<'a> {
+    pub fn new(notes: &'a mut NotesState, bridge: &'a Bridge) -> Self {
+        NotesWidget {
+            common: widget::CommonBuilder::default(),
+            notes,
+            bridge,
+            palette: Palette::DARK,
+        }
+    }
+
+    pub fn palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+}
+
+widget_ids! {
+    pub struct NotesIds {
+        background,
+        open,
+        save,
+        back,
+        forward,
+        file_name,
+        canvas,
+        text,
+    }
+}
+
+impl<'a> Widget for NotesWidget<'a> {
+    type State = NotesIds;
+    type Style = ();
+    type Event = ();
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        NotesIds::new(id_gen)
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs {
+            id,
+            state,
+            ui,
+            rect,
+            ..
+        } = args;
+        let NotesWidget {
+            notes,
+            bridge,
+            palette,
+            ..
+        } = self;
+
+        const PAD: conrod_core::Scalar = 8.0;
+        const ROW_H: conrod_core::Scalar = 28.0;
+        const BUTTON_W: conrod_core::Scalar = 80.0;
+
+        widget::Rectangle::fill(rect.dim())
+            .color(palette.background)
+            .middle_of(id)
+            .graphics_for(id)
+            .set(state.background, ui);
+        if widget::Button::new()
+            .label("Open...")
+            .label_font_size(14)
+            .color(palette.control)
+            .label_color(palette.control_text)
+            .w_h(BUTTON_W, ROW_H)
+            .top_left_with_margin_on(id, PAD)
+            .set(state.open, ui)
+            .was_clicked()
+        {
+            file_dialog::open_file(bridge, FilePurpose::Notes);
+        }
+        if widget::Button::new()
+            .label("Save...")
+            .label_font_size(14)
+            .color(palette.control)
+            .label_color(palette.control_text)
+            .w_h(BUTTON_W, ROW_H)
+            .right_from(state.open, PAD)
+            .set(state.save, ui)
+            .was_clicked()
+        {
+            file_dialog::save_file(
+                bridge,
+                FilePurpose::Notes,
+                notes.suggested_file_name(),
+                notes.text.as_bytes(),
+            );
+        }
+        // Dimmed when there is nowhere to go, since buttons cannot be
+        // disabled.
+        let history_button = |label, enabled| {
+            widget::Button::new()
+                .label(label)
+                .label_font_size(14)
+                .color(if enabled {
+                    palette.control
+                } else {
+                    palette.dimmed_control
+                })
+                .label_color(palette.control_text)
+                .w_h(ROW_H, ROW_H)
+        };
+        if history_button("<", notes.can_go_back())
+            .right_from(state.save, PAD)
+            .set(state.back, ui)
+            .was_clicked()
+        {
+            notes.go_back();
+        }
+        if history_button(">", notes.can_go_forward())
+            .right_from(state.back, 0.0)
+            .set(state.forward, ui)
+            .was_clicked()
+        {
+            notes.go_forward();
+        }
+        widget::Text::new(notes.file_name.as_deref().unwrap_or("(unsaved)"))
+            .font_size(12)
+            .color(palette.text)
+            .right_from(state.forward, PAD)
+            .set(state.file_name, ui);
+
+        widget::Canvas::new()
+            .color(palette.field)
+            .w_h(
+                rect.w() - PAD * 2.0,
+                (rect.h() - ROW_H - PAD * 3.0).max(0.0),
+            )
+            .down_from(state.open, PAD)
+            .align_left_of(state.open)
+            .scroll_kids_vertically()
+            .set(state.canvas, ui);
+        if let Some(text) = widget::TextEdit::new(&notes.text)
+            .font_size(14)
+            .color(palette.field_text)
+            .padded_w_of(state.canvas, PAD)
+            .mid_top_with_margin_on(state.canvas, PAD)
+            .left_justify()
+            .line_spacing(2.5)
+            .restrict_to_height(false)
+            .set(state.text, ui)
+        {
+            notes.text = text;
+        }
+    }
+}
@@ -0,0 +1,170 @@
+// Toast notifications shown in the bottom-right corner of the backdrop.
+
+use conrod_core::{
+    color, widget, widget_ids, Colorable, Labelable, Positionable, Sizeable, Widget, WidgetCommon,
+};
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Level {
+    Info,
+    Error,
+}
+
+pub struct Notification {
+    /// Identifies the notification for `dismiss_id`, as the indices change.
+    pub id: u64,
+    pub level: Level,
+    pub text: String,
+}
+
+/// The list of notifications which haven't been dismissed yet.
+pub struct Notifications {
+    items: Vec<Notification>,
+    next_id: u64,
+    /// The info notifications which should time out, until `take_timeouts`.
+    timeouts: Vec<u64>,
+}
+
+impl Default for Notifications {
+    fn default() -> Self {
+        Notifications::new()
+    }
+}
+
+impl Notifications {
+    /// The maximum number of notifications kept at once. The oldest ones are
+    /// dropped first.
+    const MAX_ITEMS: usize = 5;
+    /// How long the info notifications are shown for.
+    pub const TIMEOUT: Duration = Duration::from_secs(5);
+
+    pub fn new() -> Self {
+        Notifications {
+            items: Vec::new(),
+            next_id: 0,
+            timeouts: Vec::new(),
+        }
+    }
+
+    pub fn info(&mut self, text: impl Into<String>) {
+        self.push(Level::Info, text.into());
+    }
+
+    pub fn error(&mut self, text: impl Into<String>) {
+        self.push(Level::Error, text.into());
+    }
+
+    pub fn push(&mut self, level: Level, text: String) {
+        if self.items.len() >= Self::MAX_ITEMS {
+            self.items.remove(0);
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        // Errors stay until they are dismissed, so that they aren't missed.
+        if level == Level::Info {
+            self.timeouts.push(id);
+        }
+        self.items.push(Notification { id, level, text });
+    }
+
+    /// The ids of the new notifications which go away by themselves, for
+    /// the caller to time out.
+    pub fn take_timeouts(&mut self) -> Vec<u64> {
+        std::mem::take(&mut self.timeouts)
+    }
+
+    pub fn items(&self) -> &[Notification] {
+        &self.items
+    }
+
+    pub fn dismiss(&mut self, index: usize) {
+        if index < self.items.len() {
+            self.items.remove(index);
+        }
+    }
+
+    pub fn dismiss_id(&mut self, id: u64) {
+        self.items.retain(|item| item.id != id);
+    }
+}
+
+/// Displays the notifications as a stack of toasts, newest at the bottom.
+#[derive(WidgetCommon)]
+pub struct NotificationsWidget<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    notifications: &'a mut Notifications,
+}
+
+impl<'a> NotificationsWidget<'a> {
+    pub fn new(notifications: &'a mut Notifications) -> Self {
+        NotificationsWidget {
+            common: widget::CommonBuilder::default(),
+            notifications,
+        }
+    }
+}
+
+widget_ids! {
+    pub struct NotificationIds {
+        toasts[],
+    }
+}
+
+impl<'a> Widget for NotificationsWidget<'a> {
+    type State = NotificationIds;
+    type Style = ();
+    type Event = ();
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        NotificationIds::new(id_gen)
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    // Only the toasts themselves should block the widgets underneath.
+    fn is_over(&self) -> widget::IsOverFn {
+        |_, _, _| widget::IsOver::Bool(false)
+    }
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs { id, state, ui, .. } = args;
+        let notifications = self.notifications;
+
+        const TOAST_W: conrod_core::Scalar = 320.0;
+        const TOAST_H: conrod_core::Scalar = 36.0;
+        const GAP: conrod_core::Scalar = 6.0;
+
+        let count = notifications.items().len();
+        if state.toasts.len() < count {
+            let id_gen = &mut ui.widget_id_generator();
+            state.update(|state| state.toasts.resize(count, id_gen));
+        }
+
+        let mut dismissed = None;
+        for (i, item) in notifications.items().iter().enumerate() {
+            let color = match item.level {
+                Level::Info => color::DARK_CHARCOAL,
+                Level::Error => color::DARK_RED,
+            };
+            let from_bottom = (count - 1 - i) as conrod_core::Scalar * (TOAST_H + GAP);
+            // Clicking a toast dismisses it.
+            if widget::Button::new()
+                .label(&item.text)
+                .label_font_size(13)
+                .label_color(color::WHITE)
+                .color(color)
+                .w_h(TOAST_W, TOAST_H)
+                .bottom_right_with_margins_on(id, GAP + from_bottom, GAP)
+                .set(state.toasts[i], ui)
+                .was_clicked()
+            {
+                dismissed = Some(i);
+            }
+        }
+        if let Some(i) = dismissed {
+            notifications.dismiss(i);
+        }
+    }
+}
@@ -0,0 +1,104 @@
+// Color palettes which can be picked for a single window from its context
+// menu, to compare them side by side with the other windows.
+//
+// conrod's `Theme` belongs to the whole `Ui` and can't be swapped while the
+// widgets are being set, so `set_widgets` hands the palette of each window to
+// its content widget instead. Only the widgets which take a `Palette` follow
+// it; the others keep the colors of the theme.
+
+use conrod_core::color::{self, Color};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Palette {
+    pub name: &'static str,
+    pub background: Color,
+    pub text: Color,
+    /// Buttons, toggles and sliders.
+    pub control: Color,
+    pub control_text: Color,
+    /// Controls which do nothing at the moment, since buttons can't be
+    /// disabled.
+    pub dimmed_control: Color,
+    /// Text fields.
+    pub field: Color,
+    pub field_text: Color,
+}
+
+impl Palette {
+    /// The colors of `conrod_example_shared::theme`, used by the windows
+    /// without an override.
+    pub const DARK: Palette = Palette {
+        name: "Dark",
+        background: color::DARK_CHARCOAL,
+        text: color::LIGHT_GREY,
+        control: color::LIGHT_CHARCOAL,
+        control_text: color::WHITE,
+        dimmed_control: color::DARK_CHARCOAL,
+        field: color::WHITE,
+        field_text: color::BLACK,
+    };
+    pub const LIGHT: Palette = Palette {
+        name: "Light",
+        background: color::LIGHT_GREY,
+        text: color::DARK_CHARCOAL,
+        control: color::WHITE,
+        control_text: color::BLACK,
+        dimmed_control: color::GREY,
+        field: color::WHITE,
+        field_text: color::BLACK,
+    };
+    pub const HIGH_CONTRAST: Palette = Palette {
+        name: "High contrast",
+        background: color::BLACK,
+        text: color::WHITE,
+        control: color::BLACK,
+        control_text: color::YELLOW,
+        dimmed_control: color::DARK_GREY,
+        field: color::BLACK,
+        field_text: color::WHITE,
+    };
+
+    pub const ALL: [Palette; 3] = [Palette::DARK, Palette::LIGHT, Palette::HIGH_CONTRAST];
+}
+
+/// The palettes picked for some of the windows, by title. They only last
+/// for the session.
+pub struct PaletteOverrides {
+    overrides: Vec<(&'static str, Palette)>,
+}
+
+impl Default for PaletteOverrides {
+    fn default() -> Self {
+        PaletteOverrides::new()
+    }
+}
+
+impl PaletteOverrides {
+    pub fn new() -> Self {
+        PaletteOverrides {
+            overrides: Vec::new(),
+        }
+    }
+
+    /// The palette picked for the window, if any.
+    pub fn get(&self, title: &str) -> Option<Palette> {
+        self.overrides
+            .iter()
+            .find(|(t, _)| *t == title)
+            .map(|&(_, palette)| palette)
+    }
+
+    /// The palette of the window's content.
+    pub fn of(&self, title: &str) -> Palette {
+        self.get(title).unwrap_or(Palette::DARK)
+    }
+
+    /// Pick a palette for the window, or go back to the default one with
+    /// `None`.
+    pub fn set(&mut self, title: &'static str, palette: Option<Palette>) {
+        self.overrides.retain(|(t, _)| *t != title);
+        if let Some(palette) = palette {
+            self.overrides.push((title, palette));
+        }
+    }
+}
@@ -0,0 +1,72 @@
+// Pen and touch input with pressure.
+//
+// conrod's `Touch` input has no room for the force reported by winit, so
+// `v023_convert_pen` converts touch events a second time into a `PenInput`
+// which keeps it. The touch events still go to conrod as usual.
+
+use conrod_core::input::touch::Phase;
+use conrod_core::Point;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PenInput {
+    pub phase: Phase,
+    /// Identifies the finger or pen for the duration of the touch.
+    pub id: u64,
+    /// In the same coordinates as conrod's input, from the middle of the
+    /// window with y up.
+    pub xy: Point,
+    /// From 0.0 to 1.0, or `None` if the device doesn't report pressure,
+    /// like most touch screens.
+    pub pressure: Option<f64>,
+    /// The angle between the pen and the surface in radians, where π/2 is
+    /// perpendicular. Only reported for the Apple Pencil.
+    pub altitude: Option<f64>,
+}
+
+/// Converts a `winit::event::WindowEvent::Touch` to a `PenInput`, keeping
+/// the pressure and tilt.
+///
+/// Expects a `winit::event::WindowEvent` and a reference to a
+/// `winit::window::Window`, like `v023_convert_window_event`, and returns an
+/// `Option<PenInput>`.
+///
+/// Requires that both the `conrod_core` and `winit` crates are in the crate
+/// root.
+#[macro_export]
+macro_rules! v023_convert_pen {
+    ($event:expr, $window:expr) => {{
+        match $event {
+            winit::event::WindowEvent::Touch(winit::event::Touch {
+                phase,
+                location,
+                id,
+                force,
+                ..
+            }) => {
+                let scale_factor: f64 = $window.scale_factor();
+                let (win_w, win_h): (f64, f64) =
+                    $window.inner_size().to_logical::<f64>(scale_factor).into();
+                let winit::dpi::LogicalPosition { x, y } = location.to_logical::<f64>(scale_factor);
+                let phase = match phase {
+                    winit::event::TouchPhase::Started => conrod_core::input::touch::Phase::Start,
+                    winit::event::TouchPhase::Moved => conrod_core::input::touch::Phase::Move,
+                    winit::event::TouchPhase::Cancelled => conrod_core::input::touch::Phase::Cancel,
+                    winit::event::TouchPhase::Ended => conrod_core::input::touch::Phase::End,
+                };
+                let force: Option<winit::event::Force> = force.clone();
+                let altitude = match force {
+                    Some(winit::event::Force::Calibrated { altitude_angle, .. }) => altitude_angle,
+                    _ => None,
+                };
+                Some($crate::pen::PenInput {
+                    phase,
+                    id: id.clone(),
+                    xy: [x - win_w / 2.0, -(y - win_h / 2.0)],
+                    pressure: force.map(|force| force.normalized()),
+                    altitude,
+                })
+            }
+            _ => None,
+        }
+    }};
+}
@@ -0,0 +1,257 @@
+// Opt-in timing of each window's widget set-up, shown in the "Performance"
+// window.
+
+use crate::trace::now_us;
+use conrod_core::{
+    color, widget, widget_ids, Colorable, Labelable, Positionable, Sizeable, Widget, WidgetCommon,
+};
+
+/// Timings of one window, in microseconds.
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub name: &'static str,
+    pub last: f64,
+    /// An exponential moving average, so that a single slow frame doesn't
+    /// hide the steady state.
+    pub average: f64,
+    pub max: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortBy {
+    Name,
+    Last,
+    Average,
+    Max,
+}
+
+/// Started by `Profiler::start` and passed back to `Profiler::record`.
+///
+/// This is `None` when profiling is disabled, so it costs nothing then.
+#[must_use]
+pub struct Timer(Option<f64>);
+
+pub struct Profiler {
+    pub enabled: bool,
+    /// How many mouse motion inputs the event loop has merged, see
+    /// `input::MotionCoalescer`.
+    pub events_coalesced: u64,
+    entries: Vec<Entry>,
+    sort_by: SortBy,
+    descending: bool,
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Profiler::new()
+    }
+}
+
+impl Profiler {
+    /// The weight of the newest sample in the moving average.
+    const SMOOTHING: f64 = 0.1;
+
+    pub fn new() -> Self {
+        Profiler {
+            enabled: false,
+            events_coalesced: 0,
+            entries: Vec::new(),
+            sort_by: SortBy::Average,
+            descending: true,
+        }
+    }
+
+    pub fn start(&self) -> Timer {
+        Timer(if self.enabled { Some(now_us()) } else { None })
+    }
+
+    /// Record the time elapsed since `timer` was started under `name`.
+    pub fn record(&mut self, name: &'static str, timer: Timer) {
+        let start = match timer.0 {
+            Some(start) => start,
+            None => return,
+        };
+        let elapsed = now_us() - start;
+        match self.entries.iter_mut().find(|e| e.name == name) {
+            Some(entry) => {
+                entry.last = elapsed;
+                entry.average += (elapsed - entry.average) * Self::SMOOTHING;
+                entry.max = entry.max.max(elapsed);
+            }
+            None => self.entries.push(Entry {
+                name,
+                last: elapsed,
+                average: elapsed,
+                max: elapsed,
+            }),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Sort by `sort_by`, or reverse the order if already sorted by it.
+    pub fn sort(&mut self, sort_by: SortBy) {
+        if self.sort_by == sort_by {
+            self.descending = !self.descending;
+        } else {
+            self.sort_by = sort_by;
+            // Names read best A to Z, times slowest first.
+            self.descending = sort_by != SortBy::Name;
+        }
+    }
+
+    pub fn sorted_entries(&self) -> Vec<&Entry> {
+        let mut entries: Vec<&Entry> = self.entries.iter().collect();
+        entries.sort_by(|a, b| {
+            let ordering = match self.sort_by {
+                SortBy::Name => a.name.cmp(b.name),
+                SortBy::Last => a.last.partial_cmp(&b.last).unwrap(),
+                SortBy::Average => a.average.partial_cmp(&b.average).unwrap(),
+                SortBy::Max => a.max.partial_cmp(&b.max).unwrap(),
+            };
+            if self.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+        entries
+    }
+}
+
+#[derive(WidgetCommon)]
+pub struct ProfilerWidget<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    profiler: &'a mut Profiler,
+}
+
+impl<'a> ProfilerWidget<'a> {
+    pub fn new(profiler: &'a mut Profiler) -> Self {
+        ProfilerWidget {
+            common: widget::CommonBuilder::default(),
+            profiler,
+        }
+    }
+}
+
+widget_ids! {
+    pub struct ProfilerIds {
+        enable,
+        reset,
+        coalesced,
+        headers[],
+        cells[],
+    }
+}
+
+impl<'a> Widget for ProfilerWidget<'a> {
+    type State = ProfilerIds;
+    type Style = ();
+    type Event = ();
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        ProfilerIds::new(id_gen)
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs {
+            id,
+            state,
+            ui,
+            rect,
+            ..
+        } = args;
+        let ProfilerWidget { profiler, .. } = self;
+
+        const PAD: conrod_core::Scalar = 8.0;
+        const ROW_H: conrod_core::Scalar = 24.0;
+        const COLUMNS: [(&str, SortBy); 4] = [
+            ("Window", SortBy::Name),
+            ("Last (ms)", SortBy::Last),
+            ("Avg (ms)", SortBy::Average),
+            ("Max (ms)", SortBy::Max),
+        ];
+
+        for enabled in widget::Toggle::new(profiler.enabled)
+            .label("Profile windows")
+            .label_font_size(14)
+            .w_h(160.0, ROW_H)
+            .top_left_with_margin_on(id, PAD)
+            .set(state.enable, ui)
+        {
+            profiler.enabled = enabled;
+        }
+        if widget::Button::new()
+            .label("Reset")
+            .label_font_size(14)
+            .w_h(80.0, ROW_H)
+            .right_from(state.enable, PAD)
+            .set(state.reset, ui)
+            .was_clicked()
+        {
+            profiler.reset();
+        }
+        widget::Text::new(&format!("{} events coalesced", profiler.events_coalesced))
+            .font_size(12)
+            .color(color::LIGHT_GREY)
+            .right_from(state.reset, PAD)
+            .align_middle_y_of(state.reset)
+            .set(state.coalesced, ui);
+
+        let entries: Vec<Entry> = profiler.sorted_entries().into_iter().cloned().collect();
+        let cell_count = entries.len() * COLUMNS.len();
+        if state.headers.len() < COLUMNS.len() || state.cells.len() < cell_count {
+            let id_gen = &mut ui.widget_id_generator();
+            state.update(|state| {
+                state.headers.resize(COLUMNS.len(), id_gen);
+                state.cells.resize(cell_count, id_gen);
+            });
+        }
+
+        // Clicking a header sorts by that column.
+        let col_w = ((rect.w() - PAD * 2.0) / COLUMNS.len() as f64).max(0.0);
+        for (i, &(label, sort_by)) in COLUMNS.iter().enumerate() {
+            let header = widget::Button::new()
+                .label(label)
+                .label_font_size(12)
+                .w_h(col_w, ROW_H);
+            let header = if i == 0 {
+                header.down_from(state.enable, PAD)
+            } else {
+                header.right_from(state.headers[i - 1], 0.0)
+            };
+            if header.set(state.headers[i], ui).was_clicked() {
+                profiler.sort(sort_by);
+            }
+        }
+
+        for (row, entry) in entries.iter().enumerate() {
+            let values = [
+                entry.name.to_owned(),
+                format!("{:.3}", entry.last / 1000.0),
+                format!("{:.3}", entry.average / 1000.0),
+                format!("{:.3}", entry.max / 1000.0),
+            ];
+            for (col, value) in values.iter().enumerate() {
+                let cell_id = state.cells[row * COLUMNS.len() + col];
+                let above = if row == 0 {
+                    state.headers[col]
+                } else {
+                    state.cells[(row - 1) * COLUMNS.len() + col]
+                };
+                widget::Text::new(value)
+                    .font_size(12)
+                    .color(color::WHITE)
+                    .w_h(col_w, ROW_H)
+                    .down_from(above, 0.0)
+                    .center_justify()
+                    .set(cell_id, ui);
+            }
+        }
+    }
+}
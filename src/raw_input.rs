@@ -0,0 +1,105 @@
+// Input given directly to the content of a floating window instead of
+// conrod's widgets.
+//
+// Content like the Game Viewport and the Terminal claims the raw input in
+// each update while it has the keyboard. Until the next update, the event
+// loop then gives it the key presses, releases and text, and the scrolls
+// over its rect, instead of giving them to conrod, so that no other widget
+// handles them as well. Mouse presses still go to conrod, so clicking
+// elsewhere takes the keyboard away and the claim ends with the next update.
+
+use conrod_core::event::{Input, Motion};
+use conrod_core::input::{Button, Key};
+use conrod_core::{widget, Point, Rect};
+
+struct Claim {
+    id: widget::Id,
+    rect: Rect,
+}
+
+pub struct RawInput {
+    /// The claim made in the last update, which the events go to.
+    claim: Option<Claim>,
+    /// The claim made in this update so far.
+    next_claim: Option<Claim>,
+    events: Vec<Input>,
+}
+
+impl Default for RawInput {
+    fn default() -> Self {
+        RawInput::new()
+    }
+}
+
+impl RawInput {
+    pub fn new() -> Self {
+        RawInput {
+            claim: None,
+            next_claim: None,
+            events: Vec::new(),
+        }
+    }
+
+    /// Take the raw input over `rect` for `id` until the next update, and
+    /// return the events it got since the last one.
+    ///
+    /// Only one widget can have the raw input, the last one to claim it.
+    pub fn claim(&mut self, id: widget::Id, rect: Rect) -> Vec<Input> {
+        self.next_claim = Some(Claim { id, rect });
+        match &self.claim {
+            Some(claim) if claim.id == id => std::mem::take(&mut self.events),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Call after `set_widgets`, to route the events to the new claim.
+    pub fn finish(&mut self) {
+        self.claim = self.next_claim.take();
+        self.events.clear();
+    }
+
+    /// Keep `input` if it goes to the claimed content, or else give it back
+    /// for conrod. `mouse` is where conrod has the mouse.
+    ///
+    /// The modifier keys go to both, so that conrod knows which are held
+    /// down when the claim ends, and the content can read them from conrod.
+    pub fn route(&mut self, input: Input, mouse: Point) -> Option<Input> {
+        let claim = match &self.claim {
+            Some(claim) => claim,
+            None => return Some(input),
+        };
+        match input {
+            Input::Press(Button::Keyboard(key)) | Input::Release(Button::Keyboard(key))
+                if is_modifier(key) =>
+            {
+                self.events.push(input.clone());
+                Some(input)
+            }
+            Input::Press(Button::Keyboard(_))
+            | Input::Release(Button::Keyboard(_))
+            | Input::Text(_) => {
+                self.events.push(input);
+                None
+            }
+            Input::Motion(Motion::Scroll { .. }) if claim.rect.is_over(mouse) => {
+                self.events.push(input);
+                None
+            }
+            input => Some(input),
+        }
+    }
+}
+
+fn is_modifier(key: Key) -> bool {
+    match key {
+        Key::LCtrl
+        | Key::RCtrl
+        | Key::LShift
+        | Key::RShift
+        | Key::LAlt
+        | Key::RAlt
+        | Key::LGui
+        | Key::RGui => true,
+        _ => false,
+    }
+}
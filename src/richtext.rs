@@ -0,0 +1,548 @@
+// Text made of spans in different fonts, sizes and colors.
+//
+// conrod's `Text` widget has a single style, so `layout` wraps the spans word
+// by word and groups each run of words from the same span on the same line
+// into a `Fragment`. `RichText` then sets a `Text` for each fragment and a
+// `Rectangle` for each background, underline and selected line.
+//
+// Positions in the text are byte offsets into the texts of all the spans put
+// together. Coordinates are from the top left of the layout, going down.
+
+use crate::hotspots::{Action, Hotspots};
+use crate::selection::TextLayout;
+use conrod_core::cursor::MouseCursor;
+use conrod_core::text::{self, font};
+use conrod_core::{
+    color, widget, widget_ids, Colorable, FontSize, Positionable, Scalar, Ui, Widget, WidgetCommon,
+};
+use std::ops::Range;
+
+/// The line height relative to the font's own, for some room between lines.
+const LINE_SPACING: f32 = 1.2;
+/// The width shown for a selected line break.
+const BREAK_W: Scalar = 4.0;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Span {
+    pub text: String,
+    /// The font to use instead of the default one.
+    pub font_id: Option<font::Id>,
+    pub font_size: FontSize,
+    pub color: color::Color,
+    pub background: Option<color::Color>,
+    pub underline: bool,
+    /// Identifies the span to the action of `RichText::links`.
+    pub tag: Option<usize>,
+}
+
+impl Span {
+    pub fn new(text: impl Into<String>, font_size: FontSize, color: color::Color) -> Self {
+        Span {
+            text: text.into(),
+            font_id: None,
+            font_size,
+            color,
+            background: None,
+            underline: false,
+            tag: None,
+        }
+    }
+
+    pub fn font(mut self, font_id: font::Id) -> Self {
+        self.font_id = Some(font_id);
+        self
+    }
+
+    pub fn background(mut self, color: color::Color) -> Self {
+        self.background = Some(color);
+        self
+    }
+
+    pub fn underline(mut self) -> Self {
+        self.underline = true;
+        self
+    }
+
+    pub fn tag(mut self, tag: usize) -> Self {
+        self.tag = Some(tag);
+        self
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// The words of one span on one line.
+#[derive(Clone, Debug)]
+pub struct Fragment {
+    /// The index of the span.
+    pub span: usize,
+    /// Where `text` starts in the text.
+    pub start: usize,
+    pub text: String,
+    pub font_id: font::Id,
+    pub font_size: FontSize,
+    pub x: Scalar,
+    /// The top of the fragment, which puts its baseline on the line's.
+    pub y: Scalar,
+    pub w: Scalar,
+    pub h: Scalar,
+}
+
+impl Fragment {
+    fn end(&self) -> usize {
+        self.start + self.text.len()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Line {
+    pub y: Scalar,
+    pub h: Scalar,
+    /// The fragments on the line.
+    pub fragments: Range<usize>,
+    /// The part of the text on the line, without the line break.
+    pub range: Range<usize>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Layout {
+    /// The texts of the spans put together.
+    pub text: String,
+    pub fragments: Vec<Fragment>,
+    pub lines: Vec<Line>,
+    pub width: Scalar,
+    pub height: Scalar,
+}
+
+/// The theme's font, or else the first one loaded.
+pub fn default_font(ui: &Ui) -> Option<font::Id> {
+    ui.theme.font_id.or_else(|| ui.fonts.ids().next())
+}
+
+fn v_metrics(font: &text::Font, font_size: FontSize) -> text::rt::VMetrics {
+    font.v_metrics(text::pt_to_scale(font_size))
+}
+
+struct Builder<'a> {
+    fonts: &'a font::Map,
+    default_font: font::Id,
+    max_width: Scalar,
+    align: Align,
+    fragments: Vec<Fragment>,
+    lines: Vec<Line>,
+    /// Where the current line starts, in the fragments and the text.
+    line_start: (usize, usize),
+    y: Scalar,
+    /// The size of the last span, for the height of empty lines.
+    font_size: FontSize,
+}
+
+impl<'a> Builder<'a> {
+    fn finish_line(&mut self, end: usize) {
+        let fragments = self.line_start.0..self.fragments.len();
+        let metrics: Vec<_> = self.fragments[fragments.clone()]
+            .iter()
+            .map(|fragment| match self.fonts.get(fragment.font_id) {
+                Some(font) => v_metrics(font, fragment.font_size),
+                None => text::rt::VMetrics {
+                    ascent: 0.0,
+                    descent: 0.0,
+                    line_gap: 0.0,
+                },
+            })
+            .collect();
+        let (ascent, descent) = if metrics.is_empty() {
+            match self.fonts.get(self.default_font) {
+                Some(font) => {
+                    let m = v_metrics(font, self.font_size);
+                    (m.ascent, m.descent)
+                }
+                None => (self.font_size as f32, 0.0),
+            }
+        } else {
+            metrics.iter().fold((0.0f32, 0.0f32), |(a, d), m| {
+                (a.max(m.ascent), d.min(m.descent))
+            })
+        };
+        let h = ((ascent - descent) * LINE_SPACING) as Scalar;
+        let gap = (h - (ascent - descent) as Scalar) / 2.0;
+
+        // Trailing spaces don't count for the alignment.
+        let line_w = match self.fragments[fragments.clone()].last() {
+            Some(last) => match self.fonts.get(last.font_id) {
+                Some(font) => {
+                    last.x + text::line::width(last.text.trim_end(), font, last.font_size)
+                }
+                None => last.x + last.w,
+            },
+            None => 0.0,
+        };
+        let dx = match self.align {
+            Align::Left => 0.0,
+            Align::Center => ((self.max_width - line_w) / 2.0).max(0.0),
+            Align::Right => (self.max_width - line_w).max(0.0),
+        };
+        for (fragment, m) in self.fragments[fragments.clone()].iter_mut().zip(&metrics) {
+            fragment.x += dx;
+            fragment.y = self.y + gap + (ascent - m.ascent) as Scalar;
+            fragment.h = (m.ascent - m.descent) as Scalar;
+        }
+
+        self.lines.push(Line {
+            y: self.y,
+            h,
+            fragments,
+            range: self.line_start.1..end,
+        });
+        self.y += h;
+        self.line_start = (self.fragments.len(), end);
+    }
+}
+
+/// Lay out `spans` in lines no wider than `max_width`, except for words
+/// which don't fit on a line of their own. Line breaks in the text start a
+/// new line.
+pub fn layout(
+    spans: &[Span],
+    fonts: &font::Map,
+    default_font: font::Id,
+    max_width: Scalar,
+    align: Align,
+) -> Layout {
+    let mut builder = Builder {
+        fonts,
+        default_font,
+        max_width,
+        align,
+        fragments: Vec::new(),
+        lines: Vec::new(),
+        line_start: (0, 0),
+        y: 0.0,
+        font_size: spans.first().map(|span| span.font_size).unwrap_or(14),
+    };
+    let mut x = 0.0;
+    let mut offset = 0;
+    for (i, span) in spans.iter().enumerate() {
+        let font_id = span.font_id.unwrap_or(default_font);
+        builder.font_size = span.font_size;
+        let font = match fonts.get(font_id) {
+            Some(font) => font,
+            None => {
+                offset += span.text.len();
+                continue;
+            }
+        };
+        let mut rest = span.text.as_str();
+        while !rest.is_empty() {
+            let start = offset + span.text.len() - rest.len();
+            if rest.starts_with('\n') {
+                builder.finish_line(start);
+                builder.line_start.1 = start + 1;
+                x = 0.0;
+                rest = &rest[1..];
+                continue;
+            }
+            // A word with the spaces after it.
+            let end = rest
+                .find(|ch: char| ch == ' ' || ch == '\n')
+                .map(|i| if rest[i..].starts_with(' ') { i + 1 } else { i })
+                .unwrap_or(rest.len());
+            let (word, tail) = rest.split_at(end);
+            rest = tail;
+            let w = text::line::width(word, font, span.font_size);
+            let trimmed_w = text::line::width(word.trim_end(), font, span.font_size);
+            if x > 0.0 && x + trimmed_w > max_width {
+                builder.finish_line(start);
+                x = 0.0;
+            }
+            let on_line = builder.fragments.len() > builder.line_start.0;
+            match builder.fragments.last_mut() {
+                Some(last) if on_line && last.span == i && last.end() == start => {
+                    last.text.push_str(word);
+                    last.w += w;
+                }
+                _ => builder.fragments.push(Fragment {
+                    span: i,
+                    start,
+                    text: word.to_owned(),
+                    font_id,
+                    font_size: span.font_size,
+                    x,
+                    y: 0.0,
+                    w,
+                    h: 0.0,
+                }),
+            }
+            x += w;
+        }
+        offset += span.text.len();
+    }
+    builder.finish_line(offset);
+
+    let width = builder
+        .fragments
+        .iter()
+        .map(|fragment| fragment.x + fragment.w)
+        .fold(0.0, Scalar::max);
+    Layout {
+        text: spans.iter().map(|span| span.text.as_str()).collect(),
+        fragments: builder.fragments,
+        lines: builder.lines,
+        width,
+        height: builder.y,
+    }
+}
+
+impl Layout {
+    /// The x of the position `index` within `fragment`.
+    fn x_in_fragment(&self, fonts: &font::Map, fragment: &Fragment, index: usize) -> Scalar {
+        let end = index
+            .saturating_sub(fragment.start)
+            .min(fragment.text.len());
+        match fonts.get(fragment.font_id) {
+            Some(font) => {
+                fragment.x + text::line::width(&fragment.text[..end], font, fragment.font_size)
+            }
+            None => fragment.x,
+        }
+    }
+
+    /// The x of the position `index` on `line`.
+    fn x_on_line(&self, fonts: &font::Map, line: &Line, index: usize) -> Scalar {
+        let fragments = &self.fragments[line.fragments.clone()];
+        match fragments.iter().find(|fragment| index <= fragment.end()) {
+            Some(fragment) => self.x_in_fragment(fonts, fragment, index),
+            None => fragments
+                .last()
+                .map(|fragment| fragment.x + fragment.w)
+                .unwrap_or(0.0),
+        }
+    }
+
+    /// The rectangles covering `range`, one for each line, as
+    /// `[x, y, w, h]`.
+    pub fn selection_rects(&self, fonts: &font::Map, range: Range<usize>) -> Vec<[Scalar; 4]> {
+        let mut rects = Vec::new();
+        if range.start >= range.end {
+            return rects;
+        }
+        let last = self.lines.len().saturating_sub(1);
+        for (i, line) in self.lines.iter().enumerate() {
+            // Include the line break after the line, if there is one.
+            let line_end = if i < last {
+                self.lines[i + 1].range.start
+            } else {
+                line.range.end
+            };
+            if range.end <= line.range.start || range.start >= line_end.max(line.range.start + 1) {
+                continue;
+            }
+            let left = self.x_on_line(fonts, line, range.start.max(line.range.start));
+            let mut right = self.x_on_line(fonts, line, range.end.min(line.range.end));
+            if range.end > line.range.end && i < last {
+                right += BREAK_W;
+            }
+            if right > left {
+                rects.push([left, line.y, right - left, line.h]);
+            }
+        }
+        rects
+    }
+
+    /// The caret before `index`, as `[x, y, h]`. A position at the end of
+    /// a wrapped line is put at the end of that line.
+    pub fn caret(&self, fonts: &font::Map, index: usize) -> [Scalar; 3] {
+        let line = self
+            .lines
+            .iter()
+            .find(|line| index <= line.range.end)
+            .or_else(|| self.lines.last());
+        match line {
+            Some(line) => [self.x_on_line(fonts, line, index), line.y, line.h],
+            None => [0.0, 0.0, 0.0],
+        }
+    }
+
+    /// The position nearest to `[x, y]`.
+    pub fn index_at(&self, fonts: &font::Map, [x, y]: [Scalar; 2]) -> usize {
+        let line = match self
+            .lines
+            .iter()
+            .find(|line| y < line.y + line.h)
+            .or_else(|| self.lines.last())
+        {
+            Some(line) => line,
+            None => return 0,
+        };
+        for fragment in &self.fragments[line.fragments.clone()] {
+            if x >= fragment.x + fragment.w {
+                continue;
+            }
+            if x <= fragment.x {
+                return fragment.start;
+            }
+            // The character boundary nearest to `x`.
+            let mut prev = (fragment.start, fragment.x);
+            for (i, ch) in fragment.text.char_indices() {
+                let next_index = fragment.start + i + ch.len_utf8();
+                let next_x = self.x_in_fragment(fonts, fragment, next_index);
+                if x < next_x {
+                    return if x - prev.1 < next_x - x {
+                        prev.0
+                    } else {
+                        next_index
+                    };
+                }
+                prev = (next_index, next_x);
+            }
+            return fragment.end();
+        }
+        line.range.end
+    }
+}
+
+#[derive(WidgetCommon)]
+pub struct RichText<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    spans: &'a [Span],
+    layout: &'a Layout,
+    selection: Option<Range<usize>>,
+    links: Option<(&'a mut Hotspots, &'a dyn Fn(usize) -> Action)>,
+}
+
+impl<'a> RichText<'a> {
+    /// Draw `layout`, which was laid out from `spans`. The widget should be
+    /// at least as big as the layout.
+    pub fn new(spans: &'a [Span], layout: &'a Layout) -> Self {
+        RichText {
+            common: widget::CommonBuilder::default(),
+            spans,
+            layout,
+            selection: None,
+            links: None,
+        }
+    }
+
+    pub fn selection(mut self, selection: Range<usize>) -> Self {
+        self.selection = Some(selection);
+        self
+    }
+
+    /// Make the tagged spans links, which show a hand and do what `action`
+    /// returns for their tag when clicked.
+    pub fn links(
+        mut self,
+        hotspots: &'a mut Hotspots,
+        action: &'a dyn Fn(usize) -> Action,
+    ) -> Self {
+        self.links = Some((hotspots, action));
+        self
+    }
+}
+
+widget_ids! {
+    pub struct RichTextIds {
+        backgrounds[],
+        selection[],
+        fragments[],
+        underlines[],
+    }
+}
+
+impl<'a> Widget for RichText<'a> {
+    type State = RichTextIds;
+    type Style = ();
+    type Event = ();
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        RichTextIds::new(id_gen)
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs { id, state, ui, .. } = args;
+        let RichText {
+            spans,
+            layout,
+            selection,
+            mut links,
+            ..
+        } = self;
+
+        let selection_rects = match selection {
+            Some(range) => layout.selection_rects(&ui.fonts, range),
+            None => Vec::new(),
+        };
+        let count = layout.fragments.len();
+        if state.fragments.len() < count || state.selection.len() < selection_rects.len() {
+            let id_gen = &mut ui.widget_id_generator();
+            state.update(|state| {
+                state.backgrounds.resize(count, id_gen);
+                state.fragments.resize(count, id_gen);
+                state.underlines.resize(count, id_gen);
+                state
+                    .selection
+                    .resize(selection_rects.len().max(state.selection.len()), id_gen);
+            });
+        }
+
+        for (i, fragment) in layout.fragments.iter().enumerate() {
+            if let Some(background) = spans[fragment.span].background {
+                widget::Rectangle::fill([fragment.w, fragment.h])
+                    .color(background)
+                    .top_left_with_margins_on(id, fragment.y, fragment.x)
+                    .graphics_for(id)
+                    .set(state.backgrounds[i], ui);
+            }
+        }
+        for (i, &[x, y, w, h]) in selection_rects.iter().enumerate() {
+            widget::Rectangle::fill([w, h])
+                .color(color::LIGHT_BLUE.alpha(0.4))
+                .top_left_with_margins_on(id, y, x)
+                .graphics_for(id)
+                .set(state.selection[i], ui);
+        }
+
+        for (i, fragment) in layout.fragments.iter().enumerate() {
+            let span = &spans[fragment.span];
+            widget::Text::new(&fragment.text)
+                .font_id(fragment.font_id)
+                .font_size(fragment.font_size)
+                .color(span.color)
+                .no_line_wrap()
+                .top_left_with_margins_on(id, fragment.y, fragment.x)
+                .set(state.fragments[i], ui);
+            if span.underline {
+                widget::Rectangle::fill([fragment.w, 1.0])
+                    .color(span.color)
+                    .top_left_with_margins_on(id, fragment.y + fragment.h, fragment.x)
+                    .graphics_for(state.fragments[i])
+                    .set(state.underlines[i], ui);
+            }
+            if let (Some(tag), Some((hotspots, action))) = (span.tag, &mut links) {
+                hotspots.register(state.fragments[i], MouseCursor::Hand, action(tag));
+            }
+        }
+    }
+}
+
+impl TextLayout for Layout {
+    fn text(&self) -> &str {
+        &self.text
+    }
+
+    fn index_at(&self, fonts: &font::Map, point: [Scalar; 2]) -> usize {
+        Layout::index_at(self, fonts, point)
+    }
+
+    fn caret(&self, fonts: &font::Map, index: usize) -> [Scalar; 3] {
+        Layout::caret(self, fonts, index)
+    }
+}
@@ -0,0 +1,180 @@
+// A search bar for windows with rows, opened with Ctrl+F.
+//
+// The window hosting the bar finds the matching rows itself and tells the
+// bar how many there are. The bar keeps the query and which match is the
+// current one, which Enter and Shift+Enter move between.
+
+use conrod_core::input::{Key, ModifierKey};
+use conrod_core::{
+    color, widget, widget_ids, Colorable, Labelable, Positionable, Sizeable, UiCell, Widget,
+    WidgetCommon,
+};
+
+pub struct SearchState {
+    pub open: bool,
+    pub query: String,
+    /// The index of the current match.
+    pub current: usize,
+}
+
+impl Default for SearchState {
+    fn default() -> Self {
+        SearchState::new()
+    }
+}
+
+impl SearchState {
+    pub fn new() -> Self {
+        SearchState {
+            open: false,
+            query: String::new(),
+            current: 0,
+        }
+    }
+
+    /// Whether `text` matches the query, ignoring case. Nothing matches an
+    /// empty query.
+    pub fn matches(&self, text: &str) -> bool {
+        !self.query.is_empty() && text.to_lowercase().contains(&self.query.to_lowercase())
+    }
+
+    /// Open the bar if Ctrl+F was pressed in the window `find_in` and `id`
+    /// is inside it.
+    pub fn open_if_requested(&mut self, ui: &UiCell, id: widget::Id, find_in: Option<widget::Id>) {
+        if let Some(window) = find_in {
+            if ui
+                .widget_graph()
+                .does_recursive_depth_edge_exist(window, id)
+            {
+                self.open = true;
+            }
+        }
+    }
+}
+
+#[derive(WidgetCommon)]
+pub struct SearchBar<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    search: &'a mut SearchState,
+    match_count: usize,
+}
+
+impl<'a> SearchBar<'a> {
+    pub fn new(search: &'a mut SearchState, match_count: usize) -> Self {
+        SearchBar {
+            common: widget::CommonBuilder::default(),
+            search,
+            match_count,
+        }
+    }
+}
+
+widget_ids! {
+    pub struct SearchBarIds {
+        text_box,
+        count,
+        close_button,
+    }
+}
+
+impl<'a> Widget for SearchBar<'a> {
+    type State = SearchBarIds;
+    type Style = ();
+    /// Whether the current match has changed, so that the window can scroll
+    /// to it.
+    type Event = bool;
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        SearchBarIds::new(id_gen)
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs {
+            id,
+            state,
+            ui,
+            rect,
+            ..
+        } = args;
+        let SearchBar {
+            search,
+            match_count,
+            ..
+        } = self;
+
+        const PAD: conrod_core::Scalar = 8.0;
+        const COUNT_W: conrod_core::Scalar = 70.0;
+        const CLOSE_W: conrod_core::Scalar = 24.0;
+
+        let mut changed = false;
+        let text_w = (rect.w() - COUNT_W - CLOSE_W - PAD * 2.0).max(0.0);
+        for event in widget::TextBox::new(&search.query)
+            .font_size(12)
+            .w_h(text_w, rect.h())
+            .top_left_of(id)
+            .set(state.text_box, ui)
+        {
+            match event {
+                widget::text_box::Event::Update(query) => {
+                    search.query = query;
+                    search.current = 0;
+                    changed = true;
+                }
+                widget::text_box::Event::Enter if match_count > 0 => {
+                    let shift = ui
+                        .global_input()
+                        .current
+                        .modifiers
+                        .contains(ModifierKey::SHIFT);
+                    search.current = if shift {
+                        (search.current + match_count - 1) % match_count
+                    } else {
+                        (search.current + 1) % match_count
+                    };
+                    changed = true;
+                }
+                widget::text_box::Event::Enter => {}
+            }
+        }
+        if match_count > 0 {
+            search.current = search.current.min(match_count - 1);
+        }
+
+        let count = if search.query.is_empty() {
+            String::new()
+        } else if match_count == 0 {
+            "No matches".to_owned()
+        } else {
+            format!("{} of {}", search.current + 1, match_count)
+        };
+        widget::Text::new(&count)
+            .font_size(12)
+            .color(color::LIGHT_GREY)
+            .w(COUNT_W)
+            .no_line_wrap()
+            .right_from(state.text_box, PAD)
+            .set(state.count, ui);
+
+        let escape = ui
+            .widget_input(state.text_box)
+            .presses()
+            .key()
+            .any(|press| press.key == Key::Escape);
+        if widget::Button::new()
+            .label("x")
+            .label_font_size(12)
+            .w_h(CLOSE_W, rect.h())
+            .top_right_of(id)
+            .set(state.close_button, ui)
+            .was_clicked()
+            || escape
+        {
+            search.open = false;
+        }
+
+        changed
+    }
+}
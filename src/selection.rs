@@ -0,0 +1,283 @@
+// Selecting text with the mouse and the keyboard, for widgets which lay out
+// their own text, like the Markdown window.
+//
+// Dragging selects across wrapped lines, a double click selects a word and a
+// triple click a line of the text. Shift with the arrows, Home and End moves
+// the end of the selection, Ctrl+A selects everything and Ctrl+C asks for the
+// selection to be copied. The caret blinks by the time since it last moved,
+// so the widget needs to be updated again after `until_blink` while it has
+// the keyboard.
+//
+// conrod's `TextEdit` keeps its selection to itself, so the Notes window
+// keeps the one it has.
+
+use conrod_core::input::{Key, ModifierKey, MouseButton};
+use conrod_core::text::font;
+use conrod_core::{event, widget, Point, Scalar, Ui, UiCell};
+use std::ops::Range;
+use std::time::Duration;
+
+/// How long the caret is shown, then hidden.
+const BLINK_US: f64 = 530_000.0;
+
+/// Text laid out from the top left, going down.
+pub trait TextLayout {
+    /// The whole text. Positions are byte offsets into it.
+    fn text(&self) -> &str;
+
+    /// The position nearest to `point`.
+    fn index_at(&self, fonts: &font::Map, point: [Scalar; 2]) -> usize;
+
+    /// The caret before `index`, as `[x, y, h]`.
+    fn caret(&self, fonts: &font::Map, index: usize) -> [Scalar; 3];
+}
+
+pub struct Selection {
+    anchor: usize,
+    head: usize,
+    /// The time, place and number of the last clicks, for counting double
+    /// and triple clicks.
+    last_click: Option<(f64, Point, u32)>,
+    dragging: bool,
+    /// When the head last moved, which restarts the blinking.
+    moved_at: f64,
+}
+
+impl Default for Selection {
+    fn default() -> Self {
+        Selection::new()
+    }
+}
+
+impl Selection {
+    pub fn new() -> Self {
+        Selection {
+            anchor: 0,
+            head: 0,
+            last_click: None,
+            dragging: false,
+            moved_at: 0.0,
+        }
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.anchor.min(self.head)..self.anchor.max(self.head)
+    }
+
+    /// Where the caret is, at the end of the selection which moves.
+    pub fn head(&self) -> usize {
+        self.head
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.anchor == self.head
+    }
+
+    /// Go back to the start, as when the text is replaced.
+    pub fn reset(&mut self) {
+        self.anchor = 0;
+        self.head = 0;
+        self.dragging = false;
+    }
+
+    pub fn caret_visible(&self, now: f64) -> bool {
+        ((now - self.moved_at) / BLINK_US) as u64 % 2 == 0
+    }
+
+    /// How long until the caret is shown or hidden next.
+    pub fn until_blink(&self, now: f64) -> Duration {
+        let us = BLINK_US - (now - self.moved_at).rem_euclid(BLINK_US);
+        Duration::from_micros(us as u64)
+    }
+
+    fn select(&mut self, range: Range<usize>, now: f64) {
+        self.anchor = range.start;
+        self.head = range.end;
+        self.moved_at = now;
+    }
+
+    /// Move the head, and the anchor with it unless `extend`.
+    fn move_to(&mut self, index: usize, extend: bool, now: f64) {
+        self.head = index;
+        if !extend {
+            self.anchor = index;
+        }
+        self.moved_at = now;
+    }
+
+    /// Handle the mouse and keyboard input of this update.
+    ///
+    /// Mouse presses count when they are on `target` or its descendants,
+    /// and keys when one of them has the keyboard. `origin` is where the top
+    /// left of `layout` is in the UI. Returns the selected text when the
+    /// user asked to copy it.
+    pub fn update(
+        &mut self,
+        ui: &UiCell,
+        target: widget::Id,
+        layout: &dyn TextLayout,
+        origin: Point,
+        now: f64,
+    ) -> Option<String> {
+        let text = layout.text();
+        let len = text.len();
+        self.anchor = self.anchor.min(len);
+        self.head = self.head.min(len);
+        let to_layout = |[x, y]: Point| [x - origin[0], origin[1] - y];
+        // Clicks count as a double or triple click by the same thresholds as
+        // conrod's double clicks.
+        let multi_click_us = ui.theme.double_click_threshold.as_secs_f64() * 1e6;
+        let multi_click_distance = ui.theme.mouse_drag_threshold;
+        let mut copy = None;
+
+        for event in ui.global_input().events().ui() {
+            match event {
+                event::Ui::Press(Some(id), press) if is_within(ui, *id, target) => {
+                    match press.button {
+                        event::Button::Mouse(MouseButton::Left, xy) => {
+                            let clicks = match self.last_click {
+                                Some((at, place, clicks))
+                                    if now - at < multi_click_us
+                                        && (place[0] - xy[0]).abs() <= multi_click_distance
+                                        && (place[1] - xy[1]).abs() <= multi_click_distance =>
+                                {
+                                    clicks % 3 + 1
+                                }
+                                _ => 1,
+                            };
+                            self.last_click = Some((now, xy, clicks));
+                            let index = layout.index_at(&ui.fonts, to_layout(xy));
+                            match clicks {
+                                1 => {
+                                    let extend = press.modifiers.contains(ModifierKey::SHIFT);
+                                    self.move_to(index, extend, now);
+                                    self.dragging = true;
+                                }
+                                2 => self.select(word_at(text, index), now),
+                                _ => self.select(line_at(text, index), now),
+                            }
+                        }
+                        event::Button::Keyboard(key) => {
+                            let shift = press.modifiers.contains(ModifierKey::SHIFT);
+                            let ctrl = press.modifiers.contains(ModifierKey::CTRL);
+                            let [x, y, h] = layout.caret(&ui.fonts, self.head);
+                            let range = self.range();
+                            match key {
+                                Key::A if ctrl => self.select(0..len, now),
+                                Key::C if ctrl => {
+                                    if !self.is_empty() {
+                                        copy = Some(text[range].to_owned());
+                                    }
+                                }
+                                Key::Left if !shift && !self.is_empty() => {
+                                    self.move_to(range.start, false, now)
+                                }
+                                Key::Right if !shift && !self.is_empty() => {
+                                    self.move_to(range.end, false, now)
+                                }
+                                Key::Left => {
+                                    self.move_to(prev_boundary(text, self.head), shift, now)
+                                }
+                                Key::Right => {
+                                    self.move_to(next_boundary(text, self.head), shift, now)
+                                }
+                                Key::Up => {
+                                    let index = layout.index_at(&ui.fonts, [x, y - h / 2.0]);
+                                    self.move_to(index, shift, now);
+                                }
+                                Key::Down => {
+                                    let index = layout.index_at(&ui.fonts, [x, y + h * 1.5]);
+                                    self.move_to(index, shift, now);
+                                }
+                                Key::Home => {
+                                    let index = layout.index_at(&ui.fonts, [0.0, y + h / 2.0]);
+                                    self.move_to(index, shift, now);
+                                }
+                                Key::End => {
+                                    let point = [Scalar::MAX, y + h / 2.0];
+                                    let index = layout.index_at(&ui.fonts, point);
+                                    self.move_to(index, shift, now);
+                                }
+                                _ => {}
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                event::Ui::Drag(_, drag) if self.dragging => {
+                    if drag.button == MouseButton::Left {
+                        let index = layout.index_at(&ui.fonts, to_layout(drag.to));
+                        self.move_to(index, true, now);
+                    }
+                }
+                event::Ui::Release(_, release) => {
+                    if let event::Button::Mouse(MouseButton::Left, _) = release.button {
+                        self.dragging = false;
+                    }
+                }
+                _ => {}
+            }
+        }
+        copy
+    }
+}
+
+/// Whether `id` is `ancestor` or one of its descendants.
+pub fn is_within(ui: &Ui, mut id: widget::Id, ancestor: widget::Id) -> bool {
+    let graph = ui.widget_graph();
+    loop {
+        if id == ancestor {
+            return true;
+        }
+        match graph.depth_parent(id) {
+            Some(parent) => id = parent,
+            None => return false,
+        }
+    }
+}
+
+fn prev_boundary(text: &str, index: usize) -> usize {
+    text[..index]
+        .char_indices()
+        .next_back()
+        .map_or(0, |(i, _)| i)
+}
+
+fn next_boundary(text: &str, index: usize) -> usize {
+    text[index..]
+        .chars()
+        .next()
+        .map_or(index, |ch| index + ch.len_utf8())
+}
+
+fn is_word_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
+}
+
+/// The word around `index`, or the character after it if that isn't part of
+/// a word.
+fn word_at(text: &str, index: usize) -> Range<usize> {
+    let after = text[index..].chars().next();
+    let before = text[..index].chars().next_back();
+    let in_word = after.map_or(false, is_word_char) || before.map_or(false, is_word_char);
+    if !in_word {
+        return index..next_boundary(text, index);
+    }
+    let start = text[..index]
+        .char_indices()
+        .rev()
+        .find(|&(_, ch)| !is_word_char(ch))
+        .map_or(0, |(i, ch)| i + ch.len_utf8());
+    let end = text[index..]
+        .char_indices()
+        .find(|&(_, ch)| !is_word_char(ch))
+        .map_or(text.len(), |(i, _)| index + i);
+    start..end
+}
+
+/// The line around `index`, without the line break.
+fn line_at(text: &str, index: usize) -> Range<usize> {
+    let start = text[..index].rfind('\n').map_or(0, |i| i + 1);
+    let end = text[index..].find('\n').map_or(text.len(), |i| index + i);
+    start..end
+}
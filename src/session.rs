@@ -0,0 +1,65 @@
+// Exporting and importing the state of the demo as a single JSON file.
+//
+// Every field has a default, so a file from an older version loads with the
+// missing parts reset, and fields added by a newer version are ignored. The
+// `version` is only bumped when the meaning of an existing field changes.
+//
+// floatwin doesn't expose the window positions and sizes, so the layout is
+// limited to which workspace each window is on, which is in the `Config`.
+
+use crate::config::Config;
+use crate::node_graph::NodeGraphState;
+use crate::tool_palette::Tool;
+use crate::whiteboard::WhiteboardState;
+use serde::{Deserialize, Serialize};
+
+pub const SESSION_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    /// Zero when missing, which means the file isn't a session.
+    pub version: u32,
+    pub config: Config,
+    pub notes: NotesSession,
+    pub whiteboard: WhiteboardState,
+    pub node_graph: NodeGraphState,
+    pub tool: Tool,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Session {
+            version: 0,
+            config: Config::default(),
+            notes: NotesSession::default(),
+            whiteboard: WhiteboardState::new(),
+            node_graph: NodeGraphState::new(),
+            tool: Tool::Select,
+        }
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotesSession {
+    pub text: String,
+    pub file_name: Option<String>,
+}
+
+impl Session {
+    pub fn to_json(&self) -> Result<Vec<u8>, String> {
+        serde_json::to_vec_pretty(self).map_err(|e| e.to_string())
+    }
+
+    /// Parse a session file. Returns the session and whether it was made by
+    /// a newer version, in which case some of it may have been left out.
+    pub fn from_json(data: &[u8]) -> Result<(Self, bool), String> {
+        let session: Session = serde_json::from_slice(data).map_err(|e| e.to_string())?;
+        if session.version == 0 {
+            return Err("not a session file".to_owned());
+        }
+        let newer = session.version > SESSION_VERSION;
+        Ok((session, newer))
+    }
+}
@@ -0,0 +1,344 @@
+// The Settings window, for editing the persistent `Config`.
+
+use crate::config::{Config, MSAA_CHOICES};
+use crate::desktop_widgets::{Corner, DesktopWidgetKind};
+use crate::focus::FocusPolicy;
+use crate::shortcuts::Shortcuts;
+use conrod_core::{
+    color, widget, widget_ids, Colorable, Labelable, Positionable, Sizeable, Widget, WidgetCommon,
+};
+
+#[derive(WidgetCommon)]
+pub struct SettingsWidget<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    config: &'a mut Config,
+    shortcuts: &'a Shortcuts,
+}
+
+impl<'a> SettingsWidget<'a> {
+    pub fn new(config: &'a mut Config, shortcuts: &'a Shortcuts) -> Self {
+        SettingsWidget {
+            common: widget::CommonBuilder::default(),
+            config,
+            shortcuts,
+        }
+    }
+}
+
+widget_ids! {
+    pub struct SettingsIds {
+        mouse_title,
+        back_button,
+        forward_button,
+        focus_title,
+        focus_policy,
+        edge_margin,
+        keep_in_view,
+        input_title,
+        double_click,
+        drag_distance,
+        scroll_speed,
+        natural_scroll,
+        predict_drags,
+        audit_title,
+        min_hit_target,
+        rendering_title,
+        msaa,
+        msaa_label,
+        fxaa,
+        focus_ring,
+        desktop_title,
+        desktop_corners[],
+        desktop_labels[],
+        shortcuts_title,
+        shortcuts[],
+    }
+}
+
+impl<'a> Widget for SettingsWidget<'a> {
+    type State = SettingsIds;
+    type Style = ();
+    /// Whether the configuration has been changed and should be saved.
+    type Event = bool;
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        SettingsIds::new(id_gen)
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs { id, state, ui, .. } = args;
+        let SettingsWidget {
+            config, shortcuts, ..
+        } = self;
+
+        const PAD: conrod_core::Scalar = 8.0;
+        const ROW_H: conrod_core::Scalar = 28.0;
+        const DIALER_W: conrod_core::Scalar = 220.0;
+
+        let mut changed = false;
+
+        widget::Text::new("Mouse buttons (winit numbering)")
+            .font_size(14)
+            .color(color::WHITE)
+            .top_left_with_margin_on(id, PAD)
+            .set(state.mouse_title, ui);
+        let bindings = &mut config.mouse_bindings;
+        if let Some(value) = widget::NumberDialer::new(bindings.back as f32, 0.0, u8::MAX as f32, 0)
+            .label("Back")
+            .label_font_size(14)
+            .w_h(DIALER_W, ROW_H)
+            .down_from(state.mouse_title, PAD)
+            .set(state.back_button, ui)
+        {
+            bindings.back = value as u8;
+            changed = true;
+        }
+        if let Some(value) =
+            widget::NumberDialer::new(bindings.forward as f32, 0.0, u8::MAX as f32, 0)
+                .label("Forward")
+                .label_font_size(14)
+                .w_h(DIALER_W, ROW_H)
+                .down_from(state.back_button, PAD)
+                .set(state.forward_button, ui)
+        {
+            bindings.forward = value as u8;
+            changed = true;
+        }
+
+        widget::Text::new("Window focus")
+            .font_size(14)
+            .color(color::WHITE)
+            .down_from(state.forward_button, PAD * 2.0)
+            .set(state.focus_title, ui);
+        let policy_names: Vec<&str> = FocusPolicy::ALL.iter().map(|p| p.name()).collect();
+        let selected = FocusPolicy::ALL
+            .iter()
+            .position(|&p| p == config.focus_policy);
+        if let Some(index) = widget::DropDownList::new(&policy_names, selected)
+            .label_font_size(14)
+            .w_h(DIALER_W, ROW_H)
+            .down_from(state.focus_title, PAD)
+            .set(state.focus_policy, ui)
+        {
+            config.focus_policy = FocusPolicy::ALL[index];
+            changed = true;
+        }
+        if let Some(value) =
+            widget::NumberDialer::new(config.window_edge_margin as f32, 0.0, 32.0, 0)
+                .label("Edge margin")
+                .label_font_size(14)
+                .w_h(DIALER_W, ROW_H)
+                .down_from(state.focus_policy, PAD)
+                .set(state.edge_margin, ui)
+        {
+            config.window_edge_margin = value as f64;
+            changed = true;
+        }
+        for value in widget::Toggle::new(config.keep_windows_in_view)
+            .label("Keep windows in view")
+            .label_font_size(14)
+            .w_h(DIALER_W, ROW_H)
+            .down_from(state.edge_margin, PAD)
+            .set(state.keep_in_view, ui)
+        {
+            config.keep_windows_in_view = value;
+            changed = true;
+        }
+
+        widget::Text::new("Mouse input")
+            .font_size(14)
+            .color(color::WHITE)
+            .down_from(state.keep_in_view, PAD * 2.0)
+            .set(state.input_title, ui);
+        let input = &mut config.input;
+        if let Some(value) =
+            widget::NumberDialer::new(input.double_click_ms as f32, 100.0, 2000.0, 0)
+                .label("Double click (ms)")
+                .label_font_size(14)
+                .w_h(DIALER_W, ROW_H)
+                .down_from(state.input_title, PAD)
+                .set(state.double_click, ui)
+        {
+            input.double_click_ms = value as u32;
+            changed = true;
+        }
+        if let Some(value) = widget::NumberDialer::new(input.drag_distance as f32, 0.0, 32.0, 0)
+            .label("Drag distance")
+            .label_font_size(14)
+            .w_h(DIALER_W, ROW_H)
+            .down_from(state.double_click, PAD)
+            .set(state.drag_distance, ui)
+        {
+            input.drag_distance = value as f64;
+            changed = true;
+        }
+        if let Some(value) = widget::NumberDialer::new(input.scroll_speed as f32, 0.1, 5.0, 1)
+            .label("Scroll speed")
+            .label_font_size(14)
+            .w_h(DIALER_W, ROW_H)
+            .down_from(state.drag_distance, PAD)
+            .set(state.scroll_speed, ui)
+        {
+            input.scroll_speed = value as f64;
+            changed = true;
+        }
+        for value in widget::Toggle::new(input.natural_scroll)
+            .label("Natural scrolling")
+            .label_font_size(14)
+            .w_h(DIALER_W, ROW_H)
+            .down_from(state.scroll_speed, PAD)
+            .set(state.natural_scroll, ui)
+        {
+            input.natural_scroll = value;
+            changed = true;
+        }
+        for value in widget::Toggle::new(input.predict_drags)
+            .label("Predict drags")
+            .label_font_size(14)
+            .w_h(DIALER_W, ROW_H)
+            .down_from(state.natural_scroll, PAD)
+            .set(state.predict_drags, ui)
+        {
+            input.predict_drags = value;
+            changed = true;
+        }
+
+        widget::Text::new("Accessibility audit")
+            .font_size(14)
+            .color(color::WHITE)
+            .down_from(state.predict_drags, PAD * 2.0)
+            .set(state.audit_title, ui);
+        if let Some(value) = widget::NumberDialer::new(config.min_hit_target as f32, 8.0, 64.0, 0)
+            .label("Min. hit target")
+            .label_font_size(14)
+            .w_h(DIALER_W, ROW_H)
+            .down_from(state.audit_title, PAD)
+            .set(state.min_hit_target, ui)
+        {
+            config.min_hit_target = value as f64;
+            changed = true;
+        }
+
+        widget::Text::new("Rendering")
+            .font_size(14)
+            .color(color::WHITE)
+            .down_from(state.min_hit_target, PAD * 2.0)
+            .set(state.rendering_title, ui);
+        let msaa_names: Vec<String> = MSAA_CHOICES
+            .iter()
+            .map(|&samples| match samples {
+                0 => "Off".to_owned(),
+                samples => format!("{}x", samples),
+            })
+            .collect();
+        let selected = MSAA_CHOICES
+            .iter()
+            .position(|&samples| samples == config.msaa_samples);
+        if let Some(index) = widget::DropDownList::new(&msaa_names, selected)
+            .label_font_size(14)
+            .w_h(DIALER_W / 2.0, ROW_H)
+            .down_from(state.rendering_title, PAD)
+            .set(state.msaa, ui)
+        {
+            config.msaa_samples = MSAA_CHOICES[index];
+            changed = true;
+        }
+        widget::Text::new("Antialiasing (MSAA)")
+            .font_size(14)
+            .color(color::LIGHT_GREY)
+            .right_from(state.msaa, PAD)
+            .set(state.msaa_label, ui);
+        for value in widget::Toggle::new(config.fxaa)
+            .label("Antialiasing (FXAA)")
+            .label_font_size(14)
+            .w_h(DIALER_W, ROW_H)
+            .down_from(state.msaa, PAD)
+            .set(state.fxaa, ui)
+        {
+            config.fxaa = value;
+            changed = true;
+        }
+        for value in widget::Toggle::new(config.focus_ring)
+            .label("Focus ring")
+            .label_font_size(14)
+            .w_h(DIALER_W, ROW_H)
+            .down_from(state.fxaa, PAD)
+            .set(state.focus_ring, ui)
+        {
+            config.focus_ring = value;
+            changed = true;
+        }
+
+        widget::Text::new("Desktop widgets")
+            .font_size(14)
+            .color(color::WHITE)
+            .down_from(state.focus_ring, PAD * 2.0)
+            .set(state.desktop_title, ui);
+        let kinds: Vec<DesktopWidgetKind> = DesktopWidgetKind::ALL
+            .iter()
+            .cloned()
+            .filter(|kind| kind.is_available())
+            .collect();
+        if state.desktop_corners.len() < kinds.len() {
+            let id_gen = &mut ui.widget_id_generator();
+            state.update(|state| {
+                state.desktop_corners.resize(kinds.len(), id_gen);
+                state.desktop_labels.resize(kinds.len(), id_gen);
+            });
+        }
+        // "Hidden", then the corners.
+        let corner_names: Vec<&str> = std::iter::once("Hidden")
+            .chain(Corner::ALL.iter().map(|c| c.name()))
+            .collect();
+        let desktop_widgets = &mut config.desktop_widgets;
+        let mut prev = state.desktop_title;
+        for (i, &kind) in kinds.iter().enumerate() {
+            let selected = match desktop_widgets.corner(kind) {
+                Some(corner) => Corner::ALL.iter().position(|&c| c == corner).map(|i| i + 1),
+                None => Some(0),
+            };
+            if let Some(index) = widget::DropDownList::new(&corner_names, selected)
+                .label_font_size(14)
+                .w_h(DIALER_W / 2.0, ROW_H)
+                .down_from(prev, PAD)
+                .set(state.desktop_corners[i], ui)
+            {
+                let corner = index.checked_sub(1).map(|index| Corner::ALL[index]);
+                desktop_widgets.set_corner(kind, corner);
+                changed = true;
+            }
+            widget::Text::new(kind.name())
+                .font_size(14)
+                .color(color::LIGHT_GREY)
+                .right_from(state.desktop_corners[i], PAD)
+                .set(state.desktop_labels[i], ui);
+            prev = state.desktop_corners[i];
+        }
+
+        widget::Text::new("Shortcuts")
+            .font_size(14)
+            .color(color::WHITE)
+            .down_from(prev, PAD * 2.0)
+            .set(state.shortcuts_title, ui);
+        let bindings = shortcuts.bindings();
+        if state.shortcuts.len() < bindings.len() {
+            let id_gen = &mut ui.widget_id_generator();
+            state.update(|state| state.shortcuts.resize(bindings.len(), id_gen));
+        }
+        let mut prev = state.shortcuts_title;
+        for (i, (shortcut, command)) in bindings.iter().enumerate() {
+            let text = format!("{}: {}", shortcuts.display_name(shortcut), command.name());
+            widget::Text::new(&text)
+                .font_size(12)
+                .color(color::LIGHT_GREY)
+                .down_from(prev, PAD / 2.0)
+                .set(state.shortcuts[i], ui);
+            prev = state.shortcuts[i];
+        }
+
+        changed
+    }
+}
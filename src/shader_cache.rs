@@ -0,0 +1,178 @@
+// Caching the linked shader programs on disk, so that later startups load
+// them with `glProgramBinary` instead of compiling the shaders again.
+//
+// glow has no calls for program binaries, so they are loaded here from the
+// context like glow loads its own. A binary is only good for the driver
+// which made it, so the files are keyed by a hash of the GL vendor, renderer
+// and version along with the sources of the shaders, and a driver update
+// simply misses the cache. The driver can still turn a binary down, after an
+// update which kept the version string, in which case the file is deleted
+// and the program is compiled from source as if there was no cache.
+//
+// WebGL has no program binaries, so the web build always compiles.
+
+use crate::conrod_glow::ProgramCache;
+use glow::HasContext;
+use std::ffi::c_void;
+use std::os::raw::{c_int, c_uint};
+use std::path::PathBuf;
+
+type GetProgramBinary =
+    unsafe extern "system" fn(c_uint, c_int, *mut c_int, *mut c_uint, *mut c_void);
+type ProgramBinary = unsafe extern "system" fn(c_uint, c_uint, *const c_void, c_int);
+type ProgramParameteri = unsafe extern "system" fn(c_uint, c_uint, c_int);
+type GetProgramiv = unsafe extern "system" fn(c_uint, c_uint, *mut c_int);
+
+/// Starts each file, before the binary format and the binary.
+const MAGIC: &[u8; 8] = b"CFDGPRG1";
+
+pub struct ProgramBinaryCache {
+    get_program_binary: GetProgramBinary,
+    program_binary: ProgramBinary,
+    program_parameteri: ProgramParameteri,
+    get_programiv: GetProgramiv,
+    dir: PathBuf,
+    /// The vendor, renderer and version of the GL.
+    driver: String,
+}
+
+impl ProgramBinaryCache {
+    /// The cache for `gl`, whose functions `loader` looks up like for
+    /// `glow::Context::from_loader_function`. `None` if the GL has no program
+    /// binaries, or there is no cache directory.
+    pub fn new(gl: &glow::Context, mut loader: impl FnMut(&str) -> *const c_void) -> Option<Self> {
+        let formats = unsafe { gl.get_parameter_i32(glow::NUM_PROGRAM_BINARY_FORMATS) };
+        if formats <= 0 {
+            return None;
+        }
+        let mut load = |name: &str| Some(loader(name)).filter(|f| !f.is_null());
+        let get_program_binary = load("glGetProgramBinary")?;
+        let program_binary = load("glProgramBinary")?;
+        let program_parameteri = load("glProgramParameteri")?;
+        let get_programiv = load("glGetProgramiv")?;
+        let dir = dirs::cache_dir()?
+            .join("conrod_floatwin_demo_glow")
+            .join("shaders");
+        let driver = unsafe {
+            format!(
+                "{}\n{}\n{}",
+                gl.get_parameter_string(glow::VENDOR),
+                gl.get_parameter_string(glow::RENDERER),
+                gl.get_parameter_string(glow::VERSION)
+            )
+        };
+        // The pointers are the functions of these types, by their names.
+        unsafe {
+            Some(ProgramBinaryCache {
+                get_program_binary: std::mem::transmute(get_program_binary),
+                program_binary: std::mem::transmute(program_binary),
+                program_parameteri: std::mem::transmute(program_parameteri),
+                get_programiv: std::mem::transmute(get_programiv),
+                dir,
+                driver,
+            })
+        }
+    }
+
+    fn path(&self, vs: &str, fs: &str) -> PathBuf {
+        let hash = fnv1a(&[self.driver.as_bytes(), vs.as_bytes(), fs.as_bytes()]);
+        self.dir.join(format!("{:016x}.bin", hash))
+    }
+
+    fn programiv(&self, program: glow::Program, parameter: u32) -> i32 {
+        let mut value = 0;
+        unsafe { (self.get_programiv)(program, parameter, &mut value) };
+        value
+    }
+}
+
+impl ProgramCache for ProgramBinaryCache {
+    fn load(&self, gl: &glow::Context, vs: &str, fs: &str) -> Option<glow::Program> {
+        let path = self.path(vs, fs);
+        let file = std::fs::read(&path).ok()?;
+        let binary = match parse(&file) {
+            Some(binary) => binary,
+            None => {
+                let _ = std::fs::remove_file(&path);
+                return None;
+            }
+        };
+        unsafe {
+            let program = gl.create_program().ok()?;
+            let (format, data) = binary;
+            (self.program_binary)(
+                program,
+                format,
+                data.as_ptr() as *const c_void,
+                data.len() as c_int,
+            );
+            if gl.get_error() != glow::NO_ERROR || !gl.get_program_link_status(program) {
+                gl.delete_program(program);
+                let _ = std::fs::remove_file(&path);
+                return None;
+            }
+            Some(program)
+        }
+    }
+
+    fn before_link(&self, _gl: &glow::Context, program: glow::Program) {
+        unsafe {
+            (self.program_parameteri)(program, glow::PROGRAM_BINARY_RETRIEVABLE_HINT, 1);
+        }
+    }
+
+    fn store(&self, _gl: &glow::Context, vs: &str, fs: &str, program: glow::Program) {
+        let length = self.programiv(program, glow::PROGRAM_BINARY_LENGTH);
+        if length <= 0 {
+            return;
+        }
+        let mut data = vec![0u8; length as usize];
+        let mut written = 0;
+        let mut format = 0;
+        unsafe {
+            (self.get_program_binary)(
+                program,
+                length,
+                &mut written,
+                &mut format,
+                data.as_mut_ptr() as *mut c_void,
+            );
+        }
+        if written <= 0 {
+            return;
+        }
+        data.truncate(written as usize);
+        let mut file = Vec::with_capacity(MAGIC.len() + 4 + data.len());
+        file.extend_from_slice(MAGIC);
+        file.extend_from_slice(&format.to_le_bytes());
+        file.extend_from_slice(&data);
+        // The program works without the file, it only takes longer next time.
+        let _ = std::fs::create_dir_all(&self.dir)
+            .and_then(|()| std::fs::write(self.path(vs, fs), file));
+    }
+}
+
+/// The binary format and the binary of a file, `None` if it isn't one of
+/// ours.
+fn parse(file: &[u8]) -> Option<(u32, &[u8])> {
+    if file.len() <= MAGIC.len() + 4 || !file.starts_with(MAGIC) {
+        return None;
+    }
+    let (format, data) = file[MAGIC.len()..].split_at(4);
+    let mut bytes = [0; 4];
+    bytes.copy_from_slice(format);
+    Some((u32::from_le_bytes(bytes), data))
+}
+
+/// 64-bit FNV-1a over `parts`, which unlike `DefaultHasher` stays the same
+/// across Rust releases. A zero byte goes between the parts.
+fn fnv1a(parts: &[&[u8]]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for part in parts {
+        for &byte in part.iter().chain(&[0]) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+    hash
+}
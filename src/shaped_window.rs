@@ -0,0 +1,189 @@
+// Floating windows without a frame or a background, whose content gives
+// their shape, like the round clock on the backdrop.
+//
+// floatwin always draws a rectangular frame and background, so these aren't
+// floatwin windows. They are set right after the windowing area, above its
+// windows, and are moved by dragging any opaque part of them. Clicks go
+// through the transparent parts: the widget keeps an alpha mask of the shape
+// on the CPU in its state, which is all that conrod gives to `is_over`.
+
+use conrod_core::graph::Container;
+use conrod_core::{widget, Dimensions, Point, Positionable, Theme, Widget, WidgetCommon};
+
+/// The shape of the content of a `ShapedWindow`.
+pub trait Silhouette {
+    /// How opaque the content is at `xy`, relative to the middle of a window
+    /// of `dim`, from 0.0 to 1.0.
+    fn alpha(&self, xy: Point, dim: Dimensions) -> f32;
+}
+
+/// The alpha of a silhouette, one sample per point.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AlphaMask {
+    width: usize,
+    height: usize,
+    alpha: Vec<u8>,
+}
+
+impl AlphaMask {
+    /// Where clicks stop going through.
+    const OPAQUE: u8 = 128;
+
+    /// Sample `silhouette` at the middle of each point of a window of `dim`.
+    pub fn sample(silhouette: &dyn Silhouette, dim: Dimensions) -> Self {
+        let width = dim[0].max(0.0).ceil() as usize;
+        let height = dim[1].max(0.0).ceil() as usize;
+        let mut alpha = Vec::with_capacity(width * height);
+        for row in 0..height {
+            let y = dim[1] / 2.0 - row as f64 - 0.5;
+            for col in 0..width {
+                let x = col as f64 + 0.5 - dim[0] / 2.0;
+                let a = silhouette.alpha([x, y], dim).max(0.0).min(1.0);
+                alpha.push((a * 255.0).round() as u8);
+            }
+        }
+        AlphaMask {
+            width,
+            height,
+            alpha,
+        }
+    }
+
+    /// Whether the silhouette is opaque enough at `xy`, relative to the
+    /// middle, to take a click there.
+    pub fn is_opaque_at(&self, xy: Point) -> bool {
+        let col = xy[0] + self.width as f64 / 2.0;
+        let row = self.height as f64 / 2.0 - xy[1];
+        if !(col >= 0.0 && row >= 0.0) {
+            return false;
+        }
+        let (col, row) = (col as usize, row as usize);
+        if col >= self.width || row >= self.height {
+            return false;
+        }
+        self.alpha[row * self.width + col] >= Self::OPAQUE
+    }
+}
+
+pub struct ShapedWindowState {
+    /// Where the middle of the window is, once it has been dragged.
+    pub xy: Option<Point>,
+}
+
+impl Default for ShapedWindowState {
+    fn default() -> Self {
+        ShapedWindowState::new()
+    }
+}
+
+impl ShapedWindowState {
+    pub fn new() -> Self {
+        ShapedWindowState { xy: None }
+    }
+}
+
+/// The window itself, which takes the clicks and drags on its silhouette.
+/// Its content is set afterwards over it, as graphics for it, and keeps to
+/// the silhouette.
+#[derive(WidgetCommon)]
+pub struct ShapedWindow<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    window: &'a mut ShapedWindowState,
+    silhouette: &'a dyn Silhouette,
+}
+
+impl<'a> ShapedWindow<'a> {
+    pub fn new(window: &'a mut ShapedWindowState, silhouette: &'a dyn Silhouette) -> Self {
+        ShapedWindow {
+            common: widget::CommonBuilder::default(),
+            window,
+            silhouette,
+        }
+    }
+
+    /// Place the window where it was dragged to, or else where `place`
+    /// puts it.
+    pub fn placed(self, place: impl FnOnce(Self) -> Self) -> Self {
+        match self.window.xy {
+            Some(xy) => self.xy(xy),
+            None => place(self),
+        }
+    }
+}
+
+pub struct State {
+    /// The mask for the size it was sampled at, which is sampled again when
+    /// the size changes.
+    mask: AlphaMask,
+    dim: Dimensions,
+}
+
+impl<'a> Widget for ShapedWindow<'a> {
+    type State = State;
+    type Style = ();
+    type Event = ();
+
+    fn init_state(&self, _: widget::id::Generator) -> Self::State {
+        State {
+            mask: AlphaMask::default(),
+            dim: [0.0, 0.0],
+        }
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    fn is_over(&self) -> widget::IsOverFn {
+        is_over_silhouette
+    }
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs {
+            id,
+            state,
+            rect,
+            ui,
+            ..
+        } = args;
+        let ShapedWindow {
+            window, silhouette, ..
+        } = self;
+
+        if state.dim != rect.dim() {
+            let mask = AlphaMask::sample(silhouette, rect.dim());
+            state.update(|state| {
+                state.mask = mask;
+                state.dim = rect.dim();
+            });
+        }
+
+        // Moved in the next update, keeping the middle in the OS window.
+        let mut xy = rect.xy();
+        let mut dragged = false;
+        for drag in ui.widget_input(id).drags().left() {
+            xy[0] += drag.delta_xy[0];
+            xy[1] += drag.delta_xy[1];
+            dragged = true;
+        }
+        if dragged {
+            let [win_w, win_h] = ui.window_dim();
+            let [half_w, half_h] = [win_w / 2.0, win_h / 2.0];
+            window.xy = Some([
+                xy[0].max(-half_w).min(half_w),
+                xy[1].max(-half_h).min(half_h),
+            ]);
+        }
+    }
+}
+
+fn is_over_silhouette(container: &Container, xy: Point, _: &Theme) -> widget::IsOver {
+    let rect = container.rect;
+    let is_over = match container.state_and_style::<State, ()>() {
+        Some(unique) => unique
+            .state
+            .mask
+            .is_opaque_at([xy[0] - rect.x(), xy[1] - rect.y()]),
+        None => false,
+    };
+    widget::IsOver::Bool(is_over)
+}
@@ -0,0 +1,359 @@
+// Keyboard shortcuts for app commands.
+//
+// Most shortcuts match the `VirtualKeyCode`, so that Ctrl+O is the key
+// labelled O whatever the keyboard layout. Shortcuts which are about the
+// position of a key instead, like the key below Escape, match the scancode,
+// which doesn't change with the layout. Text input is not affected by any of
+// this and keeps using `ReceivedCharacter`.
+
+use crate::workspaces::LAYOUT_SLOT_COUNT;
+use std::collections::HashMap;
+use winit::event::{ModifiersState, ScanCode, VirtualKeyCode};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    OpenNotes,
+    OpenImage,
+    ExportView,
+    /// Save what was drawn last as a PNG, without asking where.
+    #[cfg(not(target_arch = "wasm32"))]
+    Screenshot,
+    /// Keep drawing the last frame, saved to a file, or go back to drawing
+    /// the UI.
+    #[cfg(not(target_arch = "wasm32"))]
+    ReplayLastFrame,
+    ToggleDebug,
+    /// Go to the next color blindness simulation, or turn it off.
+    CycleColorFilter,
+    ToggleAudit,
+    /// Bring back the windows which are out of view.
+    GatherWindows,
+    Find,
+    /// Switch to the workspace with the index.
+    SwitchWorkspace(usize),
+    /// Save the workspace of each window to the layout slot with the index.
+    SaveLayout(usize),
+    /// Go back to the layout saved in the slot with the index.
+    RecallLayout(usize),
+    NextWindow,
+    PreviousWindow,
+}
+
+impl Command {
+    pub fn name(self) -> &'static str {
+        match self {
+            Command::OpenNotes => "Open notes",
+            Command::OpenImage => "Open image",
+            Command::ExportView => "Export view",
+            #[cfg(not(target_arch = "wasm32"))]
+            Command::Screenshot => "Take screenshot",
+            #[cfg(not(target_arch = "wasm32"))]
+            Command::ReplayLastFrame => "Replay last frame",
+            Command::ToggleDebug => "Toggle windowing debug",
+            Command::CycleColorFilter => "Cycle color blindness simulation",
+            Command::ToggleAudit => "Toggle accessibility audit",
+            Command::GatherWindows => "Gather windows",
+            Command::Find => "Find in window",
+            Command::SwitchWorkspace(index) => WORKSPACE_NAMES[index],
+            Command::SaveLayout(index) => SAVE_LAYOUT_NAMES[index],
+            Command::RecallLayout(index) => RECALL_LAYOUT_NAMES[index],
+            Command::NextWindow => "Switch to next window",
+            Command::PreviousWindow => "Switch to previous window",
+        }
+    }
+}
+
+const WORKSPACE_NAMES: [&str; 9] = [
+    "Workspace 1",
+    "Workspace 2",
+    "Workspace 3",
+    "Workspace 4",
+    "Workspace 5",
+    "Workspace 6",
+    "Workspace 7",
+    "Workspace 8",
+    "Workspace 9",
+];
+
+const SAVE_LAYOUT_NAMES: [&str; LAYOUT_SLOT_COUNT] = [
+    "Save layout slot 1",
+    "Save layout slot 2",
+    "Save layout slot 3",
+];
+
+const RECALL_LAYOUT_NAMES: [&str; LAYOUT_SLOT_COUNT] = [
+    "Recall layout slot 1",
+    "Recall layout slot 2",
+    "Recall layout slot 3",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShortcutKey {
+    Virtual(VirtualKeyCode),
+    /// A physical key, independent of the keyboard layout.
+    Scancode(ScanCode),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Shortcut {
+    pub key: ShortcutKey,
+    pub modifiers: ModifiersState,
+    /// Whether holding the keys down runs the command again on every
+    /// auto-repeat.
+    pub repeat: bool,
+}
+
+impl Shortcut {
+    pub fn new(key: ShortcutKey, modifiers: ModifiersState) -> Self {
+        Shortcut {
+            key,
+            modifiers,
+            repeat: false,
+        }
+    }
+
+    pub fn repeating(mut self) -> Self {
+        self.repeat = true;
+        self
+    }
+}
+
+pub struct Shortcuts {
+    bindings: Vec<(Shortcut, Command)>,
+    modifiers: ModifiersState,
+    /// The virtual key last seen for each scancode, which gives the name of
+    /// the key in the current layout.
+    seen_keys: HashMap<ScanCode, VirtualKeyCode>,
+}
+
+impl Default for Shortcuts {
+    fn default() -> Self {
+        Shortcuts::new()
+    }
+}
+
+impl Shortcuts {
+    /// The key below Escape on most keyboards.
+    const SCANCODE_BELOW_ESCAPE: ScanCode = 0x29;
+
+    pub fn new() -> Self {
+        let ctrl = ModifiersState::CTRL;
+        let mut bindings = vec![
+            (
+                Shortcut::new(ShortcutKey::Virtual(VirtualKeyCode::O), ctrl),
+                Command::OpenNotes,
+            ),
+            (
+                Shortcut::new(
+                    ShortcutKey::Virtual(VirtualKeyCode::O),
+                    ctrl | ModifiersState::SHIFT,
+                ),
+                Command::OpenImage,
+            ),
+            (
+                Shortcut::new(ShortcutKey::Virtual(VirtualKeyCode::E), ctrl),
+                Command::ExportView,
+            ),
+            #[cfg(not(target_arch = "wasm32"))]
+            (
+                Shortcut::new(
+                    ShortcutKey::Virtual(VirtualKeyCode::F12),
+                    ModifiersState::empty(),
+                ),
+                Command::Screenshot,
+            ),
+            #[cfg(not(target_arch = "wasm32"))]
+            (
+                Shortcut::new(
+                    ShortcutKey::Virtual(VirtualKeyCode::R),
+                    ctrl | ModifiersState::SHIFT,
+                ),
+                Command::ReplayLastFrame,
+            ),
+            (
+                Shortcut::new(ShortcutKey::Scancode(Self::SCANCODE_BELOW_ESCAPE), ctrl),
+                Command::ToggleDebug,
+            ),
+            (
+                Shortcut::new(
+                    ShortcutKey::Virtual(VirtualKeyCode::B),
+                    ctrl | ModifiersState::SHIFT,
+                ),
+                Command::CycleColorFilter,
+            ),
+            (
+                Shortcut::new(
+                    ShortcutKey::Virtual(VirtualKeyCode::A),
+                    ctrl | ModifiersState::SHIFT,
+                ),
+                Command::ToggleAudit,
+            ),
+            (
+                Shortcut::new(
+                    ShortcutKey::Virtual(VirtualKeyCode::G),
+                    ctrl | ModifiersState::SHIFT,
+                ),
+                Command::GatherWindows,
+            ),
+            (
+                Shortcut::new(ShortcutKey::Virtual(VirtualKeyCode::F), ctrl),
+                Command::Find,
+            ),
+            // Held down to go through the windows.
+            (
+                Shortcut::new(
+                    ShortcutKey::Virtual(VirtualKeyCode::Tab),
+                    ModifiersState::ALT,
+                )
+                .repeating(),
+                Command::NextWindow,
+            ),
+            (
+                Shortcut::new(
+                    ShortcutKey::Virtual(VirtualKeyCode::Tab),
+                    ModifiersState::ALT | ModifiersState::SHIFT,
+                )
+                .repeating(),
+                Command::PreviousWindow,
+            ),
+        ];
+        // Ctrl+1 to Ctrl+9 for the workspaces.
+        let number_keys = [
+            VirtualKeyCode::Key1,
+            VirtualKeyCode::Key2,
+            VirtualKeyCode::Key3,
+            VirtualKeyCode::Key4,
+            VirtualKeyCode::Key5,
+            VirtualKeyCode::Key6,
+            VirtualKeyCode::Key7,
+            VirtualKeyCode::Key8,
+            VirtualKeyCode::Key9,
+        ];
+        for (index, &key) in number_keys.iter().enumerate() {
+            bindings.push((
+                Shortcut::new(ShortcutKey::Virtual(key), ctrl),
+                Command::SwitchWorkspace(index),
+            ));
+        }
+        // Ctrl+Shift+1 to 3 to save the layouts, and Ctrl+Alt+1 to 3 to
+        // recall them.
+        for (index, &key) in number_keys[..LAYOUT_SLOT_COUNT].iter().enumerate() {
+            bindings.push((
+                Shortcut::new(ShortcutKey::Virtual(key), ctrl | ModifiersState::SHIFT),
+                Command::SaveLayout(index),
+            ));
+            bindings.push((
+                Shortcut::new(ShortcutKey::Virtual(key), ctrl | ModifiersState::ALT),
+                Command::RecallLayout(index),
+            ));
+        }
+        Shortcuts {
+            bindings,
+            modifiers: ModifiersState::empty(),
+            seen_keys: HashMap::new(),
+        }
+    }
+
+    pub fn bindings(&self) -> &[(Shortcut, Command)] {
+        &self.bindings
+    }
+
+    /// The modifier keys currently held down.
+    pub fn modifiers(&self) -> ModifiersState {
+        self.modifiers
+    }
+
+    /// Returns the command if `event` triggers one, in which case the event
+    /// should not also be passed on to conrod.
+    ///
+    /// `is_repeat` tells whether the event is an auto-repeated key press, as
+    /// given by `input::KeyRepeat`. Repeats only trigger the shortcuts which
+    /// opted in with `Shortcut::repeating`.
+    pub fn handle_window_event(
+        &mut self,
+        event: &winit::event::WindowEvent,
+        is_repeat: bool,
+    ) -> Option<Command> {
+        let input = match event {
+            winit::event::WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = *modifiers;
+                return None;
+            }
+            winit::event::WindowEvent::KeyboardInput { input, .. } => input,
+            _ => return None,
+        };
+        if let Some(key) = input.virtual_keycode {
+            self.seen_keys.insert(input.scancode, key);
+        }
+        if input.state != winit::event::ElementState::Pressed {
+            return None;
+        }
+        self.bindings
+            .iter()
+            .find(|(shortcut, _)| {
+                shortcut.modifiers == self.modifiers
+                    && (shortcut.repeat || !is_repeat)
+                    && match shortcut.key {
+                        ShortcutKey::Virtual(key) => input.virtual_keycode == Some(key),
+                        ShortcutKey::Scancode(scancode) => input.scancode == scancode,
+                    }
+            })
+            .map(|&(_, command)| command)
+    }
+
+    /// A name for the shortcut like "Ctrl+Shift+O", using the current
+    /// keyboard layout for scancodes once the key has been pressed.
+    pub fn display_name(&self, shortcut: &Shortcut) -> String {
+        let mut name = String::new();
+        for &(modifier, modifier_name) in &[
+            (ModifiersState::CTRL, "Ctrl+"),
+            (ModifiersState::ALT, "Alt+"),
+            (ModifiersState::SHIFT, "Shift+"),
+            (ModifiersState::LOGO, "Super+"),
+        ] {
+            if shortcut.modifiers.contains(modifier) {
+                name.push_str(modifier_name);
+            }
+        }
+        let key = match shortcut.key {
+            ShortcutKey::Virtual(key) => key_name(key),
+            ShortcutKey::Scancode(scancode) => match self.seen_keys.get(&scancode) {
+                Some(&key) => key_name(key),
+                None => scancode_name(scancode)
+                    .map(str::to_owned)
+                    .unwrap_or_else(|| format!("Scancode {:#x}", scancode)),
+            },
+        };
+        name.push_str(&key);
+        name
+    }
+}
+
+fn key_name(key: VirtualKeyCode) -> String {
+    let name = format!("{:?}", key);
+    // `Key1` and so on.
+    if name.len() == 4 && name.starts_with("Key") {
+        name[3..].to_owned()
+    } else {
+        name
+    }
+}
+
+/// The names of the keys on a US layout, for scancodes which haven't been
+/// seen yet. The scancodes are the same on Windows and Linux, but not on the
+/// other platforms.
+fn scancode_name(scancode: ScanCode) -> Option<&'static str> {
+    if cfg!(any(target_os = "macos", target_arch = "wasm32")) {
+        return None;
+    }
+    const ROWS: [(ScanCode, &str); 4] = [
+        (0x02, "1234567890-="),
+        (0x10, "QWERTYUIOP[]"),
+        (0x1e, "ASDFGHJKL;'`"),
+        (0x2b, "\\ZXCVBNM,./"),
+    ];
+    ROWS.iter().find_map(|&(first, keys)| {
+        let index = scancode.checked_sub(first)? as usize;
+        keys.get(index..index + 1)
+    })
+}
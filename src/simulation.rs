@@ -0,0 +1,194 @@
+// Running the demo UI without a window or GL, for tests.
+//
+// `simulate` feeds a script of conrod inputs and app commands to a `Ui` and
+// calls `set_widgets` once per frame, like the event loops do, but nothing
+// is drawn. The config is neither loaded nor saved, and events posted through
+// the `Bridge` are handled at the start of the next frame.
+//
+// The UI runs the same on every run, except for the animations which use the
+// wall clock, like the game viewport while a key is held.
+
+use crate::bridge::{AppEvent, Bridge};
+use crate::config::Config;
+use crate::shortcuts::Command;
+use crate::{set_widgets, Ids, UiState};
+use conrod_core::event::Input;
+
+pub const SIM_W: f64 = 1280.0;
+pub const SIM_H: f64 = 800.0;
+
+pub enum Step {
+    /// An input as converted from a winit event.
+    Input(Input),
+    /// A command, as from a keyboard shortcut.
+    Command(Command),
+}
+
+/// The steps to run, each at the start of a frame.
+pub struct Script {
+    steps: Vec<(u32, Step)>,
+}
+
+impl Default for Script {
+    fn default() -> Self {
+        Script::new()
+    }
+}
+
+impl Script {
+    pub fn new() -> Self {
+        Script { steps: Vec::new() }
+    }
+
+    /// Run `step` at the start of `frame`, after the steps already added for
+    /// that frame.
+    pub fn at(mut self, frame: u32, step: Step) -> Self {
+        self.steps.push((frame, step));
+        self
+    }
+}
+
+pub struct Simulation {
+    pub ui: conrod_core::Ui,
+    ids: Ids,
+    pub state: UiState,
+    events: std::sync::mpsc::Receiver<AppEvent>,
+    /// The number of frames run so far.
+    pub frame: u32,
+}
+
+impl Default for Simulation {
+    fn default() -> Self {
+        Simulation::new()
+    }
+}
+
+impl Simulation {
+    pub fn new() -> Self {
+        let mut ui = conrod_core::UiBuilder::new([SIM_W, SIM_H]).build();
+        let ids = Ids::new(ui.widget_id_generator());
+        // The images are never drawn, so the id doesn't need a texture.
+        let rust_logo = conrod_core::image::Map::<()>::new().insert(());
+        let (bridge, events) = Bridge::headless();
+        let mut state = UiState::new(
+            conrod_example_shared::DemoApp::new(rust_logo),
+            bridge,
+            Config::default(),
+        );
+        state.persist_config = false;
+        Simulation {
+            ui,
+            ids,
+            state,
+            events,
+            frame: 0,
+        }
+    }
+
+    /// Run one frame with the steps given for it.
+    pub fn run_frame<'a>(&mut self, steps: impl IntoIterator<Item = &'a Step>) {
+        while let Ok(event) = self.events.try_recv() {
+            self.state.handle_app_event(event);
+        }
+        for step in steps {
+            match step {
+                Step::Input(input) => self.ui.handle_event(input.clone()),
+                Step::Command(command) => self.state.handle_command(*command),
+            }
+        }
+        set_widgets(self.ui.set_widgets(), &mut self.ids, 1.0, &mut self.state);
+        self.frame += 1;
+    }
+}
+
+/// Run `frames` frames of `script` on a new `Simulation`, which is returned
+/// for checking the resulting state.
+pub fn simulate(frames: u32, script: &Script) -> Simulation {
+    let mut sim = Simulation::new();
+    for frame in 0..frames {
+        let steps = script
+            .steps
+            .iter()
+            .filter(|(at, _)| *at == frame)
+            .map(|(_, step)| step);
+        sim.run_frame(steps);
+    }
+    sim
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn switching_workspace() {
+        let script = Script::new().at(1, Step::Command(Command::SwitchWorkspace(2)));
+        let sim = simulate(3, &script);
+        assert_eq!(sim.state.config.workspaces.current, 2);
+        assert_eq!(sim.state.workspace_menu, None);
+    }
+
+    #[test]
+    fn moving_window_to_workspace() {
+        let mut sim = simulate(2, &Script::new());
+        sim.state.config.workspaces.move_window("Notes", 1);
+        sim.run_frame(std::iter::empty());
+        assert!(!sim.state.config.workspaces.shows("Notes"));
+        assert!(sim.state.config.workspaces.is_used(1));
+        sim.run_frame(&[Step::Command(Command::SwitchWorkspace(1))]);
+        assert!(sim.state.config.workspaces.shows("Notes"));
+    }
+
+    #[test]
+    fn recalling_a_layout_slot() {
+        let mut sim = simulate(2, &Script::new());
+        sim.state.config.workspaces.move_window("Notes", 1);
+        sim.run_frame(&[Step::Command(Command::SaveLayout(0))]);
+        sim.state.config.workspaces.move_window("Notes", 2);
+        sim.run_frame(&[Step::Command(Command::SwitchWorkspace(2))]);
+        sim.run_frame(&[Step::Command(Command::RecallLayout(0))]);
+        assert_eq!(sim.state.config.workspaces.workspace_of("Notes"), 1);
+        assert_eq!(sim.state.config.workspaces.current, 0);
+    }
+
+    #[test]
+    fn window_switcher_picks_next_window_when_alt_is_up() {
+        let script = Script::new().at(2, Step::Command(Command::NextWindow));
+        let mut sim = simulate(3, &script);
+        // Alt isn't held, so the switcher picks and closes right away.
+        assert!(!sim.state.switcher.is_open());
+        let picked = sim.state.focus.focused();
+        assert!(picked.is_some());
+        assert!(sim.state.switcher.take_stale_snapshot().is_none());
+    }
+
+    #[test]
+    fn resizing_to_zero_and_back_keeps_the_windows() {
+        let notes_rect = |sim: &Simulation| {
+            sim.state
+                .window_tracker
+                .windows()
+                .find(|&(title, _)| title == "Notes")
+                .and_then(|(_, window)| sim.ui.rect_of(window))
+        };
+        let mut sim = simulate(2, &Script::new());
+        let before = notes_rect(&sim);
+        assert!(before.is_some());
+
+        sim.run_frame(&[Step::Input(Input::Resize(0.0, 0.0))]);
+        sim.run_frame(std::iter::empty());
+        assert!(!sim.state.compact.is_on());
+
+        sim.run_frame(&[Step::Input(Input::Resize(SIM_W, SIM_H))]);
+        sim.run_frame(std::iter::empty());
+        assert!(!sim.state.compact.is_on());
+        assert_eq!(notes_rect(&sim), before);
+    }
+
+    #[test]
+    fn find_request_lasts_one_frame() {
+        let script = Script::new().at(1, Step::Command(Command::Find));
+        let sim = simulate(2, &script);
+        assert!(!sim.state.find_requested);
+    }
+}
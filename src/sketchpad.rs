@@ -0,0 +1,252 @@
+// A sketchpad where the width of the strokes follows the pen pressure.
+//
+// The strokes are drawn with a single `Triangles` widget, which goes through
+// the renderer's plain triangles path. The mouse can draw too, with a fixed
+// width.
+
+use crate::pen::PenInput;
+use conrod_core::input::touch::Phase;
+use conrod_core::widget::triangles::Triangle;
+use conrod_core::{
+    color, widget, widget_ids, Colorable, Labelable, Point, Positionable, Rect, Sizeable, Widget,
+    WidgetCommon,
+};
+use std::collections::HashMap;
+
+struct StrokePoint {
+    /// Relative to the middle of the canvas.
+    xy: Point,
+    width: f64,
+}
+
+pub struct SketchpadState {
+    strokes: Vec<Vec<StrokePoint>>,
+    /// Pen input which hasn't been added to the strokes yet.
+    pending: Vec<PenInput>,
+    /// The stroke drawn by each pen or finger on the canvas, by touch id.
+    pen_strokes: HashMap<u64, usize>,
+    mouse_stroke: Option<usize>,
+    /// The pressure of the last pen input on the canvas.
+    last_pressure: Option<Option<f64>>,
+}
+
+impl Default for SketchpadState {
+    fn default() -> Self {
+        SketchpadState::new()
+    }
+}
+
+impl SketchpadState {
+    const MIN_WIDTH: f64 = 1.0;
+    const MAX_WIDTH: f64 = 12.0;
+    /// Used for the mouse and for touch screens without pressure.
+    const DEFAULT_PRESSURE: f64 = 0.4;
+
+    pub fn new() -> Self {
+        SketchpadState {
+            strokes: Vec::new(),
+            pending: Vec::new(),
+            pen_strokes: HashMap::new(),
+            mouse_stroke: None,
+            last_pressure: None,
+        }
+    }
+
+    /// Queue pen input, which is added to the strokes on the next update if
+    /// it is on the canvas.
+    pub fn handle_pen(&mut self, pen: PenInput) {
+        self.pending.push(pen);
+    }
+
+    fn width(pressure: Option<f64>) -> f64 {
+        let pressure = pressure.unwrap_or(Self::DEFAULT_PRESSURE);
+        Self::MIN_WIDTH + (Self::MAX_WIDTH - Self::MIN_WIDTH) * pressure
+    }
+
+    fn add_pending(&mut self, canvas: Rect) {
+        for pen in std::mem::replace(&mut self.pending, Vec::new()) {
+            let point = StrokePoint {
+                xy: clamp_to(canvas, [pen.xy[0] - canvas.x(), pen.xy[1] - canvas.y()]),
+                width: Self::width(pen.pressure),
+            };
+            let stroke = match pen.phase {
+                Phase::Start if canvas.is_over(pen.xy) => {
+                    self.strokes.push(Vec::new());
+                    self.pen_strokes.insert(pen.id, self.strokes.len() - 1);
+                    self.strokes.len() - 1
+                }
+                Phase::Start => continue,
+                Phase::Move => match self.pen_strokes.get(&pen.id) {
+                    Some(&stroke) => stroke,
+                    None => continue,
+                },
+                Phase::End => match self.pen_strokes.remove(&pen.id) {
+                    Some(stroke) => stroke,
+                    None => continue,
+                },
+                Phase::Cancel => {
+                    self.pen_strokes.remove(&pen.id);
+                    continue;
+                }
+            };
+            self.last_pressure = Some(pen.pressure);
+            self.strokes[stroke].push(point);
+        }
+    }
+}
+
+/// Keep `xy`, relative to the middle of `rect`, inside of `rect`.
+fn clamp_to(rect: Rect, xy: Point) -> Point {
+    let (w, h) = (rect.w() / 2.0, rect.h() / 2.0);
+    [xy[0].max(-w).min(w), xy[1].max(-h).min(h)]
+}
+
+/// Triangles for a stroke, in absolute coordinates: a quad between each pair
+/// of points and a square on each point to fill in the joins.
+fn stroke_triangles(stroke: &[StrokePoint], origin: Point, triangles: &mut Vec<Triangle<Point>>) {
+    let abs = |xy: Point| [origin[0] + xy[0], origin[1] + xy[1]];
+    for point in stroke {
+        let [x, y] = abs(point.xy);
+        let r = point.width / 2.0;
+        triangles.push(Triangle([[x - r, y - r], [x + r, y - r], [x + r, y + r]]));
+        triangles.push(Triangle([[x - r, y - r], [x + r, y + r], [x - r, y + r]]));
+    }
+    for pair in stroke.windows(2) {
+        let (a, b) = (abs(pair[0].xy), abs(pair[1].xy));
+        let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+        let len = (dx * dx + dy * dy).sqrt();
+        if len < 0.01 {
+            continue;
+        }
+        let normal = [-dy / len, dx / len];
+        let side = |xy: Point, width: f64, sign: f64| {
+            let r = width / 2.0 * sign;
+            [xy[0] + normal[0] * r, xy[1] + normal[1] * r]
+        };
+        let (a1, a2) = (side(a, pair[0].width, 1.0), side(a, pair[0].width, -1.0));
+        let (b1, b2) = (side(b, pair[1].width, 1.0), side(b, pair[1].width, -1.0));
+        triangles.push(Triangle([a1, a2, b1]));
+        triangles.push(Triangle([a2, b2, b1]));
+    }
+}
+
+#[derive(WidgetCommon)]
+pub struct SketchpadWidget<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    sketchpad: &'a mut SketchpadState,
+}
+
+impl<'a> SketchpadWidget<'a> {
+    pub fn new(sketchpad: &'a mut SketchpadState) -> Self {
+        SketchpadWidget {
+            common: widget::CommonBuilder::default(),
+            sketchpad,
+        }
+    }
+}
+
+widget_ids! {
+    pub struct SketchpadIds {
+        clear_button,
+        pressure_text,
+        canvas,
+        strokes,
+    }
+}
+
+impl<'a> Widget for SketchpadWidget<'a> {
+    type State = SketchpadIds;
+    type Style = ();
+    type Event = ();
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        SketchpadIds::new(id_gen)
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs {
+            id,
+            state,
+            ui,
+            rect,
+            ..
+        } = args;
+        let sketchpad = self.sketchpad;
+
+        const PAD: conrod_core::Scalar = 8.0;
+        const ROW_H: conrod_core::Scalar = 28.0;
+
+        for _click in widget::Button::new()
+            .label("Clear")
+            .label_font_size(14)
+            .w_h(80.0, ROW_H)
+            .top_left_with_margin_on(id, PAD)
+            .set(state.clear_button, ui)
+        {
+            sketchpad.strokes.clear();
+            sketchpad.pen_strokes.clear();
+            sketchpad.mouse_stroke = None;
+        }
+        let pressure_text = match sketchpad.last_pressure {
+            Some(Some(pressure)) => format!("Pressure: {:.2}", pressure),
+            Some(None) => "No pressure reported".to_owned(),
+            None => "Draw with a pen or the mouse".to_owned(),
+        };
+        widget::Text::new(&pressure_text)
+            .font_size(12)
+            .color(color::LIGHT_GREY)
+            .right_from(state.clear_button, PAD)
+            .set(state.pressure_text, ui);
+
+        let canvas_w = (rect.w() - PAD * 2.0).max(0.0);
+        let canvas_h = (rect.h() - ROW_H - PAD * 3.0).max(0.0);
+        widget::Rectangle::fill([canvas_w, canvas_h])
+            .color(color::WHITE)
+            .down_from(state.clear_button, PAD)
+            .set(state.canvas, ui);
+        let canvas = match ui.rect_of(state.canvas) {
+            Some(canvas) => canvas,
+            None => return,
+        };
+
+        sketchpad.add_pending(canvas);
+        // The drags are relative to the middle of the canvas.
+        for drag in ui.widget_input(state.canvas).drags().left() {
+            let stroke = match sketchpad.mouse_stroke {
+                Some(stroke) => stroke,
+                None => {
+                    sketchpad.strokes.push(Vec::new());
+                    sketchpad.mouse_stroke = Some(sketchpad.strokes.len() - 1);
+                    sketchpad.strokes.len() - 1
+                }
+            };
+            let width = SketchpadState::width(None);
+            let stroke = &mut sketchpad.strokes[stroke];
+            if stroke.is_empty() {
+                stroke.push(StrokePoint {
+                    xy: clamp_to(canvas, drag.from),
+                    width,
+                });
+            }
+            stroke.push(StrokePoint {
+                xy: clamp_to(canvas, drag.to),
+                width,
+            });
+        }
+        if !ui.global_input().current.mouse.buttons.left().is_down() {
+            sketchpad.mouse_stroke = None;
+        }
+
+        let mut triangles = Vec::new();
+        for stroke in &sketchpad.strokes {
+            stroke_triangles(stroke, canvas.xy(), &mut triangles);
+        }
+        widget::Triangles::single_color(color::BLACK, triangles)
+            .with_bounding_rect(canvas)
+            .graphics_for(state.canvas)
+            .set(state.strokes, ui);
+    }
+}
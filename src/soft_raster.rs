@@ -0,0 +1,347 @@
+// Drawing the UI on the CPU, for machines where no OpenGL context can be
+// made, and for tests which check the pixels drawn.
+//
+// `SoftRasterizer` fills the triangles of a `Frame` like the shaders of the
+// `Renderer` do: text takes its alpha from a copy of the glyph cache texture,
+// images are sampled from `SoftImage`s, and the colors are blended in linear
+// RGB, then encoded to sRGB like an sRGB framebuffer. Textures are sampled
+// without filtering, which only shows on scaled images, as the glyphs are
+// drawn at their size.
+//
+// The frames come from a `Tessellator` of its own, whose meshes carry the
+// glyphs to copy into the glyph cache here with `Mesh::upload_glyphs`.
+
+use crate::conrod_glow::{
+    Frame, FrameCommand, GlyphPixels, Mesh, Vertex, MODE_GEOMETRY, MODE_IMAGE, MODE_TEXT,
+};
+
+/// An image for the `SoftRasterizer`, in RGBA with sRGB colors and the top
+/// row first.
+#[derive(Clone, Debug)]
+pub struct SoftImage {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+impl SoftImage {
+    pub fn from_rgba(image: &::image::RgbaImage) -> Self {
+        SoftImage {
+            width: image.width(),
+            height: image.height(),
+            data: image.clone().into_raw(),
+        }
+    }
+}
+
+/// The part of the framebuffer drawn to, in pixels from the top left, with
+/// the ends excluded.
+#[derive(Clone, Copy, Debug)]
+struct Clip {
+    left: u32,
+    top: u32,
+    right: u32,
+    bottom: u32,
+}
+
+/// What the triangles of a command are textured with.
+#[derive(Clone, Copy)]
+enum Texture<'a> {
+    Glyphs,
+    Image(&'a SoftImage),
+}
+
+#[derive(Clone, Copy)]
+struct Point {
+    x: f32,
+    y: f32,
+}
+
+pub struct SoftRasterizer {
+    width: u32,
+    height: u32,
+    /// Linear RGBA, the top row first.
+    pixels: Vec<[f32; 4]>,
+    glyphs: GlyphPixels,
+    /// The linear values of the sRGB bytes.
+    to_linear: Vec<f32>,
+    /// The sRGB bytes of linear values, in `ENCODE_STEPS` steps.
+    to_srgb: Vec<u8>,
+}
+
+impl Default for SoftRasterizer {
+    fn default() -> Self {
+        SoftRasterizer::new()
+    }
+}
+
+impl SoftRasterizer {
+    const ENCODE_STEPS: usize = 4096;
+
+    pub fn new() -> Self {
+        let to_linear = (0..256)
+            .map(|byte| {
+                let c = byte as f32 / 255.0;
+                if c <= 0.04045 {
+                    c / 12.92
+                } else {
+                    ((c + 0.055) / 1.055).powf(2.4)
+                }
+            })
+            .collect();
+        let to_srgb = (0..Self::ENCODE_STEPS)
+            .map(|step| {
+                let c = step as f32 / (Self::ENCODE_STEPS - 1) as f32;
+                let c = if c <= 0.003_130_8 {
+                    c * 12.92
+                } else {
+                    1.055 * c.powf(1.0 / 2.4) - 0.055
+                };
+                (c * 255.0).round() as u8
+            })
+            .collect();
+        SoftRasterizer {
+            width: 0,
+            height: 0,
+            pixels: Vec::new(),
+            glyphs: GlyphPixels {
+                version: 0,
+                width: 0,
+                height: 0,
+                data: Vec::new(),
+            },
+            to_linear,
+            to_srgb,
+        }
+    }
+
+    /// Copy the glyphs cached while making `mesh`, before drawing its frame.
+    pub fn load_glyphs(&mut self, mesh: &Mesh) {
+        mesh.upload_glyphs(&mut self.glyphs);
+    }
+
+    /// Replace the glyph cache with `pixels`.
+    pub fn load_glyph_pixels(&mut self, pixels: GlyphPixels) {
+        self.glyphs = pixels;
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Clear the framebuffer to the linear color `clear` at the size of
+    /// `frame`, and draw it. The images of the frame are given by their
+    /// index in `images`, and the others are left out, like the commands
+    /// with vertices out of range.
+    pub fn draw(&mut self, frame: &Frame, images: &[&SoftImage], clear: [f32; 4]) {
+        let (width, height) = frame.framebuffer;
+        self.width = width;
+        self.height = height;
+        self.pixels.clear();
+        self.pixels.resize(width as usize * height as usize, clear);
+        let full = Clip {
+            left: 0,
+            top: 0,
+            right: width,
+            bottom: height,
+        };
+        let mut clip = full;
+        for command in &frame.commands {
+            let (texture, range) = match command {
+                FrameCommand::Scissor {
+                    left,
+                    bottom,
+                    width: w,
+                    height: h,
+                } => {
+                    let top = height.saturating_sub(bottom.saturating_add(*h));
+                    clip = Clip {
+                        left: (*left).min(width),
+                        top,
+                        right: left.saturating_add(*w).min(width),
+                        bottom: height.saturating_sub(*bottom),
+                    };
+                    continue;
+                }
+                FrameCommand::Plain(range) => (Texture::Glyphs, range),
+                FrameCommand::Image(index, range) => match images.get(*index as usize) {
+                    Some(image) => (Texture::Image(image), range),
+                    None => continue,
+                },
+            };
+            let vertices = match frame.vertices.get(range.clone()) {
+                Some(vertices) => vertices,
+                None => continue,
+            };
+            for triangle in vertices.chunks_exact(3) {
+                self.fill_triangle([&triangle[0], &triangle[1], &triangle[2]], clip, texture);
+            }
+        }
+    }
+
+    fn to_pixels(&self, vertex: &Vertex) -> Point {
+        Point {
+            x: (vertex.position[0] + 1.0) / 2.0 * self.width as f32,
+            y: (1.0 - vertex.position[1]) / 2.0 * self.height as f32,
+        }
+    }
+
+    fn fill_triangle(&mut self, triangle: [&Vertex; 3], clip: Clip, texture: Texture) {
+        let [a, mut b, mut c] = triangle;
+        let (pa, mut pb, mut pc) = (self.to_pixels(a), self.to_pixels(b), self.to_pixels(c));
+        let mut area = edge(pa, pb, pc);
+        if area == 0.0 || !area.is_finite() {
+            return;
+        }
+        // Go around every triangle the same way, so that the edges they
+        // share are drawn by one of them only.
+        if area < 0.0 {
+            std::mem::swap(&mut b, &mut c);
+            std::mem::swap(&mut pb, &mut pc);
+            area = -area;
+        }
+
+        let min_x = pa.x.min(pb.x).min(pc.x).floor().max(clip.left as f32) as u32;
+        let max_x = pa.x.max(pb.x).max(pc.x).ceil().min(clip.right as f32) as u32;
+        let min_y = pa.y.min(pb.y).min(pc.y).floor().max(clip.top as f32) as u32;
+        let max_y = pa.y.max(pb.y).max(pc.y).ceil().min(clip.bottom as f32) as u32;
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let p = Point {
+                    x: x as f32 + 0.5,
+                    y: y as f32 + 0.5,
+                };
+                let weights = [edge(pb, pc, p), edge(pc, pa, p), edge(pa, pb, p)];
+                let edges = [(pb, pc), (pc, pa), (pa, pb)];
+                let inside = weights
+                    .iter()
+                    .zip(&edges)
+                    .all(|(&w, &(from, to))| w > 0.0 || (w == 0.0 && owns_edge(from, to)));
+                if !inside {
+                    continue;
+                }
+                let [wa, wb, wc] = [weights[0] / area, weights[1] / area, weights[2] / area];
+                let mix = |fa: f32, fb: f32, fc: f32| fa * wa + fb * wb + fc * wc;
+                let u = mix(a.tex_coords[0], b.tex_coords[0], c.tex_coords[0]);
+                let v = mix(a.tex_coords[1], b.tex_coords[1], c.tex_coords[1]);
+                let mut color = [0.0; 4];
+                for (i, channel) in color.iter_mut().enumerate() {
+                    *channel = mix(a.color[i], b.color[i], c.color[i]);
+                }
+                if let Some(color) = self.shade(a.mode, color, (u, v), texture) {
+                    let index = y as usize * self.width as usize + x as usize;
+                    blend(&mut self.pixels[index], color);
+                }
+            }
+        }
+    }
+
+    /// The linear color of a fragment, like the fragment shaders of the
+    /// `Renderer`, `None` for modes they don't draw.
+    fn shade(
+        &self,
+        mode: u32,
+        color: [f32; 4],
+        (u, v): (f32, f32),
+        texture: Texture,
+    ) -> Option<[f32; 4]> {
+        match (mode, texture) {
+            (MODE_TEXT, _) => {
+                let glyphs = &self.glyphs;
+                let alpha = sample(glyphs.width, glyphs.height, u, v)
+                    .and_then(|(x, y)| glyphs.data.get((y * glyphs.width + x) as usize))
+                    .map_or(0.0, |&coverage| coverage as f32 / 255.0);
+                Some([color[0], color[1], color[2], color[3] * alpha])
+            }
+            // The texture's rows go from the bottom.
+            (MODE_IMAGE, Texture::Image(image)) => {
+                let (x, y) = sample(image.width, image.height, u, 1.0 - v)?;
+                let start = ((y * image.width + x) * 4) as usize;
+                let rgba = image.data.get(start..start + 4)?;
+                Some([
+                    self.to_linear[rgba[0] as usize],
+                    self.to_linear[rgba[1] as usize],
+                    self.to_linear[rgba[2] as usize],
+                    rgba[3] as f32 / 255.0,
+                ])
+            }
+            (MODE_IMAGE, Texture::Glyphs) => None,
+            (MODE_GEOMETRY, _) => Some(color),
+            _ => None,
+        }
+    }
+
+    /// The sRGB bytes of `linear`.
+    fn encode(&self, linear: [f32; 4]) -> [u8; 4] {
+        let steps = (Self::ENCODE_STEPS - 1) as f32;
+        let channel = |c: f32| self.to_srgb[(c.max(0.0).min(1.0) * steps).round() as usize];
+        let alpha = (linear[3].max(0.0).min(1.0) * 255.0).round() as u8;
+        [
+            channel(linear[0]),
+            channel(linear[1]),
+            channel(linear[2]),
+            alpha,
+        ]
+    }
+
+    /// The sRGB color at `(x, y)` from the top left, `None` outside of the
+    /// framebuffer.
+    pub fn pixel(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        Some(self.encode(self.pixels[(y * self.width + x) as usize]))
+    }
+
+    /// The framebuffer in sRGB RGBA, the top row first.
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        self.pixels
+            .iter()
+            .flat_map(|&p| self.encode(p).to_vec())
+            .collect()
+    }
+
+    /// The framebuffer as `0RGB` words in sRGB, the top row first, as
+    /// windows are given pixels without GL.
+    pub fn write_xrgb(&self, out: &mut Vec<u32>) {
+        out.clear();
+        out.extend(self.pixels.iter().map(|&p| {
+            let [r, g, b, _] = self.encode(p);
+            (r as u32) << 16 | (g as u32) << 8 | b as u32
+        }));
+    }
+}
+
+/// Twice the signed area of the triangle `a`, `b`, `p`, which is positive
+/// when `p` is on the same side of the edge `a` to `b` for every edge of a
+/// triangle going around the same way.
+fn edge(a: Point, b: Point, p: Point) -> f32 {
+    (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+
+/// Whether the pixels right on the edge `from` to `to` belong to the
+/// triangle. The triangle on the other side goes the other way along it,
+/// so exactly one of the two gets them.
+fn owns_edge(from: Point, to: Point) -> bool {
+    let (dx, dy) = (to.x - from.x, to.y - from.y);
+    dy > 0.0 || (dy == 0.0 && dx < 0.0)
+}
+
+/// The texel at the texture coordinates `(u, v)` of a texture of `width`
+/// by `height`, from the first row, `None` if the texture is empty.
+fn sample(width: u32, height: u32, u: f32, v: f32) -> Option<(u32, u32)> {
+    if width == 0 || height == 0 {
+        return None;
+    }
+    let texel = |t: f32, size: u32| ((t * size as f32).floor().max(0.0) as u32).min(size - 1);
+    Some((texel(u, width), texel(v, height)))
+}
+
+/// Blend `src` over `dst` like the `Renderer` does.
+fn blend(dst: &mut [f32; 4], src: [f32; 4]) {
+    let alpha = src[3];
+    for i in 0..3 {
+        dst[i] = src[i] * alpha + dst[i] * (1.0 - alpha);
+    }
+    dst[3] = alpha + dst[3] * (1.0 - alpha);
+}
@@ -0,0 +1,95 @@
+// Showing the frames of a `SoftRasterizer` in a window through softbuffer,
+// for when no OpenGL context can be made for it.
+//
+// softbuffer takes the window handle of a newer raw-window-handle than the
+// one winit 0.22 gives, so the handle is carried over by hand, for the
+// window systems both of them know.
+
+use crate::soft_raster::SoftRasterizer;
+use raw_window_handle_04 as rwh04;
+
+/// The handle of a window for softbuffer, which outlives the `SoftWindow`.
+struct WindowHandle(rwh04::RawWindowHandle);
+
+unsafe impl rwh04::HasRawWindowHandle for WindowHandle {
+    fn raw_window_handle(&self) -> rwh04::RawWindowHandle {
+        self.0
+    }
+}
+
+pub struct SoftWindow {
+    context: softbuffer::GraphicsContext<WindowHandle>,
+    buffer: Vec<u32>,
+}
+
+impl SoftWindow {
+    /// Draw to `window`, which has to outlive it.
+    pub fn new(window: &winit::window::Window) -> Result<Self, String> {
+        let handle = convert_handle(window).ok_or("the window system is not supported")?;
+        let context = unsafe { softbuffer::GraphicsContext::new(WindowHandle(handle)) }
+            .map_err(|err| err.to_string())?;
+        Ok(SoftWindow {
+            context,
+            buffer: Vec::new(),
+        })
+    }
+
+    /// Show what `rasterizer` drew last. softbuffer takes sizes of up to
+    /// 65535 pixels, which no window reaches.
+    pub fn present(&mut self, rasterizer: &SoftRasterizer) {
+        let (width, height) = rasterizer.size();
+        if width == 0 || height == 0 || width > 0xffff || height > 0xffff {
+            return;
+        }
+        rasterizer.write_xrgb(&mut self.buffer);
+        self.context
+            .set_buffer(&self.buffer, width as u16, height as u16);
+    }
+}
+
+fn convert_handle(window: &winit::window::Window) -> Option<rwh04::RawWindowHandle> {
+    use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+    match window.raw_window_handle() {
+        #[cfg(any(
+            target_os = "linux",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "netbsd",
+            target_os = "openbsd"
+        ))]
+        RawWindowHandle::Xlib(xlib) => {
+            let mut handle = rwh04::XlibHandle::empty();
+            handle.window = xlib.window;
+            handle.display = xlib.display;
+            Some(rwh04::RawWindowHandle::Xlib(handle))
+        }
+        #[cfg(any(
+            target_os = "linux",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "netbsd",
+            target_os = "openbsd"
+        ))]
+        RawWindowHandle::Wayland(wayland) => {
+            let mut handle = rwh04::WaylandHandle::empty();
+            handle.surface = wayland.surface;
+            handle.display = wayland.display;
+            Some(rwh04::RawWindowHandle::Wayland(handle))
+        }
+        #[cfg(target_os = "windows")]
+        RawWindowHandle::Windows(windows) => {
+            let mut handle = rwh04::Win32Handle::empty();
+            handle.hwnd = windows.hwnd;
+            handle.hinstance = windows.hinstance;
+            Some(rwh04::RawWindowHandle::Win32(handle))
+        }
+        #[cfg(target_os = "macos")]
+        RawWindowHandle::MacOS(macos) => {
+            let mut handle = rwh04::AppKitHandle::empty();
+            handle.ns_window = macos.ns_window;
+            handle.ns_view = macos.ns_view;
+            Some(rwh04::RawWindowHandle::AppKit(handle))
+        }
+        _ => None,
+    }
+}
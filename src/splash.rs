@@ -0,0 +1,32 @@
+// A loading screen drawn while the app starts up, before the fonts and the
+// renderer are ready.
+//
+// It is only a progress bar, drawn by clearing scissor rectangles, so that it
+// needs no shaders or textures and shows as soon as there is a context.
+
+use glow::HasContext;
+
+/// Draw the loading screen to the default framebuffer of `size` pixels, with
+/// `progress` from 0 to 1.
+pub fn draw(gl: &glow::Context, (width, height): (u32, u32), progress: f32) {
+    let bar_w = (width / 3) as i32;
+    let bar_h = (height / 100).max(4) as i32;
+    let x = (width as i32 - bar_w) / 2;
+    let y = (height as i32 - bar_h) / 2;
+    let filled = (bar_w as f32 * progress.max(0.0).min(1.0)) as i32;
+    unsafe {
+        gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+        gl.viewport(0, 0, width as i32, height as i32);
+        gl.disable(glow::SCISSOR_TEST);
+        gl.clear_color(0.1, 0.1, 0.1, 1.0);
+        gl.clear(glow::COLOR_BUFFER_BIT);
+        gl.enable(glow::SCISSOR_TEST);
+        gl.scissor(x, y, bar_w, bar_h);
+        gl.clear_color(0.3, 0.3, 0.3, 1.0);
+        gl.clear(glow::COLOR_BUFFER_BIT);
+        gl.scissor(x, y, filled, bar_h);
+        gl.clear_color(0.45, 0.62, 0.81, 1.0);
+        gl.clear(glow::COLOR_BUFFER_BIT);
+        gl.disable(glow::SCISSOR_TEST);
+    }
+}
@@ -0,0 +1,328 @@
+// A table widget with a fixed header, resizable and sortable columns and row
+// selection.
+//
+// The rows come from a `TableData`, and only the rows in view get widgets,
+// so the table stays fast with any number of rows.
+
+use conrod_core::{
+    color, widget, widget_ids, Borderable, Colorable, Labelable, Positionable, Sizeable, Widget,
+    WidgetCommon,
+};
+use std::cmp::Ordering;
+
+/// Provides the rows of a `Table`.
+pub trait TableData {
+    fn column_names(&self) -> &[&str];
+    fn row_count(&self) -> usize;
+    fn cell(&self, row: usize, column: usize) -> String;
+    /// Compare two rows by a column, for sorting.
+    fn compare(&self, a: usize, b: usize, column: usize) -> Ordering;
+}
+
+/// The state of a table kept between updates, like the column widths and
+/// the selection.
+pub struct TableState {
+    column_widths: Vec<f64>,
+    /// The column to sort by and whether the order is descending.
+    sort: Option<(usize, bool)>,
+    /// The rows in display order, by data index.
+    order: Vec<usize>,
+    /// The selected row, by data index.
+    pub selected: Option<usize>,
+    /// How far the rows are scrolled down, in points.
+    scroll: f64,
+    /// How far the columns are scrolled to the right, in points.
+    scroll_x: f64,
+    /// The height of the rows area in the last update.
+    view_h: f64,
+}
+
+impl Default for TableState {
+    fn default() -> Self {
+        TableState::new()
+    }
+}
+
+impl TableState {
+    const DEFAULT_COLUMN_W: f64 = 120.0;
+    const MIN_COLUMN_W: f64 = 40.0;
+    const ROW_H: f64 = 22.0;
+
+    pub fn new() -> Self {
+        TableState {
+            column_widths: Vec::new(),
+            sort: None,
+            order: Vec::new(),
+            selected: None,
+            scroll: 0.0,
+            scroll_x: 0.0,
+            view_h: 0.0,
+        }
+    }
+
+    /// The column sorted by and whether the order is descending.
+    pub fn sort(&self) -> Option<(usize, bool)> {
+        self.sort
+    }
+
+    /// The rows in display order, by data index. This is empty until the
+    /// first update.
+    pub fn display_order(&self) -> &[usize] {
+        &self.order
+    }
+
+    /// Scroll just enough for the row with the data index `row` to be in
+    /// view.
+    pub fn scroll_to(&mut self, row: usize) {
+        let position = match self.order.iter().position(|&index| index == row) {
+            Some(position) => position as f64,
+            None => return,
+        };
+        let top = position * Self::ROW_H;
+        let bottom = top + Self::ROW_H;
+        if top < self.scroll {
+            self.scroll = top;
+        } else if bottom > self.scroll + self.view_h {
+            self.scroll = bottom - self.view_h;
+        }
+    }
+
+    /// Sort by `column`, or reverse the order if already sorted by it.
+    pub fn sort_by(&mut self, column: usize) {
+        self.sort = match self.sort {
+            Some((sorted, descending)) if sorted == column => Some((column, !descending)),
+            _ => Some((column, false)),
+        };
+        self.order.clear();
+    }
+
+    /// Call when the rows of the `TableData` have changed, to sort them again.
+    pub fn invalidate(&mut self) {
+        self.order.clear();
+    }
+
+    fn update_order(&mut self, data: &dyn TableData) {
+        if self.order.len() == data.row_count() {
+            return;
+        }
+        self.order = (0..data.row_count()).collect();
+        if let Some((column, descending)) = self.sort {
+            self.order.sort_by(|&a, &b| {
+                let ordering = data.compare(a, b, column);
+                if descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
+        }
+    }
+}
+
+#[derive(WidgetCommon)]
+pub struct Table<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    table: &'a mut TableState,
+    data: &'a dyn TableData,
+    highlighted: &'a [usize],
+}
+
+impl<'a> Table<'a> {
+    pub fn new(table: &'a mut TableState, data: &'a dyn TableData) -> Self {
+        Table {
+            common: widget::CommonBuilder::default(),
+            table,
+            data,
+            highlighted: &[],
+        }
+    }
+
+    /// Highlight the rows with these data indices, like search matches.
+    pub fn highlighted(mut self, rows: &'a [usize]) -> Self {
+        self.highlighted = rows;
+        self
+    }
+}
+
+widget_ids! {
+    pub struct TableIds {
+        header,
+        body,
+        headers[],
+        resize_handles[],
+        rows[],
+        cells[],
+    }
+}
+
+impl<'a> Widget for Table<'a> {
+    type State = TableIds;
+    type Style = ();
+    /// The data index of a row which was clicked.
+    type Event = Option<usize>;
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        TableIds::new(id_gen)
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs {
+            id,
+            state,
+            ui,
+            rect,
+            ..
+        } = args;
+        let Table {
+            table,
+            data,
+            highlighted,
+            ..
+        } = self;
+
+        const HEADER_H: conrod_core::Scalar = 24.0;
+        const ROW_H: conrod_core::Scalar = TableState::ROW_H;
+        const CELL_PAD: conrod_core::Scalar = 4.0;
+        const HANDLE_W: conrod_core::Scalar = 6.0;
+
+        let columns = data.column_names();
+        table
+            .column_widths
+            .resize(columns.len(), TableState::DEFAULT_COLUMN_W);
+        table.update_order(data);
+
+        let body_h = (rect.h() - HEADER_H).max(0.0);
+        table.view_h = body_h;
+        // One more row than fits, for the one partly scrolled out at the top.
+        let visible_rows = ((body_h / ROW_H).ceil() as usize + 1).min(data.row_count());
+        if state.headers.len() < columns.len() || state.rows.len() < visible_rows {
+            let id_gen = &mut ui.widget_id_generator();
+            let cell_count = visible_rows * columns.len();
+            state.update(|state| {
+                state.headers.resize(columns.len(), id_gen);
+                state.resize_handles.resize(columns.len(), id_gen);
+                state.rows.resize(visible_rows, id_gen);
+                state.cells.resize(cell_count, id_gen);
+            });
+        }
+        let cell_id = |row: usize, col: usize| state.cells[row * columns.len() + col];
+
+        // Scroll with the wheel over any of the rows, and sideways when the
+        // columns are wider than the table.
+        let max_scroll = (data.row_count() as f64 * ROW_H - body_h).max(0.0);
+        let columns_w: f64 = table.column_widths.iter().sum();
+        let max_scroll_x = (columns_w - rect.w()).max(0.0);
+        for &widget_id in std::iter::once(&state.body).chain(state.rows.iter()) {
+            for scroll in ui.widget_input(widget_id).scrolls() {
+                table.scroll += scroll.y;
+                table.scroll_x += scroll.x;
+            }
+        }
+        table.scroll = table.scroll.max(0.0).min(max_scroll);
+        table.scroll_x = table.scroll_x.max(0.0).min(max_scroll_x);
+
+        // The header stays in place while the rows scroll. Clicking a header
+        // sorts by that column and dragging the handle on its right side
+        // resizes it.
+        widget::Canvas::new()
+            .color(color::DARK_CHARCOAL)
+            .border(0.0)
+            .crop_kids()
+            .w_h(rect.w(), HEADER_H)
+            .top_left_of(id)
+            .set(state.header, ui);
+        let mut x = -table.scroll_x;
+        for (col, name) in columns.iter().enumerate() {
+            let w = table.column_widths[col];
+            let label = match table.sort {
+                Some((sorted, false)) if sorted == col => format!("{} ^", name),
+                Some((sorted, true)) if sorted == col => format!("{} v", name),
+                _ => name.to_string(),
+            };
+            if widget::Button::new()
+                .label(&label)
+                .label_font_size(12)
+                .w_h(w, HEADER_H)
+                .top_left_with_margins_on(state.header, 0.0, x)
+                .set(state.headers[col], ui)
+                .was_clicked()
+            {
+                table.sort_by(col);
+            }
+            widget::Rectangle::fill([HANDLE_W, HEADER_H])
+                .color(color::DARK_GREY)
+                .top_left_with_margins_on(state.header, 0.0, x + w - HANDLE_W)
+                .set(state.resize_handles[col], ui);
+            for drag in ui.widget_input(state.resize_handles[col]).drags().left() {
+                let w = &mut table.column_widths[col];
+                *w = (*w + drag.delta_xy[0]).max(TableState::MIN_COLUMN_W);
+            }
+            x += table.column_widths[col];
+        }
+
+        widget::Canvas::new()
+            .color(color::CHARCOAL)
+            .border(0.0)
+            .crop_kids()
+            .w_h(rect.w(), body_h)
+            .top_left_with_margins_on(id, HEADER_H, 0.0)
+            .set(state.body, ui);
+
+        let first = (table.scroll / ROW_H) as usize;
+        let offset = table.scroll - first as f64 * ROW_H;
+        let mut clicked = None;
+        for slot in 0..visible_rows {
+            let row_id = state.rows[slot];
+            let index = match table.order.get(first + slot) {
+                Some(&index) => index,
+                None => {
+                    // Keep the spare row out of view.
+                    widget::Rectangle::fill([0.0, 0.0])
+                        .top_left_of(state.body)
+                        .set(row_id, ui);
+                    for col in 0..columns.len() {
+                        widget::Text::new("")
+                            .top_left_of(row_id)
+                            .set(cell_id(slot, col), ui);
+                    }
+                    continue;
+                }
+            };
+            let row_color = if table.selected == Some(index) {
+                color::LIGHT_BLUE.with_alpha(0.5)
+            } else if highlighted.contains(&index) {
+                color::DARK_YELLOW.with_alpha(0.6)
+            } else if (first + slot) % 2 == 0 {
+                color::CHARCOAL
+            } else {
+                color::DARK_CHARCOAL
+            };
+            widget::Rectangle::fill([columns_w.max(rect.w()), ROW_H])
+                .color(row_color)
+                .top_left_with_margins_on(state.body, slot as f64 * ROW_H - offset, -table.scroll_x)
+                .set(row_id, ui);
+            if ui.widget_input(row_id).clicks().left().next().is_some() {
+                table.selected = Some(index);
+                clicked = Some(index);
+            }
+            let mut cell_x = 0.0;
+            for col in 0..columns.len() {
+                let w = table.column_widths[col];
+                widget::Text::new(&data.cell(index, col))
+                    .font_size(12)
+                    .color(color::WHITE)
+                    .no_line_wrap()
+                    .w((w - CELL_PAD * 2.0).max(0.0))
+                    .mid_left_with_margin_on(row_id, cell_x + CELL_PAD)
+                    .graphics_for(row_id)
+                    .set(cell_id(slot, col), ui);
+                cell_x += w;
+            }
+        }
+
+        clicked
+    }
+}
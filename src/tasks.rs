@@ -0,0 +1,366 @@
+// Background tasks with progress, listed in the "Tasks" window.
+//
+// A task runs on a thread on native, or in JS callbacks on wasm, and reports
+// back with a `TaskHandle`, which posts `AppEvent::Task` through the
+// `Bridge`. The `TaskManager` keeps the list of tasks for the UI.
+
+use crate::bridge::{AppEvent, Bridge};
+use conrod_core::{
+    color, widget, widget_ids, Colorable, Labelable, Positionable, Sizeable, Widget, WidgetCommon,
+};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+pub type TaskId = u64;
+
+#[derive(Debug)]
+pub enum TaskEvent {
+    Progress {
+        id: TaskId,
+        progress: f32,
+    },
+    Finished {
+        id: TaskId,
+        result: Result<(), String>,
+    },
+}
+
+/// Given to the code running a task, to report its progress and check
+/// whether it has been cancelled.
+#[derive(Clone)]
+pub struct TaskHandle {
+    id: TaskId,
+    bridge: Bridge,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl TaskHandle {
+    /// Report the progress, from 0.0 to 1.0.
+    pub fn set_progress(&self, progress: f32) {
+        self.bridge.send(AppEvent::Task(TaskEvent::Progress {
+            id: self.id,
+            progress,
+        }));
+    }
+
+    /// Whether the user has cancelled the task, in which case it should stop
+    /// and call `finish` as soon as it can.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub fn finish(self, result: Result<(), String>) {
+        self.bridge.send(AppEvent::Task(TaskEvent::Finished {
+            id: self.id,
+            result,
+        }));
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TaskStatus {
+    Running,
+    Done,
+    Failed(String),
+    Cancelled,
+}
+
+pub struct Task {
+    pub id: TaskId,
+    pub name: String,
+    pub progress: f32,
+    pub status: TaskStatus,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Task {
+    /// Whether cancelling has been asked for but the task hasn't stopped
+    /// yet.
+    pub fn is_cancelling(&self) -> bool {
+        self.status == TaskStatus::Running && self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+pub struct TaskManager {
+    tasks: Vec<Task>,
+    next_id: TaskId,
+    bridge: Bridge,
+}
+
+impl TaskManager {
+    pub fn new(bridge: Bridge) -> Self {
+        TaskManager {
+            tasks: Vec::new(),
+            next_id: 0,
+            bridge,
+        }
+    }
+
+    /// Add a task to the list. The returned handle goes to the code running
+    /// it.
+    pub fn start(&mut self, name: impl Into<String>) -> TaskHandle {
+        let id = self.next_id;
+        self.next_id += 1;
+        let cancelled = Arc::new(AtomicBool::new(false));
+        self.tasks.push(Task {
+            id,
+            name: name.into(),
+            progress: 0.0,
+            status: TaskStatus::Running,
+            cancelled: cancelled.clone(),
+        });
+        TaskHandle {
+            id,
+            bridge: self.bridge.clone(),
+            cancelled,
+        }
+    }
+
+    pub fn cancel(&mut self, id: TaskId) {
+        if let Some(task) = self.tasks.iter().find(|task| task.id == id) {
+            task.cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+
+    pub fn tasks(&self) -> &[Task] {
+        &self.tasks
+    }
+
+    /// Remove the tasks which are no longer running.
+    pub fn clear_finished(&mut self) {
+        self.tasks.retain(|task| task.status == TaskStatus::Running);
+    }
+
+    /// Update a task from its handle. Returns the task if it has just
+    /// finished.
+    pub fn handle_event(&mut self, event: TaskEvent) -> Option<&Task> {
+        match event {
+            TaskEvent::Progress { id, progress } => {
+                if let Some(task) = self.tasks.iter_mut().find(|task| task.id == id) {
+                    task.progress = progress.max(0.0).min(1.0);
+                }
+                None
+            }
+            TaskEvent::Finished { id, result } => {
+                let task = self.tasks.iter_mut().find(|task| task.id == id)?;
+                task.status = match result {
+                    Err(err) => TaskStatus::Failed(err),
+                    Ok(()) if task.cancelled.load(Ordering::Relaxed) => TaskStatus::Cancelled,
+                    Ok(()) => {
+                        task.progress = 1.0;
+                        TaskStatus::Done
+                    }
+                };
+                Some(task)
+            }
+        }
+    }
+}
+
+/// A task which does nothing for a few seconds, to try out the Tasks window.
+pub fn run_demo_task(task: TaskHandle) {
+    const STEPS: u32 = 50;
+    const STEP_MS: u32 = 100;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    std::thread::spawn(move || {
+        for step in 1..=STEPS {
+            if task.is_cancelled() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(STEP_MS as u64));
+            task.set_progress(step as f32 / STEPS as f32);
+        }
+        task.finish(Ok(()));
+    });
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+        use wasm_bindgen::{closure::Closure, JsCast};
+
+        let window = web_sys::window().unwrap();
+        // The closure clears its own interval when done, so it needs to know
+        // the interval id, which is only known after it is set.
+        let interval = Rc::new(RefCell::new(None));
+        let mut step = 0;
+        let mut pending = Some(task.clone());
+        let on_interval = {
+            let interval = interval.clone();
+            let window = window.clone();
+            Closure::wrap(Box::new(move || {
+                step += 1;
+                let done = match &pending {
+                    Some(task) if !task.is_cancelled() && step <= STEPS => {
+                        task.set_progress(step as f32 / STEPS as f32);
+                        step == STEPS
+                    }
+                    Some(_) => true,
+                    None => return,
+                };
+                if done {
+                    pending.take().unwrap().finish(Ok(()));
+                    if let Some(id) = interval.borrow_mut().take() {
+                        window.clear_interval_with_handle(id);
+                    }
+                }
+            }) as Box<dyn FnMut()>)
+        };
+        match window.set_interval_with_callback_and_timeout_and_arguments_0(
+            on_interval.as_ref().unchecked_ref(),
+            STEP_MS as i32,
+        ) {
+            Ok(id) => *interval.borrow_mut() = Some(id),
+            Err(_) => task.finish(Err("cannot start a timer".to_owned())),
+        }
+        // The closure lives as long as the interval, which it clears itself.
+        on_interval.forget();
+    }
+}
+
+#[derive(WidgetCommon)]
+pub struct TasksWidget<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    tasks: &'a mut TaskManager,
+}
+
+impl<'a> TasksWidget<'a> {
+    pub fn new(tasks: &'a mut TaskManager) -> Self {
+        TasksWidget {
+            common: widget::CommonBuilder::default(),
+            tasks,
+        }
+    }
+}
+
+widget_ids! {
+    pub struct TasksIds {
+        demo_button,
+        clear_button,
+        empty_text,
+        names[],
+        bar_backs[],
+        bars[],
+        cancel_buttons[],
+    }
+}
+
+impl<'a> Widget for TasksWidget<'a> {
+    type State = TasksIds;
+    type Style = ();
+    /// Whether the demo task button was clicked.
+    type Event = bool;
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        TasksIds::new(id_gen)
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs {
+            id,
+            state,
+            ui,
+            rect,
+            ..
+        } = args;
+        let tasks = self.tasks;
+
+        const PAD: conrod_core::Scalar = 8.0;
+        const ROW_H: conrod_core::Scalar = 28.0;
+        const BAR_H: conrod_core::Scalar = 10.0;
+        const CANCEL_W: conrod_core::Scalar = 70.0;
+
+        let run_demo = widget::Button::new()
+            .label("Run demo task")
+            .label_font_size(14)
+            .w_h(130.0, ROW_H)
+            .top_left_with_margin_on(id, PAD)
+            .set(state.demo_button, ui)
+            .was_clicked();
+        if widget::Button::new()
+            .label("Clear finished")
+            .label_font_size(14)
+            .w_h(120.0, ROW_H)
+            .right_from(state.demo_button, PAD)
+            .set(state.clear_button, ui)
+            .was_clicked()
+        {
+            tasks.clear_finished();
+        }
+
+        let count = tasks.tasks().len();
+        if state.names.len() < count {
+            let id_gen = &mut ui.widget_id_generator();
+            state.update(|state| {
+                state.names.resize(count, id_gen);
+                state.bar_backs.resize(count, id_gen);
+                state.bars.resize(count, id_gen);
+                state.cancel_buttons.resize(count, id_gen);
+            });
+        }
+        if count == 0 {
+            widget::Text::new("No tasks")
+                .font_size(12)
+                .color(color::LIGHT_GREY)
+                .down_from(state.demo_button, PAD)
+                .set(state.empty_text, ui);
+        }
+
+        // Each task has its name and status on one line and a progress bar
+        // with a cancel button on the next.
+        let bar_w = (rect.w() - CANCEL_W - PAD * 3.0).max(0.0);
+        let mut cancel = None;
+        let mut prev = state.demo_button;
+        for (i, task) in tasks.tasks().iter().enumerate() {
+            let status = match &task.status {
+                TaskStatus::Running if task.is_cancelling() => "cancelling".to_owned(),
+                TaskStatus::Running => format!("{:.0}%", task.progress * 100.0),
+                TaskStatus::Done => "done".to_owned(),
+                TaskStatus::Failed(err) => format!("failed: {}", err),
+                TaskStatus::Cancelled => "cancelled".to_owned(),
+            };
+            widget::Text::new(&format!("{} ({})", task.name, status))
+                .font_size(12)
+                .color(color::WHITE)
+                .down_from(prev, PAD)
+                .x_align_to(state.demo_button, conrod_core::position::Align::Start)
+                .set(state.names[i], ui);
+            widget::Rectangle::fill([bar_w, BAR_H])
+                .color(color::DARK_GREY)
+                .down_from(state.names[i], PAD / 2.0)
+                .set(state.bar_backs[i], ui);
+            let bar_color = match task.status {
+                TaskStatus::Failed(_) => color::LIGHT_RED,
+                TaskStatus::Cancelled => color::GREY,
+                _ => color::LIGHT_GREEN,
+            };
+            widget::Rectangle::fill([bar_w * task.progress as f64, BAR_H])
+                .color(bar_color)
+                .mid_left_of(state.bar_backs[i])
+                .graphics_for(state.bar_backs[i])
+                .set(state.bars[i], ui);
+            prev = state.bar_backs[i];
+            if task.status == TaskStatus::Running
+                && widget::Button::new()
+                    .label("Cancel")
+                    .label_font_size(12)
+                    .w_h(CANCEL_W, ROW_H * 0.75)
+                    .right_from(state.bar_backs[i], PAD)
+                    .enabled(!task.is_cancelling())
+                    .set(state.cancel_buttons[i], ui)
+                    .was_clicked()
+            {
+                cancel = Some(task.id);
+            }
+        }
+        if let Some(id) = cancel {
+            tasks.cancel(id);
+        }
+
+        run_demo
+    }
+}
@@ -0,0 +1,522 @@
+// A terminal running the user's shell in a pty, on the desktop only.
+//
+// A background thread reads the shell's output and posts it as
+// `AppEvent::Terminal` through the `Bridge`. `vte` parses it into a grid of
+// cells, which only knows the most common escape sequences: moving the
+// cursor, erasing and the 16 colors. The grid is drawn by a `GlyphGrid`, so
+// only the rows which changed are copied into the UI.
+
+use crate::bridge::{AppEvent, Bridge};
+use crate::glyph_grid::{self, GlyphGrid, GridCell};
+use crate::raw_input::RawInput;
+use conrod_core::event::Input;
+use conrod_core::input::{Button, Key, ModifierKey};
+use conrod_core::{
+    color, widget, widget_ids, Colorable, Positionable, Sizeable, Widget, WidgetCommon,
+};
+use std::io::{Read, Write};
+
+#[derive(Debug)]
+pub enum TerminalEvent {
+    Output(Vec<u8>),
+    /// The shell has exited, or reading its output failed.
+    Exited,
+}
+
+const FONT_SIZE: conrod_core::FontSize = 13;
+
+/// The 8 normal colors, then the 8 bright ones.
+const PALETTE: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 49, 49),
+    (13, 188, 121),
+    (229, 229, 16),
+    (36, 114, 200),
+    (188, 63, 188),
+    (17, 168, 205),
+    (229, 229, 229),
+    (102, 102, 102),
+    (241, 76, 76),
+    (35, 209, 139),
+    (245, 245, 67),
+    (59, 142, 234),
+    (214, 112, 214),
+    (41, 184, 219),
+    (255, 255, 255),
+];
+
+fn palette(index: usize) -> color::Color {
+    let (r, g, b) = PALETTE[index];
+    color::rgb_bytes(r, g, b)
+}
+
+fn default_color() -> color::Color {
+    palette(7)
+}
+
+/// The character grid and the cursor, which `vte` writes to.
+struct Screen {
+    cols: usize,
+    rows: usize,
+    cells: Vec<GridCell>,
+    /// Which rows changed since they were last given to the `GlyphGrid`.
+    dirty: Vec<bool>,
+    cursor: (usize, usize),
+    color: color::Color,
+}
+
+impl Screen {
+    fn new(cols: usize, rows: usize) -> Self {
+        Screen {
+            cols,
+            rows,
+            cells: vec![GridCell::blank(default_color()); cols * rows],
+            dirty: vec![true; rows],
+            cursor: (0, 0),
+            color: default_color(),
+        }
+    }
+
+    /// Resize the grid, keeping the text at the top left and the cursor
+    /// within the grid.
+    fn resize(&mut self, cols: usize, rows: usize) {
+        let mut cells = vec![GridCell::blank(default_color()); cols * rows];
+        for row in 0..rows.min(self.rows) {
+            let n = cols.min(self.cols);
+            cells[row * cols..row * cols + n]
+                .copy_from_slice(&self.cells[row * self.cols..row * self.cols + n]);
+        }
+        self.cells = cells;
+        self.cols = cols;
+        self.rows = rows;
+        self.dirty = vec![true; rows];
+        self.cursor = (self.cursor.0.min(cols - 1), self.cursor.1.min(rows - 1));
+    }
+
+    /// Blank the cells from `start` to `end` of the grid, row by row.
+    fn erase(&mut self, start: usize, end: usize) {
+        let end = end.min(self.cells.len());
+        if start >= end {
+            return;
+        }
+        for cell in &mut self.cells[start..end] {
+            *cell = GridCell::blank(default_color());
+        }
+        for row in start / self.cols..=(end - 1) / self.cols {
+            self.dirty[row] = true;
+        }
+    }
+
+    fn line_feed(&mut self) {
+        if self.cursor.1 + 1 < self.rows {
+            self.cursor.1 += 1;
+        } else {
+            // Scroll up, which changes every row.
+            self.cells.drain(..self.cols);
+            let blank = GridCell::blank(default_color());
+            self.cells.extend(std::iter::repeat(blank).take(self.cols));
+            for flag in &mut self.dirty {
+                *flag = true;
+            }
+        }
+    }
+
+    fn select_graphic_rendition(&mut self, params: &[i64]) {
+        if params.is_empty() {
+            self.color = default_color();
+        }
+        for &param in params {
+            match param {
+                0 | 39 => self.color = default_color(),
+                30..=37 => self.color = palette((param - 30) as usize),
+                90..=97 => self.color = palette((param - 90 + 8) as usize),
+                // Extended colors take more parameters, which could be
+                // mistaken for other attributes.
+                38 | 48 => break,
+                _ => {}
+            }
+        }
+    }
+}
+
+impl vte::Perform for Screen {
+    fn print(&mut self, ch: char) {
+        let (mut col, _) = self.cursor;
+        if col >= self.cols {
+            col = 0;
+            self.line_feed();
+        }
+        let row = self.cursor.1;
+        self.cells[row * self.cols + col] = GridCell {
+            ch,
+            color: self.color,
+        };
+        self.dirty[row] = true;
+        self.cursor.0 = col + 1;
+    }
+
+    fn execute(&mut self, byte: u8) {
+        match byte {
+            b'\n' | 0x0b | 0x0c => self.line_feed(),
+            b'\r' => self.cursor.0 = 0,
+            0x08 => self.cursor.0 = self.cursor.0.min(self.cols - 1).saturating_sub(1),
+            b'\t' => self.cursor.0 = ((self.cursor.0 / 8 + 1) * 8).min(self.cols - 1),
+            _ => {}
+        }
+    }
+
+    fn hook(&mut self, _params: &[i64], _intermediates: &[u8], _ignore: bool, _action: char) {}
+
+    fn put(&mut self, _byte: u8) {}
+
+    fn unhook(&mut self) {}
+
+    fn osc_dispatch(&mut self, _params: &[&[u8]], _bell_terminated: bool) {}
+
+    fn csi_dispatch(&mut self, params: &[i64], intermediates: &[u8], ignore: bool, action: char) {
+        // Private modes, like hiding the cursor, aren't supported.
+        if ignore || !intermediates.is_empty() {
+            return;
+        }
+        // Parameters are 1-based, and 0 or missing means the default.
+        let param = |i: usize, default: usize| match params.get(i) {
+            Some(&p) if p > 0 => p as usize,
+            _ => default,
+        };
+        let (col, row) = self.cursor;
+        let (last_col, last_row) = (self.cols - 1, self.rows - 1);
+        match action {
+            'A' => self.cursor.1 = row.saturating_sub(param(0, 1)),
+            'B' => self.cursor.1 = (row + param(0, 1)).min(last_row),
+            'C' => self.cursor.0 = (col + param(0, 1)).min(last_col),
+            'D' => self.cursor.0 = col.min(last_col).saturating_sub(param(0, 1)),
+            'G' => self.cursor.0 = (param(0, 1) - 1).min(last_col),
+            'd' => self.cursor.1 = (param(0, 1) - 1).min(last_row),
+            'H' | 'f' => {
+                self.cursor = (
+                    (param(1, 1) - 1).min(last_col),
+                    (param(0, 1) - 1).min(last_row),
+                )
+            }
+            'J' => {
+                let at = row * self.cols + col.min(last_col);
+                match params.get(0).copied().unwrap_or(0) {
+                    0 => self.erase(at, self.cells.len()),
+                    1 => self.erase(0, at + 1),
+                    _ => self.erase(0, self.cells.len()),
+                }
+            }
+            'K' => {
+                let start = row * self.cols;
+                let at = start + col.min(last_col);
+                match params.get(0).copied().unwrap_or(0) {
+                    0 => self.erase(at, start + self.cols),
+                    1 => self.erase(start, at + 1),
+                    _ => self.erase(start, start + self.cols),
+                }
+            }
+            'm' => self.select_graphic_rendition(params),
+            _ => {}
+        }
+    }
+
+    fn esc_dispatch(&mut self, _intermediates: &[u8], _ignore: bool, _byte: u8) {}
+}
+
+struct Pty {
+    master: Box<dyn portable_pty::MasterPty + Send>,
+    child: Box<dyn portable_pty::Child>,
+}
+
+impl Pty {
+    fn spawn(cols: usize, rows: usize, bridge: Bridge) -> Result<Self, String> {
+        let pair = portable_pty::native_pty_system()
+            .openpty(pty_size(cols, rows))
+            .map_err(|e| e.to_string())?;
+        let shell = if cfg!(windows) {
+            std::env::var("COMSPEC").unwrap_or_else(|_| "cmd.exe".to_owned())
+        } else {
+            std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_owned())
+        };
+        let mut command = portable_pty::CommandBuilder::new(shell);
+        command.env("TERM", "ansi");
+        let child = pair
+            .slave
+            .spawn_command(command)
+            .map_err(|e| e.to_string())?;
+        let mut reader = pair.master.try_clone_reader().map_err(|e| e.to_string())?;
+        std::thread::spawn(move || {
+            let mut buf = [0; 4096];
+            loop {
+                match reader.read(&mut buf) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => {
+                        let output = buf[..n].to_vec();
+                        if !bridge.send(AppEvent::Terminal(TerminalEvent::Output(output))) {
+                            return;
+                        }
+                    }
+                }
+            }
+            bridge.send(AppEvent::Terminal(TerminalEvent::Exited));
+        });
+        Ok(Pty {
+            master: pair.master,
+            child,
+        })
+    }
+}
+
+impl Drop for Pty {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+    }
+}
+
+fn pty_size(cols: usize, rows: usize) -> portable_pty::PtySize {
+    portable_pty::PtySize {
+        rows: rows as u16,
+        cols: cols as u16,
+        pixel_width: 0,
+        pixel_height: 0,
+    }
+}
+
+pub struct TerminalState {
+    screen: Screen,
+    parser: vte::Parser,
+    /// `None` until the window is first shown, and after the shell exits.
+    pty: Option<Pty>,
+    /// Shown below the grid, like why the shell couldn't be started.
+    status: String,
+    /// Set when the shell has exited, until the user asks for a new one.
+    exited: bool,
+}
+
+impl Default for TerminalState {
+    fn default() -> Self {
+        TerminalState::new()
+    }
+}
+
+impl TerminalState {
+    pub fn new() -> Self {
+        TerminalState {
+            screen: Screen::new(80, 24),
+            parser: vte::Parser::new(),
+            pty: None,
+            status: String::new(),
+            exited: false,
+        }
+    }
+
+    pub fn handle_event(&mut self, event: TerminalEvent) {
+        match event {
+            TerminalEvent::Output(bytes) => {
+                for byte in bytes {
+                    self.parser.advance(&mut self.screen, byte);
+                }
+            }
+            TerminalEvent::Exited => {
+                self.pty = None;
+                self.exited = true;
+                self.status = "The shell has exited. Press Enter to start a new one.".to_owned();
+            }
+        }
+    }
+
+    fn start(&mut self, bridge: &Bridge) {
+        self.exited = false;
+        self.parser = vte::Parser::new();
+        match Pty::spawn(self.screen.cols, self.screen.rows, bridge.clone()) {
+            Ok(pty) => {
+                self.pty = Some(pty);
+                self.status = "Click the terminal to type.".to_owned();
+            }
+            Err(err) => {
+                self.exited = true;
+                self.status = format!("Cannot start the shell: {}", err);
+            }
+        }
+    }
+
+    fn resize(&mut self, cols: usize, rows: usize) {
+        if (cols, rows) == (self.screen.cols, self.screen.rows) {
+            return;
+        }
+        self.screen.resize(cols, rows);
+        if let Some(pty) = &self.pty {
+            let _ = pty.master.resize(pty_size(cols, rows));
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        if let Some(pty) = &mut self.pty {
+            let _ = pty.master.write_all(bytes);
+        }
+    }
+}
+
+/// The bytes a terminal sends for a key which doesn't produce text.
+fn key_bytes(key: Key, modifiers: ModifierKey) -> Option<&'static [u8]> {
+    const CTRL_LETTERS: [&[u8]; 26] = [
+        b"\x01", b"\x02", b"\x03", b"\x04", b"\x05", b"\x06", b"\x07", b"\x08", b"\x09", b"\x0a",
+        b"\x0b", b"\x0c", b"\x0d", b"\x0e", b"\x0f", b"\x10", b"\x11", b"\x12", b"\x13", b"\x14",
+        b"\x15", b"\x16", b"\x17", b"\x18", b"\x19", b"\x1a",
+    ];
+    let code = key as u32;
+    if modifiers.contains(ModifierKey::CTRL) && (Key::A as u32..=Key::Z as u32).contains(&code) {
+        return Some(CTRL_LETTERS[(code - Key::A as u32) as usize]);
+    }
+    Some(match key {
+        Key::Return | Key::NumPadEnter => b"\r",
+        Key::Backspace => b"\x7f",
+        Key::Tab => b"\t",
+        Key::Escape => b"\x1b",
+        Key::Up => b"\x1b[A",
+        Key::Down => b"\x1b[B",
+        Key::Right => b"\x1b[C",
+        Key::Left => b"\x1b[D",
+        Key::Home => b"\x1b[H",
+        Key::End => b"\x1b[F",
+        Key::Delete => b"\x1b[3~",
+        Key::PageUp => b"\x1b[5~",
+        Key::PageDown => b"\x1b[6~",
+        _ => return None,
+    })
+}
+
+#[derive(WidgetCommon)]
+pub struct TerminalWidget<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    terminal: &'a mut TerminalState,
+    raw_input: &'a mut RawInput,
+    bridge: &'a Bridge,
+}
+
+impl<'a> TerminalWidget<'a> {
+    pub fn new(
+        terminal: &'a mut TerminalState,
+        raw_input: &'a mut RawInput,
+        bridge: &'a Bridge,
+    ) -> Self {
+        TerminalWidget {
+            common: widget::CommonBuilder::default(),
+            terminal,
+            raw_input,
+            bridge,
+        }
+    }
+}
+
+widget_ids! {
+    pub struct TerminalIds {
+        background,
+        grid,
+        cursor,
+        status,
+    }
+}
+
+impl<'a> Widget for TerminalWidget<'a> {
+    type State = TerminalIds;
+    type Style = ();
+    type Event = ();
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        TerminalIds::new(id_gen)
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs {
+            id,
+            state,
+            ui,
+            rect,
+            ..
+        } = args;
+        let TerminalWidget {
+            terminal,
+            raw_input,
+            bridge,
+            ..
+        } = self;
+
+        const PAD: conrod_core::Scalar = 8.0;
+        const STATUS_H: conrod_core::Scalar = 16.0;
+
+        let [cell_w, cell_h] = glyph_grid::cell_size(ui, FONT_SIZE);
+        let cols = ((rect.w() - PAD * 2.0) / cell_w).floor().max(1.0) as usize;
+        let rows = ((rect.h() - STATUS_H - PAD * 3.0) / cell_h)
+            .floor()
+            .max(1.0) as usize;
+        terminal.resize(cols, rows);
+        if terminal.pty.is_none() && !terminal.exited {
+            terminal.start(bridge);
+        }
+
+        widget::Rectangle::fill([cols as f64 * cell_w, rows as f64 * cell_h])
+            .color(color::BLACK)
+            .top_left_with_margin_on(id, PAD)
+            .set(state.background, ui);
+
+        // Clicking the terminal gives it the keyboard, and then it takes the
+        // keys before conrod, so that Tab and the arrows go to the shell.
+        let focused = ui.global_input().current.widget_capturing_keyboard == Some(state.background);
+        let events = match ui.rect_of(state.background) {
+            Some(background) if focused => raw_input.claim(state.background, background),
+            _ => Vec::new(),
+        };
+        let modifiers = ui.global_input().current.modifiers;
+        for event in events {
+            match event {
+                Input::Press(Button::Keyboard(key)) => {
+                    if terminal.exited {
+                        if key == Key::Return {
+                            terminal.start(bridge);
+                        }
+                    } else if let Some(bytes) = key_bytes(key, modifiers) {
+                        terminal.write(bytes);
+                    }
+                }
+                Input::Text(text) => {
+                    if !modifiers.contains(ModifierKey::CTRL) {
+                        let text: String = text.chars().filter(|ch| !ch.is_control()).collect();
+                        terminal.write(text.as_bytes());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let screen = &mut terminal.screen;
+        GlyphGrid::new(cols, rows, &screen.cells, &mut screen.dirty)
+            .font_size(FONT_SIZE)
+            .wh_of(state.background)
+            .middle_of(state.background)
+            .graphics_for(state.background)
+            .set(state.grid, ui);
+
+        if focused && terminal.pty.is_some() {
+            let (col, row) = screen.cursor;
+            widget::Rectangle::fill([cell_w, cell_h])
+                .color(color::LIGHT_GREY.alpha(0.5))
+                .top_left_with_margins_on(
+                    state.background,
+                    row as f64 * cell_h,
+                    col.min(cols - 1) as f64 * cell_w,
+                )
+                .graphics_for(state.background)
+                .set(state.cursor, ui);
+        }
+
+        widget::Text::new(&terminal.status)
+            .font_size(12)
+            .color(color::LIGHT_GREY)
+            .down_from(state.background, PAD)
+            .set(state.status, ui);
+    }
+}
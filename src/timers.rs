@@ -0,0 +1,143 @@
+// Timers for things which happen after some time without any input, like
+// the caret blinking or a toast going away.
+//
+// The event loop waits until the next timer is due instead of updating the
+// UI continuously, then gives the messages of the due timers to
+// `UiState::handle_timer`, the same way as the `AppEvent`s. Times are in
+// microseconds of `trace::now_us`.
+
+use crate::trace::now_us;
+use std::time::Duration;
+
+#[derive(Clone, Debug)]
+pub enum TimerMsg {
+    /// Dismiss the notification with this id.
+    DismissNotification(u64),
+    /// Read the `metrics` of the machine again.
+    RefreshMetrics,
+    /// Reload the Designer window if its file has changed.
+    #[cfg(not(target_arch = "wasm32"))]
+    ReloadDesigner,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimerId(u64);
+
+struct Timer {
+    id: TimerId,
+    due: f64,
+    /// The interval of a repeating timer.
+    every: Option<f64>,
+    msg: TimerMsg,
+}
+
+pub struct Timers {
+    timers: Vec<Timer>,
+    next_id: u64,
+    /// When the UI needs to be updated without a message, as asked with
+    /// `wake_after`.
+    wake_at: Option<f64>,
+}
+
+impl Default for Timers {
+    fn default() -> Self {
+        Timers::new()
+    }
+}
+
+impl Timers {
+    pub fn new() -> Self {
+        Timers {
+            timers: Vec::new(),
+            next_id: 0,
+            wake_at: None,
+        }
+    }
+
+    /// Deliver `msg` once, after `duration`.
+    pub fn after(&mut self, duration: Duration, msg: TimerMsg) -> TimerId {
+        self.add(duration, None, msg)
+    }
+
+    /// Deliver `msg` every `duration`, until cancelled.
+    pub fn every(&mut self, duration: Duration, msg: TimerMsg) -> TimerId {
+        self.add(duration, Some(micros(duration)), msg)
+    }
+
+    pub fn cancel(&mut self, id: TimerId) {
+        self.timers.retain(|timer| timer.id != id);
+    }
+
+    /// Update the UI after `duration`, for animations which only change
+    /// now and then. Only the earliest of these is kept, as every widget
+    /// asks again in each update.
+    pub fn wake_after(&mut self, duration: Duration) {
+        let at = now_us() + micros(duration);
+        self.wake_at = Some(self.wake_at.map_or(at, |wake_at| wake_at.min(at)));
+    }
+
+    fn add(&mut self, duration: Duration, every: Option<f64>, msg: TimerMsg) -> TimerId {
+        let id = TimerId(self.next_id);
+        self.next_id += 1;
+        self.timers.push(Timer {
+            id,
+            due: now_us() + micros(duration),
+            every,
+            msg,
+        });
+        id
+    }
+
+    /// How long until the next timer is due, if there is one.
+    pub fn next_due(&self) -> Option<Duration> {
+        let due = self
+            .timers
+            .iter()
+            .map(|timer| timer.due)
+            .chain(self.wake_at)
+            .fold(f64::INFINITY, f64::min);
+        if due.is_infinite() {
+            return None;
+        }
+        Some(Duration::from_micros((due - now_us()).max(0.0) as u64))
+    }
+
+    /// Whether a timer is due, and if so the messages of those which are.
+    /// Repeating timers are scheduled again from now.
+    pub fn take_due(&mut self) -> Option<Vec<TimerMsg>> {
+        let now = now_us();
+        let mut woken = self.wake_at.map_or(false, |at| at <= now);
+        if woken {
+            self.wake_at = None;
+        }
+        let mut msgs = Vec::new();
+        let mut i = 0;
+        while i < self.timers.len() {
+            let timer = &mut self.timers[i];
+            if timer.due > now {
+                i += 1;
+                continue;
+            }
+            woken = true;
+            msgs.push(timer.msg.clone());
+            match timer.every {
+                Some(every) => {
+                    timer.due = now + every;
+                    i += 1;
+                }
+                None => {
+                    self.timers.remove(i);
+                }
+            }
+        }
+        if woken {
+            Some(msgs)
+        } else {
+            None
+        }
+    }
+}
+
+fn micros(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1e6
+}
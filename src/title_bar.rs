@@ -0,0 +1,147 @@
+// Widgets which a window adds to its title bar, like a connection indicator
+// or a button for its most used action.
+//
+// floatwin draws the title bar itself, so the items are set right after the
+// content of the window, as kids of the window widget like the focus outline.
+// That puts them above the title bar and below the windows in front. They
+// are lined up from the right end, and left out altogether when they would
+// cover the title.
+
+use crate::window_modes::WindowModes;
+use crate::workspaces::WindowTracker;
+use conrod_core::{
+    color, text, widget, Color, Colorable, Labelable, Positionable, Scalar, Sizeable, UiCell,
+    Widget,
+};
+
+pub enum TitleBarItem {
+    /// A button with a short label.
+    Button(String),
+    /// A dot of the color, for a state such as being connected.
+    Indicator(Color),
+}
+
+/// Implemented by the state of the windows with title bar items.
+pub trait TitleBarItems {
+    /// The items, from left to right.
+    fn title_bar_items(&self) -> Vec<TitleBarItem>;
+
+    /// The button at `index` in the items was clicked.
+    fn title_bar_clicked(&mut self, _index: usize) {}
+}
+
+/// The widget ids of the title bar items of each window.
+pub struct TitleBars {
+    ids: Vec<(&'static str, widget::id::List)>,
+}
+
+impl Default for TitleBars {
+    fn default() -> Self {
+        TitleBars::new()
+    }
+}
+
+impl TitleBars {
+    const TITLE_BAR_H: Scalar = 24.0;
+    /// The title as floatwin draws it, to keep it clear.
+    const TITLE_FONT_SIZE: u32 = 14;
+    const TITLE_LEFT: Scalar = 8.0;
+    /// Kept free at the right end, for the minimize and maximize buttons.
+    const RIGHT_MARGIN: Scalar = 6.0 + WindowModes::BUTTONS_W;
+    const ITEM_H: Scalar = 18.0;
+    const GAP: Scalar = 4.0;
+    const FONT_SIZE: u32 = 11;
+
+    pub fn new() -> Self {
+        TitleBars { ids: Vec::new() }
+    }
+
+    /// Set the items of the window `title`, right after its content.
+    pub fn set(
+        &mut self,
+        title: &'static str,
+        items: &mut dyn TitleBarItems,
+        tracker: &WindowTracker,
+        ui: &mut UiCell,
+    ) {
+        let window = match tracker.window(title) {
+            Some(window) => window,
+            None => return,
+        };
+        let rect = match ui.rect_of(window) {
+            Some(rect) => rect,
+            None => return,
+        };
+        let font = match ui.theme.font_id.or_else(|| ui.fonts.ids().next()) {
+            Some(font_id) => match ui.fonts.get(font_id) {
+                Some(font) => font.clone(),
+                None => return,
+            },
+            None => return,
+        };
+        let list = self.ids_of(title);
+        let title_bar_items = items.title_bar_items();
+        if list.len() < title_bar_items.len() {
+            let id_gen = &mut ui.widget_id_generator();
+            list.resize(title_bar_items.len(), id_gen);
+        }
+
+        let widths: Vec<Scalar> = title_bar_items
+            .iter()
+            .map(|item| match item {
+                TitleBarItem::Button(label) => {
+                    text::line::width(label, &font, Self::FONT_SIZE) + Self::ITEM_H
+                }
+                TitleBarItem::Indicator(_) => Self::ITEM_H / 2.0,
+            })
+            .collect();
+        let items_w: Scalar = widths.iter().map(|w| w + Self::GAP).sum();
+        let title_w = Self::TITLE_LEFT + text::line::width(title, &font, Self::TITLE_FONT_SIZE);
+        if title_w + items_w + Self::RIGHT_MARGIN > rect.w() {
+            return;
+        }
+
+        let y = rect.top() - Self::TITLE_BAR_H / 2.0;
+        let mut left = rect.right() - Self::RIGHT_MARGIN - items_w + Self::GAP;
+        for (i, (item, w)) in title_bar_items.iter().zip(&widths).enumerate() {
+            let x = left + w / 2.0;
+            match item {
+                TitleBarItem::Button(label) => {
+                    if widget::Button::new()
+                        .label(label)
+                        .label_font_size(Self::FONT_SIZE)
+                        .color(color::LIGHT_CHARCOAL)
+                        .label_color(color::WHITE)
+                        .w_h(*w, Self::ITEM_H)
+                        .x_y(x, y)
+                        .parent(window)
+                        .set(list[i], ui)
+                        .was_clicked()
+                    {
+                        items.title_bar_clicked(i);
+                    }
+                }
+                TitleBarItem::Indicator(color) => {
+                    widget::Circle::fill(w / 2.0)
+                        .color(*color)
+                        .x_y(x, y)
+                        .parent(window)
+                        .graphics_for(window)
+                        .set(list[i], ui);
+                }
+            }
+            left += w + Self::GAP;
+        }
+    }
+
+    fn ids_of(&mut self, title: &'static str) -> &mut widget::id::List {
+        let index = match self.ids.iter().position(|(t, _)| *t == title) {
+            Some(index) => index,
+            None => {
+                self.ids.push((title, widget::id::List::new()));
+                self.ids.len() - 1
+            }
+        };
+        &mut self.ids[index].1
+    }
+}
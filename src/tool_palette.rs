@@ -0,0 +1,193 @@
+// A vertical palette of tool buttons which can be reordered by dragging.
+//
+// The buttons are only dragged inside the window content, so they don't
+// conflict with floatwin, which only drags windows by the title bar. The
+// order is saved in `Config::tool_order`.
+
+use conrod_core::{
+    color, widget, widget_ids, Colorable, Labelable, Positionable, Sizeable, Widget, WidgetCommon,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Tool {
+    Select,
+    Pen,
+    Eraser,
+    Line,
+    Rectangle,
+    Fill,
+    Text,
+    Picker,
+}
+
+impl Tool {
+    pub const ALL: [Tool; 8] = [
+        Tool::Select,
+        Tool::Pen,
+        Tool::Eraser,
+        Tool::Line,
+        Tool::Rectangle,
+        Tool::Fill,
+        Tool::Text,
+        Tool::Picker,
+    ];
+
+    /// A short label standing in for an icon.
+    pub fn label(self) -> &'static str {
+        match self {
+            Tool::Select => "Sel",
+            Tool::Pen => "Pen",
+            Tool::Eraser => "Ers",
+            Tool::Line => "Ln",
+            Tool::Rectangle => "Rect",
+            Tool::Fill => "Fill",
+            Tool::Text => "Txt",
+            Tool::Picker => "Pick",
+        }
+    }
+
+    fn index(self) -> usize {
+        Tool::ALL.iter().position(|&tool| tool == self).unwrap()
+    }
+}
+
+/// Make `order` list every tool once, as it may come from an older or edited
+/// config.
+pub fn normalize_order(order: &mut Vec<Tool>) {
+    let mut seen = Vec::new();
+    order.retain(|tool| {
+        let new = !seen.contains(tool);
+        seen.push(*tool);
+        new
+    });
+    for &tool in &Tool::ALL {
+        if !order.contains(&tool) {
+            order.push(tool);
+        }
+    }
+}
+
+#[derive(WidgetCommon)]
+pub struct ToolPalette<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    order: &'a mut Vec<Tool>,
+    selected: &'a mut Tool,
+}
+
+impl<'a> ToolPalette<'a> {
+    pub fn new(order: &'a mut Vec<Tool>, selected: &'a mut Tool) -> Self {
+        ToolPalette {
+            common: widget::CommonBuilder::default(),
+            order,
+            selected,
+        }
+    }
+}
+
+widget_ids! {
+    pub struct ToolPaletteIds {
+        buttons[],
+    }
+}
+
+pub struct ToolPaletteState {
+    /// One button per tool rather than per slot, so that a button keeps
+    /// capturing the mouse while it is dragged to another slot.
+    ids: ToolPaletteIds,
+    /// Whether the button being pressed has been dragged, in which case
+    /// releasing it doesn't select the tool.
+    dragged: bool,
+}
+
+impl<'a> Widget for ToolPalette<'a> {
+    type State = ToolPaletteState;
+    type Style = ();
+    /// Whether the order has changed and should be saved.
+    type Event = bool;
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        ToolPaletteState {
+            ids: ToolPaletteIds::new(id_gen),
+            dragged: false,
+        }
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs { id, state, ui, .. } = args;
+        let ToolPalette {
+            order, selected, ..
+        } = self;
+
+        const PAD: conrod_core::Scalar = 4.0;
+        const BUTTON_SIZE: conrod_core::Scalar = 40.0;
+        const SLOT_H: conrod_core::Scalar = BUTTON_SIZE + PAD;
+
+        if state.ids.buttons.len() < Tool::ALL.len() {
+            let id_gen = &mut ui.widget_id_generator();
+            state.update(|state| state.ids.buttons.resize(Tool::ALL.len(), id_gen));
+        }
+        normalize_order(order);
+
+        // Move the dragged button to the slot under the mouse.
+        let mut changed = false;
+        let mut dragging = None;
+        let top = match ui.rect_of(id) {
+            Some(rect) => rect.top() - PAD,
+            None => return false,
+        };
+        let capturing = ui.global_input().current.widget_capturing_mouse;
+        for slot in 0..order.len() {
+            let tool = order[slot];
+            let button_id = state.ids.buttons[tool.index()];
+            if !state.dragged && ui.widget_input(button_id).drags().left().next().is_some() {
+                state.update(|state| state.dragged = true);
+            }
+            if state.dragged && capturing == Some(button_id) {
+                let mouse_y = ui.global_input().current.mouse.xy[1];
+                let target = ((top - mouse_y) / SLOT_H).max(0.0) as usize;
+                let target = target.min(order.len() - 1);
+                if target != slot {
+                    let tool = order.remove(slot);
+                    order.insert(target, tool);
+                    changed = true;
+                }
+                dragging = Some((tool, mouse_y));
+                break;
+            }
+        }
+
+        for (slot, &tool) in order.iter().enumerate() {
+            let button_id = state.ids.buttons[tool.index()];
+            let color = if tool == *selected {
+                color::LIGHT_BLUE
+            } else {
+                color::DARK_GREY
+            };
+            let button = widget::Button::new()
+                .label(tool.label())
+                .label_font_size(12)
+                .color(color)
+                .w_h(BUTTON_SIZE, BUTTON_SIZE);
+            // The dragged button follows the mouse, above the others.
+            let button = match dragging {
+                Some((dragged, mouse_y)) if dragged == tool => {
+                    button.x_relative_to(id, 0.0).y(mouse_y).depth(-1.0)
+                }
+                _ => button.mid_top_with_margin_on(id, PAD + slot as f64 * SLOT_H),
+            };
+            let clicked = button.set(button_id, ui).was_clicked();
+            if clicked && !state.dragged {
+                *selected = tool;
+            }
+        }
+        if state.dragged && !ui.global_input().current.mouse.buttons.left().is_down() {
+            state.update(|state| state.dragged = false);
+        }
+
+        changed
+    }
+}
@@ -0,0 +1,156 @@
+// Per-stage timing of the main loop, exported in the Chrome trace event
+// format so it can be inspected in chrome://tracing or Perfetto.
+//
+// The stages are always timed, even when no trace is recorded, so that the
+// frames which go over the budget can be reported as `Jank`.
+
+use serde::Serialize;
+
+/// A timed section of the main loop, in the Chrome "complete event" form.
+#[derive(Serialize)]
+struct TraceEvent {
+    name: &'static str,
+    cat: &'static str,
+    ph: &'static str,
+    /// Start time in microseconds.
+    ts: f64,
+    /// Duration in microseconds.
+    dur: f64,
+    pid: u32,
+    tid: u32,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Trace<'a> {
+    trace_events: &'a [TraceEvent],
+    display_time_unit: &'static str,
+}
+
+/// A frame which took longer than `Tracer::FRAME_BUDGET_US`.
+#[derive(Clone, Debug)]
+pub struct Jank {
+    /// The time of all the stages, in microseconds.
+    pub total: f64,
+    /// The time of each stage, slowest first.
+    pub stages: Vec<(&'static str, f64)>,
+}
+
+impl std::fmt::Display for Jank {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:.1} ms (", self.total / 1000.0)?;
+        for (i, (name, time)) in self.stages.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{} {:.1} ms", name, time / 1000.0)?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// Records spans while tracing is active, and times the stages of each
+/// frame to catch the slow ones.
+pub struct Tracer {
+    events: Option<Vec<TraceEvent>>,
+    /// The time of each stage since the last `end_frame`.
+    stages: Vec<(&'static str, f64)>,
+}
+
+impl Default for Tracer {
+    fn default() -> Self {
+        Tracer::new()
+    }
+}
+
+impl Tracer {
+    /// Stop recording new spans after this many, so that a forgotten trace
+    /// doesn't use up all the memory.
+    const MAX_EVENTS: usize = 1_000_000;
+    /// Frames taking longer than this are reported, which is about three
+    /// frames at 60 Hz, so that only stutters a user would notice count.
+    pub const FRAME_BUDGET_US: f64 = 50_000.0;
+
+    pub fn new() -> Self {
+        Tracer {
+            events: None,
+            stages: Vec::new(),
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.events.is_some()
+    }
+
+    pub fn start(&mut self) {
+        self.events = Some(Vec::new());
+    }
+
+    /// Stop recording and return the trace as Chrome trace JSON.
+    pub fn stop(&mut self) -> Result<Vec<u8>, String> {
+        let events = self.events.take().unwrap_or_default();
+        let trace = Trace {
+            trace_events: &events,
+            display_time_unit: "ms",
+        };
+        serde_json::to_vec(&trace).map_err(|e| e.to_string())
+    }
+
+    /// Run `f`, recording how long it takes as a span called `name`.
+    pub fn span<R>(&mut self, name: &'static str, f: impl FnOnce() -> R) -> R {
+        let start = now_us();
+        let result = f();
+        let end = now_us();
+        match self.stages.iter_mut().find(|(stage, _)| *stage == name) {
+            Some((_, time)) => *time += end - start,
+            None => self.stages.push((name, end - start)),
+        }
+        if let Some(events) = &mut self.events {
+            if events.len() < Self::MAX_EVENTS {
+                events.push(TraceEvent {
+                    name,
+                    cat: "frame",
+                    ph: "X",
+                    ts: start,
+                    dur: end - start,
+                    pid: 1,
+                    tid: 1,
+                });
+            }
+        }
+        result
+    }
+
+    /// Call once a frame has been presented, or skipped as nothing changed.
+    /// Returns the time of its stages if they went over the budget.
+    ///
+    /// The events handled since the last frame count as part of this one,
+    /// but the time spent waiting for them doesn't.
+    pub fn end_frame(&mut self) -> Option<Jank> {
+        let total: f64 = self.stages.iter().map(|(_, time)| time).sum();
+        let mut stages = std::mem::take(&mut self.stages);
+        if total <= Self::FRAME_BUDGET_US {
+            return None;
+        }
+        stages.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+        Some(Jank { total, stages })
+    }
+}
+
+/// A monotonic timestamp in microseconds.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn now_us() -> f64 {
+    use std::time::Instant;
+    thread_local! {
+        static EPOCH: Instant = Instant::now();
+    }
+    EPOCH.with(|epoch| epoch.elapsed().as_secs_f64() * 1_000_000.0)
+}
+
+// `std::time::Instant` is not available on wasm.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn now_us() -> f64 {
+    web_sys::window()
+        .and_then(|w| w.performance())
+        .map_or(0.0, |p| p.now() * 1000.0)
+}
@@ -0,0 +1,346 @@
+// A tree view widget with expandable nodes and keyboard navigation.
+//
+// The children of a node are only asked from the `TreeSource` when the node
+// is first expanded, so the tree can be as large as a file system.
+
+use conrod_core::input::Key;
+use conrod_core::{
+    color, widget, widget_ids, Borderable, Colorable, Labelable, Positionable, Sizeable, Widget,
+    WidgetCommon,
+};
+
+pub struct TreeEntry {
+    pub label: String,
+    /// Whether the node can be expanded, even if its children aren't known
+    /// yet.
+    pub has_children: bool,
+}
+
+/// Provides the nodes of a `TreeView`.
+pub trait TreeSource {
+    /// The children of the node at `path`, given as the labels from the top
+    /// level down. An empty path asks for the top level nodes.
+    fn children(&self, path: &[&str]) -> Vec<TreeEntry>;
+}
+
+struct Node {
+    label: String,
+    parent: Option<usize>,
+    depth: usize,
+    has_children: bool,
+    /// `None` until the node is first expanded.
+    children: Option<Vec<usize>>,
+    expanded: bool,
+}
+
+/// The nodes loaded so far, which of them are expanded and the selection.
+pub struct TreeState {
+    nodes: Vec<Node>,
+    /// The top level nodes, loaded on the first update.
+    roots: Option<Vec<usize>>,
+    selected: Option<usize>,
+}
+
+impl Default for TreeState {
+    fn default() -> Self {
+        TreeState::new()
+    }
+}
+
+impl TreeState {
+    pub fn new() -> Self {
+        TreeState {
+            nodes: Vec::new(),
+            roots: None,
+            selected: None,
+        }
+    }
+
+    /// Forget all the nodes, to load them again from the source.
+    pub fn reset(&mut self) {
+        *self = TreeState::new();
+    }
+
+    /// The labels from the top level down to `node`.
+    pub fn path(&self, node: usize) -> Vec<&str> {
+        let mut path = Vec::new();
+        let mut node = Some(node);
+        while let Some(index) = node {
+            path.push(self.nodes[index].label.as_str());
+            node = self.nodes[index].parent;
+        }
+        path.reverse();
+        path
+    }
+
+    pub fn selected_path(&self) -> Option<Vec<&str>> {
+        self.selected.map(|node| self.path(node))
+    }
+
+    pub fn select(&mut self, node: usize) {
+        self.selected = Some(node);
+    }
+
+    pub fn label(&self, node: usize) -> &str {
+        &self.nodes[node].label
+    }
+
+    fn load(&mut self, source: &dyn TreeSource, parent: Option<usize>) -> Vec<usize> {
+        let entries = match parent {
+            Some(node) => source.children(&self.path(node)),
+            None => source.children(&[]),
+        };
+        let depth = parent.map_or(0, |node| self.nodes[node].depth + 1);
+        entries
+            .into_iter()
+            .map(|entry| {
+                self.nodes.push(Node {
+                    label: entry.label,
+                    parent,
+                    depth,
+                    has_children: entry.has_children,
+                    children: None,
+                    expanded: false,
+                });
+                self.nodes.len() - 1
+            })
+            .collect()
+    }
+
+    fn set_expanded(&mut self, source: &dyn TreeSource, node: usize, expanded: bool) {
+        if !self.nodes[node].has_children {
+            return;
+        }
+        if expanded && self.nodes[node].children.is_none() {
+            let children = self.load(source, Some(node));
+            self.nodes[node].children = Some(children);
+        }
+        self.nodes[node].expanded = expanded;
+    }
+
+    /// The nodes which are shown, in order.
+    pub fn visible_nodes(&self) -> Vec<usize> {
+        fn add(tree: &TreeState, nodes: &[usize], out: &mut Vec<usize>) {
+            for &node in nodes {
+                out.push(node);
+                if let (true, Some(children)) =
+                    (tree.nodes[node].expanded, &tree.nodes[node].children)
+                {
+                    add(tree, children, out);
+                }
+            }
+        }
+        let mut out = Vec::new();
+        add(
+            self,
+            self.roots.as_ref().map_or(&[][..], |roots| &roots[..]),
+            &mut out,
+        );
+        out
+    }
+
+    fn handle_key(&mut self, source: &dyn TreeSource, visible: &[usize], key: Key) {
+        let position = self
+            .selected
+            .and_then(|selected| visible.iter().position(|&node| node == selected));
+        let position = match (position, key) {
+            (None, Key::Up) | (None, Key::Down) => {
+                self.selected = visible.first().cloned();
+                return;
+            }
+            (Some(position), _) => position,
+            (None, _) => return,
+        };
+        let node = visible[position];
+        match key {
+            Key::Up => self.selected = Some(visible[position.saturating_sub(1)]),
+            Key::Down => self.selected = Some(visible[(position + 1).min(visible.len() - 1)]),
+            // Expand, or go to the first child if already expanded.
+            Key::Right if self.nodes[node].expanded => {
+                if let Some(&child) = self.nodes[node].children.as_ref().and_then(|c| c.first()) {
+                    self.selected = Some(child);
+                }
+            }
+            Key::Right => self.set_expanded(source, node, true),
+            // Collapse, or go to the parent if already collapsed.
+            Key::Left if self.nodes[node].expanded => self.set_expanded(source, node, false),
+            Key::Left => {
+                if let Some(parent) = self.nodes[node].parent {
+                    self.selected = Some(parent);
+                }
+            }
+            Key::Return | Key::Space => {
+                let expanded = self.nodes[node].expanded;
+                self.set_expanded(source, node, !expanded);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(WidgetCommon)]
+pub struct TreeView<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    tree: &'a mut TreeState,
+    source: &'a dyn TreeSource,
+    highlighted: &'a [usize],
+}
+
+impl<'a> TreeView<'a> {
+    pub fn new(tree: &'a mut TreeState, source: &'a dyn TreeSource) -> Self {
+        TreeView {
+            common: widget::CommonBuilder::default(),
+            tree,
+            source,
+            highlighted: &[],
+        }
+    }
+
+    /// Highlight these nodes, like search matches.
+    pub fn highlighted(mut self, nodes: &'a [usize]) -> Self {
+        self.highlighted = nodes;
+        self
+    }
+}
+
+widget_ids! {
+    pub struct TreeViewIds {
+        canvas,
+        scrollbar,
+        rows[],
+        toggles[],
+        labels[],
+    }
+}
+
+impl<'a> Widget for TreeView<'a> {
+    type State = TreeViewIds;
+    type Style = ();
+    /// Whether the selection changed.
+    type Event = bool;
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        TreeViewIds::new(id_gen)
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs {
+            id,
+            state,
+            ui,
+            rect,
+            ..
+        } = args;
+        let TreeView {
+            tree,
+            source,
+            highlighted,
+            ..
+        } = self;
+
+        const ROW_H: conrod_core::Scalar = 22.0;
+        const INDENT: conrod_core::Scalar = 16.0;
+        const TOGGLE_W: conrod_core::Scalar = 16.0;
+
+        if tree.roots.is_none() {
+            let roots = tree.load(source, None);
+            tree.roots = Some(roots);
+        }
+        let selected_before = tree.selected;
+
+        widget::Canvas::new()
+            .color(color::CHARCOAL)
+            .border(0.0)
+            .scroll_kids_vertically()
+            .wh_of(id)
+            .middle_of(id)
+            .set(state.canvas, ui);
+
+        // The keys go to whichever row was clicked last, or the canvas.
+        let mut keys = Vec::new();
+        for &widget_id in std::iter::once(&state.canvas)
+            .chain(state.rows.iter())
+            .chain(state.toggles.iter())
+        {
+            for press in ui.widget_input(widget_id).presses().key() {
+                keys.push(press.key);
+            }
+        }
+        for key in keys {
+            let visible = tree.visible_nodes();
+            tree.handle_key(source, &visible, key);
+        }
+
+        // The rows change when nodes are expanded, so the ids are allocated
+        // for as many rows as are visible now.
+        let visible = tree.visible_nodes();
+        if state.rows.len() < visible.len() {
+            let id_gen = &mut ui.widget_id_generator();
+            let len = visible.len();
+            state.update(|state| {
+                state.rows.resize(len, id_gen);
+                state.toggles.resize(len, id_gen);
+                state.labels.resize(len, id_gen);
+            });
+        }
+
+        let mut toggled = None;
+        for (i, &node_index) in visible.iter().enumerate() {
+            let node = &tree.nodes[node_index];
+            let row_color = if tree.selected == Some(node_index) {
+                color::LIGHT_BLUE.with_alpha(0.5)
+            } else if highlighted.contains(&node_index) {
+                color::DARK_YELLOW.with_alpha(0.6)
+            } else {
+                color::CHARCOAL
+            };
+            widget::Rectangle::fill([rect.w(), ROW_H])
+                .color(row_color)
+                .top_left_with_margins_on(state.canvas, i as f64 * ROW_H, 0.0)
+                .set(state.rows[i], ui);
+            if ui
+                .widget_input(state.rows[i])
+                .clicks()
+                .left()
+                .next()
+                .is_some()
+            {
+                tree.selected = Some(node_index);
+            }
+            let indent = node.depth as f64 * INDENT;
+            if node.has_children {
+                if widget::Button::new()
+                    .label(if node.expanded { "-" } else { "+" })
+                    .label_font_size(12)
+                    .w_h(TOGGLE_W, TOGGLE_W)
+                    .mid_left_with_margin_on(state.rows[i], indent + 2.0)
+                    .set(state.toggles[i], ui)
+                    .was_clicked()
+                {
+                    toggled = Some(node_index);
+                }
+            }
+            widget::Text::new(&node.label)
+                .font_size(12)
+                .color(color::WHITE)
+                .no_line_wrap()
+                .mid_left_with_margin_on(state.rows[i], indent + TOGGLE_W + 6.0)
+                .graphics_for(state.rows[i])
+                .set(state.labels[i], ui);
+        }
+        if let Some(node) = toggled {
+            let expanded = tree.nodes[node].expanded;
+            tree.set_expanded(source, node, !expanded);
+            tree.selected = Some(node);
+        }
+
+        widget::Scrollbar::y_axis(state.canvas)
+            .auto_hide(true)
+            .set(state.scrollbar, ui);
+
+        tree.selected != selected_before
+    }
+}
@@ -1,7 +1,13 @@
-use crate::{conrod_glow, set_widgets, Ids, UiState, WinIds};
+use crate::{
+    bridge::AppEvent, bridge::Bridge, capture, clipboard, color_filter::ColorFilter,
+    config::Config, conrod_glow, debug_draw, diagnostics::Diagnostics, error, error::AppError,
+    export, focus_ring::FocusRing, gesture::GestureConverter, image_gc::ImageGc,
+    image_viewer::LoadedImage, input, set_widgets, splash, trace::Tracer, window_switcher, Ids,
+    UiState,
+};
 
-use conrod_floatwin::WindowingState;
-use conrod_glow::Renderer;
+use crate::conrod_winit_compat::{EventConverter, WindowInfo};
+use conrod_glow::{GlyphCache, Renderer};
 use glow::HasContext;
 use wasm_bindgen::{prelude::*, JsCast};
 use winit::platform::web::WindowBuilderExtWebSys;
@@ -20,31 +26,154 @@ const WIN_H: u32 = 600;
 pub fn wasm_start() {
     console_error_panic_hook::set_once();
 
-    let window = web_sys::window().unwrap();
-    let document = window.document().unwrap();
+    if let Err(err) = start() {
+        show_error_overlay(&err);
+    }
+}
+
+/// There is no UI to show the error in, so cover the page with it.
+fn show_error_overlay(err: &AppError) {
+    error::log(err);
+    let document = match web_sys::window().and_then(|window| window.document()) {
+        Some(document) => document,
+        None => return,
+    };
+    let (overlay, body) = match (document.create_element("div"), document.body()) {
+        (Ok(overlay), Some(body)) => (overlay, body),
+        _ => return,
+    };
+    let _ = overlay.set_attribute(
+        "style",
+        "position: fixed; top: 0; left: 0; right: 0; bottom: 0; padding: 2em; \
+         background: #222; color: #eee; font: 16px sans-serif; white-space: pre-wrap;",
+    );
+    overlay.set_text_content(Some(&err.describe()));
+    let _ = body.append_child(&overlay);
+}
+
+/// The message of a JavaScript exception.
+fn js_error(err: JsValue) -> String {
+    err.as_string().unwrap_or_else(|| format!("{:?}", err))
+}
+
+fn start() -> Result<(), AppError> {
+    let document = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or(AppError::MissingCanvas)?;
     let canvas = document
         .get_element_by_id("canvas")
-        .unwrap()
-        .dyn_into::<web_sys::HtmlCanvasElement>()
-        .unwrap();
+        .and_then(|canvas| canvas.dyn_into::<web_sys::HtmlCanvasElement>().ok())
+        .ok_or(AppError::MissingCanvas)?;
+    // `?safemode` turns off antialiasing and uses the simplest texture
+    // uploads, for drivers which fail otherwise.
+    let safe_mode = document.url().map_or(false, |url| {
+        url.splitn(2, '?').nth(1).map_or(false, |query| {
+            query.split('&').any(|param| param == "safemode")
+        })
+    });
+    // The browser picks the samples of an antialiased context, and they
+    // can't change, so the MSAA setting only decides whether to ask for one.
+    // Without one, the UI is drawn multisampled offscreen as the setting asks.
+    let config = Config::load();
+    let antialias = !safe_mode && config.msaa_samples > 0;
+    let context_options = js_sys::Object::new();
+    let _ = js_sys::Reflect::set(&context_options, &"antialias".into(), &antialias.into());
     let webgl2_context = canvas
-        .get_context("webgl2")
-        .unwrap()
-        .unwrap()
-        .dyn_into::<web_sys::WebGl2RenderingContext>()
-        .unwrap();
+        .get_context_with_context_options("webgl2", &context_options)
+        .ok()
+        .and_then(|context| context)
+        .and_then(|context| context.dyn_into::<web_sys::WebGl2RenderingContext>().ok())
+        .ok_or(AppError::WebGl2Unavailable)?;
+    // glow can't list the extensions on the web, so ask WebGL directly.
+    let extensions = webgl2_context
+        .get_supported_extensions()
+        .map(|names| names.iter().filter_map(|name| name.as_string()).collect())
+        .unwrap_or_default();
     let gl = glow::Context::from_webgl2_context(webgl2_context);
 
-    let event_loop = winit::event_loop::EventLoop::new();
+    // Keep getting pointer events while a button is held down, even outside
+    // of the canvas, so that dragging a floating window doesn't get stuck.
+    // The browser releases the capture when the button is released.
+    // `set_cursor_grab` is not used as it locks the pointer on the web.
+    let on_pointer_down = {
+        let canvas = canvas.clone();
+        Closure::wrap(Box::new(move |event: web_sys::PointerEvent| {
+            let _ = canvas.set_pointer_capture(event.pointer_id());
+        }) as Box<dyn FnMut(_)>)
+    };
+    canvas
+        .add_event_listener_with_callback("pointerdown", on_pointer_down.as_ref().unchecked_ref())
+        .map_err(|err| AppError::Context(js_error(err)))?;
+    on_pointer_down.forget();
+
+    let event_loop = winit::event_loop::EventLoop::with_user_event();
+    let bridge = Bridge::new(&event_loop);
+    // Ctrl+V reaches the app only as a `paste` event on the page.
+    clipboard::listen_for_paste(&document, &bridge)
+        .map_err(|err| AppError::Context(js_error(err)))?;
+
     let winit_window = winit::window::WindowBuilder::new()
         .with_title("Conrod with glow!")
         // .with_inner_size(winit::dpi::LogicalSize::new(WIN_W, WIN_H))
         .with_auto_parent_size()
-        .with_canvas(Some(canvas));
-    let winit_window = winit_window.build(&event_loop).unwrap();
+        .with_canvas(Some(canvas.clone()));
+    let winit_window = winit_window
+        .build(&event_loop)
+        .map_err(|err| AppError::Context(err.to_string()))?;
 
-    // let mut current_hidpi_factor = window.device_pixel_ratio();
-    let mut current_hidpi_factor = winit_window.scale_factor();
+    splash::draw(&gl, winit_window.inner_size().into(), 0.0);
+    let context = Context {
+        canvas,
+        gl,
+        extensions,
+        safe_mode,
+        antialias,
+        config,
+        event_loop,
+        bridge,
+        winit_window,
+    };
+    after_paint(move || load_assets(context));
+    Ok(())
+}
+
+/// What `start` sets up before the loading screen is shown.
+struct Context {
+    canvas: web_sys::HtmlCanvasElement,
+    gl: glow::Context,
+    extensions: Vec<String>,
+    safe_mode: bool,
+    /// Whether the context was made with the browser's own antialiasing.
+    antialias: bool,
+    config: Config,
+    event_loop: winit::event_loop::EventLoop<AppEvent>,
+    bridge: Bridge,
+    winit_window: winit::window::Window,
+}
+
+/// Run `step` once the browser has shown what was drawn so far, which is
+/// after the next animation frame. Errors cover the page like those of
+/// `start`.
+fn after_paint(step: impl FnOnce() -> Result<(), AppError> + 'static) {
+    fn request_animation_frame(callback: impl FnOnce() + 'static) {
+        let callback = Closure::once_into_js(callback);
+        if let Some(window) = web_sys::window() {
+            let _ = window.request_animation_frame(callback.unchecked_ref());
+        }
+    }
+    request_animation_frame(move || {
+        request_animation_frame(move || {
+            if let Err(err) = step() {
+                show_error_overlay(&err);
+            }
+        })
+    });
+}
+
+/// Parse the fonts and upload the example image, the slow part of starting
+/// up, between two paints of the loading screen.
+fn load_assets(context: Context) -> Result<(), AppError> {
+    let gl = &context.gl;
 
     // Construct our `Ui`.
     let mut ui = conrod_core::UiBuilder::new([WIN_W as f64, WIN_H as f64])
@@ -55,65 +184,49 @@ pub fn wasm_start() {
     let font_collection = conrod_core::text::FontCollection::from_bytes(include_bytes!(
         "../assets/fonts/NotoSans/NotoSans-Regular.ttf"
     ) as &[u8])
-    .unwrap();
+    .map_err(|err| AppError::Font(err.to_string()))?;
     for font in font_collection.into_fonts() {
-        ui.fonts.insert(font.unwrap());
+        ui.fonts
+            .insert(font.map_err(|err| AppError::Font(err.to_string()))?);
     }
 
     // Load the Rust logo from our assets folder to use as an example image.
-    fn load_rust_logo(gl: &glow::Context) -> conrod_glow::Texture {
-        let rgba_image = image::load_from_memory_with_format(
-            include_bytes!("../assets/images/rust.png"),
-            image::ImageFormat::PNG,
-        )
-        .unwrap()
-        .to_rgba();
-        let image_dimensions = rgba_image.dimensions();
-
-        let pixels: Vec<_> = rgba_image
-            .into_raw()
-            .chunks(image_dimensions.0 as usize * 4)
-            .rev()
-            .flat_map(|row| row.iter())
-            .map(|p| p.clone())
-            .collect();
-
-        let texture;
-        unsafe {
-            texture = gl.create_texture().unwrap();
-            gl.bind_texture(glow::TEXTURE_2D, Some(texture));
-            gl.tex_image_2d(
-                glow::TEXTURE_2D,
-                0,
-                glow::RGBA as i32,
-                image_dimensions.0 as i32,
-                image_dimensions.1 as i32,
-                0,
-                glow::RGBA,
-                glow::UNSIGNED_BYTE,
-                Some(&pixels),
-            );
-            gl.tex_parameter_i32(
-                glow::TEXTURE_2D,
-                glow::TEXTURE_MAG_FILTER,
-                glow::NEAREST as i32,
-            );
-            gl.tex_parameter_i32(
-                glow::TEXTURE_2D,
-                glow::TEXTURE_MIN_FILTER,
-                glow::NEAREST as i32,
-            );
-        }
+    let rust_logo = image::load_from_memory_with_format(
+        include_bytes!("../assets/images/rust.png"),
+        image::ImageFormat::PNG,
+    )
+    .map_err(|err| AppError::Image(err.to_string()))?;
+    let rust_logo = conrod_glow::Texture::from_image(gl, &rust_logo, Default::default())
+        .map_err(AppError::Renderer)?;
+    let mut image_map = conrod_core::image::Map::new();
+    let rust_logo = image_map.insert(rust_logo);
 
-        conrod_glow::Texture {
-            texture,
-            width: image_dimensions.0,
-            height: image_dimensions.1,
-        }
-    }
+    splash::draw(gl, context.winit_window.inner_size().into(), 0.5);
+    after_paint(move || run(context, ui, image_map, rust_logo));
+    Ok(())
+}
 
-    let mut image_map = conrod_core::image::Map::new();
-    let rust_logo = image_map.insert(load_rust_logo(&gl));
+/// Create the renderer and the state of the UI, and run the event loop.
+fn run(
+    context: Context,
+    mut ui: conrod_core::Ui,
+    mut image_map: conrod_core::image::Map<conrod_glow::Texture>,
+    rust_logo: conrod_core::image::Id,
+) -> Result<(), AppError> {
+    let Context {
+        canvas,
+        gl,
+        extensions,
+        safe_mode,
+        antialias,
+        config,
+        event_loop,
+        bridge,
+        winit_window,
+    } = context;
+
+    // let mut current_hidpi_factor = window.device_pixel_ratio();
+    let mut current_hidpi_factor = winit_window.scale_factor();
 
     // A type used for converting `conrod_core::render::Primitives` into `Command`s that can be used
     // for drawing to the glium `Surface`.
@@ -124,48 +237,103 @@ pub fn wasm_start() {
     // - a `Vec` for collecting `backend::glium::Vertex`s generated when translating the
     // `conrod_core::render::Primitive`s.
     // - a `Vec` of commands that describe how to draw the vertices.
-    let mut renderer = Renderer::new(&gl, false).unwrap();
+    let framebuffer = winit_window.inner_size().into();
+    let mut renderer = Renderer::builder()
+        .safe_mode(safe_mode)
+        .glyph_cache_for_framebuffer(framebuffer, GlyphCache::DEFAULT_SCALE)
+        .build(&gl)
+        .map_err(|err| AppError::Renderer(err.to_string()))?;
+    let mut image_gc = ImageGc::new(ImageGc::DEFAULT_IDLE_FILLS);
+    // The switcher snapshot and the exports, one of each at a time.
+    let mut target_pool = conrod_glow::RenderTargetPool::new(2);
+    let mut color_filter = ColorFilter::new();
 
     let mut ids = Ids::new(ui.widget_id_generator());
 
-    let mut win_state = WindowingState::new();
-    let win_ids = WinIds {
-        conrod_example: win_state.next_id(),
-    };
-
-    let mut ui_state = UiState {
-        enable_debug: false,
-        win_state,
-        win_ids,
-        conrod_example_app: conrod_example_shared::DemoApp::new(rust_logo),
+    let mut ui_state = UiState::new(
+        conrod_example_shared::DemoApp::new(rust_logo),
+        bridge,
+        config,
+    );
+    if let Err(err) = ui_state.load_layout() {
+        ui_state
+            .notifications
+            .error(format!("Cannot load the window layout: {}", err));
+    }
+    let surface = if antialias {
+        "WebGL 2 canvas, antialiased by the browser, not sRGB"
+    } else {
+        "WebGL 2 canvas, not sRGB"
     };
+    ui_state.diagnostics = Diagnostics::query(&gl, renderer.info().clone(), surface.to_owned());
+    ui_state.diagnostics.extensions = extensions;
+    ui_state
+        .designer
+        .load_str(include_str!("../assets/ui/designer.json"));
 
     macro_rules! verify {
         () => {{
-            let err = gl.get_error();
-            if err != 0 {
-                panic!("gl error {}", err);
+            match gl.get_error() {
+                0 => Ok(()),
+                err => Err(format!("GL error {:#x}", err)),
             }
         }};
     }
 
-    unsafe {
-        gl.clear_color(0.0, 0.0, 0.0, 1.0);
-        verify!();
-        gl.enable(glow::BLEND);
-        verify!();
-        gl.blend_func_separate(
-            glow::SRC_ALPHA,
-            glow::ONE_MINUS_SRC_ALPHA,
-            glow::ONE,
-            glow::ONE_MINUS_SRC_ALPHA,
-        );
-        verify!();
-    }
-
     let mut should_update_ui = true;
     let mut needs_next_update = true;
+    let mut recorder: Option<capture::Recorder> = None;
+    let mut tracer = Tracer::new();
+    let mut gestures = GestureConverter::new();
+    let mut key_repeat = input::KeyRepeat::new();
+    let mut coalescer = input::MotionCoalescer::new();
+    let mut event_converter = EventConverter::new();
+    let mut drag_predictor = input::DragPredictor::new();
+    let mut focus_ring = FocusRing::new();
     event_loop.run(move |event, _, control_flow| {
+        // Events posted from background work through the `Bridge`.
+        if let winit::event::Event::UserEvent(app_event) = event {
+            ui_state.handle_app_event(app_event);
+            if let Some(text) = ui_state.pending_paste.take() {
+                let text = conrod_core::event::Input::Text(text);
+                let mouse = ui.global_input().current.mouse.xy;
+                if let Some(event) = ui_state.raw_input.route(text, mouse) {
+                    ui.handle_event(event);
+                }
+            }
+            for (name, image) in std::mem::take(&mut ui_state.pending_images) {
+                let (width, height) = image.dimensions();
+                let data = image.into_raw();
+                let texture = match conrod_glow::Texture::from_rgba8(
+                    &gl,
+                    width,
+                    height,
+                    &data,
+                    Default::default(),
+                ) {
+                    Ok(texture) => texture,
+                    Err(err) => {
+                        ui_state.report_error(&AppError::Renderer(err));
+                        continue;
+                    }
+                };
+                let id = image_map.insert(texture);
+                let loaded = LoadedImage {
+                    name,
+                    id,
+                    width,
+                    height,
+                };
+                if let Some(old) = ui_state.image_viewer.image.replace(loaded) {
+                    if let Some(texture) = image_map.remove(old.id) {
+                        texture.delete(&gl);
+                    }
+                }
+            }
+            should_update_ui = true;
+            return;
+        }
+
         // Break from the loop upon `Escape` or closed window.
         match &event {
             winit::event::Event::WindowEvent { event, .. } => match event {
@@ -185,6 +353,30 @@ pub fn wasm_start() {
                 winit::event::WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
                     current_hidpi_factor = *scale_factor;
                 }
+                winit::event::WindowEvent::Focused(focused) => {
+                    ui_state.window_focused = *focused;
+                    if !focused {
+                        for input in input::focus_lost_inputs() {
+                            ui.handle_event(input);
+                        }
+                    }
+                    should_update_ui = true;
+                }
+                winit::event::WindowEvent::Touch(_) => {
+                    if let Some(pen) = crate::v023_convert_pen!(event, &winit_window) {
+                        ui_state.handle_pen(pen);
+                    }
+                }
+                winit::event::WindowEvent::MouseInput {
+                    state: winit::event::ElementState::Pressed,
+                    button,
+                    ..
+                } => {
+                    if let Some(action) = ui_state.config.mouse_bindings.action_for(*button) {
+                        ui_state.handle_mouse_action(action);
+                        should_update_ui = true;
+                    }
+                }
                 // Toggle fullscreen on `F11`.
                 winit::event::WindowEvent::KeyboardInput {
                     input:
@@ -212,30 +404,121 @@ pub fn wasm_start() {
             _ => {}
         }
 
+        // Shortcuts and gestures are handled by the app rather than conrod.
+        if let winit::event::Event::WindowEvent { event, .. } = &event {
+            ui_state.keyboard.handle_window_event(event);
+            let is_repeat = key_repeat.is_repeat(event);
+            if let Some(command) = ui_state.shortcuts.handle_window_event(event, is_repeat) {
+                ui_state.handle_command(command);
+                should_update_ui = true;
+                return;
+            }
+            if let Some(gesture) = gestures.convert_window_event(event) {
+                ui_state.handle_gesture(gesture);
+                should_update_ui = true;
+                return;
+            }
+            let modifiers = ui_state.shortcuts.modifiers();
+            if let Some(shortcut) = convert_clipboard_shortcut(event, modifiers) {
+                ui_state.handle_clipboard_shortcut(shortcut);
+            }
+        }
+
         // Use the `winit` backend feature to convert the winit event to a conrod one.
-        if let Some(event) = convert_event(&event, &winit_window) {
-            ui.handle_event(event);
+        let window_info = WindowInfo::of(&winit_window);
+        let inputs = tracer.span("convert_event", || {
+            event_converter.convert_event(&event, &window_info)
+        });
+        for event in inputs {
+            let event = ui_state.config.input.adjust(event, &ui_state.keyboard);
+            for event in coalescer.push(event) {
+                for event in drag_predictor.settle(event) {
+                    let event = ui_state.confine_drag(event, &ui, &ids);
+                    let mouse = ui.global_input().current.mouse.xy;
+                    if let Some(event) = ui_state.raw_input.route(event, mouse) {
+                        ui.handle_event(event);
+                    }
+                }
+            }
             should_update_ui = true;
         }
 
         match &event {
             winit::event::Event::MainEventsCleared => {
+                let dragging = ui_state.config.input.predict_drags
+                    && ui.global_input().current.widget_capturing_mouse.is_some();
+                if let Some(event) = drag_predictor.predict(coalescer.flush(), dragging) {
+                    let event = ui_state.confine_drag(event, &ui, &ids);
+                    let mouse = ui.global_input().current.mouse.xy;
+                    if let Some(event) = ui_state.raw_input.route(event, mouse) {
+                        ui.handle_event(event);
+                    }
+                }
+                ui_state.profiler.events_coalesced = coalescer.coalesced;
+                if let Some(msgs) = ui_state.timers.take_due() {
+                    for msg in msgs {
+                        ui_state.handle_timer(msg);
+                    }
+                    should_update_ui = true;
+                }
                 if should_update_ui || needs_next_update {
                     needs_next_update = true;
                     should_update_ui = false;
 
                     // Instantiate a GUI demonstrating every widget type provided by conrod.
                     // conrod_example_shared::gui(&mut ui.set_widgets(), &ids, &mut app);
-                    set_widgets(
-                        ui.set_widgets(),
-                        &mut ids,
-                        current_hidpi_factor,
-                        &mut ui_state,
-                    );
-
-                    // Get the underlying winit window and update the mouse cursor as set by conrod.
+                    ui_state.config.input.apply(&mut ui.theme);
+                    debug_draw::begin_frame();
+                    tracer.span("set_widgets", || {
+                        set_widgets(
+                            ui.set_widgets(),
+                            &mut ids,
+                            current_hidpi_factor,
+                            &mut ui_state,
+                        )
+                    });
+                    if debug_draw::changed() {
+                        ui.needs_redraw();
+                    }
+
+                    // A page can be closed without notice, so the layout is
+                    // saved as soon as a window has been let go of.
+                    if ui_state.layout_dirty
+                        && ui.global_input().current.mouse.buttons.left().is_up()
+                    {
+                        if let Err(err) = ui_state.save_layout() {
+                            // Not tried again until it changes again.
+                            ui_state.layout_dirty = false;
+                            ui_state
+                                .notifications
+                                .error(format!("Cannot save the window layout: {}", err));
+                        }
+                    }
+
+                    // Get the underlying winit window and update the mouse cursor as set by conrod
+                    // and the hotspots.
                     winit_window.set_cursor_icon(convert_mouse_cursor(ui.mouse_cursor()));
 
+                    let keyboard = ui
+                        .global_input()
+                        .current
+                        .widget_capturing_keyboard
+                        .and_then(|id| Some((id, ui.rect_of(id)?)));
+                    let ring = keyboard.filter(|_| ui_state.config.focus_ring);
+                    if focus_ring.update(ring) {
+                        ui.needs_redraw();
+                        needs_next_update = true;
+                    }
+
+                    match ui_state.minimap.upload(&gl, &mut image_map, false) {
+                        Ok(true) => {
+                            ui.needs_redraw();
+                            needs_next_update = true;
+                        }
+                        Ok(false) => {}
+                        Err(err) => ui_state.report_error(&AppError::Renderer(err)),
+                    }
+
                     // Draw the `Ui` if it has changed.
                     if let Some(primitives) = ui.draw_if_changed() {
                         let display = (
@@ -243,22 +526,142 @@ pub fn wasm_start() {
                             winit_window.inner_size().height,
                             winit_window.scale_factor(),
                         );
-                        renderer.fill(&display, &gl, primitives, &image_map);
-                        unsafe {
-                            gl.clear(glow::COLOR_BUFFER_BIT | glow::DEPTH_BUFFER_BIT);
-                            verify!();
-                            gl.viewport(
-                                0,
-                                0,
-                                winit_window.inner_size().width as i32,
-                                winit_window.inner_size().height as i32,
-                            );
-                            verify!();
+                        let result = tracer.span("fill", || {
+                            renderer.fill(&display, &gl, primitives, &image_map).and(
+                                renderer.fill_debug(
+                                    &display,
+                                    &gl,
+                                    &debug_draw::take_frame(),
+                                    &ui.fonts,
+                                ),
+                            )
+                        });
+                        if let Err(err) = result {
+                            ui_state.report_error(&AppError::Draw(err.to_string()));
+                        }
+                        if renderer.glyph_cache_grew() {
+                            ui_state.diagnostics.renderer = Some(renderer.info().clone());
+                        }
+                        image_gc.track(&renderer, &image_map);
+                        ui_state.diagnostics.idle_textures = image_gc.unused().len();
+                        let size = winit_window.inner_size();
+                        let frame = renderer.begin_frame(&gl, (size.width, size.height));
+                        frame.clear([0.0, 0.0, 0.0, 1.0]);
+                        // An antialiased canvas can't be drawn to through
+                        // another multisampled target.
+                        let samples = if antialias || safe_mode {
+                            0
+                        } else {
+                            ui_state.config.msaa_samples
+                        };
+                        // The error flag stays set until checked, so one check covers all calls.
+                        let cleared = unsafe { verify!() };
+                        // The browser presents the frame, so there is no swap to trace.
+                        let drawn = cleared.and_then(|()| {
+                            tracer.span("draw", || {
+                                color_filter.draw(
+                                    &gl,
+                                    &mut target_pool,
+                                    &renderer,
+                                    &image_map,
+                                    (size.width, size.height),
+                                    ui_state.color_filter,
+                                    samples,
+                                    !safe_mode && ui_state.config.fxaa,
+                                )
+                            })
+                        });
+                        let ring_drawn = focus_ring.draw(
+                            &gl,
+                            renderer.info(),
+                            (size.width, size.height),
+                            winit_window.scale_factor(),
+                        );
+                        drop(frame);
+                        ui_state.diagnostics.msaa_samples = color_filter.samples();
+                        if let Err(err) = drawn.and(ring_drawn) {
+                            ui_state.report_error(&AppError::Draw(err));
                         }
-                        renderer.draw(&gl, &image_map).unwrap();
                     } else {
                         needs_next_update = false;
                     }
+                    if ui_state.keep_updating {
+                        needs_next_update = true;
+                    }
+
+                    // Before the switcher is shown, so that it isn't in its
+                    // own thumbnails.
+                    if ui_state.switcher.wants_snapshot() {
+                        let size = winit_window.inner_size();
+                        let snapshot = window_switcher::Snapshot::take(
+                            &gl,
+                            &mut target_pool,
+                            &renderer,
+                            &mut image_map,
+                            (size.width, size.height),
+                            false,
+                        );
+                        if let Err(err) = ui_state.switcher.set_snapshot(snapshot) {
+                            ui_state.notifications.error(format!(
+                                "Cannot take a snapshot for the window switcher: {}",
+                                err
+                            ));
+                        }
+                        needs_next_update = true;
+                    }
+                    if let Some(snapshot) = ui_state.switcher.take_stale_snapshot() {
+                        snapshot.delete(&gl, &mut target_pool, &mut image_map);
+                    }
+
+                    if let Some(scale) = ui_state.pending_export.take() {
+                        let display = (
+                            winit_window.inner_size().width,
+                            winit_window.inner_size().height,
+                            winit_window.scale_factor(),
+                        );
+                        let result = export::export_view(
+                            &gl,
+                            &mut target_pool,
+                            &mut renderer,
+                            &ui,
+                            &image_map,
+                            &display,
+                            scale,
+                            false,
+                        );
+                        ui_state.finish_export(result);
+                        // The renderer now holds the exported frame.
+                        ui.needs_redraw();
+                        needs_next_update = true;
+                    }
+
+                    if ui_state.tracing != tracer.is_recording() {
+                        if ui_state.tracing {
+                            tracer.start();
+                        } else {
+                            ui_state.finish_trace(tracer.stop());
+                        }
+                    }
+
+                    if let Some(seconds) = ui_state.pending_recording.take() {
+                        match capture::Recorder::new(&canvas, seconds, &ui_state.bridge) {
+                            Ok(new_recorder) => recorder = Some(new_recorder),
+                            Err(err) => ui_state
+                                .notifications
+                                .error(format!("Cannot record: {}", err)),
+                        }
+                    }
+                    if recorder.as_ref().map_or(false, |r| r.is_finished()) {
+                        recorder.take().unwrap().finish(&ui_state.bridge);
+                    }
+                    // Keep updating so that the recording stops on time.
+                    if recorder.is_some() {
+                        needs_next_update = true;
+                    }
+
+                    if let Some(jank) = tracer.end_frame() {
+                        ui_state.report_jank(jank);
+                    }
                 }
             }
             _ => {}
@@ -267,6 +670,10 @@ pub fn wasm_start() {
             // On WASM, ControlFlow::Poll uses `requestAnimationFrame`, so this
             // is completely fine.
             *control_flow = winit::event_loop::ControlFlow::Poll;
+        } else if ui_state.timers.next_due().is_some() {
+            // `WaitUntil` takes the `Instant` of the `instant` crate on the
+            // web, so poll for the timers instead.
+            *control_flow = winit::event_loop::ControlFlow::Poll;
         } else {
             *control_flow = winit::event_loop::ControlFlow::Wait;
         }
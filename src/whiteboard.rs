@@ -0,0 +1,258 @@
+// A whiteboard for freehand drawing with the mouse.
+//
+// Each stroke is tessellated every frame into a strip of triangles with a
+// thin fringe on both sides which fades to transparent, which antialiases
+// the edges with the renderer's usual shader through the multi-colour
+// triangles path. Long drawings make for a lot of dynamic geometry.
+
+use conrod_core::color::Rgba;
+use conrod_core::widget::triangles::Triangle;
+use conrod_core::{
+    color, widget, widget_ids, Colorable, Labelable, Point, Positionable, Sizeable, Widget,
+    WidgetCommon,
+};
+use serde::{Deserialize, Serialize};
+
+/// The strokes are saved in sessions.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WhiteboardState {
+    /// The points of each stroke, relative to the middle of the canvas.
+    strokes: Vec<Vec<Point>>,
+    /// Whether the last stroke is still being drawn.
+    #[serde(skip)]
+    drawing: bool,
+}
+
+impl WhiteboardState {
+    pub fn new() -> Self {
+        WhiteboardState {
+            strokes: Vec::new(),
+            drawing: false,
+        }
+    }
+
+    pub fn undo(&mut self) {
+        self.strokes.pop();
+        self.drawing = false;
+    }
+
+    pub fn clear(&mut self) {
+        self.strokes.clear();
+        self.drawing = false;
+    }
+}
+
+const STROKE_WIDTH: f64 = 3.0;
+/// The width of the fringe, which is about a pixel.
+const FEATHER: f64 = 1.0;
+/// Points closer than this to the last one are dropped.
+const MIN_DISTANCE: f64 = 1.5;
+
+fn sub(a: Point, b: Point) -> Point {
+    [a[0] - b[0], a[1] - b[1]]
+}
+
+fn normal(a: Point, b: Point) -> Option<Point> {
+    let [dx, dy] = sub(b, a);
+    let len = (dx * dx + dy * dy).sqrt();
+    if len > 0.0 {
+        Some([-dy / len, dx / len])
+    } else {
+        None
+    }
+}
+
+/// The offset of the edges from each point, mitering the joins.
+fn offsets(points: &[Point]) -> Vec<Point> {
+    let seg_normal = |i: usize| normal(points[i], points[i + 1]).unwrap_or([0.0, 1.0]);
+    let last = points.len() - 1;
+    (0..points.len())
+        .map(|i| {
+            if last == 0 {
+                return [0.0, 1.0];
+            }
+            let before = seg_normal(i.max(1) - 1);
+            let after = seg_normal(i.min(last - 1));
+            let sum = [before[0] + after[0], before[1] + after[1]];
+            let len = (sum[0] * sum[0] + sum[1] * sum[1]).sqrt();
+            if len < 0.01 {
+                // The stroke turns right back.
+                return after;
+            }
+            let miter = [sum[0] / len, sum[1] / len];
+            // Lengthen the miter to keep the width, but not too much on
+            // sharp turns.
+            let cos = miter[0] * after[0] + miter[1] * after[1];
+            let scale = (1.0 / cos.max(0.5)).min(2.0);
+            [miter[0] * scale, miter[1] * scale]
+        })
+        .collect()
+}
+
+/// Tessellate a stroke into absolute coordinates, with a solid core and a
+/// fringe on each side going from `color` to transparent.
+fn tessellate(
+    points: &[Point],
+    origin: Point,
+    color: Rgba,
+    triangles: &mut Vec<Triangle<(Point, Rgba)>>,
+) {
+    if points.len() < 2 {
+        return;
+    }
+    let clear = Rgba(color.0, color.1, color.2, 0.0);
+    // Across the stroke: the outer edge, the core edges and the other outer
+    // edge.
+    let across = [
+        (STROKE_WIDTH / 2.0 + FEATHER, clear),
+        (STROKE_WIDTH / 2.0, color),
+        (-STROKE_WIDTH / 2.0, color),
+        (-STROKE_WIDTH / 2.0 - FEATHER, clear),
+    ];
+    let offsets = offsets(points);
+    let vertices = |i: usize| {
+        let p = [origin[0] + points[i][0], origin[1] + points[i][1]];
+        let n = offsets[i];
+        let mut out = [([0.0; 2], clear); 4];
+        for (v, &(d, c)) in out.iter_mut().zip(across.iter()) {
+            *v = ([p[0] + n[0] * d, p[1] + n[1] * d], c);
+        }
+        out
+    };
+    let mut prev = vertices(0);
+    for i in 1..points.len() {
+        let next = vertices(i);
+        for band in 0..3 {
+            let (a, b) = (prev[band], prev[band + 1]);
+            let (c, d) = (next[band], next[band + 1]);
+            triangles.push(Triangle([a, b, c]));
+            triangles.push(Triangle([b, d, c]));
+        }
+        prev = next;
+    }
+}
+
+#[derive(WidgetCommon)]
+pub struct WhiteboardWidget<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    whiteboard: &'a mut WhiteboardState,
+}
+
+impl<'a> WhiteboardWidget<'a> {
+    pub fn new(whiteboard: &'a mut WhiteboardState) -> Self {
+        WhiteboardWidget {
+            common: widget::CommonBuilder::default(),
+            whiteboard,
+        }
+    }
+}
+
+widget_ids! {
+    pub struct WhiteboardIds {
+        undo_button,
+        clear_button,
+        stats,
+        canvas,
+        strokes,
+    }
+}
+
+impl<'a> Widget for WhiteboardWidget<'a> {
+    type State = WhiteboardIds;
+    type Style = ();
+    type Event = ();
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        WhiteboardIds::new(id_gen)
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs {
+            id,
+            state,
+            ui,
+            rect,
+            ..
+        } = args;
+        let whiteboard = self.whiteboard;
+
+        const PAD: conrod_core::Scalar = 8.0;
+        const ROW_H: conrod_core::Scalar = 28.0;
+
+        for _click in widget::Button::new()
+            .label("Undo")
+            .label_font_size(14)
+            .w_h(70.0, ROW_H)
+            .top_left_with_margin_on(id, PAD)
+            .set(state.undo_button, ui)
+        {
+            whiteboard.undo();
+        }
+        for _click in widget::Button::new()
+            .label("Clear")
+            .label_font_size(14)
+            .w_h(70.0, ROW_H)
+            .right_from(state.undo_button, PAD)
+            .set(state.clear_button, ui)
+        {
+            whiteboard.clear();
+        }
+
+        let canvas_w = (rect.w() - PAD * 2.0).max(0.0);
+        let canvas_h = (rect.h() - ROW_H - PAD * 3.0).max(0.0);
+        widget::Rectangle::fill([canvas_w, canvas_h])
+            .color(color::WHITE)
+            .down_from(state.undo_button, PAD)
+            .set(state.canvas, ui);
+        let canvas = match ui.rect_of(state.canvas) {
+            Some(canvas) => canvas,
+            None => return,
+        };
+
+        // The drags are relative to the middle of the canvas.
+        let (half_w, half_h) = (canvas_w / 2.0, canvas_h / 2.0);
+        let clamp = |xy: Point| {
+            [
+                xy[0].max(-half_w).min(half_w),
+                xy[1].max(-half_h).min(half_h),
+            ]
+        };
+        for drag in ui.widget_input(state.canvas).drags().left() {
+            if !whiteboard.drawing {
+                whiteboard.strokes.push(vec![clamp(drag.from)]);
+                whiteboard.drawing = true;
+            }
+            let stroke = whiteboard.strokes.last_mut().unwrap();
+            let to = clamp(drag.to);
+            let [dx, dy] = sub(to, *stroke.last().unwrap());
+            if (dx * dx + dy * dy).sqrt() >= MIN_DISTANCE {
+                stroke.push(to);
+            }
+        }
+        if !ui.global_input().current.mouse.buttons.left().is_down() {
+            whiteboard.drawing = false;
+        }
+
+        let mut triangles = Vec::new();
+        for stroke in &whiteboard.strokes {
+            tessellate(stroke, canvas.xy(), color::BLACK.to_rgb(), &mut triangles);
+        }
+        widget::Text::new(&format!(
+            "{} strokes, {} triangles",
+            whiteboard.strokes.len(),
+            triangles.len()
+        ))
+        .font_size(12)
+        .color(color::LIGHT_GREY)
+        .right_from(state.clear_button, PAD)
+        .set(state.stats, ui);
+        widget::Triangles::multi_color(triangles)
+            .with_bounding_rect(canvas)
+            .graphics_for(state.canvas)
+            .set(state.strokes, ui);
+    }
+}
@@ -0,0 +1,313 @@
+// Minimizing and maximizing the floating windows, from buttons at the right
+// end of their title bars.
+//
+// A minimized window is simply not made, like a window on another workspace,
+// and a strip with its title is docked at the bottom of the windowing area
+// instead, which restores it when clicked. conrod_floatwin can't move or
+// resize a window from outside, so a maximized window is made under a
+// `WinId` of its own, which floatwin places over the whole area, and under
+// its usual id again once restored. floatwin keeps the position and size
+// from before maximizing with the usual id in the `WindowingState`
+// meanwhile. The compact layout already fills the area with one window at a
+// time, so both are left alone while it is on.
+//
+// A layout restored at startup is applied the same way, as the initial
+// position and size of the windows under fresh ids.
+
+use crate::layout::{WindowLayout, WindowPlacement};
+use crate::workspaces::Workspaces;
+use conrod_core::{
+    color, widget, Colorable, Dimensions, Labelable, Positionable, Scalar, Sizeable, UiCell, Widget,
+};
+use conrod_floatwin::{WinId, WindowBuilder, WindowingState};
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WindowControl {
+    Minimize,
+    Maximize,
+    /// Back from being minimized, or else from being maximized.
+    Restore,
+}
+
+struct Maximized {
+    /// The id the window is made with while maximized, allocated for an
+    /// area of `size`. `None` until the next update.
+    id: Option<WinId>,
+    size: Dimensions,
+}
+
+struct WindowMode {
+    title: String,
+    minimized: bool,
+    maximized: Option<Maximized>,
+}
+
+pub struct WindowModes {
+    /// The windows which are minimized or maximized.
+    windows: Vec<WindowMode>,
+    /// Where the windows of a restored layout go, by title, until the
+    /// windows are gathered.
+    placements: BTreeMap<String, WindowPlacement>,
+    compact: bool,
+    /// The ids of the buttons of each window.
+    button_ids: Vec<(&'static str, widget::id::List)>,
+    strip_ids: widget::id::List,
+}
+
+impl Default for WindowModes {
+    fn default() -> Self {
+        WindowModes::new()
+    }
+}
+
+impl WindowModes {
+    const TITLE_BAR_H: Scalar = 24.0;
+    const BUTTON_SIZE: Scalar = 18.0;
+    const GAP: Scalar = 4.0;
+    const RIGHT_MARGIN: Scalar = 6.0;
+    const FONT_SIZE: u32 = 11;
+    const STRIP_W: Scalar = 160.0;
+    /// The room which the buttons take at the right end of a title bar,
+    /// along with the gap before them.
+    pub const BUTTONS_W: Scalar = (Self::BUTTON_SIZE + Self::GAP) * 2.0;
+
+    pub fn new() -> Self {
+        WindowModes {
+            windows: Vec::new(),
+            placements: BTreeMap::new(),
+            compact: false,
+            button_ids: Vec::new(),
+            strip_ids: widget::id::List::new(),
+        }
+    }
+
+    /// Before the windows are made in a windowing area of `size`. Maximized
+    /// windows get a new id whenever the area changes size, to fill it again.
+    pub fn update(&mut self, size: Dimensions, compact: bool, win_state: &mut WindowingState) {
+        self.compact = compact;
+        if compact || size[0] <= 0.0 || size[1] <= 0.0 {
+            return;
+        }
+        for maximized in self.windows.iter_mut().filter_map(|w| w.maximized.as_mut()) {
+            if maximized.id.is_none() || maximized.size != size {
+                maximized.id = Some(win_state.next_id());
+                maximized.size = size;
+            }
+        }
+    }
+
+    /// The `WindowingState` was made anew, without the ids of the maximized
+    /// windows. The windows go back to their initial places.
+    pub fn forget_ids(&mut self) {
+        for maximized in self.windows.iter_mut().filter_map(|w| w.maximized.as_mut()) {
+            maximized.id = None;
+        }
+        self.placements.clear();
+    }
+
+    /// Restore `layout` into a `WindowingState` made anew.
+    pub fn place(&mut self, layout: &WindowLayout) {
+        let titles: BTreeSet<&String> = layout.minimized.union(&layout.maximized).collect();
+        self.windows = titles
+            .into_iter()
+            .map(|title| WindowMode {
+                title: title.clone(),
+                minimized: layout.minimized.contains(title),
+                maximized: if layout.maximized.contains(title) {
+                    Some(Maximized {
+                        id: None,
+                        size: [0.0, 0.0],
+                    })
+                } else {
+                    None
+                },
+            })
+            .collect();
+        self.placements = layout.windows.clone();
+    }
+
+    fn mode(&self, title: &str) -> Option<&WindowMode> {
+        self.windows.iter().find(|w| w.title == title)
+    }
+
+    /// Whether to make the window `title`, which isn't if it is minimized.
+    pub fn shows(&self, title: &str) -> bool {
+        self.compact || !self.mode(title).map_or(false, |w| w.minimized)
+    }
+
+    pub fn is_maximized(&self, title: &str) -> bool {
+        self.mode(title).map_or(false, |w| w.maximized.is_some())
+    }
+
+    pub fn minimized<'a>(&'a self) -> impl Iterator<Item = &'a str> + 'a {
+        self.windows
+            .iter()
+            .filter(|w| w.minimized)
+            .map(|w| w.title.as_str())
+    }
+
+    pub fn maximized<'a>(&'a self) -> impl Iterator<Item = &'a str> + 'a {
+        self.windows
+            .iter()
+            .filter(|w| w.maximized.is_some())
+            .map(|w| w.title.as_str())
+    }
+
+    /// The builder and id to make the window `title` with, whose usual id is
+    /// `id`.
+    pub fn fit(&self, title: &str, builder: WindowBuilder, id: WinId) -> (WindowBuilder, WinId) {
+        if self.compact {
+            return (builder, id);
+        }
+        let maximized = self.mode(title).and_then(|w| w.maximized.as_ref());
+        match maximized.and_then(|m| Some((m.id?, m.size))) {
+            Some((maximized_id, [w, h])) => {
+                let size = [w, (h - Self::TITLE_BAR_H).max(0.0)];
+                let builder = builder.initial_position([0.0, 0.0]).initial_size(size);
+                (builder, maximized_id)
+            }
+            None => match self.placements.get(title) {
+                Some(placement) => {
+                    let builder = builder
+                        .initial_position(placement.position)
+                        .initial_size(placement.size);
+                    (builder, id)
+                }
+                None => (builder, id),
+            },
+        }
+    }
+
+    pub fn apply(&mut self, title: &str, control: WindowControl) {
+        let index = match self.windows.iter().position(|w| w.title == title) {
+            Some(index) => index,
+            None => {
+                self.windows.push(WindowMode {
+                    title: title.to_owned(),
+                    minimized: false,
+                    maximized: None,
+                });
+                self.windows.len() - 1
+            }
+        };
+        let window = &mut self.windows[index];
+        match control {
+            // It comes back maximized if it was.
+            WindowControl::Minimize => window.minimized = true,
+            WindowControl::Maximize => {
+                window.maximized = Some(Maximized {
+                    id: None,
+                    size: [0.0, 0.0],
+                })
+            }
+            WindowControl::Restore if window.minimized => window.minimized = false,
+            WindowControl::Restore => window.maximized = None,
+        }
+        if !window.minimized && window.maximized.is_none() {
+            self.windows.remove(index);
+        }
+    }
+
+    /// Stop tracking the window `title`, which was closed.
+    pub fn forget(&mut self, title: &str) {
+        self.windows.retain(|w| w.title != title);
+    }
+
+    /// Set the buttons of the window `title`, after the windows. Returns the
+    /// control clicked, if any.
+    pub fn set_buttons(
+        &mut self,
+        title: &'static str,
+        window: widget::Id,
+        ui: &mut UiCell,
+    ) -> Option<WindowControl> {
+        let rect = ui.rect_of(window)?;
+        let controls = [
+            (WindowControl::Minimize, "_"),
+            if self.is_maximized(title) {
+                (WindowControl::Restore, "[=]")
+            } else {
+                (WindowControl::Maximize, "[ ]")
+            },
+        ];
+        let index = match self.button_ids.iter().position(|(t, _)| *t == title) {
+            Some(index) => index,
+            None => {
+                self.button_ids.push((title, widget::id::List::new()));
+                self.button_ids.len() - 1
+            }
+        };
+        let list = &mut self.button_ids[index].1;
+        if list.len() < controls.len() {
+            list.resize(controls.len(), &mut ui.widget_id_generator());
+        }
+
+        let y = rect.top() - Self::TITLE_BAR_H / 2.0;
+        let right = rect.right() - Self::RIGHT_MARGIN;
+        let mut clicked = None;
+        for (i, &(control, label)) in controls.iter().enumerate() {
+            let from_right = (controls.len() - i) as Scalar;
+            let x = right - (Self::BUTTON_SIZE + Self::GAP) * from_right
+                + Self::GAP
+                + Self::BUTTON_SIZE / 2.0;
+            if widget::Button::new()
+                .label(label)
+                .label_font_size(Self::FONT_SIZE)
+                .color(color::LIGHT_CHARCOAL)
+                .label_color(color::WHITE)
+                .w_h(Self::BUTTON_SIZE, Self::BUTTON_SIZE)
+                .x_y(x, y)
+                .parent(window)
+                .set(list[i], ui)
+                .was_clicked()
+            {
+                clicked = Some(control);
+            }
+        }
+        clicked
+    }
+
+    /// Set the strips of the minimized windows of the current workspace,
+    /// docked at the bottom left of the windowing area `area`, as kids of
+    /// `parent` above the windows. Returns the title of the strip clicked,
+    /// if any.
+    pub fn set_strips(
+        &mut self,
+        area: widget::Id,
+        parent: widget::Id,
+        workspaces: &Workspaces,
+        ui: &mut UiCell,
+    ) -> Option<String> {
+        if self.compact {
+            return None;
+        }
+        let titles: Vec<String> = self
+            .windows
+            .iter()
+            .filter(|w| w.minimized && workspaces.shows(&w.title))
+            .map(|w| w.title.clone())
+            .collect();
+        if self.strip_ids.len() < titles.len() {
+            self.strip_ids
+                .resize(titles.len(), &mut ui.widget_id_generator());
+        }
+        let mut clicked = None;
+        for (i, title) in titles.into_iter().enumerate() {
+            if widget::Button::new()
+                .label(&title)
+                .label_font_size(12)
+                .color(color::DARK_CHARCOAL)
+                .label_color(color::WHITE)
+                .w_h(Self::STRIP_W, Self::TITLE_BAR_H)
+                .bottom_left_with_margins_on(area, 0.0, (Self::STRIP_W + Self::GAP) * i as Scalar)
+                .parent(parent)
+                .set(self.strip_ids[i], ui)
+                .was_clicked()
+            {
+                clicked = Some(title);
+            }
+        }
+        clicked
+    }
+}
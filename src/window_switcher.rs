@@ -0,0 +1,351 @@
+// An Alt+Tab window switcher with thumbnails of the floating windows.
+//
+// The renderer doesn't cache the windows separately, so the thumbnails are
+// cut out of a snapshot of the whole view, which the event loop takes right
+// after the switcher is asked for and before it is shown. They show the
+// windows as they were when the switcher opened.
+//
+// floatwin only raises a window when it is clicked, so picking a window
+// focuses it without raising it.
+
+use crate::conrod_glow::{OffscreenTarget, PixelFormat, RenderTargetPool, Renderer, Texture};
+use conrod_core::input::Key;
+use conrod_core::{
+    color, event, image, widget, widget_ids, Borderable, Colorable, Positionable, Sizeable, Widget,
+    WidgetCommon,
+};
+use glow::HasContext;
+
+/// The whole view rendered offscreen, and its texture in the `image::Map`.
+pub struct Snapshot {
+    target: OffscreenTarget,
+    image: image::Id,
+}
+
+impl Snapshot {
+    /// Draw what `renderer` was last filled with into a new texture.
+    pub fn take(
+        gl: &glow::Context,
+        pool: &mut RenderTargetPool,
+        renderer: &Renderer,
+        image_map: &mut image::Map<Texture>,
+        (width, height): (u32, u32),
+        is_srgb: bool,
+    ) -> Result<Self, String> {
+        let target = pool.take(gl, width, height, is_srgb)?;
+        target.bind(gl);
+        let frame = renderer.begin_frame(gl, (width, height));
+        frame.clear([0.0, 0.0, 0.0, 1.0]);
+        let result = renderer.draw(gl, image_map).map_err(|err| err.to_string());
+        drop(frame);
+        unsafe {
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+        }
+        if let Err(err) = result {
+            pool.give_back(gl, target);
+            return Err(err);
+        }
+        let image = image_map.insert(Texture {
+            texture: target.texture(),
+            width,
+            height,
+            format: PixelFormat::Rgba8,
+            mipmaps: false,
+        });
+        Ok(Snapshot { target, image })
+    }
+
+    /// Remove the texture from `image_map` and give the target back to
+    /// `pool`.
+    pub fn delete(
+        self,
+        gl: &glow::Context,
+        pool: &mut RenderTargetPool,
+        image_map: &mut image::Map<Texture>,
+    ) {
+        image_map.remove(self.image);
+        pool.give_back(gl, self.target);
+    }
+}
+
+enum Thumbnails {
+    /// Waiting for the event loop to take the snapshot.
+    Pending,
+    Ready(Snapshot),
+    /// The snapshot failed, so only the titles are shown.
+    Unavailable,
+}
+
+pub struct SwitcherState {
+    /// `None` while the switcher is closed.
+    thumbnails: Option<Thumbnails>,
+    selected: usize,
+    /// How far Tab and Shift+Tab have moved the selection since the last
+    /// update, as the number of windows is only known then.
+    steps: isize,
+    /// A snapshot from a switcher which has been closed, for the event loop
+    /// to delete.
+    stale: Option<Snapshot>,
+}
+
+impl Default for SwitcherState {
+    fn default() -> Self {
+        SwitcherState::new()
+    }
+}
+
+impl SwitcherState {
+    pub fn new() -> Self {
+        SwitcherState {
+            thumbnails: None,
+            selected: 0,
+            steps: 0,
+            stale: None,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.thumbnails.is_some()
+    }
+
+    /// Open the switcher, or move the selection to the next window, or the
+    /// previous one if `backwards`.
+    pub fn cycle(&mut self, backwards: bool) {
+        if self.thumbnails.is_none() {
+            self.thumbnails = Some(Thumbnails::Pending);
+            // The first window is the focused one, so start on the next.
+            self.selected = 0;
+        }
+        self.steps += if backwards { -1 } else { 1 };
+    }
+
+    pub fn close(&mut self) {
+        if let Some(Thumbnails::Ready(snapshot)) = self.thumbnails.take() {
+            self.stale = Some(snapshot);
+        }
+    }
+
+    /// Whether the event loop should take a snapshot and give it to
+    /// `set_snapshot`.
+    pub fn wants_snapshot(&self) -> bool {
+        match self.thumbnails {
+            Some(Thumbnails::Pending) => true,
+            _ => false,
+        }
+    }
+
+    /// Show the thumbnails of `snapshot`, or only the titles if it couldn't be
+    /// taken, in which case the error is given back to be shown.
+    pub fn set_snapshot(&mut self, snapshot: Result<Snapshot, String>) -> Result<(), String> {
+        let (thumbnails, result) = match snapshot {
+            Ok(snapshot) => (Thumbnails::Ready(snapshot), Ok(())),
+            Err(err) => (Thumbnails::Unavailable, Err(err)),
+        };
+        self.thumbnails = Some(thumbnails);
+        result
+    }
+
+    /// The selected window out of `windows`, to pick when Alt is released.
+    pub fn selected(&self, windows: &[(&'static str, widget::Id)]) -> Option<widget::Id> {
+        windows.get(self.selected).map(|&(_, window)| window)
+    }
+
+    /// The snapshot of a closed switcher, which the event loop should delete.
+    pub fn take_stale_snapshot(&mut self) -> Option<Snapshot> {
+        self.stale.take()
+    }
+}
+
+#[derive(WidgetCommon)]
+pub struct WindowSwitcher<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    switcher: &'a mut SwitcherState,
+    /// The title and widget of each window, the focused one first.
+    windows: &'a [(&'static str, widget::Id)],
+    hidpi_factor: f64,
+}
+
+impl<'a> WindowSwitcher<'a> {
+    pub fn new(
+        switcher: &'a mut SwitcherState,
+        windows: &'a [(&'static str, widget::Id)],
+        hidpi_factor: f64,
+    ) -> Self {
+        WindowSwitcher {
+            common: widget::CommonBuilder::default(),
+            switcher,
+            windows,
+            hidpi_factor,
+        }
+    }
+}
+
+widget_ids! {
+    pub struct WindowSwitcherIds {
+        dim,
+        panel,
+        frames[],
+        thumbnails[],
+        titles[],
+    }
+}
+
+impl<'a> Widget for WindowSwitcher<'a> {
+    type State = WindowSwitcherIds;
+    type Style = ();
+    /// The window which was picked by clicking it or pressing Enter.
+    type Event = Option<widget::Id>;
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        WindowSwitcherIds::new(id_gen)
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs {
+            id,
+            state,
+            ui,
+            rect,
+            ..
+        } = args;
+        let WindowSwitcher {
+            switcher,
+            windows,
+            hidpi_factor,
+            ..
+        } = self;
+
+        const PAD: conrod_core::Scalar = 12.0;
+        const CELL_W: conrod_core::Scalar = 200.0;
+        const CELL_H: conrod_core::Scalar = 150.0;
+        const TITLE_H: conrod_core::Scalar = 20.0;
+
+        if windows.is_empty() {
+            return None;
+        }
+        let count = windows.len();
+        // Tab moves the selection even before the switcher is shown, in case
+        // Alt is released before that.
+        let steps = std::mem::replace(&mut switcher.steps, 0);
+        switcher.selected =
+            (switcher.selected as isize + steps).rem_euclid(count as isize) as usize;
+
+        let snapshot = match &switcher.thumbnails {
+            // Wait for the snapshot, so that the switcher isn't in it.
+            None | Some(Thumbnails::Pending) => return None,
+            Some(Thumbnails::Ready(snapshot)) => Some(snapshot),
+            Some(Thumbnails::Unavailable) => None,
+        };
+
+        let cols = (count as f64).sqrt().ceil() as usize;
+        let rows = (count + cols - 1) / cols;
+
+        // Arrow keys move around the grid.
+        let mut steps = 0;
+        let mut picked = false;
+        for event in ui.global_input().events().ui() {
+            if let event::Ui::Press(_, press) = event {
+                match press.button {
+                    event::Button::Keyboard(Key::Left) => steps -= 1,
+                    event::Button::Keyboard(Key::Right) => steps += 1,
+                    event::Button::Keyboard(Key::Up) => steps -= cols as isize,
+                    event::Button::Keyboard(Key::Down) => steps += cols as isize,
+                    event::Button::Keyboard(Key::Return) => picked = true,
+                    _ => {}
+                }
+            }
+        }
+        switcher.selected =
+            (switcher.selected as isize + steps).rem_euclid(count as isize) as usize;
+
+        if state.frames.len() < count {
+            let id_gen = &mut ui.widget_id_generator();
+            state.update(|state| {
+                state.frames.resize(count, id_gen);
+                state.thumbnails.resize(count, id_gen);
+                state.titles.resize(count, id_gen);
+            });
+        }
+
+        widget::Rectangle::fill(rect.dim())
+            .color(color::BLACK.alpha(0.4))
+            .middle_of(id)
+            .set(state.dim, ui);
+        widget::Canvas::new()
+            .color(color::DARK_CHARCOAL)
+            .border(0.0)
+            .w_h(
+                (CELL_W + PAD) * cols as f64 + PAD,
+                (CELL_H + TITLE_H + PAD) * rows as f64 + PAD,
+            )
+            .middle_of(id)
+            .set(state.panel, ui);
+
+        let [view_w, view_h] = ui.window_dim();
+        for (i, &(title, window)) in windows.iter().enumerate() {
+            let (col, row) = (i % cols, i / cols);
+            let frame_color = if i == switcher.selected {
+                color::LIGHT_BLUE
+            } else {
+                color::DARK_GREY
+            };
+            widget::Rectangle::fill([CELL_W + PAD / 2.0, CELL_H + TITLE_H + PAD / 2.0])
+                .color(frame_color)
+                .top_left_with_margins_on(
+                    state.panel,
+                    PAD / 2.0 + (CELL_H + TITLE_H + PAD) * row as f64,
+                    PAD / 2.0 + (CELL_W + PAD) * col as f64,
+                )
+                .set(state.frames[i], ui);
+
+            // Fit the window into the cell, keeping its aspect ratio.
+            if let (Some(snapshot), Some(win_rect)) = (snapshot, ui.rect_of(window)) {
+                let scale = (CELL_W / win_rect.w()).min(CELL_H / win_rect.h()).min(1.0);
+                // The snapshot is in physical pixels with the origin at the
+                // bottom left.
+                let source = conrod_core::Rect::from_corners(
+                    [
+                        (win_rect.left() + view_w / 2.0) * hidpi_factor,
+                        (win_rect.bottom() + view_h / 2.0) * hidpi_factor,
+                    ],
+                    [
+                        (win_rect.right() + view_w / 2.0) * hidpi_factor,
+                        (win_rect.top() + view_h / 2.0) * hidpi_factor,
+                    ],
+                );
+                widget::Image::new(snapshot.image)
+                    .source_rectangle(source)
+                    .w_h(win_rect.w() * scale, win_rect.h() * scale)
+                    .mid_top_with_margin_on(state.frames[i], PAD / 4.0)
+                    .graphics_for(state.frames[i])
+                    .set(state.thumbnails[i], ui);
+            }
+            widget::Text::new(title)
+                .font_size(12)
+                .color(color::WHITE)
+                .no_line_wrap()
+                .mid_bottom_with_margin_on(state.frames[i], PAD / 4.0)
+                .graphics_for(state.frames[i])
+                .set(state.titles[i], ui);
+
+            // Hovering selects, clicking picks.
+            let input = ui.widget_input(state.frames[i]);
+            if input.mouse().is_some() {
+                switcher.selected = i;
+            }
+            if input.clicks().left().next().is_some() {
+                switcher.selected = i;
+                picked = true;
+            }
+        }
+
+        if picked {
+            Some(windows[switcher.selected].1)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,366 @@
+// Workspaces, each showing its own set of the floating windows.
+//
+// A window which isn't on the current workspace is simply not made, so
+// floatwin keeps its position and size in the `WindowingState` for when it
+// is shown again. Windows are known by their titles here, as the `WinId`s
+// are allocated anew on each run, and the workspace of each window is saved
+// in the `Config`.
+
+use crate::attention::Attention;
+use crate::palette::{Palette, PaletteOverrides};
+use crate::trace::now_us;
+use conrod_core::{
+    color, event, widget, widget_ids, Colorable, Labelable, Positionable, Sizeable, UiCell, Widget,
+    WidgetCommon,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub const WORKSPACE_COUNT: usize = 9;
+/// The slots of the Save layout and Recall layout commands.
+pub const LAYOUT_SLOT_COUNT: usize = 3;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Workspaces {
+    pub current: usize,
+    /// The workspace of each window by title. Windows which aren't listed
+    /// are on the first workspace.
+    pub windows: BTreeMap<String, usize>,
+}
+
+impl Workspaces {
+    pub fn workspace_of(&self, title: &str) -> usize {
+        self.windows.get(title).cloned().unwrap_or(0)
+    }
+
+    /// Whether the window is on the current workspace.
+    pub fn shows(&self, title: &str) -> bool {
+        self.workspace_of(title) == self.current
+    }
+
+    /// Whether any window is on `workspace`.
+    pub fn is_used(&self, workspace: usize) -> bool {
+        workspace == 0 || self.windows.values().any(|&w| w == workspace)
+    }
+
+    pub fn move_window(&mut self, title: &str, workspace: usize) {
+        if workspace == 0 {
+            self.windows.remove(title);
+        } else {
+            self.windows.insert(title.to_owned(), workspace);
+        }
+    }
+
+    /// Fix up values which may come from an edited config.
+    pub fn normalize(&mut self) {
+        if self.current >= WORKSPACE_COUNT {
+            self.current = 0;
+        }
+        self.windows
+            .retain(|_, &mut w| w > 0 && w < WORKSPACE_COUNT);
+    }
+}
+
+/// The widgets of the windows shown so far, to find which window was right
+/// clicked.
+pub struct WindowTracker {
+    /// The title, the window widget and its content widget.
+    windows: Vec<(&'static str, widget::Id, widget::Id)>,
+}
+
+impl Default for WindowTracker {
+    fn default() -> Self {
+        WindowTracker::new()
+    }
+}
+
+impl WindowTracker {
+    pub fn new() -> Self {
+        WindowTracker {
+            windows: Vec::new(),
+        }
+    }
+
+    /// Remember the window `title` in the windowing area `area`, right after
+    /// its content has been set.
+    pub fn track(&mut self, title: &'static str, area: widget::Id, ui: &UiCell) {
+        let content = match ui.maybe_prev_widget() {
+            Some(content) => content,
+            None => return,
+        };
+        let graph = ui.widget_graph();
+        let mut window = content;
+        while let Some(parent) = graph.depth_parent(window) {
+            if parent == area {
+                break;
+            }
+            window = parent;
+        }
+        match self.windows.iter_mut().find(|(t, _, _)| *t == title) {
+            Some(entry) => *entry = (title, window, content),
+            None => self.windows.push((title, window, content)),
+        }
+    }
+
+    /// The widget of the window `title`, if it was shown.
+    pub fn window(&self, title: &str) -> Option<widget::Id> {
+        self.windows
+            .iter()
+            .find(|(t, _, _)| *t == title)
+            .map(|&(_, window, _)| window)
+    }
+
+    /// Stop tracking the window `title`, which was closed.
+    pub fn forget(&mut self, title: &str) {
+        self.windows.retain(|(t, _, _)| *t != title);
+    }
+
+    /// The title and widget of each window shown so far.
+    pub fn windows<'a>(&'a self) -> impl Iterator<Item = (&'static str, widget::Id)> + 'a {
+        self.windows
+            .iter()
+            .map(|&(title, window, _)| (title, window))
+    }
+
+    /// The title of the window whose title bar or frame was right clicked in
+    /// this update, if any.
+    pub fn right_clicked(&self, ui: &UiCell) -> Option<&'static str> {
+        let pressed = ui
+            .global_input()
+            .events()
+            .ui()
+            .find_map(|event| match event {
+                event::Ui::Press(
+                    Some(widget),
+                    event::Press {
+                        button: event::Button::Mouse(conrod_core::input::MouseButton::Right, _),
+                        ..
+                    },
+                ) => Some(*widget),
+                _ => None,
+            })?;
+        let graph = ui.widget_graph();
+        let is_in =
+            |ancestor, id| ancestor == id || graph.does_recursive_depth_edge_exist(ancestor, id);
+        self.windows
+            .iter()
+            .find(|&&(_, window, content)| is_in(window, pressed) && !is_in(content, pressed))
+            .map(|&(title, _, _)| title)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum WorkspaceAction {
+    Switch(usize),
+    /// Move the window with the title to the workspace.
+    Move(&'static str, usize),
+    /// Pick the palette of the window with the title, `None` for the
+    /// default.
+    Palette(&'static str, Option<Palette>),
+}
+
+/// The pager in the bottom right corner, and the context menu of a window
+/// when `menu` is set to its title and where the menu opens.
+#[derive(WidgetCommon)]
+pub struct WorkspaceBar<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    workspaces: &'a Workspaces,
+    palettes: &'a PaletteOverrides,
+    attention: &'a Attention,
+    menu: &'a mut Option<(&'static str, conrod_core::Point)>,
+}
+
+impl<'a> WorkspaceBar<'a> {
+    pub fn new(
+        workspaces: &'a Workspaces,
+        palettes: &'a PaletteOverrides,
+        attention: &'a Attention,
+        menu: &'a mut Option<(&'static str, conrod_core::Point)>,
+    ) -> Self {
+        WorkspaceBar {
+            common: widget::CommonBuilder::default(),
+            workspaces,
+            palettes,
+            attention,
+            menu,
+        }
+    }
+}
+
+widget_ids! {
+    pub struct WorkspaceBarIds {
+        pager,
+        pages[],
+        menu,
+        menu_title,
+        menu_items[],
+        palette_items[],
+    }
+}
+
+impl<'a> Widget for WorkspaceBar<'a> {
+    type State = WorkspaceBarIds;
+    type Style = ();
+    type Event = Option<WorkspaceAction>;
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        WorkspaceBarIds::new(id_gen)
+    }
+
+    fn style(&self) -> Self::Style {}
+
+    // Only the pager and the menu should block the widgets underneath.
+    fn is_over(&self) -> widget::IsOverFn {
+        |_, _, _| widget::IsOver::Bool(false)
+    }
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs { id, state, ui, .. } = args;
+        let WorkspaceBar {
+            workspaces,
+            palettes,
+            attention,
+            menu,
+            ..
+        } = self;
+
+        const PAD: conrod_core::Scalar = 4.0;
+        const PAGE_SIZE: conrod_core::Scalar = 24.0;
+        const ITEM_W: conrod_core::Scalar = 180.0;
+        const ITEM_H: conrod_core::Scalar = 26.0;
+
+        if state.pages.len() < WORKSPACE_COUNT {
+            let id_gen = &mut ui.widget_id_generator();
+            state.update(|state| {
+                state.pages.resize(WORKSPACE_COUNT, id_gen);
+                state.menu_items.resize(WORKSPACE_COUNT, id_gen);
+                state.palette_items.resize(Palette::ALL.len() + 1, id_gen);
+            });
+        }
+
+        let mut action = None;
+        let pager_w = (PAGE_SIZE + PAD) * WORKSPACE_COUNT as f64 + PAD;
+        widget::Canvas::new()
+            .color(color::DARK_CHARCOAL)
+            .w_h(pager_w, PAGE_SIZE + PAD * 2.0)
+            .bottom_right_of(id)
+            .set(state.pager, ui);
+        // The count of a page is the sum of those of its windows, and it
+        // flashes while any of them does.
+        let now = now_us();
+        for workspace in 0..WORKSPACE_COUNT {
+            let asking: Vec<&'static str> = attention
+                .titles()
+                .filter(|&title| workspaces.workspace_of(title) == workspace)
+                .collect();
+            let count: u32 = asking.iter().map(|&title| attention.count(title)).sum();
+            let label = match count {
+                0 => (workspace + 1).to_string(),
+                count => format!("{}:{}", workspace + 1, count),
+            };
+            let page_color = if workspace == workspaces.current {
+                color::LIGHT_BLUE
+            } else if asking.iter().any(|&title| attention.is_lit(title, now)) {
+                color::ORANGE
+            } else if workspaces.is_used(workspace) {
+                color::GREY
+            } else {
+                color::DARK_GREY
+            };
+            if widget::Button::new()
+                .label(&label)
+                .label_font_size(12)
+                .color(page_color)
+                .w_h(PAGE_SIZE, PAGE_SIZE)
+                .top_left_with_margins_on(
+                    state.pager,
+                    PAD,
+                    PAD + (PAGE_SIZE + PAD) * workspace as f64,
+                )
+                .set(state.pages[workspace], ui)
+                .was_clicked()
+            {
+                action = Some(WorkspaceAction::Switch(workspace));
+            }
+        }
+
+        let (title, xy) = match *menu {
+            Some(menu) => menu,
+            None => return action,
+        };
+        // The title, the workspaces, and the default palette and the others.
+        let item_count = 1 + WORKSPACE_COUNT + 1 + Palette::ALL.len();
+        widget::Canvas::new()
+            .color(color::DARK_CHARCOAL)
+            .w_h(ITEM_W, ITEM_H * item_count as f64)
+            .x_y(
+                xy[0] + ITEM_W / 2.0,
+                xy[1] - ITEM_H * item_count as f64 / 2.0,
+            )
+            .set(state.menu, ui);
+        widget::Text::new(title)
+            .font_size(12)
+            .color(color::LIGHT_GREY)
+            .mid_top_with_margin_on(state.menu, PAD * 2.0)
+            .set(state.menu_title, ui);
+        let current = workspaces.workspace_of(title);
+        for workspace in 0..WORKSPACE_COUNT {
+            if widget::Button::new()
+                .label(&format!("Move to workspace {}", workspace + 1))
+                .label_font_size(14)
+                .w_h(ITEM_W, ITEM_H)
+                .top_left_with_margins_on(state.menu, ITEM_H * (workspace + 1) as f64, 0.0)
+                .enabled(workspace != current)
+                .set(state.menu_items[workspace], ui)
+                .was_clicked()
+            {
+                action = Some(WorkspaceAction::Move(title, workspace));
+                *menu = None;
+            }
+        }
+        let current = palettes.get(title);
+        let choices = std::iter::once(None).chain(Palette::ALL.iter().cloned().map(Some));
+        for (i, palette) in choices.enumerate() {
+            let label = match palette {
+                Some(palette) => format!("{} palette", palette.name),
+                None => "Default palette".to_owned(),
+            };
+            if widget::Button::new()
+                .label(&label)
+                .label_font_size(14)
+                .w_h(ITEM_W, ITEM_H)
+                .top_left_with_margins_on(
+                    state.menu,
+                    ITEM_H * (1 + WORKSPACE_COUNT + i) as f64,
+                    0.0,
+                )
+                .enabled(palette != current)
+                .set(state.palette_items[i], ui)
+                .was_clicked()
+            {
+                action = Some(WorkspaceAction::Palette(title, palette));
+                *menu = None;
+            }
+        }
+
+        // Clicking anywhere else closes the menu.
+        let menu_id = state.menu;
+        let graph = ui.widget_graph();
+        let pressed_outside = ui.global_input().events().ui().any(|event| match event {
+            event::Ui::Press(widget, _) => match *widget {
+                Some(widget) => {
+                    widget != menu_id && !graph.does_recursive_depth_edge_exist(menu_id, widget)
+                }
+                None => true,
+            },
+            _ => false,
+        });
+        if pressed_outside {
+            *menu = None;
+        }
+
+        action
+    }
+}
@@ -0,0 +1,267 @@
+// Property tests for `v023_convert_window_event` and the `EventConverter`
+// around it.
+//
+// The macro only needs `scale_factor` and `inner_size` from the window, so a
+// fake window stands in for a real one. The same invariants should hold for
+// the macros of any newer winit version.
+
+use conrod_core::event::Input;
+use conrod_core::input::{touch, Button, Key, Motion};
+use conrod_core::Rect;
+use conrod_floatwin_demo_glow::conrod_winit_compat::{EventConverter, WindowInfo};
+use proptest::prelude::*;
+use winit::dpi::{LogicalPosition, PhysicalPosition, PhysicalSize};
+use winit::event::{DeviceId, ModifiersState, MouseScrollDelta, Touch, TouchPhase, WindowEvent};
+
+struct FakeWindow {
+    scale_factor: f64,
+    size: PhysicalSize<u32>,
+}
+
+impl FakeWindow {
+    fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    fn inner_size(&self) -> PhysicalSize<u32> {
+        self.size
+    }
+
+    /// The size in points.
+    fn logical_size(&self) -> (f64, f64) {
+        (
+            self.size.width as f64 / self.scale_factor,
+            self.size.height as f64 / self.scale_factor,
+        )
+    }
+}
+
+fn convert(event: &WindowEvent, window: &FakeWindow) -> Option<Input> {
+    conrod_floatwin_demo_glow::v023_convert_window_event!(event, window)
+}
+
+fn device_id() -> DeviceId {
+    unsafe { DeviceId::dummy() }
+}
+
+#[allow(deprecated)]
+fn cursor_moved(x: f64, y: f64) -> WindowEvent<'static> {
+    WindowEvent::CursorMoved {
+        device_id: device_id(),
+        position: PhysicalPosition::new(x, y),
+        modifiers: Default::default(),
+    }
+}
+
+#[allow(deprecated)]
+fn mouse_wheel(delta: MouseScrollDelta) -> WindowEvent<'static> {
+    WindowEvent::MouseWheel {
+        device_id: device_id(),
+        delta,
+        phase: TouchPhase::Moved,
+        modifiers: Default::default(),
+    }
+}
+
+fn cursor_xy(input: Option<Input>) -> [f64; 2] {
+    match input {
+        Some(Input::Motion(Motion::MouseCursor { x, y })) => [x, y],
+        other => panic!("expected a cursor motion, got {:?}", other),
+    }
+}
+
+fn scroll_xy(input: Option<Input>) -> [f64; 2] {
+    match input {
+        Some(Input::Motion(Motion::Scroll { x, y })) => [x, y],
+        other => panic!("expected a scroll, got {:?}", other),
+    }
+}
+
+prop_compose! {
+    fn window()(
+        scale_factor in 0.5f64..4.0,
+        width in 1u32..8192,
+        height in 1u32..8192,
+    ) -> FakeWindow {
+        FakeWindow {
+            scale_factor,
+            size: PhysicalSize::new(width, height),
+        }
+    }
+}
+
+prop_compose! {
+    /// A window and a physical position inside it, as fractions of its size.
+    fn window_and_position()(
+        window in window(),
+        fx in 0.0f64..=1.0,
+        fy in 0.0f64..=1.0,
+    ) -> (FakeWindow, f64, f64) {
+        let x = fx * window.size.width as f64;
+        let y = fy * window.size.height as f64;
+        (window, x, y)
+    }
+}
+
+/// Allow for the rounding of the division by the scale factor.
+const EPSILON: f64 = 1e-9;
+
+fn approx_eq(a: f64, b: f64) -> bool {
+    (a - b).abs() <= EPSILON * a.abs().max(b.abs()).max(1.0)
+}
+
+proptest! {
+    #[test]
+    fn resize_is_in_points(window in window(), width in 0u32..16384, height in 0u32..16384) {
+        let event = WindowEvent::Resized(PhysicalSize::new(width, height));
+        match convert(&event, &window) {
+            Some(Input::Resize(w, h)) => {
+                prop_assert!(w.is_finite() && h.is_finite());
+                prop_assert!(approx_eq(w, width as f64 / window.scale_factor));
+                prop_assert!(approx_eq(h, height as f64 / window.scale_factor));
+            }
+            other => prop_assert!(false, "expected a resize, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn cursor_stays_within_window((window, x, y) in window_and_position()) {
+        let [cx, cy] = cursor_xy(convert(&cursor_moved(x, y), &window));
+        let (w, h) = window.logical_size();
+        prop_assert!(cx.is_finite() && cy.is_finite());
+        prop_assert!(cx.abs() <= w / 2.0 + EPSILON * w, "x {} out of {}", cx, w);
+        prop_assert!(cy.abs() <= h / 2.0 + EPSILON * h, "y {} out of {}", cy, h);
+    }
+
+    #[test]
+    fn cursor_y_points_up(
+        (window, x, y) in window_and_position(),
+        dy in 1.0f64..100.0,
+    ) {
+        let [_, upper] = cursor_xy(convert(&cursor_moved(x, y), &window));
+        let [_, lower] = cursor_xy(convert(&cursor_moved(x, y + dy), &window));
+        prop_assert!(lower < upper);
+    }
+
+    #[test]
+    fn window_corners_map_to_rect_corners(window in window()) {
+        let (w, h) = window.logical_size();
+        let size = window.size;
+        let top_left = cursor_xy(convert(&cursor_moved(0.0, 0.0), &window));
+        let bottom_right = cursor_xy(convert(
+            &cursor_moved(size.width as f64, size.height as f64),
+            &window,
+        ));
+        prop_assert!(approx_eq(top_left[0], -w / 2.0));
+        prop_assert!(approx_eq(top_left[1], h / 2.0));
+        prop_assert!(approx_eq(bottom_right[0], w / 2.0));
+        prop_assert!(approx_eq(bottom_right[1], -h / 2.0));
+    }
+
+    #[test]
+    fn cursor_left_is_outside_window(window in window()) {
+        let [x, y] = cursor_xy(convert(&WindowEvent::CursorLeft { device_id: device_id() }, &window));
+        let (w, h) = window.logical_size();
+        prop_assert!(x < -w / 2.0 && y > h / 2.0);
+    }
+
+    #[test]
+    fn touch_matches_cursor((window, x, y) in window_and_position(), id in any::<u64>()) {
+        let event = WindowEvent::Touch(Touch {
+            device_id: device_id(),
+            phase: TouchPhase::Started,
+            location: PhysicalPosition::new(x, y),
+            force: None,
+            id,
+        });
+        let cursor = cursor_xy(convert(&cursor_moved(x, y), &window));
+        match convert(&event, &window) {
+            Some(Input::Touch(touch)) => {
+                prop_assert_eq!(touch.phase, touch::Phase::Start);
+                prop_assert_eq!(touch.id, touch::Id::new(id));
+                prop_assert_eq!(touch.xy, cursor);
+            }
+            other => prop_assert!(false, "expected a touch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn ime_position_is_where_the_cursor_is_at_the_rect_corner(
+        (window, x, y) in window_and_position(),
+        w in 0.0f64..500.0,
+        h in 0.0f64..500.0,
+    ) {
+        let [left, bottom] = cursor_xy(convert(&cursor_moved(x, y), &window));
+        let rect = Rect::from_corners([left, bottom], [left + w, bottom + h]);
+        let position: LogicalPosition<f64> =
+            conrod_floatwin_demo_glow::v023_convert_ime_position!(rect, &window);
+        prop_assert!(approx_eq(position.x * window.scale_factor, x));
+        prop_assert!(approx_eq(position.y * window.scale_factor, y));
+    }
+
+    #[test]
+    fn modifier_keys_follow_modifiers_changed(
+        window in window(),
+        states in prop::collection::vec(0u32..16, 0..20),
+    ) {
+        let window = WindowInfo {
+            scale_factor: window.scale_factor,
+            inner_size: window.size,
+        };
+        let modifiers = [
+            (ModifiersState::SHIFT, Key::LShift),
+            (ModifiersState::CTRL, Key::LCtrl),
+            (ModifiersState::ALT, Key::LAlt),
+            (ModifiersState::LOGO, Key::LGui),
+        ];
+        let mut converter = EventConverter::new();
+        let mut down = [false; 4];
+        for bits in states {
+            let mut state = ModifiersState::empty();
+            for (i, &(modifier, _)) in modifiers.iter().enumerate() {
+                if bits & (1 << i) != 0 {
+                    state.insert(modifier);
+                }
+            }
+            let event = WindowEvent::ModifiersChanged(state);
+            let inputs = converter.convert_window_event(&event, &window);
+            for input in inputs {
+                let (key, press) = match input {
+                    Input::Press(Button::Keyboard(key)) => (key, true),
+                    Input::Release(Button::Keyboard(key)) => (key, false),
+                    other => {
+                        return Err(TestCaseError::fail(format!("expected a key, got {:?}", other)))
+                    }
+                };
+                let i = modifiers.iter().position(|&(_, k)| k == key).unwrap();
+                // Only changes are sent, never a second press or release.
+                prop_assert_ne!(down[i], press);
+                down[i] = press;
+            }
+            for (i, &(modifier, _)) in modifiers.iter().enumerate() {
+                prop_assert_eq!(down[i], state.contains(modifier));
+            }
+        }
+    }
+
+    #[test]
+    fn pixel_scroll_is_in_points_with_y_flipped(
+        window in window(),
+        dx in -10000.0f64..10000.0,
+        dy in -10000.0f64..10000.0,
+    ) {
+        let delta = MouseScrollDelta::PixelDelta(PhysicalPosition::new(dx, dy));
+        let [x, y] = scroll_xy(convert(&mouse_wheel(delta), &window));
+        prop_assert!(x.is_finite() && y.is_finite());
+        prop_assert!(approx_eq(x, dx / window.scale_factor));
+        prop_assert!(approx_eq(y, -dy / window.scale_factor));
+    }
+
+    #[test]
+    fn line_scroll_has_y_flipped(window in window(), dx in -100.0f32..100.0, dy in -100.0f32..100.0) {
+        let [x, y] = scroll_xy(convert(&mouse_wheel(MouseScrollDelta::LineDelta(dx, dy)), &window));
+        prop_assert!(x.is_finite() && y.is_finite());
+        prop_assert!(x == 0.0 || x.signum() == (dx as f64).signum());
+        prop_assert!(y == 0.0 || y.signum() == -(dy as f64).signum());
+    }
+}
@@ -0,0 +1,123 @@
+// Property tests for `Frame::to_bytes` and `Frame::from_bytes`.
+//
+// The bytes come from other instances over the network and from files, so
+// reading them back must give the same frame, down to the bits of NaN
+// coordinates, and anything else must be turned down without panicking or
+// allocating for counts the bytes can't hold.
+
+use conrod_floatwin_demo_glow::conrod_glow::{Frame, FrameCommand, Vertex};
+use proptest::prelude::*;
+
+fn command() -> impl Strategy<Value = FrameCommand> {
+    let range = (0usize..64, 0usize..64).prop_map(|(start, end)| start..end);
+    prop_oneof![
+        range.clone().prop_map(FrameCommand::Plain),
+        (any::<u32>(), range).prop_map(|(index, range)| FrameCommand::Image(index, range)),
+        any::<[u32; 4]>().prop_map(|[left, bottom, width, height]| FrameCommand::Scissor {
+            left,
+            bottom,
+            width,
+            height,
+        }),
+    ]
+}
+
+fn vertex() -> impl Strategy<Value = Vertex> {
+    (
+        any::<u32>(),
+        any::<[f32; 2]>(),
+        any::<[f32; 2]>(),
+        any::<[f32; 4]>(),
+    )
+        .prop_map(|(mode, position, tex_coords, color)| Vertex {
+            mode,
+            position,
+            tex_coords,
+            color,
+        })
+}
+
+fn frame() -> impl Strategy<Value = Frame> {
+    (
+        any::<(u32, u32)>(),
+        prop::collection::vec(command(), 0..16),
+        prop::collection::vec(vertex(), 0..64),
+    )
+        .prop_map(|(framebuffer, commands, vertices)| Frame {
+            framebuffer,
+            commands,
+            vertices,
+        })
+}
+
+fn vertex_bits(vertex: &Vertex) -> Vec<u32> {
+    let floats = vertex
+        .position
+        .iter()
+        .chain(&vertex.tex_coords)
+        .chain(&vertex.color);
+    std::iter::once(vertex.mode)
+        .chain(floats.map(|f| f.to_bits()))
+        .collect()
+}
+
+proptest! {
+    #[test]
+    fn reads_back_the_same_frame(frame in frame()) {
+        let read = Frame::from_bytes(&frame.to_bytes()).unwrap();
+        prop_assert_eq!(read.framebuffer, frame.framebuffer);
+        prop_assert_eq!(&read.commands, &frame.commands);
+        prop_assert_eq!(
+            read.vertices.iter().map(vertex_bits).collect::<Vec<_>>(),
+            frame.vertices.iter().map(vertex_bits).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn turns_down_frames_cut_short(frame in frame(), cut in any::<prop::sample::Index>()) {
+        let bytes = frame.to_bytes();
+        let len = cut.index(bytes.len());
+        prop_assert!(Frame::from_bytes(&bytes[..len]).is_err());
+    }
+
+    #[test]
+    fn reads_any_bytes_without_panicking(
+        bytes in prop::collection::vec(any::<u8>(), 0..256),
+    ) {
+        let mut framed = b"CFDGFRM1".to_vec();
+        framed.extend_from_slice(&bytes);
+        let _ = Frame::from_bytes(&framed);
+    }
+}
+
+#[test]
+fn turns_down_other_bytes() {
+    let frame = Frame {
+        framebuffer: (4, 4),
+        commands: vec![FrameCommand::Plain(0..0)],
+        vertices: Vec::new(),
+    };
+    let bytes = frame.to_bytes();
+    assert!(Frame::from_bytes(&bytes).is_ok());
+
+    // Another version of the layout.
+    let mut other = bytes.clone();
+    other[7] = b'2';
+    assert!(Frame::from_bytes(&other).is_err());
+
+    // Bytes after the frame.
+    let mut longer = bytes.clone();
+    longer.push(0);
+    assert!(Frame::from_bytes(&longer).is_err());
+
+    // A command of an unknown kind, after the magic and three `u32`s.
+    let mut unknown = bytes;
+    unknown[8 + 12] = 3;
+    assert!(Frame::from_bytes(&unknown).is_err());
+
+    // Counts far beyond the bytes.
+    let mut huge = b"CFDGFRM1".to_vec();
+    huge.extend_from_slice(&[0; 8]);
+    huge.extend_from_slice(&u32::MAX.to_le_bytes());
+    assert!(Frame::from_bytes(&huge).is_err());
+}
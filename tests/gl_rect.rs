@@ -0,0 +1,190 @@
+// Property tests for `GlRect::from_rect` and the `ClipStack`.
+//
+// A buggy layout can hand the renderer any rectangle, and the window any
+// scale factor, so the coordinates include huge, infinite and NaN ones. The
+// scissor rectangle must still fit in the framebuffer.
+
+use conrod_core::{Range, Rect};
+use conrod_floatwin_demo_glow::conrod_glow::{ClipStack, GlRect};
+use proptest::prelude::*;
+
+fn scalar() -> impl Strategy<Value = f64> {
+    prop_oneof![
+        -4096.0f64..4096.0,
+        -1e9f64..1e9,
+        any::<f64>(),
+        Just(f64::INFINITY),
+        Just(f64::NEG_INFINITY),
+        Just(f64::NAN),
+    ]
+}
+
+fn dpi_factor() -> impl Strategy<Value = f64> {
+    prop_oneof![0.5f64..4.0, any::<f64>(), Just(0.0), Just(f64::NAN)]
+}
+
+fn fits(gl_rect: GlRect, (screen_w, screen_h): (u32, u32)) -> bool {
+    gl_rect.left() as u64 + gl_rect.width() as u64 <= screen_w as u64
+        && gl_rect.bottom() as u64 + gl_rect.height() as u64 <= screen_h as u64
+}
+
+const SCREEN: (u32, u32) = (1024, 768);
+
+/// A rectangle of a framebuffer of `SCREEN` size, possibly reaching out of
+/// it, one point per pixel.
+fn clip_rect() -> impl Strategy<Value = GlRect> {
+    (
+        -256.0f64..1280.0,
+        -256.0f64..1280.0,
+        0.0f64..1024.0,
+        0.0f64..1024.0,
+    )
+        .prop_map(|(left, bottom, w, h)| {
+            let (half_w, half_h) = (SCREEN.0 as f64 / 2.0, SCREEN.1 as f64 / 2.0);
+            let rect = Rect::from_corners(
+                [left - half_w, bottom - half_h],
+                [left + w - half_w, bottom + h - half_h],
+            );
+            GlRect::from_rect(rect, 1.0, SCREEN)
+        })
+}
+
+/// Whether `inner` covers no pixel outside of `outer`.
+fn is_within(inner: GlRect, outer: GlRect) -> bool {
+    inner.width() == 0
+        || inner.height() == 0
+        || (inner.left() >= outer.left()
+            && inner.bottom() >= outer.bottom()
+            && inner.left() + inner.width() <= outer.left() + outer.width()
+            && inner.bottom() + inner.height() <= outer.bottom() + outer.height())
+}
+
+proptest! {
+    #[test]
+    fn fits_in_the_framebuffer(
+        x in (scalar(), scalar()),
+        y in (scalar(), scalar()),
+        dpi_factor in dpi_factor(),
+        screen in (0u32..16384, 0u32..16384),
+    ) {
+        let rect = Rect {
+            x: Range::new(x.0, x.1),
+            y: Range::new(y.0, y.1),
+        };
+        let gl_rect = GlRect::from_rect(rect, dpi_factor, screen);
+        prop_assert!(fits(gl_rect, screen), "{:?} for {:?}", gl_rect, screen);
+    }
+
+    #[test]
+    fn fits_in_the_largest_framebuffer(
+        x in (scalar(), scalar()),
+        y in (scalar(), scalar()),
+        dpi_factor in dpi_factor(),
+    ) {
+        let screen = (u32::MAX, u32::MAX);
+        let rect = Rect {
+            x: Range::new(x.0, x.1),
+            y: Range::new(y.0, y.1),
+        };
+        let gl_rect = GlRect::from_rect(rect, dpi_factor, screen);
+        prop_assert!(fits(gl_rect, screen), "{:?} for {:?}", gl_rect, screen);
+    }
+
+    #[test]
+    fn covers_a_rect_inside_the_framebuffer(
+        w in 1u32..4096,
+        h in 1u32..4096,
+        dpi_factor in 1u32..4,
+    ) {
+        // The whole framebuffer, in points.
+        let screen = (w * dpi_factor, h * dpi_factor);
+        let rect = Rect::from_xy_dim([0.0, 0.0], [w as f64, h as f64]);
+        let gl_rect = GlRect::from_rect(rect, dpi_factor as f64, screen);
+        prop_assert_eq!(
+            (gl_rect.left(), gl_rect.bottom(), gl_rect.width(), gl_rect.height()),
+            (0, 0, screen.0, screen.1)
+        );
+    }
+
+    #[test]
+    fn nested_clips_stay_within_every_clip_pushed(
+        rects in prop::collection::vec(clip_rect(), 1..8),
+    ) {
+        let mut clips = ClipStack::new(GlRect::full(SCREEN));
+        let mut pushed = vec![GlRect::full(SCREEN)];
+        for &rect in &rects {
+            let clip = clips.push_clip(rect);
+            pushed.push(rect);
+            prop_assert!(fits(clip, SCREEN), "{:?}", clip);
+            for &outer in &pushed {
+                prop_assert!(is_within(clip, outer), "{:?} out of {:?}", clip, outer);
+            }
+        }
+        prop_assert_eq!(clips.depth(), rects.len());
+    }
+
+    #[test]
+    fn popping_restores_the_clip_pushed_inside_of(
+        rects in prop::collection::vec(clip_rect(), 1..8),
+    ) {
+        let mut clips = ClipStack::new(GlRect::full(SCREEN));
+        let mut before = Vec::new();
+        for &rect in &rects {
+            before.push(clips.current());
+            clips.push_clip(rect);
+        }
+        while let Some(expected) = before.pop() {
+            prop_assert_eq!(clips.pop_clip(), expected);
+        }
+        // One pop too many leaves the whole framebuffer.
+        prop_assert_eq!(clips.pop_clip(), GlRect::full(SCREEN));
+        prop_assert_eq!(clips.depth(), 0);
+    }
+}
+
+/// `[left, bottom, width, height]` of the rect between `corners` in a
+/// framebuffer of `screen` size.
+fn gl_rect_of(corners: ([f64; 2], [f64; 2]), dpi_factor: f64, screen: (u32, u32)) -> [u32; 4] {
+    let gl_rect = GlRect::from_rect(Rect::from_corners(corners.0, corners.1), dpi_factor, screen);
+    [
+        gl_rect.left(),
+        gl_rect.bottom(),
+        gl_rect.width(),
+        gl_rect.height(),
+    ]
+}
+
+#[test]
+fn clips_a_rect_off_the_left_edge() {
+    let corners = ([-500.0, -100.0], [-300.0, 100.0]);
+    assert_eq!(gl_rect_of(corners, 1.0, (800, 600)), [0, 200, 100, 200]);
+}
+
+#[test]
+fn clips_a_rect_off_the_bottom_edge() {
+    let corners = ([-100.0, -400.0], [100.0, -200.0]);
+    assert_eq!(gl_rect_of(corners, 1.0, (800, 600)), [300, 0, 200, 100]);
+}
+
+#[test]
+fn clips_a_rect_off_the_right_and_top_edges() {
+    let corners = ([300.0, 200.0], [500.0, 400.0]);
+    assert_eq!(gl_rect_of(corners, 1.0, (800, 600)), [700, 500, 100, 100]);
+}
+
+#[test]
+fn clips_a_rect_off_the_bottom_left_corner_at_a_high_dpi_factor() {
+    let corners = ([-500.0, -400.0], [-300.0, -200.0]);
+    assert_eq!(gl_rect_of(corners, 2.0, (1600, 1200)), [0, 0, 200, 200]);
+}
+
+#[test]
+fn empties_a_rect_wholly_off_the_framebuffer() {
+    let size = (800, 600);
+    let left = gl_rect_of(([-900.0, -100.0], [-500.0, 100.0]), 1.0, size);
+    assert_eq!((left[2], left[3]), (0, 200));
+    let below = gl_rect_of(([-100.0, -900.0], [100.0, -700.0]), 1.0, size);
+    assert_eq!((below[2], below[3]), (200, 0));
+    let right = gl_rect_of(([500.0, -100.0], [900.0, 100.0]), 1.0, size);
+    assert_eq!(right, [800, 200, 0, 200]);
+}
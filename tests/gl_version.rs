@@ -0,0 +1,97 @@
+// Tests of `GlVersion::parse` on the version strings of real drivers, and of
+// the shaders picked for them by `ShaderVariant::candidates`.
+
+use conrod_floatwin_demo_glow::conrod_glow::{GlVersion, ShaderVariant};
+
+fn version(version: &str, glsl_version: &str) -> GlVersion {
+    GlVersion::parse(version, glsl_version).expect("a version")
+}
+
+#[test]
+fn parses_desktop_versions() {
+    let nvidia = version("4.6.0 NVIDIA 460.39", "4.60 NVIDIA");
+    assert_eq!(
+        nvidia,
+        GlVersion {
+            is_es: false,
+            major: 4,
+            minor: 6,
+            glsl: (4, 60),
+        }
+    );
+    let mesa = version("3.0 Mesa 20.0.8", "1.30");
+    assert_eq!((mesa.is_es, mesa.major, mesa.glsl), (false, 3, (1, 30)));
+    let mac = version("2.1 ATI-4.2.15", "1.20");
+    assert_eq!((mac.major, mac.minor, mac.glsl), (2, 1, (1, 20)));
+    // A minor GLSL version of one digit is in tenths.
+    assert_eq!(version("3.1", "1.4").glsl, (1, 40));
+}
+
+#[test]
+fn parses_es_and_webgl_versions() {
+    let es = version("OpenGL ES 3.2 Mesa 20.0.8", "OpenGL ES GLSL ES 3.20");
+    assert_eq!(
+        (es.is_es, es.major, es.minor, es.glsl),
+        (true, 3, 2, (3, 20))
+    );
+    let es2 = version("OpenGL ES 2.0 build 1.8@2792582", "OpenGL ES GLSL ES 1.00");
+    assert_eq!((es2.is_es, es2.major, es2.glsl), (true, 2, (1, 0)));
+    let webgl = version(
+        "WebGL 2.0 (OpenGL ES 3.0 Chromium)",
+        "WebGL GLSL ES 3.00 (OpenGL ES GLSL ES 3.0 Chromium)",
+    );
+    assert_eq!((webgl.is_es, webgl.major, webgl.glsl), (true, 2, (3, 0)));
+}
+
+#[test]
+fn does_not_parse_versions_without_numbers() {
+    assert_eq!(GlVersion::parse("", "1.20"), None);
+    assert_eq!(GlVersion::parse("4.6.0", "unknown"), None);
+    assert_eq!(GlVersion::parse("4 NVIDIA", "4.60"), None);
+}
+
+#[test]
+fn picks_glsl_140_first_on_desktop() {
+    let gl = Some(version("4.6.0 NVIDIA 460.39", "4.60 NVIDIA"));
+    assert_eq!(
+        ShaderVariant::candidates(gl, true, false),
+        [ShaderVariant::Glsl140, ShaderVariant::Glsl120]
+    );
+    assert_eq!(
+        ShaderVariant::candidates(gl, true, true),
+        [ShaderVariant::Glsl120]
+    );
+    assert_eq!(
+        ShaderVariant::candidates(gl, false, false),
+        [ShaderVariant::Glsl120LinearToSrgb]
+    );
+}
+
+#[test]
+fn skips_glsl_140_without_it() {
+    let gl = Some(version("2.1 ATI-4.2.15", "1.20"));
+    assert_eq!(
+        ShaderVariant::candidates(gl, true, false),
+        [ShaderVariant::Glsl120]
+    );
+    let gl = Some(version("2.0 Mesa", "1.10"));
+    assert!(ShaderVariant::candidates(gl, true, false).is_empty());
+}
+
+#[test]
+fn picks_glsl_es_300_on_es() {
+    let gl = Some(version(
+        "OpenGL ES 3.2 Mesa 20.0.8",
+        "OpenGL ES GLSL ES 3.20",
+    ));
+    assert_eq!(
+        ShaderVariant::candidates(gl, true, false),
+        [ShaderVariant::Es300]
+    );
+    assert_eq!(
+        ShaderVariant::candidates(gl, false, true),
+        [ShaderVariant::Es300LinearToSrgb, ShaderVariant::Es300]
+    );
+    let gl = Some(version("OpenGL ES 2.0", "OpenGL ES GLSL ES 1.00"));
+    assert!(ShaderVariant::candidates(gl, false, false).is_empty());
+}
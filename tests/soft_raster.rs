@@ -0,0 +1,274 @@
+// Golden pixel tests of the `SoftRasterizer`, which draw frames on the CPU
+// and check the colors of the pixels.
+//
+// Most frames are made by hand, in the framebuffer coordinates of the
+// `Renderer`: -1.0 to 1.0 from the left to the right and from the bottom to
+// the top, and texture coordinates from the bottom left. One of them is
+// sent through `Frame::to_bytes` first, and the last tests draw a `Ui`
+// through a `Tessellator`, as the demo does without GL.
+
+use conrod_core::{color, widget, Colorable, Positionable, Widget};
+use conrod_floatwin_demo_glow::conrod_glow::{
+    Frame, FrameCommand, GlyphPixels, Tessellator, Vertex, MODE_GEOMETRY, MODE_IMAGE, MODE_TEXT,
+};
+use conrod_floatwin_demo_glow::soft_raster::{SoftImage, SoftRasterizer};
+use proptest::prelude::*;
+
+const SCREEN: (u32, u32) = (16, 16);
+const BLACK: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+
+fn vertex(mode: u32, position: [f32; 2], tex_coords: [f32; 2], color: [f32; 4]) -> Vertex {
+    Vertex {
+        mode,
+        position,
+        tex_coords,
+        color,
+    }
+}
+
+/// The two triangles of the rectangle from `(l, b)` to `(r, t)`, with the
+/// texture coordinates of the whole texture.
+fn quad(mode: u32, [l, b, r, t]: [f32; 4], color: [f32; 4]) -> Vec<Vertex> {
+    let v = |x, y, u, v| vertex(mode, [x, y], [u, v], color);
+    vec![
+        v(l, b, 0.0, 0.0),
+        v(l, t, 0.0, 1.0),
+        v(r, t, 1.0, 1.0),
+        v(l, b, 0.0, 0.0),
+        v(r, t, 1.0, 1.0),
+        v(r, b, 1.0, 0.0),
+    ]
+}
+
+const FULL: [f32; 4] = [-1.0, -1.0, 1.0, 1.0];
+
+fn plain_frame(vertices: Vec<Vertex>) -> Frame {
+    Frame {
+        framebuffer: SCREEN,
+        commands: vec![FrameCommand::Plain(0..vertices.len())],
+        vertices,
+    }
+}
+
+fn draw(frame: &Frame, images: &[&SoftImage]) -> SoftRasterizer {
+    let mut rasterizer = SoftRasterizer::new();
+    rasterizer.draw(frame, images, BLACK);
+    rasterizer
+}
+
+#[test]
+fn fills_a_quad_with_its_linear_color_in_srgb() {
+    let frame = plain_frame(quad(MODE_GEOMETRY, FULL, [1.0, 0.0, 0.0, 1.0]));
+    let rasterizer = draw(&frame, &[]);
+    assert_eq!(rasterizer.size(), SCREEN);
+    assert!(rasterizer
+        .to_rgba8()
+        .chunks(4)
+        .all(|pixel| pixel == [255, 0, 0, 255]));
+
+    // Linear 0.5 is encoded to 188 in sRGB, not 128.
+    let frame = plain_frame(quad(MODE_GEOMETRY, FULL, [0.5, 0.5, 0.5, 1.0]));
+    assert_eq!(draw(&frame, &[]).pixel(3, 3), Some([188, 188, 188, 255]));
+}
+
+#[test]
+fn fills_the_left_half_of_the_framebuffer() {
+    let frame = plain_frame(quad(MODE_GEOMETRY, [-1.0, -1.0, 0.0, 1.0], [1.0; 4]));
+    let rasterizer = draw(&frame, &[]);
+    assert_eq!(rasterizer.pixel(7, 0), Some([255, 255, 255, 255]));
+    assert_eq!(rasterizer.pixel(8, 15), Some([0, 0, 0, 255]));
+    assert_eq!(rasterizer.pixel(16, 0), None);
+}
+
+#[test]
+fn draws_inside_of_the_scissor_rectangle_only() {
+    let vertices = quad(MODE_GEOMETRY, FULL, [1.0; 4]);
+    let frame = Frame {
+        framebuffer: SCREEN,
+        commands: vec![
+            FrameCommand::Scissor {
+                left: 4,
+                bottom: 2,
+                width: 8,
+                height: 4,
+            },
+            FrameCommand::Plain(0..vertices.len()),
+        ],
+        vertices,
+    };
+    let rasterizer = draw(&frame, &[]);
+    // From the top, the rows 10 to 13 are the rows 2 to 5 from the bottom.
+    assert_eq!(rasterizer.pixel(4, 10), Some([255, 255, 255, 255]));
+    assert_eq!(rasterizer.pixel(11, 13), Some([255, 255, 255, 255]));
+    assert_eq!(rasterizer.pixel(3, 10), Some([0, 0, 0, 255]));
+    assert_eq!(rasterizer.pixel(12, 13), Some([0, 0, 0, 255]));
+    assert_eq!(rasterizer.pixel(4, 9), Some([0, 0, 0, 255]));
+    assert_eq!(rasterizer.pixel(4, 14), Some([0, 0, 0, 255]));
+}
+
+#[test]
+fn takes_the_alpha_of_text_from_the_glyph_cache() {
+    // Covered on the left half of the top row only.
+    let mut data = vec![0; 4 * 2];
+    data[0] = 255;
+    data[1] = 255;
+    let mut rasterizer = SoftRasterizer::new();
+    rasterizer.load_glyph_pixels(GlyphPixels {
+        version: 1,
+        width: 4,
+        height: 2,
+        data,
+    });
+    // The glyph cache goes from the top, unlike images.
+    let mut vertices = quad(MODE_TEXT, FULL, [1.0; 4]);
+    for vertex in &mut vertices {
+        vertex.tex_coords[1] = 1.0 - vertex.tex_coords[1];
+    }
+    rasterizer.draw(&plain_frame(vertices), &[], BLACK);
+    assert_eq!(rasterizer.pixel(0, 0), Some([255, 255, 255, 255]));
+    assert_eq!(rasterizer.pixel(7, 7), Some([255, 255, 255, 255]));
+    assert_eq!(rasterizer.pixel(8, 0), Some([0, 0, 0, 255]));
+    assert_eq!(rasterizer.pixel(0, 8), Some([0, 0, 0, 255]));
+}
+
+#[test]
+fn draws_the_top_row_of_an_image_at_the_top() {
+    let image = SoftImage {
+        width: 1,
+        height: 2,
+        data: vec![255, 0, 0, 255, 0, 0, 255, 255],
+    };
+    let vertices = quad(MODE_IMAGE, FULL, [1.0; 4]);
+    let frame = Frame {
+        framebuffer: SCREEN,
+        commands: vec![FrameCommand::Image(0, 0..vertices.len())],
+        vertices,
+    };
+    let rasterizer = draw(&frame, &[&image]);
+    assert_eq!(rasterizer.pixel(5, 0), Some([255, 0, 0, 255]));
+    assert_eq!(rasterizer.pixel(5, 15), Some([0, 0, 255, 255]));
+
+    // Without the image, nothing is drawn.
+    let rasterizer = draw(&frame, &[]);
+    assert_eq!(rasterizer.pixel(5, 0), Some([0, 0, 0, 255]));
+}
+
+#[test]
+fn leaves_out_commands_with_vertices_out_of_range() {
+    let mut frame = plain_frame(quad(MODE_GEOMETRY, FULL, [1.0; 4]));
+    frame.commands = vec![FrameCommand::Plain(0..7)];
+    assert_eq!(draw(&frame, &[]).pixel(0, 0), Some([0, 0, 0, 255]));
+}
+
+#[test]
+fn draws_a_frame_read_back_from_bytes_the_same() {
+    let mut vertices = quad(MODE_GEOMETRY, [-1.0, -1.0, 0.0, 0.5], [1.0, 0.0, 0.0, 1.0]);
+    vertices.extend(quad(MODE_GEOMETRY, FULL, [0.0, 0.0, 1.0, 0.5]));
+    let frame = Frame {
+        framebuffer: SCREEN,
+        commands: vec![
+            FrameCommand::Plain(0..6),
+            FrameCommand::Scissor {
+                left: 2,
+                bottom: 2,
+                width: 10,
+                height: 10,
+            },
+            FrameCommand::Plain(6..12),
+        ],
+        vertices,
+    };
+    let read = Frame::from_bytes(&frame.to_bytes()).unwrap();
+    assert_eq!(draw(&read, &[]).to_rgba8(), draw(&frame, &[]).to_rgba8());
+}
+
+/// A coordinate on the pixel grid of `SCREEN`, or off it by up to 4
+/// pixels, where the pixel centers are right on the edges of some quads.
+fn grid_coordinate() -> impl Strategy<Value = f32> {
+    (-4i32..=20).prop_map(|pixels| pixels as f32 / 8.0 - 1.0)
+}
+
+proptest! {
+    #[test]
+    fn blends_a_translucent_quad_once_per_pixel(
+        x in (grid_coordinate(), grid_coordinate()),
+        y in (grid_coordinate(), grid_coordinate()),
+    ) {
+        let rect = [x.0.min(x.1), y.0.min(y.1), x.0.max(x.1), y.0.max(y.1)];
+        let frame = plain_frame(quad(MODE_GEOMETRY, rect, [1.0, 1.0, 1.0, 0.5]));
+        let rasterizer = draw(&frame, &[]);
+        for pixel in rasterizer.to_rgba8().chunks(4) {
+            // Black, or half of white once, but never blended twice on the
+            // diagonal.
+            prop_assert!(pixel == [0, 0, 0, 255] || pixel == [188, 188, 188, 255]);
+        }
+    }
+}
+
+#[test]
+fn draws_a_ui_through_a_tessellator() {
+    let (w, h) = (64, 48);
+    let mut ui = conrod_core::UiBuilder::new([w as f64, h as f64]).build();
+    let id = ui.widget_id_generator().next();
+    {
+        let ui = &mut ui.set_widgets();
+        widget::Rectangle::fill([20.0, 10.0])
+            .color(color::rgb(0.0, 1.0, 0.0))
+            .middle_of(ui.window)
+            .set(id, ui);
+    }
+    let mut tessellator = Tessellator::new((256, 256), (1024, 1024));
+    let mesh = tessellator.tessellate(&(w, h, 1.0), ui.draw(), &Default::default());
+    let mut rasterizer = SoftRasterizer::new();
+    rasterizer.load_glyphs(&mesh);
+    rasterizer.draw(&mesh.frame((w, h), &[]), &[], BLACK);
+
+    assert_eq!(rasterizer.size(), (w, h));
+    assert_eq!(rasterizer.pixel(32, 24), Some([0, 255, 0, 255]));
+    // The rectangle goes from 22 to 42 across and 19 to 29 down.
+    assert_eq!(rasterizer.pixel(22, 19), Some([0, 255, 0, 255]));
+    assert_eq!(rasterizer.pixel(41, 28), Some([0, 255, 0, 255]));
+    assert_eq!(rasterizer.pixel(21, 24), Some([0, 0, 0, 255]));
+    assert_eq!(rasterizer.pixel(32, 29), Some([0, 0, 0, 255]));
+}
+
+#[test]
+fn draws_text_cached_before_the_glyph_cache_grew() {
+    let (w, h) = (128, 64);
+    let mut ui = conrod_core::UiBuilder::new([w as f64, h as f64]).build();
+    let font_path = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/assets/fonts/NotoSans/NotoSans-Regular.ttf"
+    );
+    ui.fonts.insert_from_file(font_path).unwrap();
+    let small = ui.widget_id_generator().next();
+    let large = ui.widget_id_generator().next();
+    {
+        let ui = &mut ui.set_widgets();
+        // Fits in the smallest cache, so it is cached before the cache grows for the rest.
+        widget::Text::new("ab")
+            .font_size(10)
+            .color(color::WHITE)
+            .top_left_of(ui.window)
+            .set(small, ui);
+        widget::Text::new("The quick brown fox")
+            .font_size(20)
+            .color(color::WHITE)
+            .bottom_left_of(ui.window)
+            .set(large, ui);
+    }
+    let draw = |size| {
+        let mut tessellator = Tessellator::new(size, (1024, 1024));
+        let mesh = tessellator.tessellate(&(w, h, 1.0), ui.draw(), &Default::default());
+        let mut rasterizer = SoftRasterizer::new();
+        rasterizer.load_glyphs(&mesh);
+        rasterizer.draw(&mesh.frame((w, h), &[]), &[], BLACK);
+        (mesh.glyph_cache_grew(), rasterizer.to_rgba8())
+    };
+
+    let (grew, growing) = draw((32, 32));
+    let (large_grew, large) = draw((1024, 1024));
+    assert!(grew);
+    assert!(!large_grew);
+    assert!(growing == large);
+}